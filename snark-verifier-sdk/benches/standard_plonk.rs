@@ -3,7 +3,6 @@ use criterion::{BenchmarkId, Criterion};
 use pprof::criterion::{Output, PProfProfiler};
 
 use ark_std::{end_timer, start_timer};
-use halo2_base::halo2_proofs;
 use halo2_proofs::halo2curves as halo2_curves;
 use halo2_proofs::{
     halo2curves::bn256::Bn256,
@@ -12,6 +11,7 @@ use halo2_proofs::{
 use rand::rngs::OsRng;
 use rand::SeedableRng;
 use rand_chacha::ChaCha20Rng;
+use snark_verifier_sdk::halo2_proofs;
 use snark_verifier_sdk::CircuitExt;
 use snark_verifier_sdk::{
     gen_pk,
@@ -180,7 +180,7 @@ fn gen_application_snark(params: &ParamsKZG<Bn256>) -> Snark {
 fn bench(c: &mut Criterion) {
     std::env::set_var("VERIFY_CONFIG", "./configs/example_evm_accumulator.config");
     let k = 21;
-    let params = halo2_base::utils::fs::gen_srs(k);
+    let params = snark_verifier_sdk::halo2_base::utils::fs::gen_srs(k);
     let params_app = {
         let mut params = params.clone();
         params.downsize(8);
@@ -0,0 +1,301 @@
+//! Benchmarks of verifier side costs, so performance regressions of loaders and
+//! PCS get caught automatically:
+//!
+//! - `native-verify`: native `Plonk::verify` of a snark of a zkEVM-shaped
+//!   circuit, which has many advice columns, degree-9 gates and lookups.
+//! - `aggregation-witness-gen`: `AggregationCircuit::new` and its synthesis
+//!   for 4, 8 and 16 snarks.
+//! - `evm-verifier-gas`: gas used by deployed verifiers, measured by [`Gas`]
+//!   instead of time.
+use criterion::measurement::{Measurement, ValueFormatter};
+use criterion::{criterion_group, criterion_main};
+use criterion::{BenchmarkId, Criterion, Throughput};
+use ethereum_types::Address;
+use pprof::criterion::{Output, PProfProfiler};
+
+use halo2_proofs::{
+    dev::MockProver,
+    halo2curves::bn256::{Bn256, Fr},
+    poly::{commitment::ParamsProver, kzg::commitment::ParamsKZG},
+};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use snark_verifier::{
+    loader::{evm::ExecutorBuilder, native::NativeLoader},
+    verifier::PlonkVerifier,
+};
+use snark_verifier_sdk::halo2_proofs;
+use snark_verifier_sdk::{
+    evm::{encode_calldata, gen_evm_proof_shplonk, gen_evm_verifier_shplonk},
+    gen_pk,
+    halo2::{
+        aggregation::{load_verify_circuit_degree, AggregationCircuit, Shplonk},
+        gen_snark_shplonk, PoseidonTranscript,
+    },
+    CircuitExt, Snark,
+};
+
+mod application {
+    #[cfg(feature = "halo2-pse")]
+    use super::halo2_proofs::circuit::Value;
+    use super::halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        halo2curves::bn256::Fr,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Fixed},
+        poly::Rotation,
+    };
+    use snark_verifier_sdk::CircuitExt;
+
+    /// Degree of each gate.
+    const DEGREE: usize = 9;
+
+    #[derive(Clone)]
+    pub struct WideConfig {
+        fixed: Vec<Column<Fixed>>,
+    }
+
+    /// Circuit with `NUM_ADVICE` advice columns, `NUM_GATE` gates of degree
+    /// [`DEGREE`] and `NUM_LOOKUP` lookups, whose witness are all zero, to
+    /// have the shape of a real world circuit (e.g. zkEVM) without
+    /// depending on it.
+    #[derive(Clone, Default)]
+    pub struct WideCircuit<const NUM_ADVICE: usize, const NUM_GATE: usize, const NUM_LOOKUP: usize>;
+
+    impl<const NUM_ADVICE: usize, const NUM_GATE: usize, const NUM_LOOKUP: usize> CircuitExt<Fr>
+        for WideCircuit<NUM_ADVICE, NUM_GATE, NUM_LOOKUP>
+    {
+        fn num_instance(&self) -> Vec<usize> {
+            vec![1]
+        }
+
+        fn instances(&self) -> Vec<Vec<Fr>> {
+            vec![vec![Fr::one()]]
+        }
+    }
+
+    impl<const NUM_ADVICE: usize, const NUM_GATE: usize, const NUM_LOOKUP: usize> Circuit<Fr>
+        for WideCircuit<NUM_ADVICE, NUM_GATE, NUM_LOOKUP>
+    {
+        type Config = WideConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let advices = [(); NUM_ADVICE].map(|_| meta.advice_column());
+            let selectors = [(); NUM_GATE].map(|_| meta.fixed_column());
+            let table = meta.fixed_column();
+            meta.instance_column();
+
+            advices.map(|column| meta.enable_equality(column));
+
+            for (idx, selector) in selectors.into_iter().enumerate() {
+                meta.create_gate("", |meta| {
+                    let selector = meta.query_fixed(selector, Rotation::cur());
+                    let product = (0..DEGREE - 1)
+                        .map(|offset| {
+                            let column = advices[(idx * (DEGREE - 1) + offset) % NUM_ADVICE];
+                            meta.query_advice(column, Rotation((offset % 2) as i32))
+                        })
+                        .reduce(|acc, expr| acc * expr)
+                        .unwrap();
+                    Some(selector * product)
+                });
+            }
+            for idx in 0..NUM_LOOKUP {
+                meta.lookup_any("", |meta| {
+                    let input = meta.query_advice(advices[idx % NUM_ADVICE], Rotation::cur());
+                    vec![(input, meta.query_fixed(table, Rotation::cur()))]
+                });
+            }
+
+            WideConfig { fixed: selectors.into_iter().chain([table]).collect() }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            // Gates and lookups are satisfied by zero advice, and fixed columns
+            // are assigned to avoid commitments of identity.
+            layouter.assign_region(
+                || "",
+                |mut region| {
+                    for column in config.fixed.iter() {
+                        #[cfg(feature = "halo2-pse")]
+                        region.assign_fixed(|| "", *column, 0, || Value::known(Fr::one()))?;
+                        #[cfg(feature = "halo2-axiom")]
+                        region.assign_fixed(*column, 0, Fr::one());
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+}
+
+use application::WideCircuit;
+
+type ZkevmShapedCircuit = WideCircuit<64, 32, 8>;
+type SmallCircuit = WideCircuit<4, 2, 1>;
+
+fn gen_application_snark<C: CircuitExt<Fr> + Default>(
+    params: &ParamsKZG<Bn256>,
+    rng: &mut ChaCha20Rng,
+) -> Snark {
+    let circuit = C::default();
+    let pk = gen_pk(params, &circuit, None);
+    gen_snark_shplonk(params, &pk, circuit, rng, None::<&str>)
+}
+
+fn bench_native_verify(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let params = ParamsKZG::<Bn256>::setup(10, &mut rng);
+    let snark = gen_application_snark::<ZkevmShapedCircuit>(&params, &mut rng);
+    let svk = params.get_g()[0].into();
+    let dk = (params.g2(), params.s_g2()).into();
+
+    let mut group = c.benchmark_group("native-verify");
+    group.sample_size(10);
+    group.bench_with_input(BenchmarkId::new("zkevm-shaped", 10), &snark, |b, snark| {
+        b.iter(|| {
+            let mut transcript = PoseidonTranscript::<NativeLoader, _>::new(snark.proof.as_slice());
+            let proof =
+                Shplonk::read_proof(&svk, &snark.protocol, &snark.instances, &mut transcript);
+            assert!(Shplonk::verify(&svk, &dk, &snark.protocol, &snark.instances, &proof))
+        })
+    });
+    group.finish();
+}
+
+fn bench_aggregation_witness_gen(c: &mut Criterion) {
+    std::env::set_var("VERIFY_CONFIG", "./configs/bench_aggregation.config");
+    let k_agg = load_verify_circuit_degree();
+
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    // `AggregationCircuit::new` only needs the first few points of SRS, so
+    // params of applications are used directly instead of downsizing.
+    let params = ParamsKZG::<Bn256>::setup(8, &mut rng);
+    let snarks = (0..16)
+        .map(|_| gen_application_snark::<SmallCircuit>(&params, &mut rng))
+        .collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("aggregation-witness-gen");
+    group.sample_size(10);
+    for num_snark in [4, 8, 16] {
+        let snarks = &snarks[..num_snark];
+        group.bench_with_input(BenchmarkId::new("accumulate", num_snark), snarks, |b, snarks| {
+            b.iter(|| AggregationCircuit::new(&params, snarks.to_vec(), &mut rng))
+        });
+
+        let agg_circuit = AggregationCircuit::new(&params, snarks.to_vec(), &mut rng);
+        let instances = agg_circuit.instances();
+        group.bench_with_input(
+            BenchmarkId::new("synthesize", num_snark),
+            &(&agg_circuit, &instances),
+            |b, &(agg_circuit, instances)| {
+                b.iter(|| MockProver::run(k_agg, agg_circuit, instances.clone()).unwrap())
+            },
+        );
+    }
+    group.finish();
+}
+
+/// Gas used by a call, recorded as a [`Measurement`] so criterion tracks
+/// regressions of it as it does for time. Gas is deterministic, so the value
+/// of each sample is expected to be returned by `iter_custom`.
+struct Gas;
+
+impl Measurement for Gas {
+    type Intermediate = ();
+    type Value = u64;
+
+    fn start(&self) {}
+
+    fn end(&self, _: ()) -> u64 {
+        0
+    }
+
+    fn add(&self, lhs: &u64, rhs: &u64) -> u64 {
+        lhs + rhs
+    }
+
+    fn zero(&self) -> u64 {
+        0
+    }
+
+    fn to_f64(&self, value: &u64) -> f64 {
+        *value as f64
+    }
+
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &GasFormatter
+    }
+}
+
+struct GasFormatter;
+
+impl ValueFormatter for GasFormatter {
+    fn scale_values(&self, _: f64, _: &mut [f64]) -> &'static str {
+        "gas"
+    }
+
+    fn scale_throughputs(&self, _: f64, _: &Throughput, _: &mut [f64]) -> &'static str {
+        "gas"
+    }
+
+    fn scale_for_machines(&self, _: &mut [f64]) -> &'static str {
+        "gas"
+    }
+}
+
+fn bench_evm_verifier_gas<C: CircuitExt<Fr> + Default>(c: &mut Criterion<Gas>, name: &str, k: u32) {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let params = ParamsKZG::<Bn256>::setup(k, &mut rng);
+    let circuit = C::default();
+    let pk = gen_pk(&params, &circuit, None);
+    let instances = circuit.instances();
+    let deployment_code =
+        gen_evm_verifier_shplonk::<C>(&params, pk.get_vk(), circuit.num_instance(), None);
+    let proof = gen_evm_proof_shplonk(&params, &pk, circuit, instances.clone(), &mut rng);
+    let calldata = encode_calldata(&instances, &proof);
+
+    let mut evm = ExecutorBuilder::default().with_gas_limit(u64::MAX.into()).build();
+    let caller = Address::from_low_u64_be(0xfe);
+    let verifier = evm.deploy(caller, deployment_code.into(), 0.into()).address.unwrap();
+
+    let mut group = c.benchmark_group("evm-verifier-gas");
+    group.sample_size(10);
+    group.bench_with_input(BenchmarkId::new(name, k), &calldata, |b, calldata| {
+        b.iter_custom(|iters| {
+            (0..iters)
+                .map(|_| {
+                    let result = evm.call_raw(caller, verifier, calldata.clone().into(), 0.into());
+                    assert!(!result.reverted);
+                    result.gas_used
+                })
+                .sum()
+        })
+    });
+    group.finish();
+}
+
+fn bench_gas(c: &mut Criterion<Gas>) {
+    bench_evm_verifier_gas::<SmallCircuit>(c, "small", 8);
+    bench_evm_verifier_gas::<WideCircuit<16, 8, 2>>(c, "wide", 10);
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().with_profiler(PProfProfiler::new(10, Output::Flamegraph(None)));
+    targets = bench_native_verify, bench_aggregation_witness_gen
+}
+criterion_group! {
+    name = gas;
+    config = Criterion::default().with_measurement(Gas);
+    targets = bench_gas
+}
+criterion_main!(benches, gas);
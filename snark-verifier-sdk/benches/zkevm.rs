@@ -1,10 +1,10 @@
 use ark_std::{end_timer, start_timer};
-use halo2_base::halo2_proofs;
-use halo2_base::utils::fs::gen_srs;
 use halo2_proofs::halo2curves::bn256::Fr;
 use rand::SeedableRng;
 use rand_chacha::ChaCha20Rng;
 use snark_verifier::loader::native::NativeLoader;
+use snark_verifier_sdk::halo2_base::utils::fs::gen_srs;
+use snark_verifier_sdk::halo2_proofs;
 use snark_verifier_sdk::{
     self,
     evm::{
@@ -0,0 +1,257 @@
+//! Differential fuzzing of native verifier against generated EVM verifier.
+//!
+//! A valid proof of a standard plonk circuit is generated once, then each
+//! input is decoded into a sequence of [`Mutation`]s applied to its calldata.
+//! The mutated calldata is verified natively by [`verify_evm_calldata`] and by
+//! the generated verifier executed in revm, and both must either accept or
+//! reject.
+//!
+//! Run with `cargo fuzz run evm_differential` in `snark-verifier-sdk`.
+#![no_main]
+
+use snark_verifier_sdk::halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    halo2curves::bn256::{Bn256, Fq, Fr, G1Affine},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Fixed, Instance},
+    poly::{commitment::ParamsProver, kzg::commitment::ParamsKZG, Rotation},
+};
+use lazy_static::lazy_static;
+use libfuzzer_sys::fuzz_target;
+use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+use snark_verifier::{
+    loader::evm::{modulus, U256},
+    pcs::kzg::{Bdfg21, Kzg, KzgDecidingKey, KzgSuccinctVerifyingKey},
+    system::halo2::{compile, Config},
+    Protocol,
+};
+use snark_verifier_sdk::{
+    evm::{
+        encode_calldata, evm_verify_calldata, gen_evm_proof_shplonk, gen_evm_verifier_shplonk,
+        verify_evm_calldata,
+    },
+    gen_pk, CircuitExt,
+};
+
+type Pcs = Kzg<Bn256, Bdfg21>;
+
+#[derive(Clone, Copy)]
+struct StandardPlonkConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    c: Column<Advice>,
+    q_a: Column<Fixed>,
+    q_b: Column<Fixed>,
+    q_c: Column<Fixed>,
+    q_ab: Column<Fixed>,
+    constant: Column<Fixed>,
+    #[allow(dead_code)]
+    instance: Column<Instance>,
+}
+
+impl StandardPlonkConfig {
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self {
+        let [a, b, c] = [(); 3].map(|_| meta.advice_column());
+        let [q_a, q_b, q_c, q_ab, constant] = [(); 5].map(|_| meta.fixed_column());
+        let instance = meta.instance_column();
+
+        [a, b, c].map(|column| meta.enable_equality(column));
+
+        meta.create_gate(
+            "q_a·a + q_b·b + q_c·c + q_ab·a·b + constant + instance = 0",
+            |meta| {
+                let [a, b, c] = [a, b, c].map(|column| meta.query_advice(column, Rotation::cur()));
+                let [q_a, q_b, q_c, q_ab, constant] = [q_a, q_b, q_c, q_ab, constant]
+                    .map(|column| meta.query_fixed(column, Rotation::cur()));
+                let instance = meta.query_instance(instance, Rotation::cur());
+                Some(
+                    q_a * a.clone()
+                        + q_b * b.clone()
+                        + q_c * c
+                        + q_ab * a * b
+                        + constant
+                        + instance,
+                )
+            },
+        );
+
+        StandardPlonkConfig { a, b, c, q_a, q_b, q_c, q_ab, constant, instance }
+    }
+}
+
+#[derive(Clone, Default)]
+struct StandardPlonk(Fr);
+
+impl CircuitExt<Fr> for StandardPlonk {
+    fn num_instance(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn instances(&self) -> Vec<Vec<Fr>> {
+        vec![vec![self.0]]
+    }
+}
+
+impl Circuit<Fr> for StandardPlonk {
+    type Config = StandardPlonkConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        meta.set_minimum_degree(4);
+        StandardPlonkConfig::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "",
+            |mut region| {
+                region.assign_advice(|| "", config.a, 0, || Value::known(self.0))?;
+                region.assign_fixed(|| "", config.q_a, 0, || Value::known(-Fr::one()))?;
+                region.assign_advice(|| "", config.a, 1, || Value::known(-Fr::from(5)))?;
+                for (idx, column) in
+                    (1..).zip([config.q_a, config.q_b, config.q_c, config.q_ab, config.constant])
+                {
+                    region.assign_fixed(
+                        || "",
+                        column,
+                        1,
+                        || Value::known(Fr::from(idx as u64)),
+                    )?;
+                }
+                let a = region.assign_advice(|| "", config.a, 2, || Value::known(Fr::one()))?;
+                a.copy_advice(|| "", &mut region, config.b, 3)?;
+                a.copy_advice(|| "", &mut region, config.c, 4)?;
+
+                Ok(())
+            },
+        )
+    }
+}
+
+struct Setup {
+    svk: KzgSuccinctVerifyingKey<G1Affine>,
+    dk: KzgDecidingKey<Bn256>,
+    protocol: Protocol<G1Affine>,
+    deployment_code: Vec<u8>,
+    calldata: Vec<u8>,
+}
+
+lazy_static! {
+    static ref SETUP: Setup = {
+        let params = ParamsKZG::<Bn256>::setup(8, ChaCha20Rng::from_seed(Default::default()));
+        let mut rng = ChaCha20Rng::from_seed([1; 32]);
+
+        let circuit = StandardPlonk(Fr::from(42));
+        let pk = gen_pk(&params, &circuit, None);
+        let num_instance = circuit.num_instance();
+        let deployment_code = gen_evm_verifier_shplonk::<StandardPlonk>(
+            &params,
+            pk.get_vk(),
+            num_instance.clone(),
+            None,
+        );
+        let protocol =
+            compile(&params, pk.get_vk(), Config::kzg().with_num_instance(num_instance));
+
+        let instances = circuit.instances();
+        let proof = gen_evm_proof_shplonk(&params, &pk, circuit, instances.clone(), &mut rng);
+        let calldata = encode_calldata(&instances, &proof);
+
+        Setup {
+            svk: params.get_g()[0].into(),
+            dk: (params.g2(), params.s_g2()).into(),
+            protocol,
+            deployment_code,
+            calldata,
+        }
+    };
+}
+
+/// Mutation of calldata decoded from 4 bytes of fuzz input.
+#[derive(Debug)]
+enum Mutation {
+    /// Xor a byte at offset with non-zero mask.
+    FlipByte(usize, u8),
+    /// Overwrite a 32 bytes word with special value.
+    SetWord(usize, u8),
+    /// Swap two 32 bytes words.
+    SwapWords(usize, usize),
+    /// Truncate calldata at offset.
+    Truncate(usize),
+}
+
+impl Mutation {
+    fn decode(bytes: &[u8]) -> Self {
+        let offset = u16::from_be_bytes([bytes[1], bytes[2]]) as usize;
+        match bytes[0] % 4 {
+            0 => Mutation::FlipByte(offset, bytes[3].max(1)),
+            1 => Mutation::SetWord(offset, bytes[3]),
+            2 => Mutation::SwapWords(offset, offset + bytes[3] as usize),
+            _ => Mutation::Truncate(offset),
+        }
+    }
+
+    fn apply(&self, calldata: &mut Vec<u8>) {
+        if calldata.len() < 0x20 {
+            return;
+        }
+        let num_word = calldata.len() / 0x20;
+        let word = |idx: usize| (idx % num_word) * 0x20..(idx % num_word + 1) * 0x20;
+        match *self {
+            Mutation::FlipByte(offset, mask) => {
+                let len = calldata.len();
+                calldata[offset % len] ^= mask;
+            }
+            Mutation::SetWord(idx, kind) => {
+                // Values around moduli hit the boundary of canonical
+                // encoding, zero hits the identity encoding.
+                let value = match kind % 6 {
+                    0 => U256::zero(),
+                    1 => U256::one(),
+                    2 => modulus::<Fr>(),
+                    3 => modulus::<Fr>() - 1,
+                    4 => modulus::<Fq>(),
+                    _ => U256::MAX,
+                };
+                value.to_big_endian(&mut calldata[word(idx)]);
+            }
+            Mutation::SwapWords(lhs, rhs) => {
+                let (lhs, rhs) = (word(lhs), word(rhs));
+                let tmp = calldata[lhs.clone()].to_vec();
+                calldata.copy_within(rhs.clone(), lhs.start);
+                calldata[rhs].copy_from_slice(&tmp);
+            }
+            Mutation::Truncate(offset) => {
+                let len = calldata.len();
+                calldata.truncate(offset % len);
+            }
+        }
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let setup = &*SETUP;
+
+    let mutations = data.chunks_exact(4).map(Mutation::decode).collect::<Vec<_>>();
+    let mut calldata = setup.calldata.clone();
+    for mutation in mutations.iter() {
+        mutation.apply(&mut calldata);
+    }
+
+    let native = verify_evm_calldata::<Pcs>(&setup.svk, &setup.dk, &setup.protocol, &calldata);
+    let evm = evm_verify_calldata(setup.deployment_code.clone(), calldata.clone());
+
+    assert_eq!(
+        native.is_ok(),
+        evm,
+        "Native verifier ({native:?}) and EVM verifier (accept: {evm}) disagree \
+         after {mutations:?}",
+    );
+});
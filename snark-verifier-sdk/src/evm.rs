@@ -1,8 +1,5 @@
-use super::{CircuitExt, Plonk};
-#[cfg(feature = "display")]
-use ark_std::{end_timer, start_timer};
-use ethereum_types::Address;
-use halo2_base::halo2_proofs::{
+use super::{CircuitExt, Plonk, BITS, LIMBS};
+use crate::halo2_proofs::{
     halo2curves::bn256::{Bn256, Fq, Fr, G1Affine},
     plonk::{create_proof, verify_proof, Circuit, ProvingKey, VerifyingKey},
     poly::{
@@ -15,22 +12,46 @@ use halo2_base::halo2_proofs::{
         },
         VerificationStrategy,
     },
-    transcript::{TranscriptReadBuffer, TranscriptWriterBuffer},
 };
+#[cfg(feature = "display")]
+use ark_std::{end_timer, start_timer};
+use ethereum_types::Address;
 use itertools::Itertools;
 use rand::Rng;
-pub use snark_verifier::loader::evm::encode_calldata;
+pub use snark_verifier::loader::evm::{
+    encode_calldata, encode_calldata_with_vk, encode_staged_calldata, encode_verify_raw_calldata,
+    encode_vk_registry_constructor_args, staged_instance_digest, staging_sol_code, vk_digest,
+    BlobCheck, EntryPoint, SolcConfig, VerifierArtifact,
+};
 use snark_verifier::{
-    loader::evm::{compile_solidity, ExecutorBuilder, EvmLoader},
+    cost::CostEstimation,
+    loader::{
+        evm::{compile_solidity, EvmLoader, ExecutorBuilder},
+        native::NativeLoader,
+    },
     pcs::{
-        kzg::{Bdfg21, Gwc19, Kzg, KzgAccumulator, KzgDecidingKey, KzgSuccinctVerifyingKey},
-        Decider, MultiOpenScheme, PolynomialCommitmentScheme,
+        kzg::{
+            Bdfg21, Gwc19, Kzg, KzgAccumulator, KzgDecidingKey, KzgSuccinctVerifyingKey,
+            LimbsEncoding,
+        },
+        Decider, MultiOpenScheme, PolynomialCommitmentScheme, Query,
+    },
+    system::halo2::{
+        compile,
+        transcript::evm::{EvmTranscript, EvmTranscriptBuilder},
+        Config,
     },
-    system::halo2::{compile, transcript::evm::EvmTranscript, Config},
+    util::{arithmetic::PrimeField, transcript::Transcript},
     verifier::PlonkVerifier,
+    Error, Protocol,
 };
 use std::{fs, io, path::Path, rc::Rc};
 
+pub mod layout;
+pub mod negative;
+
+use layout::CalldataLayout;
+
 /// Generates a proof for evm verification using either SHPLONK or GWC proving method. Uses Keccak for Fiat-Shamir.
 pub fn gen_evm_proof<'params, C, P, V>(
     params: &'params ParamsKZG<Bn256>,
@@ -39,6 +60,32 @@ pub fn gen_evm_proof<'params, C, P, V>(
     instances: Vec<Vec<Fr>>,
     rng: &mut (impl Rng + Send),
 ) -> Vec<u8>
+where
+    C: Circuit<Fr>,
+    P: Prover<'params, KZGCommitmentScheme<Bn256>>,
+    V: Verifier<
+        'params,
+        KZGCommitmentScheme<Bn256>,
+        Guard = GuardKZG<'params, Bn256>,
+        MSMAccumulator = DualMSM<'params, Bn256>,
+    >,
+{
+    gen_evm_proof_with_aux::<C, P, V>(params, pk, circuit, instances, &[], rng)
+}
+
+/// Same as [`gen_evm_proof`], but absorbs external `aux` (e.g. randomness from
+/// a L1 beacon) into the transcript before anything else, so the proof is
+/// bound to it. The verifier should be generated by
+/// [`gen_evm_verifier_sol_code_with_aux`] and called with calldata encoded by
+/// [`encode_calldata_with_aux`].
+pub fn gen_evm_proof_with_aux<'params, C, P, V>(
+    params: &'params ParamsKZG<Bn256>,
+    pk: &'params ProvingKey<G1Affine>,
+    circuit: C,
+    instances: Vec<Vec<Fr>>,
+    aux: &[Fr],
+    rng: &mut (impl Rng + Send),
+) -> Vec<u8>
 where
     C: Circuit<Fr>,
     P: Prover<'params, KZGCommitmentScheme<Bn256>>,
@@ -51,7 +98,7 @@ where
 {
     #[cfg(debug_assertions)]
     {
-        use halo2_base::halo2_proofs::{dev::MockProver, poly::commitment::Params};
+        use crate::halo2_proofs::{dev::MockProver, poly::commitment::Params};
         MockProver::run(params.k(), &circuit, instances.clone()).unwrap().assert_satisfied();
     }
 
@@ -60,7 +107,8 @@ where
     #[cfg(feature = "display")]
     let proof_time = start_timer!(|| "Create EVM proof");
     let proof = {
-        let mut transcript = TranscriptWriterBuffer::<_, G1Affine, _>::init(Vec::new());
+        let mut transcript = EvmTranscript::<G1Affine, NativeLoader, _, _>::new(Vec::new());
+        transcript.absorb_aux(aux).unwrap();
         create_proof::<KZGCommitmentScheme<Bn256>, P, _, _, EvmTranscript<_, _, _, _>, _>(
             params,
             pk,
@@ -76,7 +124,8 @@ where
     end_timer!(proof_time);
 
     let accept = {
-        let mut transcript = TranscriptReadBuffer::<_, G1Affine, _>::init(proof.as_slice());
+        let mut transcript = EvmTranscript::<G1Affine, NativeLoader, _, _>::new(proof.as_slice());
+        transcript.absorb_aux(aux).unwrap();
         VerificationStrategy::<_, V>::finalize(
             verify_proof::<_, V, _, EvmTranscript<_, _, _, _>, _>(
                 params.verifier_params(),
@@ -113,12 +162,12 @@ pub fn gen_evm_proof_shplonk<'params, C: Circuit<Fr>>(
     gen_evm_proof::<C, ProverSHPLONK<_>, VerifierSHPLONK<_>>(params, pk, circuit, instances, rng)
 }
 
-pub fn gen_evm_verifier<C, PCS>(
+/// Generates the Solidity source of the evm verifier.
+pub fn gen_evm_verifier_sol_code<C, PCS>(
     params: &ParamsKZG<Bn256>,
     vk: &VerifyingKey<G1Affine>,
     num_instance: Vec<usize>,
-    path: Option<&Path>,
-) -> Vec<u8>
+) -> String
 where
     C: CircuitExt<Fr>,
     PCS: PolynomialCommitmentScheme<
@@ -131,25 +180,267 @@ where
             SuccinctVerifyingKey = KzgSuccinctVerifyingKey<G1Affine>,
         > + Decider<G1Affine, Rc<EvmLoader>, DecidingKey = KzgDecidingKey<Bn256>>,
 {
-    let svk = params.get_g()[0].into();
-    let dk = (params.g2(), params.s_g2()).into();
     let protocol = compile(
         params,
         vk,
         Config::kzg()
-            .with_num_instance(num_instance.clone())
+            .with_num_instance(num_instance)
             .with_accumulator_indices(C::accumulator_indices()),
     );
+    gen_evm_verifier_sol_code_from_protocol::<PCS>(params, &protocol)
+}
 
-    let loader = EvmLoader::new::<Fq, Fr>();
+/// Generates the Solidity source of the evm verifier from compiled `protocol`,
+/// e.g. read by [`read_protocol`](crate::read_protocol), without circuit code.
+pub fn gen_evm_verifier_sol_code_from_protocol<PCS>(
+    params: &ParamsKZG<Bn256>,
+    protocol: &Protocol<G1Affine>,
+) -> String
+where
+    PCS: PolynomialCommitmentScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            Accumulator = KzgAccumulator<G1Affine, Rc<EvmLoader>>,
+        > + MultiOpenScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            SuccinctVerifyingKey = KzgSuccinctVerifyingKey<G1Affine>,
+        > + Decider<G1Affine, Rc<EvmLoader>, DecidingKey = KzgDecidingKey<Bn256>>,
+{
+    gen_evm_verifier_sol_code_inner::<PCS>(params, protocol, None, EntryPoint::Fallback, 0)
+}
+
+/// Generates the Solidity source of the evm verifier from compiled `protocol`,
+/// which reads `num_aux` scalars of external data (e.g. randomness from a L1
+/// beacon) from calldata and absorbs them into the transcript, for proofs
+/// generated by [`gen_evm_proof_with_aux`]. Calldata should be encoded by
+/// [`encode_calldata_with_aux`], and non-canonical `aux` is rejected.
+pub fn gen_evm_verifier_sol_code_with_aux<PCS>(
+    params: &ParamsKZG<Bn256>,
+    protocol: &Protocol<G1Affine>,
+    num_aux: usize,
+) -> String
+where
+    PCS: PolynomialCommitmentScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            Accumulator = KzgAccumulator<G1Affine, Rc<EvmLoader>>,
+        > + MultiOpenScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            SuccinctVerifyingKey = KzgSuccinctVerifyingKey<G1Affine>,
+        > + Decider<G1Affine, Rc<EvmLoader>, DecidingKey = KzgDecidingKey<Bn256>>,
+{
+    gen_evm_verifier_sol_code_inner::<PCS>(params, protocol, None, EntryPoint::Fallback, num_aux)
+}
+
+/// Generates the Solidity source of the evm verifier from compiled `protocol`
+/// with given `entry_point`, e.g. [`EntryPoint::VerifyRaw`] for proof
+/// marketplaces and verifier routers expecting `verify_raw(bytes)`, whose
+/// calldata should be encoded by [`encode_verify_raw_calldata`].
+pub fn gen_evm_verifier_sol_code_with_entry_point<PCS>(
+    params: &ParamsKZG<Bn256>,
+    protocol: &Protocol<G1Affine>,
+    entry_point: EntryPoint,
+) -> String
+where
+    PCS: PolynomialCommitmentScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            Accumulator = KzgAccumulator<G1Affine, Rc<EvmLoader>>,
+        > + MultiOpenScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            SuccinctVerifyingKey = KzgSuccinctVerifyingKey<G1Affine>,
+        > + Decider<G1Affine, Rc<EvmLoader>, DecidingKey = KzgDecidingKey<Bn256>>,
+{
+    gen_evm_verifier_sol_code_inner::<PCS>(params, protocol, None, entry_point, 0)
+}
+
+/// Generates the Solidity source of the evm verifier from compiled `protocol`,
+/// which also checks instances against an EIP-4844 blob according to
+/// `blob_check`. Calldata should be encoded by [`encode_calldata_with_blob`],
+/// and the source must be compiled with EVM version `cancun`.
+pub fn gen_evm_verifier_sol_code_with_blob_check<PCS>(
+    params: &ParamsKZG<Bn256>,
+    protocol: &Protocol<G1Affine>,
+    blob_check: &BlobCheck,
+) -> String
+where
+    PCS: PolynomialCommitmentScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            Accumulator = KzgAccumulator<G1Affine, Rc<EvmLoader>>,
+        > + MultiOpenScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            SuccinctVerifyingKey = KzgSuccinctVerifyingKey<G1Affine>,
+        > + Decider<G1Affine, Rc<EvmLoader>, DecidingKey = KzgDecidingKey<Bn256>>,
+{
+    gen_evm_verifier_sol_code_inner::<PCS>(
+        params,
+        protocol,
+        Some(blob_check),
+        EntryPoint::Fallback,
+        0,
+    )
+}
+
+fn gen_evm_verifier_sol_code_inner<PCS>(
+    params: &ParamsKZG<Bn256>,
+    protocol: &Protocol<G1Affine>,
+    blob_check: Option<&BlobCheck>,
+    entry_point: EntryPoint,
+    num_aux: usize,
+) -> String
+where
+    PCS: PolynomialCommitmentScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            Accumulator = KzgAccumulator<G1Affine, Rc<EvmLoader>>,
+        > + MultiOpenScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            SuccinctVerifyingKey = KzgSuccinctVerifyingKey<G1Affine>,
+        > + Decider<G1Affine, Rc<EvmLoader>, DecidingKey = KzgDecidingKey<Bn256>>,
+{
+    let svk = params.get_g()[0].into();
+    let dk = (params.g2(), params.s_g2()).into();
+    LimbsEncoding::<LIMBS, BITS>::validate(protocol).unwrap_or_else(|err| panic!("{err:?}"));
+
+    let loader = EvmLoader::builder::<Fq, Fr>().with_entry_point(entry_point).build();
     let protocol = protocol.loaded(&loader);
-    let mut transcript = EvmTranscript::<_, Rc<EvmLoader>, _, _>::new(&loader);
+    let mut transcript = EvmTranscriptBuilder::new(&loader)
+        .with_calldata_offset(entry_point.calldata_offset())
+        .build::<G1Affine>();
 
-    let instances = transcript.load_instances(num_instance);
+    if num_aux > 0 {
+        transcript.read_aux(num_aux);
+    }
+    let instances = transcript.load_instances(protocol.num_instance.clone());
     let proof = Plonk::<PCS>::read_proof(&svk, &protocol, &instances, &mut transcript);
+    if let Some(blob_check) = blob_check {
+        transcript.check_blob(&instances, blob_check);
+    }
     Plonk::<PCS>::verify(&svk, &dk, &protocol, &instances, &proof);
 
-    let sol_code = loader.solidity_code();
+    loader.solidity_code()
+}
+
+/// Generates the Solidity source of the evm verifier shared by `protocols`,
+/// which reads verifying key from calldata and accepts only verifying keys
+/// registered in constructor, so aggregation circuits of the same shape (e.g.
+/// different batch sizes with the same config) could share one deployment.
+///
+/// Calldata should be encoded by [`encode_calldata_with_vk`], and deployment
+/// code should be appended with [`encode_vk_registry_constructor_args`] of
+/// [`vk_digest`] of each protocol to accept.
+///
+/// # Panics
+///
+/// Panics if `protocols` is empty, or they differ in anything other than
+/// preprocessed commitments and `transcript_initial_state`.
+pub fn gen_evm_verifier_sol_code_with_vk_registry<PCS>(
+    params: &ParamsKZG<Bn256>,
+    protocols: &[Protocol<G1Affine>],
+) -> String
+where
+    PCS: PolynomialCommitmentScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            Accumulator = KzgAccumulator<G1Affine, Rc<EvmLoader>>,
+        > + MultiOpenScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            SuccinctVerifyingKey = KzgSuccinctVerifyingKey<G1Affine>,
+        > + Decider<G1Affine, Rc<EvmLoader>, DecidingKey = KzgDecidingKey<Bn256>>,
+{
+    // Verifying key is not embedded, so code generated for protocols of the
+    // same shape are identical.
+    let sol_codes = protocols
+        .iter()
+        .map(|protocol| gen_evm_verifier_sol_code_with_vk_registry_inner::<PCS>(params, protocol))
+        .collect_vec();
+    assert!(!sol_codes.is_empty(), "At least one protocol should be given");
+    assert!(
+        sol_codes.iter().all_equal(),
+        "Protocols should differ only in preprocessed and transcript_initial_state"
+    );
+    sol_codes.into_iter().next().unwrap()
+}
+
+fn gen_evm_verifier_sol_code_with_vk_registry_inner<PCS>(
+    params: &ParamsKZG<Bn256>,
+    protocol: &Protocol<G1Affine>,
+) -> String
+where
+    PCS: PolynomialCommitmentScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            Accumulator = KzgAccumulator<G1Affine, Rc<EvmLoader>>,
+        > + MultiOpenScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            SuccinctVerifyingKey = KzgSuccinctVerifyingKey<G1Affine>,
+        > + Decider<G1Affine, Rc<EvmLoader>, DecidingKey = KzgDecidingKey<Bn256>>,
+{
+    let svk = params.get_g()[0].into();
+    let dk = (params.g2(), params.s_g2()).into();
+    LimbsEncoding::<LIMBS, BITS>::validate(protocol).unwrap_or_else(|err| panic!("{err:?}"));
+
+    // Calldata is laid out as `vk_digest || vk || instances || proof`
+    let vk_len = 0x40 * protocol.preprocessed.len()
+        + 0x20 * protocol.transcript_initial_state.iter().count();
+    let loader = EvmLoader::new::<Fq, Fr>();
+    let mut transcript =
+        EvmTranscriptBuilder::new(&loader).with_calldata_offset(0x20 + vk_len).build::<G1Affine>();
+    let protocol = protocol.loaded_vk_from_calldata(&loader, 0);
+
+    let instances = transcript.load_instances(protocol.num_instance.clone());
+    let proof = Plonk::<PCS>::read_proof(&svk, &protocol, &instances, &mut transcript);
+    Plonk::<PCS>::verify(&svk, &dk, &protocol, &instances, &proof);
+
+    loader.solidity_code()
+}
+
+/// Encode calldata of verifier generated by
+/// [`gen_evm_verifier_sol_code_with_blob_check`], which has blob `commitment`
+/// and KZG `kzg_proof` (both compressed BLS12-381 G1 points) appended.
+pub fn encode_calldata_with_blob(
+    instances: &[Vec<Fr>],
+    proof: &[u8],
+    commitment: &[u8; 48],
+    kzg_proof: &[u8; 48],
+) -> Vec<u8> {
+    [encode_calldata(instances, proof), commitment.to_vec(), kzg_proof.to_vec()].concat()
+}
+
+/// Encode calldata of verifier generated by
+/// [`gen_evm_verifier_sol_code_with_aux`], which has `aux` prepended.
+pub fn encode_calldata_with_aux(aux: &[Fr], instances: &[Vec<Fr>], proof: &[u8]) -> Vec<u8> {
+    let aux = aux.iter().flat_map(|scalar| scalar.to_repr().into_iter().rev()).collect_vec();
+    [aux, encode_calldata(instances, proof)].concat()
+}
+
+pub fn gen_evm_verifier<C, PCS>(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    num_instance: Vec<usize>,
+    path: Option<&Path>,
+) -> Vec<u8>
+where
+    C: CircuitExt<Fr>,
+    PCS: PolynomialCommitmentScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            Accumulator = KzgAccumulator<G1Affine, Rc<EvmLoader>>,
+        > + MultiOpenScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            SuccinctVerifyingKey = KzgSuccinctVerifyingKey<G1Affine>,
+        > + Decider<G1Affine, Rc<EvmLoader>, DecidingKey = KzgDecidingKey<Bn256>>,
+{
+    let sol_code = gen_evm_verifier_sol_code::<C, PCS>(params, vk, num_instance);
     let byte_code = compile_solidity(&sol_code);
     if let Some(path) = path {
         path.parent().and_then(|dir| fs::create_dir_all(dir).ok()).unwrap();
@@ -158,6 +449,62 @@ where
     byte_code
 }
 
+/// Generates the evm verifier from compiled `protocol`, and writes its Solidity
+/// source to `path` along with [`CalldataLayout`] in JSON to `path` with
+/// extension `json`, for indexers and SDKs in other languages to build and
+/// decode calldata.
+pub fn gen_evm_verifier_with_layout<PCS>(
+    params: &ParamsKZG<Bn256>,
+    protocol: &Protocol<G1Affine>,
+    path: &Path,
+) -> io::Result<Vec<u8>>
+where
+    PCS: PolynomialCommitmentScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            Accumulator = KzgAccumulator<G1Affine, Rc<EvmLoader>>,
+        > + MultiOpenScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            SuccinctVerifyingKey = KzgSuccinctVerifyingKey<G1Affine>,
+        > + Decider<G1Affine, Rc<EvmLoader>, DecidingKey = KzgDecidingKey<Bn256>>
+        + MultiOpenScheme<G1Affine, NativeLoader>
+        + CostEstimation<G1Affine, Input = Vec<Query<Fr>>>,
+{
+    let sol_code = gen_evm_verifier_sol_code_from_protocol::<PCS>(params, protocol);
+    let byte_code = compile_solidity(&sol_code);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, sol_code)?;
+    CalldataLayout::new::<PCS>(protocol).write(path.with_extension("json"))?;
+    Ok(byte_code)
+}
+
+/// Generates the evm verifier as [`VerifierArtifact`], containing source, abi,
+/// creation code, runtime code and codehash.
+pub fn gen_evm_verifier_artifact<C, PCS>(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    num_instance: Vec<usize>,
+    solc_config: &SolcConfig,
+) -> io::Result<VerifierArtifact>
+where
+    C: CircuitExt<Fr>,
+    PCS: PolynomialCommitmentScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            Accumulator = KzgAccumulator<G1Affine, Rc<EvmLoader>>,
+        > + MultiOpenScheme<
+            G1Affine,
+            Rc<EvmLoader>,
+            SuccinctVerifyingKey = KzgSuccinctVerifyingKey<G1Affine>,
+        > + Decider<G1Affine, Rc<EvmLoader>, DecidingKey = KzgDecidingKey<Bn256>>,
+{
+    let sol_code = gen_evm_verifier_sol_code::<C, PCS>(params, vk, num_instance);
+    VerifierArtifact::compile(sol_code, solc_config)
+}
+
 pub fn gen_evm_verifier_gwc<C: CircuitExt<Fr>>(
     params: &ParamsKZG<Bn256>,
     vk: &VerifyingKey<G1Affine>,
@@ -178,18 +525,73 @@ pub fn gen_evm_verifier_shplonk<C: CircuitExt<Fr>>(
 
 pub fn evm_verify(deployment_code: Vec<u8>, instances: Vec<Vec<Fr>>, proof: Vec<u8>) {
     let calldata = encode_calldata(&instances, &proof);
-    let success = {
-        let mut evm = ExecutorBuilder::default().with_gas_limit(u64::MAX.into()).build();
+    assert!(evm_verify_calldata(deployment_code, calldata));
+}
 
-        let caller = Address::from_low_u64_be(0xfe);
-        let verifier = evm.deploy(caller, deployment_code.into(), 0.into()).address.unwrap();
-        let result = evm.call_raw(caller, verifier, calldata.into(), 0.into());
+/// Deploys `deployment_code` and calls it with raw `calldata`, then returns
+/// whether the call succeeds instead of asserting.
+pub fn evm_verify_calldata(deployment_code: Vec<u8>, calldata: Vec<u8>) -> bool {
+    let mut evm = ExecutorBuilder::default().with_gas_limit(u64::MAX.into()).build();
 
-        log::info!("gas used: {}", result.gas_used);
+    let caller = Address::from_low_u64_be(0xfe);
+    let verifier = evm.deploy(caller, deployment_code.into(), 0.into()).address.unwrap();
+    let result = evm.call_raw(caller, verifier, calldata.into(), 0.into());
 
-        !result.reverted
-    };
-    assert!(success);
+    log::info!("gas used: {}", result.gas_used);
+
+    !result.reverted
+}
+
+/// Decodes `calldata` into instances and proof with the same layout as the
+/// generated verifier, and verifies it natively, so transactions destined for
+/// the verifier could be pre-validated before broadcasting.
+///
+/// Non-canonical instances and scalars in proof are rejected as the verifier
+/// does.
+pub fn verify_evm_calldata<PCS>(
+    svk: &KzgSuccinctVerifyingKey<G1Affine>,
+    dk: &KzgDecidingKey<Bn256>,
+    protocol: &Protocol<G1Affine>,
+    calldata: &[u8],
+) -> Result<(), Error>
+where
+    PCS: PolynomialCommitmentScheme<
+            G1Affine,
+            NativeLoader,
+            Accumulator = KzgAccumulator<G1Affine, NativeLoader>,
+        > + MultiOpenScheme<
+            G1Affine,
+            NativeLoader,
+            SuccinctVerifyingKey = KzgSuccinctVerifyingKey<G1Affine>,
+        > + Decider<G1Affine, NativeLoader, DecidingKey = KzgDecidingKey<Bn256>, Output = bool>,
+{
+    let num_instance = protocol.num_instance.iter().sum::<usize>();
+    if calldata.len() < num_instance * 0x20 {
+        return Err(Error::InvalidInstances);
+    }
+    let (instances, proof) = calldata.split_at(num_instance * 0x20);
+    let mut instances = instances
+        .chunks(0x20)
+        .map(|bytes| {
+            let mut repr = [0; 0x20];
+            repr.copy_from_slice(bytes);
+            repr.reverse();
+            Option::<Fr>::from(Fr::from_repr(repr)).ok_or(Error::InvalidInstances)
+        })
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter();
+    let instances = protocol
+        .num_instance
+        .iter()
+        .map(|num_instance| instances.by_ref().take(*num_instance).collect_vec())
+        .collect_vec();
+
+    let mut transcript = EvmTranscript::<G1Affine, NativeLoader, _, _>::new(proof);
+    let proof = Plonk::<PCS>::try_read_proof(svk, protocol, &instances, &mut transcript)?;
+    let verified = Plonk::<PCS>::verify(svk, dk, protocol, &instances, &proof);
+    verified
+        .then_some(())
+        .ok_or_else(|| Error::AssertionFailure("Proof verification failed".to_string()))
 }
 
 pub fn write_calldata(instances: &[Vec<Fr>], proof: &[u8], path: &Path) -> io::Result<String> {
@@ -0,0 +1,185 @@
+//! Machine-readable layout of calldata encoded by [`encode_calldata`] for
+//! generated verifiers, so indexers and SDKs in other languages could build
+//! and decode calldata from JSON instead of reading Rust.
+//!
+//! Calldata is instances as 32-byte big-endian words column by column,
+//! followed by proof of [`EvmTranscript`], where commitments are encoded as
+//! `x || y` in 32-byte big-endian words and evaluations as 32-byte
+//! big-endian words. All offsets are in bytes from the start of calldata.
+//!
+//! [`encode_calldata`]: super::encode_calldata
+//! [`EvmTranscript`]: snark_verifier::system::halo2::transcript::evm::EvmTranscript
+use crate::halo2_proofs::halo2curves::bn256::{Fr, G1Affine};
+use crate::{BITS, LIMBS};
+use serde::{Deserialize, Serialize};
+use snark_verifier::{
+    cost::CostEstimation,
+    loader::native::NativeLoader,
+    pcs::{self, MultiOpenScheme},
+    verifier::PlonkProof,
+    Protocol,
+};
+use std::{fs::File, io, iter, path::Path};
+
+const WORD: usize = 0x20;
+const POINT: usize = 2 * WORD;
+
+/// Instance at `offset` of calldata.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstanceSlot {
+    pub column: usize,
+    pub row: usize,
+    pub offset: usize,
+}
+
+/// Offsets of instances holding limbs of an accumulator encoded by
+/// [`LimbsEncoding`], where each coordinate is `num_limbs` little-endian limbs
+/// of `limb_bits` bits.
+///
+/// [`LimbsEncoding`]: snark_verifier::pcs::kzg::LimbsEncoding
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccumulatorSlot {
+    pub limb_bits: usize,
+    pub num_limbs: usize,
+    pub lhs_x: Vec<usize>,
+    pub lhs_y: Vec<usize>,
+    pub rhs_x: Vec<usize>,
+    pub rhs_y: Vec<usize>,
+}
+
+/// Item of proof, in the order they are read by the verifier.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ProofItem {
+    /// The `index`-th witness commitment of `phase`.
+    Witness { phase: usize, index: usize },
+    /// The `chunk`-th quotient chunk commitment.
+    Quotient { chunk: usize },
+    /// Evaluation of the `poly`-th polynomial at `rotation`.
+    Evaluation { poly: usize, rotation: i32 },
+    /// The `index`-th commitment of multi-open proof.
+    MultiOpen { index: usize },
+}
+
+/// [`ProofItem`] at `offset` of calldata, which is `length` bytes.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofSlot {
+    #[serde(flatten)]
+    pub item: ProofItem,
+    pub offset: usize,
+    pub length: usize,
+}
+
+/// Layout of calldata of verifier generated from a [`Protocol`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CalldataLayout {
+    /// Number of instances in each column.
+    pub num_instance: Vec<usize>,
+    /// Instances in the order they are encoded.
+    pub instances: Vec<InstanceSlot>,
+    /// Accumulators carried in instances, if any.
+    pub accumulators: Vec<AccumulatorSlot>,
+    /// Offset where proof starts, which is also the length of instances.
+    pub proof_offset: usize,
+    /// Items of proof.
+    pub proof: Vec<ProofSlot>,
+    /// Length of proof.
+    pub proof_length: usize,
+    /// Length of calldata.
+    pub calldata_length: usize,
+}
+
+impl CalldataLayout {
+    /// Returns layout of calldata of verifier generated from `protocol` with
+    /// multi-open scheme `PCS`, whose accumulators in instances (if any) are
+    /// encoded in [`LIMBS`] limbs of [`BITS`] bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any accumulator in instances isn't encoded in `4 * LIMBS`
+    /// limbs.
+    pub fn new<PCS>(protocol: &Protocol<G1Affine>) -> Self
+    where
+        PCS: MultiOpenScheme<G1Affine, NativeLoader>
+            + CostEstimation<G1Affine, Input = Vec<pcs::Query<Fr>>>,
+    {
+        let column_offsets = protocol
+            .num_instance
+            .iter()
+            .scan(0, |offset, num_instance| {
+                let column_offset = *offset;
+                *offset += num_instance * WORD;
+                Some(column_offset)
+            })
+            .collect::<Vec<_>>();
+        let instance_offset = |(column, row): (usize, usize)| column_offsets[column] + row * WORD;
+        let instances = protocol
+            .num_instance
+            .iter()
+            .enumerate()
+            .flat_map(|(column, num_instance)| (0..*num_instance).map(move |row| (column, row)))
+            .map(|(column, row)| InstanceSlot {
+                column,
+                row,
+                offset: instance_offset((column, row)),
+            })
+            .collect::<Vec<_>>();
+        let accumulators = protocol
+            .accumulator_indices
+            .iter()
+            .map(|indices| {
+                assert_eq!(indices.len(), 4 * LIMBS, "Accumulator should be encoded in 4 * LIMBS");
+                let mut offsets = indices.iter().map(|index| instance_offset(*index));
+                let [lhs_x, lhs_y, rhs_x, rhs_y] =
+                    [(); 4].map(|_| offsets.by_ref().take(LIMBS).collect());
+                AccumulatorSlot { limb_bits: BITS, num_limbs: LIMBS, lhs_x, lhs_y, rhs_x, rhs_y }
+            })
+            .collect();
+
+        let num_multi_open = {
+            let queries = PlonkProof::<G1Affine, NativeLoader, PCS>::empty_queries(protocol);
+            PCS::estimate_cost(&queries).num_commitment
+        };
+        let items = iter::empty()
+            .chain(protocol.num_witness.iter().enumerate().flat_map(|(phase, num_witness)| {
+                (0..*num_witness).map(move |index| (ProofItem::Witness { phase, index }, POINT))
+            }))
+            .chain(
+                (0..protocol.quotient.num_chunk())
+                    .map(|chunk| (ProofItem::Quotient { chunk }, POINT)),
+            )
+            .chain(protocol.evaluations.iter().map(|query| {
+                (ProofItem::Evaluation { poly: query.poly, rotation: query.rotation.0 }, WORD)
+            }))
+            .chain((0..num_multi_open).map(|index| (ProofItem::MultiOpen { index }, POINT)));
+        let proof_offset = instances.len() * WORD;
+        let mut offset = proof_offset;
+        let proof = items
+            .map(|(item, length)| {
+                let slot = ProofSlot { item, offset, length };
+                offset += length;
+                slot
+            })
+            .collect();
+
+        Self {
+            num_instance: protocol.num_instance.clone(),
+            instances,
+            accumulators,
+            proof_offset,
+            proof,
+            proof_length: offset - proof_offset,
+            calldata_length: offset,
+        }
+    }
+
+    /// Returns layout in pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Write layout in JSON to `path`, e.g. alongside the verifier.
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        serde_json::to_writer_pretty(File::create(path)?, self).map_err(io::Error::from)
+    }
+}
@@ -0,0 +1,177 @@
+//! Negative-test toolkit for generated verifiers, which tampers a valid proof
+//! and instances in systematic ways and asserts the verifier rejects all of
+//! them, as a soundness smoke-suite to run before deployment.
+use super::{encode_calldata, evm_verify_calldata};
+use crate::halo2_proofs::halo2curves::bn256::{Fq, Fr, G1Affine};
+use snark_verifier::{
+    loader::evm::{modulus, U256},
+    Protocol,
+};
+use std::fmt::{self, Display};
+
+/// Kind of tampering applied on valid instances and proof.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tamper {
+    /// Flip the least significant bit of the first evaluation in proof.
+    FlipProofByte,
+    /// Add one to the first instance.
+    WrongInstance,
+    /// Swap the first two commitments in proof.
+    SwapCommitments,
+    /// Replace the first commitment in proof by identity encoded as `(0, 0)`.
+    IdentityCommitment,
+    /// Add scalar field modulus to the first instance.
+    NonCanonicalInstance,
+    /// Add scalar field modulus to the first evaluation in proof.
+    NonCanonicalEvaluation,
+    /// Add base field modulus to x-coordinate of the first commitment in proof.
+    NonCanonicalCommitment,
+}
+
+impl Tamper {
+    /// Returns all kinds of tampering.
+    pub fn all() -> [Tamper; 7] {
+        [
+            Tamper::FlipProofByte,
+            Tamper::WrongInstance,
+            Tamper::SwapCommitments,
+            Tamper::IdentityCommitment,
+            Tamper::NonCanonicalInstance,
+            Tamper::NonCanonicalEvaluation,
+            Tamper::NonCanonicalCommitment,
+        ]
+    }
+}
+
+impl Display for Tamper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Tamper::FlipProofByte => "flipped proof byte",
+            Tamper::WrongInstance => "wrong instance",
+            Tamper::SwapCommitments => "swapped commitments",
+            Tamper::IdentityCommitment => "identity commitment",
+            Tamper::NonCanonicalInstance => "non-canonical instance",
+            Tamper::NonCanonicalEvaluation => "non-canonical evaluation",
+            Tamper::NonCanonicalCommitment => "non-canonical commitment",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Calldata tampered by [`Tamper`].
+#[derive(Clone, Debug)]
+pub struct TamperedCalldata {
+    pub tamper: Tamper,
+    pub calldata: Vec<u8>,
+}
+
+fn add_to_word(word: &mut [u8], value: U256) {
+    let (sum, overflow) = U256::from_big_endian(word).overflowing_add(value);
+    assert!(!overflow);
+    sum.to_big_endian(word);
+}
+
+/// Returns calldata of `instances` and `proof` tampered by `tamper`, or `None`
+/// if it's not applicable (e.g. [`Tamper::WrongInstance`] with no instance).
+///
+/// The layout of `proof` is derived from `protocol`, which starts with
+/// commitments of witness and quotient, then evaluations.
+pub fn tamper_calldata(
+    protocol: &Protocol<G1Affine>,
+    instances: &[Vec<Fr>],
+    proof: &[u8],
+    tamper: Tamper,
+) -> Option<Vec<u8>> {
+    let mut calldata = encode_calldata(instances, proof);
+    let num_instance = instances.iter().map(Vec::len).sum::<usize>();
+    let num_commitment = protocol.num_witness.iter().sum::<usize>() + protocol.quotient.num_chunk();
+    let commitment_offset = num_instance * 0x20;
+    let evaluation_offset = commitment_offset + num_commitment * 0x40;
+    let has_evaluation = !protocol.evaluations.is_empty() && calldata.len() > evaluation_offset;
+
+    match tamper {
+        Tamper::FlipProofByte => {
+            if !has_evaluation {
+                return None;
+            }
+            calldata[evaluation_offset + 0x1f] ^= 1;
+        }
+        Tamper::WrongInstance => {
+            if num_instance == 0 {
+                return None;
+            }
+            add_to_word(&mut calldata[..0x20], U256::one());
+        }
+        Tamper::SwapCommitments => {
+            let (lhs, rhs) = (commitment_offset, commitment_offset + 0x40);
+            if num_commitment < 2 || calldata[lhs..rhs] == calldata[rhs..rhs + 0x40] {
+                return None;
+            }
+            let tmp = calldata[lhs..rhs].to_vec();
+            calldata.copy_within(rhs..rhs + 0x40, lhs);
+            calldata[rhs..rhs + 0x40].copy_from_slice(&tmp);
+        }
+        Tamper::IdentityCommitment => {
+            if num_commitment == 0 {
+                return None;
+            }
+            calldata[commitment_offset..commitment_offset + 0x40].fill(0);
+        }
+        Tamper::NonCanonicalInstance => {
+            if num_instance == 0 {
+                return None;
+            }
+            add_to_word(&mut calldata[..0x20], modulus::<Fr>());
+        }
+        Tamper::NonCanonicalEvaluation => {
+            if !has_evaluation {
+                return None;
+            }
+            add_to_word(
+                &mut calldata[evaluation_offset..evaluation_offset + 0x20],
+                modulus::<Fr>(),
+            );
+        }
+        Tamper::NonCanonicalCommitment => {
+            if num_commitment == 0 {
+                return None;
+            }
+            add_to_word(
+                &mut calldata[commitment_offset..commitment_offset + 0x20],
+                modulus::<Fq>(),
+            );
+        }
+    }
+
+    Some(calldata)
+}
+
+/// Returns calldata tampered by every applicable [`Tamper`].
+pub fn tampered_calldatas(
+    protocol: &Protocol<G1Affine>,
+    instances: &[Vec<Fr>],
+    proof: &[u8],
+) -> Vec<TamperedCalldata> {
+    Tamper::all()
+        .into_iter()
+        .filter_map(|tamper| {
+            tamper_calldata(protocol, instances, proof, tamper)
+                .map(|calldata| TamperedCalldata { tamper, calldata })
+        })
+        .collect()
+}
+
+/// Assert the verifier deployed by `deployment_code` rejects every tampered
+/// calldata in `cases`.
+///
+/// # Panics
+///
+/// Panics with all kinds of tampering that get accepted.
+pub fn assert_evm_rejects(deployment_code: &[u8], cases: &[TamperedCalldata]) {
+    let accepted = cases
+        .iter()
+        .filter(|case| evm_verify_calldata(deployment_code.to_vec(), case.calldata.clone()))
+        .map(|case| case.tamper.to_string())
+        .collect::<Vec<_>>();
+    assert!(accepted.is_empty(), "Verifier accepts tampered calldata: {}", accepted.join(", "));
+}
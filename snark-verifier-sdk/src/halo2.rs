@@ -1,9 +1,12 @@
-use super::{read_instances, write_instances, CircuitExt, Snark, SnarkWitness};
-#[cfg(feature = "display")]
-use ark_std::{end_timer, start_timer};
-use halo2_base::halo2_proofs::{
+use super::{
+    check_instances, deserialize_versioned, read_instances, serialize_versioned, write_instances,
+    CircuitExt, Snark, SnarkWitness,
+};
+use crate::halo2_proofs::{
     self, poly::kzg::strategy::SingleStrategy, transcript::TranscriptReadBuffer,
 };
+#[cfg(feature = "display")]
+use ark_std::{end_timer, start_timer};
 use halo2_proofs::{
     circuit::Layouter,
     halo2curves::{
@@ -33,17 +36,23 @@ use snark_verifier::{
     loader::native::NativeLoader,
     pcs::{self, MultiOpenScheme},
     system::halo2::{compile, Config},
-    util::transcript::TranscriptWrite,
+    util::transcript::{Transcript, TranscriptWrite},
     verifier::PlonkProof,
     PoseidonSpec,
 };
 use std::{
     fs::{self, File},
+    io::{BufReader, BufWriter},
     marker::PhantomData,
     path::Path,
 };
 
+pub mod accumulation;
 pub mod aggregation;
+pub mod blame;
+pub mod compression;
+#[cfg(feature = "parallel")]
+pub mod parallel;
 
 // Poseidon parameters
 const T: usize = 5;
@@ -88,17 +97,49 @@ where
         MSMAccumulator = DualMSM<'params, Bn256>,
     >,
 {
+    gen_proof_multi::<C, P, V>(params, pk, vec![circuit], vec![instances], rng, path)
+}
+
+/// Generates a single native proof of multiple `circuits` sharing the same
+/// `pk`, where `instances[i]` are instances of `circuits[i]`, using either
+/// SHPLONK or GWC proving method. Uses Poseidon for Fiat-Shamir.
+///
+/// Caches the instances (concatenated in order of circuits) and proof if
+/// `path = Some(instance_path, proof_path)` is specified.
+pub fn gen_proof_multi<'params, C, P, V>(
+    params: &'params ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuits: Vec<C>,
+    instances: Vec<Vec<Vec<Fr>>>,
+    rng: &mut (impl Rng + Send),
+    path: Option<(&Path, &Path)>,
+) -> Vec<u8>
+where
+    C: Circuit<Fr>,
+    P: Prover<'params, KZGCommitmentScheme<Bn256>>,
+    V: Verifier<
+        'params,
+        KZGCommitmentScheme<Bn256>,
+        Guard = GuardKZG<'params, Bn256>,
+        MSMAccumulator = DualMSM<'params, Bn256>,
+    >,
+{
+    assert!(!circuits.is_empty());
+    assert_eq!(circuits.len(), instances.len());
+
     #[cfg(debug_assertions)]
     {
         use halo2_proofs::poly::commitment::Params;
-        halo2_proofs::dev::MockProver::run(params.k(), &circuit, instances.clone())
-            .unwrap()
-            .assert_satisfied();
+        for (circuit, instances) in circuits.iter().zip(instances.iter()) {
+            halo2_proofs::dev::MockProver::run(params.k(), circuit, instances.clone())
+                .unwrap()
+                .assert_satisfied();
+        }
     }
 
     if let Some((instance_path, proof_path)) = path {
         let cached_instances = read_instances(instance_path);
-        if matches!(cached_instances, Ok(tmp) if tmp == instances) && proof_path.exists() {
+        if matches!(cached_instances, Ok(tmp) if tmp == instances.concat()) && proof_path.exists() {
             #[cfg(feature = "display")]
             let read_time = start_timer!(|| format!("Reading proof from {proof_path:?}"));
 
@@ -110,6 +151,10 @@ where
         }
     }
 
+    let instances = instances
+        .iter()
+        .map(|instances| instances.iter().map(Vec::as_slice).collect_vec())
+        .collect_vec();
     let instances = instances.iter().map(Vec::as_slice).collect_vec();
 
     #[cfg(feature = "display")]
@@ -117,7 +162,7 @@ where
 
     let mut transcript =
         PoseidonTranscript::<NativeLoader, Vec<u8>>::from_spec(vec![], POSEIDON_SPEC.clone());
-    create_proof::<_, P, _, _, _, _>(params, pk, &[circuit], &[&instances], rng, &mut transcript)
+    create_proof::<_, P, _, _, _, _>(params, pk, &circuits, &instances, rng, &mut transcript)
         .unwrap();
     let proof = transcript.finalize();
 
@@ -125,7 +170,7 @@ where
     end_timer!(proof_time);
 
     if let Some((instance_path, proof_path)) = path {
-        write_instances(&instances, instance_path);
+        write_instances(&instances.concat(), instance_path);
         fs::write(proof_path, &proof).unwrap();
     }
 
@@ -136,7 +181,7 @@ where
                 params.verifier_params(),
                 pk.get_vk(),
                 AccumulatorStrategy::new(params.verifier_params()),
-                &[instances.as_slice()],
+                &instances,
                 &mut transcript_read,
             )
             .unwrap(),
@@ -174,10 +219,69 @@ pub fn gen_proof_shplonk<C: Circuit<Fr>>(
     gen_proof::<C, ProverSHPLONK<_>, VerifierSHPLONK<_>>(params, pk, circuit, instances, rng, path)
 }
 
+/// Generates a native proof with external `aux` (e.g. randomness from a L1
+/// beacon) absorbed into the transcript before anything else, using either
+/// SHPLONK or GWC multi-open scheme. Uses Poseidon for Fiat-Shamir.
+///
+/// Its snark should be aggregated by
+/// [`aggregate_with_aux`](crate::halo2::aggregation::aggregate_with_aux) with
+/// the same `aux`.
+pub fn gen_proof_with_aux<'params, C, P, V>(
+    params: &'params ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: C,
+    instances: Vec<Vec<Fr>>,
+    aux: &[Fr],
+    rng: &mut (impl Rng + Send),
+) -> Vec<u8>
+where
+    C: Circuit<Fr>,
+    P: Prover<'params, KZGCommitmentScheme<Bn256>>,
+    V: Verifier<
+        'params,
+        KZGCommitmentScheme<Bn256>,
+        Guard = GuardKZG<'params, Bn256>,
+        MSMAccumulator = DualMSM<'params, Bn256>,
+    >,
+{
+    let instances = instances.iter().map(Vec::as_slice).collect_vec();
+
+    let mut transcript =
+        PoseidonTranscript::<NativeLoader, Vec<u8>>::from_spec(vec![], POSEIDON_SPEC.clone());
+    transcript.absorb_aux(aux).unwrap();
+    create_proof::<_, P, _, _, _, _>(
+        params,
+        pk,
+        &[circuit],
+        &[instances.as_slice()],
+        rng,
+        &mut transcript,
+    )
+    .unwrap();
+    let proof = transcript.finalize();
+
+    debug_assert!({
+        let mut transcript_read = PoseidonTranscript::<NativeLoader, &[u8]>::new(proof.as_slice());
+        transcript_read.absorb_aux(aux).unwrap();
+        VerificationStrategy::<_, V>::finalize(
+            verify_proof::<_, V, _, _, _>(
+                params.verifier_params(),
+                pk.get_vk(),
+                AccumulatorStrategy::new(params.verifier_params()),
+                &[instances.as_slice()],
+                &mut transcript_read,
+            )
+            .unwrap(),
+        )
+    });
+
+    proof
+}
+
 /// Generates a SNARK using either SHPLONK or GWC multi-open scheme. Uses Poseidon for Fiat-Shamir.
 ///
 /// Tries to first deserialize from / later serialize the entire SNARK into `path` if specified.
-/// Serialization is done using `bincode`.
+/// Serialization is done using [`serialize_versioned`].
 pub fn gen_snark<'params, ConcreteCircuit, P, V>(
     params: &'params ParamsKZG<Bn256>,
     pk: &ProvingKey<G1Affine>,
@@ -185,6 +289,36 @@ pub fn gen_snark<'params, ConcreteCircuit, P, V>(
     rng: &mut (impl Rng + Send),
     path: Option<impl AsRef<Path>>,
 ) -> Snark
+where
+    ConcreteCircuit: CircuitExt<Fr>,
+    P: Prover<'params, KZGCommitmentScheme<Bn256>>,
+    V: Verifier<
+        'params,
+        KZGCommitmentScheme<Bn256>,
+        Guard = GuardKZG<'params, Bn256>,
+        MSMAccumulator = DualMSM<'params, Bn256>,
+    >,
+{
+    gen_snark_multi::<ConcreteCircuit, P, V>(params, pk, vec![circuit], rng, path)
+}
+
+/// Generates a SNARK of a single proof of multiple `circuits` sharing the same
+/// `pk`, using either SHPLONK or GWC multi-open scheme. Uses Poseidon for
+/// Fiat-Shamir.
+///
+/// The protocol is compiled with `num_proof` set to number of `circuits`, and
+/// instances of the SNARK are the ones of each circuit concatenated in order,
+/// so it can be verified or aggregated as any other SNARK.
+///
+/// Tries to first deserialize from / later serialize the entire SNARK into `path` if specified.
+/// Serialization is done using [`serialize_versioned`].
+pub fn gen_snark_multi<'params, ConcreteCircuit, P, V>(
+    params: &'params ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuits: Vec<ConcreteCircuit>,
+    rng: &mut (impl Rng + Send),
+    path: Option<impl AsRef<Path>>,
+) -> Snark
 where
     ConcreteCircuit: CircuitExt<Fr>,
     P: Prover<'params, KZGCommitmentScheme<Bn256>>,
@@ -200,24 +334,32 @@ where
             return snark;
         }
     }
+    assert!(!circuits.is_empty());
     let protocol = compile(
         params,
         pk.get_vk(),
         Config::kzg()
-            .with_num_instance(circuit.num_instance())
-            .with_accumulator_indices(ConcreteCircuit::accumulator_indices()),
+            .with_num_instance(circuits[0].num_instance())
+            .with_accumulator_indices(ConcreteCircuit::accumulator_indices())
+            .with_num_proof(circuits.len()),
     );
 
-    let instances = circuit.instances();
-    let proof =
-        gen_proof::<ConcreteCircuit, P, V>(params, pk, circuit, instances.clone(), rng, None);
+    let instances = circuits.iter().map(CircuitExt::instances).collect_vec();
+    let proof = gen_proof_multi::<ConcreteCircuit, P, V>(
+        params,
+        pk,
+        circuits,
+        instances.clone(),
+        rng,
+        None,
+    );
 
-    let snark = Snark::new(protocol, instances, proof);
+    let snark = Snark::new(protocol, instances.concat(), proof);
     if let Some(path) = &path {
         let f = File::create(path).unwrap();
         #[cfg(feature = "display")]
         let write_time = start_timer!(|| "Write SNARK");
-        bincode::serialize_into(f, &snark).unwrap();
+        serialize_versioned(BufWriter::new(f), &snark).unwrap();
         #[cfg(feature = "display")]
         end_timer!(write_time);
     }
@@ -254,6 +396,38 @@ pub fn gen_snark_shplonk<ConcreteCircuit: CircuitExt<Fr>>(
     )
 }
 
+/// Generates a SNARK of a single proof of multiple `circuits` using GWC multi-open scheme. Uses
+/// Poseidon for Fiat-Shamir.
+///
+/// Tries to first deserialize from / later serialize the entire SNARK into `path` if specified.
+pub fn gen_snark_multi_gwc<ConcreteCircuit: CircuitExt<Fr>>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuits: Vec<ConcreteCircuit>,
+    rng: &mut (impl Rng + Send),
+    path: Option<impl AsRef<Path>>,
+) -> Snark {
+    gen_snark_multi::<ConcreteCircuit, ProverGWC<_>, VerifierGWC<_>>(
+        params, pk, circuits, rng, path,
+    )
+}
+
+/// Generates a SNARK of a single proof of multiple `circuits` using SHPLONK multi-open scheme.
+/// Uses Poseidon for Fiat-Shamir.
+///
+/// Tries to first deserialize from / later serialize the entire SNARK into `path` if specified.
+pub fn gen_snark_multi_shplonk<ConcreteCircuit: CircuitExt<Fr>>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuits: Vec<ConcreteCircuit>,
+    rng: &mut (impl Rng + Send),
+    path: Option<impl AsRef<Path>>,
+) -> Snark {
+    gen_snark_multi::<ConcreteCircuit, ProverSHPLONK<_>, VerifierSHPLONK<_>>(
+        params, pk, circuits, rng, path,
+    )
+}
+
 /// Verifies a native proof using either SHPLONK or GWC proving method. Uses Poseidon for Fiat-Shamir.
 ///
 pub fn verify_snark<'params, ConcreteCircuit, V>(
@@ -274,11 +448,19 @@ where
         TranscriptReadBuffer::<_, G1Affine, _>::init(snark.proof.as_slice());
     let strategy = SingleStrategy::new(verifier_params);
     let instance_slice = snark.instances.iter().map(|x| &x[..]).collect::<Vec<_>>();
+    // Instances of SNARK generated by `gen_snark_multi` are split into the
+    // ones of each circuit
+    let num_instance_column = vk.cs().num_instance_columns();
+    let instance_slices = if num_instance_column == 0 {
+        vec![instance_slice.as_slice()]
+    } else {
+        instance_slice.chunks(num_instance_column).collect_vec()
+    };
     match verify_proof::<_, V, _, _, _>(
         verifier_params,
         vk,
         strategy,
-        &[instance_slice.as_slice()],
+        &instance_slices,
         &mut transcript,
     ) {
         Ok(_p) => true,
@@ -312,12 +494,17 @@ where
     verify_snark::<ConcreteCircuit, VerifierGWC<_>>(verifier_params, snark, vk)
 }
 
-/// Tries to deserialize a SNARK from the specified `path` using `bincode`.
+/// Tries to deserialize a SNARK from the specified `path` using
+/// [`deserialize_versioned`], which refuses
+/// SNARKs of incompatible format version.
 ///
 /// WARNING: The user must keep track of whether the SNARK was generated using the GWC or SHPLONK multi-open scheme.
 pub fn read_snark(path: impl AsRef<Path>) -> Result<Snark, bincode::Error> {
     let f = File::open(path).map_err(Box::<bincode::ErrorKind>::from)?;
-    bincode::deserialize_from(f)
+    let snark: Snark = deserialize_versioned(BufReader::new(f))?;
+    check_instances(&snark.protocol.num_instance, &snark.instances)
+        .map_err(|err| bincode::ErrorKind::Custom(err.to_string()))?;
+    Ok(snark)
 }
 
 pub fn gen_dummy_snark<ConcreteCircuit, MOS>(
@@ -0,0 +1,216 @@
+//! Native incremental accumulation of snarks, so a long-running service could
+//! fold proofs into a persisted accumulator as they arrive, and only run the
+//! decider (or an aggregation circuit) periodically.
+//!
+//! Each [`fold`] derives the random linear combination challenge from Poseidon
+//! transcript over all folded accumulators, the same as [`KzgAs`] does in
+//! [`AggregationCircuit`](super::aggregation::AggregationCircuit), so the
+//! result is deterministic and the final accumulator decides iff all folded
+//! ones do (with overwhelming probability).
+use super::{aggregation::Shplonk, PoseidonTranscript, Snark, POSEIDON_SPEC};
+use crate::halo2_proofs::{
+    halo2curves::{
+        bn256::{Bn256, G1Affine},
+        group::GroupEncoding,
+    },
+    poly::{commitment::ParamsProver, kzg::commitment::ParamsKZG},
+};
+use rand::rngs::OsRng;
+use snark_verifier::{
+    loader::native::NativeLoader,
+    pcs::{
+        kzg::{Bdfg21, Kzg, KzgAccumulator, KzgAs, KzgDecidingKey, KzgSuccinctVerifyingKey},
+        AccumulationSchemeProver, Decider,
+    },
+    verifier::PlonkVerifier,
+};
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::PathBuf,
+};
+
+pub type NativeAccumulator = KzgAccumulator<G1Affine, NativeLoader>;
+
+/// Returns accumulators of `snark` by succinct verification, which still need
+/// to be decided. If `snark` is of an aggregation circuit, the old accumulator
+/// in its instances at `protocol.accumulator_indices` is also returned after
+/// the new one, so chained aggregation needs no extra handling.
+pub fn succinct_verify_snark(
+    svk: &KzgSuccinctVerifyingKey<G1Affine>,
+    snark: &Snark,
+) -> Vec<NativeAccumulator> {
+    let mut transcript = PoseidonTranscript::<NativeLoader, &[u8]>::from_spec(
+        snark.proof.as_slice(),
+        POSEIDON_SPEC.clone(),
+    );
+    let proof = Shplonk::read_proof(svk, &snark.protocol, &snark.instances, &mut transcript);
+    Shplonk::succinct_verify(svk, &snark.protocol, &snark.instances, &proof)
+}
+
+/// Fold `acc_old` (if any) and `new_accumulators` into a single accumulator,
+/// which is the identity accumulator if there is nothing to fold.
+pub fn fold(
+    acc_old: Option<&NativeAccumulator>,
+    new_accumulators: &[NativeAccumulator],
+) -> NativeAccumulator {
+    let accumulators = acc_old.into_iter().chain(new_accumulators).cloned().collect::<Vec<_>>();
+    let mut transcript =
+        PoseidonTranscript::<NativeLoader, Vec<u8>>::from_spec(vec![], POSEIDON_SPEC.clone());
+    // Accumulation without zero-knowledge doesn't sample any randomness.
+    KzgAs::<Kzg<Bn256, Bdfg21>>::create_proof(
+        &Default::default(),
+        &accumulators,
+        &mut transcript,
+        OsRng,
+    )
+    .unwrap()
+}
+
+/// Accumulator folded so far with number of snarks folded into it.
+#[derive(Clone, Debug)]
+pub struct AccumulatorState {
+    pub accumulator: NativeAccumulator,
+    pub num_snark: u64,
+}
+
+impl AccumulatorState {
+    /// Length in bytes of [`AccumulatorState::to_bytes`].
+    pub const BYTES: usize = 8 + 2 * 32;
+
+    /// Serialize as `num_snark` in little-endian followed by compressed `lhs`
+    /// and `rhs`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let KzgAccumulator { lhs, rhs } = &self.accumulator;
+        [&self.num_snark.to_le_bytes()[..], lhs.to_bytes().as_ref(), rhs.to_bytes().as_ref()]
+            .concat()
+    }
+
+    /// Deserialize from bytes of [`AccumulatorState::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.len() != Self::BYTES {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Expected {} bytes of accumulator state, got {}", Self::BYTES, bytes.len()),
+            ));
+        }
+        let num_snark = u64::from_le_bytes(bytes[..8].try_into().unwrap());
+        let [lhs, rhs] = [&bytes[8..40], &bytes[40..]].map(|bytes| {
+            let mut repr = <G1Affine as GroupEncoding>::Repr::default();
+            repr.as_mut().copy_from_slice(bytes);
+            Option::<G1Affine>::from(G1Affine::from_bytes(&repr)).ok_or_else(|| {
+                io::Error::new(ErrorKind::InvalidData, "Invalid accumulator ec point")
+            })
+        });
+        Ok(Self { accumulator: KzgAccumulator::new(lhs?, rhs?), num_snark })
+    }
+}
+
+/// Persistence of [`AccumulatorState`].
+pub trait AccumulatorStore {
+    /// Returns stored state, or `None` if nothing has been stored.
+    fn load(&self) -> io::Result<Option<AccumulatorState>>;
+
+    /// Replace stored state by `state`.
+    fn store(&mut self, state: &AccumulatorState) -> io::Result<()>;
+}
+
+/// [`AccumulatorStore`] in memory.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryAccumulatorStore(Option<AccumulatorState>);
+
+impl AccumulatorStore for MemoryAccumulatorStore {
+    fn load(&self) -> io::Result<Option<AccumulatorState>> {
+        Ok(self.0.clone())
+    }
+
+    fn store(&mut self, state: &AccumulatorState) -> io::Result<()> {
+        self.0 = Some(state.clone());
+        Ok(())
+    }
+}
+
+/// [`AccumulatorStore`] in a single file, which is replaced atomically by
+/// renaming a temporary file written next to it.
+#[derive(Clone, Debug)]
+pub struct FileAccumulatorStore {
+    path: PathBuf,
+}
+
+impl FileAccumulatorStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl AccumulatorStore for FileAccumulatorStore {
+    fn load(&self) -> io::Result<Option<AccumulatorState>> {
+        match fs::read(&self.path) {
+            Ok(bytes) => AccumulatorState::from_bytes(&bytes).map(Some),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn store(&mut self, state: &AccumulatorState) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, state.to_bytes())?;
+        fs::rename(tmp, &self.path)
+    }
+}
+
+/// Incremental accumulation service folding snarks into [`AccumulatorStore`].
+#[derive(Clone, Debug)]
+pub struct AccumulationService<S> {
+    svk: KzgSuccinctVerifyingKey<G1Affine>,
+    dk: KzgDecidingKey<Bn256>,
+    store: S,
+}
+
+impl<S: AccumulatorStore> AccumulationService<S> {
+    /// Snarks should be generated with params downsized from `params`.
+    pub fn new(params: &ParamsKZG<Bn256>, store: S) -> Self {
+        Self { svk: params.get_g()[0].into(), dk: (params.g2(), params.s_g2()).into(), store }
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Fold accumulators of `snarks` into the stored one and returns the new
+    /// state, which is stored before returning.
+    pub fn accumulate<'a>(
+        &mut self,
+        snarks: impl IntoIterator<Item = &'a Snark>,
+    ) -> io::Result<AccumulatorState> {
+        let state = self.store.load()?;
+        let (mut accumulators, mut num_snark) = (Vec::new(), 0);
+        for snark in snarks {
+            accumulators.extend(succinct_verify_snark(&self.svk, snark));
+            num_snark += 1;
+        }
+        if accumulators.is_empty() {
+            return state.ok_or_else(|| io::Error::new(ErrorKind::NotFound, "Nothing to fold"));
+        }
+
+        let state = AccumulatorState {
+            accumulator: fold(state.as_ref().map(|state| &state.accumulator), &accumulators),
+            num_snark: state.map(|state| state.num_snark).unwrap_or_default() + num_snark,
+        };
+        self.store.store(&state)?;
+        Ok(state)
+    }
+
+    /// Run the decider on the stored accumulator, which returns `true` if
+    /// nothing has been accumulated.
+    pub fn decide(&self) -> io::Result<bool> {
+        Ok(self
+            .store
+            .load()?
+            .map(|state| Kzg::<Bn256, Bdfg21>::decide(&self.dk, state.accumulator))
+            .unwrap_or(true))
+    }
+}
@@ -1,8 +1,6 @@
 #![allow(clippy::clone_on_copy)]
-use crate::{Plonk, BITS, LIMBS};
-#[cfg(feature = "display")]
-use ark_std::{end_timer, start_timer};
-use halo2_base::{
+use crate::halo2_base::{
+    self,
     halo2_proofs::{
         circuit::{Layouter, SimpleFloorPlanner, Value},
         halo2curves::bn256::{Bn256, Fq, Fr, G1Affine},
@@ -10,35 +8,64 @@ use halo2_base::{
         poly::{commitment::ParamsProver, kzg::commitment::ParamsKZG},
     },
     utils::value_to_option,
-    AssignedValue,
+    AssignedValue, Context, ContextParams,
 };
-use halo2_base::{Context, ContextParams};
+use crate::{Plonk, BITS, LIMBS};
+#[cfg(feature = "display")]
+use ark_std::{end_timer, start_timer};
 use itertools::Itertools;
+use num_integer::Integer;
 use rand::Rng;
 use snark_verifier::{
     loader::{
         self,
         halo2::{
             halo2_ecc::{self, ecc::EccChip},
-            EccInstructions,
+            MsmConfig, Scalar,
         },
         native::NativeLoader,
+        EcPointLoader, LoadedEcPoint, ScalarLoader,
     },
     pcs::{
-        kzg::{Bdfg21, Kzg, KzgAccumulator, KzgAs, KzgSuccinctVerifyingKey},
-        AccumulationScheme, AccumulationSchemeProver, MultiOpenScheme, PolynomialCommitmentScheme,
+        kzg::{
+            Bdfg21, Kzg, KzgAccumulator, KzgAs, KzgDecidingKey, KzgSuccinctVerifyingKey,
+            LimbsEncodingInstructions,
+        },
+        AccumulationScheme, AccumulationSchemeProver, Decider, MultiOpenScheme,
+        PolynomialCommitmentScheme,
     },
-    util::arithmetic::fe_to_limbs,
-    verifier::PlonkVerifier,
+    system::halo2::transcript::halo2::NativeEncoding,
+    util::{
+        arithmetic::{fe_from_limbs, fe_to_fe, fe_to_limbs, CurveAffine},
+        hash,
+        transcript::Transcript,
+    },
+    verifier::{
+        groth16::{Groth16, Groth16Proof, Groth16VerifyingKey},
+        PlonkVerifier,
+    },
+    Protocol,
+};
+use std::{
+    fs::File,
+    io,
+    path::Path,
+    rc::Rc,
+    sync::{Arc, Mutex},
 };
-use std::{fs::File, rc::Rc};
 
-use super::{CircuitExt, PoseidonTranscript, Snark, SnarkWitness, POSEIDON_SPEC};
+use super::{
+    accumulation::succinct_verify_snark, blame::WitnessLayout, CircuitExt, PoseidonTranscript,
+    Snark, SnarkWitness, POSEIDON_SPEC, RATE, T,
+};
+use crate::check_instances;
 
 pub type Svk = KzgSuccinctVerifyingKey<G1Affine>;
 pub type BaseFieldEccChip = halo2_ecc::ecc::BaseFieldEccChip<G1Affine>;
-pub type Halo2Loader<'a> = loader::halo2::Halo2Loader<'a, G1Affine, BaseFieldEccChip>;
+pub type Halo2Loader<'a, EccChip = BaseFieldEccChip> =
+    loader::halo2::Halo2Loader<'a, G1Affine, EccChip>;
 pub type Shplonk = Plonk<Kzg<Bn256, Bdfg21>>;
+type Poseidon<L> = hash::Poseidon<Fr, L, T, RATE>;
 
 pub fn load_verify_circuit_degree() -> u32 {
     let path = std::env::var("VERIFY_CONFIG")
@@ -50,44 +77,344 @@ pub fn load_verify_circuit_degree() -> u32 {
     params.degree
 }
 
-pub fn flatten_accumulator<'a>(
-    accumulator: KzgAccumulator<G1Affine, Rc<Halo2Loader<'a>>>,
-) -> Vec<AssignedValue<Fr>> {
+/// Returns limbs of `accumulator` encoded by [`LimbsEncoding`], in the order
+/// of `lhs.x`, `lhs.y`, `rhs.x` and `rhs.y`.
+///
+/// [`LimbsEncoding`]: snark_verifier::pcs::kzg::LimbsEncoding
+pub fn flatten_accumulator<'a, EccChip>(
+    accumulator: KzgAccumulator<G1Affine, Rc<Halo2Loader<'a, EccChip>>>,
+) -> Vec<EccChip::AssignedCell>
+where
+    EccChip: LimbsEncodingInstructions<'a, G1Affine, LIMBS, BITS>,
+{
     let KzgAccumulator { lhs, rhs } = accumulator;
-    let lhs = lhs.into_assigned();
-    let rhs = rhs.into_assigned();
-
-    lhs.x
-        .truncation
-        .limbs
-        .into_iter()
-        .chain(lhs.y.truncation.limbs.into_iter())
-        .chain(rhs.x.truncation.limbs.into_iter())
-        .chain(rhs.y.truncation.limbs.into_iter())
+    let loader = lhs.loader().clone();
+
+    [lhs, rhs]
+        .iter()
+        .flat_map(|ec_point| {
+            let assigned = ec_point.assigned();
+            loader.ecc_chip().assign_ec_point_to_limbs(&mut loader.ctx_mut(), assigned).unwrap()
+        })
         .collect()
 }
 
 #[allow(clippy::type_complexity)]
 /// Core function used in `synthesize` to aggregate multiple `snarks`.
 ///  
+/// Each snark is verified with the domain of its own protocol, so snarks over
+/// different `k` could be aggregated together, as long as they share the same
+/// SRS, see [`AggregationCircuit::new`].
+///
+/// Operations of each snark and of accumulation are metered on `loader`, see
+/// [`Halo2Loader::op_meterings`].
+///
+/// `loader` could be backed by any chip implementing the instructions required
+/// by [`Plonk`] and [`PoseidonTranscript`], not only [`BaseFieldEccChip`].
+///
 /// Returns the assigned instances of previous snarks and the new final pair that needs to be verified in a pairing check.
 /// For each previous snark, we concatenate all instances into a single vector. We return a vector of vectors,
 /// one vector per snark, for convenience.
-pub fn aggregate<'a, PCS>(
+pub fn aggregate<'a, PCS, EccChip>(
+    svk: &PCS::SuccinctVerifyingKey,
+    loader: &Rc<Halo2Loader<'a, EccChip>>,
+    snarks: &[SnarkWitness],
+    as_proof: Value<&'_ [u8]>,
+) -> (Vec<Vec<EccChip::AssignedScalar>>, KzgAccumulator<G1Affine, Rc<Halo2Loader<'a, EccChip>>>)
+where
+    PCS: PolynomialCommitmentScheme<
+            G1Affine,
+            Rc<Halo2Loader<'a, EccChip>>,
+            Accumulator = KzgAccumulator<G1Affine, Rc<Halo2Loader<'a, EccChip>>>,
+        > + MultiOpenScheme<G1Affine, Rc<Halo2Loader<'a, EccChip>>>,
+    EccChip: LimbsEncodingInstructions<'a, G1Affine, LIMBS, BITS> + NativeEncoding<'a, G1Affine>,
+{
+    aggregate_with_hook::<PCS, EccChip>(svk, loader, snarks, as_proof, |_| {})
+}
+
+#[allow(clippy::type_complexity)]
+/// Same as [`aggregate`], but calls `after_snark` right after each snark is
+/// verified, e.g. to flush its witness by
+/// [`AggregationConfig::flush_lookup_cells`].
+pub fn aggregate_with_hook<'a, PCS, EccChip>(
+    svk: &PCS::SuccinctVerifyingKey,
+    loader: &Rc<Halo2Loader<'a, EccChip>>,
+    snarks: &[SnarkWitness],
+    as_proof: Value<&'_ [u8]>,
+    after_snark: impl FnMut(&Rc<Halo2Loader<'a, EccChip>>),
+) -> (Vec<Vec<EccChip::AssignedScalar>>, KzgAccumulator<G1Affine, Rc<Halo2Loader<'a, EccChip>>>)
+where
+    PCS: PolynomialCommitmentScheme<
+            G1Affine,
+            Rc<Halo2Loader<'a, EccChip>>,
+            Accumulator = KzgAccumulator<G1Affine, Rc<Halo2Loader<'a, EccChip>>>,
+        > + MultiOpenScheme<G1Affine, Rc<Halo2Loader<'a, EccChip>>>,
+    EccChip: LimbsEncodingInstructions<'a, G1Affine, LIMBS, BITS> + NativeEncoding<'a, G1Affine>,
+{
+    let (previous_instances, _, accumulator) =
+        aggregate_with_accumulators::<PCS, EccChip>(svk, loader, snarks, as_proof, after_snark);
+    (previous_instances, accumulator)
+}
+
+#[allow(clippy::type_complexity)]
+/// Same as [`aggregate_with_hook`], but also returns accumulators extracted
+/// from each snark before folding, which are the one of its own proof followed
+/// by the ones carried in its instances (if any), see
+/// [`PlonkVerifier::succinct_verify`].
+///
+/// So outer circuits could selectively commit to them or apply conditional
+/// logic on them, and auditors could trace the folding.
+pub fn aggregate_with_accumulators<'a, PCS, EccChip>(
+    svk: &PCS::SuccinctVerifyingKey,
+    loader: &Rc<Halo2Loader<'a, EccChip>>,
+    snarks: &[SnarkWitness],
+    as_proof: Value<&'_ [u8]>,
+    after_snark: impl FnMut(&Rc<Halo2Loader<'a, EccChip>>),
+) -> (
+    Vec<Vec<EccChip::AssignedScalar>>,
+    Vec<Vec<KzgAccumulator<G1Affine, Rc<Halo2Loader<'a, EccChip>>>>>,
+    KzgAccumulator<G1Affine, Rc<Halo2Loader<'a, EccChip>>>,
+)
+where
+    PCS: PolynomialCommitmentScheme<
+            G1Affine,
+            Rc<Halo2Loader<'a, EccChip>>,
+            Accumulator = KzgAccumulator<G1Affine, Rc<Halo2Loader<'a, EccChip>>>,
+        > + MultiOpenScheme<G1Affine, Rc<Halo2Loader<'a, EccChip>>>,
+    EccChip: LimbsEncodingInstructions<'a, G1Affine, LIMBS, BITS> + NativeEncoding<'a, G1Affine>,
+{
+    aggregate_impl::<PCS, EccChip>(
+        svk,
+        loader,
+        snarks,
+        &[],
+        as_proof,
+        |protocol| protocol.loaded(loader),
+        after_snark,
+        |_, accumulators| accumulators.to_vec(),
+    )
+}
+
+#[allow(clippy::type_complexity)]
+/// Same as [`aggregate`], but absorbs `aux` (e.g. randomness from a L1 beacon)
+/// into the transcript of each snark before reading its proof, for snarks
+/// proven with the same `aux` absorbed first, e.g. by
+/// [`gen_proof_with_aux`](crate::halo2::gen_proof_with_aux).
+///
+/// Callers are responsible for constraining `aux`, e.g. by exposing them as
+/// instances, otherwise the prover could choose them freely.
+pub fn aggregate_with_aux<'a, PCS, EccChip>(
+    svk: &PCS::SuccinctVerifyingKey,
+    loader: &Rc<Halo2Loader<'a, EccChip>>,
+    snarks: &[SnarkWitness],
+    aux: &[Scalar<'a, G1Affine, EccChip>],
+    as_proof: Value<&'_ [u8]>,
+) -> (Vec<Vec<EccChip::AssignedScalar>>, KzgAccumulator<G1Affine, Rc<Halo2Loader<'a, EccChip>>>)
+where
+    PCS: PolynomialCommitmentScheme<
+            G1Affine,
+            Rc<Halo2Loader<'a, EccChip>>,
+            Accumulator = KzgAccumulator<G1Affine, Rc<Halo2Loader<'a, EccChip>>>,
+        > + MultiOpenScheme<G1Affine, Rc<Halo2Loader<'a, EccChip>>>,
+    EccChip: LimbsEncodingInstructions<'a, G1Affine, LIMBS, BITS> + NativeEncoding<'a, G1Affine>,
+{
+    let (previous_instances, _, accumulator) = aggregate_impl::<PCS, EccChip>(
+        svk,
+        loader,
+        snarks,
+        aux,
+        as_proof,
+        |protocol| protocol.loaded(loader),
+        |_| {},
+        |_, accumulators| accumulators.to_vec(),
+    );
+    (previous_instances, accumulator)
+}
+
+#[allow(clippy::type_complexity)]
+/// Same as [`aggregate_with_hook`], but accumulators of the `idx`-th snark are
+/// replaced by `neutral_accumulator` when `flags[idx]` is `0`, so the snark
+/// could be any one with the same protocol (e.g. a dummy one) without failing
+/// the final pairing check. It's for aggregating a variable number of real
+/// snarks by one circuit.
+///
+/// `neutral_accumulator` should pass the pairing check by itself, e.g.
+/// `(s * G, G)` where `s * G` is the second point of SRS. `flags` are
+/// constrained to be boolean, and callers are responsible for exposing them,
+/// otherwise the verifier can't tell which snarks are aggregated.
+///
+/// # Panics
+///
+/// Panics if length of `flags` is different from `snarks`.
+pub fn aggregate_selectively<'a, PCS, EccChip>(
+    svk: &PCS::SuccinctVerifyingKey,
+    loader: &Rc<Halo2Loader<'a, EccChip>>,
+    snarks: &[SnarkWitness],
+    flags: &[Scalar<'a, G1Affine, EccChip>],
+    neutral_accumulator: &KzgAccumulator<G1Affine, NativeLoader>,
+    as_proof: Value<&'_ [u8]>,
+    after_snark: impl FnMut(&Rc<Halo2Loader<'a, EccChip>>),
+) -> (Vec<Vec<EccChip::AssignedScalar>>, KzgAccumulator<G1Affine, Rc<Halo2Loader<'a, EccChip>>>)
+where
+    PCS: PolynomialCommitmentScheme<
+            G1Affine,
+            Rc<Halo2Loader<'a, EccChip>>,
+            Accumulator = KzgAccumulator<G1Affine, Rc<Halo2Loader<'a, EccChip>>>,
+        > + MultiOpenScheme<G1Affine, Rc<Halo2Loader<'a, EccChip>>>,
+    EccChip: LimbsEncodingInstructions<'a, G1Affine, LIMBS, BITS> + NativeEncoding<'a, G1Affine>,
+{
+    assert_eq!(flags.len(), snarks.len(), "Each snark should have a flag");
+
+    for flag in flags {
+        let square = loader.sum_products(&[(flag, flag)]);
+        loader.assert_eq("flag is boolean", &square, flag).unwrap();
+    }
+
+    let select = |idx: usize, accumulators: &[KzgAccumulator<_, _>]| {
+        accumulators
+            .iter()
+            .map(|accumulator| {
+                let [lhs, rhs] = [
+                    (&accumulator.lhs, &neutral_accumulator.lhs),
+                    (&accumulator.rhs, &neutral_accumulator.rhs),
+                ]
+                .map(|(ec_point, neutral)| {
+                    let neutral = loader.ec_point_load_const(neutral);
+                    loader.ec_point_select(ec_point, &neutral, &flags[idx])
+                });
+                KzgAccumulator::new(lhs, rhs)
+            })
+            .collect_vec()
+    };
+    let (previous_instances, _, accumulator) = aggregate_impl::<PCS, EccChip>(
+        svk,
+        loader,
+        snarks,
+        &[],
+        as_proof,
+        |protocol| protocol.loaded(loader),
+        after_snark,
+        select,
+    );
+    (previous_instances, accumulator)
+}
+
+#[allow(clippy::type_complexity)]
+/// Same as [`aggregate_with_hook`], but preprocessed commitments and
+/// `transcript_initial_state` of each snark are assigned as witness instead of
+/// constants, and returned along with the Poseidon digest of them, see
+/// [`preprocessed_digest`]. So snarks of circuits with private structure could
+/// be aggregated, as long as callers expose the digests, otherwise the prover
+/// could verify snarks against any verifying key.
+///
+/// Only preprocessed commitments and `transcript_initial_state` are blinded,
+/// so the protocol of each snark must still match the one of keygen in
+/// everything else, e.g. the number of instances, queries and the expression
+/// of quotient.
+pub fn aggregate_blinded<'a, PCS, EccChip>(
+    svk: &PCS::SuccinctVerifyingKey,
+    loader: &Rc<Halo2Loader<'a, EccChip>>,
+    snarks: &[SnarkWitness],
+    as_proof: Value<&'_ [u8]>,
+    after_snark: impl FnMut(&Rc<Halo2Loader<'a, EccChip>>),
+) -> (
+    Vec<Vec<EccChip::AssignedScalar>>,
+    Vec<EccChip::AssignedScalar>,
+    KzgAccumulator<G1Affine, Rc<Halo2Loader<'a, EccChip>>>,
+)
+where
+    PCS: PolynomialCommitmentScheme<
+            G1Affine,
+            Rc<Halo2Loader<'a, EccChip>>,
+            Accumulator = KzgAccumulator<G1Affine, Rc<Halo2Loader<'a, EccChip>>>,
+        > + MultiOpenScheme<G1Affine, Rc<Halo2Loader<'a, EccChip>>>,
+    EccChip: LimbsEncodingInstructions<'a, G1Affine, LIMBS, BITS> + NativeEncoding<'a, G1Affine>,
+{
+    let mut digests = Vec::with_capacity(snarks.len());
+    let load_protocol = |protocol: &Protocol<G1Affine>| {
+        let protocol = protocol.loaded_preprocessed_as_witness(loader);
+        let inputs = protocol
+            .preprocessed
+            .iter()
+            .flat_map(|ec_point| {
+                loader.ecc_chip().encode(&mut loader.ctx_mut(), &ec_point.assigned()).unwrap()
+            })
+            .map(|assigned| loader.scalar_from_assigned(assigned))
+            .chain(protocol.transcript_initial_state.clone())
+            .collect_vec();
+        let mut hasher = Poseidon::from_spec(loader, POSEIDON_SPEC.clone());
+        hasher.update(&inputs);
+        digests.push(hasher.squeeze().into_assigned());
+        protocol
+    };
+    let (previous_instances, _, accumulator) = aggregate_impl::<PCS, EccChip>(
+        svk,
+        loader,
+        snarks,
+        &[],
+        as_proof,
+        load_protocol,
+        after_snark,
+        |_, accumulators| accumulators.to_vec(),
+    );
+    (previous_instances, digests, accumulator)
+}
+
+/// Returns Poseidon digest of preprocessed commitments, as native encoding of
+/// their coordinates, followed by `transcript_initial_state` of `protocol`,
+/// which is what [`aggregate_blinded`] computes in circuit.
+///
+/// # Panics
+///
+/// Panics if any preprocessed commitment is the identity, which has no
+/// coordinates to encode.
+pub fn preprocessed_digest(protocol: &Protocol<G1Affine>) -> Fr {
+    let inputs = protocol
+        .preprocessed
+        .iter()
+        .flat_map(|ec_point| {
+            let coordinates = ec_point.coordinates().unwrap();
+            [*coordinates.x(), *coordinates.y()]
+        })
+        .map(fe_to_fe)
+        .chain(protocol.transcript_initial_state)
+        .collect_vec();
+    let mut hasher = Poseidon::from_spec(&NativeLoader, POSEIDON_SPEC.clone());
+    hasher.update(&inputs);
+    hasher.squeeze()
+}
+
+#[allow(clippy::type_complexity)]
+/// Succinctly verifies `snarks` with protocols loaded by `load_protocol` and
+/// folds their accumulators, after mapping accumulators of the `idx`-th snark
+/// by `select(idx, accumulators)`. Returns accumulators of each snark before
+/// mapping.
+fn aggregate_impl<'a, PCS, EccChip>(
     svk: &PCS::SuccinctVerifyingKey,
-    loader: &Rc<Halo2Loader<'a>>,
+    loader: &Rc<Halo2Loader<'a, EccChip>>,
     snarks: &[SnarkWitness],
+    aux: &[Scalar<'a, G1Affine, EccChip>],
     as_proof: Value<&'_ [u8]>,
+    mut load_protocol: impl FnMut(
+        &Protocol<G1Affine>,
+    ) -> Protocol<G1Affine, Rc<Halo2Loader<'a, EccChip>>>,
+    mut after_snark: impl FnMut(&Rc<Halo2Loader<'a, EccChip>>),
+    mut select: impl FnMut(
+        usize,
+        &[KzgAccumulator<G1Affine, Rc<Halo2Loader<'a, EccChip>>>],
+    ) -> Vec<KzgAccumulator<G1Affine, Rc<Halo2Loader<'a, EccChip>>>>,
 ) -> (
-    Vec<Vec<<BaseFieldEccChip as EccInstructions<'a, G1Affine>>::AssignedScalar>>,
-    KzgAccumulator<G1Affine, Rc<Halo2Loader<'a>>>,
+    Vec<Vec<EccChip::AssignedScalar>>,
+    Vec<Vec<KzgAccumulator<G1Affine, Rc<Halo2Loader<'a, EccChip>>>>>,
+    KzgAccumulator<G1Affine, Rc<Halo2Loader<'a, EccChip>>>,
 )
 where
     PCS: PolynomialCommitmentScheme<
             G1Affine,
-            Rc<Halo2Loader<'a>>,
-            Accumulator = KzgAccumulator<G1Affine, Rc<Halo2Loader<'a>>>,
-        > + MultiOpenScheme<G1Affine, Rc<Halo2Loader<'a>>>,
+            Rc<Halo2Loader<'a, EccChip>>,
+            Accumulator = KzgAccumulator<G1Affine, Rc<Halo2Loader<'a, EccChip>>>,
+        > + MultiOpenScheme<G1Affine, Rc<Halo2Loader<'a, EccChip>>>,
+    EccChip: LimbsEncodingInstructions<'a, G1Affine, LIMBS, BITS> + NativeEncoding<'a, G1Affine>,
 {
     let assign_instances = |instances: &[Vec<Value<Fr>>]| {
         instances
@@ -100,46 +427,108 @@ where
 
     // TODO pre-allocate capacity better
     let mut previous_instances = Vec::with_capacity(snarks.len());
-    let mut transcript = PoseidonTranscript::<Rc<Halo2Loader<'a>>, _>::from_spec(
+    let mut transcript = PoseidonTranscript::<Rc<Halo2Loader<'a, EccChip>>, _>::from_spec(
         loader,
         Value::unknown(),
         POSEIDON_SPEC.clone(),
     );
 
-    let mut accumulators = snarks
+    let snark_accumulators = snarks
         .iter()
-        .flat_map(|snark| {
-            let protocol = snark.protocol.loaded(loader);
+        .enumerate()
+        .map(|(idx, snark)| {
+            loader.start_op_metering(&format!("snark {idx}"));
+            loader.start_witness_span(&format!("snark {idx}: load instances"));
+            let protocol = load_protocol(&snark.protocol);
+            // Shape of instances is bound to protocol, so witness can't be
+            // assigned with a layout different from the one of keygen.
+            check_instances(&protocol.num_instance, &snark.instances)
+                .unwrap_or_else(|err| panic!("Snark {idx} has invalid instances: {err}"));
             // TODO use 1d vector
             let instances = assign_instances(&snark.instances);
 
             // read the transcript and perform Fiat-Shamir
             // run through verification computation and produce the final pair `succinct`
             transcript.new_stream(snark.proof());
+            transcript.absorb_aux(aux).unwrap();
+            loader.start_witness_span(&format!("snark {idx}: read proof"));
             let proof = Plonk::<PCS>::read_proof(svk, &protocol, &instances, &mut transcript);
+            loader.start_witness_span(&format!("snark {idx}: succinct verify"));
             let accumulator = Plonk::<PCS>::succinct_verify(svk, &protocol, &instances, &proof);
 
             previous_instances.push(
                 instances.into_iter().flatten().map(|scalar| scalar.into_assigned()).collect(),
             );
+            loader.end_op_metering();
+            after_snark(loader);
 
             accumulator
         })
         .collect_vec();
 
-    let accumulator = if accumulators.len() > 1 {
-        transcript.new_stream(as_proof);
-        let proof =
-            KzgAs::<PCS>::read_proof(&Default::default(), &accumulators, &mut transcript).unwrap();
-        KzgAs::<PCS>::verify(&Default::default(), &accumulators, &proof).unwrap()
-    } else {
-        accumulators.pop().unwrap()
-    };
+    loader.start_witness_span("select accumulators");
+    let accumulators = snark_accumulators
+        .iter()
+        .enumerate()
+        .flat_map(|(idx, accumulators)| select(idx, accumulators))
+        .collect_vec();
 
-    (previous_instances, accumulator)
+    loader.start_op_metering("accumulation");
+    loader.start_witness_span("accumulation: read proof");
+    transcript.new_stream(as_proof);
+    let proof =
+        KzgAs::<PCS>::read_proof(&Default::default(), &accumulators, &mut transcript).unwrap();
+    loader.start_witness_span("accumulation: verify");
+    let accumulator = KzgAs::<PCS>::verify(&Default::default(), &accumulators, &proof).unwrap();
+    loader.end_op_metering();
+
+    (previous_instances, snark_accumulators, accumulator)
+}
+
+/// Groth16 proof to be aggregated by [`AggregationCircuit::new_with_groth16`].
+#[derive(Clone, Debug)]
+pub struct Groth16Snark {
+    pub vk: Groth16VerifyingKey<Bn256>,
+    pub instances: Vec<Fr>,
+    pub proof: Groth16Proof<Bn256, NativeLoader>,
+}
+
+/// Witness of [`Groth16Snark`] in [`AggregationCircuit`], which doesn't
+/// include the proof since only `IC` is computed in circuit.
+#[derive(Clone, Debug)]
+pub struct Groth16SnarkWitness {
+    pub vk: Groth16VerifyingKey<Bn256>,
+    pub instances: Vec<Value<Fr>>,
+}
+
+impl From<Groth16Snark> for Groth16SnarkWitness {
+    fn from(snark: Groth16Snark) -> Self {
+        Self { vk: snark.vk, instances: snark.instances.into_iter().map(Value::known).collect() }
+    }
+}
+
+impl Groth16SnarkWitness {
+    pub fn without_witnesses(&self) -> Self {
+        Self { vk: self.vk.clone(), instances: vec![Value::unknown(); self.instances.len()] }
+    }
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+/// Decode `IC` of each Groth16 proof from instances of [`AggregationCircuit`],
+/// which are encoded as limbs after the accumulator. The pairing check of each
+/// Groth16 proof is deferred to the verifier of [`AggregationCircuit`], which
+/// should run [`Groth16::verify_prepared`] with them.
+pub fn decode_groth16_prepared_instances(instances: &[Fr]) -> Vec<G1Affine> {
+    instances[4 * LIMBS..]
+        .chunks(2 * LIMBS)
+        .map(|limbs| {
+            let [x, y] = [&limbs[..LIMBS], &limbs[LIMBS..]]
+                .map(|limbs| fe_from_limbs::<_, _, LIMBS, BITS>(limbs.try_into().unwrap()));
+            G1Affine::from_xy(x, y).unwrap()
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct AggregationConfigParams {
     pub strategy: halo2_ecc::fields::fp::FpStrategy,
     pub degree: u32,
@@ -149,12 +538,84 @@ pub struct AggregationConfigParams {
     pub lookup_bits: usize,
     pub limb_bits: usize,
     pub num_limbs: usize,
+    /// Parameters of in-circuit MSMs, which default to the ecc chip defaults
+    /// when absent from config file.
+    #[serde(default)]
+    pub msm: MsmConfig,
+    /// Whether to copy cells pending lookup into lookup advice columns right
+    /// after each snark is verified, instead of keeping all of them until
+    /// `finalize`, to lower peak memory when aggregating many snarks. It
+    /// defaults to `false` when absent from config file.
+    #[serde(default)]
+    pub low_memory: bool,
+}
+
+impl AggregationConfigParams {
+    /// Returns params of degree `k` with the least `num_advice` and
+    /// `num_lookup_advice` to fit aggregating `num_snark` snarks, and
+    /// `lookup_bits = k - 1`, where other fields are kept.
+    ///
+    /// Cells are extrapolated from `layout` recorded by synthesizing an
+    /// [`AggregationCircuit`] of the same kind of snarks with any params that
+    /// fit and have the same `lookup_bits` (e.g. a wider or larger one), see
+    /// [`AggregationCircuit::witness_layout`], so there is no need to search
+    /// by trial and error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `layout` is recorded with different `lookup_bits`, or without
+    /// any snark.
+    pub fn tuned(&self, k: u32, num_snark: usize, layout: &WitnessLayout) -> Self {
+        let lookup_bits = k as usize - 1;
+        assert_eq!(
+            layout.lookup_bits, lookup_bits,
+            "Witness layout should be recorded with lookup_bits = k - 1"
+        );
+        let (measured_num_snark, measured_snark_cells) = layout.snark_advice_cells();
+        assert!(measured_num_snark > 0, "Witness layout should be recorded with snarks");
+
+        // Cells of verifying snarks scale with the number of them, and lookups
+        // scale with advice cells.
+        let num_advice_cells = layout.num_advice_cells() - measured_snark_cells
+            + Integer::div_ceil(&(measured_snark_cells * num_snark), &measured_num_snark);
+        let num_lookup =
+            Integer::div_ceil(&(layout.num_lookup * num_advice_cells), &layout.num_advice_cells());
+
+        // Usable rows depend on the constraint system, so columns are searched
+        // until they are stable.
+        let mut params = Self {
+            degree: k,
+            num_advice: vec![1],
+            num_lookup_advice: vec![1],
+            lookup_bits,
+            ..self.clone()
+        };
+        loop {
+            let max_rows = {
+                let mut meta = ConstraintSystem::default();
+                AggregationConfig::configure(&mut meta, params.clone()).gate().max_rows
+            };
+            let num_advice = vec![Integer::div_ceil(&num_advice_cells, &max_rows)];
+            let num_lookup_advice = vec![Integer::div_ceil(&num_lookup, &max_rows)];
+            if num_advice == params.num_advice && num_lookup_advice == params.num_lookup_advice {
+                return params;
+            }
+            params = Self { num_advice, num_lookup_advice, ..params };
+        }
+    }
+
+    /// Write params as the config JSON read from `VERIFY_CONFIG`.
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        serde_json::to_writer(File::create(path)?, self).map_err(io::Error::from)
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct AggregationConfig {
     pub base_field_config: halo2_ecc::fields::fp::FpConfig<Fr, Fq>,
     pub instance: Column<Instance>,
+    pub msm_config: MsmConfig,
+    pub low_memory: bool,
 }
 
 impl AggregationConfig {
@@ -181,7 +642,7 @@ impl AggregationConfig {
         let instance = meta.instance_column();
         meta.enable_equality(instance);
 
-        Self { base_field_config, instance }
+        Self { base_field_config, instance, msm_config: params.msm, low_memory: params.low_memory }
     }
 
     pub fn range(&self) -> &halo2_base::gates::range::RangeConfig<Fr> {
@@ -195,6 +656,44 @@ impl AggregationConfig {
     pub fn ecc_chip(&self) -> halo2_ecc::ecc::BaseFieldEccChip<G1Affine> {
         EccChip::construct(self.base_field_config.clone())
     }
+
+    /// Returns [`Halo2Loader`] with [`AggregationConfig::msm_config`].
+    pub fn loader<'a>(&self, ctx: Context<'a, Fr>) -> Rc<Halo2Loader<'a>> {
+        Halo2Loader::with_msm_config(self.ecc_chip(), ctx, self.msm_config)
+    }
+
+    /// Copy cells pending lookup in `ctx` into lookup advice columns from row
+    /// `*offset` on, and drop them from `ctx`, which is otherwise done all at
+    /// once by `finalize`. It does nothing unless
+    /// [`AggregationConfig::low_memory`] is set.
+    ///
+    /// Once called, it must be called again right before `finalize`, since
+    /// `finalize` copies the remaining cells from the first row, which would
+    /// overwrite the ones copied here.
+    pub fn flush_lookup_cells(
+        &self,
+        ctx: &mut Context<'_, Fr>,
+        offset: &mut usize,
+    ) -> Result<(), plonk::Error> {
+        let lookup_advice = match self.range().lookup_advice.first() {
+            Some(lookup_advice) if self.low_memory && !lookup_advice.is_empty() => lookup_advice,
+            _ => return Ok(()),
+        };
+
+        for acell in std::mem::take(&mut ctx.cells_to_lookup) {
+            let column = *lookup_advice
+                .get(*offset / ctx.max_rows)
+                .expect("Not enough lookup advice columns");
+            let row = *offset % ctx.max_rows;
+            #[cfg(feature = "halo2-pse")]
+            let bcell = ctx.region.assign_advice(|| "", column, row, || acell.value().copied())?;
+            #[cfg(feature = "halo2-axiom")]
+            let bcell = ctx.region.assign_advice(column, row, acell.value().copied())?;
+            ctx.region.constrain_equal(acell.cell(), bcell.cell())?;
+            *offset += 1;
+        }
+        Ok(())
+    }
 }
 
 /// Aggregation circuit that does not re-expose any public inputs from aggregated snarks
@@ -209,32 +708,136 @@ pub struct AggregationCircuit {
     instances: Vec<Fr>,
     // accumulation scheme proof, private input
     as_proof: Value<Vec<u8>>,
+    // Groth16 proofs whose `IC` is computed in circuit and exposed after the
+    // accumulator, with pairing check deferred to the verifier
+    groth16: Vec<Groth16SnarkWitness>,
+    // whether each snark is aggregated or replaced by `neutral_accumulator`,
+    // exposed after Groth16 `IC`s, empty if all snarks are aggregated
+    flags: Vec<Value<bool>>,
+    neutral_accumulator: KzgAccumulator<G1Affine, NativeLoader>,
+    // whether preprocessed commitments of snarks are witness bound by their
+    // digests, exposed after flags, instead of constants
+    blinded: bool,
+    // spans of witness recorded in the latest synthesis, to blame failures of
+    // `MockProver` on verification steps
+    witness_layout: Arc<Mutex<WitnessLayout>>,
 }
 
 impl AggregationCircuit {
+    /// Snarks could be generated over different `k`, as long as their params
+    /// are all downsized from `params`, so they share the same SRS.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any snark fails to verify against `params`, e.g. it's
+    /// generated with params from another SRS.
     pub fn new(
         params: &ParamsKZG<Bn256>,
         snarks: impl IntoIterator<Item = Snark>,
         rng: impl Rng + Send,
+    ) -> Self {
+        Self::new_with_groth16(params, snarks, [], rng)
+    }
+
+    /// Aggregate `snarks` along with `groth16_snarks`, e.g. to migrate from
+    /// circom systems. The pairing check of Groth16 proofs can't be deferred
+    /// into the KZG accumulator because `B` is a variable G2 point, so only
+    /// `IC` of each of them is computed in circuit and exposed as `2 * LIMBS`
+    /// instances after the accumulator, and the verifier of this circuit
+    /// should decode them by [`decode_groth16_prepared_instances`] and check
+    /// each proof by [`Groth16::verify_prepared`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if any snark or Groth16 proof fails to verify.
+    pub fn new_with_groth16(
+        params: &ParamsKZG<Bn256>,
+        snarks: impl IntoIterator<Item = Snark>,
+        groth16_snarks: impl IntoIterator<Item = Groth16Snark>,
+        rng: impl Rng + Send,
+    ) -> Self {
+        Self::new_inner(params, snarks, Vec::new(), groth16_snarks, false, rng)
+    }
+
+    /// Aggregate `snarks` with blinded verifying keys, where preprocessed
+    /// commitments and `transcript_initial_state` of each snark are witness
+    /// instead of constants of this circuit (see [`aggregate_blinded`]), and
+    /// are bound by their digest exposed as an instance after the accumulator,
+    /// one per snark. So snarks of circuits whose structure is private could
+    /// be aggregated, and the verifier only learns the digests, which could be
+    /// checked against the ones of accepted circuits by
+    /// [`preprocessed_digest`].
+    ///
+    /// Circuits of snarks must still share the same [`Protocol`] as the ones
+    /// of keygen except preprocessed commitments and
+    /// `transcript_initial_state`, e.g. by the same shape of gates and columns.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any snark fails to verify.
+    pub fn new_blinded(
+        params: &ParamsKZG<Bn256>,
+        snarks: impl IntoIterator<Item = Snark>,
+        rng: impl Rng + Send,
+    ) -> Self {
+        Self::new_inner(params, snarks, Vec::new(), [], true, rng)
+    }
+
+    /// Aggregate `snarks` selectively, where the `idx`-th snark is aggregated
+    /// only if `flags[idx]` is `true`, otherwise its accumulators are replaced
+    /// by a neutral one `(s * G, G)` natively and in circuit (see
+    /// [`aggregate_selectively`]). So snarks with flag `false` could be any
+    /// ones with the same protocol (e.g. dummy ones), and batches with a
+    /// variable number of real snarks could be aggregated under one verifying
+    /// key. Flags are exposed as instances after the accumulator, so the
+    /// verifier knows which snarks are aggregated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if length of `flags` is different from `snarks`, or any snark
+    /// with flag `true` fails to verify.
+    pub fn new_with_flags(
+        params: &ParamsKZG<Bn256>,
+        snarks: impl IntoIterator<Item = Snark>,
+        flags: impl IntoIterator<Item = bool>,
+        rng: impl Rng + Send,
+    ) -> Self {
+        let snarks = snarks.into_iter().collect_vec();
+        let flags = flags.into_iter().collect_vec();
+        assert_eq!(flags.len(), snarks.len(), "Each snark should have a flag");
+        Self::new_inner(params, snarks, flags, [], false, rng)
+    }
+
+    fn new_inner(
+        params: &ParamsKZG<Bn256>,
+        snarks: impl IntoIterator<Item = Snark>,
+        flags: Vec<bool>,
+        groth16_snarks: impl IntoIterator<Item = Groth16Snark>,
+        blinded: bool,
+        rng: impl Rng + Send,
     ) -> Self {
         let svk = params.get_g()[0].into();
+        let dk: KzgDecidingKey<Bn256> = (params.g2(), params.s_g2()).into();
+        let neutral_accumulator = KzgAccumulator::new(params.get_g()[1], params.get_g()[0]);
         let snarks = snarks.into_iter().collect_vec();
 
         // TODO: this is all redundant calculation to get the public output
         // Halo2 should just be able to expose public output to instance column directly
-        let mut transcript_read =
-            PoseidonTranscript::<NativeLoader, &[u8]>::from_spec(&[], POSEIDON_SPEC.clone());
         let accumulators = snarks
             .iter()
-            .flat_map(|snark| {
-                transcript_read.new_stream(snark.proof.as_slice());
-                let proof = Shplonk::read_proof(
-                    &svk,
-                    &snark.protocol,
-                    &snark.instances,
-                    &mut transcript_read,
+            .enumerate()
+            .flat_map(|(idx, snark)| {
+                let accumulators = succinct_verify_snark(&svk, snark);
+                if !flags.get(idx).copied().unwrap_or(true) {
+                    return vec![neutral_accumulator.clone(); accumulators.len()];
+                }
+                assert!(
+                    Kzg::<Bn256, Bdfg21>::decide_all(&dk, accumulators.clone()),
+                    "Snark {idx} with k = {} fails to verify, its params should be downsized \
+                     from aggregation params",
+                    snark.protocol.domain.k,
                 );
-                Shplonk::succinct_verify(&svk, &snark.protocol, &snark.instances, &proof)
+                accumulators
             })
             .collect_vec();
 
@@ -254,14 +857,43 @@ impl AggregationCircuit {
             (accumulator, transcript_write.finalize())
         };
 
+        let groth16_snarks = groth16_snarks.into_iter().collect_vec();
+        let ics = groth16_snarks
+            .iter()
+            .enumerate()
+            .map(|(idx, snark)| {
+                assert!(
+                    Groth16::verify(&snark.vk, &snark.instances, &snark.proof).unwrap_or(false),
+                    "Groth16 proof {idx} fails to verify"
+                );
+                Groth16::prepare_instances(&NativeLoader, &snark.vk, &snark.instances).unwrap()
+            })
+            .collect_vec();
+
         let KzgAccumulator { lhs, rhs } = accumulator;
-        let instances = [lhs.x, lhs.y, rhs.x, rhs.y].map(fe_to_limbs::<_, _, LIMBS, BITS>).concat();
+        let instances = [lhs.x, lhs.y, rhs.x, rhs.y]
+            .into_iter()
+            .chain(ics.iter().flat_map(|ic| {
+                let coordinates = ic.coordinates().unwrap();
+                [*coordinates.x(), *coordinates.y()]
+            }))
+            .flat_map(fe_to_limbs::<_, _, LIMBS, BITS>)
+            .chain(flags.iter().map(|flag| Fr::from(*flag as u64)))
+            .chain(
+                snarks.iter().filter(|_| blinded).map(|snark| preprocessed_digest(&snark.protocol)),
+            )
+            .collect();
 
         Self {
             svk,
             snarks: snarks.into_iter().map_into().collect(),
             instances,
             as_proof: Value::known(as_proof),
+            groth16: groth16_snarks.into_iter().map_into().collect(),
+            flags: flags.into_iter().map(Value::known).collect(),
+            neutral_accumulator,
+            blinded,
+            witness_layout: Default::default(),
         }
     }
 
@@ -280,12 +912,28 @@ impl AggregationCircuit {
     pub fn as_proof(&self) -> Value<&[u8]> {
         self.as_proof.as_ref().map(Vec::as_slice)
     }
+
+    pub fn groth16_snarks(&self) -> &[Groth16SnarkWitness] {
+        &self.groth16
+    }
+
+    /// Returns [`WitnessLayout`] recorded in the latest synthesis (e.g. by
+    /// `MockProver::run`), to blame its failures on verification steps by
+    /// [`WitnessLayout::blame`].
+    pub fn witness_layout(&self) -> WitnessLayout {
+        self.witness_layout.lock().unwrap().clone()
+    }
 }
 
 impl CircuitExt<Fr> for AggregationCircuit {
     fn num_instance(&self) -> Vec<usize> {
-        // [..lhs, ..rhs]
-        vec![4 * LIMBS]
+        // [..lhs, ..rhs, ..groth16_ics, ..flags, ..preprocessed_digests]
+        vec![
+            4 * LIMBS
+                + 2 * LIMBS * self.groth16.len()
+                + self.flags.len()
+                + self.snarks.len() * usize::from(self.blinded),
+        ]
     }
 
     fn instances(&self) -> Vec<Vec<Fr>> {
@@ -311,6 +959,11 @@ impl Circuit<Fr> for AggregationCircuit {
             snarks: self.snarks.iter().map(SnarkWitness::without_witnesses).collect(),
             instances: Vec::new(),
             as_proof: Value::unknown(),
+            groth16: self.groth16.iter().map(Groth16SnarkWitness::without_witnesses).collect(),
+            flags: vec![Value::unknown(); self.flags.len()],
+            neutral_accumulator: self.neutral_accumulator.clone(),
+            blinded: self.blinded,
+            witness_layout: Default::default(),
         }
     }
 
@@ -352,18 +1005,87 @@ impl Circuit<Fr> for AggregationCircuit {
                         },
                     );
 
-                    let ecc_chip = config.ecc_chip();
-                    let loader = Halo2Loader::new(ecc_chip, ctx);
-                    let (_, acc) = aggregate::<Kzg<Bn256, Bdfg21>>(
-                        &self.svk,
-                        &loader,
-                        &self.snarks,
-                        self.as_proof(),
-                    );
+                    let loader = config.loader(ctx);
+                    let mut lookup_offset = 0;
+                    let mut flush = |loader: &Rc<Halo2Loader>| {
+                        config.flush_lookup_cells(&mut loader.ctx_mut(), &mut lookup_offset)
+                    };
+                    loader.start_witness_span("flags");
+                    let flags = self
+                        .flags
+                        .iter()
+                        .map(|flag| loader.assign_scalar(flag.map(|flag| Fr::from(flag as u64))))
+                        .collect_vec();
+                    let mut digests = Vec::new();
+                    let (_, acc) = if self.blinded {
+                        let (previous_instances, preprocessed_digests, acc) =
+                            aggregate_blinded::<Kzg<Bn256, Bdfg21>, _>(
+                                &self.svk,
+                                &loader,
+                                &self.snarks,
+                                self.as_proof(),
+                                |loader| flush(loader).unwrap(),
+                            );
+                        digests = preprocessed_digests;
+                        (previous_instances, acc)
+                    } else if flags.is_empty() {
+                        aggregate_with_hook::<Kzg<Bn256, Bdfg21>, _>(
+                            &self.svk,
+                            &loader,
+                            &self.snarks,
+                            self.as_proof(),
+                            |loader| flush(loader).unwrap(),
+                        )
+                    } else {
+                        aggregate_selectively::<Kzg<Bn256, Bdfg21>, _>(
+                            &self.svk,
+                            &loader,
+                            &self.snarks,
+                            &flags,
+                            &self.neutral_accumulator,
+                            self.as_proof(),
+                            |loader| flush(loader).unwrap(),
+                        )
+                    };
 
                     instances.extend(
                         flatten_accumulator(acc).iter().map(|assigned| assigned.cell().clone()),
                     );
+                    assert_accumulator_exposed(instances.len(), Self::accumulator_indices());
+                    for (idx, groth16) in self.groth16.iter().enumerate() {
+                        loader.start_op_metering(&format!("groth16 {idx}"));
+                        loader.start_witness_span(&format!("groth16 {idx}"));
+                        let groth16_instances = groth16
+                            .instances
+                            .iter()
+                            .map(|instance| loader.assign_scalar(*instance))
+                            .collect_vec();
+                        let ic =
+                            Groth16::prepare_instances(&loader, &groth16.vk, &groth16_instances)
+                                .unwrap()
+                                .into_assigned();
+                        instances.extend(
+                            ic.x.truncation
+                                .limbs
+                                .iter()
+                                .chain(ic.y.truncation.limbs.iter())
+                                .map(|assigned| assigned.cell().clone()),
+                        );
+                        loader.end_op_metering();
+                        flush(&loader)?;
+                    }
+                    instances
+                        .extend(flags.into_iter().map(|flag| flag.into_assigned().cell().clone()));
+                    instances.extend(digests.iter().map(|digest| digest.cell().clone()));
+
+                    flush(&loader)?;
+                    *self.witness_layout.lock().unwrap() = WitnessLayout {
+                        max_rows: config.gate().max_rows,
+                        num_advice: config.gate().basic_gates[0].len(),
+                        spans: loader.witness_spans(),
+                        num_lookup: lookup_offset + loader.ctx().cells_to_lookup.len(),
+                        lookup_bits: config.range().lookup_bits,
+                    };
 
                     config.range().finalize(&mut loader.ctx_mut());
                     #[cfg(feature = "display")]
@@ -374,6 +1096,7 @@ impl Circuit<Fr> for AggregationCircuit {
             .unwrap();
 
         // Expose instances
+        assert_instances_exposed(instances.len(), &self.num_instance());
         for (i, cell) in instances.into_iter().enumerate() {
             layouter.constrain_instance(cell, config.instance, i)?;
         }
@@ -401,17 +1124,61 @@ impl PublicAggregationCircuit {
         has_prev_accumulator: bool,
         rng: &mut (impl Rng + Send),
     ) -> Self {
+        if has_prev_accumulator {
+            assert_prev_accumulators(snarks.iter().map(|snark| &snark.protocol));
+        }
         Self { aggregation: AggregationCircuit::new(params, snarks, rng), has_prev_accumulator }
     }
 }
 
+/// Assert accumulator of each protocol is at the first `4 * LIMBS` instances,
+/// which is where [`PublicAggregationCircuit`] skips it when passing through
+/// previous instances.
+///
+/// # Panics
+///
+/// Panics if any protocol has its accumulator elsewhere or no accumulator.
+fn assert_prev_accumulators<'a>(protocols: impl IntoIterator<Item = &'a Protocol<G1Affine>>) {
+    let expected = AggregationCircuit::accumulator_indices().unwrap();
+    for (idx, protocol) in protocols.into_iter().enumerate() {
+        assert!(
+            protocol.accumulator_indices == [expected.clone()],
+            "Snark {idx} should have accumulator at the first {} instances",
+            4 * LIMBS
+        );
+    }
+}
+
+/// Assert cells of accumulator exposed so far, `num_exposed` of them, are at
+/// `accumulator_indices`.
+fn assert_accumulator_exposed(
+    num_exposed: usize,
+    accumulator_indices: Option<Vec<(usize, usize)>>,
+) {
+    assert_eq!(
+        Some((0..num_exposed).map(|idx| (0, idx)).collect_vec()),
+        accumulator_indices,
+        "Accumulator should be exposed at accumulator_indices"
+    );
+}
+
+/// Assert `num_exposed` cells cover all instances of `num_instance`, otherwise
+/// instances not exposed would be unconstrained.
+fn assert_instances_exposed(num_exposed: usize, num_instance: &[usize]) {
+    assert_eq!(
+        [num_exposed].as_slice(),
+        num_instance,
+        "Exposed instances should match num_instance"
+    );
+}
+
 impl CircuitExt<Fr> for PublicAggregationCircuit {
     fn num_instance(&self) -> Vec<usize> {
         let prev_num = self
             .aggregation
             .snarks
             .iter()
-            .map(|snark| snark.instances.iter().map(|instance| instance.len()).sum::<usize>())
+            .map(|snark| snark.protocol.num_instance.iter().sum::<usize>())
             .sum::<usize>()
             - self.aggregation.snarks.len() * 4 * LIMBS * usize::from(self.has_prev_accumulator);
         vec![4 * LIMBS + prev_num]
@@ -466,6 +1233,9 @@ impl Circuit<Fr> for PublicAggregationCircuit {
     ) -> Result<(), plonk::Error> {
         #[cfg(feature = "display")]
         let witness_time = start_timer!(|| { "synthesize | EVM verifier" });
+        if self.has_prev_accumulator {
+            assert_prev_accumulators(self.aggregation.snarks.iter().map(|snark| &snark.protocol));
+        }
         config.range().load_lookup_table(&mut layouter).expect("load range lookup table");
         let mut first_pass = halo2_base::SKIP_FIRST_PASS;
         let mut instances = vec![];
@@ -486,17 +1256,22 @@ impl Circuit<Fr> for PublicAggregationCircuit {
                         },
                     );
 
-                    let ecc_chip = config.ecc_chip();
-                    let loader = Halo2Loader::new(ecc_chip, ctx);
-                    let (prev_instances, acc) = aggregate::<Kzg<Bn256, Bdfg21>>(
+                    let loader = config.loader(ctx);
+                    let mut lookup_offset = 0;
+                    let mut flush = |loader: &Rc<Halo2Loader>| {
+                        config.flush_lookup_cells(&mut loader.ctx_mut(), &mut lookup_offset)
+                    };
+                    let (prev_instances, acc) = aggregate_with_hook::<Kzg<Bn256, Bdfg21>, _>(
                         &self.aggregation.svk,
                         &loader,
                         &self.aggregation.snarks,
                         self.aggregation.as_proof(),
+                        |loader| flush(loader).unwrap(),
                     );
 
                     // accumulator
                     instances.extend(flatten_accumulator(acc).iter().map(|a| a.cell().clone()));
+                    assert_accumulator_exposed(instances.len(), Self::accumulator_indices());
                     // prev instances except accumulators
                     let start_idx = 4 * LIMBS * usize::from(self.has_prev_accumulator);
                     for prev_instance in prev_instances {
@@ -504,6 +1279,7 @@ impl Circuit<Fr> for PublicAggregationCircuit {
                             .extend(prev_instance[start_idx..].iter().map(|a| a.cell().clone()));
                     }
 
+                    flush(&loader)?;
                     config.range().finalize(&mut loader.ctx_mut());
                     #[cfg(feature = "display")]
                     loader.ctx_mut().print_stats(&["Range"]);
@@ -512,6 +1288,7 @@ impl Circuit<Fr> for PublicAggregationCircuit {
             )
             .unwrap();
         // Expose instances
+        assert_instances_exposed(instances.len(), &self.num_instance());
         for (i, cell) in instances.into_iter().enumerate() {
             layouter.constrain_instance(cell, config.instance, i)?;
         }
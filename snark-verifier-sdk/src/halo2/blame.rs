@@ -0,0 +1,100 @@
+//! Blame of `MockProver` failures of [`AggregationCircuit`] on verification
+//! steps of aggregated snarks, since the failures otherwise point into
+//! halo2-ecc internals.
+//!
+//! Witness of [`AggregationCircuit`] is assigned by [`Halo2Loader`] into
+//! advice columns of the gate one after another, and each verification step
+//! (e.g. `snark 1: read proof`, where the transcript squeezes challenges) is
+//! recorded as a span of offsets `column * max_rows + row` by
+//! `Halo2Loader::start_witness_span`. A failure only tells the row in the
+//! region, so it's blamed on steps which assigned witness at that row of any
+//! advice column.
+//!
+//! [`AggregationCircuit`]: crate::halo2::aggregation::AggregationCircuit
+//! [`Halo2Loader`]: snark_verifier::loader::halo2::Halo2Loader
+use crate::halo2_proofs::dev::{FailureLocation, VerifyFailure};
+use itertools::Itertools;
+use std::ops::Range;
+
+/// Spans of witness assigned by each verification step, recorded in the
+/// latest synthesis of [`AggregationCircuit`].
+///
+/// [`AggregationCircuit`]: crate::halo2::aggregation::AggregationCircuit
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WitnessLayout {
+    /// Maximum number of rows of each advice column.
+    pub max_rows: usize,
+    /// Number of advice columns of the gate.
+    pub num_advice: usize,
+    /// Identifier of each step with its span of offsets.
+    pub spans: Vec<(String, Range<usize>)>,
+    /// Number of cells range checked by lookup.
+    pub num_lookup: usize,
+    /// Bits of the range check lookup table.
+    pub lookup_bits: usize,
+}
+
+/// Verification steps a [`VerifyFailure`] is blamed on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Blame {
+    /// Index of the failure.
+    pub failure: usize,
+    /// Row of the failure in the region.
+    pub row: usize,
+    /// Identifiers of steps which assigned witness at `row`, e.g.
+    /// `"snark 1: succinct verify"`.
+    pub steps: Vec<String>,
+}
+
+impl WitnessLayout {
+    /// Returns number of advice cells spanned, including ones left unused at
+    /// the end of each column.
+    pub fn num_advice_cells(&self) -> usize {
+        self.spans.last().map(|(_, span)| span.end).unwrap_or_default()
+    }
+
+    /// Returns number of snarks and advice cells spanned by verifying them.
+    pub fn snark_advice_cells(&self) -> (usize, usize) {
+        let spans = self.spans.iter().filter(|(identifier, _)| identifier.starts_with("snark "));
+        let num_snark = spans.clone().filter(|(id, _)| id.ends_with(": load instances")).count();
+        (num_snark, spans.map(|(_, span)| span.len()).sum())
+    }
+
+    /// Returns identifiers of steps which assigned witness at `row` of any
+    /// advice column.
+    pub fn steps_at(&self, row: usize) -> Vec<String> {
+        (0..self.num_advice)
+            .map(|column| column * self.max_rows + row)
+            .filter_map(|offset| self.spans.iter().find(|(_, span)| span.contains(&offset)))
+            .map(|(identifier, _)| identifier.clone())
+            .dedup()
+            .collect()
+    }
+
+    /// Blame each of `failures` located in the region on steps, where ones
+    /// located outside of it are skipped.
+    pub fn blame(&self, failures: &[VerifyFailure]) -> Vec<Blame> {
+        failures
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, failure)| {
+                let row = failure_row(failure)?;
+                Some(Blame { failure: idx, row, steps: self.steps_at(row) })
+            })
+            .collect()
+    }
+}
+
+fn failure_row(failure: &VerifyFailure) -> Option<usize> {
+    let location = match failure {
+        VerifyFailure::CellNotAssigned { offset, .. } => return usize::try_from(*offset).ok(),
+        VerifyFailure::ConstraintNotSatisfied { location, .. }
+        | VerifyFailure::Lookup { location, .. }
+        | VerifyFailure::Permutation { location, .. } => location,
+        _ => return None,
+    };
+    match location {
+        FailureLocation::InRegion { offset, .. } => Some(*offset),
+        FailureLocation::OutsideRegion { .. } => None,
+    }
+}
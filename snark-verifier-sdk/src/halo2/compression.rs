@@ -0,0 +1,188 @@
+//! Compression circuit, which verifies exactly one snark and re-proves it with
+//! its own config, usually wider and with smaller degree than the layer below,
+//! so the outer proof could be cheaper to verify or be proved with another
+//! transcript (e.g. [`gen_evm_proof_shplonk`] for an EVM verifier).
+//!
+//! [`gen_evm_proof_shplonk`]: crate::evm::gen_evm_proof_shplonk
+#![allow(clippy::clone_on_copy)]
+use super::aggregation::{
+    aggregate, flatten_accumulator, AggregationCircuit, AggregationConfig, AggregationConfigParams,
+};
+use crate::halo2_base::{
+    self,
+    halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        halo2curves::bn256::{Bn256, Fr},
+        plonk::{self, Circuit, Selector},
+        poly::kzg::commitment::ParamsKZG,
+    },
+    utils::value_to_option,
+    Context, ContextParams,
+};
+use crate::{CircuitExt, Snark, LIMBS};
+#[cfg(feature = "display")]
+use ark_std::{end_timer, start_timer};
+use itertools::Itertools;
+use rand::Rng;
+use snark_verifier::pcs::kzg::{Bdfg21, Kzg};
+use std::fs::File;
+
+/// Environment variable of path to [`AggregationConfigParams`] of
+/// [`CompressionCircuit`].
+pub const COMPRESSION_CONFIG: &str = "COMPRESSION_CONFIG";
+
+pub fn load_compression_circuit_params() -> AggregationConfigParams {
+    let path = std::env::var(COMPRESSION_CONFIG)
+        .unwrap_or_else(|_| "./configs/compression_circuit.config".to_string());
+    serde_json::from_reader(
+        File::open(path.as_str()).unwrap_or_else(|_| panic!("{path} does not exist")),
+    )
+    .unwrap()
+}
+
+pub fn load_compression_circuit_degree() -> u32 {
+    load_compression_circuit_params().degree
+}
+
+/// This circuit takes exactly one SNARK and passes through all of its
+/// instances except the old accumulator.
+///
+/// * If `has_accumulator = true`, the SNARK is assumed to be of an aggregation
+/// or compression circuit with old accumulator as the first `4 * LIMBS`
+/// instances of the first instance column, which is folded into the new one.
+/// * Otherwise all instances are passed through.
+///
+/// The SNARK must be generated with Poseidon transcript, e.g. by
+/// [`gen_snark_shplonk`](super::gen_snark_shplonk).
+#[derive(Clone)]
+pub struct CompressionCircuit {
+    aggregation: AggregationCircuit,
+    has_accumulator: bool,
+}
+
+impl CompressionCircuit {
+    pub fn new(
+        params: &ParamsKZG<Bn256>,
+        snark: Snark,
+        has_accumulator: bool,
+        rng: impl Rng + Send,
+    ) -> Self {
+        Self { aggregation: AggregationCircuit::new(params, [snark], rng), has_accumulator }
+    }
+
+    fn num_old_accumulator_instance(&self) -> usize {
+        4 * LIMBS * usize::from(self.has_accumulator)
+    }
+}
+
+impl CircuitExt<Fr> for CompressionCircuit {
+    fn num_instance(&self) -> Vec<usize> {
+        let num_prev_instance = self.aggregation.snarks()[0]
+            .instances
+            .iter()
+            .map(|instances| instances.len())
+            .sum::<usize>();
+        vec![4 * LIMBS + num_prev_instance - self.num_old_accumulator_instance()]
+    }
+
+    fn instances(&self) -> Vec<Vec<Fr>> {
+        let start_idx = self.num_old_accumulator_instance();
+        let instance = self
+            .aggregation
+            .instance()
+            .into_iter()
+            .chain(self.aggregation.snarks()[0].instances.iter().enumerate().flat_map(
+                |(i, instances)| {
+                    instances[usize::from(i == 0) * start_idx..]
+                        .iter()
+                        .map(|v| value_to_option(*v).unwrap())
+                },
+            ))
+            .collect_vec();
+        vec![instance]
+    }
+
+    fn accumulator_indices() -> Option<Vec<(usize, usize)>> {
+        Some((0..4 * LIMBS).map(|idx| (0, idx)).collect())
+    }
+
+    fn selectors(config: &Self::Config) -> Vec<Selector> {
+        AggregationCircuit::selectors(config)
+    }
+}
+
+impl Circuit<Fr> for CompressionCircuit {
+    type Config = AggregationConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            aggregation: self.aggregation.without_witnesses(),
+            has_accumulator: self.has_accumulator,
+        }
+    }
+
+    fn configure(meta: &mut plonk::ConstraintSystem<Fr>) -> Self::Config {
+        AggregationConfig::configure(meta, load_compression_circuit_params())
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), plonk::Error> {
+        #[cfg(feature = "display")]
+        let witness_time = start_timer!(|| "synthesize | Compression Circuit");
+        config.range().load_lookup_table(&mut layouter).expect("load range lookup table");
+        let mut first_pass = halo2_base::SKIP_FIRST_PASS;
+        let mut instances = vec![];
+        layouter
+            .assign_region(
+                || "",
+                |region| {
+                    if first_pass {
+                        first_pass = false;
+                        return Ok(());
+                    }
+                    let ctx = Context::new(
+                        region,
+                        ContextParams {
+                            max_rows: config.gate().max_rows,
+                            num_context_ids: 1,
+                            fixed_columns: config.gate().constants.clone(),
+                        },
+                    );
+
+                    let loader = config.loader(ctx);
+                    let (prev_instances, acc) = aggregate::<Kzg<Bn256, Bdfg21>, _>(
+                        self.aggregation.succinct_verifying_key(),
+                        &loader,
+                        self.aggregation.snarks(),
+                        self.aggregation.as_proof(),
+                    );
+
+                    // accumulator
+                    instances.extend(flatten_accumulator(acc).iter().map(|a| a.cell().clone()));
+                    // prev instances except old accumulator
+                    let start_idx = self.num_old_accumulator_instance();
+                    for prev_instance in prev_instances {
+                        instances
+                            .extend(prev_instance[start_idx..].iter().map(|a| a.cell().clone()));
+                    }
+
+                    config.range().finalize(&mut loader.ctx_mut());
+                    #[cfg(feature = "display")]
+                    loader.ctx_mut().print_stats(&["Range"]);
+                    Ok(())
+                },
+            )
+            .unwrap();
+        // Expose instances
+        for (i, cell) in instances.into_iter().enumerate() {
+            layouter.constrain_instance(cell, config.instance, i)?;
+        }
+        #[cfg(feature = "display")]
+        end_timer!(witness_time);
+        Ok(())
+    }
+}
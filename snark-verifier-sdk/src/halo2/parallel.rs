@@ -0,0 +1,144 @@
+//! Generate many application SNARKs concurrently with a bounded [`rayon`]
+//! thread pool, instead of mapping over circuits sequentially.
+//!
+//! Each proof gets its own [`ChaCha20Rng`] seeded by `seed` on stream of its
+//! index, so the output only depends on `seed` and order of circuits, but not
+//! on scheduling or number of threads.
+use super::{gen_proof, gen_snark};
+use crate::{halo2_proofs, CircuitExt, Snark};
+use halo2_proofs::{
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::{Circuit, ProvingKey},
+    poly::{
+        commitment::{Prover, Verifier},
+        kzg::{
+            commitment::{KZGCommitmentScheme, ParamsKZG},
+            msm::DualMSM,
+            multiopen::{ProverGWC, ProverSHPLONK, VerifierGWC, VerifierSHPLONK},
+            strategy::GuardKZG,
+        },
+    },
+};
+use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
+
+/// Returns rng for the `idx`-th proof.
+pub fn proof_rng(seed: [u8; 32], idx: usize) -> ChaCha20Rng {
+    let mut rng = ChaCha20Rng::from_seed(seed);
+    rng.set_stream(idx as u64);
+    rng
+}
+
+/// Builds a thread pool with `num_threads` threads, where `0` means the
+/// [`rayon`] default (number of logical cpus or `RAYON_NUM_THREADS`).
+///
+/// The halo2 prover is parallelized by [`rayon`] as well, so it runs on the
+/// same pool, and `num_threads` also bounds how many proofs (and so how many
+/// sets of witnesses and polynomials) are in memory at the same time.
+fn thread_pool(num_threads: usize) -> ThreadPool {
+    ThreadPoolBuilder::new().num_threads(num_threads).build().unwrap()
+}
+
+/// Generates native proofs of `circuits` with their `instances` concurrently on
+/// at most `num_threads` threads, using either SHPLONK or GWC proving method.
+/// Uses Poseidon for Fiat-Shamir.
+///
+/// Proofs are returned in the same order as `circuits`.
+pub fn gen_proofs_parallel<'params, C, P, V>(
+    params: &'params ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuits: Vec<(C, Vec<Vec<Fr>>)>,
+    seed: [u8; 32],
+    num_threads: usize,
+) -> Vec<Vec<u8>>
+where
+    C: Circuit<Fr> + Send,
+    P: Prover<'params, KZGCommitmentScheme<Bn256>>,
+    V: Verifier<
+        'params,
+        KZGCommitmentScheme<Bn256>,
+        Guard = GuardKZG<'params, Bn256>,
+        MSMAccumulator = DualMSM<'params, Bn256>,
+    >,
+{
+    thread_pool(num_threads).install(|| {
+        circuits
+            .into_par_iter()
+            .enumerate()
+            .map(|(idx, (circuit, instances))| {
+                let mut rng = proof_rng(seed, idx);
+                gen_proof::<C, P, V>(params, pk, circuit, instances, &mut rng, None)
+            })
+            .collect()
+    })
+}
+
+/// Generates SNARKs of `circuits` concurrently on at most `num_threads`
+/// threads, using either SHPLONK or GWC multi-open scheme. Uses Poseidon for
+/// Fiat-Shamir.
+///
+/// SNARKs are returned in the same order as `circuits`.
+pub fn gen_snarks_parallel<'params, ConcreteCircuit, P, V>(
+    params: &'params ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuits: Vec<ConcreteCircuit>,
+    seed: [u8; 32],
+    num_threads: usize,
+) -> Vec<Snark>
+where
+    ConcreteCircuit: CircuitExt<Fr> + Send,
+    P: Prover<'params, KZGCommitmentScheme<Bn256>>,
+    V: Verifier<
+        'params,
+        KZGCommitmentScheme<Bn256>,
+        Guard = GuardKZG<'params, Bn256>,
+        MSMAccumulator = DualMSM<'params, Bn256>,
+    >,
+{
+    thread_pool(num_threads).install(|| {
+        circuits
+            .into_par_iter()
+            .enumerate()
+            .map(|(idx, circuit)| {
+                let mut rng = proof_rng(seed, idx);
+                gen_snark::<ConcreteCircuit, P, V>(params, pk, circuit, &mut rng, None::<&str>)
+            })
+            .collect()
+    })
+}
+
+/// Generates SNARKs of `circuits` concurrently using GWC multi-open scheme.
+/// See [`gen_snarks_parallel`].
+pub fn gen_snarks_parallel_gwc<ConcreteCircuit: CircuitExt<Fr> + Send>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuits: Vec<ConcreteCircuit>,
+    seed: [u8; 32],
+    num_threads: usize,
+) -> Vec<Snark> {
+    gen_snarks_parallel::<ConcreteCircuit, ProverGWC<_>, VerifierGWC<_>>(
+        params,
+        pk,
+        circuits,
+        seed,
+        num_threads,
+    )
+}
+
+/// Generates SNARKs of `circuits` concurrently using SHPLONK multi-open
+/// scheme. See [`gen_snarks_parallel`].
+pub fn gen_snarks_parallel_shplonk<ConcreteCircuit: CircuitExt<Fr> + Send>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuits: Vec<ConcreteCircuit>,
+    seed: [u8; 32],
+    num_threads: usize,
+) -> Vec<Snark> {
+    gen_snarks_parallel::<ConcreteCircuit, ProverSHPLONK<_>, VerifierSHPLONK<_>>(
+        params,
+        pk,
+        circuits,
+        seed,
+        num_threads,
+    )
+}
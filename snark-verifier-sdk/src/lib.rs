@@ -1,24 +1,34 @@
 #![feature(associated_type_defaults)]
 #[cfg(feature = "display")]
 use ark_std::{end_timer, start_timer};
-use halo2_base::halo2_proofs;
+// Re-export halo2 ecosystem crates of `snark_verifier`, so circuits fed into
+// `compile` or `AggregationCircuit` are built with exactly the same versions.
 use halo2_proofs::{
     circuit::Value,
     halo2curves::{
         bn256::{Bn256, Fr, G1Affine},
-        group::ff::Field,
+        group::{ff::Field, GroupEncoding},
     },
-    plonk::{keygen_pk, keygen_vk, Circuit, ProvingKey, Selector},
-    poly::kzg::commitment::ParamsKZG,
+    plonk::{keygen_pk, keygen_vk, Circuit, ProvingKey, Selector, VerifyingKey},
+    poly::{commitment::Params, kzg::commitment::ParamsKZG},
     SerdeFormat,
 };
 use itertools::Itertools;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+#[cfg(feature = "loader_halo2")]
+pub use snark_verifier::halo2_ecc;
 pub use snark_verifier::loader::native::NativeLoader;
-use snark_verifier::{pcs::kzg::LimbsEncoding, verifier, Protocol};
+use snark_verifier::{
+    format::{FormatVersion, FORMAT_MAGIC, FORMAT_VERSION},
+    pcs::kzg::LimbsEncoding,
+    system::halo2::{try_compile, Config},
+    verifier, Protocol,
+};
+pub use snark_verifier::{halo2_base, halo2_curves, halo2_proofs};
 use std::{
     fs::{self, File},
-    io::{self, BufReader, BufWriter},
+    io::{self, BufReader, BufWriter, Read, Write},
     path::Path,
 };
 
@@ -26,6 +36,8 @@ use std::{
 pub mod evm;
 #[cfg(feature = "loader_halo2")]
 pub mod halo2;
+#[cfg(all(feature = "loader_evm", feature = "loader_halo2"))]
+pub mod pipeline;
 
 #[cfg(test)]
 mod tests;
@@ -44,9 +56,87 @@ pub struct Snark {
 }
 
 impl Snark {
+    /// Initialize a [`Snark`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `instances` don't match `protocol.num_instance`, see
+    /// [`Snark::try_new`].
     pub fn new(protocol: Protocol<G1Affine>, instances: Vec<Vec<Fr>>, proof: Vec<u8>) -> Self {
-        Self { protocol, instances, proof }
+        Self::try_new(protocol, instances, proof).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Initialize a [`Snark`], or returns [`InstanceError`] if `instances`
+    /// don't match `protocol.num_instance`.
+    pub fn try_new(
+        protocol: Protocol<G1Affine>,
+        instances: Vec<Vec<Fr>>,
+        proof: Vec<u8>,
+    ) -> Result<Self, InstanceError> {
+        check_instances(&protocol.num_instance, &instances)?;
+        Ok(Self { protocol, instances, proof })
     }
+
+    /// Returns instance columns along with their indices.
+    pub fn instance_columns(&self) -> impl Iterator<Item = InstanceColumn<'_>> {
+        self.instances.iter().enumerate().map(|(index, values)| InstanceColumn { index, values })
+    }
+}
+
+/// Instances of a single column of [`Snark`], whose length is checked
+/// against `num_instance[index]` of its protocol.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InstanceColumn<'a> {
+    pub index: usize,
+    pub values: &'a [Fr],
+}
+
+/// Mismatch between instances and `num_instance` of a protocol.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InstanceError {
+    /// Number of instance columns is not `expected`.
+    NumColumn { expected: usize, found: usize },
+    /// Length of instance column `column` is not `expected`.
+    ColumnLength { column: usize, expected: usize, found: usize },
+}
+
+impl std::fmt::Display for InstanceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NumColumn { expected, found } => {
+                write!(f, "Expected {expected} instance columns, but got {found}")
+            }
+            Self::ColumnLength { column, expected, found } => {
+                write!(f, "Expected {expected} instances in column {column}, but got {found}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for InstanceError {}
+
+/// Check `instances` have the same shape as `num_instance`, which returns
+/// [`InstanceError`] of the first mismatch.
+pub fn check_instances<T>(
+    num_instance: &[usize],
+    instances: &[Vec<T>],
+) -> Result<(), InstanceError> {
+    if num_instance.len() != instances.len() {
+        return Err(InstanceError::NumColumn {
+            expected: num_instance.len(),
+            found: instances.len(),
+        });
+    }
+    for (column, (expected, instances)) in num_instance.iter().zip(instances).enumerate() {
+        if *expected != instances.len() {
+            return Err(InstanceError::ColumnLength {
+                column,
+                expected: *expected,
+                found: instances.len(),
+            });
+        }
+    }
+    Ok(())
 }
 
 impl From<Snark> for SnarkWitness {
@@ -71,13 +161,17 @@ pub struct SnarkWitness {
 }
 
 impl SnarkWitness {
+    /// Returns [`SnarkWitness`] with unknown instances and proof, whose shape of
+    /// instances is taken from `protocol.num_instance` instead of the given
+    /// instances, so circuits are always laid out by protocol constants.
     pub fn without_witnesses(&self) -> Self {
         SnarkWitness {
             protocol: self.protocol.clone(),
             instances: self
-                .instances
+                .protocol
+                .num_instance
                 .iter()
-                .map(|instances| vec![Value::unknown(); instances.len()])
+                .map(|num_instance| vec![Value::unknown(); *num_instance])
                 .collect(),
             proof: Value::unknown(),
         }
@@ -163,6 +257,80 @@ pub fn gen_pk<C: Circuit<Fr>>(
     pk
 }
 
+/// Returns hash identifying proving key of `vk` over `params`, which covers
+/// [`VerifyingKey::transcript_repr`] (the pinned constraint system together
+/// with fixed and permutation commitments, so any change of constants is
+/// covered too), the SRS and `config` (e.g. content of config json read by
+/// [`Circuit::configure`]).
+pub fn pk_hash(params: &ParamsKZG<Bn256>, vk: &VerifyingKey<G1Affine>, config: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(vk.transcript_repr().to_bytes());
+    hasher.update(params.k().to_le_bytes());
+    hasher.update(params.get_g()[0].to_bytes());
+    hasher.update(params.g2().to_bytes());
+    hasher.update(params.s_g2().to_bytes());
+    hasher.update(config);
+    hasher.finalize().into()
+}
+
+/// Writes `pk` into `path` prefixed with `hash` from [`pk_hash`].
+pub fn write_pk(pk: &ProvingKey<G1Affine>, hash: &[u8; 32], path: &Path) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut f = BufWriter::new(File::create(path)?);
+    f.write_all(hash)?;
+    pk.write(&mut f, SerdeFormat::RawBytesUnchecked)
+}
+
+/// Reads proving key written by [`write_pk`] from `path`, and refuses to load
+/// it if it's written with a different `hash`.
+pub fn read_pk_with_hash<C: Circuit<Fr>>(
+    hash: &[u8; 32],
+    path: &Path,
+) -> io::Result<ProvingKey<G1Affine>> {
+    let mut bufreader = BufReader::new(File::open(path)?);
+    let mut stored = [0; 32];
+    bufreader.read_exact(&mut stored)?;
+    if &stored != hash {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Stale proving key at {path:?} with hash {}", hex::encode(stored)),
+        ));
+    }
+    ProvingKey::read::<_, C>(&mut bufreader, SerdeFormat::RawBytesUnchecked)
+}
+
+/// Reads proving key cached at `path`, or generates and caches it when it
+/// doesn't exist or is stale, i.e. written for a different [`pk_hash`] of
+/// `circuit`'s verifying key, `params` and `config`.
+///
+/// The verifying key is always generated to compute the hash, which is much
+/// cheaper than generating the proving key.
+pub fn gen_pk_with_hash<C: Circuit<Fr>>(
+    params: &ParamsKZG<Bn256>,
+    circuit: &C,
+    config: &[u8],
+    path: &Path,
+) -> ProvingKey<G1Affine> {
+    let vk = keygen_vk(params, circuit).unwrap();
+    let hash = pk_hash(params, &vk, config);
+    match read_pk_with_hash::<C>(&hash, path) {
+        Ok(pk) => return pk,
+        Err(err) if matches!(err.kind(), io::ErrorKind::NotFound | io::ErrorKind::InvalidData) => {}
+        Err(err) => panic!("{err}"),
+    }
+
+    #[cfg(feature = "display")]
+    let pk_time = start_timer!(|| "Generating pkey");
+    let pk = keygen_pk(params, vk, circuit).unwrap();
+    #[cfg(feature = "display")]
+    end_timer!(pk_time);
+
+    write_pk(&pk, &hash, path).unwrap();
+    pk
+}
+
 pub fn read_instances(path: impl AsRef<Path>) -> Result<Vec<Vec<Fr>>, bincode::Error> {
     let f = File::open(path)?;
     let reader = BufReader::new(f);
@@ -190,3 +358,67 @@ pub fn write_instances(instances: &[&[Fr]], path: impl AsRef<Path>) {
     let f = BufWriter::new(File::create(path).unwrap());
     bincode::serialize_into(f, &instances).unwrap();
 }
+
+/// Reads [`VerifyingKey`] of circuit `C` written by [`VerifyingKey::write`] with
+/// `format`. Only `C::configure` is run to recover the constraint system, so
+/// neither witnesses nor keygen are needed.
+pub fn read_vk<C: Circuit<Fr>>(
+    reader: &mut impl Read,
+    format: SerdeFormat,
+) -> io::Result<VerifyingKey<G1Affine>> {
+    VerifyingKey::read::<_, C>(reader, format)
+}
+
+/// Compiles [`Protocol`] from bytes of [`VerifyingKey`] of circuit `C` and
+/// circuit-shape metadata in `config` (number of instances and accumulator
+/// indices), without regenerating the verifying key.
+///
+/// The constraint system is not part of serialized verifying key, so `C` is
+/// still needed for its `configure`. To generate verifiers in environments
+/// without circuit code at all, compile once and ship the [`Protocol`] by
+/// [`write_protocol`] instead.
+pub fn compile_from_vk_bytes<C: Circuit<Fr>>(
+    params: &ParamsKZG<Bn256>,
+    vk_bytes: &[u8],
+    format: SerdeFormat,
+    config: Config,
+) -> io::Result<Protocol<G1Affine>> {
+    let vk = read_vk::<C>(&mut &vk_bytes[..], format)?;
+    try_compile(params, &vk, config)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{err:?}")))
+}
+
+/// Writes [`Protocol`] to `path` using [`serialize_versioned`], which is all
+/// needed to generate verifiers besides params.
+pub fn write_protocol(protocol: &Protocol<G1Affine>, path: impl AsRef<Path>) -> io::Result<()> {
+    let f = BufWriter::new(File::create(path)?);
+    serialize_versioned(f, protocol).map_err(|err| io::Error::new(io::ErrorKind::Other, err))
+}
+
+/// Reads [`Protocol`] written by [`write_protocol`], see
+/// [`deserialize_versioned`].
+pub fn read_protocol(path: impl AsRef<Path>) -> Result<Protocol<G1Affine>, bincode::Error> {
+    let f = File::open(path)?;
+    deserialize_versioned(BufReader::new(f))
+}
+
+/// Serializes `value` using `bincode`, prefixed by [`FORMAT_MAGIC`] and
+/// [`FORMAT_VERSION`].
+pub fn serialize_versioned<T: Serialize>(writer: impl Write, value: &T) -> bincode::Result<()> {
+    bincode::serialize_into(writer, &(FORMAT_MAGIC, FORMAT_VERSION, value))
+}
+
+/// Deserializes value written by [`serialize_versioned`], and refuses it when
+/// its format version is incompatible with [`FORMAT_VERSION`]. Values written
+/// by plain `bincode` before versioning are migrated by reading as is.
+pub fn deserialize_versioned<T: DeserializeOwned>(mut reader: impl Read) -> bincode::Result<T> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if magic != FORMAT_MAGIC {
+        return bincode::deserialize_from(magic.as_slice().chain(reader));
+    }
+
+    let version: FormatVersion = bincode::deserialize_from(&mut reader)?;
+    version.check().map_err(|err| bincode::ErrorKind::Custom(format!("{err:?}")))?;
+    bincode::deserialize_from(reader)
+}
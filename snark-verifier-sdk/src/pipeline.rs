@@ -0,0 +1,286 @@
+//! Layered proving pipeline, which wires app snarks through optional
+//! compression and aggregation into a proof for the EVM verifier:
+//!
+//! ```text
+//! app circuits ─► app snarks ─► (compression snarks) ─► aggregation ─► EVM proof
+//! ```
+//!
+//! Params of every layer are downsized from the same SRS, and proving keys are
+//! cached in [`Pipeline::with_cache_dir`] if set, one file per layer with
+//! [`pk_hash`] of the layer's verifying key, params and config stored in it.
+//! A cached proving key whose hash doesn't match is regenerated.
+//!
+//! [`pk_hash`]: crate::pk_hash
+use crate::halo2_proofs::{
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::{Circuit, ProvingKey},
+    poly::{commitment::Params, kzg::commitment::ParamsKZG},
+};
+use crate::{
+    evm::{encode_calldata, gen_evm_proof_shplonk, gen_evm_verifier},
+    gen_pk, gen_pk_with_hash,
+    halo2::{
+        aggregation::PublicAggregationCircuit,
+        compression::{CompressionCircuit, COMPRESSION_CONFIG},
+        gen_snark_shplonk,
+    },
+    CircuitExt, Snark,
+};
+#[cfg(feature = "display")]
+use ark_std::{end_timer, start_timer};
+use rand::{rngs::OsRng, Rng};
+use snark_verifier::pcs::kzg::{Bdfg21, Kzg};
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+/// Final output of [`Pipeline::run`].
+#[derive(Clone, Debug)]
+pub struct PipelineOutput {
+    /// Deployment code of the EVM verifier.
+    pub deployment_code: Vec<u8>,
+    /// Instances of the outermost proof, which start with the accumulator.
+    pub instances: Vec<Vec<Fr>>,
+    /// Outermost proof with EVM transcript.
+    pub proof: Vec<u8>,
+    /// Calldata to the EVM verifier.
+    pub calldata: Vec<u8>,
+}
+
+/// Builder of layered proving pipeline.
+#[derive(Clone, Debug)]
+pub struct Pipeline {
+    k_app: u32,
+    k_compression: u32,
+    k_aggregation: u32,
+    compression: bool,
+    params_dir: PathBuf,
+    cache_dir: Option<PathBuf>,
+    compression_config: Option<PathBuf>,
+    aggregation_config: Option<PathBuf>,
+}
+
+impl Pipeline {
+    /// Returns a pipeline proving app circuits with degree `k_app` and
+    /// aggregating them with degree `k_aggregation`, without compression.
+    pub fn new(k_app: u32, k_aggregation: u32) -> Self {
+        Self {
+            k_app,
+            k_compression: k_aggregation,
+            k_aggregation,
+            compression: false,
+            params_dir: PathBuf::from("./params"),
+            cache_dir: None,
+            compression_config: None,
+            aggregation_config: None,
+        }
+    }
+
+    /// Set whether to compress each app snark before aggregation.
+    pub fn set_compression(mut self, compression: bool) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Set degree of compression circuits.
+    pub fn with_compression_k(mut self, k_compression: u32) -> Self {
+        self.k_compression = k_compression;
+        self
+    }
+
+    /// Set directory to read params from, where the SRS of the largest degree
+    /// is expected to be at `kzg_bn254_{k}.srs`. If it doesn't exist, an
+    /// insecure one is generated by randomness and written, which should only
+    /// be used for testing.
+    pub fn with_params_dir(mut self, params_dir: impl Into<PathBuf>) -> Self {
+        self.params_dir = params_dir.into();
+        self
+    }
+
+    /// Set directory to cache proving keys and the generated verifier into.
+    /// Proving keys are stored with [`pk_hash`](crate::pk_hash), so stale
+    /// ones are regenerated instead of being loaded.
+    pub fn with_cache_dir(mut self, cache_dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(cache_dir.into());
+        self
+    }
+
+    /// Set path to config of compression circuits, otherwise environment
+    /// variable [`COMPRESSION_CONFIG`] is used.
+    pub fn with_compression_config(mut self, path: impl Into<PathBuf>) -> Self {
+        self.compression_config = Some(path.into());
+        self
+    }
+
+    /// Set path to config of aggregation circuit, otherwise environment
+    /// variable `VERIFY_CONFIG` is used.
+    pub fn with_aggregation_config(mut self, path: impl Into<PathBuf>) -> Self {
+        self.aggregation_config = Some(path.into());
+        self
+    }
+
+    fn cache_path(&self, name: &str) -> Option<PathBuf> {
+        self.cache_dir.as_ref().map(|dir| dir.join(name))
+    }
+
+    /// Returns proving key of `circuit`, cached by [`gen_pk_with_hash`] as
+    /// `name` with `config` if cache directory is set.
+    fn gen_pk<C: Circuit<Fr>>(
+        &self,
+        params: &ParamsKZG<Bn256>,
+        circuit: &C,
+        name: &str,
+        config: &[u8],
+    ) -> ProvingKey<G1Affine> {
+        match self.cache_path(&format!("{name}.pkey")) {
+            Some(path) => gen_pk_with_hash(params, circuit, config, &path),
+            None => gen_pk(params, circuit, None),
+        }
+    }
+
+    /// Compress each snark with [`CompressionCircuit`], which shares the same
+    /// proving key as long as all snarks are of the same circuit.
+    fn compress(
+        &self,
+        params: &ParamsKZG<Bn256>,
+        snarks: Vec<Snark>,
+        rng: &mut (impl Rng + Send),
+    ) -> Vec<Snark> {
+        let circuits = snarks
+            .into_iter()
+            .map(|snark| CompressionCircuit::new(params, snark, false, &mut *rng))
+            .collect::<Vec<_>>();
+        let pk = self.gen_pk(
+            params,
+            &circuits[0],
+            "compression",
+            &config_with(
+                COMPRESSION_CONFIG,
+                "./configs/compression_circuit.config",
+                &format!("num_instance={:?}", circuits[0].num_instance()),
+            ),
+        );
+        circuits
+            .into_iter()
+            .map(|circuit| gen_snark_shplonk(params, &pk, circuit, rng, None::<&Path>))
+            .collect()
+    }
+
+    /// Returns params of the largest degree among layers, read from or
+    /// written into [`Pipeline::with_params_dir`].
+    fn load_params(&self) -> ParamsKZG<Bn256> {
+        let k = self.k_app.max(self.k_compression).max(self.k_aggregation);
+        let path = self.params_dir.join(format!("kzg_bn254_{k}.srs"));
+        if let Ok(f) = File::open(&path) {
+            return ParamsKZG::<Bn256>::read(&mut BufReader::new(f)).unwrap();
+        }
+
+        let params = ParamsKZG::<Bn256>::setup(k, OsRng);
+        fs::create_dir_all(&self.params_dir).unwrap();
+        params.write(&mut BufWriter::new(File::create(&path).unwrap())).unwrap();
+        params
+    }
+
+    /// Prove all `circuits` through the layers and returns [`PipelineOutput`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `circuits` is empty.
+    pub fn run<C: CircuitExt<Fr>>(
+        &self,
+        circuits: Vec<C>,
+        rng: &mut (impl Rng + Send),
+    ) -> PipelineOutput {
+        assert!(!circuits.is_empty(), "Pipeline requires at least one circuit");
+
+        if let Some(path) = &self.compression_config {
+            std::env::set_var(COMPRESSION_CONFIG, path);
+        }
+        if let Some(path) = &self.aggregation_config {
+            std::env::set_var("VERIFY_CONFIG", path);
+        }
+
+        let params = self.load_params();
+        let downsized = |k: u32| {
+            let mut params = params.clone();
+            if params.k() > k {
+                params.downsize(k);
+            }
+            params
+        };
+
+        // App layer
+        #[cfg(feature = "display")]
+        let app_time = start_timer!(|| "Pipeline | app layer");
+        let params_app = downsized(self.k_app);
+        let pk_app = self.gen_pk(&params_app, &circuits[0], "app", &[]);
+        let snarks = circuits
+            .into_iter()
+            .map(|circuit| gen_snark_shplonk(&params_app, &pk_app, circuit, rng, None::<&Path>))
+            .collect::<Vec<_>>();
+        #[cfg(feature = "display")]
+        end_timer!(app_time);
+
+        // Compression layer
+        let snarks = if self.compression {
+            #[cfg(feature = "display")]
+            let compression_time = start_timer!(|| "Pipeline | compression layer");
+            let params_compression = downsized(self.k_compression);
+            let snarks = self.compress(&params_compression, snarks, rng);
+            #[cfg(feature = "display")]
+            end_timer!(compression_time);
+            snarks
+        } else {
+            snarks
+        };
+
+        // Aggregation layer
+        #[cfg(feature = "display")]
+        let aggregation_time = start_timer!(|| "Pipeline | aggregation layer");
+        let params_aggregation = downsized(self.k_aggregation);
+        let num_snark = snarks.len();
+        let circuit =
+            PublicAggregationCircuit::new(&params_aggregation, snarks, self.compression, rng);
+        let pk_aggregation = self.gen_pk(
+            &params_aggregation,
+            &circuit,
+            &format!("aggregation_{num_snark}"),
+            &config_with(
+                "VERIFY_CONFIG",
+                "./configs/verify_circuit.config",
+                &format!("num_snark={num_snark},has_prev_accumulator={}", self.compression),
+            ),
+        );
+        let deployment_code = gen_evm_verifier::<PublicAggregationCircuit, Kzg<Bn256, Bdfg21>>(
+            &params_aggregation,
+            pk_aggregation.get_vk(),
+            circuit.num_instance(),
+            self.cache_path(&format!("aggregation_{num_snark}.sol")).as_deref(),
+        );
+        let instances = circuit.instances();
+        let proof = gen_evm_proof_shplonk(
+            &params_aggregation,
+            &pk_aggregation,
+            circuit,
+            instances.clone(),
+            rng,
+        );
+        let calldata = encode_calldata(&instances, &proof);
+        #[cfg(feature = "display")]
+        end_timer!(aggregation_time);
+
+        PipelineOutput { deployment_code, instances, proof, calldata }
+    }
+}
+
+/// Returns content of config file at path of environment variable `var` (or
+/// `default`) followed by `extra`, which is hashed into
+/// [`pk_hash`](crate::pk_hash) of proving key.
+fn config_with(var: &str, default: &str, extra: &str) -> Vec<u8> {
+    let path = std::env::var(var).unwrap_or_else(|_| default.to_string());
+    let mut config = fs::read(path).unwrap_or_default();
+    config.extend_from_slice(extra.as_bytes());
+    config
+}
@@ -0,0 +1,59 @@
+use super::TestCircuit1;
+use crate::halo2::accumulation::{
+    AccumulationService, AccumulatorState, AccumulatorStore, FileAccumulatorStore,
+    MemoryAccumulatorStore,
+};
+use crate::halo2_proofs;
+use crate::{gen_pk, halo2::gen_snark_shplonk};
+use ark_std::test_rng;
+use halo2_proofs::{
+    halo2curves::bn256::{Bn256, G1Affine},
+    poly::{commitment::ParamsProver, kzg::commitment::ParamsKZG},
+};
+use snark_verifier::pcs::kzg::KzgAccumulator;
+
+#[test]
+fn test_incremental_accumulation() {
+    let mut rng = test_rng();
+    let params = ParamsKZG::<Bn256>::setup(8, &mut rng);
+    let circuit = TestCircuit1::rand(&mut rng);
+    let pk = gen_pk(&params, &circuit, None);
+    let snarks = (0..3)
+        .map(|_| {
+            let circuit = TestCircuit1::rand(&mut rng);
+            gen_snark_shplonk(&params, &pk, circuit, &mut rng, None::<&str>)
+        })
+        .collect::<Vec<_>>();
+
+    // Fold one by one into file, which decides after each step
+    let path = std::path::Path::new("data/accumulation/accumulator");
+    let _ = std::fs::remove_file(path);
+    let mut service = AccumulationService::new(&params, FileAccumulatorStore::new(path));
+    assert!(service.decide().unwrap());
+    for snark in snarks.iter() {
+        service.accumulate([snark]).unwrap();
+        assert!(service.decide().unwrap());
+    }
+    let state = service.store().load().unwrap().unwrap();
+    assert_eq!(state.num_snark, 3);
+    let roundtrip = AccumulatorState::from_bytes(&state.to_bytes()).unwrap();
+    assert_eq!(
+        (roundtrip.accumulator.lhs, roundtrip.accumulator.rhs),
+        (state.accumulator.lhs, state.accumulator.rhs)
+    );
+    std::fs::remove_file(path).unwrap();
+
+    // Invalid accumulator makes the folded one fail to decide
+    let mut service = AccumulationService::new(&params, MemoryAccumulatorStore::default());
+    let state = service.accumulate(&snarks).unwrap();
+    let g1 = params.get_g()[0];
+    let invalid = AccumulatorState {
+        accumulator: KzgAccumulator::<G1Affine, _>::new(g1, g1),
+        num_snark: state.num_snark,
+    };
+    let mut store = MemoryAccumulatorStore::default();
+    store.store(&invalid).unwrap();
+    let mut service = AccumulationService::new(&params, store);
+    service.accumulate(&snarks).unwrap();
+    assert!(!service.decide().unwrap());
+}
@@ -0,0 +1,58 @@
+use super::TestCircuit1;
+use crate::halo2::aggregation::AggregationCircuit;
+use crate::CircuitExt;
+use crate::{gen_pk, halo2::gen_snark_shplonk};
+use crate::{halo2_base::utils::fs::gen_srs, halo2_proofs};
+use ark_std::test_rng;
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::halo2curves::bn256::Fr;
+use halo2_proofs::poly::commitment::Params;
+
+#[test]
+fn test_shplonk_aggregation_blame() {
+    std::env::set_var("VERIFY_CONFIG", "./configs/example_evm_accumulator.config");
+    let k = 8;
+    let k_agg = 21;
+
+    let mut rng = test_rng();
+    let params_outer = gen_srs(k_agg);
+    let params_inner = {
+        let mut params = params_outer.clone();
+        params.downsize(k);
+        params
+    };
+    let circuit = TestCircuit1::rand(&mut rng);
+    let pk = gen_pk(&params_inner, &circuit, None);
+    let snark = gen_snark_shplonk(&params_inner, &pk, circuit, &mut rng, None::<&str>);
+
+    let agg_circuit = AggregationCircuit::new(&params_outer, [snark], &mut rng);
+    let mut instances = agg_circuit.instances();
+    MockProver::run(k_agg, &agg_circuit, instances.clone()).unwrap().assert_satisfied();
+
+    // Each step is recorded with contiguous spans
+    let layout = agg_circuit.witness_layout();
+    let identifiers =
+        layout.spans.iter().map(|(identifier, _)| identifier.as_str()).collect::<Vec<_>>();
+    assert_eq!(
+        identifiers,
+        [
+            "flags",
+            "snark 0: load instances",
+            "snark 0: read proof",
+            "snark 0: succinct verify",
+            "select accumulators",
+            "accumulation: read proof",
+            "accumulation: verify",
+        ]
+    );
+    for ((_, lhs), (_, rhs)) in layout.spans.iter().zip(layout.spans.iter().skip(1)) {
+        assert_eq!(lhs.end, rhs.start);
+    }
+
+    // Accumulator exposed as instances is blamed on accumulation
+    instances[0][0] += Fr::one();
+    let failures = MockProver::run(k_agg, &agg_circuit, instances).unwrap().verify().unwrap_err();
+    let blames = agg_circuit.witness_layout().blame(&failures);
+    let blamed = |step: &str| blames.iter().any(|blame| blame.steps.iter().any(|s| s == step));
+    assert!(blamed("accumulation: verify"));
+}
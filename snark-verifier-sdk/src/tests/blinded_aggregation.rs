@@ -0,0 +1,55 @@
+use super::{TestCircuit1, TestCircuit2};
+use crate::halo2::aggregation::{preprocessed_digest, AggregationCircuit};
+use crate::CircuitExt;
+use crate::{gen_pk, halo2::gen_snark_shplonk};
+use crate::{halo2_base::utils::fs::gen_srs, halo2_proofs, LIMBS};
+use ark_std::test_rng;
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::halo2curves::bn256::Fr;
+use halo2_proofs::plonk::{keygen_vk, Circuit};
+use halo2_proofs::poly::commitment::Params;
+
+#[test]
+fn test_shplonk_blinded_aggregation() {
+    std::env::set_var("VERIFY_CONFIG", "./configs/example_evm_accumulator.config");
+    let k = 8;
+    let k_agg = 21;
+
+    let mut rng = test_rng();
+    let params_outer = gen_srs(k_agg);
+    let params_inner = {
+        let mut params = params_outer.clone();
+        params.downsize(k);
+        params
+    };
+    // Circuits of the same shape but different fixed columns
+    let snark_1 = {
+        let circuit = TestCircuit1::rand(&mut rng);
+        let pk = gen_pk(&params_inner, &circuit, None);
+        gen_snark_shplonk(&params_inner, &pk, circuit, &mut rng, None::<&str>)
+    };
+    let snark_2 = {
+        let circuit = TestCircuit2::rand(&mut rng);
+        let pk = gen_pk(&params_inner, &circuit, None);
+        gen_snark_shplonk(&params_inner, &pk, circuit, &mut rng, None::<&str>)
+    };
+    assert_ne!(preprocessed_digest(&snark_1.protocol), preprocessed_digest(&snark_2.protocol));
+
+    let agg_circuit_1 = AggregationCircuit::new_blinded(&params_outer, [snark_1.clone()], &mut rng);
+    let agg_circuit_2 = AggregationCircuit::new_blinded(&params_outer, [snark_2], &mut rng);
+    let instances = agg_circuit_1.instances();
+    assert_eq!(agg_circuit_1.num_instance(), vec![4 * LIMBS + 1]);
+    assert_eq!(instances[0][4 * LIMBS], preprocessed_digest(&snark_1.protocol));
+    MockProver::run(k_agg, &agg_circuit_1, instances.clone()).unwrap().assert_satisfied();
+
+    // Verifying keys of aggregated circuits are not part of the one of
+    // aggregation circuit
+    let [vk_1, vk_2] = [&agg_circuit_1, &agg_circuit_2]
+        .map(|circuit| keygen_vk(&params_outer, &circuit.without_witnesses()).unwrap());
+    assert_eq!(vk_1.transcript_repr(), vk_2.transcript_repr());
+
+    // Digest is bound by instances
+    let mut instances = instances;
+    instances[0][4 * LIMBS] += Fr::one();
+    assert!(MockProver::run(k_agg, &agg_circuit_1, instances).unwrap().verify().is_err());
+}
@@ -0,0 +1,65 @@
+use super::TestCircuit1;
+use crate::evm::{evm_verify, gen_evm_proof_shplonk, gen_evm_verifier};
+use crate::halo2::{
+    aggregation::AggregationCircuit,
+    compression::{CompressionCircuit, COMPRESSION_CONFIG},
+};
+use crate::halo2_base::utils::fs::gen_srs;
+use crate::halo2_proofs;
+use crate::{gen_pk, halo2::gen_snark_shplonk};
+use crate::{CircuitExt, LIMBS};
+use ark_std::test_rng;
+use halo2_proofs::halo2curves::bn256::Bn256;
+use halo2_proofs::poly::commitment::Params;
+use snark_verifier::pcs::kzg::{Bdfg21, Kzg};
+
+#[test]
+fn test_aggregation_then_compression_evm_verification() {
+    std::env::set_var("VERIFY_CONFIG", "./configs/example_evm_accumulator.config");
+    std::env::set_var(COMPRESSION_CONFIG, "./configs/compression_circuit.config");
+    let k = 8;
+    let k_agg = 21;
+
+    let mut rng = test_rng();
+    let params_outer = gen_srs(k_agg);
+    let params_inner = {
+        let mut params = params_outer.clone();
+        params.downsize(k);
+        params
+    };
+
+    // layer 1 snarks
+    let circuit = TestCircuit1::rand(&mut rng);
+    let pk_inner = gen_pk(&params_inner, &circuit, None);
+    let snarks = (0..2)
+        .map(|_| {
+            gen_snark_shplonk(&params_inner, &pk_inner, circuit.clone(), &mut rng, None::<&str>)
+        })
+        .collect::<Vec<_>>();
+
+    // layer 2, aggregation with poseidon transcript
+    let agg_circuit = AggregationCircuit::new(&params_outer, snarks, &mut rng);
+    let pk_agg = gen_pk(&params_outer, &agg_circuit, None);
+    let agg_snark = gen_snark_shplonk(&params_outer, &pk_agg, agg_circuit, &mut rng, None::<&str>);
+
+    // layer 3, compression with evm transcript
+    let compression_circuit = CompressionCircuit::new(&params_outer, agg_snark, true, &mut rng);
+    // inner snark has only old accumulator as instances
+    assert_eq!(compression_circuit.num_instance(), vec![4 * LIMBS]);
+    let pk_compression = gen_pk(&params_outer, &compression_circuit, None);
+    let deployment_code = gen_evm_verifier::<CompressionCircuit, Kzg<Bn256, Bdfg21>>(
+        &params_outer,
+        pk_compression.get_vk(),
+        compression_circuit.num_instance(),
+        None,
+    );
+    let instances = compression_circuit.instances();
+    let proof = gen_evm_proof_shplonk(
+        &params_outer,
+        &pk_compression,
+        compression_circuit,
+        instances.clone(),
+        &mut rng,
+    );
+    evm_verify(deployment_code, instances, proof)
+}
@@ -0,0 +1,46 @@
+use super::TestCircuit1;
+use crate::halo2::aggregation::{AggregationCircuit, AggregationConfigParams};
+use crate::CircuitExt;
+use crate::{gen_pk, halo2::gen_snark_shplonk};
+use crate::{halo2_base::utils::fs::gen_srs, halo2_proofs};
+use ark_std::test_rng;
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::poly::commitment::Params;
+use std::fs::File;
+
+#[test]
+fn test_aggregation_config_params_tuned() {
+    let config = "./configs/example_evm_accumulator.config";
+    std::env::set_var("VERIFY_CONFIG", config);
+    let k = 8;
+    let k_agg = 21;
+
+    let mut rng = test_rng();
+    let params_outer = gen_srs(k_agg);
+    let params_inner = {
+        let mut params = params_outer.clone();
+        params.downsize(k);
+        params
+    };
+    let circuit = TestCircuit1::rand(&mut rng);
+    let pk = gen_pk(&params_inner, &circuit, None);
+    let snark = gen_snark_shplonk(&params_inner, &pk, circuit, &mut rng, None::<&str>);
+
+    // Record layout of aggregating one snark with the example config
+    let agg_circuit = AggregationCircuit::new(&params_outer, [snark.clone()], &mut rng);
+    MockProver::run(k_agg, &agg_circuit, agg_circuit.instances()).unwrap().assert_satisfied();
+    let layout = agg_circuit.witness_layout();
+
+    // Tune for aggregating two snarks
+    let params: AggregationConfigParams =
+        serde_json::from_reader(File::open(config).unwrap()).unwrap();
+    let tuned = params.tuned(k_agg, 2, &layout);
+    assert_eq!((tuned.degree, tuned.lookup_bits), (k_agg, k_agg as usize - 1));
+    assert_eq!(tuned.tuned(k_agg, 2, &layout).num_advice, tuned.num_advice);
+
+    let path = std::env::temp_dir().join("tuned_aggregation.config");
+    tuned.write(&path).unwrap();
+    std::env::set_var("VERIFY_CONFIG", &path);
+    let agg_circuit = AggregationCircuit::new(&params_outer, [snark.clone(), snark], &mut rng);
+    MockProver::run(k_agg, &agg_circuit, agg_circuit.instances()).unwrap().assert_satisfied();
+}
@@ -0,0 +1,62 @@
+//! Proofs, calldata and verifier code must be byte-identical across platforms
+//! (e.g. aarch64 vs x86_64, with or without `asm` field backends), given the
+//! same srs and rng seed.
+use super::TestCircuit1;
+use crate::evm::{encode_calldata, gen_evm_proof_shplonk, gen_evm_verifier_sol_code};
+use crate::gen_pk;
+use crate::halo2_proofs;
+use crate::CircuitExt;
+use ark_std::test_rng;
+use halo2_proofs::{
+    halo2curves::bn256::{Bn256, Fr},
+    poly::kzg::commitment::ParamsKZG,
+};
+use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+use snark_verifier::pcs::kzg::{Bdfg21, Kzg};
+
+struct Artifacts {
+    proof: Vec<u8>,
+    calldata: Vec<u8>,
+    sol_code: String,
+}
+
+fn gen_artifacts() -> Artifacts {
+    // Fixed seed instead of `gen_srs`, which might be read from a file
+    // generated with os randomness.
+    let params = ParamsKZG::<Bn256>::setup(8, ChaCha20Rng::from_seed(Default::default()));
+    let mut rng = test_rng();
+
+    let circuit = TestCircuit1::rand(&mut rng);
+    let pk = gen_pk(&params, &circuit, None);
+    let sol_code = gen_evm_verifier_sol_code::<TestCircuit1, Kzg<Bn256, Bdfg21>>(
+        &params,
+        pk.get_vk(),
+        circuit.num_instance(),
+    );
+
+    let instances: Vec<Vec<Fr>> = circuit.instances();
+    let proof = gen_evm_proof_shplonk(&params, &pk, circuit, instances.clone(), &mut rng);
+    let calldata = encode_calldata(&instances, &proof);
+
+    Artifacts { proof, calldata, sol_code }
+}
+
+#[test]
+fn test_deterministic_artifacts() {
+    let lhs = gen_artifacts();
+    let rhs = gen_artifacts();
+    assert_eq!(lhs.proof, rhs.proof);
+    assert_eq!(lhs.calldata, rhs.calldata);
+    assert_eq!(lhs.sol_code, rhs.sol_code);
+
+    // Compare against golden files generated on another platform.
+    #[cfg(feature = "golden")]
+    {
+        use snark_verifier::util::golden::{assert_golden, canonical_calldata, canonical_yul};
+        assert_golden(
+            "./data/golden/deterministic/calldata.txt",
+            &canonical_calldata(&lhs.calldata),
+        );
+        assert_golden("./data/golden/deterministic/verifier.yul", &canonical_yul(&lhs.sol_code));
+    }
+}
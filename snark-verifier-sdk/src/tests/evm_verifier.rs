@@ -1,12 +1,26 @@
-use super::TestCircuit1;
-use crate::evm::{evm_verify, gen_evm_proof_shplonk, gen_evm_verifier};
-use crate::gen_pk;
-use crate::CircuitExt;
+use super::{TestCircuit1, TestCircuit2};
+use crate::evm::{
+    encode_calldata, encode_calldata_with_aux, encode_calldata_with_vk, encode_verify_raw_calldata,
+    encode_vk_registry_constructor_args, evm_verify, evm_verify_calldata, gen_evm_proof_shplonk,
+    gen_evm_proof_with_aux, gen_evm_verifier, gen_evm_verifier_sol_code,
+    gen_evm_verifier_sol_code_from_protocol, gen_evm_verifier_sol_code_with_aux,
+    gen_evm_verifier_sol_code_with_entry_point, gen_evm_verifier_sol_code_with_vk_registry,
+    gen_evm_verifier_with_layout,
+    layout::{CalldataLayout, ProofItem},
+    negative::{assert_evm_rejects, tampered_calldatas, Tamper},
+    verify_evm_calldata, vk_digest, EntryPoint,
+};
+use crate::halo2_base::utils::fs::gen_srs;
+use crate::halo2_proofs;
+use crate::{compile_from_vk_bytes, gen_pk, read_protocol, write_protocol};
+use crate::{CircuitExt, LIMBS};
 use ark_std::test_rng;
-use halo2_base::halo2_proofs;
-use halo2_proofs::halo2curves::bn256::Bn256;
-use snark_verifier::loader::halo2::halo2_ecc::halo2_base::utils::fs::gen_srs;
+use halo2_proofs::{halo2curves::bn256::Bn256, poly::commitment::ParamsProver, SerdeFormat};
+use snark_verifier::loader::evm::compile_solidity;
 use snark_verifier::pcs::kzg::{Bdfg21, Kzg};
+use snark_verifier::system::halo2::{compile, Config};
+use snark_verifier::{Error, Protocol};
+use std::{fs::File, io, path::Path};
 
 #[test]
 fn test_evm_verification() {
@@ -28,3 +42,293 @@ fn test_evm_verification() {
     let proof = gen_evm_proof_shplonk(&params, &pk, circuit.clone(), instances.clone(), &mut rng);
     evm_verify(deployment_code.clone(), circuit.instances(), proof)
 }
+
+#[test]
+fn test_evm_rejects_tampered() {
+    let mut rng = test_rng();
+    let params = gen_srs(8);
+
+    let circuit = TestCircuit1::rand(&mut rng);
+    let pk = gen_pk(&params, &circuit, None);
+    let deployment_code = gen_evm_verifier::<TestCircuit1, Kzg<Bn256, Bdfg21>>(
+        &params,
+        pk.get_vk(),
+        circuit.num_instance(),
+        None,
+    );
+    let protocol =
+        compile(&params, pk.get_vk(), Config::kzg().with_num_instance(circuit.num_instance()));
+
+    let instances = circuit.instances();
+    let proof = gen_evm_proof_shplonk(&params, &pk, circuit, instances.clone(), &mut rng);
+    let cases = tampered_calldatas(&protocol, &instances, &proof);
+    assert_eq!(cases.len(), Tamper::all().len());
+    assert_evm_rejects(&deployment_code, &cases);
+}
+
+#[test]
+#[cfg(feature = "testing")]
+fn test_evm_rejects_malicious() {
+    use snark_verifier::system::halo2::malicious::MaliciousProver;
+
+    let mut rng = test_rng();
+    let params = gen_srs(8);
+
+    let circuit = TestCircuit1::rand(&mut rng);
+    let pk = gen_pk(&params, &circuit, None);
+    let deployment_code = gen_evm_verifier::<TestCircuit1, Kzg<Bn256, Bdfg21>>(
+        &params,
+        pk.get_vk(),
+        circuit.num_instance(),
+        None,
+    );
+    let config = Config::kzg().with_num_instance(circuit.num_instance());
+
+    let instances = circuit.instances();
+    let proof = gen_evm_proof_shplonk(&params, &pk, circuit, instances.clone(), &mut rng);
+    // `EvmTranscript` encodes both coordinates of commitments.
+    let crafted = MaliciousProver::new(pk.get_vk(), &config).with_point_size(64).craft_all(&proof);
+    assert!(!crafted.is_empty());
+    for (malicious, proof) in crafted {
+        let calldata = encode_calldata(&instances, &proof);
+        assert!(
+            !evm_verify_calldata(deployment_code.clone(), calldata),
+            "Verifier accepts {malicious}"
+        );
+    }
+}
+
+#[test]
+fn test_evm_calldata_layout() {
+    let mut rng = test_rng();
+    let params = gen_srs(8);
+
+    let circuit = TestCircuit1::rand(&mut rng);
+    let pk = gen_pk(&params, &circuit, None);
+    let protocol =
+        compile(&params, pk.get_vk(), Config::kzg().with_num_instance(circuit.num_instance()));
+    let path = Path::new("data/layout/test_circuit_1.sol");
+    let deployment_code =
+        gen_evm_verifier_with_layout::<Kzg<Bn256, Bdfg21>>(&params, &protocol, path).unwrap();
+    let layout: CalldataLayout =
+        serde_json::from_reader(File::open(path.with_extension("json")).unwrap()).unwrap();
+    assert_eq!(layout, CalldataLayout::new::<Kzg<Bn256, Bdfg21>>(&protocol));
+
+    let instances = circuit.instances();
+    let proof = gen_evm_proof_shplonk(&params, &pk, circuit, instances.clone(), &mut rng);
+    let calldata = encode_calldata(&instances, &proof);
+    assert_eq!(layout.proof_offset, 0x20);
+    assert_eq!(layout.proof_length, proof.len());
+    assert_eq!(layout.calldata_length, calldata.len());
+    assert_eq!(
+        calldata[layout.instances[0].offset..layout.proof_offset],
+        encode_calldata(&instances, &[])
+    );
+
+    // Items of proof are located by offsets
+    let evaluation =
+        layout.proof.iter().find(|slot| matches!(slot.item, ProofItem::Evaluation { .. })).unwrap();
+    let mut tampered = calldata.clone();
+    tampered[evaluation.offset + evaluation.length - 1] ^= 1;
+    assert!(evm_verify_calldata(deployment_code.clone(), calldata));
+    assert!(!evm_verify_calldata(deployment_code, tampered));
+
+    // Limbs of accumulator are located by accumulator indices
+    let protocol = Protocol {
+        num_instance: vec![1, 4 * LIMBS],
+        accumulator_indices: vec![(0..4 * LIMBS).map(|row| (1, row)).collect()],
+        ..protocol
+    };
+    let layout = CalldataLayout::new::<Kzg<Bn256, Bdfg21>>(&protocol);
+    assert_eq!(layout.accumulators[0].lhs_x, [0x20, 0x40, 0x60]);
+    assert_eq!(layout.accumulators[0].rhs_y, [0x140, 0x160, 0x180]);
+    assert_eq!(layout.proof_offset, (1 + 4 * LIMBS) * 0x20);
+}
+
+#[test]
+fn test_verify_evm_calldata() {
+    let mut rng = test_rng();
+    let params = gen_srs(8);
+
+    let circuit = TestCircuit1::rand(&mut rng);
+    let pk = gen_pk(&params, &circuit, None);
+    let protocol =
+        compile(&params, pk.get_vk(), Config::kzg().with_num_instance(circuit.num_instance()));
+    let svk = params.get_g()[0].into();
+    let dk = (params.g2(), params.s_g2()).into();
+    let verify =
+        |calldata: &[u8]| verify_evm_calldata::<Kzg<Bn256, Bdfg21>>(&svk, &dk, &protocol, calldata);
+
+    let instances = circuit.instances();
+    let proof = gen_evm_proof_shplonk(&params, &pk, circuit, instances.clone(), &mut rng);
+    let calldata = encode_calldata(&instances, &proof);
+    verify(&calldata).unwrap();
+
+    // Not even enough for instances
+    assert!(matches!(verify(&calldata[..0x10]), Err(Error::InvalidInstances)));
+    // Non-canonical instance
+    let mut non_canonical = calldata.clone();
+    non_canonical[..0x20].fill(0xff);
+    assert!(matches!(verify(&non_canonical), Err(Error::InvalidInstances)));
+    // Truncated proof
+    assert!(matches!(
+        verify(&calldata[..calldata.len() - 0x20]),
+        Err(Error::Transcript(io::ErrorKind::UnexpectedEof, _))
+    ));
+    // First witness commitment replaced by (1, 1), which is not on curve
+    let layout = CalldataLayout::new::<Kzg<Bn256, Bdfg21>>(&protocol);
+    let mut off_curve = calldata.clone();
+    off_curve[layout.proof_offset..layout.proof_offset + 0x40].fill(0);
+    off_curve[layout.proof_offset + 0x1f] = 1;
+    off_curve[layout.proof_offset + 0x3f] = 1;
+    assert!(matches!(verify(&off_curve), Err(Error::Transcript(..))));
+    // Well-formed but invalid proof
+    let evaluation =
+        layout.proof.iter().find(|slot| matches!(slot.item, ProofItem::Evaluation { .. })).unwrap();
+    let mut tampered = calldata;
+    tampered[evaluation.offset + evaluation.length - 1] ^= 1;
+    assert!(matches!(verify(&tampered), Err(Error::AssertionFailure(_))));
+}
+
+#[test]
+fn test_evm_verifier_from_serialized_vk() {
+    let mut rng = test_rng();
+    let params = gen_srs(8);
+
+    let circuit = TestCircuit1::rand(&mut rng);
+    let pk = gen_pk(&params, &circuit, None);
+    let sol_code = gen_evm_verifier_sol_code::<TestCircuit1, Kzg<Bn256, Bdfg21>>(
+        &params,
+        pk.get_vk(),
+        circuit.num_instance(),
+    );
+
+    let mut vk_bytes = Vec::new();
+    pk.get_vk().write(&mut vk_bytes, SerdeFormat::RawBytes).unwrap();
+    let protocol = compile_from_vk_bytes::<TestCircuit1>(
+        &params,
+        &vk_bytes,
+        SerdeFormat::RawBytes,
+        Config::kzg()
+            .with_num_instance(circuit.num_instance())
+            .with_accumulator_indices(TestCircuit1::accumulator_indices()),
+    )
+    .unwrap();
+
+    let path = Path::new("data/protocol/test_circuit_1.protocol");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    write_protocol(&protocol, path).unwrap();
+    let protocol = read_protocol(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(
+        gen_evm_verifier_sol_code_from_protocol::<Kzg<Bn256, Bdfg21>>(&params, &protocol),
+        sol_code
+    );
+}
+
+#[test]
+fn test_evm_verifier_with_vk_registry() {
+    let mut rng = test_rng();
+    let params = gen_srs(8);
+
+    // Circuits of the same shape but different fixed columns
+    let circuit_1 = TestCircuit1::rand(&mut rng);
+    let circuit_2 = TestCircuit2::rand(&mut rng);
+    let pk_1 = gen_pk(&params, &circuit_1, None);
+    let pk_2 = gen_pk(&params, &circuit_2, None);
+    let config = || Config::kzg().with_num_instance(vec![1]);
+    let protocol_1 = compile(&params, pk_1.get_vk(), config());
+    let protocol_2 = compile(&params, pk_2.get_vk(), config());
+
+    let sol_code = gen_evm_verifier_sol_code_with_vk_registry::<Kzg<Bn256, Bdfg21>>(
+        &params,
+        &[protocol_1.clone(), protocol_2.clone()],
+    );
+    assert!(sol_code.contains("UnregisteredVk"));
+    let byte_code = compile_solidity(&sol_code);
+    let deployment_code = |vk_digests: &[[u8; 32]]| {
+        [byte_code.clone(), encode_vk_registry_constructor_args(vk_digests)].concat()
+    };
+    let [vk_digest_1, vk_digest_2] = [&protocol_1, &protocol_2].map(vk_digest);
+    assert_ne!(vk_digest_1, vk_digest_2);
+
+    let instances_1 = circuit_1.instances();
+    let proof_1 = gen_evm_proof_shplonk(&params, &pk_1, circuit_1, instances_1.clone(), &mut rng);
+    let instances_2 = circuit_2.instances();
+    let proof_2 = gen_evm_proof_shplonk(&params, &pk_2, circuit_2, instances_2.clone(), &mut rng);
+    let calldata_1 = encode_calldata_with_vk(&protocol_1, &instances_1, &proof_1);
+    let calldata_2 = encode_calldata_with_vk(&protocol_2, &instances_2, &proof_2);
+
+    let both = deployment_code(&[vk_digest_1, vk_digest_2]);
+    assert!(evm_verify_calldata(both.clone(), calldata_1.clone()));
+    assert!(evm_verify_calldata(both.clone(), calldata_2.clone()));
+    // Proof checked against verifying key of another circuit
+    let mismatched = encode_calldata_with_vk(&protocol_2, &instances_1, &proof_1);
+    assert!(!evm_verify_calldata(both, mismatched));
+    // Verifying key not registered
+    let only_1 = deployment_code(&[vk_digest_1]);
+    assert!(evm_verify_calldata(only_1.clone(), calldata_1));
+    assert!(!evm_verify_calldata(only_1, calldata_2));
+}
+
+#[test]
+fn test_evm_verifier_verify_raw() {
+    let mut rng = test_rng();
+    let params = gen_srs(8);
+
+    let circuit = TestCircuit1::rand(&mut rng);
+    let pk = gen_pk(&params, &circuit, None);
+    let protocol =
+        compile(&params, pk.get_vk(), Config::kzg().with_num_instance(circuit.num_instance()));
+    let sol_code = gen_evm_verifier_sol_code_with_entry_point::<Kzg<Bn256, Bdfg21>>(
+        &params,
+        &protocol,
+        EntryPoint::VerifyRaw,
+    );
+    let deployment_code = compile_solidity(&sol_code);
+
+    let instances = circuit.instances();
+    let mut proof = gen_evm_proof_shplonk(&params, &pk, circuit, instances.clone(), &mut rng);
+    let calldata = encode_verify_raw_calldata(&instances, &proof);
+    assert!(evm_verify_calldata(deployment_code.clone(), calldata));
+    // Only `verify_raw(bytes)` is exposed
+    assert!(!evm_verify_calldata(deployment_code.clone(), encode_calldata(&instances, &proof)));
+
+    proof[0] ^= 1;
+    let calldata = encode_verify_raw_calldata(&instances, &proof);
+    assert!(!evm_verify_calldata(deployment_code, calldata));
+}
+
+#[test]
+fn test_evm_verifier_with_aux() {
+    use halo2_proofs::{
+        halo2curves::bn256::Fr,
+        poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK},
+    };
+
+    let mut rng = test_rng();
+    let params = gen_srs(8);
+
+    let circuit = TestCircuit1::rand(&mut rng);
+    let pk = gen_pk(&params, &circuit, None);
+    let protocol =
+        compile(&params, pk.get_vk(), Config::kzg().with_num_instance(circuit.num_instance()));
+    let sol_code = gen_evm_verifier_sol_code_with_aux::<Kzg<Bn256, Bdfg21>>(&params, &protocol, 1);
+    let deployment_code = compile_solidity(&sol_code);
+
+    let instances = circuit.instances();
+    let randomness = Fr::from(0xbeac0);
+    let proof = gen_evm_proof_with_aux::<_, ProverSHPLONK<_>, VerifierSHPLONK<_>>(
+        &params,
+        &pk,
+        circuit,
+        instances.clone(),
+        &[randomness],
+        &mut rng,
+    );
+    let calldata = |aux: Fr| encode_calldata_with_aux(&[aux], &instances, &proof);
+    assert!(evm_verify_calldata(deployment_code.clone(), calldata(randomness)));
+    // Proof is bound to the randomness it's generated with
+    assert!(!evm_verify_calldata(deployment_code.clone(), calldata(randomness + Fr::from(1))));
+    assert!(!evm_verify_calldata(deployment_code, encode_calldata(&instances, &proof)));
+}
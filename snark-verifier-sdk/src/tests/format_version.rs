@@ -0,0 +1,31 @@
+use crate::{deserialize_versioned, serialize_versioned};
+use snark_verifier::{
+    format::{FormatVersion, FORMAT_MAGIC, FORMAT_VERSION},
+    loader::evm::staging_sol_code,
+};
+
+#[test]
+fn test_format_version() {
+    let value = vec![1u64, 2, 3];
+
+    let mut bytes = Vec::new();
+    serialize_versioned(&mut bytes, &value).unwrap();
+    assert_eq!(bytes[..4], FORMAT_MAGIC);
+    assert_eq!(deserialize_versioned::<Vec<u64>>(bytes.as_slice()).unwrap(), value);
+
+    // Written before versioning
+    let legacy = bincode::serialize(&value).unwrap();
+    assert_eq!(deserialize_versioned::<Vec<u64>>(legacy.as_slice()).unwrap(), value);
+
+    // Newer minor or different major
+    for version in [
+        FormatVersion::new(FORMAT_VERSION.major, FORMAT_VERSION.minor + 1),
+        FormatVersion::new(FORMAT_VERSION.major + 1, 0),
+    ] {
+        let bytes = bincode::serialize(&(FORMAT_MAGIC, version, &value)).unwrap();
+        assert!(deserialize_versioned::<Vec<u64>>(bytes.as_slice()).is_err());
+    }
+
+    assert_eq!(FormatVersion::from_sol_code(&staging_sol_code(1)), Some(FORMAT_VERSION));
+    assert_eq!(FormatVersion::from_sol_code("contract Verifier {}"), None);
+}
@@ -0,0 +1,100 @@
+use super::TestCircuit1;
+use crate::halo2::aggregation::{
+    decode_groth16_prepared_instances, AggregationCircuit, Groth16Snark,
+};
+use crate::halo2_base::utils::fs::gen_srs;
+use crate::halo2_proofs;
+use crate::CircuitExt;
+use crate::{gen_pk, halo2::gen_snark_shplonk};
+use ark_std::test_rng;
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::halo2curves::bn256::{Bn256, Fr, G1, G2};
+use halo2_proofs::poly::{commitment::Params, kzg::commitment::ParamsKZG};
+use itertools::Itertools;
+use rand::Rng;
+use snark_verifier::{
+    util::arithmetic::{Curve, Field, Group},
+    verifier::groth16::{Groth16, Groth16Proof, Groth16VerifyingKey},
+};
+
+/// Returns a valid Groth16 proof of `num_instance` random instances, which is
+/// generated from a setup with known trapdoor.
+fn rand_groth16_snark(num_instance: usize, mut rng: impl Rng) -> Groth16Snark {
+    let [alpha, beta, gamma, delta, a, b] = [(); 6].map(|_| Fr::random(&mut rng));
+    let ic = (0..=num_instance).map(|_| Fr::random(&mut rng)).collect_vec();
+    let instances = (0..num_instance).map(|_| Fr::random(&mut rng)).collect_vec();
+    let ic_eval = ic[0] + ic[1..].iter().zip(instances.iter()).map(|(c, x)| *c * x).sum::<Fr>();
+    let c = (a * b - alpha * beta - ic_eval * gamma) * delta.invert().unwrap();
+
+    let g1 = |scalar: Fr| (G1::generator() * scalar).to_affine();
+    let g2 = |scalar: Fr| (G2::generator() * scalar).to_affine();
+    Groth16Snark {
+        vk: Groth16VerifyingKey::new(
+            g1(alpha),
+            g2(beta),
+            g2(gamma),
+            g2(delta),
+            ic.into_iter().map(g1).collect(),
+        ),
+        instances,
+        proof: Groth16Proof::new(g1(a), g2(b), g1(c)),
+    }
+}
+
+#[test]
+fn test_shplonk_aggregation_with_groth16() {
+    std::env::set_var("VERIFY_CONFIG", "./configs/example_evm_accumulator.config");
+    let k = 8;
+    let k_agg = 21;
+
+    let mut rng = test_rng();
+    let params_outer = gen_srs(k_agg);
+    let params_inner = {
+        let mut params = params_outer.clone();
+        params.downsize(k);
+        params
+    };
+
+    let circuit = TestCircuit1::rand(&mut rng);
+    let pk = gen_pk(&params_inner, &circuit, None);
+    let snark = gen_snark_shplonk(&params_inner, &pk, circuit, &mut rng, None::<&str>);
+    let groth16_snarks = [2, 0].map(|num_instance| rand_groth16_snark(num_instance, &mut rng));
+
+    let agg_circuit = AggregationCircuit::new_with_groth16(
+        &params_outer,
+        [snark],
+        groth16_snarks.clone(),
+        &mut rng,
+    );
+    let instances = agg_circuit.instances();
+    assert_eq!(agg_circuit.num_instance(), vec![instances[0].len()]);
+    MockProver::run(k_agg, &agg_circuit, instances.clone()).unwrap().assert_satisfied();
+
+    // Deferred pairing check by verifier of aggregation circuit
+    let ics = decode_groth16_prepared_instances(&instances[0]);
+    assert_eq!(ics.len(), groth16_snarks.len());
+    for (ic, groth16) in ics.iter().zip(groth16_snarks.iter()) {
+        assert!(Groth16::verify_prepared(&groth16.vk, ic, &groth16.proof));
+    }
+    assert!(!Groth16::verify_prepared(&groth16_snarks[0].vk, &ics[1], &groth16_snarks[0].proof));
+}
+
+#[test]
+#[should_panic(expected = "Groth16 proof 0 fails to verify")]
+fn test_shplonk_aggregation_with_invalid_groth16() {
+    let mut rng = test_rng();
+    let params_outer = ParamsKZG::<Bn256>::setup(10, &mut rng);
+    let params_inner = {
+        let mut params = params_outer.clone();
+        params.downsize(8);
+        params
+    };
+
+    let circuit = TestCircuit1::rand(&mut rng);
+    let pk = gen_pk(&params_inner, &circuit, None);
+    let snark = gen_snark_shplonk(&params_inner, &pk, circuit, &mut rng, None::<&str>);
+    let mut groth16 = rand_groth16_snark(1, &mut rng);
+    groth16.instances[0] += Fr::one();
+
+    AggregationCircuit::new_with_groth16(&params_outer, [snark], [groth16], &mut rng);
+}
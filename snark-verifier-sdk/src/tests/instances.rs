@@ -0,0 +1,61 @@
+use super::TestCircuit1;
+use crate::halo2::{aggregation::PublicAggregationCircuit, gen_dummy_snark, read_snark};
+use crate::halo2_proofs;
+use crate::{serialize_versioned, InstanceColumn, InstanceError, Snark, SnarkWitness};
+use ark_std::test_rng;
+use halo2_proofs::circuit::Value;
+use halo2_proofs::halo2curves::bn256::{Bn256, Fr};
+use halo2_proofs::poly::kzg::commitment::ParamsKZG;
+use snark_verifier::pcs::kzg::{Bdfg21, Kzg};
+use std::fs::File;
+
+#[test]
+fn test_snark_instance_validation() {
+    let params = ParamsKZG::<Bn256>::setup(8, test_rng());
+    let snark = gen_dummy_snark::<TestCircuit1, Kzg<Bn256, Bdfg21>>(&params, None, vec![1]);
+    assert_eq!(
+        snark.instance_columns().collect::<Vec<_>>(),
+        [InstanceColumn { index: 0, values: &[Fr::zero()] }]
+    );
+
+    let try_new = |instances: Vec<Vec<Fr>>| {
+        Snark::try_new(snark.protocol.clone(), instances, snark.proof.clone()).map(|_| ())
+    };
+    assert_eq!(try_new(vec![vec![Fr::one()]]), Ok(()));
+    assert_eq!(try_new(vec![]), Err(InstanceError::NumColumn { expected: 1, found: 0 }));
+    assert_eq!(
+        try_new(vec![vec![Fr::one(); 2]]),
+        Err(InstanceError::ColumnLength { column: 0, expected: 1, found: 2 })
+    );
+
+    // Invalid snark is rejected when read from file
+    let path = "data/invalid_instances.snark";
+    let invalid = Snark { instances: vec![vec![]], ..snark };
+    std::fs::create_dir_all("data").unwrap();
+    serialize_versioned(File::create(path).unwrap(), &invalid).unwrap();
+    assert!(read_snark(path).is_err());
+}
+
+#[test]
+fn test_snark_witness_shape_from_protocol() {
+    let params = ParamsKZG::<Bn256>::setup(8, test_rng());
+    let snark = gen_dummy_snark::<TestCircuit1, Kzg<Bn256, Bdfg21>>(&params, None, vec![1]);
+
+    // Circuits are laid out by protocol even if given instances diverge.
+    let mut witness = SnarkWitness::from(snark.clone());
+    witness.instances = vec![vec![Value::known(Fr::one()); 3], vec![]];
+    let without_witnesses = witness.without_witnesses();
+    assert_eq!(
+        without_witnesses.instances.iter().map(Vec::len).collect::<Vec<_>>(),
+        snark.protocol.num_instance
+    );
+}
+
+#[test]
+#[should_panic(expected = "Snark 0 should have accumulator at the first 12 instances")]
+fn test_public_aggregation_reject_snark_without_accumulator() {
+    let mut rng = test_rng();
+    let params = ParamsKZG::<Bn256>::setup(8, &mut rng);
+    let snark = gen_dummy_snark::<TestCircuit1, Kzg<Bn256, Bdfg21>>(&params, None, vec![1]);
+    PublicAggregationCircuit::new(&params, vec![snark], true, &mut rng);
+}
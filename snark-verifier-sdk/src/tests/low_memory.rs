@@ -0,0 +1,54 @@
+use super::{TestCircuit1, TestCircuit2};
+use crate::halo2::aggregation::{AggregationCircuit, AggregationConfigParams};
+use crate::halo2_base::utils::fs::gen_srs;
+use crate::halo2_proofs;
+use crate::CircuitExt;
+use crate::{gen_pk, halo2::gen_snark_shplonk};
+use ark_std::test_rng;
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::poly::commitment::Params;
+use std::fs::File;
+
+#[test]
+fn test_aggregation_config_params_low_memory() {
+    let params: AggregationConfigParams =
+        serde_json::from_reader(File::open("./configs/example_evm_accumulator.config").unwrap())
+            .unwrap();
+    assert!(!params.low_memory);
+
+    let params: AggregationConfigParams = serde_json::from_reader(
+        File::open("./configs/example_evm_accumulator_low_memory.config").unwrap(),
+    )
+    .unwrap();
+    assert!(params.low_memory);
+}
+
+#[test]
+fn test_shplonk_aggregation_low_memory() {
+    let k = 8;
+    let k_agg = 21;
+
+    let mut rng = test_rng();
+    let params_outer = gen_srs(k_agg);
+    let params_inner = {
+        let mut params = params_outer.clone();
+        params.downsize(k);
+        params
+    };
+
+    let circuit_1 = TestCircuit1::rand(&mut rng);
+    let pk_1 = gen_pk(&params_inner, &circuit_1, None);
+    let snark_1 = gen_snark_shplonk(&params_inner, &pk_1, circuit_1, &mut rng, None::<&str>);
+
+    let circuit_2 = TestCircuit2::rand(&mut rng);
+    let pk_2 = gen_pk(&params_inner, &circuit_2, None);
+    let snark_2 = gen_snark_shplonk(&params_inner, &pk_2, circuit_2, &mut rng, None::<&str>);
+
+    let agg_circuit = AggregationCircuit::new(&params_outer, [snark_1, snark_2], &mut rng);
+    let instances = agg_circuit.instances();
+
+    // Cells pending lookup are flushed after each snark, which should result
+    // in a circuit as sound as the one flushing them all at once.
+    std::env::set_var("VERIFY_CONFIG", "./configs/example_evm_accumulator_low_memory.config");
+    MockProver::run(k_agg, &agg_circuit, instances).unwrap().assert_satisfied();
+}
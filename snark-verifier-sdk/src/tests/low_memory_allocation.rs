@@ -0,0 +1,137 @@
+use super::{TestCircuit1, TestCircuit2};
+use crate::halo2::aggregation::AggregationCircuit;
+use crate::halo2_base::utils::fs::gen_srs;
+use crate::halo2_proofs;
+use crate::CircuitExt;
+use crate::{gen_pk, halo2::gen_snark_shplonk};
+use ark_std::test_rng;
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::halo2curves::bn256::Fr;
+use halo2_proofs::{
+    circuit::Layouter,
+    plonk::{Circuit, ConstraintSystem, Error},
+    poly::commitment::Params,
+};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+/// Allocator counting heap usage of each thread, so peak allocation of
+/// synthesis could be measured without being disturbed by other tests.
+struct CountingAllocator;
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+thread_local! {
+    static CURRENT: Cell<isize> = const { Cell::new(0) };
+    static PEAK: Cell<isize> = const { Cell::new(0) };
+}
+
+fn record(delta: isize) {
+    let _ = CURRENT.try_with(|current| {
+        current.set(current.get() + delta);
+        let _ = PEAK.try_with(|peak| peak.set(peak.get().max(current.get())));
+    });
+}
+
+fn reset_peak() -> isize {
+    let current = CURRENT.with(Cell::get);
+    PEAK.with(|peak| peak.set(current));
+    current
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            record(layout.size() as isize);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        record(-(layout.size() as isize));
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            record(new_size as isize - layout.size() as isize);
+        }
+        new_ptr
+    }
+}
+
+/// Wrapper of [`AggregationCircuit`] recording peak allocation of the thread
+/// during `synthesize`, on top of what was allocated before it.
+struct Metered<'a> {
+    circuit: &'a AggregationCircuit,
+    peak: &'a Cell<isize>,
+}
+
+impl Circuit<Fr> for Metered<'_> {
+    type Config = <AggregationCircuit as Circuit<Fr>>::Config;
+    type FloorPlanner = <AggregationCircuit as Circuit<Fr>>::FloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        unimplemented!()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        AggregationCircuit::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<Fr>) -> Result<(), Error> {
+        let base = reset_peak();
+        self.circuit.synthesize(config, layouter)?;
+        self.peak.set(PEAK.with(Cell::get) - base);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_shplonk_aggregation_low_memory_peak_allocation() {
+    let k = 8;
+    let k_agg = 21;
+
+    let mut rng = test_rng();
+    let params_outer = gen_srs(k_agg);
+    let params_inner = {
+        let mut params = params_outer.clone();
+        params.downsize(k);
+        params
+    };
+
+    let circuit_1 = TestCircuit1::rand(&mut rng);
+    let pk_1 = gen_pk(&params_inner, &circuit_1, None);
+    let snark_1 = gen_snark_shplonk(&params_inner, &pk_1, circuit_1, &mut rng, None::<&str>);
+
+    let circuit_2 = TestCircuit2::rand(&mut rng);
+    let pk_2 = gen_pk(&params_inner, &circuit_2, None);
+    let snark_2 = gen_snark_shplonk(&params_inner, &pk_2, circuit_2, &mut rng, None::<&str>);
+
+    let agg_circuit = AggregationCircuit::new(&params_outer, [snark_1, snark_2], &mut rng);
+    let instances = agg_circuit.instances();
+
+    let [peak, peak_low_memory] = [
+        "./configs/example_evm_accumulator.config",
+        "./configs/example_evm_accumulator_low_memory.config",
+    ]
+    .map(|path| {
+        std::env::set_var("VERIFY_CONFIG", path);
+        let peak = Cell::new(0);
+        let circuit = Metered { circuit: &agg_circuit, peak: &peak };
+        MockProver::run(k_agg, &circuit, instances.clone()).unwrap().assert_satisfied();
+        peak.get()
+    });
+
+    // Cells pending lookup are only kept for the snark being verified, instead
+    // of for all of them until `finalize`.
+    assert!(
+        peak_low_memory < peak,
+        "Peak allocation {peak_low_memory} in low memory mode is not lower than {peak}"
+    );
+}
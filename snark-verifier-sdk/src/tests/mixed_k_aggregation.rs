@@ -0,0 +1,75 @@
+use super::{TestCircuit1, TestCircuit2};
+use crate::evm::{evm_verify, gen_evm_proof_shplonk, gen_evm_verifier};
+use crate::halo2::aggregation::AggregationCircuit;
+use crate::halo2_base::utils::fs::gen_srs;
+use crate::halo2_proofs;
+use crate::CircuitExt;
+use crate::{gen_pk, halo2::gen_snark_shplonk};
+use ark_std::test_rng;
+use halo2_proofs::halo2curves::bn256::Bn256;
+use halo2_proofs::poly::{commitment::Params, kzg::commitment::ParamsKZG};
+use snark_verifier::pcs::kzg::{Bdfg21, Kzg};
+
+#[test]
+fn test_shplonk_aggregation_mixed_k() {
+    std::env::set_var("VERIFY_CONFIG", "./configs/example_evm_accumulator.config");
+    let k_agg = 21;
+
+    let mut rng = test_rng();
+    let params_outer = gen_srs(k_agg);
+    let [params_8, params_14] = [8, 14].map(|k| {
+        let mut params = params_outer.clone();
+        params.downsize(k);
+        params
+    });
+
+    let circuit_1 = TestCircuit1::rand(&mut rng);
+    let pk_1 = gen_pk(&params_8, &circuit_1, None);
+    let snark_1 = gen_snark_shplonk(&params_8, &pk_1, circuit_1, &mut rng, None::<&str>);
+
+    let circuit_2 = TestCircuit2::rand(&mut rng);
+    let pk_2 = gen_pk(&params_14, &circuit_2, None);
+    let snark_2 = gen_snark_shplonk(&params_14, &pk_2, circuit_2, &mut rng, None::<&str>);
+    assert_ne!(snark_1.protocol.domain.k, snark_2.protocol.domain.k);
+
+    let agg_circuit = AggregationCircuit::new(&params_outer, [snark_1, snark_2], &mut rng);
+    let pk_outer = gen_pk(&params_outer, &agg_circuit, None);
+    let instances = agg_circuit.instances();
+    let proof = gen_evm_proof_shplonk(
+        &params_outer,
+        &pk_outer,
+        agg_circuit.clone(),
+        instances.clone(),
+        &mut rng,
+    );
+
+    let deployment_code = gen_evm_verifier::<AggregationCircuit, Kzg<Bn256, Bdfg21>>(
+        &params_outer,
+        pk_outer.get_vk(),
+        agg_circuit.num_instance(),
+        None,
+    );
+    evm_verify(deployment_code, instances, proof)
+}
+
+#[test]
+#[should_panic(expected = "Snark 1 with k = 8 fails to verify")]
+fn test_shplonk_aggregation_different_srs() {
+    let mut rng = test_rng();
+    let params_outer = ParamsKZG::<Bn256>::setup(10, &mut rng);
+    let params_inner = {
+        let mut params = params_outer.clone();
+        params.downsize(8);
+        params
+    };
+    // Params of same size but from another SRS
+    let params_other = ParamsKZG::<Bn256>::setup(8, &mut rng);
+    assert_eq!(params_other.k(), params_inner.k());
+
+    let snarks = [&params_inner, &params_other].map(|params| {
+        let circuit = TestCircuit1::rand(&mut rng);
+        let pk = gen_pk(params, &circuit, None);
+        gen_snark_shplonk(params, &pk, circuit, &mut rng, None::<&str>)
+    });
+    AggregationCircuit::new(&params_outer, snarks, &mut rng);
+}
@@ -1,4 +1,4 @@
-use halo2_base::halo2_proofs;
+use crate::halo2_proofs;
 use halo2_proofs::halo2curves::bn256::Fr;
 use halo2_proofs::{
     plonk::{Advice, Column, ConstraintSystem, Fixed, Instance},
@@ -7,7 +7,27 @@ use halo2_proofs::{
 use test_circuit_1::TestCircuit1;
 use test_circuit_2::TestCircuit2;
 
+mod accumulation;
+mod blame;
+mod blinded_aggregation;
+mod compression;
+mod config_tuning;
+mod deterministic;
 mod evm_verifier;
+mod format_version;
+mod groth16_aggregation;
+mod instances;
+mod low_memory;
+#[cfg(not(feature = "jemallocator"))]
+mod low_memory_allocation;
+mod mixed_k_aggregation;
+mod msm_config;
+mod nova;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod pipeline;
+mod pk_cache;
+mod selective_aggregation;
 mod single_layer_aggregation;
 mod test_circuit_1;
 mod test_circuit_2;
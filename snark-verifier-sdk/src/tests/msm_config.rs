@@ -0,0 +1,17 @@
+use crate::halo2::aggregation::AggregationConfigParams;
+use snark_verifier::loader::halo2::MsmConfig;
+use std::fs::File;
+
+#[test]
+fn test_aggregation_config_params_msm() {
+    let mut json: serde_json::Value =
+        serde_json::from_reader(File::open("./configs/verify_circuit.config").unwrap()).unwrap();
+
+    // Config files without `msm` keep the ecc chip defaults.
+    let params: AggregationConfigParams = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(params.msm, MsmConfig::default());
+
+    json["msm"] = serde_json::json!({ "window_bits": 3, "fixed_base": false });
+    let params: AggregationConfigParams = serde_json::from_value(json).unwrap();
+    assert_eq!(params.msm, MsmConfig { window_bits: 3, fixed_base: false, ..Default::default() });
+}
@@ -0,0 +1,179 @@
+use super::StandardPlonkConfig;
+use crate::halo2::{
+    accumulation::{fold, succinct_verify_snark},
+    PoseidonTranscript, POSEIDON_SPEC,
+};
+use crate::halo2_proofs;
+use crate::{gen_pk, halo2::gen_snark_shplonk, CircuitExt, NativeLoader};
+use ark_std::test_rng;
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    halo2curves::bn256::{Bn256, Fr, G1Affine, G1},
+    plonk::{Circuit, ConstraintSystem, Error},
+    poly::{commitment::ParamsProver, kzg::commitment::ParamsKZG},
+};
+use itertools::Itertools;
+use snark_verifier::{
+    pcs::{
+        kzg::{Bdfg21, Kzg, KzgDecidingKey, KzgSuccinctVerifyingKey},
+        Decider,
+    },
+    system::nova::{NovaKzgOpening, R1csInstance, RelaxedR1csInstance},
+    util::{
+        arithmetic::{Curve, Field, Group},
+        transcript::{Transcript, TranscriptWrite},
+    },
+};
+use std::iter;
+
+/// Stand-in of the decider snark proving satisfiability of the folded relaxed
+/// R1CS instance, which only exposes the values it is bound to.
+#[derive(Clone)]
+struct MockDecider(Vec<Fr>);
+
+impl CircuitExt<Fr> for MockDecider {
+    fn num_instance(&self) -> Vec<usize> {
+        vec![self.0.len()]
+    }
+
+    fn instances(&self) -> Vec<Vec<Fr>> {
+        vec![self.0.clone()]
+    }
+}
+
+impl Circuit<Fr> for MockDecider {
+    type Config = StandardPlonkConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self(vec![Fr::zero(); self.0.len()])
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        meta.set_minimum_degree(4);
+        StandardPlonkConfig::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "",
+            |mut region| {
+                for (row, value) in self.0.iter().enumerate() {
+                    region.assign_advice(|| "", config.a, row, || Value::known(*value))?;
+                    region.assign_fixed(|| "", config.q_a, row, || Value::known(-Fr::one()))?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+/// Instances the decider snark should expose, which are the opening point,
+/// evaluations, and `u` and `x` of the folded instance.
+fn decider_instances(
+    folded: &RelaxedR1csInstance<G1Affine, NativeLoader>,
+    opening: &NovaKzgOpening<G1Affine, NativeLoader>,
+) -> Vec<Fr> {
+    iter::once(opening.point)
+        .chain(opening.evals)
+        .chain(iter::once(folded.u))
+        .chain(folded.x.iter().copied())
+        .collect()
+}
+
+#[test]
+fn test_nova_decider_aggregated_with_openings() {
+    let k = 8;
+    let mut rng = test_rng();
+    let params = ParamsKZG::<Bn256>::setup(k, &mut rng);
+    let svk = KzgSuccinctVerifyingKey::new(params.get_g()[0]);
+    let dk = KzgDecidingKey::<Bn256>::new(params.g2(), params.s_g2());
+
+    let eval =
+        |poly: &[Fr], x: Fr| poly.iter().rev().fold(Fr::zero(), |acc, coeff| acc * x + coeff);
+    let commit = |poly: &[Fr]| {
+        poly.iter().zip(params.get_g()).fold(G1::identity(), |acc, (coeff, g)| acc + *g * *coeff)
+    };
+    // Quotient of dividing `poly` by `X - x`.
+    let quotient = |poly: &[Fr], x: Fr| {
+        let mut quotient = vec![Fr::zero(); poly.len() - 1];
+        let mut acc = Fr::zero();
+        for (idx, coeff) in poly.iter().enumerate().skip(1).rev() {
+            acc = acc * x + coeff;
+            quotient[idx - 1] = acc;
+        }
+        quotient
+    };
+
+    let [w_1, e_1, w_2, t] =
+        [(); 4].map(|_| iter::repeat_with(|| Fr::random(&mut rng)).take(4).collect_vec());
+    let u_1 = Fr::random(&mut rng);
+    let (x_1, x_2) = (vec![Fr::from(1), Fr::from(2)], vec![Fr::from(3), Fr::from(4)]);
+
+    // Nova proof of the last folding step and openings, with evaluation of
+    // witness polynomial shifted by `shift`.
+    let nova_proof = |shift: Fr| {
+        let mut transcript =
+            PoseidonTranscript::<NativeLoader, Vec<u8>>::from_spec(vec![], POSEIDON_SPEC.clone());
+        for poly in [&w_1, &e_1] {
+            transcript.write_ec_point(commit(poly).to_affine()).unwrap();
+        }
+        transcript.write_scalar(u_1).unwrap();
+        x_1.iter().for_each(|x| transcript.write_scalar(*x).unwrap());
+        transcript.write_ec_point(commit(&w_2).to_affine()).unwrap();
+        x_2.iter().for_each(|x| transcript.write_scalar(*x).unwrap());
+        transcript.write_ec_point(commit(&t).to_affine()).unwrap();
+        let r = transcript.squeeze_challenge();
+        let [w, e] = [(&w_1, &w_2), (&e_1, &t)]
+            .map(|(lhs, rhs)| lhs.iter().zip(rhs).map(|(lhs, rhs)| *lhs + r * rhs).collect_vec());
+        let z = transcript.squeeze_challenge();
+        transcript.write_scalar(eval(&w, z) + shift).unwrap();
+        transcript.write_scalar(eval(&e, z)).unwrap();
+        for poly in [&w, &e] {
+            transcript.write_ec_point(commit(&quotient(poly, z)).to_affine()).unwrap();
+        }
+        transcript.finalize()
+    };
+    let read_nova_proof = |proof: &[u8]| {
+        let mut transcript =
+            PoseidonTranscript::<NativeLoader, &[u8]>::from_spec(proof, POSEIDON_SPEC.clone());
+        let running =
+            RelaxedR1csInstance::<G1Affine, NativeLoader>::read(2, &mut transcript).unwrap();
+        let incoming = R1csInstance::read(2, &mut transcript).unwrap();
+        let folded = running.fold(&incoming, &mut transcript).unwrap();
+        let opening = NovaKzgOpening::<G1Affine, NativeLoader>::read(&mut transcript).unwrap();
+        (folded, opening)
+    };
+
+    let (folded, opening) = read_nova_proof(&nova_proof(Fr::zero()));
+    let decider = MockDecider(decider_instances(&folded, &opening));
+    let pk = gen_pk(&params, &decider, None);
+
+    // Verifier binds the decider snark to the folded instance and its openings,
+    // then accumulates all of them to be decided at once.
+    let mut verify = |nova_proof: &[u8], decider: MockDecider| {
+        let (folded, opening) = read_nova_proof(nova_proof);
+        let snark = gen_snark_shplonk(&params, &pk, decider, &mut rng, None::<&str>);
+        if snark.instances != [decider_instances(&folded, &opening)] {
+            return false;
+        }
+        let accumulators = succinct_verify_snark(&svk, &snark)
+            .into_iter()
+            .chain(opening.accumulators(&svk, &folded))
+            .collect_vec();
+        Kzg::<Bn256, Bdfg21>::decide(&dk, fold(None, &accumulators))
+    };
+
+    assert!(verify(&nova_proof(Fr::zero()), decider.clone()));
+
+    // Decider snark not bound to the opened evaluations
+    let wrong_proof = nova_proof(Fr::one());
+    assert!(!verify(&wrong_proof, decider));
+    // Decider snark bound to the wrong evaluation, which fails the opening
+    let (folded, opening) = read_nova_proof(&wrong_proof);
+    assert!(!verify(&wrong_proof, MockDecider(decider_instances(&folded, &opening))));
+}
@@ -0,0 +1,32 @@
+use super::TestCircuit1;
+use crate::gen_pk;
+use crate::halo2::{
+    gen_snark_shplonk,
+    parallel::{gen_snarks_parallel_shplonk, proof_rng},
+    verify_snark_shplonk,
+};
+use crate::halo2_proofs;
+use ark_std::test_rng;
+use halo2_proofs::{halo2curves::bn256::Bn256, poly::kzg::commitment::ParamsKZG};
+use itertools::Itertools;
+
+#[test]
+fn test_gen_snarks_parallel() {
+    let mut rng = test_rng();
+    let params = ParamsKZG::<Bn256>::setup(8, &mut rng);
+    let circuits = (0..4).map(|_| TestCircuit1::rand(&mut rng)).collect_vec();
+    let pk = gen_pk(&params, &circuits[0], None);
+    let seed = [1; 32];
+
+    let snarks = gen_snarks_parallel_shplonk(&params, &pk, circuits.clone(), seed, 2);
+    assert_eq!(snarks.len(), circuits.len());
+
+    // Same as generated sequentially, no matter how proofs are scheduled.
+    for (idx, (circuit, snark)) in circuits.into_iter().zip(snarks).enumerate() {
+        let expected =
+            gen_snark_shplonk(&params, &pk, circuit, &mut proof_rng(seed, idx), None::<&str>);
+        assert_eq!(snark.instances, expected.instances);
+        assert_eq!(snark.proof, expected.proof);
+        assert!(verify_snark_shplonk::<TestCircuit1>(&params, snark, pk.get_vk()));
+    }
+}
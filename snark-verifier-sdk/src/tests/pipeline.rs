@@ -0,0 +1,19 @@
+use super::TestCircuit1;
+use crate::evm::evm_verify_calldata;
+use crate::pipeline::Pipeline;
+use ark_std::test_rng;
+
+#[test]
+fn test_pipeline_with_compression() {
+    let mut rng = test_rng();
+    let circuits = (0..2).map(|_| TestCircuit1::rand(&mut rng)).collect();
+
+    let output = Pipeline::new(8, 21)
+        .set_compression(true)
+        .with_compression_config("./configs/compression_circuit.config")
+        .with_aggregation_config("./configs/example_evm_accumulator.config")
+        .with_cache_dir("./data/pipeline")
+        .run(circuits, &mut rng);
+
+    assert!(evm_verify_calldata(output.deployment_code, output.calldata));
+}
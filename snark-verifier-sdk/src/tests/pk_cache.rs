@@ -0,0 +1,51 @@
+use super::{TestCircuit1, TestCircuit2};
+use crate::halo2_proofs;
+use crate::{gen_pk_with_hash, pk_hash, read_pk_with_hash, write_pk};
+use ark_std::test_rng;
+use halo2_proofs::{
+    halo2curves::bn256::Bn256,
+    plonk::keygen_vk,
+    poly::{commitment::Params, kzg::commitment::ParamsKZG},
+};
+use std::{fs, io, process};
+
+#[test]
+fn test_pk_cache_with_hash() {
+    let mut rng = test_rng();
+    let params = ParamsKZG::<Bn256>::setup(8, &mut rng);
+    let dir = std::env::temp_dir().join(format!("snark-verifier-pk-cache-{}", process::id()));
+    let path = dir.join("test.pkey");
+
+    let circuit = TestCircuit1::rand(&mut rng);
+    let vk = keygen_vk(&params, &circuit).unwrap();
+    let hash = pk_hash(&params, &vk, b"");
+    assert_ne!(hash, pk_hash(&params, &vk, b"config"));
+    let params_other = {
+        let mut params = ParamsKZG::<Bn256>::setup(9, &mut rng);
+        params.downsize(8);
+        params
+    };
+    assert_ne!(hash, pk_hash(&params_other, &vk, b""));
+    // Same shape but different constants
+    let vk_other = keygen_vk(&params, &TestCircuit2::rand(&mut rng)).unwrap();
+    assert_ne!(hash, pk_hash(&params, &vk_other, b""));
+
+    let pk = gen_pk_with_hash(&params, &circuit, b"", &path);
+    let cached = read_pk_with_hash::<TestCircuit1>(&hash, &path).unwrap();
+    assert_eq!(pk.get_vk().transcript_repr(), cached.get_vk().transcript_repr());
+
+    // Stale proving key is refused, and regenerated by `gen_pk_with_hash`
+    let stale = pk_hash(&params, &vk, b"stale");
+    write_pk(&pk, &stale, &path).unwrap();
+    let err = read_pk_with_hash::<TestCircuit1>(&hash, &path).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    gen_pk_with_hash(&params, &circuit, b"", &path);
+    assert!(read_pk_with_hash::<TestCircuit1>(&hash, &path).is_ok());
+
+    // Proving key of circuit with different constants is refused
+    let hash_other = pk_hash(&params, &vk_other, b"");
+    let err = read_pk_with_hash::<TestCircuit2>(&hash_other, &path).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,69 @@
+use super::TestCircuit1;
+use crate::halo2::aggregation::AggregationCircuit;
+use crate::CircuitExt;
+use crate::{gen_pk, halo2::gen_snark_shplonk};
+use crate::{halo2_base::utils::fs::gen_srs, halo2_proofs, Snark};
+use ark_std::test_rng;
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::halo2curves::bn256::{Bn256, Fr};
+use halo2_proofs::poly::{commitment::Params, kzg::commitment::ParamsKZG};
+use rand::Rng;
+
+/// Returns a snark of [`TestCircuit1`] with `params` and an invalid one with
+/// the same protocol, whose first instance is tampered.
+fn valid_and_invalid_snarks(params: &ParamsKZG<Bn256>, mut rng: impl Rng) -> (Snark, Snark) {
+    let circuit = TestCircuit1::rand(&mut rng);
+    let pk = gen_pk(params, &circuit, None);
+    let snark = gen_snark_shplonk(params, &pk, circuit, &mut rng, None::<&str>);
+    let mut invalid = snark.clone();
+    invalid.instances[0][0] += Fr::one();
+    (snark, invalid)
+}
+
+#[test]
+fn test_shplonk_selective_aggregation() {
+    std::env::set_var("VERIFY_CONFIG", "./configs/example_evm_accumulator.config");
+    let k = 8;
+    let k_agg = 21;
+
+    let mut rng = test_rng();
+    let params_outer = gen_srs(k_agg);
+    let params_inner = {
+        let mut params = params_outer.clone();
+        params.downsize(k);
+        params
+    };
+    let (snark, invalid) = valid_and_invalid_snarks(&params_inner, &mut rng);
+
+    let agg_circuit = AggregationCircuit::new_with_flags(
+        &params_outer,
+        [snark, invalid],
+        [true, false],
+        &mut rng,
+    );
+    let instances = agg_circuit.instances();
+    assert_eq!(agg_circuit.num_instance(), vec![instances[0].len()]);
+    assert_eq!(instances[0][instances[0].len() - 2..], [Fr::one(), Fr::zero()]);
+    MockProver::run(k_agg, &agg_circuit, instances.clone()).unwrap().assert_satisfied();
+
+    // Flags are bound by instances
+    let mut instances = instances;
+    let len = instances[0].len();
+    instances[0].swap(len - 2, len - 1);
+    assert!(MockProver::run(k_agg, &agg_circuit, instances).unwrap().verify().is_err());
+}
+
+#[test]
+#[should_panic(expected = "Snark 1 with k = 8 fails to verify")]
+fn test_shplonk_selective_aggregation_with_invalid_flagged_snark() {
+    let mut rng = test_rng();
+    let params_outer = ParamsKZG::<Bn256>::setup(10, &mut rng);
+    let params_inner = {
+        let mut params = params_outer.clone();
+        params.downsize(8);
+        params
+    };
+    let (snark, invalid) = valid_and_invalid_snarks(&params_inner, &mut rng);
+
+    AggregationCircuit::new_with_flags(&params_outer, [snark, invalid], [true, true], &mut rng);
+}
@@ -1,13 +1,17 @@
 use super::{TestCircuit1, TestCircuit2};
 use crate::evm::{evm_verify, gen_evm_proof_shplonk, gen_evm_verifier};
 use crate::halo2::aggregation::AggregationCircuit;
+use crate::halo2_base::utils::fs::gen_srs;
+use crate::halo2_proofs;
 use crate::CircuitExt;
-use crate::{gen_pk, halo2::gen_snark_shplonk};
+use crate::{
+    gen_pk,
+    halo2::{gen_snark_multi_shplonk, gen_snark_shplonk, verify_snark_shplonk},
+};
 use ark_std::test_rng;
-use halo2_base::halo2_proofs;
+use halo2_proofs::dev::MockProver;
 use halo2_proofs::halo2curves::bn256::Bn256;
 use halo2_proofs::poly::commitment::Params;
-use snark_verifier::loader::halo2::halo2_ecc::halo2_base::utils::fs::gen_srs;
 use snark_verifier::pcs::kzg::{Bdfg21, Kzg};
 use std::path::Path;
 
@@ -86,3 +90,32 @@ fn test_shplonk_then_sphplonk_with_evm_verification() {
     println!("finished bytecode generation");
     evm_verify(deployment_code, instances, proof)
 }
+
+#[test]
+fn test_shplonk_aggregation_of_multi_circuit_proof() {
+    std::env::set_var("VERIFY_CONFIG", "./configs/example_evm_accumulator.config");
+    let k = 8;
+    let k_agg = 21;
+
+    let mut rng = test_rng();
+    let params_outer = gen_srs(k_agg);
+    let params_inner = {
+        let mut params = params_outer.clone();
+        params.downsize(k);
+        params
+    };
+
+    // Single proof of two instances of circuit 1
+    let circuits = vec![TestCircuit1::rand(&mut rng), TestCircuit1::rand(&mut rng)];
+    let pk = gen_pk(&params_inner, &circuits[0], None);
+    let snark =
+        gen_snark_multi_shplonk(&params_inner, &pk, circuits.clone(), &mut rng, None::<&str>);
+    assert_eq!(snark.instances, [circuits[0].instances(), circuits[1].instances()].concat());
+    assert!(verify_snark_shplonk::<TestCircuit1>(&params_inner, snark.clone(), pk.get_vk()));
+
+    // Aggregated along with a SNARK of a single circuit
+    let single = gen_snark_shplonk(&params_inner, &pk, circuits[0].clone(), &mut rng, None::<&str>);
+    let agg_circuit = AggregationCircuit::new(&params_outer, [snark, single], &mut rng);
+    let instances = agg_circuit.instances();
+    MockProver::run(k_agg, &agg_circuit, instances).unwrap().assert_satisfied();
+}
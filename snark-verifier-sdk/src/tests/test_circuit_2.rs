@@ -1,7 +1,7 @@
 //! Another dummy circuit for testing.
 use super::StandardPlonkConfig;
+use crate::halo2_proofs;
 use crate::CircuitExt;
-use halo2_base::halo2_proofs;
 use halo2_proofs::halo2curves::bn256::Fr;
 use halo2_proofs::{
     circuit::{Layouter, SimpleFloorPlanner, Value},
@@ -1,14 +1,20 @@
 use super::TestCircuit1;
 use crate::evm::{evm_verify, gen_evm_proof_shplonk, gen_evm_verifier};
-use crate::halo2::aggregation::AggregationCircuit;
-use crate::CircuitExt;
+use crate::halo2::{accumulation::succinct_verify_snark, aggregation::AggregationCircuit};
+use crate::halo2_base::utils::fs::gen_srs;
+use crate::halo2_proofs;
 use crate::{gen_pk, halo2::gen_snark_shplonk};
+use crate::{CircuitExt, BITS, LIMBS};
 use ark_std::test_rng;
-use halo2_base::halo2_proofs;
 use halo2_proofs::halo2curves::bn256::Bn256;
-use halo2_proofs::poly::commitment::Params;
-use snark_verifier::loader::halo2::halo2_ecc::halo2_base::utils::fs::gen_srs;
-use snark_verifier::pcs::kzg::{Bdfg21, Kzg};
+use halo2_proofs::poly::commitment::{Params, ParamsProver};
+use snark_verifier::{
+    loader::native::NativeLoader,
+    pcs::{
+        kzg::{Bdfg21, Kzg, KzgDecidingKey, LimbsEncoding},
+        AccumulatorEncoding, Decider,
+    },
+};
 use std::path::Path;
 
 #[test]
@@ -54,6 +60,25 @@ fn test_two_layer_aggregation_evm_verification() {
     );
     println!("finished outer proof generation");
 
+    // Accumulator exposed by the first aggregation is re-accumulated along
+    // with the new one of its proof
+    let svk = params_outer.get_g()[0].into();
+    let dk: KzgDecidingKey<Bn256> = (params_outer.g2(), params_outer.s_g2()).into();
+    let accumulators = succinct_verify_snark(&svk, &first_agg_proof);
+    assert_eq!(accumulators.len(), 2);
+    let instances = first_agg_circuit.instances();
+    let old_accumulator = <LimbsEncoding<LIMBS, BITS> as AccumulatorEncoding<
+        _,
+        NativeLoader,
+        Kzg<Bn256, Bdfg21>,
+    >>::from_repr(&instances[0][..4 * LIMBS].iter().collect::<Vec<_>>())
+    .unwrap();
+    assert_eq!(
+        (accumulators[1].lhs, accumulators[1].rhs),
+        (old_accumulator.lhs, old_accumulator.rhs)
+    );
+    assert!(Kzg::<Bn256, Bdfg21>::decide_all(&dk, accumulators));
+
     // layer 3, second aggregation
     let second_agg_circuit = AggregationCircuit::new(&params_outer, [first_agg_proof], &mut rng);
     let pk_agg = gen_pk(&params_outer, &second_agg_circuit, None);
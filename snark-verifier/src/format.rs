@@ -0,0 +1,82 @@
+//! Semantic version of serialized artifacts (e.g. [`Protocol`](crate::Protocol)
+//! and snarks of `snark-verifier-sdk`) and generated verifier code, so
+//! deployments could detect drift between prover and verifier toolchains.
+//!
+//! Serialized artifacts are prefixed by [`FORMAT_MAGIC`] and
+//! [`FormatVersion`]. Artifacts without the prefix are written before
+//! versioning, whose layout is the same as payload of version `1.0`, so they
+//! could still be migrated by reading as is. Generated Solidity code carries
+//! the version in a comment, see [`FormatVersion::from_sol_code`].
+use crate::Error;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// Magic bytes prefixed to serialized artifacts, followed by
+/// [`FormatVersion`].
+pub const FORMAT_MAGIC: [u8; 4] = *b"SVFV";
+
+/// Current version of serialized artifacts and generated code.
+pub const FORMAT_VERSION: FormatVersion = FormatVersion::new(1, 0);
+
+/// Prefix of the comment line carrying [`FormatVersion`] in generated
+/// Solidity code.
+pub const SOL_CODE_FORMAT_VERSION_PREFIX: &str = "// snark-verifier format version ";
+
+/// Semantic version of format, where `major` is bumped for incompatible
+/// changes and `minor` for backward compatible additions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct FormatVersion {
+    pub major: u16,
+    pub minor: u16,
+}
+
+impl FormatVersion {
+    /// Initialize a [`FormatVersion`].
+    pub const fn new(major: u16, minor: u16) -> Self {
+        Self { major, minor }
+    }
+
+    /// Returns whether artifacts of `self` could be read by [`FORMAT_VERSION`],
+    /// which requires the same `major` and not newer `minor`.
+    pub fn is_compatible(&self) -> bool {
+        self.major == FORMAT_VERSION.major && self.minor <= FORMAT_VERSION.minor
+    }
+
+    /// Returns error if `self` is not compatible with [`FORMAT_VERSION`].
+    pub fn check(&self) -> Result<(), Error> {
+        if !self.is_compatible() {
+            return Err(Error::InvalidConfig(format!(
+                "Incompatible format version {self}, expected {}.x with x <= {}",
+                FORMAT_VERSION.major, FORMAT_VERSION.minor
+            )));
+        }
+        Ok(())
+    }
+
+    /// Returns [`FormatVersion`] of Solidity code generated by
+    /// [`EvmLoader::solidity_code`], or `None` if it's generated before
+    /// versioning.
+    ///
+    /// [`EvmLoader::solidity_code`]: crate::loader::evm::EvmLoader::solidity_code
+    pub fn from_sol_code(code: &str) -> Option<Self> {
+        code.lines()
+            .find_map(|line| line.trim().strip_prefix(SOL_CODE_FORMAT_VERSION_PREFIX))
+            .and_then(|version| version.parse().ok())
+    }
+}
+
+impl fmt::Display for FormatVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl FromStr for FormatVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidConfig(format!("Invalid format version {s}"));
+        let (major, minor) = s.trim().split_once('.').ok_or_else(invalid)?;
+        Ok(Self::new(major.parse().map_err(|_| invalid())?, minor.parse().map_err(|_| invalid())?))
+    }
+}
@@ -4,14 +4,21 @@
 #![allow(clippy::upper_case_acronyms)]
 
 pub mod cost;
+pub mod format;
 pub mod loader;
 pub mod pcs;
 pub mod system;
 pub mod util;
 pub mod verifier;
 
-pub(crate) use halo2_base::halo2_proofs;
-pub(crate) use halo2_proofs::halo2curves as halo2_curves;
+// Re-export halo2 ecosystem crates with features selected by this crate, so
+// downstream crates could construct circuits and params with exactly the same
+// versions instead of depending on them separately.
+pub use halo2_base;
+pub use halo2_base::halo2_proofs;
+#[cfg(feature = "loader_halo2")]
+pub use halo2_ecc;
+pub use halo2_proofs::halo2curves as halo2_curves;
 #[cfg(feature = "halo2-pse")]
 pub(crate) use poseidon;
 #[cfg(feature = "halo2-axiom")]
@@ -31,6 +38,12 @@ pub enum Error {
     AssertionFailure(String),
     /// Transcript error.
     Transcript(std::io::ErrorKind, String),
+    /// Inconsistent configuration, e.g. of compiling a verifying key into
+    /// protocol or of encoding accumulator into instances.
+    InvalidConfig(String),
+    /// Features of circuit that can't be compiled into protocol, see
+    /// [`system::halo2::audit`].
+    UnsupportedFeatures(Vec<system::halo2::UnsupportedFeature>),
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -65,4 +78,7 @@ where
     pub instance_committing_key: Option<util::protocol::InstanceCommittingKey<C>>,
     pub linearization: Option<util::protocol::LinearizationStrategy>,
     pub accumulator_indices: Vec<Vec<(usize, usize)>>,
+    // Precomputed structure of `queries`
+    #[serde(default)]
+    pub rotation_sets: Vec<util::protocol::RotationSet>,
 }
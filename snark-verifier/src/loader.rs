@@ -3,7 +3,8 @@
 
 use crate::{
     util::{
-        arithmetic::{CurveAffine, FieldOps, PrimeField},
+        arithmetic::{CurveAffine, Domain, FieldOps, PrimeField},
+        protocol::CommonPolynomialEvaluation,
         Itertools,
     },
     Error,
@@ -21,6 +22,18 @@ pub mod evm;
 /// Halo2 loader
 pub mod halo2;
 
+#[cfg(feature = "loader_ark")]
+/// Arkworks loader
+pub mod ark;
+
+#[cfg(feature = "loader_move")]
+/// Move loader
+pub mod move_lang;
+
+#[cfg(feature = "loader_solana")]
+/// Solana loader
+pub mod solana;
+
 /// Loaded elliptic curve point.
 pub trait LoadedEcPoint<C: CurveAffine>: Clone + Debug + PartialEq {
     /// [`Loader`].
@@ -256,6 +269,33 @@ pub trait ScalarLoader<F: PrimeField> {
     }
 }
 
+/// [`ScalarLoader`] which decomposes scalars into limbs and recomposes limbs
+/// into scalars with range constraints, as the in-circuit counterpart of
+/// [`fe_to_limbs`] and [`fe_from_limbs`], so limb encodings of instances are
+/// produced and consumed consistently across loaders.
+///
+/// Limbs are in little-endian, each constrained to have at most `BITS` bits.
+/// Note that when `LIMBS * BITS` is not less than [`PrimeField::NUM_BITS`],
+/// limbs of a scalar are only unique up to adding the modulus.
+///
+/// [`fe_to_limbs`]: crate::util::arithmetic::fe_to_limbs
+/// [`fe_from_limbs`]: crate::util::arithmetic::fe_from_limbs
+pub trait LimbsLoader<F: PrimeField>: ScalarLoader<F> {
+    /// Decompose `scalar` into `LIMBS` limbs of at most `BITS` bits, and
+    /// constrain them to recompose `scalar`.
+    fn scalar_to_limbs<const LIMBS: usize, const BITS: usize>(
+        &self,
+        scalar: &Self::LoadedScalar,
+    ) -> Result<[Self::LoadedScalar; LIMBS], Error>;
+
+    /// Recompose `LIMBS` limbs into a scalar, and constrain each limb to have
+    /// at most `BITS` bits.
+    fn scalar_from_limbs<const LIMBS: usize, const BITS: usize>(
+        &self,
+        limbs: [&Self::LoadedScalar; LIMBS],
+    ) -> Result<Self::LoadedScalar, Error>;
+}
+
 /// [`EcPointLoader`] and [`ScalarLoader`] with some helper methods.
 pub trait Loader<C: CurveAffine>:
     EcPointLoader<C> + ScalarLoader<C::ScalarExt> + Clone + Debug
@@ -265,4 +305,18 @@ pub trait Loader<C: CurveAffine>:
 
     /// End latest started cost metering.
     fn end_cost_metering(&self) {}
+
+    /// Returns evaluated [`CommonPolynomialEvaluation`] of `domain` at `z`.
+    /// Loaders could override it to memoize evaluations across proofs with
+    /// identical domain.
+    fn common_poly_eval(
+        domain: &Domain<C::Scalar>,
+        lagranges: impl IntoIterator<Item = i32>,
+        z: &Self::LoadedScalar,
+    ) -> CommonPolynomialEvaluation<C, Self> {
+        let mut common_poly_eval = CommonPolynomialEvaluation::new(domain, lagranges, z);
+        Self::batch_invert(common_poly_eval.denoms());
+        common_poly_eval.evaluate();
+        common_poly_eval
+    }
 }
@@ -0,0 +1,339 @@
+//! `Loader` implementation over [arkworks](https://github.com/arkworks-rs)
+//! bn254 types, so callers with arkworks-based infrastructure could verify
+//! proofs without converting every point and scalar into [`halo2_curves`]
+//! representations manually.
+//!
+//! Loaded values are wrapped in [`Scalar`] and [`EcPoint`], which convert
+//! from and into arkworks types by [`From`]. Transcripts implemented for
+//! [`NativeLoader`] could be reused by wrapping them in [`ArkTranscript`].
+//!
+//! [`halo2_curves`]: crate::halo2_curves
+use crate::{
+    halo2_curves::bn256::{Fq, Fq2, Fr, G1Affine, G2Affine},
+    loader::{
+        native::NativeLoader, EcPointLoader, LoadedEcPoint, LoadedScalar, Loader, ScalarLoader,
+    },
+    util::{
+        arithmetic::{Coordinates, CurveAffine, FieldOps, PrimeCurveAffine, PrimeField},
+        transcript::{Transcript, TranscriptRead},
+        Itertools,
+    },
+    Error,
+};
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{BigInteger, Field, PrimeField as _};
+use lazy_static::lazy_static;
+use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+lazy_static! {
+    /// ArkLoader instance for [`LoadedEcPoint::loader`] and
+    /// [`LoadedScalar::loader`] referencing.
+    pub static ref LOADER: ArkLoader = ArkLoader;
+}
+
+/// Convert a bn256 scalar into arkworks representation.
+pub fn fr_to_ark(scalar: &Fr) -> ark_bn254::Fr {
+    ark_bn254::Fr::from_le_bytes_mod_order(scalar.to_repr().as_ref())
+}
+
+/// Convert an arkworks bn254 scalar into [`halo2_curves`] representation.
+///
+/// [`halo2_curves`]: crate::halo2_curves
+pub fn fr_from_ark(scalar: &ark_bn254::Fr) -> Fr {
+    Fr::from_repr(le_bytes(scalar.into_bigint())).unwrap()
+}
+
+fn fq_to_ark(fq: &Fq) -> ark_bn254::Fq {
+    ark_bn254::Fq::from_le_bytes_mod_order(fq.to_repr().as_ref())
+}
+
+fn fq_from_ark(fq: &ark_bn254::Fq) -> Fq {
+    Fq::from_repr(le_bytes(fq.into_bigint())).unwrap()
+}
+
+fn fq2_to_ark(fq2: &Fq2) -> ark_bn254::Fq2 {
+    ark_bn254::Fq2::new(fq_to_ark(&fq2.c0), fq_to_ark(&fq2.c1))
+}
+
+fn le_bytes(bigint: impl BigInteger) -> [u8; 0x20] {
+    bigint.to_bytes_le().try_into().unwrap()
+}
+
+/// Convert a bn256 G1 point into arkworks representation.
+pub fn g1_to_ark(ec_point: &G1Affine) -> ark_bn254::G1Affine {
+    Option::from(ec_point.coordinates())
+        .map(|coordinates: Coordinates<G1Affine>| {
+            ark_bn254::G1Affine::new_unchecked(
+                fq_to_ark(coordinates.x()),
+                fq_to_ark(coordinates.y()),
+            )
+        })
+        .unwrap_or_else(ark_bn254::G1Affine::zero)
+}
+
+/// Convert an arkworks bn254 G1 point into [`halo2_curves`] representation.
+///
+/// [`halo2_curves`]: crate::halo2_curves
+pub fn g1_from_ark(ec_point: &ark_bn254::G1Affine) -> G1Affine {
+    ec_point
+        .xy()
+        .map(|(x, y)| G1Affine::from_xy(fq_from_ark(x), fq_from_ark(y)).unwrap())
+        .unwrap_or_else(G1Affine::identity)
+}
+
+/// Convert a bn256 G2 point into arkworks representation.
+pub fn g2_to_ark(ec_point: &G2Affine) -> ark_bn254::G2Affine {
+    Option::from(ec_point.coordinates())
+        .map(|coordinates: Coordinates<G2Affine>| {
+            ark_bn254::G2Affine::new_unchecked(
+                fq2_to_ark(coordinates.x()),
+                fq2_to_ark(coordinates.y()),
+            )
+        })
+        .unwrap_or_else(ark_bn254::G2Affine::zero)
+}
+
+/// `Loader` implementation over arkworks bn254 types.
+#[derive(Clone, Debug)]
+pub struct ArkLoader;
+
+/// Elliptic curve point wrapping [`ark_bn254::G1Affine`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EcPoint(pub ark_bn254::G1Affine);
+
+impl From<ark_bn254::G1Affine> for EcPoint {
+    fn from(ec_point: ark_bn254::G1Affine) -> Self {
+        Self(ec_point)
+    }
+}
+
+impl From<EcPoint> for ark_bn254::G1Affine {
+    fn from(ec_point: EcPoint) -> Self {
+        ec_point.0
+    }
+}
+
+impl LoadedEcPoint<G1Affine> for EcPoint {
+    type Loader = ArkLoader;
+
+    fn loader(&self) -> &ArkLoader {
+        &LOADER
+    }
+}
+
+/// Field element wrapping [`ark_bn254::Fr`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Scalar(pub ark_bn254::Fr);
+
+impl From<ark_bn254::Fr> for Scalar {
+    fn from(scalar: ark_bn254::Fr) -> Self {
+        Self(scalar)
+    }
+}
+
+impl From<Scalar> for ark_bn254::Fr {
+    fn from(scalar: Scalar) -> Self {
+        scalar.0
+    }
+}
+
+impl Add for Scalar {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Scalar {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl Mul for Scalar {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self(self.0 * rhs.0)
+    }
+}
+
+impl Neg for Scalar {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(-self.0)
+    }
+}
+
+impl<'a> Add<&'a Self> for Scalar {
+    type Output = Self;
+
+    fn add(self, rhs: &'a Self) -> Self {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl<'a> Sub<&'a Self> for Scalar {
+    type Output = Self;
+
+    fn sub(self, rhs: &'a Self) -> Self {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl<'a> Mul<&'a Self> for Scalar {
+    type Output = Self;
+
+    fn mul(self, rhs: &'a Self) -> Self {
+        Self(self.0 * rhs.0)
+    }
+}
+
+impl AddAssign for Scalar {
+    fn add_assign(&mut self, rhs: Self) {
+        self.0 += rhs.0;
+    }
+}
+
+impl SubAssign for Scalar {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl MulAssign for Scalar {
+    fn mul_assign(&mut self, rhs: Self) {
+        self.0 *= rhs.0;
+    }
+}
+
+impl<'a> AddAssign<&'a Self> for Scalar {
+    fn add_assign(&mut self, rhs: &'a Self) {
+        self.0 += rhs.0;
+    }
+}
+
+impl<'a> SubAssign<&'a Self> for Scalar {
+    fn sub_assign(&mut self, rhs: &'a Self) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl<'a> MulAssign<&'a Self> for Scalar {
+    fn mul_assign(&mut self, rhs: &'a Self) {
+        self.0 *= rhs.0;
+    }
+}
+
+impl FieldOps for Scalar {
+    fn invert(&self) -> Option<Scalar> {
+        self.0.inverse().map(Self)
+    }
+}
+
+impl LoadedScalar<Fr> for Scalar {
+    type Loader = ArkLoader;
+
+    fn loader(&self) -> &ArkLoader {
+        &LOADER
+    }
+}
+
+impl EcPointLoader<G1Affine> for ArkLoader {
+    type LoadedEcPoint = EcPoint;
+
+    fn ec_point_load_const(&self, value: &G1Affine) -> EcPoint {
+        EcPoint(g1_to_ark(value))
+    }
+
+    fn ec_point_assert_eq(
+        &self,
+        annotation: &str,
+        lhs: &EcPoint,
+        rhs: &EcPoint,
+    ) -> Result<(), Error> {
+        lhs.eq(rhs).then_some(()).ok_or_else(|| Error::AssertionFailure(annotation.to_string()))
+    }
+
+    fn multi_scalar_multiplication(pairs: &[(&Scalar, &EcPoint)]) -> EcPoint {
+        let (scalars, bases): (Vec<_>, Vec<_>) =
+            pairs.iter().map(|(scalar, base)| (scalar.0, base.0)).unzip();
+        EcPoint(ark_bn254::G1Projective::msm_unchecked(&bases, &scalars).into_affine())
+    }
+}
+
+impl ScalarLoader<Fr> for ArkLoader {
+    type LoadedScalar = Scalar;
+
+    fn load_const(&self, value: &Fr) -> Scalar {
+        Scalar(fr_to_ark(value))
+    }
+
+    fn assert_eq(&self, annotation: &str, lhs: &Scalar, rhs: &Scalar) -> Result<(), Error> {
+        lhs.eq(rhs).then_some(()).ok_or_else(|| Error::AssertionFailure(annotation.to_string()))
+    }
+
+    fn batch_invert<'a>(values: impl IntoIterator<Item = &'a mut Scalar>)
+    where
+        Scalar: 'a,
+    {
+        let mut values = values.into_iter().collect_vec();
+        let mut inverted = values.iter().map(|value| value.0).collect_vec();
+        ark_ff::batch_inversion(&mut inverted);
+        for (value, inverted) in values.iter_mut().zip(inverted) {
+            value.0 = inverted;
+        }
+    }
+}
+
+impl Loader<G1Affine> for ArkLoader {}
+
+/// Adapter of transcript implemented for [`NativeLoader`] into
+/// [`ArkLoader`], which converts values at the boundary.
+#[derive(Debug)]
+pub struct ArkTranscript<T>(pub T);
+
+impl<T> ArkTranscript<T> {
+    /// Returns inner transcript.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Transcript<G1Affine, ArkLoader> for ArkTranscript<T>
+where
+    T: Transcript<G1Affine, NativeLoader>,
+{
+    fn loader(&self) -> &ArkLoader {
+        &LOADER
+    }
+
+    fn squeeze_challenge(&mut self) -> Scalar {
+        Scalar(fr_to_ark(&self.0.squeeze_challenge()))
+    }
+
+    fn common_ec_point(&mut self, ec_point: &EcPoint) -> Result<(), Error> {
+        self.0.common_ec_point(&g1_from_ark(&ec_point.0))
+    }
+
+    fn common_scalar(&mut self, scalar: &Scalar) -> Result<(), Error> {
+        self.0.common_scalar(&fr_from_ark(&scalar.0))
+    }
+}
+
+impl<T> TranscriptRead<G1Affine, ArkLoader> for ArkTranscript<T>
+where
+    T: TranscriptRead<G1Affine, NativeLoader>,
+{
+    fn read_scalar(&mut self) -> Result<Scalar, Error> {
+        self.0.read_scalar().map(|scalar| Scalar(fr_to_ark(&scalar)))
+    }
+
+    fn read_ec_point(&mut self) -> Result<EcPoint, Error> {
+        self.0.read_ec_point().map(|ec_point| EcPoint(g1_to_ark(&ec_point)))
+    }
+}
@@ -1,17 +1,34 @@
 mod code;
 pub(crate) mod loader;
+mod staging;
 mod util;
+mod vk_registry;
 
 #[cfg(test)]
 mod test;
 
-pub use loader::{EcPoint, EvmLoader, Scalar};
+pub use loader::{
+    BlobCheck, EcPoint, EcPointCheck, EntryPoint, EvmLoader, EvmLoaderBuilder, FixedBaseMsm,
+    G2Operand, G2Source, InstanceCheck, ReturnConvention, Scalar, DEFAULT_MEMORY_LIMIT,
+    INVALID_INSTANCE_ERROR, UNREGISTERED_VK_ERROR, VERIFICATION_FAILURE_ERROR,
+    VERIFY_RAW_SIGNATURE,
+};
+pub use staging::{encode_staged_calldata, staged_instance_digest, staging_sol_code};
 pub use util::{
-    compile_solidity, encode_calldata, estimate_gas, fe_to_u256, modulus, u256_to_fe, ExecutorBuilder,
-    MemoryChunk,
+    compile_solidity, compile_solidity_with_config, compile_split_verifier,
+    decode_verification_failure, deploy_and_call, encode_bytes_call, encode_calldata,
+    encode_verify_raw_calldata, estimate_gas, execute_with_specs, fe_to_u256, modulus,
+    spec_divergences, u256_to_fe, write_calldata, DeployResult, Executor, ExecutorBuilder,
+    MemoryChunk, RawCallResult, SolcConfig, SpecId, SpecOutcome, SplitVerifier, VerifierArtifact,
+    CONTRACT_SIZE_LIMIT, SOLC_VERSION,
+};
+pub use vk_registry::{
+    encode_calldata_with_vk, encode_vk, encode_vk_registry_constructor_args, vk_digest,
 };
 
-pub use ethereum_types::U256;
+pub use ethereum_types::{Address, U256};
+
+pub(crate) use util::g2_to_u256s;
 
 #[cfg(test)]
 pub use test::execute;
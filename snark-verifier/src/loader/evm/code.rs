@@ -1,34 +1,254 @@
+use crate::{
+    format::{FORMAT_VERSION, SOL_CODE_FORMAT_VERSION_PREFIX},
+    loader::evm::loader::{
+        EntryPoint, ReturnConvention, UNREGISTERED_VK_ERROR, VERIFICATION_FAILURE_ERROR,
+    },
+    util::hash::{Digest, Keccak256},
+    Error,
+};
+use std::{cell::Cell, rc::Rc};
+
 pub enum Precompiled {
     BigModExp = 0x05,
     Bn254Add = 0x6,
     Bn254ScalarMul = 0x7,
     Bn254Pairing = 0x8,
+    PointEvaluation = 0xa,
 }
 
 #[derive(Clone, Debug)]
 pub struct SolidityAssemblyCode {
     // runtime code area
     runtime: String,
+    // entry point of the contract
+    entry_point: EntryPoint,
+    // convention of signaling result of verification
+    return_convention: ReturnConvention,
+    // memory pointer of g2 points copied from immutables
+    immutable_g2_ptr: Option<usize>,
+    // calldata offset of verifying key digest checked against registry
+    vk_digest_offset: Option<usize>,
+    // memory size laid out by loader so far
+    memory_size: Rc<Cell<usize>>,
+    // offset in runtime and memory size when each snippet is appended, which
+    // are where runtime could be split into stages
+    boundaries: Vec<(usize, usize)>,
 }
 
 impl SolidityAssemblyCode {
-    pub fn new() -> Self {
+    pub fn new(
+        memory_size: Rc<Cell<usize>>,
+        entry_point: EntryPoint,
+        return_convention: ReturnConvention,
+    ) -> Self {
         Self {
             runtime: String::new(),
+            entry_point,
+            return_convention,
+            immutable_g2_ptr: None,
+            vk_digest_offset: None,
+            memory_size,
+            boundaries: Vec::new(),
         }
     }
 
     pub fn code(&self, base_modulus: String, scalar_modulus: String) -> String {
+        assert!(
+            self.immutable_g2_ptr.is_none() || self.vk_digest_offset.is_none(),
+            "G2 points from immutables and verifying key registry can't be used together"
+        );
+        let (immutables, immutables_prelude) = match (self.immutable_g2_ptr, self.vk_digest_offset)
+        {
+            (Some(ptr), _) => (Self::immutables(&base_modulus), Self::immutables_prelude(ptr)),
+            (_, Some(offset)) => {
+                (Self::vk_registry(), Self::vk_registry_prelude(self.return_convention, offset))
+            }
+            (None, None) => Default::default(),
+        };
         format!(
             "
 // SPDX-License-Identifier: MIT
+{SOL_CODE_FORMAT_VERSION_PREFIX}{FORMAT_VERSION}
 
 pragma solidity ^0.8.0;
 
 contract Halo2Verifier {{
+    error InvalidInstance(uint256 index);
+    error VerificationFailure(bytes32 code);
+{immutables}
+    {} {{{immutables_prelude}
+        assembly {{{}{}
+            {}
+        }}
+    }}
+}}
+        ",
+            self.entry_point_signature(),
+            Self::assembly_prelude(&base_modulus, &scalar_modulus),
+            self.entry_point_prelude(),
+            self.runtime
+        )
+    }
+
+    /// Returns code of `num_stage` stage contracts, each running a consecutive
+    /// part of runtime split at boundaries of appended snippets with roughly
+    /// equal length, and code of dispatcher delegatecalling them in sequence.
+    ///
+    /// Since memory isn't shared across calls, each stage is called with the
+    /// original calldata followed by memory returned by the previous one, and
+    /// restores it from the end of calldata before running.
+    pub fn split(
+        &self,
+        base_modulus: String,
+        scalar_modulus: String,
+        num_stage: usize,
+    ) -> Result<(String, Vec<String>), Error> {
+        let unsupported = if self.immutable_g2_ptr.is_some() {
+            Some("G2 points from immutables")
+        } else if self.vk_digest_offset.is_some() {
+            Some("verifying key registry")
+        } else if self.entry_point != EntryPoint::Fallback {
+            Some("entry point other than fallback")
+        } else if self.return_convention != ReturnConvention::Revert {
+            Some("returning bool")
+        } else {
+            None
+        };
+        if let Some(unsupported) = unsupported {
+            return Err(Error::InvalidConfig(format!(
+                "Splitting verifier with {unsupported} is not supported"
+            )));
+        }
+        if num_stage == 0 || num_stage > self.boundaries.len() {
+            return Err(Error::InvalidConfig(format!(
+                "Can't split runtime of {} snippets into {num_stage} stages",
+                self.boundaries.len()
+            )));
+        }
+
+        // Index of snippet each stage starts from, leaving at least one snippet
+        // for each of the rest stages.
+        let mut starts = vec![0];
+        for idx in 1..num_stage {
+            let target = idx * self.runtime.len() / num_stage;
+            let max = self.boundaries.len() - (num_stage - idx);
+            let start = (starts.last().unwrap() + 1..=max)
+                .find(|start| self.boundaries[*start].0 >= target)
+                .unwrap_or(max);
+            starts.push(start);
+        }
+        let starts = starts.into_iter().map(|start| self.boundaries[start]).collect::<Vec<_>>();
+        let ends = starts.iter().skip(1).copied().chain([(self.runtime.len(), 0)]);
+
+        let stages = starts
+            .iter()
+            .zip(ends)
+            .enumerate()
+            .map(|(idx, (&(start, memory_in), (end, memory_out)))| {
+                // The first stage has nothing written in memory yet.
+                let restore = if idx == 0 {
+                    String::new()
+                } else {
+                    format!(
+                        "
+            calldatacopy(0, sub(calldatasize(), {memory_in:#x}), {memory_in:#x})"
+                    )
+                };
+                format!(
+                    "
+// SPDX-License-Identifier: MIT
+{SOL_CODE_FORMAT_VERSION_PREFIX}{FORMAT_VERSION}
+
+pragma solidity ^0.8.0;
+
+contract Halo2VerifierStage{idx} {{
+    error InvalidInstance(uint256 index);
+    error VerificationFailure(bytes32 code);
+
     fallback(bytes calldata) external returns (bytes memory) {{
-        assembly {{
+        assembly {{{}{restore}
+            {}{}
+
+            // Return memory for the next stage, or empty bytes if it's the last
+            return(0, {memory_out:#x})
+        }}
+    }}
+}}
+        ",
+                    Self::assembly_prelude(&base_modulus, &scalar_modulus),
+                    &self.runtime[start..end],
+                    Self::exit_on_failure(ReturnConvention::Revert),
+                )
+            })
+            .collect();
+
+        Ok((Self::dispatcher(num_stage), stages))
+    }
+
+    fn entry_point_signature(&self) -> &'static str {
+        match self.entry_point {
+            EntryPoint::Fallback => "fallback(bytes calldata) external returns (bytes memory)",
+            EntryPoint::VerifyRaw => {
+                "function verify_raw(bytes calldata raw) external view returns (bool)"
+            }
+        }
+    }
+
+    // Since offsets of `instances || proof` are fixed in runtime, `raw` is
+    // required to be encoded right after its offset.
+    fn entry_point_prelude(&self) -> &'static str {
+        match self.entry_point {
+            EntryPoint::Fallback => "",
+            EntryPoint::VerifyRaw => {
+                "
+            success := and(eq(raw.offset, 0x44), success)"
+            }
+        }
+    }
+
+    /// Returns code reverting with [`VERIFICATION_FAILURE_ERROR`] and code of
+    /// the first failed checkpoint if anything fails, or returning
+    /// `uint256(0)` with [`ReturnConvention::Bool`].
+    pub fn exit_on_failure(return_convention: ReturnConvention) -> String {
+        let exit = Self::exit_with_error(return_convention, VERIFICATION_FAILURE_ERROR, "failure");
+        format!(
+            "
+            // Exit with code of the first failed checkpoint if anything fails
+            if iszero(success) {{
+                {exit}
+            }}"
+        )
+    }
+
+    /// Returns code reverting with custom error of `signature` and a single
+    /// word `arg`, or returning `uint256(0)` with [`ReturnConvention::Bool`].
+    pub fn exit_with_error(
+        return_convention: ReturnConvention,
+        signature: &str,
+        arg: &str,
+    ) -> String {
+        match return_convention {
+            ReturnConvention::Revert => {
+                let hash = Keccak256::digest(signature.as_bytes());
+                let selector = u32::from_be_bytes(hash[..4].try_into().unwrap());
+                format!(
+                    "mstore(0x00, shl(224, {selector:#x}))
+                mstore(0x04, {arg})
+                revert(0x00, 0x24)"
+                )
+            }
+            ReturnConvention::Bool => String::from(
+                "mstore(0x00, 0)
+                return(0x00, 0x20)",
+            ),
+        }
+    }
+
+    fn assembly_prelude(base_modulus: &str, scalar_modulus: &str) -> String {
+        format!(
+            "
             let success := true
+            let failure := 0
             let f_p := {base_modulus}
             let f_q := {scalar_modulus}
             function validate_ec_point(x, y) -> valid {{
@@ -45,17 +265,186 @@ contract Halo2Verifier {{
                     let is_affine := eq(x_cube_plus_3, y_square)
                     valid := and(valid, is_affine)
                 }}
+            }}"
+        )
+    }
+
+    // Dispatcher deployed with addresses of stages as constructor arguments,
+    // which delegatecalls them in sequence and bubbles up revert of any.
+    fn dispatcher(num_stage: usize) -> String {
+        let immutables = (0..num_stage)
+            .map(|idx| format!("\n    address private immutable STAGE_{idx};"))
+            .collect::<String>();
+        let params = (0..num_stage).map(|idx| format!("address stage_{idx}")).collect::<Vec<_>>();
+        let assignments = (0..num_stage)
+            .map(|idx| format!("\n        STAGE_{idx} = stage_{idx};"))
+            .collect::<String>();
+        let locals = (0..num_stage)
+            .map(|idx| format!("\n        address stage_{idx} = STAGE_{idx};"))
+            .collect::<String>();
+        let calls = (0..num_stage)
+            .map(|idx| {
+                format!(
+                    "
+            if iszero(delegatecall(gas(), stage_{idx}, 0, input_len, 0, 0)) {{
+                returndatacopy(0, 0, returndatasize())
+                revert(0, returndatasize())
             }}
-            {}
+            returndatacopy(len, 0, returndatasize())
+            input_len := add(len, returndatasize())"
+                )
+            })
+            .collect::<String>();
+        format!(
+            "
+// SPDX-License-Identifier: MIT
+{SOL_CODE_FORMAT_VERSION_PREFIX}{FORMAT_VERSION}
+
+pragma solidity ^0.8.0;
+
+contract Halo2Verifier {{{immutables}
+
+    constructor({}) {{{assignments}
+    }}
+
+    fallback(bytes calldata) external returns (bytes memory) {{{locals}
+        assembly {{
+            let len := calldatasize()
+            calldatacopy(0, 0, len)
+            let input_len := len{calls}
+
+            // Return empty bytes on success
+            return(0, 0)
         }}
     }}
 }}
         ",
-            self.runtime
+            params.join(", ")
+        )
+    }
+
+    pub fn num_snippet(&self) -> usize {
+        self.boundaries.len()
+    }
+
+    pub fn immutable_g2_ptr(&self) -> Option<usize> {
+        self.immutable_g2_ptr
+    }
+
+    pub fn set_immutable_g2_ptr(&mut self, ptr: usize) {
+        self.immutable_g2_ptr = Some(ptr);
+    }
+
+    // Immutables of `g2` and `-s_g2` in layout of `ecPairing` input, and the
+    // constructor setting them.
+    fn immutables(base_modulus: &str) -> String {
+        format!(
+            "
+    uint256 private immutable G2_X_1;
+    uint256 private immutable G2_X_0;
+    uint256 private immutable G2_Y_1;
+    uint256 private immutable G2_Y_0;
+    uint256 private immutable NEG_S_G2_X_1;
+    uint256 private immutable NEG_S_G2_X_0;
+    uint256 private immutable NEG_S_G2_Y_1;
+    uint256 private immutable NEG_S_G2_Y_0;
+
+    constructor(uint256[4] memory g2, uint256[4] memory s_g2) {{
+        G2_X_1 = g2[0];
+        G2_X_0 = g2[1];
+        G2_Y_1 = g2[2];
+        G2_Y_0 = g2[3];
+        NEG_S_G2_X_1 = s_g2[0];
+        NEG_S_G2_X_0 = s_g2[1];
+        NEG_S_G2_Y_1 = ({base_modulus} - s_g2[2]) % {base_modulus};
+        NEG_S_G2_Y_0 = ({base_modulus} - s_g2[3]) % {base_modulus};
+    }}
+"
+        )
+    }
+
+    // Copy immutables into memory at `ptr`, since they can't be accessed in
+    // inline assembly.
+    fn immutables_prelude(ptr: usize) -> String {
+        let names = [
+            "G2_X_1",
+            "G2_X_0",
+            "G2_Y_1",
+            "G2_Y_0",
+            "NEG_S_G2_X_1",
+            "NEG_S_G2_X_0",
+            "NEG_S_G2_Y_1",
+            "NEG_S_G2_Y_0",
+        ];
+        let locals = names
+            .iter()
+            .map(|name| format!("\n            uint256 {} = {name};", name.to_lowercase()))
+            .collect::<String>();
+        let mstores = names
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| {
+                let ptr = ptr + idx * 0x20;
+                format!("\n                mstore({ptr:#x}, {})", name.to_lowercase())
+            })
+            .collect::<String>();
+        format!(
+            "
+        {{{locals}
+            assembly {{{mstores}
+            }}
+        }}"
+        )
+    }
+
+    pub fn vk_digest_offset(&self) -> Option<usize> {
+        self.vk_digest_offset
+    }
+
+    pub fn set_vk_digest_offset(&mut self, offset: usize) {
+        self.vk_digest_offset = Some(offset);
+    }
+
+    // Storage mapping of registered verifying key digests, and the constructor
+    // registering them.
+    fn vk_registry() -> String {
+        "
+    error UnregisteredVk(bytes32 digest);
+
+    mapping(bytes32 => bool) private VK_DIGESTS;
+
+    constructor(bytes32[] memory vk_digests) {
+        for (uint256 i = 0; i < vk_digests.length; i++) {
+            VK_DIGESTS[vk_digests[i]] = true;
+        }
+    }
+"
+        .to_string()
+    }
+
+    // Revert with `UnregisteredVk(digest)`, or return `uint256(0)` with
+    // `ReturnConvention::Bool`, if verifying key digest in calldata at `offset`
+    // is not registered, before running the verifier.
+    fn vk_registry_prelude(return_convention: ReturnConvention, offset: usize) -> String {
+        let exit = Self::exit_with_error(return_convention, UNREGISTERED_VK_ERROR, "vk_digest");
+        format!(
+            "
+        {{
+            bytes32 vk_digest;
+            assembly {{
+                vk_digest := calldataload({offset:#x})
+            }}
+            if (!VK_DIGESTS[vk_digest]) {{
+                assembly {{
+                    {exit}
+                }}
+            }}
+        }}"
         )
     }
 
     pub fn runtime_append(&mut self, mut code: String) {
+        self.boundaries.push((self.runtime.len(), self.memory_size.get()));
         code.push('\n');
         self.runtime.push_str(&code);
     }
@@ -4,10 +4,11 @@ use crate::{
             code::{Precompiled, SolidityAssemblyCode},
             fe_to_u256, modulus, u256_to_fe,
         },
-        EcPointLoader, LoadedEcPoint, LoadedScalar, Loader, ScalarLoader,
+        native::NativeLoader,
+        EcPointLoader, LimbsLoader, LoadedEcPoint, LoadedScalar, Loader, ScalarLoader,
     },
     util::{
-        arithmetic::{CurveAffine, FieldOps, PrimeField},
+        arithmetic::{powers, CurveAffine, FieldOps, PrimeField},
         Itertools,
     },
     Error,
@@ -15,7 +16,8 @@ use crate::{
 use ethereum_types::{U256, U512};
 use hex;
 use std::{
-    cell::RefCell,
+    array,
+    cell::{Cell, RefCell},
     collections::HashMap,
     fmt::{self, Debug},
     iter,
@@ -49,13 +51,190 @@ impl<T: Debug> Value<T> {
     }
 }
 
+/// Policy of validating elliptic curve points loaded from calldata by
+/// [`EvmLoader::calldataload_ec_point`] and [`EvmLoader::ec_point_from_limbs`].
+///
+/// For bn254 G1, which has cofactor 1, being on curve implies being in the
+/// prime order subgroup. Points not on curve or with non-canonical coordinates
+/// also make `ecAdd`, `ecMul` and `ecPairing` precompiles fail, but identity
+/// encoded as `(0, 0)` is accepted by them, and points only absorbed into
+/// transcript without being used in precompiles are never checked by them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EcPointCheck {
+    /// Check coordinates are canonical and the point is on curve, which also
+    /// rejects identity.
+    #[default]
+    Full,
+    /// Skip checks and rely on precompiles only, which saves gas for each
+    /// point but accepts identity. Use only when prover is trusted.
+    AssumeHonestProver,
+}
+
+/// Policy of validating instances loaded from calldata by
+/// [`EvmLoader::calldataload_instance`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InstanceCheck {
+    /// Revert with `InvalidInstance(uint256 index)` if any instance is not
+    /// less than scalar field modulus.
+    #[default]
+    Revert,
+    /// Skip the check to save gas for each instance. Non-canonical instances
+    /// are still absorbed into transcript as is, so they make the verification
+    /// fail, but only with a pairing failure at the end.
+    Skip,
+}
+
+/// Source of G2 points of deciding key used in the final pairing by
+/// [`EvmLoader::pairing`] and [`EvmLoader::pairing_with_immutable_g2`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum G2Source {
+    /// Inline G2 points as constants in runtime code, so the verifier is
+    /// bound to a single setup.
+    #[default]
+    Constant,
+    /// Read G2 points from immutables set by constructor arguments
+    /// `(uint256[4] g2, uint256[4] s_g2)`, so the same verifier code could be
+    /// deployed for different setups sharing the same G1 generator. Constructor
+    /// arguments could be encoded by [`KzgDecidingKey::evm_constructor_args`].
+    ///
+    /// [`KzgDecidingKey::evm_constructor_args`]: crate::pcs::kzg::KzgDecidingKey::evm_constructor_args
+    Immutable,
+}
+
+/// Code generation strategy of [`EvmLoader::fixed_base_msm`], which is used for
+/// MSM terms with constant bases, e.g. preprocessed commitments of verifying
+/// key. Bases are always embedded in code as immediates.
+///
+/// Precomputed multiples of bases are not offered, since replacing an `ecMul`
+/// (6000 gas) by `ecAdd`s (150 gas each) of windowed multiples only saves gas
+/// with windows of at least 7 bits, whose tables exceed the contract size
+/// limit for a single base.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FixedBaseMsm {
+    /// Write all terms into a table in memory and loop over it, which keeps
+    /// bytecode small.
+    #[default]
+    Loop,
+    /// Emit straight-line `ecMul` and `ecAdd` for each term, reusing a single
+    /// scratch space, which costs more bytecode but saves loop overhead and
+    /// memory expansion.
+    Unrolled,
+}
+
+/// Entry point of the generated verifier contract.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EntryPoint {
+    /// `fallback` taking raw calldata `instances || proof`, which returns
+    /// empty bytes on success.
+    #[default]
+    Fallback,
+    /// `verify_raw(bytes raw) returns (bool)` taking `instances || proof` as a
+    /// single opaque `bytes`, which is the interface expected by proof
+    /// marketplaces and verifier routers, and returns `true` on success.
+    /// Calldata could be encoded by [`encode_verify_raw_calldata`].
+    ///
+    /// [`encode_verify_raw_calldata`]: crate::loader::evm::encode_verify_raw_calldata
+    VerifyRaw,
+}
+
+impl EntryPoint {
+    /// Returns calldata offset where `instances || proof` begin, which should
+    /// be passed to [`EvmTranscriptBuilder::with_calldata_offset`].
+    ///
+    /// [`EvmTranscriptBuilder::with_calldata_offset`]: crate::system::halo2::transcript::evm::EvmTranscriptBuilder::with_calldata_offset
+    pub fn calldata_offset(&self) -> usize {
+        match self {
+            EntryPoint::Fallback => 0,
+            // Selector, offset and length of `raw`
+            EntryPoint::VerifyRaw => 0x44,
+        }
+    }
+}
+
+/// Convention of the generated verifier signaling result of verification.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReturnConvention {
+    /// Revert on failure with [`VERIFICATION_FAILURE_ERROR`] (or
+    /// [`INVALID_INSTANCE_ERROR`] with [`InstanceCheck::Revert`]), and return
+    /// as specified by [`EntryPoint`] on success.
+    #[default]
+    Revert,
+    /// Return `uint256(1)` on success and `uint256(0)` on failure without
+    /// reverting, which decodes as `bool`, for router contracts that require
+    /// verifiers not to revert, including for unregistered verifying keys
+    /// (see [`EvmLoader::calldataload_vk`]). It still reverts on running out
+    /// of gas.
+    Bool,
+}
+
+/// G2 point operand of [`EvmLoader::multi_pairing`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum G2Operand {
+    /// Constant G2 point in order of `ecPairing` input, which is
+    /// `(x.c1, x.c0, y.c1, y.c0)`.
+    Constant((U256, U256, U256, U256)),
+    /// G2 point encoded in the same order as [`G2Operand::Constant`] in 128
+    /// bytes of calldata at given offset.
+    Calldata(usize),
+}
+
+/// Consistency check of instances against an EIP-4844 blob by the
+/// point-evaluation precompile, for rollups whose data lives in blobs and
+/// whose circuit proves the opening `p(z) = y` of the blob polynomial `p`.
+///
+/// Since `z` and `y` are BLS12-381 scalars, the circuit should expose each of
+/// them as 2 instances `(hi, lo)` of 128 bits. The blob commitment and KZG
+/// proof (48 bytes each) are appended to calldata after the proof, and the
+/// versioned hash is read by `BLOBHASH`, so the generated verifier requires
+/// `solc` 0.8.24+ with EVM version `cancun` (see [`SolcConfig`]).
+///
+/// [`SolcConfig`]: crate::loader::evm::SolcConfig
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlobCheck {
+    /// Index of the blob in the transaction.
+    pub blob_index: usize,
+    /// Flattened instance indices of `(hi, lo)` of evaluation point `z`.
+    pub z: [usize; 2],
+    /// Flattened instance indices of `(hi, lo)` of evaluation `y`.
+    pub y: [usize; 2],
+}
+
+/// Signature of the function of [`EntryPoint::VerifyRaw`].
+pub const VERIFY_RAW_SIGNATURE: &str = "verify_raw(bytes)";
+
+/// Signature of custom error reverted with when verification fails, whose
+/// `code` is the annotation of the first failed checkpoint (see
+/// [`EvmLoader::checkpoint`]) or assertion (e.g. [`ScalarLoader::assert_eq`])
+/// left-aligned in `bytes32` and truncated to its first 32 bytes, or zero if it
+/// fails after the last checkpoint. It could be decoded by [`decode_verification_failure`].
+///
+/// [`decode_verification_failure`]: crate::loader::evm::decode_verification_failure
+pub const VERIFICATION_FAILURE_ERROR: &str = "VerificationFailure(bytes32)";
+
+/// Signature of custom error reverted with by [`InstanceCheck::Revert`].
+pub const INVALID_INSTANCE_ERROR: &str = "InvalidInstance(uint256)";
+
+/// Signature of custom error reverted with when verifying key digest in
+/// calldata is not registered, see [`EvmLoader::calldataload_vk`].
+pub const UNREGISTERED_VK_ERROR: &str = "UnregisteredVk(bytes32)";
+
+/// Default limit of memory layout of [`EvmLoader`] in bytes, beyond which
+/// memory expansion alone costs more than 30M gas.
+pub const DEFAULT_MEMORY_LIMIT: usize = 0x400000;
+
 /// `Loader` implementation for generating yul code as EVM verifier.
 #[derive(Clone, Debug)]
 pub struct EvmLoader {
     base_modulus: U256,
     scalar_modulus: U256,
+    ec_point_check: EcPointCheck,
+    g2_source: G2Source,
+    fixed_base_msm: FixedBaseMsm,
+    entry_point: EntryPoint,
+    return_convention: ReturnConvention,
+    memory_limit: usize,
     code: RefCell<SolidityAssemblyCode>,
-    ptr: RefCell<usize>,
+    ptr: Rc<Cell<usize>>,
     cache: RefCell<HashMap<String, usize>>,
     #[cfg(test)]
     gas_metering_ids: RefCell<Vec<String>>,
@@ -67,6 +246,91 @@ fn hex_encode_u256(value: &U256) -> String {
     format!("0x{}", hex::encode(bytes))
 }
 
+// Returns `annotation` left-aligned in a word as code of failure, truncated to
+// its first 32 bytes if longer.
+fn failure_code(annotation: &str) -> String {
+    let annotation = &annotation.as_bytes()[..annotation.len().min(0x20)];
+    let mut bytes = [0; 0x20];
+    bytes[..annotation.len()].copy_from_slice(annotation);
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Builder of [`EvmLoader`] with non-default codegen policies.
+#[derive(Clone, Debug)]
+pub struct EvmLoaderBuilder {
+    base_modulus: U256,
+    scalar_modulus: U256,
+    ec_point_check: EcPointCheck,
+    g2_source: G2Source,
+    fixed_base_msm: FixedBaseMsm,
+    entry_point: EntryPoint,
+    return_convention: ReturnConvention,
+    memory_limit: usize,
+}
+
+impl EvmLoaderBuilder {
+    /// Set policy of validating elliptic curve points loaded from calldata.
+    pub fn with_ec_point_check(mut self, ec_point_check: EcPointCheck) -> Self {
+        self.ec_point_check = ec_point_check;
+        self
+    }
+
+    /// Set source of G2 points of deciding key.
+    pub fn with_g2_source(mut self, g2_source: G2Source) -> Self {
+        self.g2_source = g2_source;
+        self
+    }
+
+    /// Set code generation strategy of MSM with constant bases.
+    pub fn with_fixed_base_msm(mut self, fixed_base_msm: FixedBaseMsm) -> Self {
+        self.fixed_base_msm = fixed_base_msm;
+        self
+    }
+
+    /// Set entry point of the generated contract.
+    pub fn with_entry_point(mut self, entry_point: EntryPoint) -> Self {
+        self.entry_point = entry_point;
+        self
+    }
+
+    /// Set convention of signaling result of verification.
+    pub fn with_return_convention(mut self, return_convention: ReturnConvention) -> Self {
+        self.return_convention = return_convention;
+        self
+    }
+
+    /// Set limit of memory layout in bytes, which defaults to
+    /// [`DEFAULT_MEMORY_LIMIT`]. Code generation panics once exceeding it.
+    pub fn with_memory_limit(mut self, memory_limit: usize) -> Self {
+        self.memory_limit = memory_limit;
+        self
+    }
+
+    /// Build the [`EvmLoader`].
+    pub fn build(self) -> Rc<EvmLoader> {
+        let ptr = Rc::new(Cell::new(0));
+        Rc::new(EvmLoader {
+            base_modulus: self.base_modulus,
+            scalar_modulus: self.scalar_modulus,
+            ec_point_check: self.ec_point_check,
+            g2_source: self.g2_source,
+            fixed_base_msm: self.fixed_base_msm,
+            entry_point: self.entry_point,
+            return_convention: self.return_convention,
+            memory_limit: self.memory_limit,
+            code: RefCell::new(SolidityAssemblyCode::new(
+                ptr.clone(),
+                self.entry_point,
+                self.return_convention,
+            )),
+            ptr,
+            cache: Default::default(),
+            #[cfg(test)]
+            gas_metering_ids: RefCell::new(Vec::new()),
+        })
+    }
+}
+
 impl EvmLoader {
     /// Initialize a [`EvmLoader`] with base and scalar field.
     pub fn new<Base, Scalar>() -> Rc<Self>
@@ -74,46 +338,132 @@ impl EvmLoader {
         Base: PrimeField<Repr = [u8; 0x20]>,
         Scalar: PrimeField<Repr = [u8; 32]>,
     {
-        let base_modulus = modulus::<Base>();
-        let scalar_modulus = modulus::<Scalar>();
-        let code = SolidityAssemblyCode::new();
-
-        Rc::new(Self {
-            base_modulus,
-            scalar_modulus,
-            code: RefCell::new(code),
-            ptr: Default::default(),
-            cache: Default::default(),
-            #[cfg(test)]
-            gas_metering_ids: RefCell::new(Vec::new()),
-        })
+        Self::builder::<Base, Scalar>().build()
+    }
+
+    /// Initialize a [`EvmLoader`] with base and scalar field, and policy of
+    /// validating elliptic curve points loaded from calldata.
+    pub fn new_with_ec_point_check<Base, Scalar>(ec_point_check: EcPointCheck) -> Rc<Self>
+    where
+        Base: PrimeField<Repr = [u8; 0x20]>,
+        Scalar: PrimeField<Repr = [u8; 32]>,
+    {
+        Self::builder::<Base, Scalar>().with_ec_point_check(ec_point_check).build()
+    }
+
+    /// Returns [`EvmLoaderBuilder`] with base and scalar field and default
+    /// policies.
+    pub fn builder<Base, Scalar>() -> EvmLoaderBuilder
+    where
+        Base: PrimeField<Repr = [u8; 0x20]>,
+        Scalar: PrimeField<Repr = [u8; 32]>,
+    {
+        EvmLoaderBuilder {
+            base_modulus: modulus::<Base>(),
+            scalar_modulus: modulus::<Scalar>(),
+            ec_point_check: EcPointCheck::default(),
+            g2_source: G2Source::default(),
+            fixed_base_msm: FixedBaseMsm::default(),
+            entry_point: EntryPoint::default(),
+            return_convention: ReturnConvention::default(),
+            memory_limit: DEFAULT_MEMORY_LIMIT,
+        }
+    }
+
+    /// Returns source of G2 points of deciding key.
+    pub fn g2_source(&self) -> G2Source {
+        self.g2_source
+    }
+
+    /// Returns code generation strategy of MSM with constant bases.
+    pub fn fixed_base_msm_strategy(&self) -> FixedBaseMsm {
+        self.fixed_base_msm
+    }
+
+    /// Returns entry point of the generated contract.
+    pub fn entry_point(&self) -> EntryPoint {
+        self.entry_point
+    }
+
+    /// Returns convention of signaling result of verification.
+    pub fn return_convention(&self) -> ReturnConvention {
+        self.return_convention
     }
 
     /// Returns generated Solidity code. This is "Solidity" code that is wrapped in an assembly block.
     /// In other words, it's basically just assembly (equivalently, Yul).
     pub fn solidity_code(self: &Rc<Self>) -> String {
-        let code = "
-            // Revert if anything fails
-            if iszero(success) { revert(0, 0) }
-
+        let ret = match (self.entry_point, self.return_convention) {
+            (EntryPoint::Fallback, ReturnConvention::Revert) => {
+                "
             // Return empty bytes on success
             return(0, 0)"
-            .to_string();
+            }
+            (EntryPoint::VerifyRaw, _) | (_, ReturnConvention::Bool) => {
+                "
+            // Return true on success
+            mstore(0, 1)
+            return(0, 0x20)"
+            }
+        };
+        let code =
+            format!("{}\n{ret}", SolidityAssemblyCode::exit_on_failure(self.return_convention));
         self.code.borrow_mut().runtime_append(code);
         self.code
             .borrow()
             .code(hex_encode_u256(&self.base_modulus), hex_encode_u256(&self.scalar_modulus))
     }
 
+    /// Returns generated Solidity code split into `num_stage` stage contracts,
+    /// and code of dispatcher delegatecalling them in sequence, which should be
+    /// deployed with addresses of deployed stages as constructor arguments.
+    /// It's called instead of [`EvmLoader::solidity_code`] for verifier whose
+    /// bytecode exceeds contract size limit, see
+    /// [`compile_split_verifier`] for choosing `num_stage` automatically.
+    ///
+    /// Returns [`Error::InvalidConfig`] if `num_stage` is zero or more than
+    /// number of code snippets, or if [`G2Source::Immutable`], verifying key
+    /// registry, [`EntryPoint::VerifyRaw`] or [`ReturnConvention::Bool`] is
+    /// used.
+    ///
+    /// [`compile_split_verifier`]: crate::loader::evm::compile_split_verifier
+    pub fn split_solidity_code(&self, num_stage: usize) -> Result<(String, Vec<String>), Error> {
+        self.code.borrow().split(
+            hex_encode_u256(&self.base_modulus),
+            hex_encode_u256(&self.scalar_modulus),
+            num_stage,
+        )
+    }
+
     /// Allocates memory chunk with given `size` and returns pointer.
+    ///
+    /// Memory is laid out by bumping a pointer and never reused, so chunks
+    /// never overlap as long as generated code only accesses allocated ones.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the layout exceeds the limit set by
+    /// [`EvmLoaderBuilder::with_memory_limit`].
     pub fn allocate(self: &Rc<Self>, size: usize) -> usize {
-        let ptr = *self.ptr.borrow();
-        *self.ptr.borrow_mut() += size;
+        let ptr = self.ptr.get();
+        match ptr.checked_add(size).filter(|end| *end <= self.memory_limit) {
+            Some(end) => self.ptr.set(end),
+            None => panic!(
+                "Memory layout of EVM verifier exceeds limit {:#x} when allocating {size:#x} \
+                 bytes at {ptr:#x}",
+                self.memory_limit
+            ),
+        }
         ptr
     }
 
+    /// Returns size of memory laid out so far in bytes.
+    pub fn memory_size(&self) -> usize {
+        self.ptr.get()
+    }
+
     pub(crate) fn ptr(&self) -> usize {
-        *self.ptr.borrow()
+        self.ptr.get()
     }
 
     pub(crate) fn code_mut(&self) -> impl DerefMut<Target = SolidityAssemblyCode> + '_ {
@@ -145,16 +495,69 @@ impl EvmLoader {
         }
     }
 
-    /// Calldata load a field element.
+    /// Calldata load a field element and validate it's canonical, instead of
+    /// reducing it, so each field element has exactly one valid encoding.
     pub fn calldataload_scalar(self: &Rc<Self>, offset: usize) -> Scalar {
         let ptr = self.allocate(0x20);
-        let code = format!("mstore({ptr:#x}, mod(calldataload({offset:#x}), f_q))");
+        self.calldataload_scalar_into(offset, ptr)
+    }
+
+    /// Calldata load a field element into memory at `ptr`, which must be
+    /// already allocated, and validate it's canonical.
+    pub(crate) fn calldataload_scalar_into(self: &Rc<Self>, offset: usize, ptr: usize) -> Scalar {
+        let code = format!(
+            "
+        {{
+            let scalar := calldataload({offset:#x})
+            mstore({ptr:#x}, scalar)
+            success := and(lt(scalar, f_q), success)
+        }}"
+        );
         self.code.borrow_mut().runtime_append(code);
         self.scalar(Value::Memory(ptr))
     }
 
-    /// Calldata load an elliptic curve point and validate it's on affine plane.
-    /// Note that identity will cause the verification to fail.
+    /// Calldata load the `idx`-th instance, and validate it's canonical
+    /// according to [`InstanceCheck`]. With [`InstanceCheck::Revert`], it
+    /// reverts immediately with [`INVALID_INSTANCE_ERROR`] and `idx`, or
+    /// returns `uint256(0)` with [`ReturnConvention::Bool`].
+    pub fn calldataload_instance(
+        self: &Rc<Self>,
+        offset: usize,
+        idx: usize,
+        instance_check: InstanceCheck,
+    ) -> Scalar {
+        let ptr = self.allocate(0x20);
+        let check_code = match instance_check {
+            InstanceCheck::Revert => {
+                let exit = SolidityAssemblyCode::exit_with_error(
+                    self.return_convention,
+                    INVALID_INSTANCE_ERROR,
+                    &idx.to_string(),
+                );
+                format!(
+                    "if iszero(lt(scalar, f_q)) {{
+                {exit}
+            }}"
+                )
+            }
+            InstanceCheck::Skip => String::new(),
+        };
+        let code = format!(
+            "
+        {{
+            let scalar := calldataload({offset:#x})
+            {check_code}
+            mstore({ptr:#x}, scalar)
+        }}"
+        );
+        self.code.borrow_mut().runtime_append(code);
+        self.scalar(Value::Memory(ptr))
+    }
+
+    /// Calldata load an elliptic curve point and validate it's on affine plane
+    /// according to [`EcPointCheck`]. Note that identity will cause the
+    /// verification to fail with [`EcPointCheck::Full`].
     pub fn calldataload_ec_point(self: &Rc<Self>, offset: usize) -> EcPoint {
         let x_ptr = self.allocate(0x40);
         let y_ptr = x_ptr + 0x20;
@@ -175,6 +578,43 @@ impl EvmLoader {
         self.ec_point(Value::Memory(x_ptr))
     }
 
+    /// Calldata load verifying key of `num_ec_point` elliptic curve points
+    /// followed by `num_scalar` field elements, which is right after its
+    /// keccak256 digest at `offset`, and check the digest matches.
+    ///
+    /// The generated contract is deployed with digests of verifying keys to
+    /// accept as constructor argument `bytes32[] vk_digests`, and reverts with
+    /// [`UNREGISTERED_VK_ERROR`] (or returns `uint256(0)` with
+    /// [`ReturnConvention::Bool`]) if the digest is not one of them, so loaded
+    /// values are trusted and not validated. Cannot be used together with
+    /// [`G2Source::Immutable`] or splitting.
+    pub fn calldataload_vk(
+        self: &Rc<Self>,
+        offset: usize,
+        num_ec_point: usize,
+        num_scalar: usize,
+    ) -> (Vec<EcPoint>, Vec<Scalar>) {
+        assert!(self.code.borrow().vk_digest_offset().is_none());
+        self.code.borrow_mut().set_vk_digest_offset(offset);
+
+        let len = num_ec_point * 0x40 + num_scalar * 0x20;
+        let ptr = self.allocate(len);
+        let cd_ptr = offset + 0x20;
+        let code = format!(
+            "
+        calldatacopy({ptr:#x}, {cd_ptr:#x}, {len:#x})
+        success := and(eq(keccak256({ptr:#x}, {len:#x}), calldataload({offset:#x})), success)"
+        );
+        self.code.borrow_mut().runtime_append(code);
+
+        let ec_points =
+            (0..num_ec_point).map(|idx| self.ec_point(Value::Memory(ptr + idx * 0x40))).collect();
+        let scalars = (0..num_scalar)
+            .map(|idx| self.scalar(Value::Memory(ptr + num_ec_point * 0x40 + idx * 0x20)))
+            .collect();
+        (ec_points, scalars)
+    }
+
     /// Decode an elliptic curve point from limbs.
     pub fn ec_point_from_limbs<const LIMBS: usize, const BITS: usize>(
         self: &Rc<Self>,
@@ -213,11 +653,49 @@ impl EvmLoader {
         }}"
         );
         self.code.borrow_mut().runtime_append(code);
+        self.checkpoint("accumulator");
         self.ec_point(Value::Memory(ptr))
     }
 
+    /// Records `annotation` as the code of failure if any check fails since
+    /// the previous checkpoint, which is reverted with
+    /// [`VERIFICATION_FAILURE_ERROR`] at the end, so failed verification is
+    /// diagnosable from transaction trace. Annotation longer than 32 bytes is
+    /// truncated.
+    pub fn checkpoint(self: &Rc<Self>, annotation: &str) {
+        let failure_code = failure_code(annotation);
+        let code =
+            format!("if and(iszero(success), iszero(failure)) {{ failure := {failure_code} }}");
+        self.code.borrow_mut().runtime_append(code);
+    }
+
+    /// Reverts immediately with [`VERIFICATION_FAILURE_ERROR`] and
+    /// `annotation` if `condition` is false, or returns `uint256(0)` with
+    /// [`ReturnConvention::Bool`].
+    fn assert(self: &Rc<Self>, annotation: &str, condition: String) {
+        let exit = SolidityAssemblyCode::exit_with_error(
+            self.return_convention,
+            VERIFICATION_FAILURE_ERROR,
+            &failure_code(annotation),
+        );
+        let code = format!(
+            "if iszero({condition}) {{
+                {exit}
+            }}"
+        );
+        self.code.borrow_mut().runtime_append(code);
+    }
+
+    /// Returns policy of validating elliptic curve points.
+    pub fn ec_point_check(&self) -> EcPointCheck {
+        self.ec_point_check
+    }
+
     fn validate_ec_point(self: &Rc<Self>) -> String {
-        "success := and(validate_ec_point(x, y), success)".to_string()
+        match self.ec_point_check {
+            EcPointCheck::Full => "success := and(validate_ec_point(x, y), success)".to_string(),
+            EcPointCheck::AssumeHonestProver => String::new(),
+        }
     }
 
     pub(crate) fn scalar(self: &Rc<Self>, value: Value<U256>) -> Scalar {
@@ -301,8 +779,16 @@ impl EvmLoader {
             Precompiled::Bn254Add => (0x80, 0x40),
             Precompiled::Bn254ScalarMul => (0x60, 0x40),
             Precompiled::Bn254Pairing => (0x180, 0x20),
+            Precompiled::PointEvaluation => (0xc0, 0x40),
         };
         let a = precompile as usize;
+        // Precompile reading or writing out of allocated memory would
+        // silently clobber chunks allocated later.
+        assert!(
+            cd_ptr + cd_len <= self.ptr() && rd_ptr + rd_len <= self.ptr(),
+            "Precompile {a:#x} accesses memory out of layout of size {:#x}",
+            self.ptr()
+        );
         let code = format!("success := and(eq(staticcall(gas(), {a:#x}, {cd_ptr:#x}, {cd_len:#x}, {rd_ptr:#x}, {rd_len:#x}), 1), success)");
         self.code.borrow_mut().runtime_append(code);
     }
@@ -335,6 +821,91 @@ impl EvmLoader {
         self.staticcall(Precompiled::Bn254ScalarMul, rd_ptr, rd_ptr);
         self.ec_point(Value::Memory(rd_ptr))
     }
+
+    /// Performs multi-scalar multiplication with constant bases (e.g.
+    /// commitments in verifying key) with bases embedded in code, either as a
+    /// loop over a table of `(x, y, scalar)` in memory or unrolled into a
+    /// single scratch space, depending on [`FixedBaseMsm`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pairs` is empty or any base is not constant.
+    pub fn fixed_base_msm(self: &Rc<Self>, pairs: &[(&Scalar, &EcPoint)]) -> EcPoint {
+        assert!(!pairs.is_empty());
+
+        let coordinates = |ec_point: &EcPoint| match ec_point.value {
+            Value::Constant((x, y)) => (hex_encode_u256(&x), hex_encode_u256(&y)),
+            _ => panic!("Base of fixed-base MSM should be constant"),
+        };
+        let [ec_add, ec_mul] = [Precompiled::Bn254Add, Precompiled::Bn254ScalarMul]
+            .map(|precompile| precompile as usize);
+
+        match self.fixed_base_msm {
+            FixedBaseMsm::Loop => {
+                let table_ptr = self.allocate(pairs.len() * 0x60);
+                for (idx, (scalar, ec_point)) in pairs.iter().enumerate() {
+                    let x_ptr = table_ptr + idx * 0x60;
+                    let y_ptr = x_ptr + 0x20;
+                    let scalar_ptr = x_ptr + 0x40;
+                    let (x, y) = coordinates(ec_point);
+                    let scalar = self.push(scalar);
+                    let code = format!(
+                        "mstore({x_ptr:#x}, {x})
+                        mstore({y_ptr:#x}, {y})
+                        mstore({scalar_ptr:#x}, {scalar})"
+                    );
+                    self.code.borrow_mut().runtime_append(code);
+                }
+
+                // Accumulator followed by scratch space of each term, so they
+                // can be added in place by `ecAdd`.
+                let rd_ptr = self.allocate(0x80);
+                let term_ptr = rd_ptr + 0x40;
+                let table_end = table_ptr + pairs.len() * 0x60;
+                let code = format!(
+                    "
+        {{
+            success := and(eq(staticcall(gas(), {ec_mul:#x}, {table_ptr:#x}, 0x60, {rd_ptr:#x}, 0x40), 1), success)
+            for {{ let ptr := {:#x} }} lt(ptr, {table_end:#x}) {{ ptr := add(ptr, 0x60) }} {{
+                success := and(eq(staticcall(gas(), {ec_mul:#x}, ptr, 0x60, {term_ptr:#x}, 0x40), 1), success)
+                success := and(eq(staticcall(gas(), {ec_add:#x}, {rd_ptr:#x}, 0x80, {rd_ptr:#x}, 0x40), 1), success)
+            }}
+        }}",
+                    table_ptr + 0x60
+                );
+                self.code.borrow_mut().runtime_append(code);
+                self.ec_point(Value::Memory(rd_ptr))
+            }
+            FixedBaseMsm::Unrolled => {
+                // Accumulator followed by scratch space of a term, where base
+                // and scalar are written before `ecMul` and product is added
+                // into accumulator in place by `ecAdd`.
+                let rd_ptr = self.allocate(0xa0);
+                let term_ptr = rd_ptr + 0x40;
+                for (idx, (scalar, ec_point)) in pairs.iter().enumerate() {
+                    let (x, y) = coordinates(ec_point);
+                    let scalar = self.push(scalar);
+                    let (y_ptr, scalar_ptr) = (term_ptr + 0x20, term_ptr + 0x40);
+                    let output_ptr = if idx == 0 { rd_ptr } else { term_ptr };
+                    let mut code = format!(
+                        "mstore({term_ptr:#x}, {x})
+                        mstore({y_ptr:#x}, {y})
+                        mstore({scalar_ptr:#x}, {scalar})
+                        success := and(eq(staticcall(gas(), {ec_mul:#x}, {term_ptr:#x}, 0x60, {output_ptr:#x}, 0x40), 1), success)"
+                    );
+                    if idx != 0 {
+                        code.push_str(&format!(
+                            "
+                        success := and(eq(staticcall(gas(), {ec_add:#x}, {rd_ptr:#x}, 0x80, {rd_ptr:#x}, 0x40), 1), success)"
+                        ));
+                    }
+                    self.code.borrow_mut().runtime_append(code);
+                }
+                self.ec_point(Value::Memory(rd_ptr))
+            }
+        }
+    }
+
     /// Performs pairing.
     pub fn pairing(
         self: &Rc<Self>,
@@ -380,6 +951,129 @@ impl EvmLoader {
         self.staticcall(Precompiled::Bn254Pairing, rd_ptr, rd_ptr);
         let code = format!("success := and(eq(mload({rd_ptr:#x}), 1), success)");
         self.code.borrow_mut().runtime_append(code);
+        self.checkpoint("pairing");
+    }
+
+    /// Performs point evaluation of the `blob_index`-th blob at `z` to be `y`,
+    /// where `z` and `y` are given in limbs `(hi, lo)` of 128 bits, and the
+    /// blob commitment and KZG proof are read from calldata at
+    /// `calldata_offset`.
+    pub fn point_evaluation(
+        self: &Rc<Self>,
+        blob_index: usize,
+        z: [&Scalar; 2],
+        y: [&Scalar; 2],
+        calldata_offset: usize,
+    ) {
+        let ptr = self.allocate(0xc0);
+        let [z_hi, z_lo, y_hi, y_lo] = [z[0], z[1], y[0], y[1]].map(|limb| self.push(limb));
+        let code = format!(
+            "
+        {{
+            let z_hi := {z_hi}
+            let z_lo := {z_lo}
+            let y_hi := {y_hi}
+            let y_lo := {y_lo}
+            success := and(iszero(shr(128, or(or(z_hi, z_lo), or(y_hi, y_lo)))), success)
+            mstore({ptr:#x}, blobhash({blob_index}))
+            mstore({:#x}, or(shl(128, z_hi), z_lo))
+            mstore({:#x}, or(shl(128, y_hi), y_lo))
+            calldatacopy({:#x}, {calldata_offset:#x}, 0x60)
+        }}",
+            ptr + 0x20,
+            ptr + 0x40,
+            ptr + 0x60,
+        );
+        self.code.borrow_mut().runtime_append(code);
+        self.staticcall(Precompiled::PointEvaluation, ptr, ptr);
+    }
+
+    /// Performs pairing check `e(lhs, g2) * e(rhs, -s_g2) == 1` with `g2` and
+    /// `s_g2` read from immutables set in constructor, which is only available
+    /// with [`G2Source::Immutable`].
+    pub fn pairing_with_immutable_g2(self: &Rc<Self>, lhs: &EcPoint, rhs: &EcPoint) {
+        assert_eq!(self.g2_source, G2Source::Immutable);
+
+        let g2_ptr = self.immutable_g2_ptr();
+        let rd_ptr = self.dup_ec_point(lhs).ptr();
+        self.allocate(0x80);
+        self.dup_ec_point(rhs);
+        self.allocate(0x80);
+        let code = [(g2_ptr, rd_ptr + 0x40), (g2_ptr + 0x80, rd_ptr + 0x100)]
+            .into_iter()
+            .flat_map(|(src_ptr, dst_ptr)| {
+                (0..4).map(move |idx| {
+                    let (src_ptr, dst_ptr) = (src_ptr + idx * 0x20, dst_ptr + idx * 0x20);
+                    format!("mstore({dst_ptr:#x}, mload({src_ptr:#x}))")
+                })
+            })
+            .join("\n            ");
+        self.code.borrow_mut().runtime_append(code);
+        self.staticcall(Precompiled::Bn254Pairing, rd_ptr, rd_ptr);
+        let code = format!("success := and(eq(mload({rd_ptr:#x}), 1), success)");
+        self.code.borrow_mut().runtime_append(code);
+        self.checkpoint("pairing");
+    }
+
+    /// Performs pairing check `e(g1_0, g2_0) * ... * e(g1_n, g2_n) == 1` with
+    /// arbitrary number of terms.
+    pub fn multi_pairing(self: &Rc<Self>, terms: &[(&EcPoint, G2Operand)]) {
+        assert!(!terms.is_empty());
+
+        let ptr = self.allocate(0xc0 * terms.len());
+        let code = terms
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, (g1, g2))| {
+                let (x_ptr, y_ptr) = (ptr + idx * 0xc0, ptr + idx * 0xc0 + 0x20);
+                let g2_ptr = x_ptr + 0x40;
+                let [g1_x, g1_y] = match g1.value {
+                    Value::Constant((x, y)) => [x, y].map(|v| hex_encode_u256(&v)),
+                    Value::Memory(src_ptr) => {
+                        [src_ptr, src_ptr + 0x20].map(|src_ptr| format!("mload({src_ptr:#x})"))
+                    }
+                    Value::Negated(_) | Value::Sum(_, _) | Value::Product(_, _) => {
+                        unreachable!()
+                    }
+                };
+                let g2 = match g2 {
+                    G2Operand::Constant(g2) => [g2.0, g2.1, g2.2, g2.3]
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, v)| {
+                            format!("mstore({:#x}, {})", g2_ptr + idx * 0x20, hex_encode_u256(v))
+                        })
+                        .collect_vec(),
+                    G2Operand::Calldata(offset) => {
+                        vec![format!("calldatacopy({g2_ptr:#x}, {offset:#x}, 0x80)")]
+                    }
+                };
+                [format!("mstore({x_ptr:#x}, {g1_x})"), format!("mstore({y_ptr:#x}, {g1_y})")]
+                    .into_iter()
+                    .chain(g2)
+            })
+            .join("\n            ");
+        self.code.borrow_mut().runtime_append(code);
+
+        let cd_len = 0xc0 * terms.len();
+        let a = Precompiled::Bn254Pairing as usize;
+        let code = format!(
+            "success := and(eq(staticcall(gas(), {a:#x}, {ptr:#x}, {cd_len:#x}, {ptr:#x}, 0x20), 1), success)
+            success := and(eq(mload({ptr:#x}), 1), success)"
+        );
+        self.code.borrow_mut().runtime_append(code);
+        self.checkpoint("pairing");
+    }
+
+    /// Returns pointer of `g2` and `-s_g2` copied from immutables, which are
+    /// allocated once and written before runtime code.
+    fn immutable_g2_ptr(self: &Rc<Self>) -> usize {
+        if let Some(ptr) = self.code.borrow().immutable_g2_ptr() {
+            return ptr;
+        }
+        let ptr = self.allocate(0x100);
+        self.code.borrow_mut().set_immutable_g2_ptr(ptr);
+        ptr
     }
 
     fn add(self: &Rc<Self>, lhs: &Scalar, rhs: &Scalar) -> Scalar {
@@ -647,20 +1341,47 @@ where
         self.ec_point(Value::Constant((x, y)))
     }
 
-    fn ec_point_assert_eq(&self, _: &str, _: &EcPoint, _: &EcPoint) -> Result<(), Error> {
-        unimplemented!()
+    fn ec_point_assert_eq(
+        &self,
+        annotation: &str,
+        lhs: &EcPoint,
+        rhs: &EcPoint,
+    ) -> Result<(), Error> {
+        let coordinates = |ec_point: &EcPoint| match ec_point.value {
+            Value::Constant((x, y)) => [x, y].map(|v| hex_encode_u256(&v)),
+            Value::Memory(ptr) => [ptr, ptr + 0x20].map(|ptr| format!("mload({ptr:#x})")),
+            Value::Negated(_) | Value::Sum(_, _) | Value::Product(_, _) => unreachable!(),
+        };
+        let ([lhs_x, lhs_y], [rhs_x, rhs_y]) = (coordinates(lhs), coordinates(rhs));
+        self.assert(annotation, format!("and(eq({lhs_x}, {rhs_x}), eq({lhs_y}, {rhs_y}))"));
+        Ok(())
     }
 
     fn multi_scalar_multiplication(
         pairs: &[(&<Self as ScalarLoader<C::Scalar>>::LoadedScalar, &EcPoint)],
     ) -> EcPoint {
-        pairs
-            .iter()
-            .cloned()
-            .map(|(scalar, ec_point)| match scalar.value {
-                Value::Constant(constant) if U256::one() == constant => ec_point.clone(),
-                _ => ec_point.loader.ec_point_scalar_mul(ec_point, scalar),
-            })
+        let is_one = |scalar: &Scalar| matches!(scalar.value, Value::Constant(constant) if U256::one() == constant);
+
+        // Group terms with constant base and non-trivial scalar into a
+        // fixed-base MSM if there are more than one of them.
+        let (fixed_base, rest): (Vec<_>, Vec<_>) =
+            pairs.iter().cloned().partition(|(scalar, ec_point)| {
+                matches!(ec_point.value, Value::Constant(_)) && !is_one(*scalar)
+            });
+        let (fixed_base, rest) =
+            if fixed_base.len() > 1 { (fixed_base, rest) } else { (Vec::new(), pairs.to_vec()) };
+
+        iter::empty()
+            .chain(
+                fixed_base.first().map(|(_, ec_point)| ec_point.loader.fixed_base_msm(&fixed_base)),
+            )
+            .chain(rest.into_iter().map(|(scalar, ec_point)| {
+                if is_one(scalar) {
+                    ec_point.clone()
+                } else {
+                    ec_point.loader.ec_point_scalar_mul(ec_point, scalar)
+                }
+            }))
             .reduce(|acc, ec_point| acc.loader.ec_point_add(&acc, &ec_point))
             .unwrap()
     }
@@ -673,8 +1394,10 @@ impl<F: PrimeField<Repr = [u8; 0x20]>> ScalarLoader<F> for Rc<EvmLoader> {
         self.scalar(Value::Constant(fe_to_u256(*value)))
     }
 
-    fn assert_eq(&self, _: &str, _: &Scalar, _: &Scalar) -> Result<(), Error> {
-        unimplemented!()
+    fn assert_eq(&self, annotation: &str, lhs: &Scalar, rhs: &Scalar) -> Result<(), Error> {
+        let (lhs, rhs) = (self.push(lhs), self.push(rhs));
+        self.assert(annotation, format!("eq({lhs}, {rhs})"));
+        Ok(())
     }
 
     fn sum_with_coeff_and_const(&self, values: &[(F, &Scalar)], constant: F) -> Scalar {
@@ -866,6 +1589,75 @@ impl<F: PrimeField<Repr = [u8; 0x20]>> ScalarLoader<F> for Rc<EvmLoader> {
     }
 }
 
+impl<F: PrimeField<Repr = [u8; 0x20]>> LimbsLoader<F> for Rc<EvmLoader> {
+    fn scalar_to_limbs<const LIMBS: usize, const BITS: usize>(
+        &self,
+        scalar: &Scalar,
+    ) -> Result<[Scalar; LIMBS], Error> {
+        if let Value::Constant(constant) = scalar.value {
+            let limbs = NativeLoader
+                .scalar_to_limbs::<LIMBS, BITS>(&u256_to_fe::<F>(constant))?
+                .map(|limb| self.load_const(&limb));
+            return Ok(limbs);
+        }
+
+        let scalar = self.push(scalar);
+        let ptr = self.allocate(0x20 * LIMBS);
+        let mask = hex_encode_u256(&((U256::one() << BITS) - 1));
+        let mut code = format!("let scalar := {scalar}\n");
+        for idx in 0..LIMBS {
+            let limb_ptr = ptr + 0x20 * idx;
+            let shift = idx * BITS;
+            code.push_str(&format!("mstore({limb_ptr:#x}, and(shr({shift}, scalar), {mask}))\n"));
+        }
+        if LIMBS * BITS < 256 {
+            let bits = LIMBS * BITS;
+            code.push_str(&format!("success := and(iszero(shr({bits}, scalar)), success)\n"));
+        }
+        self.code.borrow_mut().runtime_append(format!(
+            "{{
+            {code}
+        }}"
+        ));
+
+        Ok(array::from_fn(|idx| self.scalar(Value::Memory(ptr + 0x20 * idx))))
+    }
+
+    fn scalar_from_limbs<const LIMBS: usize, const BITS: usize>(
+        &self,
+        limbs: [&Scalar; LIMBS],
+    ) -> Result<Scalar, Error> {
+        let mut code = String::new();
+        for (idx, limb) in limbs.iter().enumerate() {
+            match limb.value {
+                Value::Constant(constant) => {
+                    if constant.bits() > BITS {
+                        return Err(Error::AssertionFailure(format!(
+                            "Limb {idx} has more than {BITS} bits"
+                        )));
+                    }
+                }
+                _ => {
+                    let limb = self.push(limb);
+                    let check = format!("success := and(iszero(shr({BITS}, {limb})), success)\n");
+                    code.push_str(&check);
+                }
+            }
+        }
+        if !code.is_empty() {
+            self.code.borrow_mut().runtime_append(format!(
+                "{{
+            {code}
+        }}"
+            ));
+        }
+
+        let radix = F::from(2).pow_vartime([BITS as u64]);
+        let values = powers(radix).zip(limbs).collect_vec();
+        Ok(self.sum_with_coeff(&values))
+    }
+}
+
 impl<C> Loader<C> for Rc<EvmLoader>
 where
     C: CurveAffine,
@@ -881,3 +1673,401 @@ where
         self.end_gas_metering()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        halo2_curves::bn256::{Bn256, Fq, Fr, G1Affine, G1, G2},
+        loader::{
+            evm::{
+                compile_solidity, decode_verification_failure, encode_calldata,
+                encode_verify_raw_calldata, encode_vk_registry_constructor_args, execute,
+                fe_to_u256, modulus, EntryPoint, EvmLoader, ExecutorBuilder, FixedBaseMsm,
+                G2Source, InstanceCheck, ReturnConvention, INVALID_INSTANCE_ERROR, U256,
+                UNREGISTERED_VK_ERROR,
+            },
+            native::NativeLoader,
+            EcPointLoader, LimbsLoader, ScalarLoader,
+        },
+        pcs::{
+            kzg::{Bdfg21, Kzg, KzgAccumulator, KzgDecidingKey},
+            Decider,
+        },
+        system::halo2::transcript::evm::{EvmTranscript, EvmTranscriptBuilder},
+        util::{
+            arithmetic::{Curve, CurveAffine, Field, Group, PrimeField},
+            hash::{Digest, Keccak256},
+            transcript::{Transcript, TranscriptRead},
+            Itertools,
+        },
+    };
+    use ethereum_types::Address;
+    use std::{iter, rc::Rc};
+
+    #[test]
+    fn test_evm_limbs_loader() {
+        let deployment_code = {
+            let loader = EvmLoader::new::<Fq, Fr>();
+            let scalar = loader.calldataload_scalar(0);
+            let limbs = [0x20, 0x40].map(|offset| loader.calldataload_scalar(offset));
+            LimbsLoader::<Fr>::scalar_to_limbs::<2, 64>(&loader, &scalar).unwrap();
+            LimbsLoader::<Fr>::scalar_from_limbs::<2, 64>(&loader, [&limbs[0], &limbs[1]]).unwrap();
+            compile_solidity(&loader.solidity_code())
+        };
+        let native_accept = |scalar: Fr, limbs: [Fr; 2]| {
+            NativeLoader.scalar_to_limbs::<2, 64>(&scalar).is_ok()
+                && NativeLoader.scalar_from_limbs::<2, 64>([&limbs[0], &limbs[1]]).is_ok()
+        };
+        let evm_accept = |scalar: Fr, limbs: [Fr; 2]| {
+            let calldata = iter::once(scalar)
+                .chain(limbs)
+                .flat_map(|value| value.to_repr().into_iter().rev())
+                .collect();
+            execute(deployment_code.clone(), calldata).0
+        };
+
+        let two_to_64 = Fr::from(u64::MAX) + Fr::one();
+        let two_to_128 = two_to_64.square();
+        for (scalar, limbs) in [
+            (two_to_128 - Fr::one(), [two_to_64 - Fr::one(); 2]),
+            (two_to_128, [Fr::zero(); 2]),
+            (Fr::zero(), [two_to_64, Fr::zero()]),
+            (Fr::zero(), [Fr::zero(), two_to_64]),
+        ] {
+            assert_eq!(native_accept(scalar, limbs), evm_accept(scalar, limbs));
+        }
+        assert!(evm_accept(two_to_128 - Fr::one(), [two_to_64 - Fr::one(); 2]));
+    }
+
+    #[test]
+    fn test_evm_fixed_base_msm() {
+        let bases = (1..=3).map(|idx| (G1::generator() * Fr::from(idx)).to_affine()).collect_vec();
+        let scalars = [Fr::from(5), Fr::from(7), -Fr::one()];
+        let expected = bases
+            .iter()
+            .zip(scalars)
+            .fold(G1::identity(), |acc, (base, scalar)| acc + base * scalar)
+            .to_affine();
+
+        let deployment_code = |fixed_base_msm| {
+            let loader = EvmLoader::builder::<Fq, Fr>().with_fixed_base_msm(fixed_base_msm).build();
+            let mut transcript = EvmTranscript::<G1Affine, Rc<EvmLoader>, _, _>::new(&loader);
+            let loaded_scalars =
+                scalars.iter().map(|_| transcript.read_scalar().unwrap()).collect_vec();
+            let loaded_bases =
+                bases.iter().map(|base| loader.ec_point_load_const(base)).collect_vec();
+            let msm = <Rc<EvmLoader> as EcPointLoader<G1Affine>>::multi_scalar_multiplication(
+                &loaded_scalars.iter().zip(loaded_bases.iter()).collect_vec(),
+            );
+            let coordinates = expected.coordinates().unwrap();
+            let [x, y] = [*coordinates.x(), *coordinates.y()].map(fe_to_u256);
+            let (x_ptr, y_ptr) = (msm.ptr(), msm.ptr() + 0x20);
+            loader.code_mut().runtime_append(format!(
+                "success := and(and(eq(mload({x_ptr:#x}), {x}), eq(mload({y_ptr:#x}), {y})), success)"
+            ));
+
+            let code = loader.solidity_code();
+            assert_eq!(code.contains("for { let ptr :="), fixed_base_msm == FixedBaseMsm::Loop);
+            compile_solidity(&code)
+        };
+
+        let calldata = encode_calldata(&[scalars.to_vec()], &[]);
+        let wrong_calldata = encode_calldata(&[vec![scalars[0], scalars[1], Fr::one()]], &[]);
+        let gas_costs = [FixedBaseMsm::Loop, FixedBaseMsm::Unrolled].map(|fixed_base_msm| {
+            let deployment_code = deployment_code(fixed_base_msm);
+            let (accept, gas_cost, _) = execute(deployment_code.clone(), calldata.clone());
+            assert!(accept);
+            assert!(!execute(deployment_code, wrong_calldata.clone()).0);
+            gas_cost
+        });
+        assert!(gas_costs[1] < gas_costs[0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Memory layout of EVM verifier exceeds limit 0x40")]
+    fn test_evm_memory_limit() {
+        let loader = EvmLoader::builder::<Fq, Fr>().with_memory_limit(0x40).build();
+        assert_eq!(loader.allocate(0x40), 0);
+        assert_eq!(loader.memory_size(), 0x40);
+        loader.allocate(0x20);
+    }
+
+    #[test]
+    fn test_evm_immutable_g2() {
+        let dk_of = |s: u64| {
+            KzgDecidingKey::<Bn256>::new(
+                G2::generator().to_affine(),
+                (G2::generator() * Fr::from(s)).to_affine(),
+            )
+        };
+        let solidity_code_of = |dk: &KzgDecidingKey<Bn256>| {
+            let loader = EvmLoader::builder::<Fq, Fr>().with_g2_source(G2Source::Immutable).build();
+            let mut transcript = EvmTranscript::<G1Affine, Rc<EvmLoader>, _, _>::new(&loader);
+            let [lhs, rhs] = [(); 2].map(|_| transcript.read_ec_point().unwrap());
+            Kzg::<Bn256, Bdfg21>::decide(dk, KzgAccumulator::new(lhs, rhs));
+            loader.solidity_code()
+        };
+
+        // Generated code is independent of deciding key.
+        let code = solidity_code_of(&dk_of(7));
+        assert_eq!(code, solidity_code_of(&dk_of(11)));
+        let deployment_code = compile_solidity(&code);
+
+        let proof = [G1::generator() * Fr::from(7), G1::generator()]
+            .into_iter()
+            .flat_map(|ec_point| {
+                let coordinates = ec_point.to_affine().coordinates().unwrap();
+                [*coordinates.x(), *coordinates.y()]
+            })
+            .flat_map(|coordinate| {
+                let mut bytes = [0; 0x20];
+                fe_to_u256(coordinate).to_big_endian(&mut bytes);
+                bytes
+            })
+            .collect::<Vec<_>>();
+        let calldata = encode_calldata::<Fr>(&[], &proof);
+        let deploy_with = |dk: KzgDecidingKey<Bn256>| {
+            let mut deployment_code = deployment_code.clone();
+            deployment_code.extend(dk.evm_constructor_args());
+            deployment_code
+        };
+        assert!(execute(deploy_with(dk_of(7)), calldata.clone()).0);
+        assert!(!execute(deploy_with(dk_of(11)), calldata).0);
+    }
+
+    #[test]
+    fn test_evm_instance_check() {
+        let deployment_code = |instance_check| {
+            let loader = EvmLoader::new::<Fq, Fr>();
+            let mut transcript = EvmTranscript::<G1Affine, Rc<EvmLoader>, _, _>::new(&loader);
+            transcript.load_instances_with_check(vec![2], instance_check);
+            compile_solidity(&loader.solidity_code())
+        };
+        let call = |deployment_code: &[u8], instances: [U256; 2]| {
+            let mut evm = ExecutorBuilder::default().with_gas_limit(u64::MAX.into()).build();
+            let caller = Address::from_low_u64_be(0xfe);
+            let contract =
+                evm.deploy(caller, deployment_code.to_vec().into(), 0.into()).address.unwrap();
+            let calldata = instances
+                .iter()
+                .flat_map(|instance| {
+                    let mut bytes = [0; 0x20];
+                    instance.to_big_endian(&mut bytes);
+                    bytes
+                })
+                .collect_vec();
+            let result = evm.call_raw(caller, contract, calldata.into(), 0.into());
+            (!result.reverted, result.result.to_vec())
+        };
+        let canonical = [U256::one(), modulus::<Fr>() - 1];
+        let non_canonical = [U256::one(), modulus::<Fr>()];
+
+        let revert = deployment_code(InstanceCheck::Revert);
+        assert!(call(&revert, canonical).0);
+        let (accepted, output) = call(&revert, non_canonical);
+        assert!(!accepted);
+        let mut expected = Keccak256::digest(INVALID_INSTANCE_ERROR.as_bytes())[..4].to_vec();
+        expected.extend([0; 0x1f]);
+        expected.push(1);
+        assert_eq!(output, expected);
+
+        let skip = deployment_code(InstanceCheck::Skip);
+        assert!(call(&skip, canonical).0);
+        assert!(call(&skip, non_canonical).0);
+    }
+
+    #[test]
+    fn test_evm_verify_raw() {
+        let entry_point = EntryPoint::VerifyRaw;
+        let loader = EvmLoader::builder::<Fq, Fr>().with_entry_point(entry_point).build();
+        let mut transcript = EvmTranscriptBuilder::new(&loader)
+            .with_calldata_offset(entry_point.calldata_offset())
+            .build::<G1Affine>();
+        transcript.load_instances(vec![2]);
+        let code = loader.solidity_code();
+        assert!(code.contains("function verify_raw(bytes calldata raw)"));
+        let deployment_code = compile_solidity(&code);
+
+        let call = |calldata: Vec<u8>| {
+            let mut evm = ExecutorBuilder::default().with_gas_limit(u64::MAX.into()).build();
+            let caller = Address::from_low_u64_be(0xfe);
+            let contract =
+                evm.deploy(caller, deployment_code.clone().into(), 0.into()).address.unwrap();
+            let result = evm.call_raw(caller, contract, calldata.into(), 0.into());
+            (!result.reverted, result.result.to_vec())
+        };
+        let calldata = encode_verify_raw_calldata(&[vec![Fr::one(), Fr::from(2)]], &[]);
+        let (accepted, output) = call(calldata.clone());
+        assert!(accepted);
+        let mut expected = [0; 0x20];
+        U256::one().to_big_endian(&mut expected);
+        assert_eq!(output, expected);
+
+        // `raw` encoded with non-standard offset
+        let mut offset = [0; 0x20];
+        U256::from(0x40).to_big_endian(&mut offset);
+        let tampered = [&calldata[..4], &offset[..], &[0; 0x20][..], &calldata[0x24..]].concat();
+        assert!(!call(tampered).0);
+        // Calldata without selector
+        assert!(!call(calldata[0x44..].to_vec()).0);
+    }
+
+    #[test]
+    fn test_evm_verification_failure() {
+        let loader = EvmLoader::new::<Fq, Fr>();
+        let mut transcript = EvmTranscript::<G1Affine, Rc<EvmLoader>, _, _>::new(&loader);
+        transcript.read_ec_point().unwrap();
+        transcript.squeeze_challenge();
+        let scalar = transcript.read_scalar().unwrap();
+        loader.assert_eq("scalar is one", &scalar, &loader.load_const(&Fr::one())).unwrap();
+        transcript.read_scalar().unwrap();
+        let deployment_code = compile_solidity(&loader.solidity_code());
+
+        let call = |words: [U256; 4]| {
+            let mut evm = ExecutorBuilder::default().with_gas_limit(u64::MAX.into()).build();
+            let caller = Address::from_low_u64_be(0xfe);
+            let contract =
+                evm.deploy(caller, deployment_code.clone().into(), 0.into()).address.unwrap();
+            let calldata = words
+                .iter()
+                .flat_map(|word| {
+                    let mut bytes = [0; 0x20];
+                    word.to_big_endian(&mut bytes);
+                    bytes
+                })
+                .collect::<Vec<_>>();
+            let result = evm.call_raw(caller, contract, calldata.into(), 0.into());
+            (!result.reverted, decode_verification_failure(&result.result))
+        };
+        let [one, two, three] = [1, 2, 3].map(U256::from);
+
+        assert_eq!(call([one, two, one, one]), (true, None));
+        // Generator `(1, 2)` replaced by a point not on curve
+        assert_eq!(call([one, three, one, one]), (false, Some("challenge 0".to_string())));
+        assert_eq!(call([one, two, two, one]), (false, Some("scalar is one".to_string())));
+        // Failing after the last checkpoint
+        assert_eq!(call([one, two, one, modulus::<Fr>()]), (false, Some(String::new())));
+    }
+
+    #[test]
+    fn test_evm_verification_failure_long_annotation() {
+        // Same annotation as the one asserted by `verify_with_challenges`, which is 33 bytes
+        let annotation = "Unexpected accumulation challenge";
+        assert!(annotation.len() > 0x20);
+
+        let loader = EvmLoader::new::<Fq, Fr>();
+        let mut transcript = EvmTranscript::<G1Affine, Rc<EvmLoader>, _, _>::new(&loader);
+        let scalar = transcript.read_scalar().unwrap();
+        loader.assert_eq(annotation, &scalar, &loader.load_const(&Fr::one())).unwrap();
+        let deployment_code = compile_solidity(&loader.solidity_code());
+
+        let call = |word: U256| {
+            let mut evm = ExecutorBuilder::default().with_gas_limit(u64::MAX.into()).build();
+            let caller = Address::from_low_u64_be(0xfe);
+            let contract =
+                evm.deploy(caller, deployment_code.clone().into(), 0.into()).address.unwrap();
+            let mut calldata = [0; 0x20];
+            word.to_big_endian(&mut calldata);
+            let result = evm.call_raw(caller, contract, calldata.to_vec().into(), 0.into());
+            (!result.reverted, decode_verification_failure(&result.result))
+        };
+
+        assert_eq!(call(U256::one()), (true, None));
+        assert_eq!(call(U256::from(2)), (false, Some(annotation[..0x20].to_string())));
+    }
+
+    #[test]
+    fn test_evm_bool_return_convention() {
+        let loader =
+            EvmLoader::builder::<Fq, Fr>().with_return_convention(ReturnConvention::Bool).build();
+        let mut transcript = EvmTranscript::<G1Affine, Rc<EvmLoader>, _, _>::new(&loader);
+        transcript.load_instances(vec![1]);
+        transcript.read_ec_point().unwrap();
+        transcript.squeeze_challenge();
+        let scalar = transcript.read_scalar().unwrap();
+        loader.assert_eq("scalar is one", &scalar, &loader.load_const(&Fr::one())).unwrap();
+        transcript.read_scalar().unwrap();
+        let deployment_code = compile_solidity(&loader.solidity_code());
+
+        let call = |words: [U256; 5]| {
+            let mut evm = ExecutorBuilder::default().with_gas_limit(u64::MAX.into()).build();
+            let caller = Address::from_low_u64_be(0xfe);
+            let contract =
+                evm.deploy(caller, deployment_code.clone().into(), 0.into()).address.unwrap();
+            let calldata = words
+                .iter()
+                .flat_map(|word| {
+                    let mut bytes = [0; 0x20];
+                    word.to_big_endian(&mut bytes);
+                    bytes
+                })
+                .collect::<Vec<_>>();
+            let result = evm.call_raw(caller, contract, calldata.into(), 0.into());
+            assert!(!result.reverted);
+            U256::from_big_endian(&result.result)
+        };
+        let [zero, one, two, three] = [0, 1, 2, 3].map(U256::from);
+
+        assert_eq!(call([one, one, two, one, one]), one);
+        // Non-canonical instance
+        assert_eq!(call([modulus::<Fr>(), one, two, one, one]), zero);
+        // Generator `(1, 2)` replaced by a point not on curve
+        assert_eq!(call([one, one, three, one, one]), zero);
+        // Failing assertion
+        assert_eq!(call([one, one, two, two, one]), zero);
+        // Non-canonical scalar
+        assert_eq!(call([one, one, two, one, modulus::<Fr>()]), zero);
+    }
+
+    #[test]
+    fn test_evm_bool_return_convention_vk_registry() {
+        // Verifying key of a single point `(1, 2)` registered in constructor
+        let vk = [U256::one(), U256::from(2)]
+            .iter()
+            .flat_map(|word| {
+                let mut bytes = [0; 0x20];
+                word.to_big_endian(&mut bytes);
+                bytes
+            })
+            .collect::<Vec<_>>();
+        let vk_digest: [u8; 32] = Keccak256::digest(&vk).into();
+        let unregistered: [u8; 32] = Keccak256::digest([vk.as_slice(), &[0]].concat()).into();
+
+        let call = |return_convention: ReturnConvention, calldata: Vec<u8>| {
+            let loader =
+                EvmLoader::builder::<Fq, Fr>().with_return_convention(return_convention).build();
+            loader.calldataload_vk(0, 1, 0);
+            let deployment_code = [
+                compile_solidity(&loader.solidity_code()),
+                encode_vk_registry_constructor_args(&[vk_digest]),
+            ]
+            .concat();
+
+            let mut evm = ExecutorBuilder::default().with_gas_limit(u64::MAX.into()).build();
+            let caller = Address::from_low_u64_be(0xfe);
+            let contract = evm.deploy(caller, deployment_code.into(), 0.into()).address.unwrap();
+            let result = evm.call_raw(caller, contract, calldata.into(), 0.into());
+            (result.reverted, result.result.to_vec())
+        };
+        let word = |value: u64| {
+            let mut bytes = [0; 0x20];
+            U256::from(value).to_big_endian(&mut bytes);
+            bytes.to_vec()
+        };
+
+        let registered = [vk_digest.as_slice(), &vk].concat();
+        assert_eq!(call(ReturnConvention::Bool, registered.clone()), (false, word(1)));
+        assert_eq!(call(ReturnConvention::Revert, registered), (false, Vec::new()));
+
+        // Unregistered verifying key returns false instead of reverting
+        let unregistered = [unregistered.as_slice(), &vk].concat();
+        assert_eq!(call(ReturnConvention::Bool, unregistered.clone()), (false, word(0)));
+        let (reverted, revert_data) = call(ReturnConvention::Revert, unregistered);
+        assert!(reverted);
+        assert_eq!(
+            revert_data,
+            [&Keccak256::digest(UNREGISTERED_VK_ERROR.as_bytes())[..4], &unregistered[..0x20]]
+                .concat()
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! Multi-transaction submission of instances too many to fit in a single
+//! transaction.
+//!
+//! Instead of passing instances to the verifier, the circuit exposes a
+//! keccak256 digest of them as only instances `[hi, lo]` (upper and lower 128
+//! bits), and instances are posted to a staging contract in chunks of
+//! `chunk_size` words across transactions. The staging contract chains the
+//! digest per chunk as `digest = keccak256(digest || chunk)` from zero, then
+//! the final transaction passes the remaining less than `chunk_size` instances
+//! with the proof, and the staging contract calls the verifier with the
+//! digest in front of the proof.
+//!
+//! The circuit is responsible for computing the digest in the same way, with
+//! the same `chunk_size`.
+use crate::{
+    format::{FORMAT_VERSION, SOL_CODE_FORMAT_VERSION_PREFIX},
+    loader::evm::util::fe_to_u256,
+    util::{
+        arithmetic::PrimeField,
+        hash::{Digest, Keccak256},
+        Itertools,
+    },
+};
+use ethereum_types::U256;
+use std::iter;
+
+/// Returns Solidity code of the staging contract, which is deployed with
+/// address of the verifier as the constructor argument.
+pub fn staging_sol_code(chunk_size: usize) -> String {
+    assert!(chunk_size > 0);
+    format!(
+        "// SPDX-License-Identifier: MIT
+{SOL_CODE_FORMAT_VERSION_PREFIX}{FORMAT_VERSION}
+pragma solidity ^0.8.0;
+
+contract InstanceStaging {{
+    uint256 constant CHUNK_SIZE = {chunk_size};
+
+    address immutable verifier;
+    mapping(address => bytes32) public digests;
+
+    constructor(address _verifier) {{
+        verifier = _verifier;
+    }}
+
+    function absorb(uint256[] calldata chunk) external {{
+        require(chunk.length == CHUNK_SIZE, \"Unexpected chunk length\");
+        digests[msg.sender] = keccak256(abi.encodePacked(digests[msg.sender], chunk));
+    }}
+
+    function verify(uint256[] calldata tail, bytes calldata proof) external {{
+        require(tail.length < CHUNK_SIZE, \"Unexpected tail length\");
+        uint256 digest = uint256(keccak256(abi.encodePacked(digests[msg.sender], tail)));
+        delete digests[msg.sender];
+        uint256 hi = digest >> 128;
+        uint256 lo = uint256(uint128(digest));
+        (bool success, ) = verifier.call(abi.encodePacked(hi, lo, proof));
+        require(success, \"Invalid proof\");
+    }}
+}}
+"
+    )
+}
+
+/// Returns the digest of `instances` as `[hi, lo]`, which should be the only
+/// instances of the circuit.
+pub fn staged_instance_digest<F>(instances: &[F], chunk_size: usize) -> [F; 2]
+where
+    F: PrimeField<Repr = [u8; 32]>,
+{
+    let digest = chunks(instances, chunk_size).fold([0; 32], |digest, chunk| {
+        let mut hasher = Keccak256::new();
+        hasher.update(digest);
+        for instance in chunk {
+            hasher.update(be_bytes(*instance));
+        }
+        hasher.finalize().into()
+    });
+    let [hi, lo] = [&digest[..16], &digest[16..]].map(|bytes| {
+        let mut repr = [0; 32];
+        repr[..16].copy_from_slice(bytes);
+        repr[..16].reverse();
+        F::from_repr(repr).unwrap()
+    });
+    [hi, lo]
+}
+
+/// Encode calldata of transactions to submit `instances` and `proof` to the
+/// staging contract, where the last one is the call to `verify`, and the
+/// others are calls to `absorb`.
+pub fn encode_staged_calldata<F>(instances: &[F], proof: &[u8], chunk_size: usize) -> Vec<Vec<u8>>
+where
+    F: PrimeField<Repr = [u8; 32]>,
+{
+    let chunks = chunks(instances, chunk_size).collect_vec();
+    let (tail, chunks) = chunks.split_last().unwrap();
+    let absorbs = chunks.iter().map(|chunk| {
+        iter::empty()
+            .chain(selector("absorb(uint256[])"))
+            .chain(word(0x20.into()))
+            .chain(word(chunk.len().into()))
+            .chain(chunk.iter().flat_map(|instance| be_bytes(*instance)))
+            .collect_vec()
+    });
+    let verify = iter::empty()
+        .chain(selector("verify(uint256[],bytes)"))
+        .chain(word(0x40.into()))
+        .chain(word((0x60 + 0x20 * tail.len()).into()))
+        .chain(word(tail.len().into()))
+        .chain(tail.iter().flat_map(|instance| be_bytes(*instance)))
+        .chain(word(proof.len().into()))
+        .chain(proof.iter().copied())
+        .chain(iter::repeat(0).take((0x20 - proof.len() % 0x20) % 0x20))
+        .collect_vec();
+    absorbs.chain(Some(verify)).collect()
+}
+
+/// Returns chunks of `instances` absorbed by `absorb`, then the tail passed to
+/// `verify`, which is always shorter than `chunk_size` and might be empty.
+fn chunks<F>(instances: &[F], chunk_size: usize) -> impl Iterator<Item = &[F]> {
+    assert!(chunk_size > 0);
+    let tail = instances.len() % chunk_size;
+    let (full, tail) = instances.split_at(instances.len() - tail);
+    full.chunks(chunk_size).chain(Some(tail))
+}
+
+fn selector(signature: &str) -> [u8; 4] {
+    Keccak256::digest(signature.as_bytes())[..4].try_into().unwrap()
+}
+
+fn word(value: U256) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    value.to_big_endian(&mut bytes);
+    bytes
+}
+
+fn be_bytes<F: PrimeField<Repr = [u8; 32]>>(instance: F) -> [u8; 32] {
+    word(fe_to_u256(instance))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        halo2_curves::bn256::{Fq, Fr, G1Affine},
+        loader::evm::{
+            compile_solidity, encode_calldata, encode_staged_calldata, fe_to_u256,
+            staged_instance_digest, staging_sol_code, write_calldata, EvmLoader, ExecutorBuilder,
+        },
+        system::halo2::transcript::evm::EvmTranscript,
+        util::{arithmetic::Field, Itertools},
+    };
+    use ethereum_types::Address;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_evm_staged_instances() {
+        const CHUNK_SIZE: usize = 4;
+
+        let instances = (0..2 * CHUNK_SIZE as u64 + 1).map(Fr::from).collect_vec();
+        let proof = vec![0xab; 0x45];
+        let mut streamed = Vec::new();
+        write_calldata(instances.iter().copied(), &proof, &mut streamed).unwrap();
+        assert_eq!(streamed, encode_calldata(&[instances.clone()], &proof));
+
+        // Mock verifier which only accepts the digest of `instances` as instances.
+        let verifier_code = {
+            let loader = EvmLoader::new::<Fq, Fr>();
+            let mut transcript = EvmTranscript::<G1Affine, Rc<EvmLoader>, _, _>::new(&loader);
+            let loaded = transcript.load_instances(vec![2]).pop().unwrap();
+            let digest = staged_instance_digest(&instances, CHUNK_SIZE).map(fe_to_u256);
+            for (instance, digest) in loaded.iter().zip(digest) {
+                let ptr = instance.ptr();
+                loader.code_mut().runtime_append(format!(
+                    "success := and(eq(mload({ptr:#x}), {digest}), success)"
+                ));
+            }
+            compile_solidity(&loader.solidity_code())
+        };
+
+        let submit = |instances: &[Fr]| {
+            let mut evm = ExecutorBuilder::default().with_gas_limit(u64::MAX.into()).build();
+            let caller = Address::from_low_u64_be(0xfe);
+            let verifier =
+                evm.deploy(caller, verifier_code.clone().into(), 0.into()).address.unwrap();
+            let mut staging_code = compile_solidity(&staging_sol_code(CHUNK_SIZE));
+            staging_code.extend([0; 12]);
+            staging_code.extend(verifier.as_bytes());
+            let staging = evm.deploy(caller, staging_code.into(), 0.into()).address.unwrap();
+
+            let calldata = encode_staged_calldata(instances, &proof, CHUNK_SIZE);
+            assert_eq!(calldata.len(), instances.len() / CHUNK_SIZE + 1);
+            calldata
+                .into_iter()
+                .all(|calldata| !evm.call(caller, staging, calldata.into(), 0.into()).reverted)
+        };
+        assert!(submit(&instances));
+        let mut tampered = instances.clone();
+        tampered[1] += Fr::one();
+        assert!(!submit(&tampered));
+    }
+}
@@ -1,5 +1,8 @@
 use crate::{
-    loader::evm::{test::tui::Tui, util::ExecutorBuilder},
+    loader::evm::{
+        test::tui::Tui,
+        util::{execute_with_specs, spec_divergences, ExecutorBuilder, SpecId},
+    },
     util::Itertools,
 };
 use ethereum_types::{Address, U256};
@@ -14,6 +17,30 @@ fn debug() -> bool {
     )
 }
 
+/// Returns specs listed in `EVM_SPECS` (e.g. `EVM_SPECS=istanbul,london`) to
+/// additionally check the verifier against.
+fn specs() -> Vec<SpecId> {
+    let specs = match var_os("EVM_SPECS") {
+        Some(specs) => specs,
+        None => return Vec::new(),
+    };
+    specs
+        .to_str()
+        .unwrap()
+        .split(',')
+        .map(|spec| match spec.trim().to_lowercase().as_str() {
+            "homestead" => SpecId::HOMESTEAD,
+            "byzantium" => SpecId::BYZANTIUM,
+            "istanbul" => SpecId::ISTANBUL,
+            "berlin" => SpecId::BERLIN,
+            "london" => SpecId::LONDON,
+            "merge" => SpecId::MERGE,
+            "latest" => SpecId::LATEST,
+            spec => panic!("Unknown EVM spec {spec}"),
+        })
+        .collect()
+}
+
 pub fn execute(deployment_code: Vec<u8>, calldata: Vec<u8>) -> (bool, u64, Vec<u64>) {
     assert!(
         deployment_code.len() <= 0x6000,
@@ -21,6 +48,13 @@ pub fn execute(deployment_code: Vec<u8>, calldata: Vec<u8>) -> (bool, u64, Vec<u
         deployment_code.len()
     );
 
+    let specs = specs();
+    if !specs.is_empty() {
+        let outcomes = execute_with_specs(&deployment_code, &calldata, &specs);
+        let divergences = spec_divergences(&outcomes);
+        assert!(divergences.is_empty(), "Divergent outcomes across EVM specs: {outcomes:?}");
+    }
+
     let debug = debug();
     let caller = Address::from_low_u64_be(0xfe);
 
@@ -1,17 +1,23 @@
 use crate::{
     cost::Cost,
-    util::{arithmetic::PrimeField, Itertools},
+    loader::evm::{EvmLoader, VERIFICATION_FAILURE_ERROR, VERIFY_RAW_SIGNATURE},
+    util::{
+        arithmetic::{CurveAffine, MultiMillerLoop, PrimeField},
+        hash::{Digest, Keccak256},
+    },
 };
-use ethereum_types::U256;
+use ethereum_types::{Address, U256};
 use std::{
-    io::Write,
-    iter,
+    io::{self, Write},
+    path::{Path, PathBuf},
     process::{Command, Stdio},
+    rc::Rc,
 };
 
 pub(crate) mod executor;
 
-pub use executor::ExecutorBuilder;
+pub use executor::{DeployResult, Executor, ExecutorBuilder, RawCallResult};
+pub use revm::SpecId;
 
 /// Memory chunk in EVM.
 #[derive(Debug)]
@@ -47,6 +53,20 @@ impl MemoryChunk {
     }
 }
 
+/// Returns coordinates of G2 point in order of `ecPairing` input, which is
+/// `(x.c1, x.c0, y.c1, y.c0)`.
+pub(crate) fn g2_to_u256s<M: MultiMillerLoop>(ec_point: M::G2Affine) -> (U256, U256, U256, U256) {
+    let coordinates = ec_point.coordinates().unwrap();
+    let x = coordinates.x().to_repr();
+    let y = coordinates.y().to_repr();
+    (
+        U256::from_little_endian(&x.as_ref()[32..]),
+        U256::from_little_endian(&x.as_ref()[..32]),
+        U256::from_little_endian(&y.as_ref()[32..]),
+        U256::from_little_endian(&y.as_ref()[..32]),
+    )
+}
+
 /// Convert a [`PrimeField`] into a [`U256`].
 /// Assuming fields that implement traits in crate `ff` always have
 /// little-endian representation.
@@ -81,15 +101,83 @@ pub fn encode_calldata<F>(instances: &[Vec<F>], proof: &[u8]) -> Vec<u8>
 where
     F: PrimeField<Repr = [u8; 32]>,
 {
-    iter::empty()
-        .chain(
-            instances
-                .iter()
-                .flatten()
-                .flat_map(|value| value.to_repr().as_ref().iter().rev().cloned().collect_vec()),
-        )
-        .chain(proof.iter().cloned())
-        .collect()
+    let num_instance = instances.iter().map(Vec::len).sum::<usize>();
+    let mut calldata = Vec::with_capacity(num_instance * 0x20 + proof.len());
+    write_calldata(instances.iter().flatten().copied(), proof, &mut calldata).unwrap();
+    calldata
+}
+
+/// Encode instances and proof into calldata of `verify_raw(bytes)` of verifier
+/// generated with [`EntryPoint::VerifyRaw`], which is the selector followed by
+/// ABI encoded [`encode_calldata`] as `bytes`.
+///
+/// [`EntryPoint::VerifyRaw`]: crate::loader::evm::EntryPoint::VerifyRaw
+pub fn encode_verify_raw_calldata<F>(instances: &[Vec<F>], proof: &[u8]) -> Vec<u8>
+where
+    F: PrimeField<Repr = [u8; 32]>,
+{
+    encode_bytes_call(VERIFY_RAW_SIGNATURE, &encode_calldata(instances, proof))
+}
+
+/// Encode calldata of function with `signature` taking a single `bytes`, which
+/// is the selector followed by ABI encoded `bytes`. It's for calling a contract
+/// consuming the verifier (e.g. `submit(bytes)` of a settlement contract
+/// forwarding `bytes` to the verifier as calldata).
+pub fn encode_bytes_call(signature: &str, bytes: &[u8]) -> Vec<u8> {
+    let selector = &Keccak256::digest(signature.as_bytes())[..4];
+    let [offset, len] = [U256::from(0x20), U256::from(bytes.len())].map(|value| {
+        let mut word = [0; 0x20];
+        value.to_big_endian(&mut word);
+        word
+    });
+    let padding = vec![0; (0x20 - bytes.len() % 0x20) % 0x20];
+    [selector, &offset[..], &len[..], bytes, &padding[..]].concat()
+}
+
+/// Write instances and proof as calldata into `writer` word by word, which is
+/// the same as [`encode_calldata`] but doesn't need instances to be in memory
+/// all at once (e.g. when they are streamed from disk).
+pub fn write_calldata<F, W>(
+    instances: impl IntoIterator<Item = F>,
+    proof: &[u8],
+    mut writer: W,
+) -> io::Result<()>
+where
+    F: PrimeField<Repr = [u8; 32]>,
+    W: Write,
+{
+    for instance in instances {
+        let mut word = instance.to_repr();
+        word.reverse();
+        writer.write_all(&word)?;
+    }
+    writer.write_all(proof)
+}
+
+/// Decodes revert data of [`VERIFICATION_FAILURE_ERROR`] into annotation of
+/// the failed checkpoint or assertion, which is empty if it fails after the
+/// last checkpoint. Returns `None` if `revert_data` is not of it.
+pub fn decode_verification_failure(revert_data: &[u8]) -> Option<String> {
+    let selector = &Keccak256::digest(VERIFICATION_FAILURE_ERROR.as_bytes())[..4];
+    if revert_data.len() != 0x24 || &revert_data[..4] != selector {
+        return None;
+    }
+    let code = &revert_data[4..];
+    let len = code.iter().rposition(|byte| *byte != 0).map_or(0, |idx| idx + 1);
+    Some(String::from_utf8_lossy(&code[..len]).to_string())
+}
+
+/// Deploy `deployment_code` and then call it with `calldata` on a fresh
+/// executor, and returns gas used by the call if both succeed.
+pub fn deploy_and_call(deployment_code: Vec<u8>, calldata: Vec<u8>) -> Result<u64, String> {
+    let mut evm = ExecutorBuilder::default().with_gas_limit(u64::MAX.into()).build();
+    let caller = Address::from_low_u64_be(0xfe);
+    let contract = evm.deploy_with_args(caller, &deployment_code, &[])?;
+    let result = evm.call_raw(caller, contract, calldata.into(), 0.into());
+    if result.reverted {
+        return Err(format!("Call reverted with {:?}", result.exit_reason));
+    }
+    Ok(result.gas_used)
 }
 
 /// Estimate gas cost with given [`Cost`].
@@ -103,19 +191,443 @@ pub fn estimate_gas(cost: Cost) -> usize {
     intrinsic_cost + calldata_cost + ec_operation_cost
 }
 
-/// Compile given Solidity `code` into deployment bytecode.
+/// Outcome of deploying and calling a verifier under an EVM spec.
+#[derive(Clone, Debug)]
+pub struct SpecOutcome {
+    /// EVM spec the verifier is executed under.
+    pub spec_id: SpecId,
+    /// Whether the deployment succeeded.
+    pub deployed: bool,
+    /// Whether the call succeeded, which is `false` if not `deployed`.
+    pub accepted: bool,
+    /// Gas used by the call.
+    pub gas_used: u64,
+}
+
+/// Deploy `deployment_code` and call it with `calldata` under each spec in
+/// `spec_ids` on a fresh executor, to find out chains with older EVM
+/// equivalence level (e.g. without `PUSH0` or some precompiles) that the
+/// verifier doesn't work on.
+pub fn execute_with_specs(
+    deployment_code: &[u8],
+    calldata: &[u8],
+    spec_ids: &[SpecId],
+) -> Vec<SpecOutcome> {
+    let caller = Address::from_low_u64_be(0xfe);
+    spec_ids
+        .iter()
+        .map(|&spec_id| {
+            let mut evm = ExecutorBuilder::default()
+                .with_gas_limit(u64::MAX.into())
+                .with_spec_id(spec_id)
+                .build();
+            match evm.deploy(caller, deployment_code.to_vec().into(), 0.into()).address {
+                Some(contract) => {
+                    let result = evm.call_raw(caller, contract, calldata.to_vec().into(), 0.into());
+                    SpecOutcome {
+                        spec_id,
+                        deployed: true,
+                        accepted: !result.reverted,
+                        gas_used: result.gas_used,
+                    }
+                }
+                None => SpecOutcome { spec_id, deployed: false, accepted: false, gas_used: 0 },
+            }
+        })
+        .collect()
+}
+
+/// Returns outcomes that are accepted differently from the first one.
+pub fn spec_divergences(outcomes: &[SpecOutcome]) -> Vec<&SpecOutcome> {
+    match outcomes.first() {
+        Some(first) => {
+            outcomes.iter().filter(|outcome| outcome.accepted != first.accepted).collect()
+        }
+        None => Vec::new(),
+    }
+}
+
+/// Default `solc` version which the generated verifier is pinned to.
+pub const SOLC_VERSION: &str = "0.8.19";
+
+/// Settings for compiling generated verifier with `solc`.
+#[derive(Clone, Debug)]
+pub struct SolcConfig {
+    /// Pinned `solc` version, which is resolved by `svm` when feature `svm` is
+    /// enabled, otherwise checked against `solc` in `PATH`.
+    pub version: String,
+    /// Full path of `solc` binary (e.g. `C:\solc\solc.exe` on Windows), which
+    /// is used instead of resolving `version` by `svm` or in `PATH`, and is
+    /// checked against `version` unless `fallback` is set.
+    pub path: Option<PathBuf>,
+    /// Whether to fallback to any `solc` in `PATH` when the pinned version
+    /// can't be resolved. It's off by default, so a missing pinned compiler is
+    /// an error instead of silently compiling with another version.
+    pub fallback: bool,
+    /// Enable optimizer with given runs.
+    pub optimizer_runs: Option<usize>,
+    /// Compile through the IR-based codegen (`--via-ir`).
+    pub via_ir: bool,
+    /// Target EVM version (`--evm-version`), e.g. `cancun` for verifier with
+    /// [`BlobCheck`](crate::loader::evm::BlobCheck).
+    pub evm_version: Option<String>,
+}
+
+impl Default for SolcConfig {
+    fn default() -> Self {
+        Self {
+            version: SOLC_VERSION.to_string(),
+            path: None,
+            fallback: false,
+            optimizer_runs: None,
+            via_ir: false,
+            evm_version: None,
+        }
+    }
+}
+
+impl SolcConfig {
+    pub fn with_version(mut self, version: impl Into<String>) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    pub fn with_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    pub fn set_fallback(mut self, fallback: bool) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    pub fn with_optimizer_runs(mut self, optimizer_runs: usize) -> Self {
+        self.optimizer_runs = Some(optimizer_runs);
+        self
+    }
+
+    pub fn set_via_ir(mut self, via_ir: bool) -> Self {
+        self.via_ir = via_ir;
+        self
+    }
+
+    pub fn with_evm_version(mut self, evm_version: impl Into<String>) -> Self {
+        self.evm_version = Some(evm_version.into());
+        self
+    }
+}
+
+/// Compile given Solidity `code` into deployment bytecode with the pinned
+/// [`SOLC_VERSION`].
+///
+/// # Panics
+///
+/// Panics if `solc` of the pinned version can't be resolved.
 pub fn compile_solidity(code: &str) -> Vec<u8> {
-    let mut cmd = Command::new("solc")
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .arg("--bin")
-        .arg("-")
-        .spawn()
-        .unwrap();
-    cmd.stdin.take().unwrap().write_all(code.as_bytes()).unwrap();
-    let output = cmd.wait_with_output().unwrap().stdout;
-    let binary = *split_by_ascii_whitespace(&output).last().unwrap();
-    hex::decode(binary).unwrap()
+    compile_solidity_with_config(code, &SolcConfig::default()).unwrap_or_else(|err| panic!("{err}"))
+}
+
+/// Compile given Solidity `code` into deployment bytecode with `solc` resolved
+/// and invoked according to `config`.
+pub fn compile_solidity_with_config(code: &str, config: &SolcConfig) -> io::Result<Vec<u8>> {
+    decode_bytecode(&run_solc(code, config, "--bin")?)
+}
+
+/// Artifact of a generated verifier, which allows deployment pipelines to
+/// check the on-chain code matches what has been generated.
+#[derive(Clone, Debug)]
+pub struct VerifierArtifact {
+    /// Solidity source with verifier in inline assembly.
+    pub source: String,
+    /// ABI in JSON.
+    pub abi: String,
+    /// Creation bytecode to deploy.
+    pub creation_code: Vec<u8>,
+    /// Expected runtime bytecode after deployment.
+    pub runtime_code: Vec<u8>,
+    /// Keccak256 of `runtime_code`, which is the expected `extcodehash` only
+    /// when the verifier has no immutables. Verifier generated with
+    /// [`G2Source::Immutable`] or with a vk registry has values filled into
+    /// its runtime code by the constructor, so `extcodehash` of the deployed
+    /// code differs and should be checked against the executed deployment.
+    ///
+    /// [`G2Source::Immutable`]: crate::loader::evm::G2Source::Immutable
+    pub codehash: [u8; 32],
+}
+
+impl VerifierArtifact {
+    /// Compile Solidity `source` into [`VerifierArtifact`] with `solc`
+    /// resolved and invoked according to `config`.
+    pub fn compile(source: String, config: &SolcConfig) -> io::Result<Self> {
+        let output = solc_output(&source, config, &["--combined-json", "abi,bin,bin-runtime"])?;
+        let (abi, creation_code, runtime_code) = parse_combined_json(&output)?;
+        let codehash = Keccak256::digest(&runtime_code).into();
+        Ok(Self { source, abi, creation_code, runtime_code, codehash })
+    }
+}
+
+// Returns abi, creation and runtime code of the only deployable contract in
+// `output` of `solc --combined-json abi,bin,bin-runtime`.
+fn parse_combined_json(output: &[u8]) -> io::Result<(String, Vec<u8>, Vec<u8>)> {
+    let invalid_data = |err: String| io::Error::new(io::ErrorKind::InvalidData, err);
+
+    let output: serde_json::Value = serde_json::from_slice(output)
+        .map_err(|err| invalid_data(format!("Invalid combined json from solc: {err}")))?;
+    let contracts = output["contracts"]
+        .as_object()
+        .ok_or_else(|| invalid_data("Missing contracts in output of solc".to_string()))?
+        .values()
+        .filter(|contract| !contract["bin"].as_str().unwrap_or_default().is_empty())
+        .collect::<Vec<_>>();
+    let contract = match contracts.as_slice() {
+        [contract] => contract,
+        _ => {
+            return Err(invalid_data(format!(
+                "Expected 1 deployable contract in output of solc, got {}",
+                contracts.len()
+            )))
+        }
+    };
+    // Older `solc` outputs abi as a string of JSON.
+    let abi = match &contract["abi"] {
+        serde_json::Value::String(abi) => abi.clone(),
+        abi => abi.to_string(),
+    };
+    let code = |key: &str| {
+        contract[key]
+            .as_str()
+            .ok_or_else(|| invalid_data(format!("Missing {key} in output of solc")))
+            .and_then(|code| decode_bytecode(code.as_bytes()))
+    };
+    Ok((abi, code("bin")?, code("bin-runtime")?))
+}
+
+/// Limit of runtime bytecode size of a contract by EIP-170.
+pub const CONTRACT_SIZE_LIMIT: usize = 0x6000;
+
+/// Verifier compiled by [`compile_split_verifier`], which is split into stages
+/// only when it exceeds [`CONTRACT_SIZE_LIMIT`].
+#[derive(Clone, Debug)]
+pub struct SplitVerifier {
+    /// Creation code of dispatcher, or of the whole verifier if not split.
+    pub dispatcher: Vec<u8>,
+    /// Creation code of stages, which are empty if not split.
+    pub stages: Vec<Vec<u8>>,
+}
+
+impl SplitVerifier {
+    /// Returns creation code of dispatcher with addresses of deployed
+    /// `stages` as constructor arguments.
+    pub fn dispatcher_deployment_code(&self, stages: &[Address]) -> Vec<u8> {
+        assert_eq!(stages.len(), self.stages.len());
+        let mut code = self.dispatcher.clone();
+        for stage in stages {
+            code.extend([0; 12]);
+            code.extend(stage.as_bytes());
+        }
+        code
+    }
+}
+
+/// Compile verifier generated by `loader` into a single contract, or when its
+/// runtime bytecode exceeds [`CONTRACT_SIZE_LIMIT`], into the least number of
+/// stages within the limit and a dispatcher delegatecalling them in sequence,
+/// see [`EvmLoader::split_solidity_code`]. It's called instead of
+/// [`EvmLoader::solidity_code`].
+///
+/// Since runtime is split into stages of roughly equal length, the number of
+/// stages is binary searched from the estimation by size of the whole runtime
+/// bytecode, so only `O(log n)` candidates are compiled for `n` code snippets.
+///
+/// Returns error of kind [`io::ErrorKind::InvalidInput`] if the verifier
+/// can't be split, see [`EvmLoader::split_solidity_code`].
+pub fn compile_split_verifier(
+    loader: &Rc<EvmLoader>,
+    config: &SolcConfig,
+) -> io::Result<SplitVerifier> {
+    // Returns creation code if runtime bytecode is within the limit, or size
+    // of runtime bytecode otherwise.
+    let compile = |code: &str| -> io::Result<Result<Vec<u8>, usize>> {
+        let runtime_code = decode_bytecode(&run_solc(code, config, "--bin-runtime")?)?;
+        if runtime_code.len() > CONTRACT_SIZE_LIMIT {
+            return Ok(Err(runtime_code.len()));
+        }
+        decode_bytecode(&run_solc(code, config, "--bin")?).map(Ok)
+    };
+    let split = |num_stage: usize| -> io::Result<(String, Vec<String>)> {
+        loader
+            .split_solidity_code(num_stage)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, format!("{err:?}")))
+    };
+    let compile_stages = |num_stage: usize| -> io::Result<Option<SplitVerifier>> {
+        let (dispatcher, stages) = split(num_stage)?;
+        let mut compiled = Vec::with_capacity(stages.len());
+        for stage in stages.iter() {
+            match compile(stage)? {
+                Ok(stage) => compiled.push(stage),
+                Err(_) => return Ok(None),
+            }
+        }
+        let dispatcher = compile_solidity_with_config(&dispatcher, config)?;
+        Ok(Some(SplitVerifier { dispatcher, stages: compiled }))
+    };
+
+    let (_, stages) = split(1)?;
+    let runtime_size = match compile(&stages[0])? {
+        Ok(verifier) => return Ok(SplitVerifier { dispatcher: verifier, stages: Vec::new() }),
+        Err(runtime_size) => runtime_size,
+    };
+
+    // Stages add up to at least the whole runtime, so fewer than the estimation
+    // can't be all within the limit.
+    let num_snippet = loader.code_mut().num_snippet();
+    let estimation = (runtime_size + CONTRACT_SIZE_LIMIT - 1) / CONTRACT_SIZE_LIMIT;
+    let (mut lo, mut hi) = (estimation.clamp(2, num_snippet.max(2)), num_snippet);
+    let mut found = None;
+    while lo <= hi {
+        let mid = (lo + hi) / 2;
+        match compile_stages(mid)? {
+            Some(verifier) => {
+                found = Some(verifier);
+                hi = mid - 1;
+            }
+            None => lo = mid + 1,
+        }
+    }
+    found.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::Other,
+            format!("Failed to split verifier into stages within limit {CONTRACT_SIZE_LIMIT}"),
+        )
+    })
+}
+
+/// Run `solc` with given `output` selection and returns the last non-empty
+/// token of its stdout.
+fn run_solc(code: &str, config: &SolcConfig, output: &str) -> io::Result<Vec<u8>> {
+    let stdout = solc_output(code, config, &[output])?;
+    split_by_ascii_whitespace(&stdout).last().map(|token| token.to_vec()).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "Empty output from solc".to_string())
+    })
+}
+
+/// Run `solc` with given `args` and returns its stdout.
+fn solc_output(code: &str, config: &SolcConfig, args: &[&str]) -> io::Result<Vec<u8>> {
+    let solc = resolve_solc(config)?;
+
+    let mut cmd = Command::new(&solc);
+    cmd.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).args(args);
+    if let Some(optimizer_runs) = config.optimizer_runs {
+        cmd.arg("--optimize").arg("--optimize-runs").arg(optimizer_runs.to_string());
+    }
+    if config.via_ir {
+        cmd.arg("--via-ir");
+    }
+    if let Some(evm_version) = &config.evm_version {
+        cmd.arg("--evm-version").arg(evm_version);
+    }
+    let mut child = cmd.arg("-").spawn().map_err(|err| solc_not_found(&solc, config, err))?;
+    child.stdin.take().unwrap().write_all(code.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!(
+                "Failed to compile verifier with {}: {}",
+                solc.display(),
+                String::from_utf8_lossy(&output.stderr)
+            ),
+        ));
+    }
+
+    Ok(output.stdout)
+}
+
+fn decode_bytecode(bytecode: &[u8]) -> io::Result<Vec<u8>> {
+    hex::decode(bytecode).map_err(|err| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("Invalid bytecode from solc: {err}"))
+    })
+}
+
+fn resolve_solc(config: &SolcConfig) -> io::Result<PathBuf> {
+    if let Some(path) = &config.path {
+        if !config.fallback {
+            check_solc_version(path, config)?;
+        }
+        return Ok(path.clone());
+    }
+
+    #[cfg(feature = "svm")]
+    {
+        let resolved = semver::Version::parse(&config.version)
+            .map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid solc version {}: {err}", config.version),
+                )
+            })
+            .and_then(|version| {
+                // Binary might be installed with extension `.exe` on Windows.
+                let version_path = svm::version_path(&config.version);
+                let installed = [String::new(), std::env::consts::EXE_SUFFIX.to_string()]
+                    .into_iter()
+                    .map(|suffix| version_path.join(format!("solc-{}{suffix}", config.version)))
+                    .find(|path| path.is_file());
+                if let Some(path) = installed {
+                    return Ok(path);
+                }
+                svm::blocking_install(&version).map_err(|err| {
+                    io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("Failed to install solc {version} via svm: {err}"),
+                    )
+                })
+            });
+        match resolved {
+            Ok(path) => return Ok(path),
+            Err(err) if !config.fallback => return Err(err),
+            Err(_) => {}
+        }
+    }
+
+    // `Command` resolves it into `solc.exe` in `PATH` on Windows.
+    let solc = PathBuf::from("solc");
+    if !config.fallback {
+        check_solc_version(&solc, config)?;
+    }
+    Ok(solc)
+}
+
+fn check_solc_version(solc: &Path, config: &SolcConfig) -> io::Result<()> {
+    let output = Command::new(solc)
+        .arg("--version")
+        .output()
+        .map_err(|err| solc_not_found(solc, config, err))?;
+    let version = String::from_utf8_lossy(&output.stdout);
+    if !version.contains(&format!("Version: {}+", config.version)) {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "{} doesn't match pinned version {}, found: {}",
+                solc.display(),
+                config.version,
+                version.trim()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn solc_not_found(solc: &Path, config: &SolcConfig, err: io::Error) -> io::Error {
+    io::Error::new(
+        err.kind(),
+        format!(
+            "Failed to run {} ({err}), install solc {} or enable feature \"svm\" to manage it",
+            solc.display(),
+            config.version
+        ),
+    )
 }
 
 fn split_by_ascii_whitespace(bytes: &[u8]) -> Vec<&[u8]> {
@@ -132,3 +644,232 @@ fn split_by_ascii_whitespace(bytes: &[u8]) -> Vec<&[u8]> {
     }
     split
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        halo2_curves::bn256::{Bn256, Fq, Fr, G1Affine, G1, G2},
+        loader::{
+            evm::{
+                compile_solidity, compile_solidity_with_config, encode_bytes_call, encode_calldata,
+                execute_with_specs, modulus, spec_divergences,
+                util::{parse_combined_json, resolve_solc, SolcConfig, SOLC_VERSION},
+                Address, EvmLoader, Executor, ExecutorBuilder, SpecId, VerifierArtifact, U256,
+            },
+            EcPointLoader,
+        },
+        pcs::{
+            kzg::{Bdfg21, Kzg, KzgAccumulator, KzgDecidingKey},
+            Decider,
+        },
+        system::halo2::transcript::evm::EvmTranscript,
+        util::{
+            arithmetic::{Curve, Group},
+            hash::{Digest, Keccak256},
+            transcript::TranscriptRead,
+        },
+    };
+    use serde_json::json;
+    use std::{fs, io, path::PathBuf, process, rc::Rc};
+
+    // Writes a fake `solc` reporting `version` into a temporary directory.
+    #[cfg(unix)]
+    fn fake_solc(version: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("snark-verifier-solc-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("solc-{version}"));
+        let script = format!("#!/bin/sh\necho 'Version: {version}+commit.7dd6d404.Linux.g++'\n");
+        fs::write(&path, script).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_resolve_solc_pinned_version() {
+        assert!(!SolcConfig::default().fallback);
+
+        let solc = fake_solc(SOLC_VERSION);
+        let pinned = SolcConfig::default().with_path(&solc).set_fallback(false);
+        assert_eq!(resolve_solc(&pinned).unwrap(), solc);
+
+        let err = resolve_solc(&pinned.clone().with_version("0.8.20")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(err.to_string().contains("doesn't match pinned version 0.8.20"));
+        // Any binary is accepted when fallback is allowed.
+        let fallback = pinned.with_version("0.8.20").set_fallback(true);
+        assert_eq!(resolve_solc(&fallback).unwrap(), solc);
+
+        let missing = SolcConfig::default().with_path(solc.with_extension("missing"));
+        assert!(resolve_solc(&missing.set_fallback(false)).is_err());
+
+        fs::remove_dir_all(solc.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_parse_combined_json() {
+        let output = json!({
+            "contracts": {
+                "<stdin>:Lib": { "abi": [], "bin": "", "bin-runtime": "" },
+                "<stdin>:Verifier": {
+                    "abi": [{ "stateMutability": "nonpayable", "type": "fallback" }],
+                    "bin": "6001",
+                    "bin-runtime": "00"
+                }
+            },
+            "version": SOLC_VERSION
+        });
+        let (abi, creation_code, runtime_code) =
+            parse_combined_json(output.to_string().as_bytes()).unwrap();
+        assert_eq!(abi, r#"[{"stateMutability":"nonpayable","type":"fallback"}]"#);
+        assert_eq!(creation_code, vec![0x60, 0x01]);
+        assert_eq!(runtime_code, vec![0x00]);
+
+        // Older `solc` outputs abi as a string.
+        let output = json!({
+            "contracts": { "<stdin>:Verifier": { "abi": "[]", "bin": "00", "bin-runtime": "00" } }
+        });
+        assert_eq!(parse_combined_json(output.to_string().as_bytes()).unwrap().0, "[]");
+
+        for output in [
+            "Warning: trailing text".to_string(),
+            json!({ "contracts": {} }).to_string(),
+            json!({ "contracts": { "<stdin>:A": { "bin": "00" }, "<stdin>:B": { "bin": "00" } } })
+                .to_string(),
+            json!({ "contracts": { "<stdin>:Verifier": { "abi": [], "bin": "0x" } } }).to_string(),
+        ] {
+            let err = parse_combined_json(output.as_bytes()).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "svm")]
+    fn test_resolve_solc_invalid_version() {
+        let config = SolcConfig::default().with_version("latest").set_fallback(false);
+        assert_eq!(resolve_solc(&config).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_evm_spec_divergences() {
+        // Scalar multiplication through precompile, which only exists since
+        // Byzantium, so does `staticcall`.
+        let loader = EvmLoader::new::<Fq, Fr>();
+        let mut transcript = EvmTranscript::<G1Affine, Rc<EvmLoader>, _, _>::new(&loader);
+        let scalar = transcript.read_scalar().unwrap();
+        let base = loader.ec_point_load_const(&G1Affine::from(G1::generator()));
+        <Rc<EvmLoader> as EcPointLoader<G1Affine>>::multi_scalar_multiplication(&[(
+            &scalar, &base,
+        )]);
+        let deployment_code = compile_solidity(&loader.solidity_code());
+        let calldata = encode_calldata(&[vec![Fr::from(7)]], &[]);
+
+        let outcomes = execute_with_specs(
+            &deployment_code,
+            &calldata,
+            &[SpecId::ISTANBUL, SpecId::BERLIN, SpecId::LONDON, SpecId::LATEST],
+        );
+        assert!(outcomes.iter().all(|outcome| outcome.accepted));
+        assert!(spec_divergences(&outcomes).is_empty());
+
+        let outcomes =
+            execute_with_specs(&deployment_code, &calldata, &[SpecId::LATEST, SpecId::HOMESTEAD]);
+        let divergences = spec_divergences(&outcomes);
+        assert_eq!(divergences.len(), 1);
+        assert_eq!(divergences[0].spec_id, SpecId::HOMESTEAD);
+        assert!(!divergences[0].accepted);
+    }
+
+    #[test]
+    fn test_evm_verifier_consumer() {
+        let verifier_code = {
+            let loader = EvmLoader::new::<Fq, Fr>();
+            let mut transcript = EvmTranscript::<G1Affine, Rc<EvmLoader>, _, _>::new(&loader);
+            transcript.load_instances(vec![1]);
+            compile_solidity(&loader.solidity_code())
+        };
+        let consumer_code = compile_solidity(
+            r#"// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+contract Settlement {
+    address private immutable VERIFIER;
+    uint256 public settled;
+
+    constructor(address verifier) {
+        VERIFIER = verifier;
+    }
+
+    function settle(bytes calldata proof) external {
+        (bool success, ) = VERIFIER.staticcall(proof);
+        require(success, "Invalid proof");
+        settled += 1;
+    }
+}"#,
+        );
+
+        let mut evm = ExecutorBuilder::default().with_gas_limit(u64::MAX.into()).build();
+        let caller = Address::from_low_u64_be(0xfe);
+        let verifier = evm.deploy_with_args(caller, &verifier_code, &[]).unwrap();
+        let consumer = evm
+            .deploy_with_args(caller, &consumer_code, &[U256::from(verifier.as_bytes())])
+            .unwrap();
+
+        let settle = |evm: &mut Executor, instance: U256| {
+            let mut proof = [0; 0x20];
+            instance.to_big_endian(&mut proof);
+            let calldata = encode_bytes_call("settle(bytes)", &proof);
+            !evm.call(caller, consumer, calldata.into(), 0.into()).reverted
+        };
+        let settled = |evm: &Executor| {
+            let calldata = Keccak256::digest(b"settled()")[..4].to_vec();
+            U256::from_big_endian(&evm.call_raw(caller, consumer, calldata.into(), 0.into()).result)
+        };
+
+        assert!(settle(&mut evm, U256::one()));
+        assert!(settle(&mut evm, U256::from(2)));
+        // Non-canonical instance is rejected by the verifier, so state is unchanged.
+        assert!(!settle(&mut evm, modulus::<Fr>()));
+        assert_eq!(settled(&evm), U256::from(2));
+    }
+
+    #[test]
+    fn test_solc_config_path() {
+        let path = std::env::temp_dir().join("nonexistent").join("solc.exe");
+        for fallback in [true, false] {
+            let config = SolcConfig::default().with_path(&path).set_fallback(fallback);
+            let err = compile_solidity_with_config("contract Verifier {}", &config).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::NotFound);
+            assert!(err.to_string().contains(&path.display().to_string()));
+        }
+    }
+
+    #[test]
+    fn test_evm_verifier_artifact() {
+        let code = {
+            let loader = EvmLoader::new::<Fq, Fr>();
+            let dk = KzgDecidingKey::<Bn256>::new(
+                G2::generator().to_affine(),
+                (G2::generator() * Fr::from(7)).to_affine(),
+            );
+            let mut transcript = EvmTranscript::<G1Affine, Rc<EvmLoader>, _, _>::new(&loader);
+            let [lhs, rhs] = [(); 2].map(|_| transcript.read_ec_point().unwrap());
+            Kzg::<Bn256, Bdfg21>::decide(&dk, KzgAccumulator::new(lhs, rhs));
+            loader.solidity_code()
+        };
+
+        let artifact = VerifierArtifact::compile(code.clone(), &SolcConfig::default()).unwrap();
+        assert_eq!(artifact.source, code);
+        assert!(serde_json::from_str::<serde_json::Value>(&artifact.abi).unwrap().is_array());
+        assert_eq!(artifact.creation_code, compile_solidity(&code));
+        let runtime_code = artifact.runtime_code.as_slice();
+        assert!(!runtime_code.is_empty());
+        assert!(artifact
+            .creation_code
+            .windows(runtime_code.len())
+            .any(|code| code == runtime_code));
+        assert_eq!(artifact.codehash, <[u8; 32]>::from(Keccak256::digest(runtime_code)));
+    }
+}
@@ -4,9 +4,10 @@
 use bytes::Bytes;
 use ethereum_types::{Address, H256, U256, U64};
 use revm::{
-    evm_inner, opcode, spec_opcode_gas, Account, BlockEnv, CallInputs, CallScheme, CreateInputs,
-    CreateScheme, Database, DatabaseCommit, EVMData, Env, ExecutionResult, Gas, GasInspector,
-    InMemoryDB, Inspector, Interpreter, Memory, OpCode, Return, TransactOut, TransactTo, TxEnv,
+    evm_inner, opcode, spec_opcode_gas, Account, BlockEnv, CallInputs, CallScheme, CfgEnv,
+    CreateInputs, CreateScheme, Database, DatabaseCommit, EVMData, Env, ExecutionResult, Gas,
+    GasInspector, InMemoryDB, Inspector, Interpreter, Memory, OpCode, Return, SpecId, TransactOut,
+    TransactTo, TxEnv,
 };
 use sha3::{Digest, Keccak256};
 use std::{cell::RefCell, collections::HashMap, fmt::Display, rc::Rc};
@@ -671,6 +672,7 @@ pub struct RawCallResult {
     pub out: TransactOut,
 }
 
+/// Deployment result.
 #[derive(Clone, Debug)]
 pub struct DeployResult {
     pub exit_reason: Return,
@@ -688,6 +690,7 @@ pub struct DeployResult {
 pub struct ExecutorBuilder {
     debugger: bool,
     gas_limit: Option<U256>,
+    spec_id: Option<SpecId>,
 }
 
 impl ExecutorBuilder {
@@ -703,22 +706,35 @@ impl ExecutorBuilder {
         self
     }
 
+    /// Set `spec_id`, which defaults to [`SpecId::LATEST`].
+    pub fn with_spec_id(mut self, spec_id: SpecId) -> Self {
+        self.spec_id = Some(spec_id);
+        self
+    }
+
     /// Initialize an `Executor`.
     pub fn build(self) -> Executor {
-        Executor::new(self.debugger, self.gas_limit.unwrap_or(U256::MAX))
+        Executor::new(
+            self.debugger,
+            self.gas_limit.unwrap_or(U256::MAX),
+            self.spec_id.unwrap_or(SpecId::LATEST),
+        )
     }
 }
 
+/// EVM executor with in-memory state, which is updated by deployments and
+/// [`Executor::call`] but not by [`Executor::call_raw`].
 #[derive(Clone, Debug)]
 pub struct Executor {
     db: InMemoryDB,
     debugger: bool,
     gas_limit: U256,
+    spec_id: SpecId,
 }
 
 impl Executor {
-    fn new(debugger: bool, gas_limit: U256) -> Self {
-        Executor { db: InMemoryDB::default(), debugger, gas_limit }
+    fn new(debugger: bool, gas_limit: U256, spec_id: SpecId) -> Self {
+        Executor { db: InMemoryDB::default(), debugger, gas_limit, spec_id }
     }
 
     pub fn db_mut(&mut self) -> &mut InMemoryDB {
@@ -761,6 +777,41 @@ impl Executor {
         self.call_raw_with_env(env)
     }
 
+    /// Same as [`Executor::call_raw`] but commits state changes, for contracts
+    /// that are called across several transactions.
+    pub fn call(
+        &mut self,
+        from: Address,
+        to: Address,
+        calldata: Bytes,
+        value: U256,
+    ) -> RawCallResult {
+        let result = self.call_raw(from, to, calldata, value);
+        self.commit(&result);
+        result
+    }
+
+    /// Deploy `deployment_code` with constructor arguments `args` appended as
+    /// ABI encoded words (e.g. `U256::from(verifier.as_bytes())` for address
+    /// of a verifier deployed earlier), and returns address of the deployed
+    /// contract, so contracts depending on each other could be deployed one by
+    /// one.
+    pub fn deploy_with_args(
+        &mut self,
+        from: Address,
+        deployment_code: &[u8],
+        args: &[U256],
+    ) -> Result<Address, String> {
+        let code = args.iter().fold(deployment_code.to_vec(), |mut code, arg| {
+            let mut word = [0; 0x20];
+            arg.to_big_endian(&mut word);
+            code.extend(word);
+            code
+        });
+        let result = self.deploy(from, code.into(), 0.into());
+        result.address.ok_or_else(|| format!("Deployment reverted with {:?}", result.exit_reason))
+    }
+
     fn call_raw_with_env(&self, mut env: Env) -> RawCallResult {
         let mut inspector = self.inspector();
         let result =
@@ -813,6 +864,7 @@ impl Executor {
         value: U256,
     ) -> Env {
         Env {
+            cfg: CfgEnv { spec_id: self.spec_id, ..CfgEnv::default() },
             block: BlockEnv { gas_limit: self.gas_limit, ..BlockEnv::default() },
             tx: TxEnv {
                 caller,
@@ -0,0 +1,133 @@
+//! Verifier shared by several verifying keys of the same circuit shape (e.g.
+//! aggregation circuits of different batch sizes with the same config), so
+//! a single contract is deployed instead of one per verifying key.
+//!
+//! Instead of embedding preprocessed commitments and
+//! `transcript_initial_state` as constants, the verifier reads them from
+//! calldata, which is laid out as `vk_digest || vk || instances || proof`,
+//! where `vk` is preprocessed commitments as `(x, y)` words followed by
+//! `transcript_initial_state`, and `vk_digest` is keccak256 of `vk`. The
+//! contract is deployed with digests of accepted verifying keys as constructor
+//! argument `bytes32[] vk_digests`, and reverts with
+//! [`UNREGISTERED_VK_ERROR`](crate::loader::evm::UNREGISTERED_VK_ERROR) for
+//! others, or returns `uint256(0)` with
+//! [`ReturnConvention::Bool`](crate::loader::evm::ReturnConvention::Bool).
+//!
+//! Verifying keys are accepted only if they share everything in [`Protocol`]
+//! except preprocessed commitments and `transcript_initial_state`, which could
+//! be checked by comparing verifiers generated for each of them.
+use crate::{
+    loader::evm::{fe_to_u256, EvmLoader},
+    util::{
+        arithmetic::{CurveAffine, PrimeField},
+        hash::{Digest, Keccak256},
+    },
+    Protocol,
+};
+use ethereum_types::U256;
+use std::{iter, rc::Rc};
+
+impl<C> Protocol<C>
+where
+    C: CurveAffine,
+    C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+{
+    /// Returns [`Protocol`] with preprocessed commitments and
+    /// `transcript_initial_state` loaded from calldata by
+    /// [`EvmLoader::calldataload_vk`], whose digest is at `offset`.
+    ///
+    /// It must be called after the transcript is initialized, so
+    /// `transcript_initial_state` could be copied into its slot.
+    pub fn loaded_vk_from_calldata(
+        &self,
+        loader: &Rc<EvmLoader>,
+        offset: usize,
+    ) -> Protocol<C, Rc<EvmLoader>> {
+        let num_scalar = self.transcript_initial_state.iter().count();
+        let (preprocessed, mut scalars) =
+            loader.calldataload_vk(offset, self.preprocessed.len(), num_scalar);
+        Protocol {
+            domain: self.domain.clone(),
+            preprocessed,
+            num_instance: self.num_instance.clone(),
+            num_witness: self.num_witness.clone(),
+            num_challenge: self.num_challenge.clone(),
+            evaluations: self.evaluations.clone(),
+            queries: self.queries.clone(),
+            quotient: self.quotient.clone(),
+            transcript_initial_state: scalars.pop(),
+            instance_committing_key: self.instance_committing_key.clone(),
+            linearization: self.linearization,
+            accumulator_indices: self.accumulator_indices.clone(),
+            rotation_sets: self.rotation_sets.clone(),
+        }
+    }
+}
+
+/// Encode verifying key of `protocol` read by
+/// [`Protocol::loaded_vk_from_calldata`].
+pub fn encode_vk<C>(protocol: &Protocol<C>) -> Vec<u8>
+where
+    C: CurveAffine,
+    C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+{
+    protocol
+        .preprocessed
+        .iter()
+        .flat_map(|ec_point| {
+            let coordinates = ec_point.coordinates().unwrap();
+            [coordinates.x(), coordinates.y()]
+                .map(|coordinate| U256::from_little_endian(coordinate.to_repr().as_ref()))
+        })
+        .chain(protocol.transcript_initial_state.map(fe_to_u256))
+        .flat_map(word)
+        .collect()
+}
+
+/// Returns digest of verifying key of `protocol` to register in the verifier
+/// and pass in calldata.
+pub fn vk_digest<C>(protocol: &Protocol<C>) -> [u8; 32]
+where
+    C: CurveAffine,
+    C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+{
+    Keccak256::digest(encode_vk(protocol)).into()
+}
+
+/// Encode calldata of verifier reading verifying key of `protocol` from
+/// calldata, which is [`encode_calldata`] prefixed by digest and verifying
+/// key.
+///
+/// [`encode_calldata`]: crate::loader::evm::encode_calldata
+pub fn encode_calldata_with_vk<C>(
+    protocol: &Protocol<C>,
+    instances: &[Vec<C::Scalar>],
+    proof: &[u8],
+) -> Vec<u8>
+where
+    C: CurveAffine,
+    C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+{
+    iter::empty()
+        .chain(vk_digest(protocol))
+        .chain(encode_vk(protocol))
+        .chain(instances.iter().flatten().flat_map(|instance| word(fe_to_u256(*instance))))
+        .chain(proof.iter().copied())
+        .collect()
+}
+
+/// Returns ABI encoded constructor argument `bytes32[] vk_digests` to append
+/// to deployment code of the verifier.
+pub fn encode_vk_registry_constructor_args(vk_digests: &[[u8; 32]]) -> Vec<u8> {
+    iter::empty()
+        .chain(word(0x20.into()))
+        .chain(word(vk_digests.len().into()))
+        .chain(vk_digests.iter().flatten().copied())
+        .collect()
+}
+
+fn word(value: U256) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    value.to_big_endian(&mut bytes);
+    bytes
+}
@@ -1,4 +1,27 @@
 //! `Loader` implementation for generating verifier in [`halo2_proofs`] circuit.
+//!
+//! [`Halo2Loader`] is generic over the chip behind [`EccInstructions`], so
+//! chips other than `halo2_ecc::ecc::BaseFieldEccChip` (e.g. a GLV-optimized
+//! chip, or chips of another halo2-lib version) could be swapped in without
+//! forking the loader. Besides [`EccInstructions`], a chip implements the
+//! instructions required by the components it's used with:
+//!
+//! - [`NativeEncoding`] for [`PoseidonTranscript`] in circuit.
+//! - [`KeccakEncoding`] for [`EvmTranscript`] in circuit.
+//! - [`LimbsEncodingInstructions`] for accumulators encoded by
+//!   [`LimbsEncoding`].
+//! - [`ScalarLimbsInstructions`] for [`LimbsLoader`].
+//!
+//! All of them are implemented for `BaseFieldEccChip`, which could be used as
+//! reference implementation.
+//!
+//! [`NativeEncoding`]: crate::system::halo2::transcript::halo2::NativeEncoding
+//! [`PoseidonTranscript`]: crate::system::halo2::transcript::halo2::PoseidonTranscript
+//! [`KeccakEncoding`]: crate::system::halo2::transcript::evm::KeccakEncoding
+//! [`EvmTranscript`]: crate::system::halo2::transcript::evm::EvmTranscript
+//! [`LimbsEncodingInstructions`]: crate::pcs::kzg::LimbsEncodingInstructions
+//! [`LimbsEncoding`]: crate::pcs::kzg::LimbsEncoding
+//! [`LimbsLoader`]: crate::loader::LimbsLoader
 use crate::halo2_proofs::circuit;
 use crate::{util::arithmetic::CurveAffine, Protocol};
 use std::rc::Rc;
@@ -9,8 +32,8 @@ mod shim;
 #[cfg(test)]
 pub(crate) mod test;
 
-pub use loader::{EcPoint, Halo2Loader, Scalar};
-pub use shim::{Context, EccInstructions, IntegerInstructions};
+pub use loader::{EcPoint, Halo2Loader, OpStats, Scalar};
+pub use shim::{Context, EccInstructions, IntegerInstructions, MsmConfig, ScalarLimbsInstructions};
 pub use util::Valuetools;
 
 pub use halo2_ecc;
@@ -66,6 +89,7 @@ where
             instance_committing_key: self.instance_committing_key.clone(),
             linearization: self.linearization,
             accumulator_indices: self.accumulator_indices.clone(),
+            rotation_sets: self.rotation_sets.clone(),
         }
     }
 }
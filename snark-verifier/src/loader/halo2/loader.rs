@@ -1,11 +1,11 @@
 use crate::halo2_proofs::circuit;
 use crate::{
     loader::{
-        halo2::shim::{EccInstructions, IntegerInstructions},
-        EcPointLoader, LoadedEcPoint, LoadedScalar, Loader, ScalarLoader,
+        halo2::shim::{EccInstructions, IntegerInstructions, MsmConfig, ScalarLimbsInstructions},
+        EcPointLoader, LimbsLoader, LoadedEcPoint, LoadedScalar, Loader, ScalarLoader,
     },
     util::{
-        arithmetic::{CurveAffine, Field, FieldOps},
+        arithmetic::{powers, CurveAffine, Field, FieldOps},
         Itertools,
     },
 };
@@ -13,7 +13,7 @@ use std::{
     cell::{Ref, RefCell, RefMut},
     fmt::{self, Debug},
     marker::PhantomData,
-    ops::{Add, AddAssign, Deref, Mul, MulAssign, Neg, Sub, SubAssign},
+    ops::{Add, AddAssign, Deref, Mul, MulAssign, Neg, Range, Sub, SubAssign},
     rc::Rc,
 };
 
@@ -24,6 +24,10 @@ pub struct Halo2Loader<'a, C: CurveAffine, EccChip: EccInstructions<'a, C>> {
     ctx: RefCell<EccChip::Context>,
     num_scalar: RefCell<usize>,
     num_ec_point: RefCell<usize>,
+    op_stats: RefCell<OpStats>,
+    op_meterings: RefCell<Vec<(String, OpStats)>>,
+    witness_spans: RefCell<Vec<(String, usize)>>,
+    msm_config: MsmConfig,
     _marker: PhantomData<C>,
     #[cfg(test)]
     row_meterings: RefCell<Vec<(String, usize)>>,
@@ -33,11 +37,25 @@ impl<'a, C: CurveAffine, EccChip: EccInstructions<'a, C>> Halo2Loader<'a, C, Ecc
     /// Initialize a [`Halo2Loader`] with given [`EccInstructions`] and
     /// [`EccInstructions::Context`].
     pub fn new(ecc_chip: EccChip, ctx: EccChip::Context) -> Rc<Self> {
+        Self::with_msm_config(ecc_chip, ctx, MsmConfig::default())
+    }
+
+    /// Initialize a [`Halo2Loader`] with given [`EccInstructions`],
+    /// [`EccInstructions::Context`] and [`MsmConfig`] for all MSMs.
+    pub fn with_msm_config(
+        ecc_chip: EccChip,
+        ctx: EccChip::Context,
+        msm_config: MsmConfig,
+    ) -> Rc<Self> {
         Rc::new(Self {
             ecc_chip: RefCell::new(ecc_chip),
             ctx: RefCell::new(ctx),
             num_scalar: RefCell::default(),
             num_ec_point: RefCell::default(),
+            op_stats: RefCell::default(),
+            op_meterings: RefCell::default(),
+            witness_spans: RefCell::default(),
+            msm_config,
             #[cfg(test)]
             row_meterings: RefCell::default(),
             _marker: PhantomData,
@@ -69,6 +87,67 @@ impl<'a, C: CurveAffine, EccChip: EccInstructions<'a, C>> Halo2Loader<'a, C, Ecc
         self.ctx.borrow_mut()
     }
 
+    /// Returns [`MsmConfig`] for all MSMs.
+    pub fn msm_config(&self) -> &MsmConfig {
+        &self.msm_config
+    }
+
+    /// Returns [`OpStats`] of all operations performed so far.
+    pub fn op_stats(&self) -> OpStats {
+        *self.op_stats.borrow()
+    }
+
+    /// Start attributing operations to `identifier` until
+    /// [`Halo2Loader::end_op_metering`], e.g. per snark being aggregated.
+    pub fn start_op_metering(&self, identifier: &str) {
+        self.op_meterings.borrow_mut().push((identifier.to_string(), self.op_stats()));
+    }
+
+    /// End the latest [`Halo2Loader::start_op_metering`].
+    pub fn end_op_metering(&self) {
+        let mut op_meterings = self.op_meterings.borrow_mut();
+        let (_, stats) = op_meterings.last_mut().unwrap();
+        *stats = self.op_stats() - *stats;
+    }
+
+    /// Returns [`OpStats`] of each metered section in order.
+    pub fn op_meterings(&self) -> Vec<(String, OpStats)> {
+        self.op_meterings.borrow().clone()
+    }
+
+    /// Attribute witness assigned from now on to `identifier` until the next
+    /// call, e.g. per verification step of each snark being aggregated, so
+    /// failures found by `MockProver` could be traced back to the step.
+    pub fn start_witness_span(&self, identifier: &str) {
+        use crate::loader::halo2::shim::Context;
+
+        let offset = self.ctx().offset();
+        self.witness_spans.borrow_mut().push((identifier.to_string(), offset));
+    }
+
+    /// Returns each span started by [`Halo2Loader::start_witness_span`] in
+    /// order, with range of offsets (see [`Context::offset`]) of witness
+    /// assigned in it, where the last one ends at the current offset.
+    ///
+    /// [`Context::offset`]: crate::loader::halo2::Context::offset
+    pub fn witness_spans(&self) -> Vec<(String, Range<usize>)> {
+        use crate::loader::halo2::shim::Context;
+
+        let witness_spans = self.witness_spans.borrow();
+        let ends = witness_spans.iter().skip(1).map(|(_, start)| *start);
+        witness_spans
+            .iter()
+            .zip(ends.chain([self.ctx().offset()]))
+            .map(|((identifier, start), end)| (identifier.clone(), *start..end))
+            .collect()
+    }
+
+    /// Record `n` Poseidon permutations performed by a transcript on this
+    /// loader, which doesn't go through the loader itself.
+    pub(crate) fn record_poseidon_permutations(&self, n: usize) {
+        self.op_stats.borrow_mut().poseidon_permutation += n;
+    }
+
     fn assign_const_scalar(self: &Rc<Self>, constant: C::Scalar) -> EccChip::AssignedScalar {
         self.scalar_chip().assign_constant(&mut self.ctx_mut(), constant).unwrap()
     }
@@ -109,6 +188,7 @@ impl<'a, C: CurveAffine, EccChip: EccInstructions<'a, C>> Halo2Loader<'a, C, Ecc
         ec_point: circuit::Value<C>,
     ) -> EcPoint<'a, C, EccChip> {
         let assigned = self.ecc_chip().assign_point(&mut self.ctx_mut(), ec_point).unwrap();
+        self.op_stats.borrow_mut().range_check += 2;
         self.ec_point_from_assigned(assigned)
     }
 
@@ -120,6 +200,21 @@ impl<'a, C: CurveAffine, EccChip: EccInstructions<'a, C>> Halo2Loader<'a, C, Ecc
         self.ec_point(Value::Assigned(assigned))
     }
 
+    /// Returns `lhs` if `condition` is `1`, otherwise `rhs`. Note that
+    /// `condition` is assumed to be boolean, which should be constrained by
+    /// caller.
+    pub fn ec_point_select(
+        self: &Rc<Self>,
+        lhs: &EcPoint<'a, C, EccChip>,
+        rhs: &EcPoint<'a, C, EccChip>,
+        condition: &Scalar<'a, C, EccChip>,
+    ) -> EcPoint<'a, C, EccChip> {
+        let [lhs, rhs] = [lhs, rhs].map(|ec_point| ec_point.assigned().clone());
+        let condition = condition.assigned().clone();
+        let output = self.ecc_chip().select(&mut self.ctx_mut(), &lhs, &rhs, &condition).unwrap();
+        self.ec_point_from_assigned(output)
+    }
+
     fn ec_point(
         self: &Rc<Self>,
         value: Value<C, EccChip::AssignedEcPoint>,
@@ -274,6 +369,48 @@ impl<'a, C: CurveAffine, EccChip: EccInstructions<'a, C>> Halo2Loader<'a, C, Ecc
     }
 }
 
+/// Counts of operations performed by [`Halo2Loader`], to attribute circuit
+/// size to them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OpStats {
+    /// Number of elliptic curve additions, not counting ones inside MSMs.
+    pub ec_add: usize,
+    /// Number of elliptic curve scalar multiplications, each as a term of
+    /// fixed-base or variable-base MSM.
+    pub ec_mul: usize,
+    /// Number of Poseidon permutations performed by transcript.
+    pub poseidon_permutation: usize,
+    /// Number of base field elements range checked when assigned, which are
+    /// coordinates of elliptic curve points read from proofs.
+    pub range_check: usize,
+}
+
+impl Add for OpStats {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            ec_add: self.ec_add + rhs.ec_add,
+            ec_mul: self.ec_mul + rhs.ec_mul,
+            poseidon_permutation: self.poseidon_permutation + rhs.poseidon_permutation,
+            range_check: self.range_check + rhs.range_check,
+        }
+    }
+}
+
+impl Sub for OpStats {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self {
+            ec_add: self.ec_add - rhs.ec_add,
+            ec_mul: self.ec_mul - rhs.ec_mul,
+            poseidon_permutation: self.poseidon_permutation - rhs.poseidon_permutation,
+            range_check: self.range_check - rhs.range_check,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum Value<T, L> {
     Constant(T),
@@ -333,7 +470,7 @@ impl<'a, C: CurveAffine, EccChip: EccInstructions<'a, C>> Scalar<'a, C, EccChip>
         self.value.borrow()
     }
 
-    fn maybe_const(&self) -> Option<C::Scalar> {
+    pub(crate) fn maybe_const(&self) -> Option<C::Scalar> {
         self.value().deref().maybe_const()
     }
 }
@@ -576,6 +713,43 @@ impl<'a, C: CurveAffine, EccChip: EccInstructions<'a, C>> ScalarLoader<C::Scalar
     }
 }
 
+impl<'a, C: CurveAffine, EccChip: ScalarLimbsInstructions<'a, C>> LimbsLoader<C::Scalar>
+    for Rc<Halo2Loader<'a, C, EccChip>>
+{
+    fn scalar_to_limbs<const LIMBS: usize, const BITS: usize>(
+        &self,
+        scalar: &Scalar<'a, C, EccChip>,
+    ) -> Result<[Scalar<'a, C, EccChip>; LIMBS], crate::Error> {
+        let scalar = scalar.assigned();
+        let limbs = self
+            .ecc_chip()
+            .decompose_scalar(&mut self.ctx_mut(), &scalar, LIMBS, BITS)
+            .map_err(|_| crate::Error::AssertionFailure("scalar_to_limbs".to_string()))?;
+        Ok(limbs
+            .into_iter()
+            .map(|limb| self.scalar_from_assigned(limb))
+            .collect_vec()
+            .try_into()
+            .unwrap())
+    }
+
+    fn scalar_from_limbs<const LIMBS: usize, const BITS: usize>(
+        &self,
+        limbs: [&Scalar<'a, C, EccChip>; LIMBS],
+    ) -> Result<Scalar<'a, C, EccChip>, crate::Error> {
+        for limb in limbs {
+            let limb = limb.assigned();
+            self.ecc_chip()
+                .range_check_scalar(&mut self.ctx_mut(), &limb, BITS)
+                .map_err(|_| crate::Error::AssertionFailure("scalar_from_limbs".to_string()))?;
+        }
+
+        let radix = C::Scalar::from(2).pow_vartime([BITS as u64]);
+        let values = powers(radix).zip(limbs).collect_vec();
+        Ok(self.sum_with_coeff(&values))
+    }
+}
+
 impl<'a, C: CurveAffine, EccChip: EccInstructions<'a, C>> EcPointLoader<C>
     for Rc<Halo2Loader<'a, C, EccChip>>
 {
@@ -624,7 +798,9 @@ impl<'a, C: CurveAffine, EccChip: EccInstructions<'a, C>> EcPointLoader<C>
                         (Value::Constant(scalar), Value::Constant(base)) => {
                             constant = (*base * scalar + constant).into()
                         }
-                        (Value::Assigned(_), Value::Constant(base)) => {
+                        (Value::Assigned(_), Value::Constant(base))
+                            if loader.msm_config.fixed_base =>
+                        {
                             fixed_base.push((scalar, *base))
                         }
                         (Value::Constant(scalar), Value::Assigned(_))
@@ -638,6 +814,16 @@ impl<'a, C: CurveAffine, EccChip: EccInstructions<'a, C>> EcPointLoader<C>
                 },
             );
 
+        {
+            let num_summand = variable_base_non_scaled.len()
+                + usize::from(!fixed_base.is_empty())
+                + usize::from(!variable_base_scaled.is_empty())
+                + usize::from(bool::from(!constant.is_identity()));
+            let mut op_stats = loader.op_stats.borrow_mut();
+            op_stats.ec_mul += fixed_base.len() + variable_base_scaled.len();
+            op_stats.ec_add += num_summand.saturating_sub(1);
+        }
+
         let fixed_base_msm = (!fixed_base.is_empty())
             .then(|| {
                 let fixed_base = fixed_base
@@ -647,7 +833,11 @@ impl<'a, C: CurveAffine, EccChip: EccInstructions<'a, C>> EcPointLoader<C>
                 loader
                     .ecc_chip
                     .borrow_mut()
-                    .fixed_base_msm(&mut loader.ctx_mut(), &fixed_base)
+                    .fixed_base_msm_with_config(
+                        &mut loader.ctx_mut(),
+                        &fixed_base,
+                        &loader.msm_config,
+                    )
                     .unwrap()
             })
             .map(RefCell::new);
@@ -660,7 +850,11 @@ impl<'a, C: CurveAffine, EccChip: EccInstructions<'a, C>> EcPointLoader<C>
                 loader
                     .ecc_chip
                     .borrow_mut()
-                    .variable_base_msm(&mut loader.ctx_mut(), &variable_base_scaled)
+                    .variable_base_msm_with_config(
+                        &mut loader.ctx_mut(),
+                        &variable_base_scaled,
+                        &loader.msm_config,
+                    )
                     .unwrap()
             })
             .map(RefCell::new);
@@ -3,11 +3,36 @@ use crate::halo2_proofs::{
     plonk::Error,
 };
 use crate::util::arithmetic::{CurveAffine, FieldExt};
+use serde::{Deserialize, Serialize};
 use std::{fmt::Debug, ops::Deref};
 
+/// Parameters of multi-scalar multiplications performed by
+/// [`EccInstructions`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MsmConfig {
+    /// Window bits of variable-base MSM.
+    pub window_bits: usize,
+    /// Radix of fixed-base MSM, where `0` lets the chip choose.
+    pub fixed_base_radix: usize,
+    /// Clump factor of fixed-base MSM.
+    pub fixed_base_clump_factor: usize,
+    /// Whether to multiply constant bases (e.g. preprocessed commitments of
+    /// protocol loaded as constants) by fixed-base MSM with precomputed tables,
+    /// otherwise they are assigned and multiplied by variable-base MSM.
+    pub fixed_base: bool,
+}
+
+impl Default for MsmConfig {
+    fn default() -> Self {
+        Self { window_bits: 4, fixed_base_radix: 0, fixed_base_clump_factor: 4, fixed_base: true }
+    }
+}
+
 pub trait Context: Debug {
     fn constrain_equal(&mut self, lhs: Cell, rhs: Cell) -> Result<(), Error>;
 
+    /// Returns offset of the next assigned cell, which only increases.
     fn offset(&self) -> usize;
 }
 
@@ -146,6 +171,31 @@ pub trait EccInstructions<'a, C: CurveAffine>: Clone + Debug {
         )],
     ) -> Result<Self::AssignedEcPoint, Error>;
 
+    /// Perform fixed base multi-scalar multiplication with [`MsmConfig`],
+    /// which is ignored by default.
+    fn fixed_base_msm_with_config(
+        &mut self,
+        ctx: &mut Self::Context,
+        pairs: &[(impl Deref<Target = Self::AssignedScalar>, C)],
+        _config: &MsmConfig,
+    ) -> Result<Self::AssignedEcPoint, Error> {
+        self.fixed_base_msm(ctx, pairs)
+    }
+
+    /// Perform variable base multi-scalar multiplication with [`MsmConfig`],
+    /// which is ignored by default.
+    fn variable_base_msm_with_config(
+        &mut self,
+        ctx: &mut Self::Context,
+        pairs: &[(
+            impl Deref<Target = Self::AssignedScalar>,
+            impl Deref<Target = Self::AssignedEcPoint>,
+        )],
+        _config: &MsmConfig,
+    ) -> Result<Self::AssignedEcPoint, Error> {
+        self.variable_base_msm(ctx, pairs)
+    }
+
     /// Enforce `lhs` and `rhs` are equal.
     fn assert_equal(
         &self,
@@ -153,6 +203,38 @@ pub trait EccInstructions<'a, C: CurveAffine>: Clone + Debug {
         lhs: &Self::AssignedEcPoint,
         rhs: &Self::AssignedEcPoint,
     ) -> Result<(), Error>;
+
+    /// Returns `lhs` if `condition` is `1`, otherwise `rhs`, assuming
+    /// `condition` is boolean.
+    fn select(
+        &self,
+        ctx: &mut Self::Context,
+        lhs: &Self::AssignedEcPoint,
+        rhs: &Self::AssignedEcPoint,
+        condition: &Self::AssignedScalar,
+    ) -> Result<Self::AssignedEcPoint, Error>;
+}
+
+/// Instructions to decompose scalar field elements into range checked limbs.
+pub trait ScalarLimbsInstructions<'a, C: CurveAffine>: EccInstructions<'a, C> {
+    /// Decompose `scalar` into `num_limbs` little-endian limbs, each range
+    /// checked to have at most `bits` bits, and constrain them to recompose
+    /// `scalar`.
+    fn decompose_scalar(
+        &self,
+        ctx: &mut Self::Context,
+        scalar: &Self::AssignedScalar,
+        num_limbs: usize,
+        bits: usize,
+    ) -> Result<Vec<Self::AssignedScalar>, Error>;
+
+    /// Enforce `scalar` to have at most `bits` bits.
+    fn range_check_scalar(
+        &self,
+        ctx: &mut Self::Context,
+        scalar: &Self::AssignedScalar,
+        bits: usize,
+    ) -> Result<(), Error>;
 }
 
 mod halo2_lib {
@@ -162,8 +244,13 @@ mod halo2_lib {
         plonk::Error,
     };
     use crate::{
-        loader::halo2::{Context, EccInstructions, IntegerInstructions},
-        util::arithmetic::{CurveAffine, Field},
+        loader::halo2::{
+            Context, EccInstructions, IntegerInstructions, MsmConfig, ScalarLimbsInstructions,
+        },
+        util::{
+            arithmetic::{fe_from_big, fe_to_big, powers, CurveAffine, Field},
+            Itertools,
+        },
     };
     use halo2_base::{
         self,
@@ -177,6 +264,8 @@ mod halo2_lib {
         ecc::{fixed_base::FixedEcPoint, BaseFieldEccChip, EcPoint},
         fields::FieldChip,
     };
+    use num_bigint::BigUint;
+    use num_traits::One;
     use std::ops::Deref;
 
     type AssignedInteger<C> = CRTInteger<<C as CurveAffine>::ScalarExt>;
@@ -191,8 +280,12 @@ mod halo2_lib {
             Ok(())
         }
 
+        /// Returns `column * max_rows + row` of the next advice cell of the
+        /// first context in the first phase, which is where all witness of
+        /// [`Halo2Loader`](crate::loader::halo2::Halo2Loader) is assigned.
         fn offset(&self) -> usize {
-            unreachable!()
+            let (column, row) = self.advice_alloc[0][0];
+            column * self.max_rows + row
         }
     }
 
@@ -362,6 +455,26 @@ mod halo2_lib {
                 impl Deref<Target = Self::AssignedScalar>,
                 impl Deref<Target = Self::AssignedEcPoint>,
             )],
+        ) -> Result<Self::AssignedEcPoint, Error> {
+            self.variable_base_msm_with_config(ctx, pairs, &MsmConfig::default())
+        }
+
+        fn fixed_base_msm(
+            &mut self,
+            ctx: &mut Self::Context,
+            pairs: &[(impl Deref<Target = Self::AssignedScalar>, C)],
+        ) -> Result<Self::AssignedEcPoint, Error> {
+            self.fixed_base_msm_with_config(ctx, pairs, &MsmConfig::default())
+        }
+
+        fn variable_base_msm_with_config(
+            &mut self,
+            ctx: &mut Self::Context,
+            pairs: &[(
+                impl Deref<Target = Self::AssignedScalar>,
+                impl Deref<Target = Self::AssignedEcPoint>,
+            )],
+            config: &MsmConfig,
         ) -> Result<Self::AssignedEcPoint, Error> {
             let (scalars, points): (Vec<_>, Vec<_>) = pairs
                 .iter()
@@ -374,14 +487,15 @@ mod halo2_lib {
                 &points,
                 &scalars,
                 C::Scalar::NUM_BITS as usize,
-                4, // empirically clump factor of 4 seems to be best
+                config.window_bits,
             ))
         }
 
-        fn fixed_base_msm(
+        fn fixed_base_msm_with_config(
             &mut self,
             ctx: &mut Self::Context,
             pairs: &[(impl Deref<Target = Self::AssignedScalar>, C)],
+            config: &MsmConfig,
         ) -> Result<Self::AssignedEcPoint, Error> {
             let (scalars, points): (Vec<_>, Vec<_>) = pairs
                 .iter()
@@ -400,8 +514,8 @@ mod halo2_lib {
                 &points,
                 &scalars,
                 C::Scalar::NUM_BITS as usize,
-                0,
-                4,
+                config.fixed_base_radix,
+                config.fixed_base_clump_factor,
             ))
         }
 
@@ -414,5 +528,63 @@ mod halo2_lib {
             self.assert_equal(ctx, a, b);
             Ok(())
         }
+
+        fn select(
+            &self,
+            ctx: &mut Self::Context,
+            a: &Self::AssignedEcPoint,
+            b: &Self::AssignedEcPoint,
+            condition: &Self::AssignedScalar,
+        ) -> Result<Self::AssignedEcPoint, Error> {
+            Ok(self.select(ctx, a, b, condition))
+        }
+    }
+
+    impl<'a, C: CurveAffineExt> ScalarLimbsInstructions<'a, C> for BaseFieldEccChip<C>
+    where
+        C::ScalarExt: PrimeField,
+        C::Base: PrimeField,
+    {
+        fn decompose_scalar(
+            &self,
+            ctx: &mut Self::Context,
+            scalar: &Self::AssignedScalar,
+            num_limbs: usize,
+            bits: usize,
+        ) -> Result<Vec<Self::AssignedScalar>, Error> {
+            let scalar_chip = EccInstructions::scalar_chip(self);
+            let limbs = (0..num_limbs)
+                .map(|idx| {
+                    let limb = scalar.value().map(|scalar| {
+                        fe_from_big((fe_to_big(*scalar) >> (idx * bits)) % (BigUint::one() << bits))
+                    });
+                    let limb = IntegerInstructions::assign_integer(scalar_chip, ctx, limb)?;
+                    self.range_check_scalar(ctx, &limb, bits)?;
+                    Ok(limb)
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+
+            let radix = C::Scalar::from(2).pow_vartime([bits as u64]);
+            let values = powers(radix).zip(limbs.iter()).collect_vec();
+            let recomposed = IntegerInstructions::sum_with_coeff_and_const(
+                scalar_chip,
+                ctx,
+                &values,
+                C::Scalar::zero(),
+            )?;
+            IntegerInstructions::assert_equal(scalar_chip, ctx, &recomposed, scalar)?;
+
+            Ok(limbs)
+        }
+
+        fn range_check_scalar(
+            &self,
+            ctx: &mut Self::Context,
+            scalar: &Self::AssignedScalar,
+            bits: usize,
+        ) -> Result<(), Error> {
+            self.field_chip.range().range_check(ctx, scalar, bits);
+            Ok(())
+        }
     }
 }
@@ -0,0 +1,614 @@
+//! `Loader` implementation for generating verifier as a [Move] module on
+//! Aptos, which relies on BN254 natives of `aptos_std::crypto_algebra`. Sui
+//! only exposes a fixed Groth16 verifier for BN254 instead of group operations,
+//! so it's not supported.
+//!
+//! The generated `verify(calldata: vector<u8>): bool` takes the same calldata
+//! as the EVM verifier (instances followed by proof, all in 32 bytes
+//! big-endian words), and [`MoveTranscript`] replays the same Keccak256
+//! transcript as [`EvmTranscript`], so proofs generated for the EVM verifier
+//! are accepted as is.
+//!
+//! Every loaded value is pushed into a vector instead of being bound to a
+//! local, since Move limits number of locals per function. Protocols with many
+//! polynomials could still exceed the size limit of a single function or
+//! module.
+//!
+//! [Move]: https://aptos.dev/move/move-on-aptos
+//! [`EvmTranscript`]: crate::system::halo2::transcript::evm::EvmTranscript
+use crate::{
+    halo2_curves::bn256::{Fr, G1Affine, G2Affine},
+    loader::{EcPointLoader, LoadedEcPoint, LoadedScalar, Loader, ScalarLoader},
+    util::{
+        arithmetic::{Coordinates, CurveAffine, Field, FieldOps, PrimeField},
+        transcript::{Transcript, TranscriptRead},
+        Itertools,
+    },
+    Error,
+};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt::{self, Debug},
+    iter,
+    ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+    rc::Rc,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Constant(Fr),
+    /// Index in vector `s` of scalars or `p` of ec points.
+    Index(usize),
+}
+
+/// `Loader` implementation for generating Move verifier module.
+#[derive(Debug, Default)]
+pub struct MoveLoader {
+    code: RefCell<String>,
+    num_scalar: RefCell<usize>,
+    num_ec_point: RefCell<usize>,
+    scalar_constants: RefCell<HashMap<[u8; 32], usize>>,
+    ec_point_constants: RefCell<HashMap<Vec<u8>, usize>>,
+    calldata_len: RefCell<usize>,
+}
+
+fn hex_bytes(bytes: &[u8]) -> String {
+    format!("x\"{}\"", hex::encode(bytes))
+}
+
+impl MoveLoader {
+    /// Initialize a [`MoveLoader`].
+    pub fn new() -> Rc<Self> {
+        Rc::new(Self::default())
+    }
+
+    /// Returns generated Move module `{address}::{name}`, with entry
+    /// `public fun verify(calldata: vector<u8>): bool` which aborts or returns
+    /// `false` on invalid proof.
+    pub fn move_code(&self, address: &str, name: &str) -> String {
+        let mut two_128 = [0; 32];
+        two_128[16] = 1;
+        let two_128 = hex_bytes(&two_128);
+        let calldata_len = *self.calldata_len.borrow();
+        let runtime = self.code.borrow();
+        format!(
+            "module {address}::{name} {{
+    use std::option;
+    use std::vector;
+    use aptos_std::aptos_hash;
+    use aptos_std::bn254_algebra::{{
+        FormatFqMsb, FormatFrLsb, FormatFrMsb, FormatG1Uncompr, FormatG2Uncompr, Fq, Fr, G1, G2,
+        Gt,
+    }};
+    use aptos_std::crypto_algebra::{{Self, Element}};
+
+    fun fr(bytes: vector<u8>): Element<Fr> {{
+        option::destroy_some(crypto_algebra::deserialize<Fr, FormatFrLsb>(&bytes))
+    }}
+
+    fun fr_be(bytes: vector<u8>): Element<Fr> {{
+        option::destroy_some(crypto_algebra::deserialize<Fr, FormatFrMsb>(&bytes))
+    }}
+
+    fun g1(bytes: vector<u8>): Element<G1> {{
+        option::destroy_some(crypto_algebra::deserialize<G1, FormatG1Uncompr>(&bytes))
+    }}
+
+    fun g2(bytes: vector<u8>): Element<G2> {{
+        option::destroy_some(crypto_algebra::deserialize<G2, FormatG2Uncompr>(&bytes))
+    }}
+
+    fun inv(x: &Element<Fr>): Element<Fr> {{
+        option::destroy_some(crypto_algebra::inv(x))
+    }}
+
+    fun msm(points: vector<Element<G1>>, scalars: vector<Element<Fr>>): Element<G1> {{
+        crypto_algebra::multi_scalar_mul<G1, Fr>(&points, &scalars)
+    }}
+
+    fun pairing_check(g1s: vector<Element<G1>>, g2s: vector<Element<G2>>): bool {{
+        let gt = crypto_algebra::multi_pairing<G1, G2, Gt>(&g1s, &g2s);
+        let one = crypto_algebra::zero<Gt>();
+        crypto_algebra::eq(&gt, &one)
+    }}
+
+    // Aborts if the word is not less than modulus of Fr.
+    fun fr_at(calldata: &vector<u8>, offset: u64): Element<Fr> {{
+        fr_be(vector::slice(calldata, offset, offset + 32))
+    }}
+
+    // Aborts if any coordinate is not less than modulus of Fq, or the point is
+    // not on curve.
+    fun g1_at(calldata: &vector<u8>, offset: u64): Element<G1> {{
+        let x = vector::slice(calldata, offset, offset + 32);
+        let y = vector::slice(calldata, offset + 32, offset + 64);
+        option::destroy_some(crypto_algebra::deserialize<Fq, FormatFqMsb>(&x));
+        option::destroy_some(crypto_algebra::deserialize<Fq, FormatFqMsb>(&y));
+        vector::reverse(&mut x);
+        vector::reverse(&mut y);
+        vector::append(&mut x, y);
+        g1(x)
+    }}
+
+    fun absorb_scalar(buf: &mut vector<u8>, x: &Element<Fr>) {{
+        vector::append(buf, crypto_algebra::serialize<Fr, FormatFrMsb>(x));
+    }}
+
+    fun absorb_ec_point(buf: &mut vector<u8>, p: &Element<G1>) {{
+        let bytes = crypto_algebra::serialize<G1, FormatG1Uncompr>(p);
+        let x = vector::slice(&bytes, 0, 32);
+        let y = vector::slice(&bytes, 32, 64);
+        vector::reverse(&mut x);
+        vector::reverse(&mut y);
+        vector::append(buf, x);
+        vector::append(buf, y);
+    }}
+
+    // Same as challenge of EVM transcript, with 256 bits hash reduced modulo
+    // Fr as `hi * 2^128 + lo`.
+    fun squeeze_challenge(buf: &mut vector<u8>): Element<Fr> {{
+        if (vector::length(buf) == 32) vector::push_back(buf, 1);
+        let hash = aptos_hash::keccak256(*buf);
+        *buf = copy hash;
+        let hi = x\"00000000000000000000000000000000\";
+        let lo = x\"00000000000000000000000000000000\";
+        vector::append(&mut hi, vector::slice(&hash, 0, 16));
+        vector::append(&mut lo, vector::slice(&hash, 16, 32));
+        let shift = fr({two_128});
+        let hi = fr_be(hi);
+        let lo = fr_be(lo);
+        let hi = crypto_algebra::mul(&hi, &shift);
+        crypto_algebra::add(&hi, &lo)
+    }}
+
+    public fun verify(calldata: vector<u8>): bool {{
+        let s = vector::empty<Element<Fr>>();
+        let p = vector::empty<Element<G1>>();
+        let buf = vector::empty<u8>();
+        let t = crypto_algebra::zero<Fr>();
+        let q = crypto_algebra::zero<G1>();
+        let success = vector::length(&calldata) == {calldata_len};
+{runtime}
+        success
+    }}
+}}
+"
+        )
+    }
+
+    fn append(&self, code: impl AsRef<str>) {
+        let mut runtime = self.code.borrow_mut();
+        runtime.push_str("        ");
+        runtime.push_str(code.as_ref());
+        runtime.push('\n');
+    }
+
+    fn scalar(self: &Rc<Self>, value: Value) -> Scalar {
+        Scalar { loader: self.clone(), value }
+    }
+
+    fn ec_point(self: &Rc<Self>, idx: usize) -> EcPoint {
+        EcPoint { loader: self.clone(), idx }
+    }
+
+    fn push_scalar(self: &Rc<Self>, expr: String) -> usize {
+        self.append(format!("t = {expr};"));
+        self.append("vector::push_back(&mut s, t);");
+        let idx = *self.num_scalar.borrow();
+        *self.num_scalar.borrow_mut() += 1;
+        idx
+    }
+
+    fn push_ec_point(self: &Rc<Self>, expr: String) -> EcPoint {
+        self.append(format!("q = {expr};"));
+        self.append("vector::push_back(&mut p, q);");
+        let idx = *self.num_ec_point.borrow();
+        *self.num_ec_point.borrow_mut() += 1;
+        self.ec_point(idx)
+    }
+
+    /// Returns expression of reference to `scalar`, which pushes it first if
+    /// it's a constant not pushed yet.
+    fn scalar_ref(self: &Rc<Self>, scalar: &Scalar) -> String {
+        let idx = match scalar.value {
+            Value::Index(idx) => idx,
+            Value::Constant(constant) => {
+                let repr = constant.to_repr();
+                if let Some(idx) = self.scalar_constants.borrow().get(&repr).copied() {
+                    return format!("vector::borrow(&s, {idx})");
+                }
+                let idx = self.push_scalar(format!("fr({})", hex_bytes(&repr)));
+                self.scalar_constants.borrow_mut().insert(repr, idx);
+                idx
+            }
+        };
+        format!("vector::borrow(&s, {idx})")
+    }
+
+    fn ec_point_ref(&self, ec_point: &EcPoint) -> String {
+        format!("vector::borrow(&p, {})", ec_point.idx)
+    }
+
+    fn push_op(self: &Rc<Self>, op: &str, operands: &[&Scalar]) -> Scalar {
+        let operands = operands.iter().map(|operand| self.scalar_ref(operand)).join(", ");
+        let idx = self.push_scalar(format!("{op}({operands})"));
+        self.scalar(Value::Index(idx))
+    }
+
+    fn add(self: &Rc<Self>, lhs: &Scalar, rhs: &Scalar) -> Scalar {
+        match (&lhs.value, &rhs.value) {
+            (Value::Constant(lhs), Value::Constant(rhs)) => {
+                self.scalar(Value::Constant(*lhs + rhs))
+            }
+            _ => self.push_op("crypto_algebra::add", &[lhs, rhs]),
+        }
+    }
+
+    fn sub(self: &Rc<Self>, lhs: &Scalar, rhs: &Scalar) -> Scalar {
+        match (&lhs.value, &rhs.value) {
+            (Value::Constant(lhs), Value::Constant(rhs)) => {
+                self.scalar(Value::Constant(*lhs - rhs))
+            }
+            _ => self.push_op("crypto_algebra::sub", &[lhs, rhs]),
+        }
+    }
+
+    fn mul(self: &Rc<Self>, lhs: &Scalar, rhs: &Scalar) -> Scalar {
+        match (&lhs.value, &rhs.value) {
+            (Value::Constant(lhs), Value::Constant(rhs)) => {
+                self.scalar(Value::Constant(*lhs * rhs))
+            }
+            _ => self.push_op("crypto_algebra::mul", &[lhs, rhs]),
+        }
+    }
+
+    fn neg(self: &Rc<Self>, scalar: &Scalar) -> Scalar {
+        match &scalar.value {
+            Value::Constant(constant) => self.scalar(Value::Constant(-*constant)),
+            _ => self.push_op("crypto_algebra::neg", &[scalar]),
+        }
+    }
+
+    fn invert(self: &Rc<Self>, scalar: &Scalar) -> Scalar {
+        match &scalar.value {
+            Value::Constant(constant) => {
+                self.scalar(Value::Constant(Option::from(constant.invert()).unwrap_or_default()))
+            }
+            _ => self.push_op("inv", &[scalar]),
+        }
+    }
+
+    fn calldataload_scalar(self: &Rc<Self>, offset: usize) -> Scalar {
+        self.extend_calldata(offset + 0x20);
+        let idx = self.push_scalar(format!("fr_at(&calldata, {offset})"));
+        self.scalar(Value::Index(idx))
+    }
+
+    fn calldataload_ec_point(self: &Rc<Self>, offset: usize) -> EcPoint {
+        self.extend_calldata(offset + 0x40);
+        self.push_ec_point(format!("g1_at(&calldata, {offset})"))
+    }
+
+    fn extend_calldata(&self, len: usize) {
+        let mut calldata_len = self.calldata_len.borrow_mut();
+        *calldata_len = (*calldata_len).max(len);
+    }
+
+    fn absorb_scalar(self: &Rc<Self>, scalar: &Scalar) {
+        let scalar = self.scalar_ref(scalar);
+        self.append(format!("absorb_scalar(&mut buf, {scalar});"));
+    }
+
+    fn absorb_ec_point(self: &Rc<Self>, ec_point: &EcPoint) {
+        let ec_point = self.ec_point_ref(ec_point);
+        self.append(format!("absorb_ec_point(&mut buf, {ec_point});"));
+    }
+
+    fn squeeze_challenge(self: &Rc<Self>) -> Scalar {
+        let idx = self.push_scalar("squeeze_challenge(&mut buf)".to_string());
+        self.scalar(Value::Index(idx))
+    }
+
+    /// Performs pairing check `e(lhs, g2) * e(rhs, minus_s_g2) == 1`.
+    pub fn pairing(
+        self: &Rc<Self>,
+        lhs: &EcPoint,
+        g2: &G2Affine,
+        rhs: &EcPoint,
+        minus_s_g2: &G2Affine,
+    ) {
+        let [g2, minus_s_g2] = [g2, minus_s_g2].map(|ec_point| {
+            let coordinates = ec_point.coordinates().unwrap();
+            let (x, y) = (coordinates.x(), coordinates.y());
+            let bytes = [x.c0, x.c1, y.c0, y.c1].map(|fq| fq.to_repr()).concat();
+            hex_bytes(&bytes)
+        });
+        let [lhs, rhs] = [lhs, rhs].map(|ec_point| self.ec_point_ref(ec_point));
+        self.append(format!(
+            "success = success && pairing_check(\
+                vector[*{lhs}, *{rhs}], vector[g2({g2}), g2({minus_s_g2})]);"
+        ));
+    }
+}
+
+/// Elliptic curve point loaded by [`MoveLoader`].
+#[derive(Clone)]
+pub struct EcPoint {
+    loader: Rc<MoveLoader>,
+    idx: usize,
+}
+
+impl Debug for EcPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EcPoint").field("idx", &self.idx).finish()
+    }
+}
+
+impl PartialEq for EcPoint {
+    fn eq(&self, other: &Self) -> bool {
+        self.idx == other.idx
+    }
+}
+
+impl LoadedEcPoint<G1Affine> for EcPoint {
+    type Loader = Rc<MoveLoader>;
+
+    fn loader(&self) -> &Rc<MoveLoader> {
+        &self.loader
+    }
+}
+
+/// Field element loaded by [`MoveLoader`].
+#[derive(Clone)]
+pub struct Scalar {
+    loader: Rc<MoveLoader>,
+    value: Value,
+}
+
+impl Debug for Scalar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scalar").field("value", &self.value).finish()
+    }
+}
+
+impl Add for Scalar {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        self.loader.add(&self, &rhs)
+    }
+}
+
+impl Sub for Scalar {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self.loader.sub(&self, &rhs)
+    }
+}
+
+impl Mul for Scalar {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        self.loader.mul(&self, &rhs)
+    }
+}
+
+impl Neg for Scalar {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.loader.neg(&self)
+    }
+}
+
+impl<'a> Add<&'a Self> for Scalar {
+    type Output = Self;
+
+    fn add(self, rhs: &'a Self) -> Self {
+        self.loader.add(&self, rhs)
+    }
+}
+
+impl<'a> Sub<&'a Self> for Scalar {
+    type Output = Self;
+
+    fn sub(self, rhs: &'a Self) -> Self {
+        self.loader.sub(&self, rhs)
+    }
+}
+
+impl<'a> Mul<&'a Self> for Scalar {
+    type Output = Self;
+
+    fn mul(self, rhs: &'a Self) -> Self {
+        self.loader.mul(&self, rhs)
+    }
+}
+
+impl AddAssign for Scalar {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = self.loader.add(self, &rhs);
+    }
+}
+
+impl SubAssign for Scalar {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = self.loader.sub(self, &rhs);
+    }
+}
+
+impl MulAssign for Scalar {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = self.loader.mul(self, &rhs);
+    }
+}
+
+impl<'a> AddAssign<&'a Self> for Scalar {
+    fn add_assign(&mut self, rhs: &'a Self) {
+        *self = self.loader.add(self, rhs);
+    }
+}
+
+impl<'a> SubAssign<&'a Self> for Scalar {
+    fn sub_assign(&mut self, rhs: &'a Self) {
+        *self = self.loader.sub(self, rhs);
+    }
+}
+
+impl<'a> MulAssign<&'a Self> for Scalar {
+    fn mul_assign(&mut self, rhs: &'a Self) {
+        *self = self.loader.mul(self, rhs);
+    }
+}
+
+impl FieldOps for Scalar {
+    fn invert(&self) -> Option<Scalar> {
+        Some(self.loader.invert(self))
+    }
+}
+
+impl PartialEq for Scalar {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl LoadedScalar<Fr> for Scalar {
+    type Loader = Rc<MoveLoader>;
+
+    fn loader(&self) -> &Self::Loader {
+        &self.loader
+    }
+}
+
+impl EcPointLoader<G1Affine> for Rc<MoveLoader> {
+    type LoadedEcPoint = EcPoint;
+
+    fn ec_point_load_const(&self, value: &G1Affine) -> EcPoint {
+        let bytes = Option::<Coordinates<G1Affine>>::from(value.coordinates())
+            .map(|coordinates| [coordinates.x().to_repr(), coordinates.y().to_repr()].concat());
+        let bytes = match bytes {
+            Some(bytes) => bytes,
+            None => return self.push_ec_point("crypto_algebra::zero<G1>()".to_string()),
+        };
+        if let Some(idx) = self.ec_point_constants.borrow().get(&bytes).copied() {
+            return self.ec_point(idx);
+        }
+        let ec_point = self.push_ec_point(format!("g1({})", hex_bytes(&bytes)));
+        self.ec_point_constants.borrow_mut().insert(bytes, ec_point.idx);
+        ec_point
+    }
+
+    fn ec_point_assert_eq(&self, _: &str, _: &EcPoint, _: &EcPoint) -> Result<(), Error> {
+        unimplemented!()
+    }
+
+    fn multi_scalar_multiplication(pairs: &[(&Scalar, &EcPoint)]) -> EcPoint {
+        let loader = &pairs.first().unwrap().1.loader;
+        if let [(scalar, ec_point)] = pairs {
+            if scalar.value == Value::Constant(Fr::one()) {
+                return (*ec_point).clone();
+            }
+        }
+        let (scalars, ec_points) = pairs
+            .iter()
+            .map(|(scalar, ec_point)| {
+                let scalar = loader.scalar_ref(scalar);
+                let ec_point = loader.ec_point_ref(ec_point);
+                (format!("*{scalar}"), format!("*{ec_point}"))
+            })
+            .unzip::<_, _, Vec<_>, Vec<_>>();
+        loader.push_ec_point(format!(
+            "msm(vector[{}], vector[{}])",
+            ec_points.join(", "),
+            scalars.join(", ")
+        ))
+    }
+}
+
+impl ScalarLoader<Fr> for Rc<MoveLoader> {
+    type LoadedScalar = Scalar;
+
+    fn load_const(&self, value: &Fr) -> Scalar {
+        self.scalar(Value::Constant(*value))
+    }
+
+    fn assert_eq(&self, _: &str, _: &Scalar, _: &Scalar) -> Result<(), Error> {
+        unimplemented!()
+    }
+}
+
+impl Loader<G1Affine> for Rc<MoveLoader> {}
+
+/// Transcript of [`MoveLoader`] reading instances and proof from `calldata`
+/// of generated verifier, which is compatible with [`EvmTranscript`].
+///
+/// [`EvmTranscript`]: crate::system::halo2::transcript::evm::EvmTranscript
+#[derive(Debug)]
+pub struct MoveTranscript {
+    loader: Rc<MoveLoader>,
+    offset: usize,
+}
+
+impl MoveTranscript {
+    /// Initialize [`MoveTranscript`] reading from the start of `calldata`.
+    pub fn new(loader: &Rc<MoveLoader>) -> Self {
+        Self { loader: loader.clone(), offset: 0 }
+    }
+
+    /// Load `num_instance` instances from `calldata`, which aborts if any
+    /// instance is not canonical.
+    pub fn load_instances(&mut self, num_instance: Vec<usize>) -> Vec<Vec<Scalar>> {
+        num_instance
+            .into_iter()
+            .map(|len| {
+                iter::repeat_with(|| {
+                    let scalar = self.loader.calldataload_scalar(self.offset);
+                    self.offset += 0x20;
+                    scalar
+                })
+                .take(len)
+                .collect_vec()
+            })
+            .collect()
+    }
+}
+
+impl Transcript<G1Affine, Rc<MoveLoader>> for MoveTranscript {
+    fn loader(&self) -> &Rc<MoveLoader> {
+        &self.loader
+    }
+
+    fn squeeze_challenge(&mut self) -> Scalar {
+        self.loader.squeeze_challenge()
+    }
+
+    fn common_ec_point(&mut self, ec_point: &EcPoint) -> Result<(), Error> {
+        self.loader.absorb_ec_point(ec_point);
+        Ok(())
+    }
+
+    fn common_scalar(&mut self, scalar: &Scalar) -> Result<(), Error> {
+        self.loader.absorb_scalar(scalar);
+        Ok(())
+    }
+}
+
+impl TranscriptRead<G1Affine, Rc<MoveLoader>> for MoveTranscript {
+    fn read_scalar(&mut self) -> Result<Scalar, Error> {
+        let scalar = self.loader.calldataload_scalar(self.offset);
+        self.offset += 0x20;
+        self.common_scalar(&scalar)?;
+        Ok(scalar)
+    }
+
+    fn read_ec_point(&mut self) -> Result<EcPoint, Error> {
+        let ec_point = self.loader.calldataload_ec_point(self.offset);
+        self.offset += 0x40;
+        self.common_ec_point(&ec_point)?;
+        Ok(ec_point)
+    }
+}
@@ -1,11 +1,65 @@
 //! `Loader` implementation in native rust.
 use crate::{
-    loader::{EcPointLoader, LoadedEcPoint, LoadedScalar, Loader, ScalarLoader},
-    util::arithmetic::{Curve, CurveAffine, FieldOps, PrimeField},
+    loader::{EcPointLoader, LimbsLoader, LoadedEcPoint, LoadedScalar, Loader, ScalarLoader},
+    util::{
+        arithmetic::{
+            self, Curve, CurveAffine, Domain, Field, FieldOps, GroupEncoding, PrimeField, Rotation,
+        },
+        msm::FixedBaseTable,
+        protocol::CommonPolynomialEvaluation,
+        Itertools,
+    },
     Error,
 };
 use lazy_static::lazy_static;
-use std::fmt::Debug;
+use std::{
+    any::{Any, TypeId},
+    cell::RefCell,
+    collections::HashMap,
+    fmt::Debug,
+    rc::Rc,
+    sync::Arc,
+};
+
+/// Maximum number of memoized lagrange omegas per thread.
+const LAGRANGE_OMEGAS_CACHE_SIZE: usize = 16;
+
+type LagrangeOmegasKey = (TypeId, Vec<u8>, Vec<i32>);
+
+thread_local! {
+    /// Memoized `omega^i` of lagranges keyed by curve, domain generator and
+    /// lagranges, which only depend on the domain and instances layout, so
+    /// verifying proofs with identical shape (e.g. when aggregating
+    /// same-shape snarks) amortizes them.
+    static LAGRANGE_OMEGAS_CACHE: RefCell<HashMap<LagrangeOmegasKey, Box<dyn Any>>> =
+        RefCell::new(HashMap::new());
+
+    /// [`FixedBaseTables`] installed by [`with_fixed_base_tables`].
+    static FIXED_BASE_TABLES: RefCell<Option<Box<dyn Any>>> = RefCell::new(None);
+}
+
+/// [`FixedBaseTable`]s keyed by encoding of their bases.
+pub(crate) type FixedBaseTables<C> = HashMap<Vec<u8>, FixedBaseTable<C>>;
+
+/// Runs `f` with `tables` installed on the current thread, so multi-scalar
+/// multiplication of [`NativeLoader`] multiplies their bases by tables.
+pub(crate) fn with_fixed_base_tables<C: CurveAffine, T>(
+    tables: &Arc<FixedBaseTables<C>>,
+    f: impl FnOnce() -> T,
+) -> T {
+    struct Restore(Option<Box<dyn Any>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            FIXED_BASE_TABLES.with(|tables| *tables.borrow_mut() = previous);
+        }
+    }
+
+    let tables: Box<dyn Any> = Box::new(tables.clone());
+    let _restore = Restore(FIXED_BASE_TABLES.with(|installed| installed.replace(Some(tables))));
+    f()
+}
 
 lazy_static! {
     /// NativeLoader instance for [`LoadedEcPoint::loader`] and
@@ -58,13 +112,23 @@ impl<C: CurveAffine> EcPointLoader<C> for NativeLoader {
     fn multi_scalar_multiplication(
         pairs: &[(&<Self as ScalarLoader<C::Scalar>>::LoadedScalar, &C)],
     ) -> C {
-        pairs
-            .iter()
-            .cloned()
-            .map(|(scalar, base)| *base * scalar)
-            .reduce(|acc, value| acc + value)
-            .unwrap()
-            .to_affine()
+        FIXED_BASE_TABLES.with(|tables| {
+            let tables = tables.borrow();
+            let tables =
+                tables.as_ref().and_then(|tables| tables.downcast_ref::<Arc<FixedBaseTables<C>>>());
+            pairs
+                .iter()
+                .cloned()
+                .map(|(scalar, base)| {
+                    match tables.and_then(|tables| tables.get(base.to_bytes().as_ref())) {
+                        Some(table) => table.mul(scalar),
+                        None => *base * scalar,
+                    }
+                })
+                .reduce(|acc, value| acc + value)
+                .unwrap()
+                .to_affine()
+        })
     }
 }
 
@@ -83,6 +147,158 @@ impl<F: PrimeField> ScalarLoader<F> for NativeLoader {
     ) -> Result<(), Error> {
         lhs.eq(rhs).then_some(()).ok_or_else(|| Error::AssertionFailure(annotation.to_string()))
     }
+
+    fn sum_with_coeff_and_const(&self, values: &[(F, &F)], constant: F) -> F {
+        arithmetic::sum_with_coeff_and_const(values, constant)
+    }
+
+    fn sum_products_with_coeff_and_const(&self, values: &[(F, &F, &F)], constant: F) -> F {
+        arithmetic::sum_products_with_coeff_and_const(values, constant)
+    }
+
+    fn batch_invert<'a>(values: impl IntoIterator<Item = &'a mut Self::LoadedScalar>)
+    where
+        Self::LoadedScalar: 'a,
+    {
+        let mut values = values.into_iter().collect_vec();
+        let mut inverted = values.iter().map(|value| **value).collect_vec();
+        arithmetic::batch_invert(&mut inverted);
+        for (value, inverted) in values.iter_mut().zip(inverted) {
+            **value = inverted;
+        }
+    }
+}
+
+impl<F: PrimeField> LimbsLoader<F> for NativeLoader {
+    fn scalar_to_limbs<const LIMBS: usize, const BITS: usize>(
+        &self,
+        scalar: &F,
+    ) -> Result<[F; LIMBS], Error> {
+        if arithmetic::fe_to_big(*scalar).bits() > (LIMBS * BITS) as u64 {
+            return Err(Error::AssertionFailure(format!(
+                "Scalar has more than {} bits",
+                LIMBS * BITS
+            )));
+        }
+        Ok(arithmetic::fe_to_limbs::<_, _, LIMBS, BITS>(*scalar))
+    }
+
+    fn scalar_from_limbs<const LIMBS: usize, const BITS: usize>(
+        &self,
+        limbs: [&F; LIMBS],
+    ) -> Result<F, Error> {
+        if let Some(idx) =
+            limbs.iter().position(|limb| arithmetic::fe_to_big(**limb).bits() > BITS as u64)
+        {
+            return Err(Error::AssertionFailure(format!("Limb {idx} has more than {BITS} bits")));
+        }
+        let radix = F::from(2).pow_vartime([BITS as u64]);
+        Ok(limbs
+            .into_iter()
+            .zip(arithmetic::powers(radix))
+            .fold(F::zero(), |acc, (limb, coeff)| acc + coeff * limb))
+    }
+}
+
+/// Returns `(i, omega^i)` of `domain` for sorted and deduplicated
+/// `lagranges`, which are memoized per thread.
+pub(crate) fn lagrange_omegas<C: CurveAffine>(
+    domain: &Domain<C::Scalar>,
+    lagranges: &[i32],
+) -> Rc<Vec<(i32, C::Scalar)>> {
+    let key = (TypeId::of::<C>(), domain.gen.to_repr().as_ref().to_vec(), lagranges.to_vec());
+
+    let cached = LAGRANGE_OMEGAS_CACHE.with(|cache| {
+        cache
+            .borrow()
+            .get(&key)
+            .and_then(|cached| cached.downcast_ref::<Rc<Vec<(i32, C::Scalar)>>>().cloned())
+    });
+    if let Some(omegas) = cached {
+        return omegas;
+    }
+
+    let omegas = Rc::new(
+        lagranges
+            .iter()
+            .map(|&i| (i, domain.rotate_scalar(C::Scalar::one(), Rotation(i))))
+            .collect_vec(),
+    );
+    LAGRANGE_OMEGAS_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.len() >= LAGRANGE_OMEGAS_CACHE_SIZE {
+            cache.clear();
+        }
+        cache.insert(key, Box::new(omegas.clone()));
+    });
+    omegas
 }
 
-impl<C: CurveAffine> Loader<C> for NativeLoader {}
+impl<C: CurveAffine> Loader<C> for NativeLoader {
+    fn common_poly_eval(
+        domain: &Domain<C::Scalar>,
+        lagranges: impl IntoIterator<Item = i32>,
+        z: &C::Scalar,
+    ) -> CommonPolynomialEvaluation<C, Self> {
+        let lagranges = lagranges.into_iter().sorted().dedup().collect_vec();
+        let omegas = lagrange_omegas::<C>(domain, &lagranges);
+
+        let mut common_poly_eval =
+            CommonPolynomialEvaluation::new_with_omegas(domain, omegas.iter().copied(), z);
+        <Self as ScalarLoader<C::Scalar>>::batch_invert(common_poly_eval.denoms());
+        common_poly_eval.evaluate();
+        common_poly_eval
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        halo2_curves::bn256::{Fr, G1Affine},
+        loader::{
+            native::{lagrange_omegas, NativeLoader},
+            Loader, ScalarLoader,
+        },
+        util::{
+            arithmetic::{root_of_unity, Domain, Field},
+            protocol::{CommonPolynomial, CommonPolynomialEvaluation},
+        },
+    };
+    use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+    use std::rc::Rc;
+
+    #[test]
+    fn test_lagrange_omegas_cache() {
+        type Evaluation = CommonPolynomialEvaluation<G1Affine, NativeLoader>;
+
+        let k = 9;
+        let domain = Domain::new(k, root_of_unity::<Fr>(k));
+        let lagranges = vec![-2, 0, 1, 5];
+
+        // Proofs of the same shape hit the cache regardless of challenges.
+        let omegas = lagrange_omegas::<G1Affine>(&domain, &lagranges);
+        assert!(Rc::ptr_eq(&omegas, &lagrange_omegas::<G1Affine>(&domain, &lagranges)));
+        assert!(!Rc::ptr_eq(&omegas, &lagrange_omegas::<G1Affine>(&domain, &lagranges[1..])));
+
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        for _ in 0..2 {
+            let z = Fr::random(&mut rng);
+            let cached = <NativeLoader as Loader<G1Affine>>::common_poly_eval(
+                &domain,
+                lagranges.iter().rev().copied(),
+                &z,
+            );
+            let expected = {
+                let mut common_poly_eval = Evaluation::new(&domain, lagranges.clone(), &z);
+                <NativeLoader as ScalarLoader<Fr>>::batch_invert(common_poly_eval.denoms());
+                common_poly_eval.evaluate();
+                common_poly_eval
+            };
+            assert_eq!(cached.zn(), expected.zn());
+            assert_eq!(cached.zn_minus_one_inv(), expected.zn_minus_one_inv());
+            for poly in lagranges.iter().copied().map(CommonPolynomial::Lagrange) {
+                assert_eq!(cached.get(poly), expected.get(poly));
+            }
+        }
+    }
+}
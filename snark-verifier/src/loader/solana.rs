@@ -0,0 +1,337 @@
+//! `Loader` implementation for Solana programs, which maps elliptic curve
+//! operations onto `alt_bn128` syscalls and hashes transcript with the
+//! `keccak` syscall, so a reusable on-chain program could verify proofs
+//! generated for the EVM verifier.
+//!
+//! Points are kept in the syscall encoding (64 bytes of big-endian `x || y`,
+//! with identity as zeros), and scalars are computed natively since there is
+//! no syscall for field arithmetic. Off-chain the syscalls fall back to
+//! native implementations, so verification could be tested without a
+//! validator.
+pub(crate) mod syscall;
+
+use crate::{
+    halo2_curves::bn256::{Fq, Fr, G1Affine, G2Affine},
+    loader::{EcPointLoader, LoadedEcPoint, LoadedScalar, Loader, ScalarLoader},
+    util::{
+        arithmetic::{fe_from_big, modulus, Coordinates, CurveAffine, Field, FieldOps, PrimeField},
+        transcript::{Transcript, TranscriptRead},
+    },
+    Error,
+};
+use num_bigint::BigUint;
+use std::{
+    io::ErrorKind,
+    ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+};
+use syscall::{alt_bn128_addition, alt_bn128_multiplication, keccak256};
+
+/// SolanaLoader instance for [`LoadedEcPoint::loader`] and
+/// [`LoadedScalar::loader`] referencing.
+pub const LOADER: SolanaLoader = SolanaLoader;
+
+fn fe_to_be_bytes<F: PrimeField<Repr = [u8; 0x20]>>(fe: &F) -> [u8; 0x20] {
+    let mut bytes = fe.to_repr();
+    bytes.reverse();
+    bytes
+}
+
+fn fe_from_be_bytes<F: PrimeField<Repr = [u8; 0x20]>>(bytes: &[u8]) -> Option<F> {
+    let mut repr = [0; 0x20];
+    repr.copy_from_slice(bytes);
+    repr.reverse();
+    F::from_repr(repr).into()
+}
+
+/// Encode a bn256 G1 point as input of `alt_bn128` syscalls.
+pub fn g1_to_bytes(ec_point: &G1Affine) -> [u8; 0x40] {
+    let mut bytes = [0; 0x40];
+    if let Some(coordinates) = Option::<Coordinates<G1Affine>>::from(ec_point.coordinates()) {
+        bytes[..0x20].copy_from_slice(&fe_to_be_bytes(coordinates.x()));
+        bytes[0x20..].copy_from_slice(&fe_to_be_bytes(coordinates.y()));
+    }
+    bytes
+}
+
+/// Encode a bn256 G2 point as input of `alt_bn128_pairing` syscall, which is
+/// `x.c1 || x.c0 || y.c1 || y.c0` in big-endian.
+pub fn g2_to_bytes(ec_point: &G2Affine) -> [u8; 0x80] {
+    let coordinates = ec_point.coordinates().unwrap();
+    let (x, y) = (coordinates.x(), coordinates.y());
+    let mut bytes = [0; 0x80];
+    for (chunk, fq) in bytes.chunks_mut(0x20).zip([x.c1, x.c0, y.c1, y.c0]) {
+        chunk.copy_from_slice(&fe_to_be_bytes(&fq));
+    }
+    bytes
+}
+
+/// `Loader` implementation for Solana programs.
+#[derive(Clone, Debug)]
+pub struct SolanaLoader;
+
+/// Elliptic curve point in encoding of `alt_bn128` syscalls.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EcPoint(pub [u8; 0x40]);
+
+impl EcPoint {
+    fn add(&self, rhs: &Self) -> Self {
+        Self(alt_bn128_addition(&[self.0, rhs.0].concat()).unwrap())
+    }
+
+    fn mul(&self, scalar: &Scalar) -> Self {
+        Self(alt_bn128_multiplication(&[&self.0[..], &fe_to_be_bytes(&scalar.0)].concat()).unwrap())
+    }
+}
+
+impl From<G1Affine> for EcPoint {
+    fn from(ec_point: G1Affine) -> Self {
+        Self(g1_to_bytes(&ec_point))
+    }
+}
+
+impl LoadedEcPoint<G1Affine> for EcPoint {
+    type Loader = SolanaLoader;
+
+    fn loader(&self) -> &SolanaLoader {
+        &LOADER
+    }
+}
+
+/// Field element computed natively.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Scalar(pub Fr);
+
+impl From<Fr> for Scalar {
+    fn from(scalar: Fr) -> Self {
+        Self(scalar)
+    }
+}
+
+impl Add for Scalar {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Scalar {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl Mul for Scalar {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self(self.0 * rhs.0)
+    }
+}
+
+impl Neg for Scalar {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(-self.0)
+    }
+}
+
+impl<'a> Add<&'a Self> for Scalar {
+    type Output = Self;
+
+    fn add(self, rhs: &'a Self) -> Self {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl<'a> Sub<&'a Self> for Scalar {
+    type Output = Self;
+
+    fn sub(self, rhs: &'a Self) -> Self {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl<'a> Mul<&'a Self> for Scalar {
+    type Output = Self;
+
+    fn mul(self, rhs: &'a Self) -> Self {
+        Self(self.0 * rhs.0)
+    }
+}
+
+impl AddAssign for Scalar {
+    fn add_assign(&mut self, rhs: Self) {
+        self.0 += rhs.0;
+    }
+}
+
+impl SubAssign for Scalar {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl MulAssign for Scalar {
+    fn mul_assign(&mut self, rhs: Self) {
+        self.0 *= rhs.0;
+    }
+}
+
+impl<'a> AddAssign<&'a Self> for Scalar {
+    fn add_assign(&mut self, rhs: &'a Self) {
+        self.0 += rhs.0;
+    }
+}
+
+impl<'a> SubAssign<&'a Self> for Scalar {
+    fn sub_assign(&mut self, rhs: &'a Self) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl<'a> MulAssign<&'a Self> for Scalar {
+    fn mul_assign(&mut self, rhs: &'a Self) {
+        self.0 *= rhs.0;
+    }
+}
+
+impl FieldOps for Scalar {
+    fn invert(&self) -> Option<Self> {
+        Option::<Fr>::from(self.0.invert()).map(Self)
+    }
+}
+
+impl LoadedScalar<Fr> for Scalar {
+    type Loader = SolanaLoader;
+
+    fn loader(&self) -> &SolanaLoader {
+        &LOADER
+    }
+}
+
+impl EcPointLoader<G1Affine> for SolanaLoader {
+    type LoadedEcPoint = EcPoint;
+
+    fn ec_point_load_const(&self, value: &G1Affine) -> EcPoint {
+        EcPoint::from(*value)
+    }
+
+    fn ec_point_assert_eq(
+        &self,
+        annotation: &str,
+        lhs: &EcPoint,
+        rhs: &EcPoint,
+    ) -> Result<(), Error> {
+        lhs.eq(rhs).then_some(()).ok_or_else(|| Error::AssertionFailure(annotation.to_string()))
+    }
+
+    fn multi_scalar_multiplication(pairs: &[(&Scalar, &EcPoint)]) -> EcPoint {
+        pairs
+            .iter()
+            .map(|(scalar, base)| if scalar.0 == Fr::one() { **base } else { base.mul(scalar) })
+            .reduce(|acc, ec_point| acc.add(&ec_point))
+            .unwrap()
+    }
+}
+
+impl ScalarLoader<Fr> for SolanaLoader {
+    type LoadedScalar = Scalar;
+
+    fn load_const(&self, value: &Fr) -> Scalar {
+        Scalar(*value)
+    }
+
+    fn assert_eq(&self, annotation: &str, lhs: &Scalar, rhs: &Scalar) -> Result<(), Error> {
+        lhs.eq(rhs).then_some(()).ok_or_else(|| Error::AssertionFailure(annotation.to_string()))
+    }
+}
+
+impl Loader<G1Affine> for SolanaLoader {}
+
+/// Transcript reading proof generated with `EvmTranscript`, which hashes by
+/// `keccak` syscall.
+#[derive(Debug)]
+pub struct SolanaTranscript<'a> {
+    stream: &'a [u8],
+    buf: Vec<u8>,
+}
+
+impl<'a> SolanaTranscript<'a> {
+    /// Initialize [`SolanaTranscript`] reading from `stream`.
+    pub fn new(stream: &'a [u8]) -> Self {
+        Self { stream, buf: Vec::new() }
+    }
+
+    fn read_word(&mut self) -> Result<&'a [u8], Error> {
+        if self.stream.len() < 0x20 {
+            return Err(Error::Transcript(
+                ErrorKind::UnexpectedEof,
+                "Proof is shorter than expected".to_string(),
+            ));
+        }
+        let (word, stream) = self.stream.split_at(0x20);
+        self.stream = stream;
+        Ok(word)
+    }
+}
+
+impl<'a> Transcript<G1Affine, SolanaLoader> for SolanaTranscript<'a> {
+    fn loader(&self) -> &SolanaLoader {
+        &LOADER
+    }
+
+    fn squeeze_challenge(&mut self) -> Scalar {
+        if self.buf.len() == 0x20 {
+            self.buf.push(1);
+        }
+        let hash = keccak256(&self.buf);
+        self.buf = hash.to_vec();
+        Scalar(fe_from_big(BigUint::from_bytes_be(&hash) % modulus::<Fr>()))
+    }
+
+    fn common_ec_point(&mut self, ec_point: &EcPoint) -> Result<(), Error> {
+        if ec_point.0 == [0; 0x40] {
+            return Err(Error::Transcript(
+                ErrorKind::Other,
+                "Cannot write points at infinity to the transcript".to_string(),
+            ));
+        }
+        self.buf.extend(ec_point.0);
+        Ok(())
+    }
+
+    fn common_scalar(&mut self, scalar: &Scalar) -> Result<(), Error> {
+        self.buf.extend(fe_to_be_bytes(&scalar.0));
+        Ok(())
+    }
+}
+
+impl<'a> TranscriptRead<G1Affine, SolanaLoader> for SolanaTranscript<'a> {
+    fn read_scalar(&mut self) -> Result<Scalar, Error> {
+        let scalar = fe_from_be_bytes::<Fr>(self.read_word()?).map(Scalar).ok_or_else(|| {
+            Error::Transcript(ErrorKind::Other, "Invalid scalar encoding in proof".to_string())
+        })?;
+        self.common_scalar(&scalar)?;
+        Ok(scalar)
+    }
+
+    fn read_ec_point(&mut self) -> Result<EcPoint, Error> {
+        let [x, y] = [self.read_word()?, self.read_word()?].map(fe_from_be_bytes::<Fq>);
+        let ec_point = x
+            .zip(y)
+            .and_then(|(x, y)| Option::<G1Affine>::from(G1Affine::from_xy(x, y)))
+            .map(EcPoint::from)
+            .ok_or_else(|| {
+                Error::Transcript(
+                    ErrorKind::Other,
+                    "Invalid elliptic curve point encoding in proof".to_string(),
+                )
+            })?;
+        self.common_ec_point(&ec_point)?;
+        Ok(ec_point)
+    }
+}
@@ -0,0 +1,156 @@
+//! `alt_bn128` and `keccak` syscalls of Solana programs, declared here instead
+//! of depending on `solana-program`, whose `curve25519-dalek` can't be
+//! resolved together with the `zeroize` required by the rest of the tree.
+//!
+//! Off-chain they fall back to native implementations with the same encoding,
+//! so verification could be tested without a validator.
+
+/// Error returned by a syscall on invalid input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SyscallError;
+
+#[cfg(target_os = "solana")]
+pub use onchain::*;
+
+#[cfg(not(target_os = "solana"))]
+pub use offchain::*;
+
+#[cfg(target_os = "solana")]
+mod onchain {
+    use super::SyscallError;
+
+    const ALT_BN128_ADD: u64 = 0;
+    const ALT_BN128_MUL: u64 = 2;
+    const ALT_BN128_PAIRING: u64 = 3;
+
+    extern "C" {
+        fn sol_alt_bn128_group_op(
+            group_op: u64,
+            input: *const u8,
+            input_size: u64,
+            result: *mut u8,
+        ) -> u64;
+        fn sol_keccak256(vals: *const u8, val_len: u64, hash_result: *mut u8) -> u64;
+    }
+
+    fn group_op<const N: usize>(op: u64, input: &[u8]) -> Result<[u8; N], SyscallError> {
+        let mut output = [0; N];
+        let result = unsafe {
+            sol_alt_bn128_group_op(op, input.as_ptr(), input.len() as u64, output.as_mut_ptr())
+        };
+        (result == 0).then_some(output).ok_or(SyscallError)
+    }
+
+    /// Returns `lhs + rhs` given input `lhs || rhs`.
+    pub fn alt_bn128_addition(input: &[u8]) -> Result<[u8; 0x40], SyscallError> {
+        group_op(ALT_BN128_ADD, input)
+    }
+
+    /// Returns `base * scalar` given input `base || scalar`.
+    pub fn alt_bn128_multiplication(input: &[u8]) -> Result<[u8; 0x40], SyscallError> {
+        group_op(ALT_BN128_MUL, input)
+    }
+
+    /// Returns whether product of pairings of input `(g1 || g2)*` is identity.
+    pub fn alt_bn128_pairing(input: &[u8]) -> Result<bool, SyscallError> {
+        group_op::<0x20>(ALT_BN128_PAIRING, input).map(|output| output[0x1f] == 1)
+    }
+
+    /// Returns keccak256 hash of `input`.
+    pub fn keccak256(input: &[u8]) -> [u8; 0x20] {
+        let mut hash = [0; 0x20];
+        let vals = [input];
+        unsafe { sol_keccak256(vals.as_ptr() as *const u8, vals.len() as u64, hash.as_mut_ptr()) };
+        hash
+    }
+}
+
+#[cfg(not(target_os = "solana"))]
+mod offchain {
+    use super::SyscallError;
+    use crate::{
+        halo2_curves::{
+            bn256::{Bn256, Fq, Fq2, Fr, G1Affine, G2Affine, G2Prepared},
+            pairing::MultiMillerLoop,
+        },
+        loader::solana::g1_to_bytes,
+        util::{
+            arithmetic::{
+                fe_from_big, modulus, CurveAffine, Group, MillerLoopResult, PrimeCurveAffine,
+                PrimeField,
+            },
+            hash::{Digest, Keccak256},
+            Itertools,
+        },
+    };
+    use num_bigint::BigUint;
+
+    fn fq_from_be_bytes(bytes: &[u8]) -> Result<Fq, SyscallError> {
+        let mut repr = [0; 0x20];
+        repr.copy_from_slice(bytes);
+        repr.reverse();
+        Option::from(Fq::from_repr(repr)).ok_or(SyscallError)
+    }
+
+    fn g1_from_bytes(bytes: &[u8]) -> Result<G1Affine, SyscallError> {
+        if bytes.iter().all(|byte| *byte == 0) {
+            return Ok(G1Affine::identity());
+        }
+        let [x, y] = [&bytes[..0x20], &bytes[0x20..]].map(fq_from_be_bytes);
+        Option::from(G1Affine::from_xy(x?, y?)).ok_or(SyscallError)
+    }
+
+    fn g2_from_bytes(bytes: &[u8]) -> Result<G2Affine, SyscallError> {
+        if bytes.iter().all(|byte| *byte == 0) {
+            return Ok(G2Affine::identity());
+        }
+        let [x_c1, x_c0, y_c1, y_c0] =
+            [0, 1, 2, 3].map(|idx| fq_from_be_bytes(&bytes[idx * 0x20..(idx + 1) * 0x20]));
+        let x = Fq2 { c0: x_c0?, c1: x_c1? };
+        let y = Fq2 { c0: y_c0?, c1: y_c1? };
+        Option::from(G2Affine::from_xy(x, y)).ok_or(SyscallError)
+    }
+
+    /// Returns `lhs + rhs` given input `lhs || rhs`.
+    pub fn alt_bn128_addition(input: &[u8]) -> Result<[u8; 0x40], SyscallError> {
+        if input.len() != 0x80 {
+            return Err(SyscallError);
+        }
+        let [lhs, rhs] = [&input[..0x40], &input[0x40..]].map(g1_from_bytes);
+        Ok(g1_to_bytes(&(lhs? + rhs?).into()))
+    }
+
+    /// Returns `base * scalar` given input `base || scalar`, where `scalar`
+    /// is reduced by the order of the group.
+    pub fn alt_bn128_multiplication(input: &[u8]) -> Result<[u8; 0x40], SyscallError> {
+        if input.len() != 0x60 {
+            return Err(SyscallError);
+        }
+        let base = g1_from_bytes(&input[..0x40])?;
+        let scalar: Fr = fe_from_big(BigUint::from_bytes_be(&input[0x40..]) % modulus::<Fr>());
+        Ok(g1_to_bytes(&(base * scalar).into()))
+    }
+
+    /// Returns whether product of pairings of input `(g1 || g2)*` is identity.
+    pub fn alt_bn128_pairing(input: &[u8]) -> Result<bool, SyscallError> {
+        if input.len() % 0xc0 != 0 {
+            return Err(SyscallError);
+        }
+        let pairs = input
+            .chunks(0xc0)
+            .map(|chunk| {
+                Ok((
+                    g1_from_bytes(&chunk[..0x40])?,
+                    G2Prepared::from(g2_from_bytes(&chunk[0x40..])?),
+                ))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let terms = pairs.iter().map(|(g1, g2)| (g1, g2)).collect_vec();
+        Ok(Bn256::multi_miller_loop(&terms).final_exponentiation().is_identity().into())
+    }
+
+    /// Returns keccak256 hash of `input`.
+    pub fn keccak256(input: &[u8]) -> [u8; 0x20] {
+        Keccak256::digest(input).into()
+    }
+}
@@ -1,10 +1,11 @@
 //! Verifiers for polynomial commitment schemes.
 
 use crate::{
-    loader::{native::NativeLoader, Loader},
+    loader::{native::NativeLoader, LoadedScalar, Loader, ScalarLoader},
     util::{
         arithmetic::{CurveAffine, PrimeField},
         msm::Msm,
+        protocol::RotationSet,
         transcript::{TranscriptRead, TranscriptWrite},
     },
     Error,
@@ -12,7 +13,9 @@ use crate::{
 use rand::Rng;
 use std::fmt::Debug;
 
+pub mod fri;
 pub mod kzg;
+pub mod zeromorph;
 
 pub trait PolynomialCommitmentScheme<C, L>: Clone + Debug
 where
@@ -47,6 +50,12 @@ impl<F: PrimeField> Query<F> {
 }
 
 /// Polynomial commitment scheme verifier.
+///
+/// Grouping of queries only depends on the protocol, so it's precomputed once
+/// by [`MultiOpenScheme::prepare`] and then reused across proofs by
+/// [`MultiOpenScheme::read_proof_prepared`] and
+/// [`MultiOpenScheme::succinct_verify_prepared`]. The versions taking queries
+/// prepare on every call.
 pub trait MultiOpenScheme<C, L>: PolynomialCommitmentScheme<C, L>
 where
     C: CurveAffine,
@@ -56,15 +65,45 @@ where
     type SuccinctVerifyingKey: Clone + Debug;
     /// Structured proof read from transcript.
     type Proof: Clone + Debug;
+    /// Per-protocol state derived from queries.
+    type Prepared: Clone + Debug;
+
+    /// Precompute [`MultiOpenScheme::Prepared`] from `queries`, which must be
+    /// in the same order as the ones passed to verification later.
+    fn prepare(queries: &[Query<C::Scalar>]) -> Self::Prepared;
+
+    /// Read [`PolynomialCommitmentScheme::Proof`] from transcript with
+    /// `prepared` state, which fails if transcript holds a malformed proof.
+    fn read_proof_prepared<T>(
+        svk: &Self::SuccinctVerifyingKey,
+        prepared: &Self::Prepared,
+        transcript: &mut T,
+    ) -> Result<Self::Proof, Error>
+    where
+        T: TranscriptRead<C, L>;
+
+    /// Verify [`MultiOpenScheme::Proof`] with `prepared` state and output
+    /// [`PolynomialCommitmentScheme::Accumulator`].
+    fn succinct_verify_prepared(
+        svk: &Self::SuccinctVerifyingKey,
+        prepared: &Self::Prepared,
+        commitments: &[Msm<C, L>],
+        point: &L::LoadedScalar,
+        queries: &[Query<C::Scalar, L::LoadedScalar>],
+        proof: &Self::Proof,
+    ) -> Self::Accumulator;
 
     /// Read [`PolynomialCommitmentScheme::Proof`] from transcript.
     fn read_proof<T>(
         svk: &Self::SuccinctVerifyingKey,
         queries: &[Query<C::Scalar>],
         transcript: &mut T,
-    ) -> Self::Proof
+    ) -> Result<Self::Proof, Error>
     where
-        T: TranscriptRead<C, L>;
+        T: TranscriptRead<C, L>,
+    {
+        Self::read_proof_prepared(svk, &Self::prepare(queries), transcript)
+    }
 
     /// Verify [`MultiOpenScheme::Proof`] and output [`PolynomialCommitmentScheme::Accumulator`].
     fn succinct_verify(
@@ -73,7 +112,68 @@ where
         point: &L::LoadedScalar,
         queries: &[Query<C::Scalar, L::LoadedScalar>],
         proof: &Self::Proof,
-    ) -> Self::Accumulator;
+    ) -> Self::Accumulator {
+        let prepared = Self::prepare(
+            &queries.iter().map(|query| Query::new(query.poly, query.shift)).collect::<Vec<_>>(),
+        );
+        Self::succinct_verify_prepared(svk, &prepared, commitments, point, queries, proof)
+    }
+
+    /// Verify [`MultiOpenScheme::Proof`] with `rotation_sets` precomputed from
+    /// queries, and output [`PolynomialCommitmentScheme::Accumulator`].
+    fn succinct_verify_with_rotation_sets(
+        svk: &Self::SuccinctVerifyingKey,
+        commitments: &[Msm<C, L>],
+        point: &L::LoadedScalar,
+        queries: &[Query<C::Scalar, L::LoadedScalar>],
+        _rotation_sets: &[RotationSet],
+        proof: &Self::Proof,
+    ) -> Self::Accumulator {
+        Self::succinct_verify(svk, commitments, point, queries, proof)
+    }
+
+    /// Returns challenges squeezed while reading [`MultiOpenScheme::Proof`] in
+    /// squeezing order, for diagnostics.
+    fn challenges(_proof: &Self::Proof) -> Vec<L::LoadedScalar> {
+        Vec::new()
+    }
+}
+
+/// Multilinear polynomial commitment scheme verifier, which opens polynomials
+/// committed by their evaluations on the boolean hypercube at a single point.
+///
+/// Commitments and evaluations are expected to be already absorbed into the
+/// transcript before [`MultilinearOpenScheme::read_proof`].
+pub trait MultilinearOpenScheme<C, L>: PolynomialCommitmentScheme<C, L>
+where
+    C: CurveAffine,
+    L: Loader<C>,
+{
+    /// Verifying key.
+    type SuccinctVerifyingKey: Clone + Debug;
+    /// Structured proof read from transcript.
+    type Proof: Clone + Debug;
+
+    /// Read [`MultilinearOpenScheme::Proof`] of opening polynomials of
+    /// `num_vars` variables from transcript.
+    fn read_proof<T>(
+        svk: &Self::SuccinctVerifyingKey,
+        num_vars: usize,
+        transcript: &mut T,
+    ) -> Result<Self::Proof, Error>
+    where
+        T: TranscriptRead<C, L>;
+
+    /// Verify `commitments` open to `evals` at `point` with
+    /// [`MultilinearOpenScheme::Proof`] and output
+    /// [`PolynomialCommitmentScheme::Accumulator`].
+    fn succinct_verify(
+        svk: &Self::SuccinctVerifyingKey,
+        commitments: &[Msm<C, L>],
+        point: &[L::LoadedScalar],
+        evals: &[L::LoadedScalar],
+        proof: &Self::Proof,
+    ) -> Result<Self::Accumulator, Error>;
 }
 
 /// Accumulation scheme verifier.
@@ -105,6 +205,33 @@ where
         instances: &[PCS::Accumulator],
         proof: &Self::Proof,
     ) -> Result<PCS::Accumulator, Error>;
+
+    /// Returns challenges squeezed while reading [`AccumulationScheme::Proof`]
+    /// in squeezing order, so outer systems could re-derive or audit them.
+    fn challenges(_proof: &Self::Proof) -> Vec<L::LoadedScalar> {
+        Vec::new()
+    }
+
+    /// Same as [`AccumulationScheme::verify`], but also asserts challenges
+    /// squeezed while reading `proof` equal the expected `challenges`, e.g.
+    /// ones derived by another implementation. When the number of them
+    /// differs, it fails with [`Error::InvalidChallenge`] of the index of the
+    /// first missing one.
+    fn verify_with_challenges(
+        vk: &Self::VerifyingKey,
+        instances: &[PCS::Accumulator],
+        proof: &Self::Proof,
+        challenges: &[L::LoadedScalar],
+    ) -> Result<PCS::Accumulator, Error> {
+        let squeezed = Self::challenges(proof);
+        if squeezed.len() != challenges.len() {
+            return Err(Error::InvalidChallenge(squeezed.len().min(challenges.len())));
+        }
+        for (squeezed, expected) in squeezed.iter().zip(challenges) {
+            squeezed.loader().assert_eq("Unexpected accumulation challenge", squeezed, expected)?;
+        }
+        Self::verify(vk, instances, proof)
+    }
 }
 
 /// Accumulation scheme decider.
@@ -149,6 +276,24 @@ where
     where
         T: TranscriptWrite<C>,
         R: Rng;
+
+    /// Same as [`AccumulationSchemeProver::create_proof`], but also returns
+    /// challenges squeezed in squeezing order, which are the ones
+    /// [`AccumulationScheme::challenges`] returns for the proof read back.
+    /// Defaults to none, so schemes squeezing challenges should override it.
+    fn create_proof_with_challenges<T, R>(
+        pk: &Self::ProvingKey,
+        instances: &[PCS::Accumulator],
+        transcript: &mut T,
+        rng: R,
+    ) -> Result<(PCS::Accumulator, Vec<C::Scalar>), Error>
+    where
+        T: TranscriptWrite<C>,
+        R: Rng,
+    {
+        Self::create_proof(pk, instances, transcript, rng)
+            .map(|accumulator| (accumulator, Vec::new()))
+    }
 }
 
 /// Accumulator encoding.
@@ -0,0 +1,440 @@
+//! [FRI](<https://eccc.weizmann.ac.il/report/2017/134/>) low-degree test over
+//! the scalar field of the curve, with folding factor 2 and Merkle trees hashed
+//! by [`Poseidon`], so it can be verified natively and in circuit by
+//! [`Halo2Loader`](crate::loader::halo2::Halo2Loader) alike.
+//!
+//! The committed codeword is evaluations of a polynomial of degree less than
+//! $2^{\text{log\_degree}}$ on the subgroup of size
+//! $2^{\text{log\_degree} + \text{log\_blowup}}$. Each layer is committed by a
+//! Merkle tree whose $i$-th leaf is $H(f(\omega^i), f(-\omega^i))$, then folded
+//! by challenge $\beta$ into $f'(x^2) = (f(x) + f(-x)) / 2 + \beta (f(x) -
+//! f(-x)) / 2x$, until degree is less than $2^{\text{log\_final\_degree}}$,
+//! where coefficients of the final polynomial are sent in clear.
+//!
+//! The prover sends, in order:
+//! 1. Merkle root of each layer, each followed by squeezing its folding
+//!    challenge.
+//! 2. Coefficients of the final polynomial.
+//! 3. For each query, whose index is the lowest bits of a squeezed challenge,
+//!    the leaf of each layer followed by its Merkle path from bottom to top.
+use crate::{
+    loader::{native::NativeLoader, LoadedScalar, Loader, ScalarLoader},
+    util::{
+        arithmetic::{fe_to_big, root_of_unity, CurveAffine, Field, PrimeField},
+        hash::Poseidon,
+        transcript::{TranscriptRead, TranscriptWrite},
+        Itertools,
+    },
+    Error,
+};
+use std::iter;
+
+const POSEIDON_T: usize = 5;
+const POSEIDON_RATE: usize = 4;
+const POSEIDON_R_F: usize = 8;
+const POSEIDON_R_P: usize = 60;
+
+/// Loader that decomposes loaded scalars into bits, which [`Fri`] needs to
+/// derive query indices from challenges.
+pub trait FriLoader<C: CurveAffine>: Loader<C> {
+    /// Returns the lowest `num_bits` little-endian bits of the canonical
+    /// decomposition of `scalar`, each constrained to be boolean.
+    fn low_bits(
+        &self,
+        scalar: &Self::LoadedScalar,
+        num_bits: usize,
+    ) -> Result<Vec<Self::LoadedScalar>, Error>;
+}
+
+impl<C: CurveAffine> FriLoader<C> for NativeLoader {
+    fn low_bits(&self, scalar: &C::Scalar, num_bits: usize) -> Result<Vec<C::Scalar>, Error> {
+        let scalar = fe_to_big(*scalar);
+        Ok((0..num_bits as u64).map(|idx| C::Scalar::from(scalar.bit(idx) as u64)).collect())
+    }
+}
+
+/// Configuration of [`Fri`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FriConfig {
+    /// Log2 of degree bound of committed polynomial.
+    pub log_degree: usize,
+    /// Log2 of blowup factor of codeword.
+    pub log_blowup: usize,
+    /// Log2 of degree bound of final polynomial sent in clear.
+    pub log_final_degree: usize,
+    /// Number of queries.
+    pub num_queries: usize,
+}
+
+impl FriConfig {
+    /// Initialize a [`FriConfig`] folding all the way down to a constant.
+    pub fn new(log_degree: usize, log_blowup: usize, num_queries: usize) -> Self {
+        Self { log_degree, log_blowup, log_final_degree: 0, num_queries }
+    }
+
+    /// Set log2 of degree bound of final polynomial.
+    pub fn with_log_final_degree(mut self, log_final_degree: usize) -> Self {
+        self.log_final_degree = log_final_degree;
+        self
+    }
+
+    /// Returns number of folding rounds.
+    pub fn num_rounds(&self) -> usize {
+        self.log_degree - self.log_final_degree
+    }
+
+    /// Returns log2 of size of initial evaluation domain.
+    pub fn log_domain_size(&self) -> usize {
+        self.log_degree + self.log_blowup
+    }
+
+    fn validate<F: PrimeField>(&self) -> Result<(), Error> {
+        if self.log_final_degree >= self.log_degree
+            || self.log_blowup == 0
+            || self.num_queries == 0
+            || self.log_domain_size() > F::S as usize
+        {
+            return Err(Error::InvalidConfig(format!("Invalid FRI config {self:?}")));
+        }
+        Ok(())
+    }
+}
+
+/// Verifier and prover of [`FriConfig`].
+#[derive(Clone, Debug)]
+pub struct Fri;
+
+/// Opening of committed codeword at a query, for caller to check consistency
+/// with other oracles.
+#[derive(Clone, Debug)]
+pub struct FriQuery<T> {
+    /// Point $x$ of the query.
+    pub point: T,
+    /// Evaluations $f(x)$ and $f(-x)$.
+    pub evals: [T; 2],
+}
+
+/// Structured proof of [`Fri`].
+#[derive(Clone, Debug)]
+pub struct FriProof<C, L>
+where
+    C: CurveAffine,
+    L: Loader<C>,
+{
+    roots: Vec<L::LoadedScalar>,
+    betas: Vec<L::LoadedScalar>,
+    final_coeffs: Vec<L::LoadedScalar>,
+    queries: Vec<FriQueryProof<L::LoadedScalar>>,
+}
+
+#[derive(Clone, Debug)]
+struct FriQueryProof<T> {
+    index_bits: Vec<T>,
+    layers: Vec<([T; 2], Vec<T>)>,
+}
+
+impl<C, L> FriProof<C, L>
+where
+    C: CurveAffine,
+    L: Loader<C>,
+{
+    /// Returns Merkle root of committed codeword.
+    pub fn commitment(&self) -> &L::LoadedScalar {
+        &self.roots[0]
+    }
+}
+
+impl Fri {
+    /// Read [`FriProof`] from transcript.
+    pub fn read_proof<C, L, T>(
+        config: &FriConfig,
+        transcript: &mut T,
+    ) -> Result<FriProof<C, L>, Error>
+    where
+        C: CurveAffine,
+        L: FriLoader<C>,
+        T: TranscriptRead<C, L>,
+    {
+        config.validate::<C::Scalar>()?;
+        let num_index_bits = config.log_domain_size() - 1;
+
+        let (roots, betas) = (0..config.num_rounds())
+            .map(|_| Ok((transcript.read_scalar()?, transcript.squeeze_challenge())))
+            .collect::<Result<Vec<_>, Error>>()?
+            .into_iter()
+            .unzip::<_, _, Vec<_>, Vec<_>>();
+        let final_coeffs = transcript.read_n_scalars(1 << config.log_final_degree)?;
+
+        let challenges = transcript.squeeze_n_challenges(config.num_queries);
+        let index_bits = challenges
+            .iter()
+            .map(|challenge| transcript.loader().low_bits(challenge, num_index_bits))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let queries = index_bits
+            .into_iter()
+            .map(|index_bits| {
+                let layers = (0..config.num_rounds())
+                    .map(|round| {
+                        let [lhs, rhs] = [(); 2].map(|_| transcript.read_scalar());
+                        let path = transcript.read_n_scalars(num_index_bits - round)?;
+                        Ok(([lhs?, rhs?], path))
+                    })
+                    .collect::<Result<_, Error>>()?;
+                Ok(FriQueryProof { index_bits, layers })
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(FriProof { roots, betas, final_coeffs, queries })
+    }
+
+    /// Verify [`FriProof`] and returns opening of committed codeword at each
+    /// query.
+    pub fn verify<C, L>(
+        config: &FriConfig,
+        proof: &FriProof<C, L>,
+    ) -> Result<Vec<FriQuery<L::LoadedScalar>>, Error>
+    where
+        C: CurveAffine,
+        L: Loader<C>,
+    {
+        config.validate::<C::Scalar>()?;
+        let loader = proof.roots[0].loader();
+        let num_index_bits = config.log_domain_size() - 1;
+        let num_rounds = config.num_rounds();
+
+        let omega = root_of_unity::<C::Scalar>(config.log_domain_size());
+        // $\omega^{2^k}$ and $\omega^{-2^k}$ for $k$ in $0..log_domain_size$.
+        let [squares_of_omega, squares_of_omega_inv] =
+            [omega, omega.invert().unwrap()].map(|omega| {
+                iter::successors(Some(omega), |omega| Some(omega.square()))
+                    .take(num_index_bits + 1)
+                    .collect_vec()
+            });
+        // Product of `bases[t]` for each set `bits[t]`.
+        let select_product = |bits: &[L::LoadedScalar], bases: &[C::Scalar]| {
+            let one = loader.load_one();
+            let factors = bits
+                .iter()
+                .zip(bases)
+                .map(|(bit, base)| {
+                    loader.sum_with_coeff_and_const(
+                        &[(*base - C::Scalar::one(), bit)],
+                        C::Scalar::one(),
+                    )
+                })
+                .collect_vec();
+            factors.iter().fold(one, |acc, factor| acc * factor)
+        };
+        let two_inv = loader.load_const(&C::Scalar::from(2).invert().unwrap());
+
+        let mut hasher =
+            Poseidon::<_, _, POSEIDON_T, POSEIDON_RATE>::new(loader, POSEIDON_R_F, POSEIDON_R_P);
+        let mut hash = |lhs: &L::LoadedScalar, rhs: &L::LoadedScalar| {
+            hasher.clear();
+            hasher.update(&[lhs.clone(), rhs.clone()]);
+            hasher.squeeze()
+        };
+
+        proof
+            .queries
+            .iter()
+            .map(|query| {
+                let bits = &query.index_bits;
+                let mut folded = None;
+                for (round, ((evals, path), (root, beta))) in
+                    query.layers.iter().zip(proof.roots.iter().zip(proof.betas.iter())).enumerate()
+                {
+                    let depth = num_index_bits - round;
+                    let [lhs, rhs] = evals;
+
+                    if let Some(folded) = folded.take() {
+                        let side = &bits[depth];
+                        let expected = lhs.clone() + side.clone() * &(rhs.clone() - lhs);
+                        loader.assert_eq("Inconsistent FRI folding", &folded, &expected)?;
+                    }
+
+                    let leaf = hash(lhs, rhs);
+                    let node = path.iter().zip(bits).fold(leaf, |node, (sibling, bit)| {
+                        let left = node.clone() + bit.clone() * &(sibling.clone() - &node);
+                        let right = node + sibling - &left;
+                        hash(&left, &right)
+                    });
+                    loader.assert_eq("Invalid FRI Merkle path", &node, root)?;
+
+                    let x_inv = select_product(&bits[..depth], &squares_of_omega_inv[round..]);
+                    folded = Some(
+                        (lhs.clone() + rhs) * &two_inv
+                            + beta.clone() * &(lhs.clone() - rhs) * &two_inv * &x_inv,
+                    );
+                }
+
+                let depth = num_index_bits + 1 - num_rounds;
+                let x = select_product(&bits[..depth], &squares_of_omega[num_rounds..]);
+                let eval = proof
+                    .final_coeffs
+                    .iter()
+                    .rev()
+                    .fold(loader.load_zero(), |acc, coeff| acc * &x + coeff);
+                loader.assert_eq("Inconsistent FRI final polynomial", &folded.unwrap(), &eval)?;
+
+                let [lhs, rhs] = query.layers[0].0.clone();
+                Ok(FriQuery { point: select_product(bits, &squares_of_omega), evals: [lhs, rhs] })
+            })
+            .collect()
+    }
+
+    /// Create a [`FriProof`] of polynomial with coefficients `coeffs`.
+    pub fn create_proof<C, T>(
+        config: &FriConfig,
+        coeffs: &[C::Scalar],
+        transcript: &mut T,
+    ) -> Result<(), Error>
+    where
+        C: CurveAffine,
+        T: TranscriptWrite<C>,
+    {
+        config.validate::<C::Scalar>()?;
+        assert!(coeffs.len() <= 1 << config.log_degree);
+        let num_index_bits = config.log_domain_size() - 1;
+
+        let mut hasher = Poseidon::<C::Scalar, C::Scalar, POSEIDON_T, POSEIDON_RATE>::new(
+            &NativeLoader,
+            POSEIDON_R_F,
+            POSEIDON_R_P,
+        );
+        let mut hash = |lhs: &C::Scalar, rhs: &C::Scalar| {
+            hasher.clear();
+            hasher.update(&[*lhs, *rhs]);
+            hasher.squeeze()
+        };
+
+        let mut coeffs = coeffs.to_vec();
+        coeffs.resize(1 << config.log_degree, C::Scalar::zero());
+        let mut omega = root_of_unity::<C::Scalar>(config.log_domain_size());
+        let mut layers = Vec::with_capacity(config.num_rounds());
+        for round in 0..config.num_rounds() {
+            let half = 1 << (num_index_bits - round);
+            let evals = iter::successors(Some(C::Scalar::one()), |x| Some(*x * omega))
+                .take(2 * half)
+                .map(|x| coeffs.iter().rev().fold(C::Scalar::zero(), |acc, coeff| acc * x + coeff))
+                .collect_vec();
+            let leaves = (0..half).map(|idx| [evals[idx], evals[idx + half]]).collect_vec();
+            let mut tree = vec![leaves.iter().map(|[lhs, rhs]| hash(lhs, rhs)).collect_vec()];
+            while tree.last().unwrap().len() > 1 {
+                let level =
+                    tree.last().unwrap().chunks(2).map(|pair| hash(&pair[0], &pair[1])).collect();
+                tree.push(level);
+            }
+
+            transcript.write_scalar(tree.last().unwrap()[0])?;
+            let beta = transcript.squeeze_challenge();
+
+            coeffs = coeffs.chunks(2).map(|pair| pair[0] + beta * pair[1]).collect();
+            omega = omega.square();
+            layers.push((leaves, tree));
+        }
+        for coeff in coeffs {
+            transcript.write_scalar(coeff)?;
+        }
+
+        let challenges = transcript.squeeze_n_challenges(config.num_queries);
+        for challenge in challenges {
+            let index = fe_to_big(challenge);
+            for (round, (leaves, tree)) in layers.iter().enumerate() {
+                let depth = num_index_bits - round;
+                let mut idx =
+                    (0..depth as u64).rev().fold(0, |idx, bit| 2 * idx + index.bit(bit) as usize);
+                for eval in leaves[idx] {
+                    transcript.write_scalar(eval)?;
+                }
+                for level in tree.iter().take(depth) {
+                    transcript.write_scalar(level[idx ^ 1])?;
+                    idx >>= 1;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "loader_halo2")]
+mod halo2 {
+    use crate::{
+        loader::halo2::{Halo2Loader, Scalar},
+        pcs::fri::FriLoader,
+        system::halo2::transcript::halo2::{canonical_bits, NativeEncoding},
+        util::arithmetic::CurveAffine,
+        Error,
+    };
+    use std::rc::Rc;
+
+    impl<'a, C, EccChip> FriLoader<C> for Rc<Halo2Loader<'a, C, EccChip>>
+    where
+        C: CurveAffine,
+        EccChip: NativeEncoding<'a, C>,
+    {
+        fn low_bits(
+            &self,
+            scalar: &Scalar<'a, C, EccChip>,
+            num_bits: usize,
+        ) -> Result<Vec<Scalar<'a, C, EccChip>>, Error> {
+            let mut bits = canonical_bits(self, scalar)?;
+            bits.truncate(num_bits);
+            Ok(bits)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::halo2_curves::bn256::{Fr, G1Affine};
+    use crate::halo2_proofs::transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+    };
+    use crate::{
+        loader::native::NativeLoader,
+        pcs::fri::{Fri, FriConfig},
+        util::{
+            arithmetic::{Field, PrimeField},
+            Itertools,
+        },
+    };
+    use rand::rngs::OsRng;
+    use std::iter;
+
+    #[test]
+    fn test_fri() {
+        let config = FriConfig::new(4, 1, 8).with_log_final_degree(1);
+        let coeffs = iter::repeat_with(|| Fr::random(OsRng)).take(1 << 4).collect_vec();
+        let eval = |x: Fr| coeffs.iter().rev().fold(Fr::zero(), |acc, coeff| acc * x + coeff);
+        let verify = |proof: &[u8]| {
+            let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(proof);
+            let proof = Fri::read_proof::<_, NativeLoader, _>(&config, &mut transcript)?;
+            Fri::verify(&config, &proof)
+        };
+
+        let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(Vec::new());
+        Fri::create_proof::<G1Affine, _>(&config, &coeffs, &mut transcript).unwrap();
+        let proof = transcript.finalize();
+        let queries = verify(&proof).unwrap();
+        assert_eq!(queries.len(), config.num_queries);
+        for query in queries {
+            assert_eq!(query.evals, [eval(query.point), eval(-query.point)]);
+        }
+
+        // Tamper with the first coefficient of final polynomial, and the last
+        // Merkle sibling of the last query.
+        let tamper = |offset: usize| {
+            let mut proof = proof.clone();
+            let mut repr = <Fr as PrimeField>::Repr::default();
+            repr.as_mut().copy_from_slice(&proof[offset..offset + 32]);
+            let scalar = Fr::from_repr(repr).unwrap() + Fr::one();
+            proof[offset..offset + 32].copy_from_slice(scalar.to_repr().as_ref());
+            proof
+        };
+        for offset in [32 * config.num_rounds(), proof.len() - 32] {
+            assert!(verify(&tamper(offset)).is_err());
+        }
+    }
+}
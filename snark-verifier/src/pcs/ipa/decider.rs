@@ -57,3 +57,60 @@ mod native {
         }
     }
 }
+
+#[cfg(feature = "loader_evm")]
+mod evm {
+    use crate::{
+        loader::{evm::EvmLoader, Loader, ScalarLoader},
+        pcs::{
+            ipa::{Ipa, IpaAccumulator, IpaDecidingKey},
+            Decider,
+        },
+        util::{
+            arithmetic::{CurveAffine, PrimeField},
+            Itertools,
+        },
+    };
+    use std::{fmt::Debug, rc::Rc};
+
+    /// Deciding on EVM computes all `2^k` coefficients of `h(X)` and a
+    /// fixed-base MSM over the whole `G` as constants, so it's only practical
+    /// for small `k`. Only curves with EVM precompiles (bn254) are supported,
+    /// so Pasta proofs can't be decided on EVM.
+    impl<C, MOS> Decider<C, Rc<EvmLoader>> for Ipa<C, MOS>
+    where
+        C: CurveAffine,
+        C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+        MOS: Clone + Debug,
+    {
+        type DecidingKey = IpaDecidingKey<C>;
+        type Output = ();
+
+        fn decide(
+            dk: &Self::DecidingKey,
+            IpaAccumulator { u, xi }: IpaAccumulator<C, Rc<EvmLoader>>,
+        ) {
+            let loader = u.loader();
+            let one = ScalarLoader::<C::Scalar>::load_one(loader);
+            let h = xi.iter().rev().fold(vec![one], |coeffs, xi| {
+                let rhs = coeffs.iter().map(|coeff| coeff.clone() * xi).collect_vec();
+                coeffs.into_iter().chain(rhs).collect()
+            });
+            let g = dk.g.iter().map(|g| Loader::<C>::ec_point_load_const(loader, g)).collect_vec();
+            let msm = <Rc<EvmLoader> as Loader<C>>::multi_scalar_multiplication(
+                &h.iter().zip_eq(g.iter()).collect_vec(),
+            );
+            Loader::<C>::ec_point_assert_eq(loader, "U == <h, G>", &u, &msm).unwrap();
+        }
+
+        fn decide_all(
+            dk: &Self::DecidingKey,
+            accumulators: Vec<IpaAccumulator<C, Rc<EvmLoader>>>,
+        ) {
+            assert!(!accumulators.is_empty());
+            for accumulator in accumulators {
+                Self::decide(dk, accumulator);
+            }
+        }
+    }
+}
@@ -15,7 +15,7 @@ mod multiopen;
 pub use accumulation::{KzgAs, KzgAsProvingKey, KzgAsVerifyingKey};
 pub use accumulator::{KzgAccumulator, LimbsEncoding};
 pub use decider::KzgDecidingKey;
-pub use multiopen::{Bdfg21, Bdfg21Proof, Gwc19, Gwc19Proof};
+pub use multiopen::{Bdfg21, Bdfg21Prepared, Bdfg21Proof, Gwc19, Gwc19Prepared, Gwc19Proof};
 
 #[cfg(feature = "loader_halo2")]
 pub use accumulator::LimbsEncodingInstructions;
@@ -16,6 +16,15 @@ use std::marker::PhantomData;
 
 /// KZG accumulation scheme. The second generic `MOS` stands for different kind
 /// of multi-open scheme.
+///
+/// Batches of any size are supported, so pipelines don't need to special-case
+/// small ones. Accumulators to fold are the given ones followed by the
+/// blinding one when zero-knowledge is enabled. When there is exactly one of
+/// them, it's passed through without touching transcript (so no challenge is
+/// squeezed nor proof-of-work done), except that the blinding one is still
+/// written to or read from transcript. When there is none, the result is the
+/// identity accumulator, which is trivially decided but only representable by
+/// loaders supporting identity (e.g. [`NativeLoader`]).
 #[derive(Clone, Debug)]
 pub struct KzgAs<PCS>(PhantomData<PCS>);
 
@@ -50,7 +59,12 @@ where
             .chain(proof.blind.as_ref().map(|(lhs, rhs)| (lhs, rhs)))
             .unzip::<_, _, Vec<_>, Vec<_>>();
 
-        let powers_of_r = proof.r.powers(lhs.len());
+        let r = match (&proof.r, lhs.len()) {
+            (Some(r), _) => r,
+            (None, 1) => return Ok(KzgAccumulator::new(lhs[0].clone(), rhs[0].clone())),
+            (None, _) => return Err(Error::InvalidInstances),
+        };
+        let powers_of_r = r.powers(lhs.len());
         let [lhs, rhs] = [lhs, rhs].map(|bases| {
             bases
                 .into_iter()
@@ -62,16 +76,34 @@ where
 
         Ok(KzgAccumulator::new(lhs, rhs))
     }
+
+    fn challenges(proof: &Self::Proof) -> Vec<L::LoadedScalar> {
+        proof.r.iter().cloned().collect()
+    }
 }
 
 /// KZG accumulation scheme proving key.
 #[derive(Clone, Copy, Debug, Default)]
-pub struct KzgAsProvingKey<C>(pub Option<(C, C)>);
+pub struct KzgAsProvingKey<C>(pub Option<(C, C)>, usize);
 
 impl<C: Clone> KzgAsProvingKey<C> {
     /// Initialize a [`KzgAsProvingKey`].
     pub fn new(g: Option<(C, C)>) -> Self {
-        Self(g)
+        Self(g, 0)
+    }
+
+    /// Require a proof-of-work of `bits` bits before squeezing the folding
+    /// challenge, which needs transcript to support
+    /// [`TranscriptWrite::write_proof_of_work`] and
+    /// [`TranscriptRead::read_proof_of_work`].
+    pub fn with_proof_of_work_bits(mut self, bits: usize) -> Self {
+        self.1 = bits;
+        self
+    }
+
+    /// Returns number of proof-of-work bits, where `0` means disabled.
+    pub fn proof_of_work_bits(&self) -> usize {
+        self.1
     }
 
     /// Returns if it supports zero-knowledge or not.
@@ -81,19 +113,24 @@ impl<C: Clone> KzgAsProvingKey<C> {
 
     /// Returns [`KzgAsVerifyingKey`].
     pub fn vk(&self) -> KzgAsVerifyingKey {
-        KzgAsVerifyingKey(self.zk())
+        KzgAsVerifyingKey(self.zk(), self.1)
     }
 }
 
 /// KZG accumulation scheme verifying key.
 #[derive(Clone, Copy, Debug, Default)]
-pub struct KzgAsVerifyingKey(bool);
+pub struct KzgAsVerifyingKey(bool, usize);
 
 impl KzgAsVerifyingKey {
     /// Returns if it supports zero-knowledge or not.
     pub fn zk(&self) -> bool {
         self.0
     }
+
+    /// Returns number of proof-of-work bits, where `0` means disabled.
+    pub fn proof_of_work_bits(&self) -> usize {
+        self.1
+    }
 }
 
 /// KZG accumulation scheme proof.
@@ -105,7 +142,7 @@ where
     PCS: PolynomialCommitmentScheme<C, L, Accumulator = KzgAccumulator<C, L>>,
 {
     blind: Option<(L::LoadedEcPoint, L::LoadedEcPoint)>,
-    r: L::LoadedScalar,
+    r: Option<L::LoadedScalar>,
     _marker: PhantomData<PCS>,
 }
 
@@ -123,7 +160,18 @@ where
     where
         T: TranscriptRead<C, L>,
     {
-        assert!(!instances.is_empty());
+        match (instances.len(), vk.zk()) {
+            (0, false) => {
+                let identity = transcript.loader().ec_point_load_const(&C::identity());
+                return Ok(Self {
+                    blind: Some((identity.clone(), identity)),
+                    r: None,
+                    _marker: PhantomData,
+                });
+            }
+            (1, false) => return Ok(Self { blind: None, r: None, _marker: PhantomData }),
+            _ => {}
+        }
 
         for accumulator in instances {
             transcript.common_ec_point(&accumulator.lhs)?;
@@ -134,9 +182,17 @@ where
             .zk()
             .then(|| (transcript.read_ec_point().unwrap(), transcript.read_ec_point().unwrap()));
 
+        if instances.is_empty() {
+            return Ok(Self { blind, r: None, _marker: PhantomData });
+        }
+
+        if vk.proof_of_work_bits() > 0 {
+            transcript.read_proof_of_work(vk.proof_of_work_bits())?;
+        }
+
         let r = transcript.squeeze_challenge();
 
-        Ok(Self { blind, r, _marker: PhantomData })
+        Ok(Self { blind, r: Some(r), _marker: PhantomData })
     }
 }
 
@@ -157,7 +213,27 @@ where
         T: TranscriptWrite<C>,
         R: Rng,
     {
-        assert!(!instances.is_empty());
+        Self::create_proof_with_challenges(pk, instances, transcript, rng)
+            .map(|(accumulator, _)| accumulator)
+    }
+
+    fn create_proof_with_challenges<T, R>(
+        pk: &Self::ProvingKey,
+        instances: &[PCS::Accumulator],
+        transcript: &mut T,
+        rng: R,
+    ) -> Result<(PCS::Accumulator, Vec<C::Scalar>), Error>
+    where
+        T: TranscriptWrite<C>,
+        R: Rng,
+    {
+        match (instances.len(), pk.zk()) {
+            (0, false) => {
+                return Ok((KzgAccumulator::new(C::identity(), C::identity()), Vec::new()));
+            }
+            (1, false) => return Ok((instances[0].clone(), Vec::new())),
+            _ => {}
+        }
 
         for accumulator in instances {
             transcript.common_ec_point(&accumulator.lhs)?;
@@ -177,6 +253,15 @@ where
             })
             .transpose()?;
 
+        if instances.is_empty() {
+            let (lhs, rhs) = blind.unwrap();
+            return Ok((KzgAccumulator::new(lhs, rhs), Vec::new()));
+        }
+
+        if pk.proof_of_work_bits() > 0 {
+            transcript.write_proof_of_work(pk.proof_of_work_bits())?;
+        }
+
         let r = transcript.squeeze_challenge();
 
         let (lhs, rhs) = instances
@@ -195,6 +280,153 @@ where
                 .evaluate(None)
         });
 
-        Ok(KzgAccumulator::new(lhs, rhs))
+        Ok((KzgAccumulator::new(lhs, rhs), vec![r]))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        halo2_curves::bn256::{Bn256, Fr, G1Affine, G1, G2},
+        halo2_proofs::transcript::{
+            Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+        },
+        loader::native::NativeLoader,
+        pcs::{
+            kzg::{Gwc19, Kzg, KzgAccumulator, KzgAs, KzgAsProvingKey, KzgDecidingKey},
+            AccumulationScheme, AccumulationSchemeProver, Decider,
+        },
+        util::{
+            arithmetic::{Curve, Field, Group, PrimeCurveAffine},
+            Itertools,
+        },
+        Error,
+    };
+    use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+
+    #[test]
+    fn test_kzg_as_small_batch() {
+        type Pcs = Kzg<Bn256, Gwc19>;
+        type As = KzgAs<Pcs>;
+
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        let s = Fr::random(&mut rng);
+        let g = G1::generator().to_affine();
+        let dk = KzgDecidingKey::<Bn256>::new(
+            G2::generator().to_affine(),
+            (G2::generator() * s).to_affine(),
+        );
+        let accumulators = (0..2)
+            .map(|_| {
+                let rhs = (g * Fr::random(&mut rng)).to_affine();
+                KzgAccumulator::<G1Affine, NativeLoader>::new((rhs * s).to_affine(), rhs)
+            })
+            .collect_vec();
+
+        for (zk, num_accumulator) in [false, true].into_iter().cartesian_product(0..=2) {
+            let pk = KzgAsProvingKey::new(zk.then(|| (g, (g * s).to_affine())));
+            let accumulators = &accumulators[..num_accumulator];
+
+            let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(Vec::new());
+            let accumulator =
+                As::create_proof(&pk, accumulators, &mut transcript, &mut rng).unwrap();
+            let proof = transcript.finalize();
+            let mut transcript =
+                Blake2bRead::<_, G1Affine, Challenge255<_>>::init(proof.as_slice());
+            let as_proof = <As as AccumulationScheme<G1Affine, NativeLoader, Pcs>>::read_proof(
+                &pk.vk(),
+                accumulators,
+                &mut transcript,
+            )
+            .unwrap();
+            let KzgAccumulator { lhs, rhs } =
+                <As as AccumulationScheme<G1Affine, NativeLoader, Pcs>>::verify(
+                    &pk.vk(),
+                    accumulators,
+                    &as_proof,
+                )
+                .unwrap();
+            assert_eq!((lhs, rhs), (accumulator.lhs, accumulator.rhs));
+            assert!(<Pcs as Decider<G1Affine, NativeLoader>>::decide(&dk, accumulator));
+
+            // Without zero-knowledge, empty batch results in identity and single
+            // one is passed through, both without any proof.
+            match (zk, num_accumulator) {
+                (false, 0) => {
+                    assert!(proof.is_empty());
+                    assert!(bool::from(lhs.is_identity() & rhs.is_identity()));
+                }
+                (false, 1) => {
+                    assert!(proof.is_empty());
+                    assert_eq!((lhs, rhs), (accumulators[0].lhs, accumulators[0].rhs));
+                }
+                _ => assert!(!proof.is_empty()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_kzg_as_challenges() {
+        type Pcs = Kzg<Bn256, Gwc19>;
+        type As = KzgAs<Pcs>;
+
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        let s = Fr::random(&mut rng);
+        let g = G1::generator().to_affine();
+        let accumulators = (0..2)
+            .map(|_| {
+                let rhs = (g * Fr::random(&mut rng)).to_affine();
+                KzgAccumulator::<G1Affine, NativeLoader>::new((rhs * s).to_affine(), rhs)
+            })
+            .collect_vec();
+
+        for num_accumulator in 1..=2 {
+            let pk = KzgAsProvingKey::new(None);
+            let accumulators = &accumulators[..num_accumulator];
+
+            let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(Vec::new());
+            let (accumulator, challenges) =
+                As::create_proof_with_challenges(&pk, accumulators, &mut transcript, &mut rng)
+                    .unwrap();
+            let proof = transcript.finalize();
+            // Single accumulator is passed through without squeezing.
+            assert_eq!(challenges.len(), num_accumulator - 1);
+
+            let mut transcript =
+                Blake2bRead::<_, G1Affine, Challenge255<_>>::init(proof.as_slice());
+            let as_proof = <As as AccumulationScheme<G1Affine, NativeLoader, Pcs>>::read_proof(
+                &pk.vk(),
+                accumulators,
+                &mut transcript,
+            )
+            .unwrap();
+            assert_eq!(
+                <As as AccumulationScheme<G1Affine, NativeLoader, Pcs>>::challenges(&as_proof),
+                challenges
+            );
+
+            let verify_with_challenges = |challenges: &[Fr]| {
+                <As as AccumulationScheme<G1Affine, NativeLoader, Pcs>>::verify_with_challenges(
+                    &pk.vk(),
+                    accumulators,
+                    &as_proof,
+                    challenges,
+                )
+            };
+            let KzgAccumulator { lhs, rhs } = verify_with_challenges(&challenges).unwrap();
+            assert_eq!((lhs, rhs), (accumulator.lhs, accumulator.rhs));
+
+            let mut extra = challenges.clone();
+            extra.push(Fr::from(1));
+            assert!(matches!(
+                verify_with_challenges(&extra),
+                Err(Error::InvalidChallenge(idx)) if idx == challenges.len()
+            ));
+            if num_accumulator > 1 {
+                let mut wrong = challenges.clone();
+                wrong[0] += Fr::from(1);
+                assert!(matches!(verify_with_challenges(&wrong), Err(Error::AssertionFailure(_))));
+            }
+        }
     }
 }
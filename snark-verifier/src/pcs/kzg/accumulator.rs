@@ -1,4 +1,8 @@
-use crate::{loader::Loader, util::arithmetic::CurveAffine};
+use crate::{
+    loader::Loader,
+    util::arithmetic::{CurveAffine, PrimeField},
+    Error, Protocol,
+};
 use std::fmt::Debug;
 
 /// KZG accumulator, containing lhs G1 and rhs G1 of pairing.
@@ -33,6 +37,48 @@ where
 #[derive(Clone, Debug)]
 pub struct LimbsEncoding<const LIMBS: usize, const BITS: usize>;
 
+impl<const LIMBS: usize, const BITS: usize> LimbsEncoding<LIMBS, BITS> {
+    /// Cross-check `protocol.accumulator_indices` against this encoding and
+    /// `protocol.num_instance`, which should be done before generating a
+    /// verifier, since a mismatch otherwise only fails verification opaquely.
+    ///
+    /// It checks that:
+    /// * `LIMBS` limbs of `BITS` bits could represent a base field element,
+    /// and each limb fits in scalar field.
+    /// * Each accumulator is encoded in exactly `4 * LIMBS` instances.
+    /// * Each index points to an existing instance.
+    pub fn validate<C: CurveAffine>(protocol: &Protocol<C>) -> Result<(), Error> {
+        let encoding = format!("LimbsEncoding<{LIMBS}, {BITS}>");
+        if LIMBS * BITS < C::Base::NUM_BITS as usize || BITS > C::Scalar::CAPACITY as usize {
+            return Err(Error::InvalidConfig(format!(
+                "{encoding} can't encode {}-bit base field element in {}-bit scalar field limbs",
+                C::Base::NUM_BITS,
+                C::Scalar::CAPACITY,
+            )));
+        }
+
+        for (t, accumulator_indices) in protocol.accumulator_indices.iter().enumerate() {
+            if accumulator_indices.len() != 4 * LIMBS {
+                return Err(Error::InvalidConfig(format!(
+                    "Accumulator of proof {t} is in {} instances, but {encoding} expects {}",
+                    accumulator_indices.len(),
+                    4 * LIMBS,
+                )));
+            }
+            for &(poly, row) in accumulator_indices {
+                if protocol.num_instance.get(poly).map_or(true, |&len| row >= len) {
+                    return Err(Error::InvalidConfig(format!(
+                        "Accumulator index ({poly}, {row}) of proof {t} is out of instances {:?}",
+                        protocol.num_instance,
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
 mod native {
     use crate::{
         loader::native::NativeLoader,
@@ -41,7 +87,7 @@ mod native {
             AccumulatorEncoding, PolynomialCommitmentScheme,
         },
         util::{
-            arithmetic::{fe_from_limbs, CurveAffine},
+            arithmetic::{try_fe_from_limbs, CurveAffine},
             Itertools,
         },
         Error,
@@ -64,17 +110,24 @@ mod native {
                 .chunks(LIMBS)
                 .into_iter()
                 .map(|limbs| {
-                    fe_from_limbs::<_, _, LIMBS, BITS>(
+                    try_fe_from_limbs::<_, _, LIMBS, BITS>(
                         limbs.iter().map(|limb| **limb).collect_vec().try_into().unwrap(),
                     )
+                    .ok_or_else(|| {
+                        Error::AssertionFailure(
+                            "Accumulator limbs exceed modulus of base field".to_string(),
+                        )
+                    })
                 })
-                .collect_vec()
+                .collect::<Result<Vec<_>, _>>()?
                 .try_into()
                 .unwrap();
-            let accumulator = KzgAccumulator::new(
-                C::from_xy(lhs_x, lhs_y).unwrap(),
-                C::from_xy(rhs_x, rhs_y).unwrap(),
-            );
+            let [lhs, rhs] = [(lhs_x, lhs_y), (rhs_x, rhs_y)].map(|(x, y)| {
+                Option::<C>::from(C::from_xy(x, y)).ok_or_else(|| {
+                    Error::AssertionFailure("Accumulator is not on the curve".to_string())
+                })
+            });
+            let accumulator = KzgAccumulator::new(lhs?, rhs?);
 
             Ok(accumulator)
         }
@@ -137,7 +190,10 @@ pub use halo2::LimbsEncodingInstructions;
 mod halo2 {
     use crate::halo2_proofs::{circuit::Value, plonk};
     use crate::{
-        loader::halo2::{EccInstructions, Halo2Loader, Scalar, Valuetools},
+        loader::{
+            halo2::{EccInstructions, Halo2Loader, Scalar, Valuetools},
+            EcPointLoader,
+        },
         pcs::{
             kzg::{KzgAccumulator, LimbsEncoding},
             AccumulatorEncoding, PolynomialCommitmentScheme,
@@ -219,6 +275,54 @@ mod halo2 {
         }
     }
 
+    impl<const LIMBS: usize, const BITS: usize> LimbsEncoding<LIMBS, BITS> {
+        /// Assert `limbs`, e.g. the accumulator exposed in instances of an inner
+        /// aggregation snark, encode the same `accumulator`, e.g. the one
+        /// recomputed by the outer circuit.
+        ///
+        /// Each half of `limbs` is decoded by
+        /// [`LimbsEncodingInstructions::assign_ec_point_from_limbs`], which
+        /// range checks every limb to `BITS` bits and the decoded point to be on
+        /// curve, then asserted to equal the corresponding point of
+        /// `accumulator`. So callers don't need to recompose limbs by hand.
+        ///
+        /// Returns [`Error::InvalidInstances`] if `limbs` doesn't have length
+        /// `4 * LIMBS`.
+        pub fn assert_accumulator_eq<'a, C, EccChip>(
+            loader: &Rc<Halo2Loader<'a, C, EccChip>>,
+            limbs: &[&Scalar<'a, C, EccChip>],
+            accumulator: &KzgAccumulator<C, Rc<Halo2Loader<'a, C, EccChip>>>,
+        ) -> Result<(), Error>
+        where
+            C: CurveAffine,
+            EccChip: LimbsEncodingInstructions<'a, C, LIMBS, BITS>,
+        {
+            if limbs.len() != 4 * LIMBS {
+                return Err(Error::InvalidInstances);
+            }
+
+            for (annotation, limbs, ec_point) in [
+                ("accumulator.lhs", &limbs[..2 * LIMBS], &accumulator.lhs),
+                ("accumulator.rhs", &limbs[2 * LIMBS..], &accumulator.rhs),
+            ] {
+                let assigned = loader
+                    .ecc_chip()
+                    .assign_ec_point_from_limbs(
+                        &mut loader.ctx_mut(),
+                        &limbs.iter().map(|limb| limb.assigned()).collect_vec(),
+                    )
+                    .map_err(|_| Error::AssertionFailure(annotation.to_string()))?;
+                loader.ec_point_assert_eq(
+                    annotation,
+                    &loader.ec_point_from_assigned(assigned),
+                    ec_point,
+                )?;
+            }
+
+            Ok(())
+        }
+    }
+
     mod halo2_lib {
         use super::*;
         use halo2_base::{
@@ -271,3 +375,108 @@ mod halo2 {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        halo2_curves::pasta::pallas,
+        loader::native::NativeLoader,
+        pcs::{
+            kzg::{KzgAccumulator, LimbsEncoding},
+            AccumulatorEncoding, PolynomialCommitmentScheme,
+        },
+        util::{
+            arithmetic::{fe_to_limbs, Curve, CurveAffine, Field, PrimeCurveAffine},
+            Itertools,
+        },
+        Error,
+    };
+    use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+
+    const LIMBS: usize = 3;
+    const BITS: usize = 88;
+
+    #[test]
+    fn test_limbs_encoding_over_pallas() {
+        // Decoding only depends on the accumulator, so it's checked over a curve
+        // without pairing, whose base field is wider than the one of bn256.
+        #[derive(Clone, Debug)]
+        struct Pcs;
+
+        impl PolynomialCommitmentScheme<pallas::Affine, NativeLoader> for Pcs {
+            type Accumulator = KzgAccumulator<pallas::Affine, NativeLoader>;
+        }
+
+        let decode = |limbs: &[pallas::Scalar]| {
+            <LimbsEncoding<LIMBS, BITS> as AccumulatorEncoding<_, _, Pcs>>::from_repr(
+                &limbs.iter().collect_vec(),
+            )
+        };
+        let is_assertion_failure = |result| matches!(result, Err(Error::AssertionFailure(_)));
+
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        let [lhs, rhs] = [(); 2]
+            .map(|_| (pallas::Affine::generator() * pallas::Scalar::random(&mut rng)).to_affine());
+        let limbs = [lhs, rhs]
+            .iter()
+            .flat_map(|ec_point| {
+                let coordinates = ec_point.coordinates().unwrap();
+                [*coordinates.x(), *coordinates.y()]
+            })
+            .flat_map(fe_to_limbs::<_, pallas::Scalar, LIMBS, BITS>)
+            .collect_vec();
+        let accumulator = decode(&limbs).unwrap();
+        assert_eq!((accumulator.lhs, accumulator.rhs), (lhs, rhs));
+
+        // Most significant limb of x exceeds modulus of base field.
+        let max_limb = pallas::Scalar::from(2).pow_vartime([BITS as u64]) - pallas::Scalar::one();
+        let mut overflown = limbs.clone();
+        overflown[LIMBS - 1] = max_limb;
+        assert!(is_assertion_failure(decode(&overflown)));
+
+        // Coordinates of lhs are swapped, so it's not on the curve.
+        let mut off_curve = limbs;
+        off_curve[..2 * LIMBS].rotate_left(LIMBS);
+        assert!(is_assertion_failure(decode(&off_curve)));
+    }
+
+    #[test]
+    #[cfg(feature = "loader_halo2")]
+    fn test_limbs_encoding_validate() {
+        use crate::{
+            halo2_curves::bn256::{Bn256, Fr},
+            halo2_proofs::plonk::keygen_vk,
+            system::halo2::{
+                compile,
+                test::{kzg::setup, StandardPlonk},
+                Config,
+            },
+        };
+
+        let params = setup::<Bn256>(9);
+        let circuit = StandardPlonk::<Fr>::rand(ChaCha20Rng::from_seed(Default::default()));
+        let vk = keygen_vk(&params, &circuit).unwrap();
+        let protocol = |num_accumulator_instance: usize| {
+            let accumulator_indices = (0..num_accumulator_instance).map(|idx| (0, idx)).collect();
+            compile(
+                &params,
+                &vk,
+                Config::kzg()
+                    .with_num_instance(vec![16])
+                    .with_accumulator_indices(Some(accumulator_indices)),
+            )
+        };
+        let is_invalid = |result| matches!(result, Err(Error::InvalidConfig(_)));
+
+        let protocol_3_88 = protocol(12);
+        assert!(LimbsEncoding::<3, 88>::validate(&protocol_3_88).is_ok());
+        assert!(is_invalid(LimbsEncoding::<4, 68>::validate(&protocol_3_88)));
+        let protocol_4_68 = protocol(16);
+        assert!(LimbsEncoding::<4, 68>::validate(&protocol_4_68).is_ok());
+        assert!(is_invalid(LimbsEncoding::<3, 88>::validate(&protocol_4_68)));
+
+        // Not enough bits for base field element, or limbs exceed scalar field.
+        assert!(is_invalid(LimbsEncoding::<3, 80>::validate(&protocol_3_88)));
+        assert!(is_invalid(LimbsEncoding::<1, 254>::validate(&protocol(4))));
+    }
+}
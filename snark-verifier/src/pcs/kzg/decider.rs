@@ -74,7 +74,7 @@ mod native {
 mod evm {
     use crate::{
         loader::{
-            evm::{loader::Value, EvmLoader},
+            evm::{g2_to_u256s, loader::Value, EvmLoader, G2Source},
             LoadedScalar,
         },
         pcs::{
@@ -82,13 +82,33 @@ mod evm {
             Decider,
         },
         util::{
-            arithmetic::{CurveAffine, MultiMillerLoop, PrimeField},
+            arithmetic::{MultiMillerLoop, PrimeField},
             msm::Msm,
         },
     };
     use ethereum_types::U256;
     use std::{fmt::Debug, rc::Rc};
 
+    impl<M: MultiMillerLoop> KzgDecidingKey<M> {
+        /// Returns ABI encoded constructor arguments `(uint256[4] g2, uint256[4]
+        /// s_g2)` to append to deployment code of verifier generated with
+        /// [`G2Source::Immutable`].
+        pub fn evm_constructor_args(&self) -> Vec<u8> {
+            [self.g2, self.s_g2]
+                .into_iter()
+                .flat_map(|ec_point| {
+                    let (x_1, x_0, y_1, y_0) = g2_to_u256s::<M>(ec_point);
+                    [x_1, x_0, y_1, y_0]
+                })
+                .flat_map(|value| {
+                    let mut bytes = [0; 0x20];
+                    value.to_big_endian(&mut bytes);
+                    bytes
+                })
+                .collect()
+        }
+    }
+
     impl<M, MOS> Decider<M::G1Affine, Rc<EvmLoader>> for Kzg<M, MOS>
     where
         M: MultiMillerLoop,
@@ -103,18 +123,13 @@ mod evm {
             KzgAccumulator { lhs, rhs }: KzgAccumulator<M::G1Affine, Rc<EvmLoader>>,
         ) {
             let loader = lhs.loader();
-            let [g2, minus_s_g2] = [dk.g2, -dk.s_g2].map(|ec_point| {
-                let coordinates = ec_point.coordinates().unwrap();
-                let x = coordinates.x().to_repr();
-                let y = coordinates.y().to_repr();
-                (
-                    U256::from_little_endian(&x.as_ref()[32..]),
-                    U256::from_little_endian(&x.as_ref()[..32]),
-                    U256::from_little_endian(&y.as_ref()[32..]),
-                    U256::from_little_endian(&y.as_ref()[..32]),
-                )
-            });
-            loader.pairing(&lhs, g2, &rhs, minus_s_g2);
+            match loader.g2_source() {
+                G2Source::Constant => {
+                    let [g2, minus_s_g2] = [dk.g2, -dk.s_g2].map(g2_to_u256s::<M>);
+                    loader.pairing(&lhs, g2, &rhs, minus_s_g2);
+                }
+                G2Source::Immutable => loader.pairing_with_immutable_g2(&lhs, &rhs),
+            }
         }
 
         fn decide_all(
@@ -159,3 +174,117 @@ mod evm {
         }
     }
 }
+
+#[cfg(feature = "loader_ark")]
+mod ark {
+    use crate::{
+        halo2_curves::bn256::{Bn256, G1Affine},
+        loader::ark::{g2_to_ark, ArkLoader},
+        pcs::{
+            kzg::{Kzg, KzgAccumulator, KzgDecidingKey},
+            Decider,
+        },
+    };
+    use ark_ec::pairing::Pairing;
+    use ark_ff::Zero;
+    use std::fmt::Debug;
+
+    impl<MOS> Decider<G1Affine, ArkLoader> for Kzg<Bn256, MOS>
+    where
+        MOS: Clone + Debug,
+    {
+        type DecidingKey = KzgDecidingKey<Bn256>;
+        type Output = bool;
+
+        fn decide(
+            dk: &Self::DecidingKey,
+            KzgAccumulator { lhs, rhs }: KzgAccumulator<G1Affine, ArkLoader>,
+        ) -> bool {
+            let [g2, minus_s_g2] = [g2_to_ark(&dk.g2), -g2_to_ark(&dk.s_g2)];
+            ark_bn254::Bn254::multi_pairing([lhs.0, rhs.0], [g2, minus_s_g2]).is_zero()
+        }
+
+        fn decide_all(
+            dk: &Self::DecidingKey,
+            accumulators: Vec<KzgAccumulator<G1Affine, ArkLoader>>,
+        ) -> bool {
+            !accumulators.into_iter().any(|accumulator| !Self::decide(dk, accumulator))
+        }
+    }
+}
+
+#[cfg(feature = "loader_solana")]
+mod solana {
+    use crate::{
+        halo2_curves::bn256::{Bn256, G1Affine},
+        loader::solana::{g2_to_bytes, syscall::alt_bn128_pairing, SolanaLoader},
+        pcs::{
+            kzg::{Kzg, KzgAccumulator, KzgDecidingKey},
+            Decider,
+        },
+    };
+    use std::fmt::Debug;
+
+    impl<MOS> Decider<G1Affine, SolanaLoader> for Kzg<Bn256, MOS>
+    where
+        MOS: Clone + Debug,
+    {
+        type DecidingKey = KzgDecidingKey<Bn256>;
+        type Output = bool;
+
+        fn decide(
+            dk: &Self::DecidingKey,
+            KzgAccumulator { lhs, rhs }: KzgAccumulator<G1Affine, SolanaLoader>,
+        ) -> bool {
+            let input =
+                [&lhs.0[..], &g2_to_bytes(&dk.g2), &rhs.0, &g2_to_bytes(&-dk.s_g2)].concat();
+            alt_bn128_pairing(&input).unwrap_or(false)
+        }
+
+        fn decide_all(
+            dk: &Self::DecidingKey,
+            accumulators: Vec<KzgAccumulator<G1Affine, SolanaLoader>>,
+        ) -> bool {
+            !accumulators.into_iter().any(|accumulator| !Self::decide(dk, accumulator))
+        }
+    }
+}
+
+#[cfg(feature = "loader_move")]
+mod move_lang {
+    use crate::{
+        halo2_curves::bn256::{Bn256, G1Affine},
+        loader::{move_lang::MoveLoader, LoadedEcPoint},
+        pcs::{
+            kzg::{Kzg, KzgAccumulator, KzgDecidingKey},
+            Decider,
+        },
+    };
+    use std::{fmt::Debug, rc::Rc};
+
+    impl<MOS> Decider<G1Affine, Rc<MoveLoader>> for Kzg<Bn256, MOS>
+    where
+        MOS: Clone + Debug,
+    {
+        type DecidingKey = KzgDecidingKey<Bn256>;
+        type Output = ();
+
+        fn decide(
+            dk: &Self::DecidingKey,
+            KzgAccumulator { lhs, rhs }: KzgAccumulator<G1Affine, Rc<MoveLoader>>,
+        ) {
+            let loader = lhs.loader();
+            loader.pairing(&lhs, &dk.g2, &rhs, &(-dk.s_g2));
+        }
+
+        fn decide_all(
+            dk: &Self::DecidingKey,
+            accumulators: Vec<KzgAccumulator<G1Affine, Rc<MoveLoader>>>,
+        ) {
+            assert!(!accumulators.is_empty());
+            for accumulator in accumulators {
+                Self::decide(dk, accumulator);
+            }
+        }
+    }
+}
@@ -8,9 +8,11 @@ use crate::{
     util::{
         arithmetic::{ilog2, CurveAffine, FieldExt, Fraction, MultiMillerLoop},
         msm::Msm,
+        protocol::RotationSet,
         transcript::TranscriptRead,
         Itertools,
     },
+    Error,
 };
 use std::{
     collections::{BTreeMap, BTreeSet},
@@ -30,49 +32,88 @@ where
 {
     type SuccinctVerifyingKey = KzgSuccinctVerifyingKey<M::G1Affine>;
     type Proof = Bdfg21Proof<M::G1Affine, L>;
+    type Prepared = Bdfg21Prepared<M::Scalar>;
 
-    fn read_proof<T>(
+    fn prepare(queries: &[Query<M::Scalar>]) -> Self::Prepared {
+        Bdfg21Prepared::new(queries)
+    }
+
+    fn read_proof_prepared<T>(
         _: &KzgSuccinctVerifyingKey<M::G1Affine>,
-        _: &[Query<M::Scalar>],
+        _: &Bdfg21Prepared<M::Scalar>,
         transcript: &mut T,
-    ) -> Bdfg21Proof<M::G1Affine, L>
+    ) -> Result<Bdfg21Proof<M::G1Affine, L>, Error>
     where
         T: TranscriptRead<M::G1Affine, L>,
     {
         Bdfg21Proof::read(transcript)
     }
 
-    fn succinct_verify(
+    fn succinct_verify_prepared(
         svk: &KzgSuccinctVerifyingKey<M::G1Affine>,
+        prepared: &Bdfg21Prepared<M::Scalar>,
         commitments: &[Msm<M::G1Affine, L>],
         z: &L::LoadedScalar,
         queries: &[Query<M::Scalar, L::LoadedScalar>],
         proof: &Bdfg21Proof<M::G1Affine, L>,
     ) -> Self::Accumulator {
-        let sets = query_sets(queries);
-        let f = {
-            let coeffs = query_set_coeffs(&sets, z, &proof.z_prime);
-
-            let powers_of_mu =
-                proof.mu.powers(Iterator::max(sets.iter().map(|set| set.polys.len())).unwrap());
-            let msms = sets
-                .iter()
-                .zip(coeffs.iter())
-                .map(|(set, coeff)| set.msm(coeff, commitments, &powers_of_mu));
+        succinct_verify(svk, commitments, z, &prepared.query_sets(queries), proof)
+    }
 
-            msms.zip(proof.gamma.powers(sets.len()).into_iter())
-                .map(|(msm, power_of_gamma)| msm * &power_of_gamma)
-                .sum::<Msm<_, _>>()
-                - Msm::base(&proof.w) * &coeffs[0].z_s
+    fn succinct_verify_with_rotation_sets(
+        svk: &KzgSuccinctVerifyingKey<M::G1Affine>,
+        commitments: &[Msm<M::G1Affine, L>],
+        z: &L::LoadedScalar,
+        queries: &[Query<M::Scalar, L::LoadedScalar>],
+        rotation_sets: &[RotationSet],
+        proof: &Bdfg21Proof<M::G1Affine, L>,
+    ) -> Self::Accumulator {
+        let sets = if rotation_sets.is_empty() {
+            Bdfg21Prepared::new(queries).query_sets(queries)
+        } else {
+            query_sets_from_rotation_sets(queries, rotation_sets)
         };
+        succinct_verify(svk, commitments, z, &sets, proof)
+    }
 
-        let rhs = Msm::base(&proof.w_prime);
-        let lhs = f + rhs.clone() * &proof.z_prime;
-
-        KzgAccumulator::new(lhs.evaluate(Some(svk.g)), rhs.evaluate(Some(svk.g)))
+    fn challenges(proof: &Bdfg21Proof<M::G1Affine, L>) -> Vec<L::LoadedScalar> {
+        vec![proof.mu.clone(), proof.gamma.clone(), proof.z_prime.clone()]
     }
 }
 
+fn succinct_verify<'a, M, L>(
+    svk: &KzgSuccinctVerifyingKey<M::G1Affine>,
+    commitments: &[Msm<'a, M::G1Affine, L>],
+    z: &L::LoadedScalar,
+    sets: &[QuerySet<'a, M::Scalar, L::LoadedScalar>],
+    proof: &Bdfg21Proof<M::G1Affine, L>,
+) -> KzgAccumulator<M::G1Affine, L>
+where
+    M: MultiMillerLoop,
+    L: Loader<M::G1Affine>,
+{
+    let f = {
+        let coeffs = query_set_coeffs(sets, z, &proof.z_prime);
+
+        let powers_of_mu =
+            proof.mu.powers(Iterator::max(sets.iter().map(|set| set.polys.len())).unwrap());
+        let msms = sets
+            .iter()
+            .zip(coeffs.iter())
+            .map(|(set, coeff)| set.msm(coeff, commitments, &powers_of_mu));
+
+        msms.zip(proof.gamma.powers(sets.len()).into_iter())
+            .map(|(msm, power_of_gamma)| msm * &power_of_gamma)
+            .sum::<Msm<_, _>>()
+            - Msm::base(&proof.w) * &coeffs[0].z_s
+    };
+
+    let rhs = Msm::base(&proof.w_prime);
+    let lhs = f + rhs.clone() * &proof.z_prime;
+
+    KzgAccumulator::new(lhs.evaluate(Some(svk.g)), rhs.evaluate(Some(svk.g)))
+}
+
 /// Structured proof of [`Bdfg21`].
 #[derive(Clone, Debug)]
 pub struct Bdfg21Proof<C, L>
@@ -92,54 +133,133 @@ where
     C: CurveAffine,
     L: Loader<C>,
 {
-    fn read<T: TranscriptRead<C, L>>(transcript: &mut T) -> Self {
+    fn read<T: TranscriptRead<C, L>>(transcript: &mut T) -> Result<Self, Error> {
         let mu = transcript.squeeze_challenge();
         let gamma = transcript.squeeze_challenge();
-        let w = transcript.read_ec_point().unwrap();
+        let w = transcript.read_ec_point()?;
         let z_prime = transcript.squeeze_challenge();
-        let w_prime = transcript.read_ec_point().unwrap();
-        Bdfg21Proof { mu, gamma, w, z_prime, w_prime }
+        let w_prime = transcript.read_ec_point()?;
+        Ok(Bdfg21Proof { mu, gamma, w, z_prime, w_prime })
     }
 }
 
-fn query_sets<F: FieldExt, T: Clone>(queries: &[Query<F, T>]) -> Vec<QuerySet<F, T>> {
-    let poly_shifts =
-        queries.iter().fold(Vec::<(usize, Vec<F>, Vec<&T>)>::new(), |mut poly_shifts, query| {
-            if let Some(pos) = poly_shifts.iter().position(|(poly, _, _)| *poly == query.poly) {
-                let (_, shifts, evals) = &mut poly_shifts[pos];
-                if !shifts.contains(&query.shift) {
-                    shifts.push(query.shift);
-                    evals.push(&query.eval);
+/// Queries of [`Bdfg21`] grouped by identical set of shifts, as indices into
+/// queries, with normalized Lagrange denominators of each set of shifts.
+#[derive(Clone, Debug)]
+pub struct Bdfg21Prepared<F> {
+    sets: Vec<PreparedSet<F>>,
+}
+
+#[derive(Clone, Debug)]
+struct PreparedSet<F> {
+    shifts: Vec<F>,
+    polys: Vec<usize>,
+    query_indices: Vec<Vec<usize>>,
+    normalized_ell_primes: Vec<F>,
+}
+
+impl<F: FieldExt> Bdfg21Prepared<F> {
+    fn new<T>(queries: &[Query<F, T>]) -> Self {
+        let poly_shifts = queries.iter().enumerate().fold(
+            Vec::<(usize, Vec<F>, Vec<usize>)>::new(),
+            |mut poly_shifts, (idx, query)| {
+                if let Some(pos) = poly_shifts.iter().position(|(poly, _, _)| *poly == query.poly) {
+                    let (_, shifts, indices) = &mut poly_shifts[pos];
+                    if !shifts.contains(&query.shift) {
+                        shifts.push(query.shift);
+                        indices.push(idx);
+                    }
+                } else {
+                    poly_shifts.push((query.poly, vec![query.shift], vec![idx]));
+                }
+                poly_shifts
+            },
+        );
+
+        let mut sets = Vec::<PreparedSet<F>>::new();
+        for (poly, shifts, indices) in poly_shifts {
+            if let Some(pos) = sets.iter().position(|set| {
+                BTreeSet::from_iter(set.shifts.iter()) == BTreeSet::from_iter(shifts.iter())
+            }) {
+                let set = &mut sets[pos];
+                if !set.polys.contains(&poly) {
+                    set.polys.push(poly);
+                    set.query_indices.push(
+                        set.shifts
+                            .iter()
+                            .map(|lhs| {
+                                let idx = shifts.iter().position(|rhs| lhs == rhs).unwrap();
+                                indices[idx]
+                            })
+                            .collect(),
+                    );
                 }
             } else {
-                poly_shifts.push((query.poly, vec![query.shift], vec![&query.eval]));
+                let normalized_ell_primes = normalized_ell_primes(&shifts);
+                sets.push(PreparedSet {
+                    shifts,
+                    polys: vec![poly],
+                    query_indices: vec![indices],
+                    normalized_ell_primes,
+                });
             }
-            poly_shifts
-        });
-
-    poly_shifts.into_iter().fold(Vec::<QuerySet<F, T>>::new(), |mut sets, (poly, shifts, evals)| {
-        if let Some(pos) = sets.iter().position(|set| {
-            BTreeSet::from_iter(set.shifts.iter()) == BTreeSet::from_iter(shifts.iter())
-        }) {
-            let set = &mut sets[pos];
-            if !set.polys.contains(&poly) {
-                set.polys.push(poly);
-                set.evals.push(
-                    set.shifts
-                        .iter()
-                        .map(|lhs| {
-                            let idx = shifts.iter().position(|rhs| lhs == rhs).unwrap();
-                            evals[idx]
-                        })
-                        .collect(),
-                );
-            }
-        } else {
-            let set = QuerySet { shifts, polys: vec![poly], evals: vec![evals] };
-            sets.push(set);
         }
-        sets
-    })
+        Self { sets }
+    }
+
+    fn query_sets<'a, T>(&self, queries: &'a [Query<F, T>]) -> Vec<QuerySet<'a, F, T>> {
+        self.sets
+            .iter()
+            .map(|set| QuerySet {
+                shifts: set.shifts.clone(),
+                polys: set.polys.clone(),
+                evals: set
+                    .query_indices
+                    .iter()
+                    .map(|indices| indices.iter().map(|idx| &queries[*idx].eval).collect())
+                    .collect(),
+                normalized_ell_primes: set.normalized_ell_primes.clone(),
+            })
+            .collect()
+    }
+}
+
+fn query_sets_from_rotation_sets<'a, F: FieldExt, T: Clone>(
+    queries: &'a [Query<F, T>],
+    rotation_sets: &[RotationSet],
+) -> Vec<QuerySet<'a, F, T>> {
+    rotation_sets
+        .iter()
+        .map(|set| {
+            let shifts = set.query_indices[0].iter().map(|idx| queries[*idx].shift).collect_vec();
+            QuerySet {
+                normalized_ell_primes: normalized_ell_primes(&shifts),
+                shifts,
+                polys: set.polys.clone(),
+                evals: set
+                    .query_indices
+                    .iter()
+                    .map(|indices| indices.iter().map(|idx| &queries[*idx].eval).collect())
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+fn normalized_ell_primes<F: FieldExt>(shifts: &[F]) -> Vec<F> {
+    shifts
+        .iter()
+        .enumerate()
+        .map(|(j, shift_j)| {
+            shifts
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| i != j)
+                .map(|(_, shift_i)| (*shift_j - shift_i))
+                .reduce(|acc, value| acc * value)
+                .unwrap_or_else(|| F::one())
+        })
+        .collect()
 }
 
 fn query_set_coeffs<'a, F: FieldExt, T: LoadedScalar<F>>(
@@ -165,6 +285,7 @@ fn query_set_coeffs<'a, F: FieldExt, T: LoadedScalar<F>>(
         .map(|set| {
             let coeff = QuerySetCoeff::new(
                 &set.shifts,
+                &set.normalized_ell_primes,
                 &powers_of_z,
                 z_prime,
                 &z_prime_minus_z_shift_i,
@@ -189,6 +310,7 @@ struct QuerySet<'a, F, T> {
     shifts: Vec<F>,
     polys: Vec<usize>,
     evals: Vec<Vec<&'a T>>,
+    normalized_ell_primes: Vec<F>,
 }
 
 impl<'a, F: FieldExt, T: LoadedScalar<F>> QuerySet<'a, F, T> {
@@ -241,6 +363,7 @@ where
 {
     fn new(
         shifts: &[F],
+        normalized_ell_primes: &[F],
         powers_of_z: &[T],
         z_prime: &T,
         z_prime_minus_z_shift_i: &BTreeMap<F, T>,
@@ -248,20 +371,6 @@ where
     ) -> Self {
         let loader = z_prime.loader();
 
-        let normalized_ell_primes = shifts
-            .iter()
-            .enumerate()
-            .map(|(j, shift_j)| {
-                shifts
-                    .iter()
-                    .enumerate()
-                    .filter(|&(i, _)| i != j)
-                    .map(|(_, shift_i)| (*shift_j - shift_i))
-                    .reduce(|acc, value| acc * value)
-                    .unwrap_or_else(|| F::one())
-            })
-            .collect_vec();
-
         let z = &powers_of_z[1];
         let z_pow_k_minus_one = {
             let k_minus_one = shifts.len() - 1;
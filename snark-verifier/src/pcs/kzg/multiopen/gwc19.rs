@@ -11,6 +11,7 @@ use crate::{
         transcript::TranscriptRead,
         Itertools,
     },
+    Error,
 };
 
 /// Verifier of multi-open KZG. It is for the GWC implementation
@@ -26,26 +27,32 @@ where
 {
     type SuccinctVerifyingKey = KzgSuccinctVerifyingKey<M::G1Affine>;
     type Proof = Gwc19Proof<M::G1Affine, L>;
+    type Prepared = Gwc19Prepared<M::Scalar>;
 
-    fn read_proof<T>(
+    fn prepare(queries: &[Query<M::Scalar>]) -> Self::Prepared {
+        Gwc19Prepared::new(queries)
+    }
+
+    fn read_proof_prepared<T>(
         _: &Self::SuccinctVerifyingKey,
-        queries: &[Query<M::Scalar>],
+        prepared: &Self::Prepared,
         transcript: &mut T,
-    ) -> Self::Proof
+    ) -> Result<Self::Proof, Error>
     where
         T: TranscriptRead<M::G1Affine, L>,
     {
-        Gwc19Proof::read(queries, transcript)
+        Gwc19Proof::read(prepared, transcript)
     }
 
-    fn succinct_verify(
+    fn succinct_verify_prepared(
         svk: &Self::SuccinctVerifyingKey,
+        prepared: &Self::Prepared,
         commitments: &[Msm<M::G1Affine, L>],
         z: &L::LoadedScalar,
         queries: &[Query<M::Scalar, L::LoadedScalar>],
         proof: &Self::Proof,
     ) -> Self::Accumulator {
-        let sets = query_sets(queries);
+        let sets = prepared.query_sets(queries);
         let powers_of_u = &proof.u.powers(sets.len());
         let f = {
             let powers_of_v =
@@ -71,6 +78,10 @@ where
             rhs.into_iter().sum::<Msm<_, _>>().evaluate(Some(svk.g)),
         )
     }
+
+    fn challenges(proof: &Self::Proof) -> Vec<L::LoadedScalar> {
+        vec![proof.v.clone(), proof.u.clone()]
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -89,14 +100,48 @@ where
     C: CurveAffine,
     L: Loader<C>,
 {
-    fn read<T>(queries: &[Query<C::Scalar>], transcript: &mut T) -> Self
+    fn read<T>(prepared: &Gwc19Prepared<C::Scalar>, transcript: &mut T) -> Result<Self, Error>
     where
         T: TranscriptRead<C, L>,
     {
         let v = transcript.squeeze_challenge();
-        let ws = transcript.read_n_ec_points(query_sets(queries).len()).unwrap();
+        let ws = transcript.read_n_ec_points(prepared.sets.len())?;
         let u = transcript.squeeze_challenge();
-        Gwc19Proof { v, ws, u }
+        Ok(Gwc19Proof { v, ws, u })
+    }
+}
+
+/// Queries of [`Gwc19`] grouped by shift, as indices into queries.
+#[derive(Clone, Debug)]
+pub struct Gwc19Prepared<F> {
+    sets: Vec<(F, Vec<usize>)>,
+}
+
+impl<F: PrimeField> Gwc19Prepared<F> {
+    fn new<T>(queries: &[Query<F, T>]) -> Self {
+        let sets = queries.iter().enumerate().fold(
+            Vec::<(F, Vec<usize>)>::new(),
+            |mut sets, (idx, query)| {
+                if let Some(pos) = sets.iter().position(|(shift, _)| *shift == query.shift) {
+                    sets[pos].1.push(idx);
+                } else {
+                    sets.push((query.shift, vec![idx]));
+                }
+                sets
+            },
+        );
+        Self { sets }
+    }
+
+    fn query_sets<'a, T>(&self, queries: &'a [Query<F, T>]) -> Vec<QuerySet<'a, F, T>> {
+        self.sets
+            .iter()
+            .map(|(shift, indices)| QuerySet {
+                shift: *shift,
+                polys: indices.iter().map(|idx| queries[*idx].poly).collect(),
+                evals: indices.iter().map(|idx| &queries[*idx].eval).collect(),
+            })
+            .collect()
     }
 }
 
@@ -129,26 +174,6 @@ where
     }
 }
 
-fn query_sets<F, T>(queries: &[Query<F, T>]) -> Vec<QuerySet<F, T>>
-where
-    F: PrimeField,
-    T: Clone + PartialEq,
-{
-    queries.iter().fold(Vec::new(), |mut sets, query| {
-        if let Some(pos) = sets.iter().position(|set| set.shift == query.shift) {
-            sets[pos].polys.push(query.poly);
-            sets[pos].evals.push(&query.eval);
-        } else {
-            sets.push(QuerySet {
-                shift: query.shift,
-                polys: vec![query.poly],
-                evals: vec![&query.eval],
-            });
-        }
-        sets
-    })
-}
-
 impl<M> CostEstimation<M::G1Affine> for Kzg<M, Gwc19>
 where
     M: MultiMillerLoop,
@@ -156,7 +181,7 @@ where
     type Input = Vec<Query<M::Scalar>>;
 
     fn estimate_cost(queries: &Vec<Query<M::Scalar>>) -> Cost {
-        let num_w = query_sets(queries).len();
+        let num_w = Gwc19Prepared::new(queries).sets.len();
         Cost::new(0, num_w, 0, num_w)
     }
 }
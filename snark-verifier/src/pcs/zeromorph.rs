@@ -0,0 +1,388 @@
+//! [Zeromorph](<https://eprint.iacr.org/2023/917>) multilinear polynomial
+//! commitment scheme on top of KZG.
+//!
+//! A multilinear polynomial $f$ of $n$ variables is committed as the univariate
+//! polynomial $U_n(f) = \sum_i f(i_0, ..., i_{n-1}) X^i$, where $i_k$ is the
+//! $k$-th bit of $i$, so commitments of multilinear and univariate KZG share
+//! the same SRS, accumulator and decider. Openings are reduced to a single KZG
+//! opening of a polynomial vanishing at challenge $x$, and result in a
+//! [`KzgAccumulator`], which can be accumulated by
+//! [`KzgAs`](crate::pcs::kzg::KzgAs) alongside univariate KZG snarks.
+//!
+//! The prover sends, in order:
+//! 1. Commitments of quotients $q_k$ of $\sum_j \rho^j f_j - v = \sum_k (X_k -
+//!    u_k) q_k$, where $q_k$ only depends on $X_0, ..., X_{k-1}$.
+//! 2. Commitment of batched quotient $\hat{q} = \sum_k y^k X^{D - 2^k + 1}
+//!    U_k(q_k)$, which bounds degree of each $U_k(q_k)$ to below $2^k$ with
+//!    $D$ the maximum degree supported by the SRS.
+//! 3. Commitment of $\pi = (\zeta_x + z Z_x) / (X - x)$, where
+//!    $\zeta_x = \hat{q} - \sum_k y^k x^{D - 2^k + 1} U_k(q_k)$ and
+//!    $Z_x = U_n(f) - v \Phi_n(x) - \sum_k (x^{2^k} \Phi_{n-k-1}(x^{2^{k+1}}) -
+//!    u_k \Phi_{n-k}(x^{2^k})) U_k(q_k)$ with
+//!    $\Phi_m(X) = \sum_{i < 2^m} X^i$.
+use crate::{
+    cost::{Cost, CostEstimation},
+    loader::{LoadedScalar, Loader, ScalarLoader},
+    pcs::{
+        kzg::{Kzg, KzgAccumulator},
+        MultilinearOpenScheme,
+    },
+    util::{
+        arithmetic::{CurveAffine, MultiMillerLoop},
+        msm::Msm,
+        transcript::TranscriptRead,
+        Itertools,
+    },
+    Error,
+};
+use std::iter;
+
+/// Verifier of Zeromorph multilinear polynomial commitment scheme, used as
+/// `Kzg<M, Zeromorph>`.
+#[derive(Clone, Debug)]
+pub struct Zeromorph;
+
+/// Zeromorph succinct verifying key.
+#[derive(Clone, Copy, Debug)]
+pub struct ZeromorphSuccinctVerifyingKey<C: CurveAffine> {
+    /// Generator.
+    pub g: C,
+    /// Maximum degree supported by the SRS, which is the degree $\hat{q}$ is
+    /// shifted to for degree check of quotients.
+    pub max_degree: usize,
+}
+
+impl<C: CurveAffine> ZeromorphSuccinctVerifyingKey<C> {
+    /// Initialize a [`ZeromorphSuccinctVerifyingKey`].
+    pub fn new(g: C, max_degree: usize) -> Self {
+        Self { g, max_degree }
+    }
+}
+
+impl<M, L> MultilinearOpenScheme<M::G1Affine, L> for Kzg<M, Zeromorph>
+where
+    M: MultiMillerLoop,
+    L: Loader<M::G1Affine>,
+{
+    type SuccinctVerifyingKey = ZeromorphSuccinctVerifyingKey<M::G1Affine>;
+    type Proof = ZeromorphProof<M::G1Affine, L>;
+
+    fn read_proof<T>(
+        _: &Self::SuccinctVerifyingKey,
+        num_vars: usize,
+        transcript: &mut T,
+    ) -> Result<Self::Proof, Error>
+    where
+        T: TranscriptRead<M::G1Affine, L>,
+    {
+        ZeromorphProof::read(num_vars, transcript)
+    }
+
+    fn succinct_verify(
+        svk: &Self::SuccinctVerifyingKey,
+        commitments: &[Msm<M::G1Affine, L>],
+        point: &[L::LoadedScalar],
+        evals: &[L::LoadedScalar],
+        proof: &Self::Proof,
+    ) -> Result<Self::Accumulator, Error> {
+        let num_vars = point.len();
+        if proof.qs.len() != num_vars || commitments.len() != evals.len() || evals.is_empty() {
+            return Err(Error::AssertionFailure(format!(
+                "Expected {} quotients and the same number of commitments and evaluations, \
+                but got {} quotients, {} commitments and {} evaluations",
+                num_vars,
+                proof.qs.len(),
+                commitments.len(),
+                evals.len()
+            )));
+        }
+        if svk.max_degree + 1 < 1 << num_vars {
+            return Err(Error::InvalidConfig(format!(
+                "Maximum degree {} is too small for {} variables",
+                svk.max_degree, num_vars
+            )));
+        }
+
+        let loader = proof.x.loader();
+        let one = loader.load_one();
+
+        // $x^{2^k}$ for $k$ in $0..=n$.
+        let squares_of_x = iter::successors(Some(proof.x.clone()), |x| Some(x.square()))
+            .take(num_vars + 1)
+            .collect_vec();
+        // $\Phi_{n-k}(x^{2^k}) = (x^{2^n} - 1) / (x^{2^k} - 1)$ for $k$ in $0..=n$.
+        let phis = {
+            let mut denoms = squares_of_x.iter().map(|square| square.clone() - &one).collect_vec();
+            L::batch_invert(denoms.iter_mut());
+            let numer = squares_of_x[num_vars].clone() - &one;
+            denoms.into_iter().map(|denom| denom * &numer).collect_vec()
+        };
+
+        let powers_of_rho = proof.rho.powers(commitments.len());
+        let f = commitments
+            .iter()
+            .zip(powers_of_rho.iter())
+            .map(|(commitment, power_of_rho)| commitment.clone() * power_of_rho)
+            .sum::<Msm<_, _>>();
+        let v = loader.sum_products(&powers_of_rho.iter().zip(evals.iter()).collect_vec());
+
+        let powers_of_y = proof.y.powers(num_vars);
+        let qs = proof
+            .qs
+            .iter()
+            .enumerate()
+            .map(|(k, q)| {
+                let shifted = proof.x.pow_const((svk.max_degree + 1 - (1 << k)) as u64);
+                let coeff = powers_of_y[k].clone() * &shifted
+                    + proof.z.clone()
+                        * &(squares_of_x[k].clone() * &phis[k + 1] - point[k].clone() * &phis[k]);
+                Msm::base(q) * &-coeff
+            })
+            .sum::<Msm<_, _>>();
+
+        let c = Msm::base(&proof.q_hat) + qs + f * &proof.z
+            - Msm::constant(proof.z.clone() * &v * &phis[0]);
+        let lhs = c + Msm::base(&proof.pi) * &proof.x;
+        let rhs = Msm::base(&proof.pi);
+
+        Ok(KzgAccumulator::new(lhs.evaluate(Some(svk.g)), rhs.evaluate(Some(svk.g))))
+    }
+}
+
+/// Structured proof of [`Zeromorph`].
+#[derive(Clone, Debug)]
+pub struct ZeromorphProof<C, L>
+where
+    C: CurveAffine,
+    L: Loader<C>,
+{
+    rho: L::LoadedScalar,
+    qs: Vec<L::LoadedEcPoint>,
+    y: L::LoadedScalar,
+    q_hat: L::LoadedEcPoint,
+    x: L::LoadedScalar,
+    z: L::LoadedScalar,
+    pi: L::LoadedEcPoint,
+}
+
+impl<C, L> ZeromorphProof<C, L>
+where
+    C: CurveAffine,
+    L: Loader<C>,
+{
+    fn read<T>(num_vars: usize, transcript: &mut T) -> Result<Self, Error>
+    where
+        T: TranscriptRead<C, L>,
+    {
+        let rho = transcript.squeeze_challenge();
+        let qs = transcript.read_n_ec_points(num_vars)?;
+        let y = transcript.squeeze_challenge();
+        let q_hat = transcript.read_ec_point()?;
+        let x = transcript.squeeze_challenge();
+        let z = transcript.squeeze_challenge();
+        let pi = transcript.read_ec_point()?;
+        Ok(Self { rho, qs, y, q_hat, x, z, pi })
+    }
+}
+
+impl<M> CostEstimation<M::G1Affine> for Kzg<M, Zeromorph>
+where
+    M: MultiMillerLoop,
+{
+    /// Number of variables and number of polynomials.
+    type Input = (usize, usize);
+
+    fn estimate_cost(&(num_vars, num_polys): &(usize, usize)) -> Cost {
+        Cost::new(0, num_vars + 2, 0, num_vars + num_polys + 3)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        halo2_curves::bn256::{Bn256, Fr, G1Affine, G1, G2},
+        halo2_proofs::transcript::{
+            Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+        },
+        loader::{native::NativeLoader, LoadedScalar},
+        pcs::{
+            kzg::{Kzg, KzgAccumulator, KzgAs, KzgAsProvingKey, KzgDecidingKey},
+            zeromorph::{Zeromorph, ZeromorphSuccinctVerifyingKey},
+            AccumulationScheme, AccumulationSchemeProver, Decider, MultilinearOpenScheme,
+        },
+        util::{
+            arithmetic::{Curve, Field, Group},
+            msm::Msm,
+            transcript::{Transcript, TranscriptRead, TranscriptWrite},
+            Itertools,
+        },
+    };
+    use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+    use std::iter;
+
+    #[test]
+    fn test_zeromorph() {
+        type Pcs = Kzg<Bn256, Zeromorph>;
+        type As = KzgAs<Pcs>;
+
+        const NUM_VARS: usize = 3;
+        const MAX_DEGREE: usize = 15;
+
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        let s = Fr::random(&mut rng);
+        let g = G1::generator().to_affine();
+        let commit = |coeffs: &[Fr]| {
+            let eval = coeffs.iter().rev().fold(Fr::zero(), |acc, coeff| acc * s + coeff);
+            (g * eval).to_affine()
+        };
+        // Quotients $q_k$ of $f - f(u) = \sum_k (X_k - u_k) q_k$ and $f(u)$.
+        let quotients = |evals: &[Fr], point: &[Fr]| {
+            let mut evals = evals.to_vec();
+            let mut qs = vec![Vec::new(); point.len()];
+            for (k, u) in point.iter().enumerate().rev() {
+                let (lo, hi) = evals.split_at(1 << k);
+                qs[k] = hi.iter().zip(lo).map(|(hi, lo)| *hi - lo).collect_vec();
+                evals = lo.iter().zip(&qs[k]).map(|(lo, q)| *lo + *u * q).collect();
+            }
+            (qs, evals[0])
+        };
+
+        let prove = |polys: &[Vec<Fr>], point: &[Fr], evals: &[Fr]| {
+            let commitments = polys.iter().map(|poly| commit(poly)).collect_vec();
+            let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(Vec::new());
+            for commitment in commitments.iter() {
+                transcript.common_ec_point(commitment).unwrap();
+            }
+            for eval in evals.iter() {
+                transcript.common_scalar(eval).unwrap();
+            }
+
+            let rho = transcript.squeeze_challenge();
+            let powers_of_rho = iter::successors(Some(Fr::one()), |power| Some(*power * rho))
+                .take(polys.len())
+                .collect_vec();
+            let f = (0..1 << NUM_VARS)
+                .map(|i| {
+                    polys
+                        .iter()
+                        .zip(&powers_of_rho)
+                        .map(|(poly, power)| poly[i] * power)
+                        .sum::<Fr>()
+                })
+                .collect_vec();
+            let v = evals.iter().zip(&powers_of_rho).map(|(eval, power)| *eval * power).sum::<Fr>();
+
+            let (qs, eval) = quotients(&f, point);
+            assert_eq!(eval, v);
+            for q in qs.iter() {
+                transcript.write_ec_point(commit(q)).unwrap();
+            }
+
+            let y = transcript.squeeze_challenge();
+            let mut q_hat = vec![Fr::zero(); MAX_DEGREE + 1];
+            for (k, (q, power_of_y)) in qs.iter().zip(y.powers(NUM_VARS)).enumerate() {
+                for (i, coeff) in q.iter().enumerate() {
+                    q_hat[MAX_DEGREE + 1 - (1 << k) + i] += power_of_y * coeff;
+                }
+            }
+            transcript.write_ec_point(commit(&q_hat)).unwrap();
+
+            let x = transcript.squeeze_challenge();
+            let z = transcript.squeeze_challenge();
+            let phi = |m: usize, x: Fr| x.powers(1 << m).into_iter().sum::<Fr>();
+            let mut poly = q_hat;
+            poly[0] -= z * v * phi(NUM_VARS, x);
+            for (coeff, f) in poly.iter_mut().zip(&f) {
+                *coeff += z * f;
+            }
+            for (k, (q, power_of_y)) in qs.iter().zip(y.powers(NUM_VARS)).enumerate() {
+                let x_square = x.pow_vartime([1u64 << k]);
+                let scalar = power_of_y * x.pow_vartime([(MAX_DEGREE + 1 - (1 << k)) as u64])
+                    + z * (x_square * phi(NUM_VARS - k - 1, x_square * x_square)
+                        - point[k] * phi(NUM_VARS - k, x_square));
+                for (coeff, q) in poly.iter_mut().zip(q) {
+                    *coeff -= scalar * q;
+                }
+            }
+            // Divide by $X - x$, which should leave no remainder.
+            let mut pi = vec![Fr::zero(); MAX_DEGREE];
+            let mut carry = Fr::zero();
+            for (i, coeff) in poly.iter().enumerate().skip(1).rev() {
+                carry = carry * x + coeff;
+                pi[i - 1] = carry;
+            }
+            assert_eq!(carry * x + poly[0], Fr::zero());
+            transcript.write_ec_point(commit(&pi)).unwrap();
+
+            (commitments, transcript.finalize())
+        };
+
+        let svk = ZeromorphSuccinctVerifyingKey::new(g, MAX_DEGREE);
+        let dk = KzgDecidingKey::<Bn256>::new(
+            G2::generator().to_affine(),
+            (G2::generator() * s).to_affine(),
+        );
+        let decide = |accumulator: KzgAccumulator<G1Affine, NativeLoader>| {
+            <Pcs as Decider<G1Affine, NativeLoader>>::decide(&dk, accumulator)
+        };
+        let verify = |commitments: &[G1Affine], point: &[Fr], evals: &[Fr], proof: &[u8]| {
+            let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(proof);
+            for commitment in commitments.iter() {
+                transcript.common_ec_point(commitment).unwrap();
+            }
+            for eval in evals.iter() {
+                transcript.common_scalar(eval).unwrap();
+            }
+            let proof = <Pcs as MultilinearOpenScheme<G1Affine, NativeLoader>>::read_proof(
+                &svk,
+                NUM_VARS,
+                &mut transcript,
+            )
+            .unwrap();
+            let commitments =
+                commitments.iter().map(Msm::<G1Affine, NativeLoader>::base).collect_vec();
+            Pcs::succinct_verify(&svk, &commitments, point, evals, &proof).unwrap()
+        };
+
+        let accumulators = (0..2)
+            .map(|_| {
+                let polys = iter::repeat_with(|| {
+                    iter::repeat_with(|| Fr::random(&mut rng)).take(1 << NUM_VARS).collect_vec()
+                })
+                .take(2)
+                .collect_vec();
+                let point = iter::repeat_with(|| Fr::random(&mut rng)).take(NUM_VARS).collect_vec();
+                let evals = polys.iter().map(|poly| quotients(poly, &point).1).collect_vec();
+                let (commitments, proof) = prove(&polys, &point, &evals);
+
+                let accumulator = verify(&commitments, &point, &evals, &proof);
+                assert!(decide(accumulator.clone()));
+                let wrong_evals = vec![evals[0] + Fr::one(), evals[1]];
+                assert!(!decide(verify(&commitments, &point, &wrong_evals, &proof)));
+                accumulator
+            })
+            .collect_vec();
+
+        // Accumulate the same way as accumulators of univariate KZG.
+        let pk = KzgAsProvingKey::<G1Affine>::new(None);
+        let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(Vec::new());
+        let accumulator = As::create_proof(&pk, &accumulators, &mut transcript, &mut rng).unwrap();
+        assert!(decide(accumulator.clone()));
+        let proof = transcript.finalize();
+        let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(proof.as_slice());
+        let as_proof = <As as AccumulationScheme<G1Affine, NativeLoader, Pcs>>::read_proof(
+            &pk.vk(),
+            &accumulators,
+            &mut transcript,
+        )
+        .unwrap();
+        let KzgAccumulator { lhs, rhs } =
+            <As as AccumulationScheme<G1Affine, NativeLoader, Pcs>>::verify(
+                &pk.vk(),
+                &accumulators,
+                &as_proof,
+            )
+            .unwrap();
+        assert_eq!((lhs, rhs), (accumulator.lhs, accumulator.rhs));
+    }
+}
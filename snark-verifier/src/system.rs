@@ -1,3 +1,7 @@
 //! Proof systems `snark-verifier` supports
 
 pub mod halo2;
+pub mod nova;
+
+#[cfg(feature = "loader_evm")]
+pub mod scroll;
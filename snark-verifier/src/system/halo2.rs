@@ -6,43 +6,63 @@ use crate::halo2_proofs::{
 };
 use crate::{
     util::{
-        arithmetic::{root_of_unity, CurveAffine, Domain, FieldExt, Rotation},
+        arithmetic::{
+            root_of_unity, Curve, CurveAffine, Domain, FieldExt, PrimeCurveAffine, Rotation,
+        },
         protocol::{
-            CommonPolynomial, Expression, InstanceCommittingKey, Query, QuotientPolynomial,
+            rotation_sets, CommonPolynomial, Expression, InstanceCommittingKey, Query,
+            QuotientPolynomial,
         },
         Itertools,
     },
-    Protocol,
+    Error, Protocol,
 };
 use num_integer::Integer;
 use std::{io, iter, mem::size_of};
 
 pub mod transcript;
 
+#[cfg(feature = "testing")]
+pub mod malicious;
+
 #[cfg(test)]
 #[cfg(feature = "loader_halo2")]
 pub(crate) mod test;
 
+/// Polynomial commitment scheme [`Config`] is built for, which determines the
+/// valid layout of accumulator in instances.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Pcs {
+    #[default]
+    Kzg,
+    Ipa,
+}
+
 /// Configuration for converting a [`VerifyingKey`] of [`halo2_proofs`] into
 /// [`PlonkProtocol`].
+///
+/// It's validated against the verifying key by [`try_compile`].
 #[derive(Clone, Debug, Default)]
 pub struct Config {
+    pcs: Pcs,
     zk: bool,
     query_instance: bool,
     num_proof: usize,
     num_instance: Vec<usize>,
     accumulator_indices: Option<Vec<(usize, usize)>>,
+    placeholder_fixed_commitments: bool,
+    simplify_quotient: bool,
 }
 
 impl Config {
     /// Returns [`Config`] with `query_instance` set to `false`.
     pub fn kzg() -> Self {
-        Self { zk: true, query_instance: false, num_proof: 1, ..Default::default() }
+        Self { pcs: Pcs::Kzg, zk: true, query_instance: false, num_proof: 1, ..Default::default() }
     }
 
     /// Returns [`Config`] with `query_instance` set to `true`.
     pub fn ipa() -> Self {
-        Self { zk: true, query_instance: true, num_proof: 1, ..Default::default() }
+        Self { pcs: Pcs::Ipa, zk: true, query_instance: true, num_proof: 1, ..Default::default() }
     }
 
     /// Set `zk`
@@ -78,29 +98,212 @@ impl Config {
         self.accumulator_indices = accumulator_indices;
         self
     }
+
+    /// Set `placeholder_fixed_commitments`. When set, fixed commitments and
+    /// `transcript_initial_state` are replaced by deterministic placeholders,
+    /// so EVM verifier could be generated before fixed values are final, and
+    /// patched by [`patch_placeholders`] later.
+    pub fn set_placeholder_fixed_commitments(
+        mut self,
+        placeholder_fixed_commitments: bool,
+    ) -> Self {
+        self.placeholder_fixed_commitments = placeholder_fixed_commitments;
+        self
+    }
+
+    /// Set `simplify_quotient`. When set, numerator of quotient is simplified
+    /// by [`Expression::simplified`], which prunes terms of selectors never
+    /// enabled and lookups with such inputs and merges identical terms, then
+    /// has common factors of terms pulled out by [`Expression::factored`],
+    /// both to save verification cost. It's off by default since it might
+    /// change [`Protocol`] (and in turn verifying keys of circuits verifying
+    /// it) even when nothing is pruned.
+    pub fn set_simplify_quotient(mut self, simplify_quotient: bool) -> Self {
+        self.simplify_quotient = simplify_quotient;
+        self
+    }
+
+    /// Validate `num_instance` and `accumulator_indices` against the
+    /// constraint system and the chosen polynomial commitment scheme.
+    fn validate<F: FieldExt>(&self, cs: &ConstraintSystem<F>) -> Result<(), Error> {
+        if self.num_instance.len() != cs.num_instance_columns() {
+            return Err(Error::InvalidConfig(format!(
+                "num_instance has {} columns but circuit has {} instance columns",
+                self.num_instance.len(),
+                cs.num_instance_columns()
+            )));
+        }
+
+        let accumulator_indices = match &self.accumulator_indices {
+            Some(accumulator_indices) => accumulator_indices,
+            None => return Ok(()),
+        };
+        if accumulator_indices.is_empty() {
+            return Err(Error::InvalidConfig(
+                "accumulator_indices should be None when there is no accumulator".to_string(),
+            ));
+        }
+        for (idx, &(column, row)) in accumulator_indices.iter().enumerate() {
+            if column >= self.num_instance.len() || row >= self.num_instance[column] {
+                return Err(Error::InvalidConfig(format!(
+                    "accumulator_indices[{idx}] = ({column}, {row}) is out of instances of \
+                     num_instance {:?}",
+                    self.num_instance
+                )));
+            }
+            if accumulator_indices[..idx].contains(&(column, row)) {
+                return Err(Error::InvalidConfig(format!(
+                    "accumulator_indices[{idx}] = ({column}, {row}) is duplicated"
+                )));
+            }
+        }
+        // KZG accumulator consists of lhs and rhs, each encoded as x and y
+        // coordinates with the same number of limbs.
+        if self.pcs == Pcs::Kzg && accumulator_indices.len() % 4 != 0 {
+            return Err(Error::InvalidConfig(format!(
+                "KZG accumulator should be encoded in multiple of 4 instances, but got {}",
+                accumulator_indices.len()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Feature of [`ConstraintSystem`] (or of [`Config`] for it) that can't be
+/// modeled by [`PlonkProtocol`], reported by [`audit`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UnsupportedFeature {
+    /// Compiling without zero-knowledge.
+    NonZk,
+    /// Simple selector not yet converted into fixed column, which should have
+    /// been done by keygen.
+    Selector(ExpressionLocation),
+    /// Advice column in a phase beyond [`ThirdPhase`].
+    AdviceColumnPhase { column: usize, phase: u8 },
+    /// Challenge in a phase beyond [`ThirdPhase`].
+    ChallengePhase { challenge: usize, phase: u8 },
+}
+
+/// Location of an expression in [`ConstraintSystem`]. Gates and lookups are
+/// identified by their index in [`ConstraintSystem::gates`] and
+/// [`ConstraintSystem::lookups`], since their names are not exposed by every
+/// supported fork of [`halo2_proofs`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExpressionLocation {
+    /// The `constraint`-th polynomial of the `gate`-th gate.
+    Gate { gate: usize, constraint: usize },
+    /// Input or table expressions of the `lookup`-th lookup.
+    Lookup { lookup: usize },
+}
+
+/// Returns all features of `vk` and `config` that can't be modeled by
+/// [`PlonkProtocol`], which [`try_compile`] rejects with
+/// [`Error::UnsupportedFeatures`] instead of producing a wrong protocol or
+/// panicking mid-way.
+pub fn audit<C: CurveAffine>(vk: &VerifyingKey<C>, config: &Config) -> Vec<UnsupportedFeature> {
+    let cs = vk.cs();
+    let has_selector = |expression: &plonk::Expression<C::Scalar>| {
+        expression.evaluate(
+            &|_| false,
+            &|_| true,
+            &|_| false,
+            &|_| false,
+            &|_| false,
+            &|_| false,
+            &|a| a,
+            &|a, b| a || b,
+            &|a, b| a || b,
+            &|a, _| a,
+        )
+    };
+
+    let gate_selectors = cs.gates().iter().enumerate().flat_map(|(idx, gate)| {
+        gate.polynomials().iter().enumerate().filter_map(move |(constraint, poly)| {
+            has_selector(poly).then_some(ExpressionLocation::Gate { gate: idx, constraint })
+        })
+    });
+    let lookup_selectors = cs.lookups().iter().enumerate().filter_map(|(lookup, argument)| {
+        argument
+            .input_expressions()
+            .iter()
+            .chain(argument.table_expressions())
+            .any(has_selector)
+            .then_some(ExpressionLocation::Lookup { lookup })
+    });
+
+    iter::empty()
+        .chain((!config.zk).then_some(UnsupportedFeature::NonZk))
+        .chain(gate_selectors.chain(lookup_selectors).map(UnsupportedFeature::Selector))
+        .chain(cs.advice_column_phase().into_iter().enumerate().filter_map(|(column, phase)| {
+            (phase > 2).then_some(UnsupportedFeature::AdviceColumnPhase { column, phase })
+        }))
+        .chain(cs.challenge_phase().into_iter().enumerate().filter_map(|(challenge, phase)| {
+            (phase > 2).then_some(UnsupportedFeature::ChallengePhase { challenge, phase })
+        }))
+        .collect()
 }
 
 /// Convert a [`VerifyingKey`] of [`halo2_proofs`] into [`PlonkProtocol`].
+///
+/// # Panics
+///
+/// Panics if `config` is inconsistent with `vk` or `params`, or if there is
+/// any unsupported feature, see [`try_compile`] for the error returned
+/// instead.
 pub fn compile<'a, C: CurveAffine, P: Params<'a, C>>(
     params: &P,
     vk: &VerifyingKey<C>,
     config: Config,
 ) -> Protocol<C> {
-    assert_eq!(vk.get_domain().k(), params.k());
+    try_compile(params, vk, config).unwrap_or_else(|err| panic!("{err:?}"))
+}
+
+/// Convert a [`VerifyingKey`] of [`halo2_proofs`] into [`PlonkProtocol`], and
+/// returns [`Error::InvalidConfig`] if `config` is inconsistent with `vk` or
+/// `params`, or [`Error::UnsupportedFeatures`] reported by [`audit`].
+pub fn try_compile<'a, C: CurveAffine, P: Params<'a, C>>(
+    params: &P,
+    vk: &VerifyingKey<C>,
+    config: Config,
+) -> Result<Protocol<C>, Error> {
+    if vk.get_domain().k() != params.k() {
+        return Err(Error::InvalidConfig(format!(
+            "Verifying key has degree {} but params has degree {}",
+            vk.get_domain().k(),
+            params.k()
+        )));
+    }
 
     let cs = vk.cs();
-    let Config { zk, query_instance, num_proof, num_instance, accumulator_indices } = config;
+    config.validate(cs)?;
+    let unsupported = audit(vk, &config);
+    if !unsupported.is_empty() {
+        return Err(Error::UnsupportedFeatures(unsupported));
+    }
+    let Config {
+        pcs: _,
+        zk,
+        query_instance,
+        num_proof,
+        num_instance,
+        accumulator_indices,
+        placeholder_fixed_commitments,
+        simplify_quotient,
+    } = config;
 
     let k = params.k() as usize;
     let domain = Domain::new(k, root_of_unity(k));
 
-    let preprocessed = vk
-        .fixed_commitments()
-        .iter()
-        .chain(vk.permutation().commitments().iter())
-        .cloned()
-        .map(Into::into)
-        .collect();
+    let preprocessed = if placeholder_fixed_commitments {
+        (0..vk.fixed_commitments().len()).map(placeholder_fixed_commitment).collect_vec()
+    } else {
+        vk.fixed_commitments().clone()
+    }
+    .into_iter()
+    .chain(vk.permutation().commitments().iter().cloned())
+    .collect();
 
     let polynomials = &Polynomials::new(cs, zk, query_instance, num_instance, num_proof);
 
@@ -128,7 +331,13 @@ pub fn compile<'a, C: CurveAffine, P: Params<'a, C>>(
         .chain(polynomials.random_query())
         .collect();
 
-    let transcript_initial_state = transcript_initial_state::<C>(vk);
+    let rotation_sets = rotation_sets(&queries);
+
+    let transcript_initial_state = if placeholder_fixed_commitments {
+        placeholder_transcript_initial_state::<C>()
+    } else {
+        transcript_initial_state::<C>(vk)
+    };
 
     let instance_committing_key = query_instance.then(|| {
         instance_committing_key(
@@ -141,7 +350,15 @@ pub fn compile<'a, C: CurveAffine, P: Params<'a, C>>(
         .map(|accumulator_indices| polynomials.accumulator_indices(accumulator_indices))
         .unwrap_or_default();
 
-    Protocol {
+    let quotient = {
+        let mut quotient = polynomials.quotient();
+        if simplify_quotient {
+            quotient.numerator = quotient.numerator.simplified().factored();
+        }
+        quotient
+    };
+
+    Ok(Protocol {
         domain,
         preprocessed,
         num_instance: polynomials.num_instance(),
@@ -149,12 +366,13 @@ pub fn compile<'a, C: CurveAffine, P: Params<'a, C>>(
         num_challenge: polynomials.num_challenge(),
         evaluations,
         queries,
-        quotient: polynomials.quotient(),
+        quotient,
         transcript_initial_state: Some(transcript_initial_state),
         instance_committing_key,
         linearization: None,
         accumulator_indices,
-    }
+        rotation_sets,
+    })
 }
 
 impl From<poly::Rotation> for Rotation {
@@ -723,6 +941,62 @@ fn transcript_initial_state<C: CurveAffine>(vk: &VerifyingKey<C>) -> C::Scalar {
     transcript.0
 }
 
+/// Tag of placeholders, which is `"phldr"` in big-endian.
+const PLACEHOLDER_TAG: u64 = 0x7068_6c64_7200_0000;
+
+/// Returns deterministic placeholder of `idx`-th fixed commitment.
+pub fn placeholder_fixed_commitment<C: CurveAffine>(idx: usize) -> C {
+    (C::generator() * C::Scalar::from(PLACEHOLDER_TAG + 1 + idx as u64)).to_affine()
+}
+
+/// Returns deterministic placeholder of `transcript_initial_state`.
+pub fn placeholder_transcript_initial_state<C: CurveAffine>() -> C::Scalar {
+    C::Scalar::from(PLACEHOLDER_TAG)
+}
+
+/// Patch EVM verifier `code` generated with
+/// [`Config::set_placeholder_fixed_commitments`] by replacing placeholders
+/// with the real fixed commitments and `transcript_initial_state` of `vk`.
+///
+/// # Panics
+///
+/// Panics if any placeholder is not found in `code`, which means `code` is
+/// not generated with placeholders or the codegen has changed how constants
+/// are emitted.
+#[cfg(feature = "loader_evm")]
+pub fn patch_placeholders<C>(code: &str, vk: &VerifyingKey<C>) -> String
+where
+    C: CurveAffine,
+    C::Scalar: crate::util::arithmetic::PrimeField<Repr = [u8; 0x20]>,
+{
+    use crate::{loader::evm::fe_to_u256, util::arithmetic::PrimeField};
+
+    fn hex_encode<C: CurveAffine>(ec_point: &C) -> [String; 2] {
+        let coordinates = ec_point.coordinates().unwrap();
+        [coordinates.x(), coordinates.y()].map(|coordinate| {
+            let mut repr = coordinate.to_repr();
+            repr.as_mut().reverse();
+            format!("0x{}", hex::encode(repr.as_ref()))
+        })
+    }
+
+    let replace = |code: String, placeholder: &str, real: &str| {
+        assert!(code.contains(placeholder), "Placeholder {placeholder} not found in code");
+        code.replace(placeholder, real)
+    };
+
+    let mut code = code.to_string();
+    for (idx, fixed_commitment) in vk.fixed_commitments().iter().enumerate() {
+        let placeholder = hex_encode(&placeholder_fixed_commitment::<C>(idx));
+        for (placeholder, real) in placeholder.iter().zip(hex_encode(fixed_commitment).iter()) {
+            code = replace(code, placeholder, real);
+        }
+    }
+    let placeholder = fe_to_u256(placeholder_transcript_initial_state::<C>());
+    let real = fe_to_u256(transcript_initial_state::<C>(vk));
+    replace(code, &format!(", {placeholder})"), &format!(", {real})"))
+}
+
 fn instance_committing_key<'a, C: CurveAffine, P: Params<'a, C>>(
     params: &P,
     len: usize,
@@ -0,0 +1,219 @@
+//! Test-only malicious prover, which crafts subtly invalid proofs from a valid
+//! one, so rejection of each class of them could be asserted for native,
+//! halo2 and EVM verifiers alike.
+//!
+//! Proofs are crafted by replacing commitments in proof by other ones in the
+//! same proof, so they still decode and are only rejected by the checks of
+//! the argument they belong to, instead of failing early on encoding.
+use crate::halo2_proofs::plonk::VerifyingKey;
+use crate::{
+    system::halo2::{Config, Polynomials},
+    util::arithmetic::{CurveAffine, GroupEncoding},
+};
+use std::{
+    fmt::{self, Display},
+    ops::Range,
+};
+
+/// Class of invalid proof crafted by [`MaliciousProver`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Malicious {
+    /// Replace the first permutation grand product commitment by the last
+    /// witness commitment.
+    WrongPermutationProduct,
+    /// Replace the first quotient chunk commitment by the last witness
+    /// commitment.
+    WrongQuotientChunk,
+    /// Swap permuted input and permuted table commitments of the first lookup.
+    MismatchedLookupCommitments,
+}
+
+impl Malicious {
+    /// Returns all classes of invalid proof.
+    pub fn all() -> [Malicious; 3] {
+        [
+            Malicious::WrongPermutationProduct,
+            Malicious::WrongQuotientChunk,
+            Malicious::MismatchedLookupCommitments,
+        ]
+    }
+}
+
+impl Display for Malicious {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Malicious::WrongPermutationProduct => "wrong permutation grand product",
+            Malicious::WrongQuotientChunk => "wrong quotient chunk",
+            Malicious::MismatchedLookupCommitments => "mismatched lookup commitments",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Crafts invalid proofs of [`Malicious`] classes from valid proofs of a
+/// [`VerifyingKey`].
+///
+/// A proof starts with witness commitments in the order of
+/// [`Protocol::num_witness`](crate::Protocol::num_witness), which are
+/// advices of each phase, permuted inputs and tables of lookups, then grand
+/// products of permutation and lookups followed by the random polynomial,
+/// then the quotient chunks.
+#[derive(Clone, Debug)]
+pub struct MaliciousProver {
+    point_size: usize,
+    num_advice: usize,
+    num_lookup_permuted: usize,
+    num_permutation_z: usize,
+    num_witness: usize,
+}
+
+impl MaliciousProver {
+    /// Initialize a [`MaliciousProver`] for proofs of `vk` compiled with
+    /// `config`, whose commitments are compressed as by transcripts of
+    /// [`halo2_proofs`](crate::halo2_proofs).
+    pub fn new<C: CurveAffine>(vk: &VerifyingKey<C>, config: &Config) -> Self {
+        let polynomials = Polynomials::new(
+            vk.cs(),
+            config.zk,
+            config.query_instance,
+            config.num_instance.clone(),
+            config.num_proof,
+        );
+        let num_witness = polynomials.num_witness();
+        let num_phase = polynomials.num_advice.len();
+        Self {
+            point_size: C::Repr::default().as_ref().len(),
+            num_advice: num_witness[..num_phase].iter().sum(),
+            num_lookup_permuted: num_witness[num_phase],
+            num_permutation_z: polynomials.num_proof * polynomials.num_permutation_z,
+            num_witness: num_witness.iter().sum(),
+        }
+    }
+
+    /// Set size of encoded commitment, e.g. `64` for
+    /// [`EvmTranscript`](crate::system::halo2::transcript::evm::EvmTranscript)
+    /// which encodes both coordinates.
+    pub fn with_point_size(mut self, point_size: usize) -> Self {
+        self.point_size = point_size;
+        self
+    }
+
+    fn commitment(&self, idx: usize) -> Range<usize> {
+        idx * self.point_size..(idx + 1) * self.point_size
+    }
+
+    /// Returns `proof` crafted to be invalid of `malicious`, or `None` if it's
+    /// not applicable (e.g. [`Malicious::MismatchedLookupCommitments`] with no
+    /// lookup) or the replacing commitment happens to be the same.
+    pub fn craft(&self, proof: &[u8], malicious: Malicious) -> Option<Vec<u8>> {
+        let (target, source) = match malicious {
+            Malicious::WrongPermutationProduct => {
+                if self.num_permutation_z == 0 {
+                    return None;
+                }
+                (self.num_advice + self.num_lookup_permuted, self.num_witness - 1)
+            }
+            Malicious::WrongQuotientChunk => (self.num_witness, self.num_witness - 1),
+            Malicious::MismatchedLookupCommitments => {
+                if self.num_lookup_permuted == 0 {
+                    return None;
+                }
+                (self.num_advice, self.num_advice + 1)
+            }
+        };
+        let (target, source) = (self.commitment(target), self.commitment(source));
+        if proof.len() < target.end.max(source.end)
+            || proof[target.clone()] == proof[source.clone()]
+        {
+            return None;
+        }
+
+        let mut crafted = proof.to_vec();
+        crafted.copy_within(source.clone(), target.start);
+        if malicious == Malicious::MismatchedLookupCommitments {
+            crafted[source].copy_from_slice(&proof[target]);
+        }
+        Some(crafted)
+    }
+
+    /// Returns `proof` crafted to be invalid of every applicable [`Malicious`]
+    /// class.
+    pub fn craft_all(&self, proof: &[u8]) -> Vec<(Malicious, Vec<u8>)> {
+        Malicious::all()
+            .into_iter()
+            .filter_map(|malicious| Some((malicious, self.craft(proof, malicious)?)))
+            .collect()
+    }
+}
+
+#[cfg(all(test, feature = "loader_halo2"))]
+mod test {
+    use crate::{
+        halo2_curves::bn256::{Bn256, G1Affine},
+        halo2_proofs::{
+            poly::{
+                commitment::ParamsProver,
+                kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK},
+            },
+            transcript::{Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer},
+        },
+        loader::native::NativeLoader,
+        pcs::kzg::{Bdfg21, Kzg, KzgDecidingKey, KzgSuccinctVerifyingKey},
+        system::halo2::{
+            malicious::{Malicious, MaliciousProver},
+            test::{
+                kzg::{halo2_kzg_config, halo2_kzg_create_snark, halo2_kzg_prepare},
+                StandardPlonk,
+            },
+        },
+        verifier::{Plonk, PlonkVerifier},
+    };
+    use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+
+    #[test]
+    fn test_malicious_prover() {
+        type Pcs = Kzg<Bn256, Bdfg21>;
+
+        let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+            9,
+            halo2_kzg_config!(true, 2),
+            StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+        );
+        let svk = KzgSuccinctVerifyingKey::new(params.get_g()[0]);
+        let dk = KzgDecidingKey::<Bn256>::new(params.g2(), params.s_g2());
+        let snark = halo2_kzg_create_snark!(
+            ProverSHPLONK<_>,
+            VerifierSHPLONK<_>,
+            Blake2bWrite<_, _, _>,
+            Blake2bRead<_, _, _>,
+            Challenge255<_>,
+            &params,
+            &pk,
+            &protocol,
+            &circuits
+        );
+        let verify = |proof: &[u8]| {
+            let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(proof);
+            let proof = <Plonk<Pcs> as PlonkVerifier<_, NativeLoader, _>>::read_proof(
+                &svk,
+                &snark.protocol,
+                &snark.instances,
+                &mut transcript,
+            );
+            Plonk::<Pcs>::verify(&svk, &dk, &snark.protocol, &snark.instances, &proof)
+        };
+        assert!(verify(&snark.proof));
+
+        // `StandardPlonk` has no lookup.
+        let crafted =
+            MaliciousProver::new(pk.get_vk(), &halo2_kzg_config!(true, 2)).craft_all(&snark.proof);
+        assert_eq!(
+            crafted.iter().map(|(malicious, _)| *malicious).collect::<Vec<_>>(),
+            [Malicious::WrongPermutationProduct, Malicious::WrongQuotientChunk]
+        );
+        for (malicious, proof) in crafted {
+            assert_eq!(proof.len(), snark.proof.len());
+            assert!(!verify(&proof), "Verifier accepts {malicious}");
+        }
+    }
+}
@@ -14,9 +14,9 @@ use rand_chacha::rand_core::RngCore;
 use std::{fs, io::Cursor};
 
 mod circuit;
-mod kzg;
+pub(crate) mod kzg;
 
-pub use circuit::standard::StandardPlonk;
+pub use circuit::{rotated_instance::RotatedInstance, standard::StandardPlonk};
 
 pub fn read_or_create_srs<'a, C: CurveAffine, P: ParamsProver<'a, C>>(
     dir: &str,
@@ -212,3 +212,85 @@ macro_rules! halo2_native_verify {
 }
 
 pub(crate) use {halo2_create_snark, halo2_native_verify, halo2_prepare};
+
+#[test]
+fn test_compile_reject_invalid_config() {
+    use crate::{
+        halo2_curves::bn256::{Bn256, Fr},
+        halo2_proofs::plonk::keygen_vk,
+        system::halo2::{test::kzg::setup, try_compile, Config},
+        Error,
+    };
+    use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+
+    let params = setup::<Bn256>(9);
+    let circuit = StandardPlonk::<Fr>::rand(ChaCha20Rng::from_seed(Default::default()));
+    let vk = keygen_vk(&params, &circuit).unwrap();
+    let is_invalid =
+        |config: Config| matches!(try_compile(&params, &vk, config), Err(Error::InvalidConfig(_)));
+    let accumulator_indices = |indices: &[(usize, usize)]| Some(indices.to_vec());
+
+    assert!(try_compile(&params, &vk, Config::kzg().with_num_instance(vec![4])).is_ok());
+    assert!(try_compile(
+        &params,
+        &vk,
+        Config::kzg().with_num_instance(vec![4]).with_accumulator_indices(accumulator_indices(&[
+            (0, 0),
+            (0, 1),
+            (0, 2),
+            (0, 3)
+        ]))
+    )
+    .is_ok());
+
+    // Mismatching instance columns
+    assert!(is_invalid(Config::kzg()));
+    assert!(is_invalid(Config::kzg().with_num_instance(vec![1, 1])));
+    // Empty, out of range, duplicated or not multiple of 4 accumulator indices
+    for indices in [
+        &[][..],
+        &[(0, 0), (0, 1), (0, 2), (0, 4)],
+        &[(0, 0), (0, 1), (1, 2), (0, 3)],
+        &[(0, 0), (0, 1), (0, 1), (0, 3)],
+        &[(0, 0), (0, 1), (0, 2)],
+    ] {
+        let config = Config::kzg()
+            .with_num_instance(vec![4])
+            .with_accumulator_indices(accumulator_indices(indices));
+        assert!(is_invalid(config));
+    }
+    // Mismatching degree
+    let mut params_downsized = params.clone();
+    params_downsized.downsize(8);
+    assert!(matches!(
+        try_compile(&params_downsized, &vk, Config::kzg().with_num_instance(vec![4])),
+        Err(Error::InvalidConfig(_))
+    ));
+}
+
+#[test]
+fn test_compile_audit() {
+    use crate::{
+        halo2_curves::bn256::{Bn256, Fr},
+        halo2_proofs::plonk::keygen_vk,
+        system::halo2::{audit, test::kzg::setup, try_compile, Config, UnsupportedFeature},
+        Error,
+    };
+    use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+
+    let params = setup::<Bn256>(9);
+    let circuit = StandardPlonk::<Fr>::rand(ChaCha20Rng::from_seed(Default::default()));
+    let vk = keygen_vk(&params, &circuit).unwrap();
+
+    let config = Config::kzg().with_num_instance(vec![4]);
+    assert!(audit(&vk, &config).is_empty());
+
+    let config = config.set_zk(false);
+    assert_eq!(audit(&vk, &config), vec![UnsupportedFeature::NonZk]);
+    match try_compile(&params, &vk, config) {
+        Err(Error::UnsupportedFeatures(features)) => {
+            assert_eq!(features, vec![UnsupportedFeature::NonZk])
+        }
+        result => panic!("Expected unsupported features but got {result:?}"),
+    }
+}
@@ -1,2 +1,3 @@
 // pub mod maingate;
+pub mod rotated_instance;
 pub mod standard;
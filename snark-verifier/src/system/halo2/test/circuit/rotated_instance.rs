@@ -0,0 +1,93 @@
+#[cfg(feature = "halo2-axiom")]
+use crate::halo2_proofs::plonk::Assigned;
+use crate::halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Fixed, Instance},
+    poly::Rotation,
+};
+use crate::util::arithmetic::FieldExt;
+use rand::RngCore;
+
+#[derive(Clone)]
+pub struct RotatedInstanceConfig {
+    a: Column<Advice>,
+    q: Column<Fixed>,
+    instance: Column<Instance>,
+}
+
+impl RotatedInstanceConfig {
+    pub fn configure<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+        let a = meta.advice_column();
+        let q = meta.fixed_column();
+        let instance = meta.instance_column();
+
+        meta.create_gate("q·(a - instance[-1] - instance[1]) = 0", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let q = meta.query_fixed(q, Rotation::cur());
+            let [prev, next] = [Rotation::prev(), Rotation::next()]
+                .map(|rotation| meta.query_instance(instance, rotation));
+            Some(q * (a - prev - next))
+        });
+
+        RotatedInstanceConfig { a, q, instance }
+    }
+}
+
+/// Circuit querying instances at non-zero rotations, which constrains
+/// `a[i] = instance[i - 1] + instance[i + 1]` for `i` in `1..=2`.
+#[derive(Clone, Default)]
+pub struct RotatedInstance<F>([F; 4]);
+
+impl<F: FieldExt> RotatedInstance<F> {
+    pub fn rand<R: RngCore>(mut rng: R) -> Self {
+        Self([(); 4].map(|_| F::from(rng.next_u32() as u64)))
+    }
+
+    pub fn num_instance() -> Vec<usize> {
+        vec![4]
+    }
+
+    pub fn instances(&self) -> Vec<Vec<F>> {
+        vec![self.0.to_vec()]
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for RotatedInstance<F> {
+    type Config = RotatedInstanceConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        RotatedInstanceConfig::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "",
+            |mut region| {
+                for row in 1..=2 {
+                    let a = self.0[row - 1] + self.0[row + 1];
+                    #[cfg(feature = "halo2-pse")]
+                    {
+                        region.assign_advice(|| "", config.a, row, || Value::known(a))?;
+                        region.assign_fixed(|| "", config.q, row, || Value::known(F::one()))?;
+                    }
+                    #[cfg(feature = "halo2-axiom")]
+                    {
+                        region.assign_advice(config.a, row, Value::known(Assigned::Trivial(a)))?;
+                        region.assign_fixed(config.q, row, Assigned::Trivial(F::one()));
+                    }
+                }
+
+                Ok(())
+            },
+        )
+    }
+}
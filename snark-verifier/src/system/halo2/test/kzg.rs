@@ -10,6 +10,18 @@ mod evm;
 #[cfg(feature = "loader_halo2")]
 pub(crate) mod halo2;
 
+#[cfg(all(feature = "loader_move", feature = "loader_evm"))]
+mod move_lang;
+
+#[cfg(all(feature = "loader_solana", feature = "loader_evm"))]
+mod solana;
+
+#[cfg(feature = "loader_ark")]
+mod ark;
+
+#[cfg(feature = "cosmwasm")]
+mod cosmwasm;
+
 #[allow(dead_code)]
 pub const TESTDATA_DIR: &str = "./src/system/halo2/test/data";
 
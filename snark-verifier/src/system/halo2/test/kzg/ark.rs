@@ -0,0 +1,83 @@
+use crate::halo2_curves::bn256::{Bn256, Fr, G1Affine};
+use crate::halo2_proofs::{
+    poly::{
+        commitment::ParamsProver,
+        kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK},
+    },
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer},
+};
+use crate::{
+    loader::{
+        ark::{fr_to_ark, g1_to_ark, ArkLoader, ArkTranscript, Scalar, LOADER},
+        native::NativeLoader,
+    },
+    pcs::kzg::{Bdfg21, Kzg, KzgAccumulator, KzgDecidingKey, KzgSuccinctVerifyingKey},
+    system::halo2::test::{
+        kzg::{halo2_kzg_config, halo2_kzg_create_snark, halo2_kzg_prepare},
+        StandardPlonk,
+    },
+    util::{arithmetic::Field, Itertools},
+    verifier::{Plonk, PlonkVerifier},
+};
+use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+
+type Pv = Plonk<Kzg<Bn256, Bdfg21>>;
+
+#[test]
+fn test_ark_verify() {
+    let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+        9,
+        halo2_kzg_config!(true, 1),
+        StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+    );
+    let snark = halo2_kzg_create_snark!(
+        ProverSHPLONK<_>,
+        VerifierSHPLONK<_>,
+        Blake2bWrite<_, _, _>,
+        Blake2bRead<_, _, _>,
+        Challenge255<_>,
+        &params,
+        &pk,
+        &protocol,
+        &circuits
+    );
+
+    let svk: KzgSuccinctVerifyingKey<G1Affine> = params.get_g()[0].into();
+    let dk: KzgDecidingKey<Bn256> = (params.g2(), params.s_g2()).into();
+    let ark_protocol = snark.protocol.loaded::<ArkLoader>(&LOADER);
+    let ark_accumulators = |instances: &[Vec<Fr>]| {
+        let instances = instances
+            .iter()
+            .map(|instances| {
+                instances.iter().map(|instance| Scalar(fr_to_ark(instance))).collect_vec()
+            })
+            .collect_vec();
+        let mut transcript =
+            ArkTranscript(Blake2bRead::<_, G1Affine, _>::init(snark.proof.as_slice()));
+        let proof = Pv::read_proof(&svk, &ark_protocol, &instances, &mut transcript);
+        let accumulators = Pv::succinct_verify(&svk, &ark_protocol, &instances, &proof);
+        (Pv::verify(&svk, &dk, &ark_protocol, &instances, &proof), accumulators)
+    };
+
+    // Accumulator is the same as the one computed by `NativeLoader`
+    let (verified, accumulators) = ark_accumulators(&snark.instances);
+    assert!(verified);
+    let mut transcript = Blake2bRead::<_, G1Affine, _>::init(snark.proof.as_slice());
+    let proof = <Pv as PlonkVerifier<_, NativeLoader, _>>::read_proof(
+        &svk,
+        &snark.protocol,
+        &snark.instances,
+        &mut transcript,
+    );
+    let native_accumulators = Pv::succinct_verify(&svk, &snark.protocol, &snark.instances, &proof);
+    assert_eq!(accumulators.len(), native_accumulators.len());
+    for (accumulator, KzgAccumulator { lhs, rhs }) in accumulators.iter().zip(native_accumulators) {
+        assert_eq!(accumulator.lhs.0, g1_to_ark(&lhs));
+        assert_eq!(accumulator.rhs.0, g1_to_ark(&rhs));
+    }
+
+    // Proof is bound to instances
+    let mut instances = snark.instances.clone();
+    instances[0][0] += Fr::one();
+    assert!(!ark_accumulators(&instances).0);
+}
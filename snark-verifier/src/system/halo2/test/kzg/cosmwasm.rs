@@ -0,0 +1,67 @@
+use crate::halo2_curves::bn256::{Fr, G1Affine};
+use crate::halo2_proofs::poly::{
+    commitment::ParamsProver,
+    kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK},
+};
+use crate::{
+    system::halo2::{
+        test::{
+            kzg::{halo2_kzg_config, halo2_kzg_create_snark, halo2_kzg_prepare},
+            StandardPlonk,
+        },
+        transcript::evm::{ChallengeEvm, EvmTranscript},
+    },
+    util::arithmetic::{Field, PrimeField},
+    verifier::cosmwasm::CosmwasmVerifier,
+    Error,
+};
+use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+
+/// Encode `instances` and `proof` the same as calldata of the EVM verifier.
+fn encode_calldata(instances: &[Vec<Fr>], proof: &[u8]) -> Vec<u8> {
+    instances
+        .iter()
+        .flatten()
+        .flat_map(|instance| instance.to_repr().into_iter().rev())
+        .chain(proof.iter().cloned())
+        .collect()
+}
+
+#[test]
+fn test_cosmwasm_verifier() {
+    let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+        9,
+        halo2_kzg_config!(true, 1),
+        StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+    );
+    let snark = halo2_kzg_create_snark!(
+        ProverSHPLONK<_>,
+        VerifierSHPLONK<_>,
+        EvmTranscript<G1Affine, _, _, _>,
+        EvmTranscript<G1Affine, _, _, _>,
+        ChallengeEvm<_>,
+        &params,
+        &pk,
+        &protocol,
+        &circuits
+    );
+
+    let verifier =
+        CosmwasmVerifier::new(snark.protocol, params.get_g()[0], params.g2(), params.s_g2());
+    let calldata = encode_calldata(&snark.instances, &snark.proof);
+    assert_eq!(verifier.calldata_len(), calldata.len());
+    assert!(verifier.verify(&calldata).unwrap());
+
+    let mut instances = snark.instances.clone();
+    instances[0][0] += Fr::one();
+    assert!(!verifier.verify(&encode_calldata(&instances, &snark.proof)).unwrap());
+    assert!(verifier.verify(&calldata[..calldata.len() - 0x20]).is_err());
+
+    // Replace the first commitment in proof with (1, 1), which is not on curve
+    let mut invalid = calldata.clone();
+    let offset = 0x20 * snark.instances.iter().map(Vec::len).sum::<usize>();
+    invalid[offset..offset + 0x40].fill(0);
+    invalid[offset + 0x1f] = 1;
+    invalid[offset + 0x3f] = 1;
+    assert!(matches!(verifier.verify(&invalid), Err(Error::Transcript(..))));
+}
@@ -8,7 +8,7 @@ use crate::{
                 self, halo2_kzg_config, halo2_kzg_create_snark, halo2_kzg_native_verify,
                 halo2_kzg_prepare, BITS, LIMBS,
             },
-            StandardPlonk,
+            RotatedInstance, StandardPlonk,
         },
         transcript::evm::{ChallengeEvm, EvmTranscript},
     },
@@ -111,6 +111,12 @@ test!(
     halo2_kzg_config!(true, 1),
     StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
 );
+test!(
+    zk_rotated_instance_rand,
+    9,
+    halo2_kzg_config!(true, 1),
+    RotatedInstance::rand(ChaCha20Rng::from_seed(Default::default()))
+);
 /*
 test!(
     zk_main_gate_with_range_with_mock_kzg_accumulator,
@@ -135,3 +141,201 @@ test!(
     halo2_kzg_config!(true, 1, Some((0..4 * LIMBS).map(|idx| (0, idx)).collect())),
     kzg::halo2::Accumulation::two_snark_with_accumulator()
 );
+
+#[test]
+fn test_evm_patch_placeholders() {
+    use crate::{
+        loader::evm::EvmLoader,
+        system::halo2::{compile, patch_placeholders},
+        verifier::PlonkVerifier,
+        Protocol,
+    };
+    use halo2_curves::bn256::{Fq, Fr};
+    use std::rc::Rc;
+
+    type Pv = Plonk<Kzg<Bn256, Bdfg21>, LimbsEncoding<LIMBS, BITS>>;
+
+    let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+        9,
+        halo2_kzg_config!(true, 1),
+        StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+    );
+    let solidity_code_of = |protocol: &Protocol<G1Affine>| {
+        use halo2_proofs::poly::commitment::ParamsProver;
+
+        let loader = EvmLoader::new::<Fq, Fr>();
+        let svk = params.get_g()[0].into();
+        let dk = (params.g2(), params.s_g2()).into();
+        let protocol = protocol.loaded(&loader);
+        let mut transcript = EvmTranscript::<_, Rc<EvmLoader>, _, _>::new(&loader);
+        let instances = transcript.load_instances(protocol.num_instance.clone());
+        let proof = Pv::read_proof(&svk, &protocol, &instances, &mut transcript);
+        Pv::verify(&svk, &dk, &protocol, &instances, &proof);
+        loader.solidity_code()
+    };
+
+    let placeholder = compile(
+        &params,
+        pk.get_vk(),
+        halo2_kzg_config!(true, 1)
+            .with_num_instance(circuits[0].instances().iter().map(Vec::len).collect())
+            .set_placeholder_fixed_commitments(true),
+    );
+    let placeholder_code = solidity_code_of(&placeholder);
+    let code = solidity_code_of(&protocol);
+    assert_ne!(placeholder_code, code);
+    assert_eq!(patch_placeholders(&placeholder_code, pk.get_vk()), code);
+}
+
+#[test]
+fn test_evm_split_verifier() {
+    use crate::{
+        loader::evm::{
+            compile_solidity, compile_split_verifier, encode_calldata, EvmLoader, ExecutorBuilder,
+            ReturnConvention, SolcConfig, SplitVerifier,
+        },
+        verifier::PlonkVerifier,
+    };
+    use ethereum_types::Address;
+    use halo2_curves::bn256::{Fq, Fr};
+    use halo2_proofs::poly::commitment::ParamsProver;
+    use std::{io, rc::Rc};
+
+    type Pcs = Kzg<Bn256, Bdfg21>;
+    type Verifier = Plonk<Pcs, LimbsEncoding<LIMBS, BITS>>;
+
+    let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+        9,
+        halo2_kzg_config!(true, 1),
+        StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+    );
+    let snark = halo2_kzg_create_snark!(
+        ProverSHPLONK<_>,
+        VerifierSHPLONK<_>,
+        EvmTranscript<G1Affine, _, _, _>,
+        EvmTranscript<G1Affine, _, _, _>,
+        ChallengeEvm<_>,
+        &params,
+        &pk,
+        &protocol,
+        &circuits
+    );
+    let loader = EvmLoader::new::<Fq, Fr>();
+    {
+        let svk = params.get_g()[0].into();
+        let dk = (params.g2(), params.s_g2()).into();
+        let protocol = snark.protocol.loaded(&loader);
+        let mut transcript = EvmTranscript::<_, Rc<EvmLoader>, _, _>::new(&loader);
+        let instances = transcript.load_instances(vec![snark.instances[0].len()]);
+        let proof = Verifier::read_proof(&svk, &protocol, &instances, &mut transcript);
+        Verifier::verify(&svk, &dk, &protocol, &instances, &proof);
+    }
+
+    let call = |verifier: &SplitVerifier, calldata: Vec<u8>| {
+        let mut evm = ExecutorBuilder::default().with_gas_limit(u64::MAX.into()).build();
+        let caller = Address::from_low_u64_be(0xfe);
+        let mut deploy = |code: Vec<u8>| evm.deploy(caller, code.into(), 0.into()).address.unwrap();
+        let stages = verifier.stages.iter().cloned().map(&mut deploy).collect::<Vec<_>>();
+        let dispatcher = deploy(verifier.dispatcher_deployment_code(&stages));
+        !evm.call_raw(caller, dispatcher, calldata.into(), 0.into()).reverted
+    };
+    let calldata = encode_calldata(&snark.instances, &snark.proof);
+    let mut wrong_calldata = calldata.clone();
+    *wrong_calldata.last_mut().unwrap() ^= 1;
+
+    // Small verifier is not split.
+    let verifier = compile_split_verifier(&loader, &SolcConfig::default()).unwrap();
+    assert!(verifier.stages.is_empty());
+    assert!(call(&verifier, calldata.clone()));
+
+    // Memory is carried across stages.
+    let (dispatcher, stages) = loader.split_solidity_code(3).unwrap();
+    let verifier = SplitVerifier {
+        dispatcher: compile_solidity(&dispatcher),
+        stages: stages.iter().map(|stage| compile_solidity(stage)).collect(),
+    };
+    assert!(call(&verifier, calldata));
+    assert!(!call(&verifier, wrong_calldata));
+
+    // Unsupported splitting is an error instead of a panic.
+    assert!(loader.split_solidity_code(0).is_err());
+    let bool_loader =
+        EvmLoader::builder::<Fq, Fr>().with_return_convention(ReturnConvention::Bool).build();
+    assert!(bool_loader.split_solidity_code(1).is_err());
+    let err = compile_split_verifier(&bool_loader, &SolcConfig::default()).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_evm_domain_separator() {
+    use crate::{
+        loader::evm::{compile_solidity, encode_calldata, execute, EvmLoader},
+        pcs::kzg::{KzgDecidingKey, KzgSuccinctVerifyingKey},
+        util::Itertools,
+        verifier::PlonkVerifier,
+    };
+    use halo2_curves::bn256::{Fq, Fr};
+    use halo2_proofs::{
+        plonk::create_proof,
+        poly::{commitment::ParamsProver, kzg::commitment::KZGCommitmentScheme},
+    };
+    use std::rc::Rc;
+
+    type Pv = Plonk<Kzg<Bn256, Bdfg21>, LimbsEncoding<LIMBS, BITS>>;
+
+    let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+        9,
+        halo2_kzg_config!(true, 1),
+        StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+    );
+    let svk: KzgSuccinctVerifyingKey<G1Affine> = params.get_g()[0].into();
+    let dk: KzgDecidingKey<Bn256> = (params.g2(), params.s_g2()).into();
+    let [tag, other_tag] = [Fr::from(1), Fr::from(2)];
+
+    let instances = circuits.iter().map(|circuit| circuit.instances()).collect_vec();
+    let proof = {
+        let instances = instances
+            .iter()
+            .map(|instances| instances.iter().map(Vec::as_slice).collect_vec())
+            .collect_vec();
+        let instances = instances.iter().map(Vec::as_slice).collect_vec();
+        let mut transcript = EvmTranscript::<G1Affine, NativeLoader, _, _>::new(Vec::new())
+            .with_domain_separator(tag);
+        create_proof::<KZGCommitmentScheme<_>, ProverSHPLONK<_>, ChallengeEvm<_>, _, _, _>(
+            &params,
+            &pk,
+            &circuits,
+            &instances,
+            ChaCha20Rng::from_seed(Default::default()),
+            &mut transcript,
+        )
+        .unwrap();
+        transcript.finalize()
+    };
+    let instances = instances.into_iter().flatten().collect_vec();
+
+    let native_accept = |tag: Fr| {
+        let mut transcript = EvmTranscript::<_, NativeLoader, _, _>::new(proof.as_slice())
+            .with_domain_separator(tag);
+        let proof = Pv::read_proof(&svk, &protocol, &instances, &mut transcript);
+        Pv::verify(&svk, &dk, &protocol, &instances, &proof)
+    };
+    let deployment_code = |tag: Fr| {
+        let loader = EvmLoader::new::<Fq, Fr>();
+        let protocol = protocol.loaded(&loader);
+        let mut transcript =
+            EvmTranscript::<_, Rc<EvmLoader>, _, _>::new(&loader).with_domain_separator(tag);
+        let instances = transcript.load_instances(protocol.num_instance.clone());
+        let proof = Pv::read_proof(&svk, &protocol, &instances, &mut transcript);
+        Pv::verify(&svk, &dk, &protocol, &instances, &proof);
+        compile_solidity(&loader.solidity_code())
+    };
+    let calldata = encode_calldata(&instances, &proof);
+
+    // Verifier on EVM squeezes the same challenges as the native one.
+    assert!(native_accept(tag));
+    assert!(execute(deployment_code(tag), calldata.clone()).0);
+    // Proof can't be replayed against verifier of another circuit with the same shape.
+    assert!(!native_accept(other_tag));
+    assert!(!execute(deployment_code(other_tag), calldata).0);
+}
@@ -144,13 +144,17 @@ pub fn accumulate<'a>(
 
     let mut accumulators = snarks
         .iter()
-        .flat_map(|snark| {
+        .enumerate()
+        .flat_map(|(idx, snark)| {
+            loader.start_op_metering(&format!("snark {idx}"));
             let protocol = snark.protocol.loaded(loader);
             let instances = assign_instances(&snark.instances);
             let mut transcript =
                 PoseidonTranscript::<Rc<Halo2Loader>, _>::new(loader, snark.proof());
             let proof = Plonk::read_proof(svk, &protocol, &instances, &mut transcript);
-            Plonk::succinct_verify(svk, &protocol, &instances, &proof)
+            let accumulators = Plonk::succinct_verify(svk, &protocol, &instances, &proof);
+            loader.end_op_metering();
+            accumulators
         })
         .collect_vec();
 
@@ -376,6 +380,13 @@ impl Circuit<Fr> for Accumulation {
                 let KzgAccumulator { lhs, rhs } =
                     accumulate(&self.svk, &loader, &self.snarks, &self.as_vk, self.as_proof());
 
+                let op_meterings = loader.op_meterings();
+                assert_eq!(op_meterings.len(), self.snarks.len());
+                for (_, op_stats) in op_meterings.iter() {
+                    assert!(op_stats.ec_mul > 0 && op_stats.poseidon_permutation > 0);
+                    assert!(op_stats.range_check > 0);
+                }
+
                 let lhs = lhs.assigned();
                 let rhs = rhs.assigned();
                 // REQUIRED STEP
@@ -459,6 +470,308 @@ test!(
     Accumulation::two_snark_with_accumulator()
 );
 
+#[test]
+#[cfg(feature = "testing")]
+fn test_shplonk_reject_malicious() {
+    use crate::{
+        halo2_proofs::dev::MockProver,
+        pcs::{kzg::KzgDecidingKey, AccumulatorEncoding, Decider},
+        system::halo2::malicious::MaliciousProver,
+    };
+
+    type Encoding = LimbsEncoding<LIMBS, BITS>;
+
+    let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+        9,
+        halo2_kzg_config!(true, 1),
+        StandardPlonk::<_>::rand(ChaCha20Rng::from_seed(Default::default()))
+    );
+    let snark = halo2_kzg_create_snark!(
+        ProverSHPLONK<_>,
+        VerifierSHPLONK<_>,
+        PoseidonTranscript<_, _>,
+        PoseidonTranscript<_, _>,
+        ChallengeScalar<_>,
+        &params,
+        &pk,
+        &protocol,
+        &circuits
+    );
+    let dk: KzgDecidingKey<Bn256> = (params.g2(), params.s_g2()).into();
+
+    // Verifier in circuit computes the same accumulator as native one, which
+    // is only rejected by the deferred pairing check.
+    let crafted =
+        MaliciousProver::new(pk.get_vk(), &halo2_kzg_config!(true, 1)).craft_all(&snark.proof);
+    assert!(!crafted.is_empty());
+    for (malicious, proof) in crafted {
+        let snark = Snark::new(snark.protocol.clone(), snark.instances.clone(), proof);
+        let circuit = Accumulation::new(&params, [snark]);
+        let instances = circuit.instances();
+        MockProver::run(21, &circuit, instances.clone()).unwrap().assert_satisfied();
+
+        let limbs = instances[0].iter().collect_vec();
+        let accumulator =
+            <Encoding as AccumulatorEncoding<G1Affine, NativeLoader, Pcs>>::from_repr(&limbs)
+                .unwrap();
+        assert!(
+            !<Pcs as Decider<G1Affine, NativeLoader>>::decide(&dk, accumulator),
+            "Deferred check accepts {malicious}"
+        );
+    }
+}
+
+/// Circuit running `synthesize` with a [`Halo2Loader`] of the verifier circuit
+/// config and exposing nothing, for checking gadgets built on the loader by
+/// `MockProver`.
+#[derive(Clone)]
+pub struct LoaderCircuit<S>(S);
+
+impl<S> LoaderCircuit<S>
+where
+    S: Clone + for<'a> Fn(&Rc<Halo2Loader<'a>>) -> Result<(), crate::Error>,
+{
+    pub fn new(synthesize: S) -> Self {
+        Self(synthesize)
+    }
+}
+
+impl<S> Circuit<Fr> for LoaderCircuit<S>
+where
+    S: Clone + for<'a> Fn(&Rc<Halo2Loader<'a>>) -> Result<(), crate::Error>,
+{
+    type Config = Halo2VerifierCircuitConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut plonk::ConstraintSystem<Fr>) -> Self::Config {
+        let path = "./configs/verify_circuit.config";
+        let params: Halo2VerifierCircuitConfigParams =
+            serde_json::from_reader(File::open(path).unwrap_or_else(|err| panic!("{err:?}")))
+                .unwrap();
+        Halo2VerifierCircuitConfig::configure(meta, params)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), plonk::Error> {
+        config.base_field_config.load_lookup_table(&mut layouter)?;
+
+        let mut first_pass = halo2_base::SKIP_FIRST_PASS;
+        layouter.assign_region(
+            || "",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let ctx = Context::new(
+                    region,
+                    ContextParams {
+                        max_rows: config.base_field_config.range.gate.max_rows,
+                        num_context_ids: 1,
+                        fixed_columns: config.base_field_config.range.gate.constants.clone(),
+                    },
+                );
+
+                let loader =
+                    Halo2Loader::new(EccChip::construct(config.base_field_config.clone()), ctx);
+                (self.0)(&loader).map_err(|_| plonk::Error::Synthesis)?;
+                config.base_field_config.finalize(&mut loader.ctx_mut());
+
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+#[cfg(feature = "loader_evm")]
+fn test_keccak256() {
+    use crate::{
+        halo2_proofs::dev::MockProver,
+        loader::ScalarLoader,
+        util::hash::{keccak256, Digest, Keccak256, KeccakBit},
+    };
+
+    fn assert_keccak_bits<'a>(
+        loader: &Rc<Halo2Loader<'a>>,
+        bits: &[KeccakBit<loader::halo2::Scalar<'a, G1Affine, BaseFieldEccChip>>],
+        bytes: &[u8],
+    ) -> Result<(), crate::Error> {
+        assert_eq!(bits.len(), bytes.len() * 8);
+        for (bit, expected) in bits.iter().zip(KeccakBit::<()>::from_bytes(bytes)) {
+            let expected = match expected {
+                KeccakBit::Constant(expected) => Fr::from(expected as u64),
+                KeccakBit::Loaded(_) => unreachable!(),
+            };
+            loader.assert_eq(
+                "Unexpected keccak bit",
+                &bit.load::<Fr>(loader),
+                &loader.load_const(&expected),
+            )?;
+        }
+        Ok(())
+    }
+
+    let circuit = |message: Vec<u8>, digest: Vec<u8>| {
+        LoaderCircuit::new(move |loader| {
+            let input = KeccakBit::<()>::from_bytes(&message)
+                .into_iter()
+                .map(|bit| match bit {
+                    KeccakBit::Constant(bit) => {
+                        KeccakBit::Loaded(loader.assign_scalar(Value::known(Fr::from(bit as u64))))
+                    }
+                    KeccakBit::Loaded(_) => unreachable!(),
+                })
+                .collect_vec();
+            assert_keccak_bits(loader, &keccak256::<Fr, _>(&input), &digest)
+        })
+    };
+
+    let message = (0..100).collect_vec();
+    let digest = Keccak256::digest(&message).to_vec();
+    MockProver::run(21, &circuit(message.clone(), digest.clone()), vec![])
+        .unwrap()
+        .assert_satisfied();
+
+    let mut wrong_digest = digest;
+    wrong_digest[0] ^= 1;
+    assert!(MockProver::run(21, &circuit(message, wrong_digest), vec![])
+        .unwrap()
+        .verify()
+        .is_err());
+}
+
+#[test]
+#[cfg(feature = "loader_evm")]
+fn test_evm_transcript() {
+    use crate::{
+        halo2_proofs::dev::MockProver,
+        loader::ScalarLoader,
+        system::halo2::transcript::evm::EvmTranscript,
+        util::{
+            arithmetic::{Curve, CurveAffine},
+            transcript::{Transcript, TranscriptRead, TranscriptWrite},
+        },
+    };
+
+    // Challenges squeezed by native transcript, which hashes by `sha3`
+    let scalar = Fr::from(0xdeadbeef);
+    let ec_point = (G1Affine::generator() * Fr::from(3)).to_affine();
+    let (proof, challenges) = {
+        let mut transcript = EvmTranscript::<G1Affine, NativeLoader, _, _>::new(Vec::new());
+        transcript.write_scalar(scalar).unwrap();
+        let c_0 = transcript.squeeze_challenge();
+        transcript.write_ec_point(ec_point).unwrap();
+        let c_1 = transcript.squeeze_challenge();
+        let c_2 = transcript.squeeze_challenge();
+        (transcript.finalize(), vec![c_0, c_1, c_2])
+    };
+
+    let circuit = |proof: Vec<u8>| {
+        let challenges = challenges.clone();
+        LoaderCircuit::new(move |loader| {
+            let mut transcript = EvmTranscript::<G1Affine, Rc<Halo2Loader>, _, _>::new(
+                loader,
+                Value::known(proof.as_slice()),
+            );
+            transcript.read_scalar()?;
+            let c_0 = transcript.squeeze_challenge();
+            transcript.read_ec_point()?;
+            let c_1 = transcript.squeeze_challenge();
+            let c_2 = transcript.squeeze_challenge();
+            for (challenge, expected) in [c_0, c_1, c_2].iter().zip(challenges.iter()) {
+                loader.assert_eq(
+                    "Unexpected challenge",
+                    challenge,
+                    &loader.load_const(expected),
+                )?;
+            }
+            Ok(())
+        })
+    };
+
+    MockProver::run(21, &circuit(proof.clone()), vec![]).unwrap().assert_satisfied();
+
+    // Scalar is still canonical but challenges differ
+    let mut tampered = proof;
+    tampered[0x1f] ^= 1;
+    assert!(MockProver::run(21, &circuit(tampered), vec![]).unwrap().verify().is_err());
+}
+
+#[test]
+fn test_fri() {
+    use crate::{
+        halo2_proofs::dev::MockProver,
+        loader::ScalarLoader,
+        pcs::fri::{Fri, FriConfig},
+        util::arithmetic::{Field, PrimeField},
+    };
+    use std::iter;
+
+    let config = FriConfig::new(4, 1, 2).with_log_final_degree(1);
+    let mut rng = ChaCha20Rng::from_seed(Default::default());
+    let coeffs = iter::repeat_with(|| Fr::random(&mut rng)).take(1 << 4).collect_vec();
+    let proof = {
+        let mut transcript = PoseidonTranscript::<NativeLoader, _>::new(Vec::new());
+        Fri::create_proof::<G1Affine, _>(&config, &coeffs, &mut transcript).unwrap();
+        transcript.finalize()
+    };
+    // Openings of native verification, which are expected in circuit as well
+    let queries = {
+        let mut transcript = PoseidonTranscript::<NativeLoader, _>::new(proof.as_slice());
+        let proof = Fri::read_proof::<G1Affine, NativeLoader, _>(&config, &mut transcript).unwrap();
+        Fri::verify(&config, &proof).unwrap()
+    };
+
+    let circuit = |proof: Vec<u8>| {
+        let queries = queries.clone();
+        LoaderCircuit::new(move |loader| {
+            let mut transcript = PoseidonTranscript::<Rc<Halo2Loader>, _>::new(
+                loader,
+                Value::known(proof.as_slice()),
+            );
+            let proof = Fri::read_proof(&config, &mut transcript)?;
+            for (query, expected) in Fri::verify(&config, &proof)?.iter().zip(queries.iter()) {
+                for (value, expected) in iter::once(&query.point)
+                    .chain(query.evals.iter())
+                    .zip(iter::once(&expected.point).chain(expected.evals.iter()))
+                {
+                    loader.assert_eq(
+                        "Unexpected FRI opening",
+                        value,
+                        &loader.load_const(expected),
+                    )?;
+                }
+            }
+            Ok(())
+        })
+    };
+
+    MockProver::run(21, &circuit(proof.clone()), vec![]).unwrap().assert_satisfied();
+
+    // Tamper with the first coefficient of final polynomial, which breaks
+    // folding, and the last Merkle sibling of the last query, which breaks
+    // Merkle path.
+    let tamper = |offset: usize| {
+        let mut proof = proof.clone();
+        let mut repr = <Fr as PrimeField>::Repr::default();
+        repr.as_mut().copy_from_slice(&proof[offset..offset + 32]);
+        let scalar = Fr::from_repr(repr).unwrap() + Fr::one();
+        proof[offset..offset + 32].copy_from_slice(scalar.to_repr().as_ref());
+        proof
+    };
+    for offset in [32 * config.num_rounds(), proof.len() - 32] {
+        assert!(MockProver::run(21, &circuit(tamper(offset)), vec![]).unwrap().verify().is_err());
+    }
+}
+
 pub trait TargetCircuit: Circuit<Fr> {
     const TARGET_CIRCUIT_K: u32;
     const PUBLIC_INPUT_SIZE: usize;
@@ -0,0 +1,136 @@
+use crate::halo2_curves::bn256::{Bn256, Fr, G1Affine};
+use crate::halo2_proofs::poly::{
+    commitment::ParamsProver,
+    kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK},
+};
+use crate::{
+    loader::move_lang::{MoveLoader, MoveTranscript},
+    pcs::kzg::{Bdfg21, Kzg},
+    system::halo2::{
+        test::{
+            kzg::{halo2_kzg_config, halo2_kzg_create_snark, halo2_kzg_prepare},
+            StandardPlonk,
+        },
+        transcript::evm::{ChallengeEvm, EvmTranscript},
+    },
+    util::{
+        arithmetic::{Field, PrimeField},
+        Itertools,
+    },
+    verifier::{Plonk, PlonkVerifier},
+};
+use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+use std::{
+    fs,
+    process::{self, Command},
+};
+
+/// Returns generated Move verifier of a [`StandardPlonk`] snark, and its
+/// instances and proof.
+fn move_verifier() -> (String, Vec<Vec<Fr>>, Vec<u8>) {
+    type Pv = Plonk<Kzg<Bn256, Bdfg21>>;
+
+    let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+        9,
+        halo2_kzg_config!(true, 1),
+        StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+    );
+    let snark = halo2_kzg_create_snark!(
+        ProverSHPLONK<_>,
+        VerifierSHPLONK<_>,
+        EvmTranscript<G1Affine, _, _, _>,
+        EvmTranscript<G1Affine, _, _, _>,
+        ChallengeEvm<_>,
+        &params,
+        &pk,
+        &protocol,
+        &circuits
+    );
+
+    let loader = MoveLoader::new();
+    let svk = params.get_g()[0].into();
+    let dk = (params.g2(), params.s_g2()).into();
+    let protocol = snark.protocol.loaded(&loader);
+    let mut transcript = MoveTranscript::new(&loader);
+    let instances = transcript
+        .load_instances(snark.instances.iter().map(|instances| instances.len()).collect_vec());
+    let proof = Pv::read_proof(&svk, &protocol, &instances, &mut transcript);
+    Pv::verify(&svk, &dk, &protocol, &instances, &proof);
+
+    (loader.move_code("0x1234", "halo2_verifier"), snark.instances, snark.proof)
+}
+
+fn calldata(instances: &[Vec<Fr>], proof: &[u8]) -> Vec<u8> {
+    instances
+        .iter()
+        .flatten()
+        .flat_map(|instance| instance.to_repr().into_iter().rev())
+        .chain(proof.iter().copied())
+        .collect()
+}
+
+#[test]
+fn test_move_verifier_codegen() {
+    let (code, instances, proof) = move_verifier();
+
+    let calldata_len = calldata(&instances, &proof).len();
+    assert!(code.starts_with("module 0x1234::halo2_verifier {"));
+    assert!(code.contains(&format!("let success = vector::length(&calldata) == {calldata_len};")));
+    assert!(code.contains("success = success && pairing_check("));
+}
+
+#[test]
+#[ignore = "cause it requires aptos CLI and network to fetch framework"]
+fn test_move_verifier_execution() {
+    let (code, instances, proof) = move_verifier();
+    let mut tampered = instances.clone();
+    tampered[0][0] += Fr::one();
+    let [accept, reject] = [&instances, &tampered]
+        .map(|instances| format!("x\"{}\"", hex::encode(calldata(instances, &proof))));
+
+    // Proof is already checked by halo2 verifier when created, so it should be
+    // accepted by the generated module as well, but not with tampered instances.
+    let test_code = format!(
+        "#[test_only]
+module 0x1234::halo2_verifier_test {{
+    use std::features;
+    use 0x1234::halo2_verifier;
+
+    #[test(fx = @std)]
+    fun test_verify(fx: signer) {{
+        let bn254 = vector[features::get_bn254_strutures_feature()];
+        features::change_feature_flags_for_testing(&fx, bn254, vector[]);
+        assert!(halo2_verifier::verify({accept}), 0);
+        assert!(!halo2_verifier::verify({reject}), 1);
+    }}
+}}
+"
+    );
+    let manifest = r#"[package]
+name = "Halo2Verifier"
+version = "0.0.0"
+
+[dependencies.AptosStdlib]
+git = "https://github.com/aptos-labs/aptos-core.git"
+rev = "mainnet"
+subdir = "aptos-move/framework/aptos-stdlib"
+
+[dependencies.MoveStdlib]
+git = "https://github.com/aptos-labs/aptos-core.git"
+rev = "mainnet"
+subdir = "aptos-move/framework/move-stdlib"
+"#;
+
+    let dir = std::env::temp_dir().join(format!("snark-verifier-move-{}", process::id()));
+    fs::create_dir_all(dir.join("sources")).unwrap();
+    fs::write(dir.join("Move.toml"), manifest).unwrap();
+    fs::write(dir.join("sources").join("halo2_verifier.move"), code).unwrap();
+    fs::write(dir.join("sources").join("halo2_verifier_test.move"), test_code).unwrap();
+    let status = Command::new("aptos")
+        .args(["move", "test", "--package-dir"])
+        .arg(&dir)
+        .status()
+        .expect("aptos CLI should be in PATH");
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(status.success());
+}
@@ -10,7 +10,7 @@ use crate::{
             halo2_kzg_config, halo2_kzg_create_snark, halo2_kzg_native_verify, halo2_kzg_prepare,
             BITS, LIMBS,
         },
-        StandardPlonk,
+        RotatedInstance, StandardPlonk,
     },
     verifier::Plonk,
 };
@@ -60,6 +60,18 @@ test!(
     halo2_kzg_config!(true, 2),
     StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
 );
+test!(
+    zk_rotated_instance_rand,
+    9,
+    halo2_kzg_config!(true, 2),
+    RotatedInstance::rand(ChaCha20Rng::from_seed(Default::default()))
+);
+test!(
+    zk_standard_plonk_rand_simplified,
+    9,
+    halo2_kzg_config!(true, 2).set_simplify_quotient(true),
+    StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+);
 /*
 test!(
     zk_main_gate_with_range_with_mock_kzg_accumulator,
@@ -0,0 +1,59 @@
+use crate::halo2_curves::bn256::{Bn256, Fr, G1Affine};
+use crate::halo2_proofs::poly::{
+    commitment::ParamsProver,
+    kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK},
+};
+use crate::{
+    loader::solana::{Scalar, SolanaTranscript, LOADER},
+    pcs::kzg::{Bdfg21, Kzg},
+    system::halo2::{
+        test::{
+            kzg::{halo2_kzg_config, halo2_kzg_create_snark, halo2_kzg_prepare},
+            StandardPlonk,
+        },
+        transcript::evm::{ChallengeEvm, EvmTranscript},
+    },
+    util::{arithmetic::Field, Itertools},
+    verifier::{Plonk, PlonkVerifier},
+};
+use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+
+type Pv = Plonk<Kzg<Bn256, Bdfg21>>;
+
+#[test]
+fn test_solana_verify() {
+    let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+        9,
+        halo2_kzg_config!(true, 1),
+        StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+    );
+    let snark = halo2_kzg_create_snark!(
+        ProverSHPLONK<_>,
+        VerifierSHPLONK<_>,
+        EvmTranscript<G1Affine, _, _, _>,
+        EvmTranscript<G1Affine, _, _, _>,
+        ChallengeEvm<_>,
+        &params,
+        &pk,
+        &protocol,
+        &circuits
+    );
+
+    let svk = params.get_g()[0].into();
+    let dk = (params.g2(), params.s_g2()).into();
+    let protocol = snark.protocol.loaded(&LOADER);
+    let verify = |instances: &[Vec<Scalar>]| {
+        let mut transcript = SolanaTranscript::new(&snark.proof);
+        let proof = Pv::read_proof(&svk, &protocol, instances, &mut transcript);
+        Pv::verify(&svk, &dk, &protocol, instances, &proof)
+    };
+
+    let mut instances = snark
+        .instances
+        .iter()
+        .map(|instances| instances.iter().copied().map(Scalar).collect_vec())
+        .collect_vec();
+    assert!(verify(&instances));
+    instances[0][0].0 += Fr::one();
+    assert!(!verify(&instances));
+}
@@ -12,7 +12,7 @@ use crate::{
 use halo2_proofs::transcript::{Blake2bRead, Blake2bWrite, Challenge255};
 use std::io::{Read, Write};
 
-#[cfg(feature = "loader_evm")]
+#[cfg(any(feature = "loader_evm", feature = "cosmwasm"))]
 pub mod evm;
 
 #[cfg(feature = "loader_halo2")]
@@ -1,36 +1,48 @@
 //! Transcript for verifier on EVM.
 use crate::halo2_proofs;
+#[cfg(feature = "loader_evm")]
+use crate::loader::{
+    evm::{loader::Value, BlobCheck, EcPoint, EvmLoader, InstanceCheck, MemoryChunk, Scalar},
+    ScalarLoader,
+};
 use crate::{
     loader::{
-        evm::{loader::Value, u256_to_fe, EcPoint, EvmLoader, MemoryChunk, Scalar},
         native::{self, NativeLoader},
         Loader,
     },
     util::{
-        arithmetic::{Coordinates, CurveAffine, PrimeField},
+        arithmetic::{
+            ec_point_from_xy_repr, fe_from_big, fe_from_repr, modulus, Coordinates, CurveAffine,
+            Decoding, PrimeField,
+        },
         hash::{Digest, Keccak256},
-        transcript::{Transcript, TranscriptRead},
+        transcript::{Transcript, TranscriptRead, TranscriptWrite},
         Itertools,
     },
     Error,
 };
-use ethereum_types::U256;
 use halo2_proofs::transcript::EncodedChallenge;
+use num_bigint::BigUint;
 use std::{
     io::{self, Read, Write},
-    iter,
     marker::PhantomData,
-    rc::Rc,
 };
+#[cfg(feature = "loader_evm")]
+use std::{iter, rc::Rc};
 
 /// Transcript for verifier on EVM using keccak256 as hasher.
 pub struct EvmTranscript<C: CurveAffine, L: Loader<C>, S, B> {
     loader: L,
     stream: S,
     buf: B,
+    // number of challenges squeezed so far
+    num_challenge: usize,
+    // decoding of scalars and elliptic curve points read natively
+    decoding: Decoding,
     _marker: PhantomData<C>,
 }
 
+#[cfg(feature = "loader_evm")]
 impl<C> EvmTranscript<C, Rc<EvmLoader>, usize, MemoryChunk>
 where
     C: CurveAffine,
@@ -39,21 +51,83 @@ where
     /// Initialize [`EvmTranscript`] given [`Rc<EvmLoader>`] and pre-allocate an
     /// u256 for `transcript_initial_state`.
     pub fn new(loader: &Rc<EvmLoader>) -> Self {
-        let ptr = loader.allocate(0x20);
-        assert_eq!(ptr, 0);
-        let mut buf = MemoryChunk::new(ptr);
-        buf.extend(0x20);
-        Self { loader: loader.clone(), stream: 0, buf, _marker: PhantomData }
+        EvmTranscriptBuilder::new(loader).build()
+    }
+
+    /// Returns calldata offset of the next instance or proof word to read.
+    pub fn calldata_offset(&self) -> usize {
+        self.stream
+    }
+
+    /// Absorb a domain separation tag (e.g. circuit id or protocol hash) before
+    /// `transcript_initial_state`, so proofs are bound to a specific circuit.
+    /// It must be called right after [`EvmTranscript::new`], and the prover
+    /// side must use [`EvmTranscript::with_domain_separator`] with the same tag.
+    pub fn with_domain_separator(mut self, domain_separator: C::Scalar) -> Self {
+        assert_eq!(self.buf.len(), 0x20);
+        let ptr = self.loader.allocate(0x20);
+        assert_eq!(ptr, self.buf.end());
+        let domain_separator = self.loader.load_const(&domain_separator);
+        self.loader.copy_scalar(&domain_separator, self.buf.ptr());
+        self.buf.extend(0x20);
+        self
+    }
+
+    /// Read `n` auxiliary scalars (e.g. a block hash or batch index) from
+    /// calldata and absorb them, which reverts if any of them is not
+    /// canonical. Same as [`Transcript::absorb_aux`], it must be called
+    /// before the proof is read, and the prover side must call
+    /// [`Transcript::absorb_aux`] with the same scalars at the same point.
+    pub fn read_aux(&mut self, n: usize) -> Vec<Scalar> {
+        let ptr = self.reserve_aux(n);
+        (0..n)
+            .map(|idx| {
+                let scalar = self.loader.calldataload_scalar_into(self.stream, ptr + idx * 0x20);
+                self.stream += 0x20;
+                scalar
+            })
+            .collect()
+    }
+
+    /// Reserve memory for `n` auxiliary scalars in front of the slot of
+    /// `transcript_initial_state`, which is moved to the end of the buffer,
+    /// and returns pointer of the first auxiliary scalar.
+    fn reserve_aux(&mut self, n: usize) -> usize {
+        assert_eq!(
+            self.loader.ptr(),
+            self.buf.end(),
+            "Auxiliary data must be absorbed before the proof is read"
+        );
+        let ptr = self.buf.end() - 0x20;
+        self.loader.allocate(n * 0x20);
+        self.buf.extend(n * 0x20);
+        ptr
     }
 
-    /// Load `num_instance` instances from calldata to memory.
+    /// Load `num_instance` instances from calldata to memory, which reverts
+    /// with [`INVALID_INSTANCE_ERROR`] if any instance is not canonical.
+    ///
+    /// [`INVALID_INSTANCE_ERROR`]: crate::loader::evm::INVALID_INSTANCE_ERROR
     pub fn load_instances(&mut self, num_instance: Vec<usize>) -> Vec<Vec<Scalar>> {
+        self.load_instances_with_check(num_instance, InstanceCheck::default())
+    }
+
+    /// Load `num_instance` instances from calldata to memory, and validate
+    /// them according to `instance_check`.
+    pub fn load_instances_with_check(
+        &mut self,
+        num_instance: Vec<usize>,
+        instance_check: InstanceCheck,
+    ) -> Vec<Vec<Scalar>> {
+        let mut idx = 0;
         num_instance
             .into_iter()
             .map(|len| {
                 iter::repeat_with(|| {
-                    let scalar = self.loader.calldataload_scalar(self.stream);
+                    let scalar =
+                        self.loader.calldataload_instance(self.stream, idx, instance_check);
                     self.stream += 0x20;
+                    idx += 1;
                     scalar
                 })
                 .take(len)
@@ -61,8 +135,90 @@ where
             })
             .collect()
     }
+
+    /// Load `num_instance_column` instance commitments from calldata to memory
+    /// for verifying in committed-instance mode. They are not absorbed here
+    /// but by [`Plonk::read_proof_with_committed_instances`].
+    ///
+    /// [`Plonk::read_proof_with_committed_instances`]: crate::verifier::Plonk::read_proof_with_committed_instances
+    pub fn load_committed_instances(&mut self, num_instance_column: usize) -> Vec<EcPoint> {
+        iter::repeat_with(|| {
+            let ec_point = self.loader.calldataload_ec_point(self.stream);
+            self.stream += 0x40;
+            ec_point
+        })
+        .take(num_instance_column)
+        .collect()
+    }
+
+    /// Check `instances` against an EIP-4844 blob according to `blob_check`,
+    /// reading blob commitment and KZG proof from calldata right after the
+    /// proof, so it must be called after the proof is read.
+    pub fn check_blob(&mut self, instances: &[Vec<Scalar>], blob_check: &BlobCheck) {
+        let instances = instances.iter().flatten().collect_vec();
+        let [z, y] = [blob_check.z, blob_check.y].map(|idx| idx.map(|idx| instances[idx]));
+        self.loader.point_evaluation(blob_check.blob_index, z, y, self.stream);
+        self.stream += 0x60;
+    }
 }
 
+/// Builder of [`EvmTranscript`] with [`Rc<EvmLoader>`], which allows the
+/// verifier to be embedded in a contract whose calldata has extra fields in
+/// front of instances and proof.
+#[cfg(feature = "loader_evm")]
+#[derive(Debug)]
+pub struct EvmTranscriptBuilder {
+    loader: Rc<EvmLoader>,
+    calldata_offset: usize,
+    length_word: bool,
+}
+
+#[cfg(feature = "loader_evm")]
+impl EvmTranscriptBuilder {
+    /// Returns [`EvmTranscriptBuilder`] reading instances and proof from the
+    /// beginning of calldata.
+    pub fn new(loader: &Rc<EvmLoader>) -> Self {
+        Self { loader: loader.clone(), calldata_offset: 0, length_word: false }
+    }
+
+    /// Set calldata offset where instances and proof begin.
+    pub fn with_calldata_offset(mut self, calldata_offset: usize) -> Self {
+        self.calldata_offset = calldata_offset;
+        self
+    }
+
+    /// Set whether there is a length word in front of instances and proof
+    /// (e.g. when they are passed as ABI-encoded `bytes`), which is skipped.
+    pub fn set_length_word(mut self, length_word: bool) -> Self {
+        self.length_word = length_word;
+        self
+    }
+
+    /// Build [`EvmTranscript`] and pre-allocate an u256 for
+    /// `transcript_initial_state`, which must be the first allocation of the
+    /// loader.
+    pub fn build<C>(self) -> EvmTranscript<C, Rc<EvmLoader>, usize, MemoryChunk>
+    where
+        C: CurveAffine,
+        C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+    {
+        let ptr = self.loader.allocate(0x20);
+        assert_eq!(ptr, 0);
+        let mut buf = MemoryChunk::new(ptr);
+        buf.extend(0x20);
+        let stream = self.calldata_offset + if self.length_word { 0x20 } else { 0 };
+        EvmTranscript {
+            loader: self.loader,
+            stream,
+            buf,
+            num_challenge: 0,
+            decoding: Decoding::default(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "loader_evm")]
 impl<C> Transcript<C, Rc<EvmLoader>> for EvmTranscript<C, Rc<EvmLoader>, usize, MemoryChunk>
 where
     C: CurveAffine,
@@ -73,6 +229,10 @@ where
     }
 
     fn squeeze_challenge(&mut self) -> Scalar {
+        // Failure since the previous challenge is in data absorbed for this one
+        self.loader.checkpoint(&format!("challenge {}", self.num_challenge));
+        self.num_challenge += 1;
+
         let len = if self.buf.len() == 0x20 {
             assert_eq!(self.loader.ptr(), self.buf.end());
             let buf_end = self.buf.end();
@@ -114,18 +274,36 @@ where
     fn common_scalar(&mut self, scalar: &Scalar) -> Result<(), Error> {
         match scalar.value() {
             Value::Constant(_) if self.buf.ptr() == 0 => {
-                self.loader.copy_scalar(scalar, self.buf.ptr());
+                self.loader.copy_scalar(scalar, self.buf.end() - 0x20);
             }
-            Value::Memory(ptr) => {
-                assert_eq!(self.buf.end(), ptr);
+            Value::Memory(ptr) if ptr == self.buf.end() => {
                 self.buf.extend(0x20);
             }
+            // `transcript_initial_state` loaded from calldata by
+            // `EvmLoader::calldataload_vk`, which is copied into its slot
+            // before anything else is absorbed.
+            Value::Memory(_) if self.buf.ptr() == 0 && self.buf.len() == 0x20 => {
+                self.loader.copy_scalar(scalar, self.buf.end() - 0x20);
+            }
             _ => unreachable!(),
         }
         Ok(())
     }
+
+    /// Copy `aux` in front of `transcript_initial_state`, so it must be called
+    /// right after initialization (and [`EvmTranscript::with_domain_separator`]),
+    /// before the proof is read. Use [`EvmTranscript::read_aux`] to read
+    /// auxiliary data from calldata instead.
+    fn absorb_aux(&mut self, aux: &[Scalar]) -> Result<(), Error> {
+        let ptr = self.reserve_aux(aux.len());
+        for (idx, scalar) in aux.iter().enumerate() {
+            self.loader.copy_scalar(scalar, ptr + idx * 0x20);
+        }
+        Ok(())
+    }
 }
 
+#[cfg(feature = "loader_evm")]
 impl<C> TranscriptRead<C, Rc<EvmLoader>> for EvmTranscript<C, Rc<EvmLoader>, usize, MemoryChunk>
 where
     C: CurveAffine,
@@ -144,6 +322,19 @@ where
         self.common_ec_point(&ec_point)?;
         Ok(ec_point)
     }
+
+    /// Check lowest `bits` bits of the keccak256 hash the challenge is reduced
+    /// from, which is cheaper than reducing it again.
+    fn read_proof_of_work(&mut self, bits: usize) -> Result<(), Error> {
+        assert!(bits <= 256);
+        self.read_scalar()?;
+        self.squeeze_challenge();
+        let hash_ptr = self.buf.ptr();
+        let shift = 256 - bits;
+        let code = format!("success := and(iszero(shl({shift}, mload({hash_ptr:#x}))), success)");
+        self.loader.code_mut().runtime_append(code);
+        Ok(())
+    }
 }
 
 impl<C, S> EvmTranscript<C, NativeLoader, S, Vec<u8>>
@@ -153,7 +344,35 @@ where
     /// Initialize [`EvmTranscript`] given readable or writeable stream for
     /// verifying or proving with [`NativeLoader`].
     pub fn new(stream: S) -> Self {
-        Self { loader: NativeLoader, stream, buf: Vec::new(), _marker: PhantomData }
+        Self {
+            loader: NativeLoader,
+            stream,
+            buf: Vec::new(),
+            num_challenge: 0,
+            decoding: Decoding::default(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<C, S> EvmTranscript<C, NativeLoader, S, Vec<u8>>
+where
+    C: CurveAffine,
+    C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+{
+    /// Absorb a domain separation tag (e.g. circuit id or protocol hash) right
+    /// after initialization, so proofs are bound to a specific circuit.
+    pub fn with_domain_separator(mut self, domain_separator: C::Scalar) -> Self {
+        assert!(self.buf.is_empty());
+        self.common_scalar(&domain_separator).unwrap();
+        self
+    }
+
+    /// Set [`Decoding`] of scalars and elliptic curve points read from the
+    /// stream, which defaults to [`Decoding::VarTime`].
+    pub fn with_decoding(mut self, decoding: Decoding) -> Self {
+        self.decoding = decoding;
+        self
     }
 }
 
@@ -175,7 +394,7 @@ where
             .collect_vec();
         let hash: [u8; 32] = Keccak256::digest(data).into();
         self.buf = hash.to_vec();
-        u256_to_fe(U256::from_big_endian(hash.as_slice()))
+        hash_to_fe(hash.as_slice())
     }
 
     fn common_ec_point(&mut self, ec_point: &C) -> Result<(), Error> {
@@ -213,7 +432,7 @@ where
             .read_exact(data.as_mut())
             .map_err(|err| Error::Transcript(err.kind(), err.to_string()))?;
         data.reverse();
-        let scalar = C::Scalar::from_repr_vartime(data).ok_or_else(|| {
+        let scalar = fe_from_repr::<C::Scalar>(data, self.decoding).ok_or_else(|| {
             Error::Transcript(io::ErrorKind::Other, "Invalid scalar encoding in proof".to_string())
         })?;
         self.common_scalar(&scalar)?;
@@ -228,18 +447,29 @@ where
                 .map_err(|err| Error::Transcript(err.kind(), err.to_string()))?;
             repr.as_mut().reverse();
         }
-        let x = Option::from(<C::Base as PrimeField>::from_repr(x));
-        let y = Option::from(<C::Base as PrimeField>::from_repr(y));
-        let ec_point =
-            x.zip(y).and_then(|(x, y)| Option::from(C::from_xy(x, y))).ok_or_else(|| {
-                Error::Transcript(
-                    io::ErrorKind::Other,
-                    "Invalid elliptic curve point encoding in proof".to_string(),
-                )
-            })?;
+        let ec_point = ec_point_from_xy_repr::<C>(x, y, self.decoding).ok_or_else(|| {
+            Error::Transcript(
+                io::ErrorKind::Other,
+                "Invalid elliptic curve point encoding in proof".to_string(),
+            )
+        })?;
         self.common_ec_point(&ec_point)?;
         Ok(ec_point)
     }
+
+    /// Check lowest `bits` bits of the keccak256 hash the challenge is reduced
+    /// from, same as verifier on EVM.
+    fn read_proof_of_work(&mut self, bits: usize) -> Result<(), Error> {
+        self.read_scalar()?;
+        self.squeeze_challenge();
+        if !is_low_bits_zero(&self.buf, bits) {
+            return Err(Error::Transcript(
+                io::ErrorKind::Other,
+                "Insufficient proof-of-work in proof".to_string(),
+            ));
+        }
+        Ok(())
+    }
 }
 
 impl<C, S> EvmTranscript<C, NativeLoader, S, Vec<u8>>
@@ -274,7 +504,7 @@ where
     type Input = [u8; 32];
 
     fn new(challenge_input: &[u8; 32]) -> Self {
-        ChallengeEvm(u256_to_fe(U256::from_big_endian(challenge_input)))
+        ChallengeEvm(hash_to_fe(challenge_input))
     }
 
     fn get_scalar(&self) -> C::Scalar {
@@ -373,6 +603,51 @@ where
     }
 }
 
+impl<C, W> TranscriptWrite<C> for EvmTranscript<C, NativeLoader, W, Vec<u8>>
+where
+    C: CurveAffine,
+    C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+    W: Write,
+{
+    fn write_scalar(&mut self, scalar: C::Scalar) -> Result<(), Error> {
+        halo2_proofs::transcript::TranscriptWrite::<C, ChallengeEvm<C>>::write_scalar(self, scalar)
+            .map_err(|err| Error::Transcript(err.kind(), err.to_string()))
+    }
+
+    fn write_ec_point(&mut self, ec_point: C) -> Result<(), Error> {
+        halo2_proofs::transcript::TranscriptWrite::<C, ChallengeEvm<C>>::write_point(self, ec_point)
+            .map_err(|err| Error::Transcript(err.kind(), err.to_string()))
+    }
+
+    fn write_proof_of_work(&mut self, bits: usize) -> Result<(), Error> {
+        let nonce = (0u64..)
+            .map(C::Scalar::from)
+            .find(|nonce| {
+                let mut transcript = EvmTranscript::<C, NativeLoader, _, _>::new(());
+                transcript.buf = self.buf.clone();
+                transcript.common_scalar(nonce).unwrap();
+                transcript.squeeze_challenge();
+                is_low_bits_zero(&transcript.buf, bits)
+            })
+            .unwrap();
+        TranscriptWrite::write_scalar(self, nonce)?;
+        self.squeeze_challenge();
+        Ok(())
+    }
+}
+
+/// Returns big-endian `hash` reduced into [`PrimeField`], same as `mod(hash, f_q)`
+/// on EVM.
+fn hash_to_fe<F: PrimeField>(hash: &[u8]) -> F {
+    fe_from_big(BigUint::from_bytes_be(hash) % modulus::<F>())
+}
+
+fn is_low_bits_zero(hash: &[u8], bits: usize) -> bool {
+    BigUint::from_bytes_be(hash)
+        .trailing_zeros()
+        .map_or(true, |trailing_zeros| trailing_zeros as usize >= bits)
+}
+
 impl<C, W: Write> halo2_proofs::transcript::TranscriptWriterBuffer<W, C, ChallengeEvm<C>>
     for EvmTranscript<C, NativeLoader, W, Vec<u8>>
 where
@@ -387,3 +662,524 @@ where
         self.finalize()
     }
 }
+
+#[cfg(feature = "loader_halo2")]
+pub use halo2::KeccakEncoding;
+
+#[cfg(feature = "loader_halo2")]
+mod halo2 {
+    use super::EvmTranscript;
+    use crate::halo2_proofs::circuit::Value;
+    use crate::{
+        loader::{
+            halo2::{EcPoint, EccInstructions, Halo2Loader, Scalar},
+            ScalarLoader,
+        },
+        system::halo2::transcript::halo2::assert_canonical_bits,
+        util::{
+            arithmetic::{modulus, CurveAffine, Field, PrimeField},
+            hash::{fe_to_bits, keccak256, reverse_bytes, KeccakBit},
+            transcript::{Transcript, TranscriptRead},
+            Itertools,
+        },
+        Error,
+    };
+    use num_bigint::BigUint;
+    use std::{
+        io::{self, Read},
+        iter,
+        marker::PhantomData,
+        mem,
+        rc::Rc,
+    };
+
+    /// Encoding that decomposes field elements into bits, for hashing them by
+    /// keccak256 in circuit.
+    pub trait KeccakEncoding<'a, C>: EccInstructions<'a, C>
+    where
+        C: CurveAffine,
+    {
+        /// Decompose `scalar` into little-endian bits constrained to be
+        /// boolean, which are not required to be canonical.
+        fn scalar_to_bits(
+            &self,
+            ctx: &mut Self::Context,
+            scalar: &Self::AssignedScalar,
+        ) -> Result<Vec<Self::AssignedScalar>, Error>;
+
+        /// Decompose coordinates of `ec_point` into little-endian bits
+        /// constrained to be boolean, which are not required to be canonical.
+        fn ec_point_to_bits(
+            &self,
+            ctx: &mut Self::Context,
+            ec_point: &Self::AssignedEcPoint,
+        ) -> Result<[Vec<Self::AssignedScalar>; 2], Error>;
+    }
+
+    type Buf<'a, C, EccChip> = Vec<KeccakBit<Scalar<'a, C, EccChip>>>;
+
+    impl<'a, C, R, EccChip>
+        EvmTranscript<C, Rc<Halo2Loader<'a, C, EccChip>>, Value<R>, Buf<'a, C, EccChip>>
+    where
+        C: CurveAffine,
+        C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+        R: Read,
+        EccChip: KeccakEncoding<'a, C>,
+    {
+        /// Initialize [`EvmTranscript`] given [`Rc<Halo2Loader>`] and readable
+        /// stream for verifying in circuit, which derives challenges same as
+        /// verifier on EVM.
+        pub fn new(loader: &Rc<Halo2Loader<'a, C, EccChip>>, stream: Value<R>) -> Self {
+            Self {
+                loader: loader.clone(),
+                stream,
+                buf: Vec::new(),
+                num_challenge: 0,
+                decoding: Decoding::default(),
+                _marker: PhantomData,
+            }
+        }
+
+        /// Absorb a domain separation tag right after initialization, which
+        /// must be same as the one given to the verifier on EVM.
+        pub fn with_domain_separator(mut self, domain_separator: C::Scalar) -> Self {
+            assert!(self.buf.is_empty());
+            self.buf.extend(reverse_bytes(&fe_to_bits(&domain_separator)));
+            self
+        }
+
+        /// Constrain little-endian `bits` to be less than `modulus` by
+        /// [`assert_canonical_bits`] and returns them padded or truncated to
+        /// 256 bits.
+        fn canonical_bits(
+            &self,
+            bits: Vec<EccChip::AssignedScalar>,
+            modulus: BigUint,
+        ) -> Result<Vec<KeccakBit<Scalar<'a, C, EccChip>>>, Error> {
+            let bits =
+                bits.into_iter().map(|bit| self.loader.scalar_from_assigned(bit)).collect_vec();
+            assert_canonical_bits(&self.loader, &bits, &modulus)?;
+
+            Ok(bits
+                .into_iter()
+                .map(KeccakBit::Loaded)
+                .chain(iter::repeat_with(|| KeccakBit::Constant(false)))
+                .take(256)
+                .collect())
+        }
+    }
+
+    impl<'a, C, R, EccChip> Transcript<C, Rc<Halo2Loader<'a, C, EccChip>>>
+        for EvmTranscript<C, Rc<Halo2Loader<'a, C, EccChip>>, Value<R>, Buf<'a, C, EccChip>>
+    where
+        C: CurveAffine,
+        C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+        R: Read,
+        EccChip: KeccakEncoding<'a, C>,
+    {
+        fn loader(&self) -> &Rc<Halo2Loader<'a, C, EccChip>> {
+            &self.loader
+        }
+
+        fn squeeze_challenge(&mut self) -> Scalar<'a, C, EccChip> {
+            let mut buf = mem::take(&mut self.buf);
+            if buf.len() == 0x20 * 8 {
+                buf.extend(KeccakBit::from_bytes(&[1]));
+            }
+            let hash = keccak256::<C::Scalar, _>(&buf);
+
+            // Hash is interpreted as big-endian u256 and reduced by modulus
+            let bits = reverse_bytes(&hash);
+            let powers_of_two =
+                iter::successors(Some(C::Scalar::one()), |power| Some(power.double()));
+            let mut constant = C::Scalar::zero();
+            let mut values = Vec::new();
+            for (bit, power_of_two) in bits.iter().zip(powers_of_two) {
+                match bit {
+                    KeccakBit::Constant(true) => constant += power_of_two,
+                    KeccakBit::Constant(false) => {}
+                    KeccakBit::Loaded(bit) => values.push((power_of_two, bit)),
+                }
+            }
+            let challenge = self.loader.sum_with_coeff_and_const(&values, constant);
+
+            self.buf = hash;
+            challenge
+        }
+
+        fn common_ec_point(&mut self, ec_point: &EcPoint<'a, C, EccChip>) -> Result<(), Error> {
+            let coordinates = self
+                .loader
+                .ecc_chip()
+                .ec_point_to_bits(&mut self.loader.ctx_mut(), &ec_point.assigned())?;
+            for bits in coordinates {
+                let bits = self.canonical_bits(bits, modulus::<C::Base>())?;
+                self.buf.extend(reverse_bytes(&bits));
+            }
+            Ok(())
+        }
+
+        fn common_scalar(&mut self, scalar: &Scalar<'a, C, EccChip>) -> Result<(), Error> {
+            let bits = match scalar.maybe_const() {
+                Some(constant) => fe_to_bits(&constant),
+                None => {
+                    let bits = self
+                        .loader
+                        .ecc_chip()
+                        .scalar_to_bits(&mut self.loader.ctx_mut(), &scalar.assigned())?;
+                    self.canonical_bits(bits, modulus::<C::Scalar>())?
+                }
+            };
+            self.buf.extend(reverse_bytes(&bits));
+            Ok(())
+        }
+    }
+
+    impl<'a, C, R, EccChip> TranscriptRead<C, Rc<Halo2Loader<'a, C, EccChip>>>
+        for EvmTranscript<C, Rc<Halo2Loader<'a, C, EccChip>>, Value<R>, Buf<'a, C, EccChip>>
+    where
+        C: CurveAffine,
+        C::Scalar: PrimeField<Repr = [u8; 0x20]>,
+        R: Read,
+        EccChip: KeccakEncoding<'a, C>,
+    {
+        fn read_scalar(&mut self) -> Result<Scalar<'a, C, EccChip>, Error> {
+            let mut invalid = false;
+            let scalar = self.stream.as_mut().and_then(|stream| {
+                let mut data = [0; 0x20];
+                if stream.read_exact(data.as_mut()).is_err() {
+                    return Value::unknown();
+                }
+                data.reverse();
+                Option::<C::Scalar>::from(C::Scalar::from_repr(data))
+                    .map(Value::known)
+                    .unwrap_or_else(|| {
+                        invalid = true;
+                        Value::unknown()
+                    })
+            });
+            if invalid {
+                return Err(Error::Transcript(
+                    io::ErrorKind::Other,
+                    "Invalid scalar encoding in proof".to_string(),
+                ));
+            }
+            let scalar = self.loader.assign_scalar(scalar);
+            self.common_scalar(&scalar)?;
+            Ok(scalar)
+        }
+
+        fn read_ec_point(&mut self) -> Result<EcPoint<'a, C, EccChip>, Error> {
+            let mut invalid = false;
+            let ec_point = self.stream.as_mut().and_then(|stream| {
+                let [mut x, mut y] = [<C::Base as PrimeField>::Repr::default(); 2];
+                for repr in [&mut x, &mut y] {
+                    if stream.read_exact(repr.as_mut()).is_err() {
+                        return Value::unknown();
+                    }
+                    repr.as_mut().reverse();
+                }
+                let x = Option::from(<C::Base as PrimeField>::from_repr(x));
+                let y = Option::from(<C::Base as PrimeField>::from_repr(y));
+                x.zip(y)
+                    .and_then(|(x, y)| Option::from(C::from_xy(x, y)))
+                    .map(Value::known)
+                    .unwrap_or_else(|| {
+                        invalid = true;
+                        Value::unknown()
+                    })
+            });
+            if invalid {
+                return Err(Error::Transcript(
+                    io::ErrorKind::Other,
+                    "Invalid elliptic curve point encoding in proof".to_string(),
+                ));
+            }
+            let ec_point = self.loader.assign_ec_point(ec_point);
+            self.common_ec_point(&ec_point)?;
+            Ok(ec_point)
+        }
+
+        fn read_proof_of_work(&mut self, bits: usize) -> Result<(), Error> {
+            self.read_scalar()?;
+            self.squeeze_challenge();
+            let zero = self.loader.load_zero();
+            for bit in reverse_bytes(&self.buf).into_iter().take(bits) {
+                match bit {
+                    KeccakBit::Constant(false) => {}
+                    KeccakBit::Constant(true) => {
+                        return Err(Error::AssertionFailure(
+                            "Insufficient proof-of-work in proof".to_string(),
+                        ))
+                    }
+                    KeccakBit::Loaded(bit) => {
+                        self.loader.assert_eq("Insufficient proof-of-work in proof", &bit, &zero)?
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    mod halo2_lib {
+        use super::KeccakEncoding;
+        use crate::halo2_curves::CurveAffineExt;
+        use halo2_base::{gates::GateInstructions, utils::BigPrimeField as PrimeField};
+        use halo2_ecc::ecc::BaseFieldEccChip;
+
+        impl<'a, C: CurveAffineExt> KeccakEncoding<'a, C> for BaseFieldEccChip<C>
+        where
+            C::Scalar: PrimeField,
+            C::Base: PrimeField,
+        {
+            fn scalar_to_bits(
+                &self,
+                ctx: &mut Self::Context,
+                scalar: &Self::AssignedScalar,
+            ) -> Result<Vec<Self::AssignedScalar>, crate::Error> {
+                let gate = self.field_chip.range().gate();
+                let num_bits = <C::Scalar as crate::util::arithmetic::PrimeField>::NUM_BITS;
+                Ok(gate.num_to_bits(ctx, scalar, num_bits as usize))
+            }
+
+            fn ec_point_to_bits(
+                &self,
+                ctx: &mut Self::Context,
+                ec_point: &Self::AssignedEcPoint,
+            ) -> Result<[Vec<Self::AssignedScalar>; 2], crate::Error> {
+                let gate = self.field_chip.range().gate();
+                let limb_bits = self.field_chip.limb_bits;
+                Ok([ec_point.x(), ec_point.y()].map(|coordinate| {
+                    coordinate
+                        .truncation
+                        .limbs
+                        .iter()
+                        .flat_map(|limb| gate.num_to_bits(ctx, limb, limb_bits))
+                        .collect()
+                }))
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "loader_evm"))]
+mod test {
+    use crate::{
+        halo2_curves::bn256::{Fq, Fr, G1Affine},
+        loader::{
+            evm::{compile_solidity, execute, modulus, BlobCheck, EcPointCheck, EvmLoader, U256},
+            native::NativeLoader,
+            ScalarLoader,
+        },
+        system::halo2::transcript::evm::{EvmTranscript, EvmTranscriptBuilder},
+        util::transcript::{Transcript, TranscriptRead, TranscriptWrite},
+    };
+    use std::rc::Rc;
+
+    #[test]
+    fn test_evm_transcript_reject_non_canonical() {
+        let to_bytes = |value: U256| {
+            let mut bytes = [0; 0x20];
+            value.to_big_endian(&mut bytes);
+            bytes
+        };
+        let (r, p) = (modulus::<Fr>(), modulus::<Fq>());
+        let generator = [to_bytes(1.into()), to_bytes(2.into())].concat();
+
+        // Native
+        let read_scalar = |bytes: &[u8]| {
+            EvmTranscript::<G1Affine, NativeLoader, _, _>::new(bytes).read_scalar().is_ok()
+        };
+        let read_ec_point = |bytes: &[u8]| {
+            EvmTranscript::<G1Affine, NativeLoader, _, _>::new(bytes).read_ec_point().is_ok()
+        };
+        assert!(read_scalar(&to_bytes(r - 1)));
+        assert!(!read_scalar(&to_bytes(r)));
+        assert!(!read_scalar(&to_bytes(r + 1)));
+        assert!(read_ec_point(&generator));
+        assert!(!read_ec_point(&[to_bytes(p + 1), to_bytes(2.into())].concat()));
+        assert!(!read_ec_point(&[to_bytes(1.into()), to_bytes(p + 2)].concat()));
+        assert!(!read_ec_point(&[0; 0x40]));
+
+        // Evm
+        let deployment_code = {
+            let loader = EvmLoader::new::<Fq, Fr>();
+            let mut transcript = EvmTranscript::<G1Affine, Rc<EvmLoader>, _, _>::new(&loader);
+            transcript.read_scalar().unwrap();
+            transcript.read_ec_point().unwrap();
+            compile_solidity(&loader.solidity_code())
+        };
+        let accept = |scalar: [u8; 0x20], ec_point: &[u8]| {
+            execute(deployment_code.clone(), [scalar.as_slice(), ec_point].concat()).0
+        };
+        assert!(accept(to_bytes(r - 1), &generator));
+        assert!(!accept(to_bytes(r), &generator));
+        assert!(!accept(to_bytes(r + 1), &generator));
+        assert!(!accept(to_bytes(r - 1), &[to_bytes(p + 1), to_bytes(2.into())].concat()));
+        assert!(!accept(to_bytes(r - 1), &[0; 0x40]));
+    }
+
+    #[test]
+    fn test_evm_transcript_proof_of_work() {
+        const BITS: usize = 8;
+        let initial_state = Fr::from(42);
+
+        let mut transcript = EvmTranscript::<G1Affine, NativeLoader, _, _>::new(Vec::new());
+        transcript.common_scalar(&initial_state).unwrap();
+        transcript.write_proof_of_work(BITS).unwrap();
+        let proof = transcript.finalize();
+
+        let native_accept = |proof: &[u8]| {
+            let mut transcript = EvmTranscript::<G1Affine, NativeLoader, _, _>::new(proof);
+            transcript.common_scalar(&initial_state).unwrap();
+            transcript.read_proof_of_work(BITS).is_ok()
+        };
+        let deployment_code = {
+            let loader = EvmLoader::new::<Fq, Fr>();
+            let mut transcript = EvmTranscript::<G1Affine, Rc<EvmLoader>, _, _>::new(&loader);
+            transcript.common_scalar(&loader.load_const(&initial_state)).unwrap();
+            transcript.read_proof_of_work(BITS).unwrap();
+            compile_solidity(&loader.solidity_code())
+        };
+        let evm_accept = |proof: &[u8]| execute(deployment_code.clone(), proof.to_vec()).0;
+
+        assert!(native_accept(&proof));
+        assert!(evm_accept(&proof));
+        for nonce in 0..16u8 {
+            let mut proof = [0; 0x20];
+            proof[0x1f] = nonce;
+            assert_eq!(native_accept(&proof), evm_accept(&proof));
+        }
+    }
+
+    #[test]
+    fn test_evm_transcript_aux() {
+        const BITS: usize = 8;
+        let initial_state = Fr::from(42);
+        let block_hash = b"block hash in bytes longer than 31 bytes";
+
+        let mut transcript = EvmTranscript::<G1Affine, NativeLoader, _, _>::new(Vec::new());
+        transcript.absorb_aux(&[Fr::from(7)]).unwrap();
+        transcript.common_bytes(block_hash).unwrap();
+        transcript.common_scalar(&initial_state).unwrap();
+        transcript.write_proof_of_work(BITS).unwrap();
+        let proof = transcript.finalize();
+
+        let native_accept = |batch_index: u8, proof: &[u8]| {
+            let mut transcript = EvmTranscript::<G1Affine, NativeLoader, _, _>::new(proof);
+            transcript.absorb_aux(&[Fr::from(batch_index as u64)]).unwrap();
+            transcript.common_bytes(block_hash).unwrap();
+            transcript.common_scalar(&initial_state).unwrap();
+            transcript.read_proof_of_work(BITS).is_ok()
+        };
+        let deployment_code = {
+            let loader = EvmLoader::new::<Fq, Fr>();
+            let mut transcript = EvmTranscript::<G1Affine, Rc<EvmLoader>, _, _>::new(&loader);
+            transcript.read_aux(1);
+            transcript.common_bytes(block_hash).unwrap();
+            transcript.common_scalar(&loader.load_const(&initial_state)).unwrap();
+            transcript.read_proof_of_work(BITS).unwrap();
+            compile_solidity(&loader.solidity_code())
+        };
+        let evm_accept = |batch_index: u8, proof: &[u8]| {
+            let mut aux = [0; 0x20];
+            aux[0x1f] = batch_index;
+            execute(deployment_code.clone(), [aux.as_slice(), proof].concat()).0
+        };
+
+        assert!(native_accept(7, &proof));
+        assert!(evm_accept(7, &proof));
+        for batch_index in 0..16 {
+            assert_eq!(native_accept(batch_index, &proof), evm_accept(batch_index, &proof));
+        }
+    }
+
+    #[test]
+    fn test_evm_ec_point_check() {
+        let deployment_code = |ec_point_check| {
+            let loader = EvmLoader::new_with_ec_point_check::<Fq, Fr>(ec_point_check);
+            let mut transcript = EvmTranscript::<G1Affine, Rc<EvmLoader>, _, _>::new(&loader);
+            transcript.read_ec_point().unwrap();
+            compile_solidity(&loader.solidity_code())
+        };
+        let [generator, identity, not_on_curve] = [[1u8, 2], [0, 0], [1, 1]].map(|coordinates| {
+            coordinates
+                .into_iter()
+                .flat_map(|coordinate| {
+                    let mut bytes = [0; 0x20];
+                    bytes[0x1f] = coordinate;
+                    bytes
+                })
+                .collect::<Vec<_>>()
+        });
+
+        let full = deployment_code(EcPointCheck::Full);
+        assert!(execute(full.clone(), generator.clone()).0);
+        assert!(!execute(full.clone(), identity.clone()).0);
+        assert!(!execute(full, not_on_curve.clone()).0);
+
+        // Points only absorbed into transcript are not checked by any precompile.
+        let assume_honest_prover = deployment_code(EcPointCheck::AssumeHonestProver);
+        assert!(execute(assume_honest_prover.clone(), generator).0);
+        assert!(execute(assume_honest_prover.clone(), identity).0);
+        assert!(execute(assume_honest_prover, not_on_curve).0);
+    }
+
+    #[test]
+    fn test_evm_transcript_builder() {
+        let loader = EvmLoader::new::<Fq, Fr>();
+        let mut transcript = EvmTranscriptBuilder::new(&loader)
+            .with_calldata_offset(0x40)
+            .set_length_word(true)
+            .build::<G1Affine>();
+        assert_eq!(transcript.calldata_offset(), 0x60);
+        transcript.load_instances(vec![1]);
+        assert_eq!(transcript.calldata_offset(), 0x80);
+        transcript.read_ec_point().unwrap();
+        assert_eq!(transcript.calldata_offset(), 0xc0);
+        let deployment_code = compile_solidity(&loader.solidity_code());
+
+        let word = |value: u8| {
+            let mut bytes = [0; 0x20];
+            bytes[0x1f] = value;
+            bytes
+        };
+        // Instance `1` followed by generator
+        let body = [word(1), word(1), word(2)].concat();
+        let prefix = [[0xff; 0x40].as_slice(), &word(body.len() as u8)].concat();
+        assert!(execute(deployment_code.clone(), [prefix.as_slice(), &body].concat()).0);
+        // Point read from shifted offset is identity
+        assert!(!execute(deployment_code.clone(), body.clone()).0);
+        let shifted = [&prefix[..0x40], &body].concat();
+        assert!(!execute(deployment_code, shifted).0);
+    }
+
+    #[test]
+    #[should_panic(expected = "unreachable")]
+    fn test_evm_transcript_memory_scalar() {
+        let loader = EvmLoader::new::<Fq, Fr>();
+        let mut transcript = EvmTranscript::<G1Affine, Rc<EvmLoader>, _, _>::new(&loader);
+        let [absorbed, transcript_initial_state, scalar] =
+            [0x100, 0x120, 0x140].map(|offset| loader.calldataload_scalar(offset));
+        // Memory scalar off the buffer is copied into the slot of
+        // `transcript_initial_state` before anything else is absorbed
+        transcript.common_scalar(&transcript_initial_state).unwrap();
+        transcript.common_scalar(&absorbed).unwrap();
+        // But not afterwards, which would overwrite the last absorbed word
+        transcript.common_scalar(&scalar).unwrap();
+    }
+
+    #[test]
+    fn test_evm_blob_check_codegen() {
+        let loader = EvmLoader::new::<Fq, Fr>();
+        let mut transcript = EvmTranscript::<G1Affine, Rc<EvmLoader>, _, _>::new(&loader);
+        let instances = transcript.load_instances(vec![4]);
+        transcript.check_blob(&instances, &BlobCheck { blob_index: 1, z: [0, 1], y: [2, 3] });
+        assert_eq!(transcript.calldata_offset(), 4 * 0x20 + 0x60);
+
+        let code = loader.solidity_code();
+        assert!(code.contains("blobhash(1)"));
+        assert!(code.contains("calldatacopy(") && code.contains(", 0x80, 0x60)"));
+        assert!(code.contains("staticcall(gas(), 0xa, "));
+    }
+}
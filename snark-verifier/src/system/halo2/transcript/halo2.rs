@@ -7,14 +7,17 @@ use crate::{
         Loader, ScalarLoader,
     },
     util::{
-        arithmetic::{fe_to_fe, CurveAffine, PrimeField},
-        hash::Poseidon,
+        arithmetic::{
+            fe_from_repr, fe_to_big, fe_to_fe, modulus, CurveAffine, Decoding, PrimeField,
+        },
+        hash::{Poseidon, SqueezeDomain},
         transcript::{Transcript, TranscriptRead, TranscriptWrite},
         Itertools,
     },
     Error,
 };
 use halo2_proofs::{circuit::Value, transcript::EncodedChallenge};
+use num_bigint::BigUint;
 use std::{
     io::{self, Read, Write},
     rc::Rc,
@@ -30,6 +33,61 @@ where
         ctx: &mut Self::Context,
         ec_point: &Self::AssignedEcPoint,
     ) -> Result<Vec<Self::AssignedScalar>, Error>;
+
+    /// Decompose `scalar` into little-endian bits constrained to be boolean,
+    /// which are not required to be canonical.
+    fn scalar_to_bits(
+        &self,
+        ctx: &mut Self::Context,
+        scalar: &Self::AssignedScalar,
+    ) -> Result<Vec<Self::AssignedScalar>, Error>;
+}
+
+/// Decompose `scalar` into little-endian bits constrained to be boolean and
+/// canonical, otherwise prover could pick the ones of `scalar + modulus`
+/// instead.
+pub fn canonical_bits<'a, C, EccChip>(
+    loader: &Rc<Halo2Loader<'a, C, EccChip>>,
+    scalar: &Scalar<'a, C, EccChip>,
+) -> Result<Vec<Scalar<'a, C, EccChip>>, Error>
+where
+    C: CurveAffine,
+    EccChip: NativeEncoding<'a, C>,
+{
+    let bits = loader
+        .ecc_chip()
+        .scalar_to_bits(&mut loader.ctx_mut(), &scalar.assigned())?
+        .into_iter()
+        .map(|bit| loader.scalar_from_assigned(bit))
+        .collect_vec();
+    assert_canonical_bits(loader, &bits, &modulus::<C::Scalar>())?;
+
+    Ok(bits)
+}
+
+/// Constrain little-endian `bits`, which are assumed to be boolean, to be less
+/// than `modulus`. The amount of `bits` could differ from the bit length of
+/// `modulus` (e.g. limbs of non-native field element).
+pub fn assert_canonical_bits<'a, C, EccChip>(
+    loader: &Rc<Halo2Loader<'a, C, EccChip>>,
+    bits: &[Scalar<'a, C, EccChip>],
+    modulus: &BigUint,
+) -> Result<(), Error>
+where
+    C: CurveAffine,
+    EccChip: EccInstructions<'a, C>,
+{
+    let one = loader.load_one();
+    let lt =
+        (0..bits.len().max(modulus.bits() as usize)).fold(loader.load_zero(), |lt, idx| {
+            match (modulus.bit(idx as u64), bits.get(idx)) {
+                (true, Some(bit)) => one.clone() - bit.clone() * (one.clone() - lt),
+                (false, Some(bit)) => lt.clone() - lt * bit,
+                (true, None) => one.clone(),
+                (false, None) => lt,
+            }
+        });
+    loader.assert_eq("Non-canonical decomposition", &lt, &one)
 }
 
 /// Transcript for verifier in [`halo2_proofs`] circuit using poseidon hasher.
@@ -50,6 +108,8 @@ pub struct PoseidonTranscript<
     loader: L,
     stream: S,
     buf: Poseidon<C::Scalar, <L as ScalarLoader<C::Scalar>>::LoadedScalar, T, RATE>,
+    domain_separator: Option<C::Scalar>,
+    decoding: Decoding,
 }
 
 impl<'a, C, R, EccChip, const T: usize, const RATE: usize, const R_F: usize, const R_P: usize>
@@ -63,7 +123,13 @@ where
     /// verifying or proving with [`NativeLoader`].
     pub fn new(loader: &Rc<Halo2Loader<'a, C, EccChip>>, stream: Value<R>) -> Self {
         let buf = Poseidon::new(loader, R_F, R_P);
-        Self { loader: loader.clone(), stream, buf }
+        Self {
+            loader: loader.clone(),
+            stream,
+            buf,
+            domain_separator: None,
+            decoding: Decoding::default(),
+        }
     }
 
     /// Initialize [`PoseidonTranscript`] from a precomputed spec of round constants and MDS matrix because computing the constants is expensive.
@@ -73,14 +139,44 @@ where
         spec: crate::poseidon::Spec<C::Scalar, T, RATE>,
     ) -> Self {
         let buf = Poseidon::from_spec(loader, spec);
-        Self { loader: loader.clone(), stream, buf }
+        Self {
+            loader: loader.clone(),
+            stream,
+            buf,
+            domain_separator: None,
+            decoding: Decoding::default(),
+        }
+    }
+
+    /// Absorb a domain separation tag (e.g. circuit id or protocol hash) right
+    /// after initialization, so the transcript is bound to a specific circuit.
+    /// The tag is re-absorbed whenever the transcript is reset by
+    /// [`PoseidonTranscript::new_stream`].
+    pub fn with_domain_separator(mut self, domain_separator: C::Scalar) -> Self {
+        self.domain_separator = Some(domain_separator);
+        self.absorb_domain_separator();
+        self
+    }
+
+    /// Set [`SqueezeDomain`] of the sponge, which defaults to
+    /// [`SqueezeDomain::Legacy`] to stay compatible with deployed circuits.
+    pub fn with_squeeze_domain(mut self, squeeze_domain: SqueezeDomain) -> Self {
+        self.buf = self.buf.with_squeeze_domain(squeeze_domain);
+        self
     }
 
     /// Clear the buffer and set the stream to a new one. Effectively the same as starting from a new transcript.
     pub fn new_stream(&mut self, stream: Value<R>) {
         self.buf.clear();
+        self.absorb_domain_separator();
         self.stream = stream;
     }
+
+    fn absorb_domain_separator(&mut self) {
+        if let Some(domain_separator) = self.domain_separator.as_ref() {
+            self.buf.update(&[self.loader.load_const(domain_separator)]);
+        }
+    }
 }
 
 impl<'a, C, R, EccChip, const T: usize, const RATE: usize, const R_F: usize, const R_P: usize>
@@ -96,6 +192,7 @@ where
     }
 
     fn squeeze_challenge(&mut self) -> Scalar<'a, C, EccChip> {
+        self.loader.record_poseidon_permutations(self.buf.num_pending_permutations());
         self.buf.squeeze()
     }
 
@@ -135,34 +232,64 @@ where
     EccChip: NativeEncoding<'a, C>,
 {
     fn read_scalar(&mut self) -> Result<Scalar<'a, C, EccChip>, Error> {
+        let mut invalid = false;
         let scalar = self.stream.as_mut().and_then(|stream| {
             let mut data = <C::Scalar as PrimeField>::Repr::default();
             if stream.read_exact(data.as_mut()).is_err() {
                 return Value::unknown();
             }
-            Option::<C::Scalar>::from(C::Scalar::from_repr(data))
-                .map(Value::known)
-                .unwrap_or_else(Value::unknown)
+            Option::<C::Scalar>::from(C::Scalar::from_repr(data)).map(Value::known).unwrap_or_else(
+                || {
+                    invalid = true;
+                    Value::unknown()
+                },
+            )
         });
+        if invalid {
+            return Err(Error::Transcript(
+                io::ErrorKind::Other,
+                "Invalid scalar encoding in proof".to_string(),
+            ));
+        }
         let scalar = self.loader.assign_scalar(scalar);
         self.common_scalar(&scalar)?;
         Ok(scalar)
     }
 
     fn read_ec_point(&mut self) -> Result<EcPoint<'a, C, EccChip>, Error> {
+        let mut invalid = false;
         let ec_point = self.stream.as_mut().and_then(|stream| {
             let mut compressed = C::Repr::default();
             if stream.read_exact(compressed.as_mut()).is_err() {
                 return Value::unknown();
             }
-            Option::<C>::from(C::from_bytes(&compressed))
-                .map(Value::known)
-                .unwrap_or_else(Value::unknown)
+            Option::<C>::from(C::from_bytes(&compressed)).map(Value::known).unwrap_or_else(|| {
+                invalid = true;
+                Value::unknown()
+            })
         });
+        if invalid {
+            return Err(Error::Transcript(
+                io::ErrorKind::Other,
+                "Invalid elliptic curve point encoding in proof".to_string(),
+            ));
+        }
         let ec_point = self.loader.assign_ec_point(ec_point);
         self.common_ec_point(&ec_point)?;
         Ok(ec_point)
     }
+
+    fn read_proof_of_work(&mut self, bits: usize) -> Result<(), Error> {
+        self.read_scalar()?;
+        let challenge = self.squeeze_challenge();
+        let challenge_bits = canonical_bits(&self.loader, &challenge)?;
+
+        let zero = self.loader.load_zero();
+        for bit in challenge_bits.iter().take(bits) {
+            self.loader.assert_eq("Insufficient proof-of-work in proof", bit, &zero)?;
+        }
+        Ok(())
+    }
 }
 
 impl<C: CurveAffine, S, const T: usize, const RATE: usize, const R_F: usize, const R_P: usize>
@@ -171,19 +298,63 @@ impl<C: CurveAffine, S, const T: usize, const RATE: usize, const R_F: usize, con
     /// Initialize [`PoseidonTranscript`] given readable or writeable stream for
     /// verifying or proving with [`NativeLoader`].
     pub fn new(stream: S) -> Self {
-        Self { loader: NativeLoader, stream, buf: Poseidon::new(&NativeLoader, R_F, R_P) }
+        Self {
+            loader: NativeLoader,
+            stream,
+            buf: Poseidon::new(&NativeLoader, R_F, R_P),
+            domain_separator: None,
+            decoding: Decoding::default(),
+        }
     }
 
     /// Initialize [`PoseidonTranscript`] from a precomputed spec of round constants and MDS matrix because computing the constants is expensive.
     pub fn from_spec(stream: S, spec: crate::poseidon::Spec<C::Scalar, T, RATE>) -> Self {
-        Self { loader: NativeLoader, stream, buf: Poseidon::from_spec(&NativeLoader, spec) }
+        Self {
+            loader: NativeLoader,
+            stream,
+            buf: Poseidon::from_spec(&NativeLoader, spec),
+            domain_separator: None,
+            decoding: Decoding::default(),
+        }
+    }
+
+    /// Absorb a domain separation tag (e.g. circuit id or protocol hash) right
+    /// after initialization, so the transcript is bound to a specific circuit.
+    /// The tag is re-absorbed whenever the transcript is reset.
+    pub fn with_domain_separator(mut self, domain_separator: C::Scalar) -> Self {
+        self.domain_separator = Some(domain_separator);
+        self.absorb_domain_separator();
+        self
+    }
+
+    /// Set [`SqueezeDomain`] of the sponge, which defaults to
+    /// [`SqueezeDomain::Legacy`] to stay compatible with deployed circuits.
+    pub fn with_squeeze_domain(mut self, squeeze_domain: SqueezeDomain) -> Self {
+        self.buf = self.buf.with_squeeze_domain(squeeze_domain);
+        self
+    }
+
+    /// Set [`Decoding`] of scalars read from the stream, which defaults to
+    /// [`Decoding::VarTime`]. Elliptic curve points are always decoded by
+    /// [`GroupEncoding`](crate::util::arithmetic::GroupEncoding), which is
+    /// already constant-time.
+    pub fn with_decoding(mut self, decoding: Decoding) -> Self {
+        self.decoding = decoding;
+        self
     }
 
     /// Clear the buffer and set the stream to a new one. Effectively the same as starting from a new transcript.
     pub fn new_stream(&mut self, stream: S) {
         self.buf.clear();
+        self.absorb_domain_separator();
         self.stream = stream;
     }
+
+    fn absorb_domain_separator(&mut self) {
+        if let Some(domain_separator) = self.domain_separator {
+            self.buf.update(&[domain_separator]);
+        }
+    }
 }
 
 impl<C: CurveAffine, const T: usize, const RATE: usize, const R_F: usize, const R_P: usize>
@@ -192,6 +363,7 @@ impl<C: CurveAffine, const T: usize, const RATE: usize, const R_F: usize, const
     /// Clear the buffer and stream.
     pub fn clear(&mut self) {
         self.buf.clear();
+        self.absorb_domain_separator();
         self.stream.clear();
     }
 }
@@ -238,7 +410,7 @@ where
         self.stream
             .read_exact(data.as_mut())
             .map_err(|err| Error::Transcript(err.kind(), err.to_string()))?;
-        let scalar = C::Scalar::from_repr_vartime(data).ok_or_else(|| {
+        let scalar = fe_from_repr::<C::Scalar>(data, self.decoding).ok_or_else(|| {
             Error::Transcript(io::ErrorKind::Other, "Invalid scalar encoding in proof".to_string())
         })?;
         self.common_scalar(&scalar)?;
@@ -259,6 +431,17 @@ where
         self.common_ec_point(&ec_point)?;
         Ok(ec_point)
     }
+
+    fn read_proof_of_work(&mut self, bits: usize) -> Result<(), Error> {
+        self.read_scalar()?;
+        if !is_low_bits_zero(&self.squeeze_challenge(), bits) {
+            return Err(Error::Transcript(
+                io::ErrorKind::Other,
+                "Insufficient proof-of-work in proof".to_string(),
+            ));
+        }
+        Ok(())
+    }
 }
 
 impl<C, W, const T: usize, const RATE: usize, const R_F: usize, const R_P: usize>
@@ -302,6 +485,24 @@ where
             )
         })
     }
+
+    fn write_proof_of_work(&mut self, bits: usize) -> Result<(), Error> {
+        let nonce = (0u64..)
+            .map(C::Scalar::from)
+            .find(|nonce| {
+                let mut buf = self.buf.clone();
+                buf.update(&[*nonce]);
+                is_low_bits_zero(&buf.squeeze(), bits)
+            })
+            .unwrap();
+        self.write_scalar(nonce)?;
+        self.squeeze_challenge();
+        Ok(())
+    }
+}
+
+fn is_low_bits_zero<F: PrimeField>(scalar: &F, bits: usize) -> bool {
+    fe_to_big(*scalar).trailing_zeros().map_or(true, |trailing_zeros| trailing_zeros >= bits as u64)
 }
 
 /// [`EncodedChallenge`] implemented for verifier in [`halo2_proofs`] circuit.
@@ -423,7 +624,7 @@ where
 mod halo2_lib {
     use crate::halo2_curves::CurveAffineExt;
     use crate::system::halo2::transcript::halo2::NativeEncoding;
-    use halo2_base::utils::BigPrimeField as PrimeField;
+    use halo2_base::{gates::GateInstructions, utils::BigPrimeField as PrimeField};
     use halo2_ecc::ecc::BaseFieldEccChip;
 
     impl<'a, C: CurveAffineExt> NativeEncoding<'a, C> for BaseFieldEccChip<C>
@@ -438,5 +639,155 @@ mod halo2_lib {
         ) -> Result<Vec<Self::AssignedScalar>, crate::Error> {
             Ok(vec![ec_point.x().native().clone(), ec_point.y().native().clone()])
         }
+
+        fn scalar_to_bits(
+            &self,
+            ctx: &mut Self::Context,
+            scalar: &Self::AssignedScalar,
+        ) -> Result<Vec<Self::AssignedScalar>, crate::Error> {
+            let gate = self.field_chip.range().gate();
+            let num_bits = <C::Scalar as crate::util::arithmetic::PrimeField>::NUM_BITS;
+            Ok(gate.num_to_bits(ctx, scalar, num_bits as usize))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        halo2_curves::{
+            bn256::{Fq, Fr, G1Affine},
+            group::GroupEncoding,
+        },
+        loader::native::NativeLoader,
+        system::halo2::transcript::{evm::EvmTranscript, halo2::PoseidonTranscript},
+        util::{
+            arithmetic::{modulus, Decoding, PrimeCurveAffine, PrimeField},
+            transcript::{Transcript, TranscriptRead, TranscriptWrite},
+        },
+    };
+
+    #[test]
+    fn test_poseidon_transcript_reject_non_canonical() {
+        type Transcript<'a> = PoseidonTranscript<G1Affine, NativeLoader, &'a [u8], 5, 4, 8, 60>;
+
+        let to_bytes = |value: num_bigint::BigUint| {
+            let mut bytes = value.to_bytes_le();
+            bytes.resize(0x20, 0);
+            bytes
+        };
+
+        let max = (-Fr::one()).to_repr();
+        assert!(Transcript::new(max.as_ref()).read_scalar().is_ok());
+        assert!(Transcript::new(&to_bytes(modulus::<Fr>())).read_scalar().is_err());
+
+        let generator = G1Affine::generator().to_bytes();
+        assert!(Transcript::new(generator.as_ref()).read_ec_point().is_ok());
+        assert!(Transcript::new(&to_bytes(modulus::<Fq>() + 1u64)).read_ec_point().is_err());
+    }
+
+    #[test]
+    fn test_transcript_decoding() {
+        type Poseidon<'a> = PoseidonTranscript<G1Affine, NativeLoader, &'a [u8], 5, 4, 8, 60>;
+        type Evm<'a> = EvmTranscript<G1Affine, NativeLoader, &'a [u8], Vec<u8>>;
+
+        let to_bytes_le = |value: num_bigint::BigUint| {
+            let mut bytes = value.to_bytes_le();
+            bytes.resize(0x20, 0);
+            bytes
+        };
+        let to_bytes_be = |value: num_bigint::BigUint| {
+            let mut bytes = to_bytes_le(value);
+            bytes.reverse();
+            bytes
+        };
+
+        let max = (-Fr::one()).to_repr();
+        let generator = G1Affine::generator();
+        let xy = |x: &[u8], y: &[u8]| [x, y].concat();
+        let [one, two] = [1u64, 2].map(|coordinate| to_bytes_be(coordinate.into()));
+        for decoding in [Decoding::VarTime, Decoding::ConstantTime] {
+            let mut transcript = Poseidon::new(max.as_ref()).with_decoding(decoding);
+            assert_eq!(transcript.read_scalar().unwrap(), -Fr::one());
+            let overflow = to_bytes_le(modulus::<Fr>());
+            let mut transcript = Poseidon::new(overflow.as_slice()).with_decoding(decoding);
+            assert!(transcript.read_scalar().is_err());
+            let bytes = generator.to_bytes();
+            let mut transcript = Poseidon::new(bytes.as_ref()).with_decoding(decoding);
+            assert_eq!(transcript.read_ec_point().unwrap(), generator);
+
+            let max = to_bytes_be(modulus::<Fr>() - 1u64);
+            let mut transcript = Evm::new(max.as_slice()).with_decoding(decoding);
+            assert_eq!(transcript.read_scalar().unwrap(), -Fr::one());
+            let overflow = to_bytes_be(modulus::<Fr>());
+            let mut transcript = Evm::new(overflow.as_slice()).with_decoding(decoding);
+            assert!(transcript.read_scalar().is_err());
+            let bytes = xy(&one, &two);
+            let mut transcript = Evm::new(bytes.as_slice()).with_decoding(decoding);
+            assert_eq!(transcript.read_ec_point().unwrap(), generator);
+            // Non-canonical x-coordinate
+            let bytes = xy(&to_bytes_be(modulus::<Fq>() + 1u64), &two);
+            let mut transcript = Evm::new(bytes.as_slice()).with_decoding(decoding);
+            assert!(transcript.read_ec_point().is_err());
+            // Not on curve
+            let bytes = xy(&one, &one);
+            let mut transcript = Evm::new(bytes.as_slice()).with_decoding(decoding);
+            assert!(transcript.read_ec_point().is_err());
+        }
+    }
+
+    #[test]
+    fn test_poseidon_transcript_proof_of_work() {
+        type Poseidon<S> = PoseidonTranscript<G1Affine, NativeLoader, S, 5, 4, 8, 60>;
+
+        let mut transcript = Poseidon::new(Vec::new());
+        transcript.write_proof_of_work(8).unwrap();
+        let challenge = transcript.squeeze_challenge();
+        let proof = transcript.finalize();
+
+        let mut transcript = Poseidon::new(proof.as_slice());
+        transcript.read_proof_of_work(8).unwrap();
+        assert_eq!(transcript.squeeze_challenge(), challenge);
+    }
+
+    #[test]
+    fn test_transcript_domain_separator() {
+        type Poseidon = PoseidonTranscript<G1Affine, NativeLoader, (), 5, 4, 8, 60>;
+        type Evm = EvmTranscript<G1Affine, NativeLoader, (), Vec<u8>>;
+
+        let message = Fr::from(7);
+        let [lhs, rhs] = [Fr::from(1), Fr::from(2)];
+
+        let poseidon = |transcript: Option<Poseidon>| {
+            let mut transcript = transcript.unwrap_or_else(|| Poseidon::new(()));
+            transcript.common_scalar(&message).unwrap();
+            transcript.squeeze_challenge()
+        };
+        let challenge = poseidon(None);
+        let separated = poseidon(Some(Poseidon::new(()).with_domain_separator(lhs)));
+        assert_ne!(separated, challenge);
+        assert_ne!(separated, poseidon(Some(Poseidon::new(()).with_domain_separator(rhs))));
+        assert_eq!(separated, poseidon(Some(Poseidon::new(()).with_domain_separator(lhs))));
+
+        // Tag is re-absorbed after reset.
+        let mut transcript = Poseidon::new(()).with_domain_separator(lhs);
+        transcript.common_scalar(&Fr::from(3)).unwrap();
+        transcript.new_stream(());
+        assert_eq!(poseidon(Some(transcript)), separated);
+
+        let evm = |transcript: Option<Evm>| {
+            let mut transcript = transcript.unwrap_or_else(|| Evm::new(()));
+            transcript.common_scalar(&message).unwrap();
+            transcript.squeeze_challenge()
+        };
+        let challenge = evm(None);
+        let separated = evm(Some(Evm::new(()).with_domain_separator(lhs)));
+        assert_ne!(separated, challenge);
+        assert_ne!(separated, evm(Some(Evm::new(()).with_domain_separator(rhs))));
+
+        // Tag is absorbed as the first scalar, same as the verifier on EVM.
+        let mut transcript = Evm::new(());
+        transcript.common_scalar(&lhs).unwrap();
+        assert_eq!(evm(Some(transcript)), separated);
     }
 }
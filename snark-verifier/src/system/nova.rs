@@ -0,0 +1,272 @@
+//! Interop with [Nova](<https://eprint.iacr.org/2021/370>) folding over relaxed
+//! R1CS, where witness and error vectors are committed by KZG as coefficients
+//! of univariate polynomials, so IVC pipelines can settle through the existing
+//! accumulation and EVM verifier generation.
+//!
+//! [`RelaxedR1csInstance::fold`] verifies the last non-interactive folding
+//! step, then [`NovaKzgOpening`] opens witness and error commitments of the
+//! folded instance at a challenge point into [`KzgAccumulator`]s, which can be
+//! accumulated by [`KzgAs`](crate::pcs::kzg::KzgAs) like the ones of any other
+//! snark. Satisfiability of relaxed R1CS by the opened polynomials is left to
+//! a decider snark, whose instances should include
+//! [`NovaKzgOpening::point`], [`NovaKzgOpening::evals`] and the folded
+//! instance.
+//!
+//! This is a partial implementation: only the last folding step and the
+//! openings are verified. The final SNARK proving the folded instance is
+//! satisfiable is not verified here, so accepting the outputs of this module
+//! alone proves nothing about the IVC computation. Callers must verify such a
+//! decider snark themselves, e.g. by aggregating it alongside.
+use crate::{
+    loader::Loader,
+    pcs::kzg::{KzgAccumulator, KzgSuccinctVerifyingKey},
+    util::{arithmetic::CurveAffine, msm::Msm, transcript::TranscriptRead, Itertools},
+    Error,
+};
+
+/// Relaxed R1CS instance $(\overline{W}, \overline{E}, u, x)$.
+#[derive(Clone, Debug)]
+pub struct RelaxedR1csInstance<C, L>
+where
+    C: CurveAffine,
+    L: Loader<C>,
+{
+    /// Commitment of witness.
+    pub comm_w: L::LoadedEcPoint,
+    /// Commitment of error.
+    pub comm_e: L::LoadedEcPoint,
+    /// Scalar $u$.
+    pub u: L::LoadedScalar,
+    /// Public inputs.
+    pub x: Vec<L::LoadedScalar>,
+}
+
+impl<C, L> RelaxedR1csInstance<C, L>
+where
+    C: CurveAffine,
+    L: Loader<C>,
+{
+    /// Read a relaxed R1CS instance with `num_public` public inputs from
+    /// transcript, in order of $\overline{W}$, $\overline{E}$, $u$ and $x$.
+    pub fn read<T>(num_public: usize, transcript: &mut T) -> Result<Self, Error>
+    where
+        T: TranscriptRead<C, L>,
+    {
+        let comm_w = transcript.read_ec_point()?;
+        let comm_e = transcript.read_ec_point()?;
+        let u = transcript.read_scalar()?;
+        let x = transcript.read_n_scalars(num_public)?;
+        Ok(Self { comm_w, comm_e, u, x })
+    }
+
+    /// Verify the non-interactive folding of strict `incoming` into `self`,
+    /// which reads commitment of cross term $\overline{T}$ and squeezes
+    /// challenge $r$, then returns the folded instance. Both instances are
+    /// expected to be already absorbed into transcript.
+    pub fn fold<T>(&self, incoming: &R1csInstance<C, L>, transcript: &mut T) -> Result<Self, Error>
+    where
+        T: TranscriptRead<C, L>,
+    {
+        if self.x.len() != incoming.x.len() {
+            return Err(Error::AssertionFailure(format!(
+                "Expected instances with same number of public inputs, but got {} and {}",
+                self.x.len(),
+                incoming.x.len()
+            )));
+        }
+
+        let comm_t = transcript.read_ec_point()?;
+        let r = transcript.squeeze_challenge();
+
+        let comm_w = (Msm::base(&self.comm_w) + Msm::base(&incoming.comm_w) * &r).evaluate(None);
+        let comm_e = (Msm::base(&self.comm_e) + Msm::base(&comm_t) * &r).evaluate(None);
+        let u = self.u.clone() + &r;
+        let x = self
+            .x
+            .iter()
+            .zip(incoming.x.iter())
+            .map(|(lhs, rhs)| lhs.clone() + r.clone() * rhs)
+            .collect_vec();
+
+        Ok(Self { comm_w, comm_e, u, x })
+    }
+}
+
+/// Strict R1CS instance $(\overline{W}, x)$, which is a relaxed one with
+/// $\overline{E} = 0$ and $u = 1$.
+#[derive(Clone, Debug)]
+pub struct R1csInstance<C, L>
+where
+    C: CurveAffine,
+    L: Loader<C>,
+{
+    /// Commitment of witness.
+    pub comm_w: L::LoadedEcPoint,
+    /// Public inputs.
+    pub x: Vec<L::LoadedScalar>,
+}
+
+impl<C, L> R1csInstance<C, L>
+where
+    C: CurveAffine,
+    L: Loader<C>,
+{
+    /// Read a strict R1CS instance with `num_public` public inputs from
+    /// transcript, in order of $\overline{W}$ and $x$.
+    pub fn read<T>(num_public: usize, transcript: &mut T) -> Result<Self, Error>
+    where
+        T: TranscriptRead<C, L>,
+    {
+        let comm_w = transcript.read_ec_point()?;
+        let x = transcript.read_n_scalars(num_public)?;
+        Ok(Self { comm_w, x })
+    }
+}
+
+/// KZG opening of witness and error commitments of [`RelaxedR1csInstance`]
+/// at a challenge point.
+#[derive(Clone, Debug)]
+pub struct NovaKzgOpening<C, L>
+where
+    C: CurveAffine,
+    L: Loader<C>,
+{
+    /// Challenge point.
+    pub point: L::LoadedScalar,
+    /// Evaluations of witness and error polynomials at [`Self::point`].
+    pub evals: [L::LoadedScalar; 2],
+    quotients: [L::LoadedEcPoint; 2],
+}
+
+impl<C, L> NovaKzgOpening<C, L>
+where
+    C: CurveAffine,
+    L: Loader<C>,
+{
+    /// Squeeze challenge point, then read evaluations of witness and error
+    /// polynomials and their quotient commitments from transcript. The
+    /// instance is expected to be already absorbed into transcript.
+    pub fn read<T>(transcript: &mut T) -> Result<Self, Error>
+    where
+        T: TranscriptRead<C, L>,
+    {
+        let point = transcript.squeeze_challenge();
+        let evals = [transcript.read_scalar()?, transcript.read_scalar()?];
+        let quotients = [transcript.read_ec_point()?, transcript.read_ec_point()?];
+        Ok(Self { point, evals, quotients })
+    }
+
+    /// Returns [`KzgAccumulator`]s of opening witness and error commitments of
+    /// `instance`, in that order.
+    pub fn accumulators(
+        &self,
+        svk: &KzgSuccinctVerifyingKey<C>,
+        instance: &RelaxedR1csInstance<C, L>,
+    ) -> Vec<KzgAccumulator<C, L>> {
+        [&instance.comm_w, &instance.comm_e]
+            .into_iter()
+            .zip(self.evals.iter())
+            .zip(self.quotients.iter())
+            .map(|((commitment, eval), quotient)| {
+                let lhs = Msm::base(commitment) - Msm::constant(eval.clone())
+                    + Msm::base(quotient) * &self.point;
+                KzgAccumulator::new(lhs.evaluate(Some(svk.g)), Msm::base(quotient).evaluate(None))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        halo2_curves::bn256::{Bn256, Fr, G1Affine, G1, G2},
+        halo2_proofs::transcript::{
+            Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+        },
+        loader::native::NativeLoader,
+        pcs::{
+            kzg::{Gwc19, Kzg, KzgDecidingKey, KzgSuccinctVerifyingKey},
+            Decider,
+        },
+        system::nova::{NovaKzgOpening, R1csInstance, RelaxedR1csInstance},
+        util::{
+            arithmetic::{Curve, Field, Group, PrimeField},
+            transcript::{Transcript, TranscriptWrite},
+            Itertools,
+        },
+    };
+    use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+    use std::iter;
+
+    #[test]
+    fn test_nova_fold_and_open() {
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        let s = Fr::random(&mut rng);
+        let g = G1::generator().to_affine();
+        let eval =
+            |poly: &[Fr], x: Fr| poly.iter().rev().fold(Fr::zero(), |acc, coeff| acc * x + coeff);
+        let commit = |poly: &[Fr]| (g * eval(poly, s)).to_affine();
+        let mut rand_poly = || iter::repeat_with(|| Fr::random(&mut rng)).take(4).collect_vec();
+
+        let [w_1, e_1, w_2, t] = [(); 4].map(|_| rand_poly());
+        let u_1 = Fr::random(&mut rng);
+        let (x_1, x_2) = (vec![Fr::from(1), Fr::from(2)], vec![Fr::from(3), Fr::from(4)]);
+
+        let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(Vec::new());
+        transcript.write_ec_point(commit(&w_1)).unwrap();
+        transcript.write_ec_point(commit(&e_1)).unwrap();
+        transcript.write_scalar(u_1).unwrap();
+        x_1.iter().for_each(|x| transcript.write_scalar(*x).unwrap());
+        transcript.write_ec_point(commit(&w_2)).unwrap();
+        x_2.iter().for_each(|x| transcript.write_scalar(*x).unwrap());
+        transcript.write_ec_point(commit(&t)).unwrap();
+        let r = transcript.squeeze_challenge();
+        let [w, e] = [(&w_1, &w_2), (&e_1, &t)]
+            .map(|(lhs, rhs)| lhs.iter().zip(rhs).map(|(lhs, rhs)| *lhs + r * rhs).collect_vec());
+        let z = transcript.squeeze_challenge();
+        for poly in [&w, &e] {
+            transcript.write_scalar(eval(poly, z)).unwrap();
+        }
+        for poly in [&w, &e] {
+            let quotient = (eval(poly, s) - eval(poly, z)) * (s - z).invert().unwrap();
+            transcript.write_ec_point((g * quotient).to_affine()).unwrap();
+        }
+        let proof = transcript.finalize();
+
+        let svk = KzgSuccinctVerifyingKey::new(g);
+        let dk = KzgDecidingKey::<Bn256>::new(
+            G2::generator().to_affine(),
+            (G2::generator() * s).to_affine(),
+        );
+        let verify = |proof: &[u8]| {
+            let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(proof);
+            let running = RelaxedR1csInstance::<G1Affine, NativeLoader>::read(2, &mut transcript)?;
+            let incoming = R1csInstance::read(2, &mut transcript)?;
+            let folded = running.fold(&incoming, &mut transcript)?;
+            let opening = NovaKzgOpening::<G1Affine, NativeLoader>::read(&mut transcript)?;
+            Ok::<_, crate::Error>((folded, opening))
+        };
+
+        let (folded, opening) = verify(&proof).unwrap();
+        assert_eq!(folded.comm_w, commit(&w));
+        assert_eq!(folded.comm_e, commit(&e));
+        assert_eq!(folded.u, u_1 + r);
+        assert_eq!(folded.x, vec![x_1[0] + r * x_2[0], x_1[1] + r * x_2[1]]);
+        assert_eq!(opening.evals, [eval(&w, z), eval(&e, z)]);
+        assert!(<Kzg<Bn256, Gwc19> as Decider<G1Affine, NativeLoader>>::decide_all(
+            &dk,
+            opening.accumulators(&svk, &folded)
+        ));
+
+        // Tamper with evaluation of witness polynomial.
+        let mut proof = proof;
+        let offset = proof.len() - 4 * 32;
+        let wrong_eval = eval(&w, z) + Fr::one();
+        proof[offset..offset + 32].copy_from_slice(wrong_eval.to_repr().as_ref());
+        let (folded, opening) = verify(&proof).unwrap();
+        assert!(!<Kzg<Bn256, Gwc19> as Decider<G1Affine, NativeLoader>>::decide_all(
+            &dk,
+            opening.accumulators(&svk, &folded)
+        ));
+    }
+}
@@ -0,0 +1,289 @@
+//! Instance layout of Scroll's zkEVM chunk and batch proofs.
+//!
+//! Both chunk proofs (after compression) and batch proofs expose the same
+//! instance column layout: `NUM_ACCUMULATOR_LIMBS` limbs of the KZG
+//! accumulator followed by the 32 bytes of the public input hash, one byte per
+//! cell. The public input hash commits to the chunk or batch described by
+//! [`ChunkHash`] or [`BatchHash`].
+use crate::{
+    util::{
+        arithmetic::PrimeField,
+        hash::{Digest, Keccak256},
+        Itertools,
+    },
+    Error,
+};
+
+/// Number of limbs of the accumulator at the beginning of the instances,
+/// which is 2 elliptic curve points with 2 coordinates and 3 limbs each.
+pub const NUM_ACCUMULATOR_LIMBS: usize = 12;
+/// Number of bytes of the public input hash.
+pub const NUM_PI_HASH_BYTES: usize = 32;
+/// Total number of instances of a chunk or batch proof.
+pub const NUM_INSTANCE: usize = NUM_ACCUMULATOR_LIMBS + NUM_PI_HASH_BYTES;
+
+/// Public data committed by a chunk proof.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChunkHash {
+    pub chain_id: u64,
+    pub prev_state_root: [u8; 32],
+    pub post_state_root: [u8; 32],
+    pub withdraw_root: [u8; 32],
+    pub data_hash: [u8; 32],
+}
+
+impl ChunkHash {
+    /// Returns public input hash as
+    /// `keccak(chain_id || prev_state_root || post_state_root || withdraw_root || data_hash)`.
+    pub fn pi_hash(&self) -> [u8; 32] {
+        Keccak256::new()
+            .chain_update(self.chain_id.to_be_bytes())
+            .chain_update(self.prev_state_root)
+            .chain_update(self.post_state_root)
+            .chain_update(self.withdraw_root)
+            .chain_update(self.data_hash)
+            .finalize()
+            .into()
+    }
+}
+
+/// Public data committed by a batch proof, which aggregates consecutive
+/// chunks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchHash {
+    chain_id: u64,
+    chunks: Vec<ChunkHash>,
+}
+
+impl BatchHash {
+    /// Construct [`BatchHash`] from `chunks`, which must be non-empty, on the
+    /// same chain and have state roots chained.
+    pub fn new(chunks: Vec<ChunkHash>) -> Result<Self, Error> {
+        let chain_id = chunks
+            .first()
+            .ok_or_else(|| Error::AssertionFailure("Batch without chunks".to_string()))?
+            .chain_id;
+        if chunks.iter().any(|chunk| chunk.chain_id != chain_id) {
+            return Err(Error::AssertionFailure("Chunks with different chain id".to_string()));
+        }
+        if let Some(idx) = chunks
+            .iter()
+            .tuple_windows()
+            .position(|(prev, next)| prev.post_state_root != next.prev_state_root)
+        {
+            return Err(Error::AssertionFailure(format!(
+                "State root of chunk {} doesn't follow chunk {idx}",
+                idx + 1
+            )));
+        }
+        Ok(Self { chain_id, chunks })
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    pub fn chunks(&self) -> &[ChunkHash] {
+        &self.chunks
+    }
+
+    pub fn prev_state_root(&self) -> [u8; 32] {
+        self.chunks.first().unwrap().prev_state_root
+    }
+
+    pub fn post_state_root(&self) -> [u8; 32] {
+        self.chunks.last().unwrap().post_state_root
+    }
+
+    pub fn withdraw_root(&self) -> [u8; 32] {
+        self.chunks.last().unwrap().withdraw_root
+    }
+
+    /// Returns `keccak(chunk_0.data_hash || ... || chunk_n.data_hash)`.
+    pub fn data_hash(&self) -> [u8; 32] {
+        self.chunks
+            .iter()
+            .fold(Keccak256::new(), |hasher, chunk| hasher.chain_update(chunk.data_hash))
+            .finalize()
+            .into()
+    }
+
+    /// Returns public input hash as
+    /// `keccak(chain_id || prev_state_root || post_state_root || withdraw_root || data_hash)`.
+    pub fn pi_hash(&self) -> [u8; 32] {
+        ChunkHash {
+            chain_id: self.chain_id,
+            prev_state_root: self.prev_state_root(),
+            post_state_root: self.post_state_root(),
+            withdraw_root: self.withdraw_root(),
+            data_hash: self.data_hash(),
+        }
+        .pi_hash()
+    }
+}
+
+/// Typed view of instances of a chunk or batch proof.
+#[derive(Clone, Debug)]
+pub struct ScrollInstances<F: PrimeField> {
+    accumulator_limbs: Vec<F>,
+    pi_hash: [u8; 32],
+}
+
+impl<F: PrimeField> ScrollInstances<F> {
+    /// Parse `instances` of the single instance column, which must have
+    /// exactly [`NUM_INSTANCE`] cells, with the last [`NUM_PI_HASH_BYTES`]
+    /// ones being bytes.
+    pub fn from_instances(instances: &[Vec<F>]) -> Result<Self, Error> {
+        let instances = match instances {
+            [instances] if instances.len() == NUM_INSTANCE => instances,
+            _ => return Err(Error::InvalidInstances),
+        };
+        let (accumulator_limbs, pi_hash) = instances.split_at(NUM_ACCUMULATOR_LIMBS);
+        let pi_hash = pi_hash
+            .iter()
+            .map(|byte| {
+                let repr = byte.to_repr();
+                match repr.as_ref().split_first() {
+                    Some((byte, rest)) if rest.iter().all(|byte| *byte == 0) => Ok(*byte),
+                    _ => Err(Error::InvalidInstances),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            accumulator_limbs: accumulator_limbs.to_vec(),
+            pi_hash: pi_hash.try_into().unwrap(),
+        })
+    }
+
+    /// Returns instances of the single instance column.
+    pub fn to_instances(&self) -> Vec<Vec<F>> {
+        vec![self
+            .accumulator_limbs
+            .iter()
+            .cloned()
+            .chain(self.pi_hash.iter().map(|byte| F::from(*byte as u64)))
+            .collect()]
+    }
+
+    pub fn accumulator_limbs(&self) -> &[F] {
+        &self.accumulator_limbs
+    }
+
+    pub fn pi_hash(&self) -> [u8; 32] {
+        self.pi_hash
+    }
+
+    /// Check the public input hash commits to `chunk`.
+    pub fn validate_chunk(&self, chunk: &ChunkHash) -> Result<(), Error> {
+        self.validate_pi_hash(chunk.pi_hash())
+    }
+
+    /// Check the public input hash commits to `batch`.
+    pub fn validate_batch(&self, batch: &BatchHash) -> Result<(), Error> {
+        self.validate_pi_hash(batch.pi_hash())
+    }
+
+    fn validate_pi_hash(&self, pi_hash: [u8; 32]) -> Result<(), Error> {
+        if self.pi_hash != pi_hash {
+            return Err(Error::AssertionFailure(format!(
+                "Public input hash mismatch, expected {}, got {}",
+                hex::encode(pi_hash),
+                hex::encode(self.pi_hash)
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        halo2_curves::bn256::Fr,
+        system::scroll::{
+            BatchHash, ChunkHash, ScrollInstances, NUM_ACCUMULATOR_LIMBS, NUM_INSTANCE,
+        },
+    };
+
+    const CHAIN_ID: u64 = 534352;
+
+    fn chunks() -> [ChunkHash; 2] {
+        [
+            ChunkHash {
+                chain_id: CHAIN_ID,
+                prev_state_root: [1; 32],
+                post_state_root: [2; 32],
+                withdraw_root: [3; 32],
+                data_hash: [4; 32],
+            },
+            ChunkHash {
+                chain_id: CHAIN_ID,
+                prev_state_root: [2; 32],
+                post_state_root: [5; 32],
+                withdraw_root: [6; 32],
+                data_hash: [7; 32],
+            },
+        ]
+    }
+
+    fn from_hex(hex: &str) -> [u8; 32] {
+        hex::decode(hex).unwrap().try_into().unwrap()
+    }
+
+    #[test]
+    fn test_pi_hash() {
+        let [chunk_0, chunk_1] = chunks();
+        assert_eq!(
+            chunk_0.pi_hash(),
+            from_hex("41011550271b7a7dfc076a627249774f4329d3efc7b971006ed93a18ad46cb63")
+        );
+        assert_eq!(
+            chunk_1.pi_hash(),
+            from_hex("9cb430341f17e5f8c5990c3f5ec7eb1cc550043424997efe400b7cc36b5a2b6e")
+        );
+
+        let batch = BatchHash::new(chunks().to_vec()).unwrap();
+        assert_eq!(batch.chain_id(), CHAIN_ID);
+        assert_eq!(batch.prev_state_root(), [1; 32]);
+        assert_eq!(batch.post_state_root(), [5; 32]);
+        assert_eq!(batch.withdraw_root(), [6; 32]);
+        assert_eq!(
+            batch.data_hash(),
+            from_hex("48e5a5e87dc537c1bf85e765f0289b19ec8b77909f0357ad7579ad5b63e89be4")
+        );
+        assert_eq!(
+            batch.pi_hash(),
+            from_hex("4ec2d402d93878229f2c6aa1e751ddcf0f448da24820709cee0c994b19ae7c54")
+        );
+    }
+
+    #[test]
+    fn test_batch_hash_reject_invalid_chunks() {
+        let [chunk_0, chunk_1] = chunks();
+        assert!(BatchHash::new(Vec::new()).is_err());
+        assert!(BatchHash::new(vec![chunk_1, chunk_0]).is_err());
+        let other_chain = ChunkHash { chain_id: CHAIN_ID + 1, ..chunk_1 };
+        assert!(BatchHash::new(vec![chunk_0, other_chain]).is_err());
+    }
+
+    #[test]
+    fn test_scroll_instances() {
+        let batch = BatchHash::new(chunks().to_vec()).unwrap();
+        let accumulator_limbs = (0..NUM_ACCUMULATOR_LIMBS as u64).map(Fr::from).collect::<Vec<_>>();
+        let instances = vec![accumulator_limbs
+            .iter()
+            .cloned()
+            .chain(batch.pi_hash().iter().map(|byte| Fr::from(*byte as u64)))
+            .collect::<Vec<_>>()];
+
+        let parsed = ScrollInstances::from_instances(&instances).unwrap();
+        assert_eq!(parsed.accumulator_limbs(), accumulator_limbs.as_slice());
+        assert_eq!(parsed.to_instances(), instances);
+        assert!(parsed.validate_batch(&batch).is_ok());
+        assert!(parsed.validate_chunk(&chunks()[0]).is_err());
+
+        let mut not_byte = instances.clone();
+        not_byte[0][NUM_INSTANCE - 1] = Fr::from(256);
+        assert!(ScrollInstances::from_instances(&not_byte).is_err());
+        assert!(ScrollInstances::from_instances(&[instances[0][1..].to_vec()]).is_err());
+    }
+}
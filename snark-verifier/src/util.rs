@@ -1,6 +1,10 @@
 //! Utilities.
 
 pub mod arithmetic;
+#[cfg(feature = "async_io")]
+pub mod async_io;
+#[cfg(feature = "golden")]
+pub mod golden;
 pub mod hash;
 pub mod msm;
 pub mod poly;
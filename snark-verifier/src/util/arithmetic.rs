@@ -58,6 +58,9 @@ pub fn batch_invert_and_mul<F: PrimeField>(values: &mut [F], coeff: &F) {
             Some(*acc)
         })
         .collect_vec();
+    if products.is_empty() {
+        return;
+    }
 
     let mut all_product_inv = products.last().unwrap().invert().unwrap() * coeff;
 
@@ -259,15 +262,23 @@ pub fn fe_to_fe<F1: PrimeField, F2: PrimeField>(fe: F1) -> F2 {
 pub fn fe_from_limbs<F1: PrimeField, F2: PrimeField, const LIMBS: usize, const BITS: usize>(
     limbs: [F1; LIMBS],
 ) -> F2 {
-    fe_from_big(
-        limbs
-            .iter()
-            .map(|limb| BigUint::from_bytes_le(limb.to_repr().as_ref()))
-            .zip((0usize..).step_by(BITS))
-            .map(|(limb, shift)| limb << shift)
-            .reduce(|acc, shifted| acc + shifted)
-            .unwrap(),
-    )
+    try_fe_from_limbs::<_, _, LIMBS, BITS>(limbs).expect("Limbs exceed modulus of target field")
+}
+
+/// Same as [`fe_from_limbs`] but returns `None` if limbs represent a value not
+/// less than modulus of `F2`, which could happen when `LIMBS * BITS` exceeds
+/// [`PrimeField::NUM_BITS`] of `F2` and limbs are not canonical.
+pub fn try_fe_from_limbs<F1: PrimeField, F2: PrimeField, const LIMBS: usize, const BITS: usize>(
+    limbs: [F1; LIMBS],
+) -> Option<F2> {
+    let big = limbs
+        .iter()
+        .map(|limb| BigUint::from_bytes_le(limb.to_repr().as_ref()))
+        .zip((0usize..).step_by(BITS))
+        .map(|(limb, shift)| limb << shift)
+        .reduce(|acc, shifted| acc + shifted)
+        .unwrap();
+    (big < modulus::<F2>()).then(|| fe_from_big(big))
 }
 
 /// Convert a [`PrimeField`] into `LIMBS` limbs where each limb contains at
@@ -286,6 +297,46 @@ pub fn fe_to_limbs<F1: PrimeField, F2: PrimeField, const LIMBS: usize, const BIT
         .unwrap()
 }
 
+/// Mode of decoding field elements and elliptic curve points from bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Decoding {
+    /// Variable-time decoding, which is faster and fine for public data like
+    /// proofs.
+    #[default]
+    VarTime,
+    /// Constant-time decoding, which only branches on whether decoding
+    /// succeeds at the very end, for data that might be derived from secrets
+    /// (e.g. verifying keys or accumulators in some MPC settings).
+    ConstantTime,
+}
+
+/// Decode a [`PrimeField`] element from its canonical `repr` according to
+/// `decoding`, and returns `None` if it's not canonical.
+pub fn fe_from_repr<F: PrimeField>(repr: F::Repr, decoding: Decoding) -> Option<F> {
+    match decoding {
+        Decoding::VarTime => F::from_repr_vartime(repr),
+        Decoding::ConstantTime => F::from_repr(repr).into(),
+    }
+}
+
+/// Decode an elliptic curve point from canonical `repr` of its coordinates
+/// according to `decoding`, and returns `None` if either coordinate is not
+/// canonical or the point is not on the curve.
+pub fn ec_point_from_xy_repr<C: CurveAffine>(
+    x: <C::Base as PrimeField>::Repr,
+    y: <C::Base as PrimeField>::Repr,
+    decoding: Decoding,
+) -> Option<C> {
+    match decoding {
+        Decoding::VarTime => fe_from_repr(x, decoding)
+            .zip(fe_from_repr(y, decoding))
+            .and_then(|(x, y)| C::from_xy(x, y).into()),
+        Decoding::ConstantTime => C::Base::from_repr(x)
+            .and_then(|x| C::Base::from_repr(y).and_then(|y| C::from_xy(x, y)))
+            .into(),
+    }
+}
+
 /// Returns iterator that yields scalar^0, scalar^1, scalar^2...
 pub fn powers<F: Field>(scalar: F) -> impl Iterator<Item = F> {
     iter::successors(Some(F::one()), move |power| Some(scalar * power))
@@ -299,3 +350,13 @@ pub fn inner_product<F: Field>(lhs: &[F], rhs: &[F]) -> F {
         .reduce(|acc, product| acc + product)
         .unwrap_or_default()
 }
+
+/// Compute `constant + sum(coeff * value)` of [`Field`] elements.
+pub fn sum_with_coeff_and_const<F: Field>(values: &[(F, &F)], constant: F) -> F {
+    values.iter().fold(constant, |acc, (coeff, value)| acc + *coeff * *value)
+}
+
+/// Compute `constant + sum(coeff * lhs * rhs)` of [`Field`] elements.
+pub fn sum_products_with_coeff_and_const<F: Field>(values: &[(F, &F, &F)], constant: F) -> F {
+    values.iter().fold(constant, |acc, (coeff, lhs, rhs)| acc + *coeff * *lhs * *rhs)
+}
@@ -0,0 +1,120 @@
+//! Native transcripts over [`AsyncRead`], so a verification service could
+//! start hashing a proof while it's still being downloaded (e.g. from object
+//! storage), instead of waiting for the whole proof.
+//!
+//! Since [`TranscriptRead`] and verifiers are synchronous, native transcripts
+//! read from [`AsyncReader`], which implements [`Read`] by blocking on the
+//! underlying [`AsyncRead`], and [`read_async`] runs such verification on a
+//! dedicated thread and resolves once it finishes, so async executors are
+//! never blocked.
+//!
+//! [`TranscriptRead`]: crate::util::transcript::TranscriptRead
+use futures::{
+    channel::oneshot,
+    executor::block_on,
+    io::{AsyncRead, AsyncReadExt},
+};
+use std::{
+    io::{self, Read},
+    thread,
+};
+
+/// [`Read`] over [`AsyncRead`] by blocking on each read, which should only be
+/// used on threads allowed to block, e.g. the one spawned by [`read_async`].
+#[derive(Debug)]
+pub struct AsyncReader<R> {
+    reader: R,
+}
+
+impl<R: AsyncRead + Unpin> AsyncReader<R> {
+    /// Initialize [`AsyncReader`] over `reader`.
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+
+    /// Returns the underlying [`AsyncRead`].
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsyncRead + Unpin> Read for AsyncReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        block_on(self.reader.read(buf))
+    }
+}
+
+/// Runs `f` with [`AsyncReader`] over `reader` on a dedicated thread, and
+/// resolves to what `f` returns, e.g. whether a proof read by a native
+/// transcript over the reader verifies.
+///
+/// # Panics
+///
+/// Panics if `f` panics.
+pub async fn read_async<R, T, F>(reader: R, f: F) -> T
+where
+    R: AsyncRead + Unpin + Send + 'static,
+    T: Send + 'static,
+    F: FnOnce(AsyncReader<R>) -> T + Send + 'static,
+{
+    let (tx, rx) = oneshot::channel();
+    thread::spawn(move || {
+        // Receiver is dropped only if the future is dropped.
+        let _ = tx.send(f(AsyncReader::new(reader)));
+    });
+    rx.await.expect("Reading from AsyncRead panics")
+}
+
+#[cfg(all(test, feature = "loader_halo2"))]
+mod test {
+    use crate::{
+        halo2_curves::bn256::{Bn256, G1Affine},
+        halo2_proofs::{
+            poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK},
+            transcript::{Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer},
+        },
+        pcs::kzg::{Bdfg21, Kzg, LimbsEncoding},
+        system::halo2::test::{
+            kzg::{
+                halo2_kzg_config, halo2_kzg_create_snark, halo2_kzg_native_verify,
+                halo2_kzg_prepare, BITS, LIMBS,
+            },
+            StandardPlonk,
+        },
+        util::async_io::read_async,
+        verifier::Plonk,
+    };
+    use futures::{executor::block_on, io::Cursor};
+    use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+
+    #[test]
+    fn test_async_io_transcript() {
+        let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+            9,
+            halo2_kzg_config!(true, 2),
+            StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+        );
+        let snark = halo2_kzg_create_snark!(
+            ProverSHPLONK<_>,
+            VerifierSHPLONK<_>,
+            Blake2bWrite<_, _, _>,
+            Blake2bRead<_, _, _>,
+            Challenge255<_>,
+            &params,
+            &pk,
+            &protocol,
+            &circuits
+        );
+
+        let reader = Cursor::new(snark.proof.clone());
+        block_on(read_async(reader, move |reader| {
+            halo2_kzg_native_verify!(
+                Plonk<Kzg<Bn256, Bdfg21>, LimbsEncoding<LIMBS, BITS>>,
+                params,
+                &snark.protocol,
+                &snark.instances,
+                &mut Blake2bRead::<_, G1Affine, _>::init(reader)
+            );
+        }));
+    }
+}
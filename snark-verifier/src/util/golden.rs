@@ -0,0 +1,267 @@
+//! Golden-file (snapshot) test utilities, which canonicalize outputs like
+//! [`Protocol`], generated Yul, calldata and gas usage into stable text, so
+//! forks can keep byte-level compatibility tests against upstream outputs.
+//!
+//! Set environment variable [`UPDATE_GOLDEN`] to overwrite golden files with
+//! actual outputs instead of comparing.
+//!
+//! [`test_vectors`] snapshots transcript challenges, Poseidon squeeze outputs,
+//! accumulator limb encodings and generated Yul for a fixed seed, so
+//! downstream crates could pin them by [`assert_test_vectors`] to detect
+//! incompatible changes when upgrading this crate.
+use crate::{
+    halo2_curves::bn256::{Fr, G1Affine, G1},
+    loader::native::NativeLoader,
+    pcs::kzg::KzgAccumulator,
+    util::{
+        arithmetic::{fe_to_limbs, Curve, CurveAffine, Group, PrimeField},
+        hash::Poseidon,
+    },
+    Protocol,
+};
+use serde::Serialize;
+use std::{fs, path::Path};
+
+/// Environment variable to update golden files instead of comparing.
+pub const UPDATE_GOLDEN: &str = "UPDATE_GOLDEN";
+
+/// Canonical serialization of [`Protocol`] as pretty JSON.
+pub fn canonical_protocol<C>(protocol: &Protocol<C>) -> String
+where
+    C: CurveAffine,
+    Protocol<C>: Serialize,
+{
+    serde_json::to_string_pretty(protocol).unwrap()
+}
+
+/// Canonical form of Yul or Solidity code, with each line trimmed and empty
+/// lines removed, so only meaningful changes are detected.
+pub fn canonical_yul(code: &str) -> String {
+    code.lines().map(str::trim).filter(|line| !line.is_empty()).fold(
+        String::new(),
+        |mut acc, line| {
+            acc.push_str(line);
+            acc.push('\n');
+            acc
+        },
+    )
+}
+
+/// Canonical form of calldata as hex encoded 32 bytes words, one per line.
+pub fn canonical_calldata(calldata: &[u8]) -> String {
+    calldata.chunks(0x20).fold(String::new(), |mut acc, word| {
+        acc.push_str(&hex::encode(word));
+        acc.push('\n');
+        acc
+    })
+}
+
+/// Canonical form of field elements as hex encoded big-endian 32 bytes words,
+/// one per line.
+pub fn canonical_scalars<F: PrimeField>(scalars: &[F]) -> String {
+    scalars.iter().fold(String::new(), |mut acc, scalar| {
+        let mut repr = scalar.to_repr();
+        repr.as_mut().reverse();
+        acc.push_str(&hex::encode(repr.as_ref()));
+        acc.push('\n');
+        acc
+    })
+}
+
+/// Canonical form of [`KzgAccumulator`] as limbs of `lhs.x`, `lhs.y`, `rhs.x`
+/// and `rhs.y`, in the same layout as [`LimbsEncoding`] expects in instances.
+///
+/// [`LimbsEncoding`]: crate::pcs::kzg::LimbsEncoding
+pub fn canonical_accumulator_limbs<C, const LIMBS: usize, const BITS: usize>(
+    accumulator: &KzgAccumulator<C, NativeLoader>,
+) -> String
+where
+    C: CurveAffine,
+{
+    let limbs = [accumulator.lhs, accumulator.rhs]
+        .iter()
+        .flat_map(|ec_point| {
+            let coordinates = ec_point.coordinates().unwrap();
+            [*coordinates.x(), *coordinates.y()]
+        })
+        .flat_map(fe_to_limbs::<_, C::Scalar, LIMBS, BITS>)
+        .collect::<Vec<_>>();
+    canonical_scalars(&limbs)
+}
+
+/// Canonical form of gas usage.
+pub fn canonical_gas(gas_used: u64) -> String {
+    format!("{gas_used}\n")
+}
+
+/// Compare `actual` against the golden file at `path`, or overwrite it when
+/// [`UPDATE_GOLDEN`] is set or it doesn't exist yet.
+///
+/// # Panics
+///
+/// Panics with the first mismatched line when `actual` differs from the
+/// golden file.
+pub fn assert_golden(path: impl AsRef<Path>, actual: &str) {
+    let path = path.as_ref();
+    if std::env::var_os(UPDATE_GOLDEN).is_some() || !path.exists() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).unwrap();
+        }
+        fs::write(path, actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(path).unwrap();
+    if expected == actual {
+        return;
+    }
+    let mismatch = expected
+        .lines()
+        .map(Some)
+        .chain(std::iter::repeat(None))
+        .zip(actual.lines().map(Some).chain(std::iter::repeat(None)))
+        .take(expected.lines().count().max(actual.lines().count()))
+        .enumerate()
+        .find(|(_, (expected, actual))| expected != actual);
+    match mismatch {
+        Some((line, (expected, actual))) => panic!(
+            "Golden file {} mismatch at line {}\n  expected: {}\n  actual:   {}\nSet {UPDATE_GOLDEN}=1 to update",
+            path.display(),
+            line + 1,
+            expected.unwrap_or("<eof>"),
+            actual.unwrap_or("<eof>"),
+        ),
+        None => panic!("Golden file {} mismatch in line endings", path.display()),
+    }
+}
+
+/// Returns scalars and points derived from `seed` to be absorbed by
+/// transcripts in [`test_vectors`].
+fn seeded_inputs(seed: u64) -> Vec<(Fr, G1Affine)> {
+    (0..4)
+        .map(|idx| {
+            let scalar = Fr::from(seed) * Fr::from(0x100000001) + Fr::from(idx + 1);
+            (scalar, (G1::generator() * scalar).to_affine())
+        })
+        .collect()
+}
+
+/// Returns named test vectors generated from `seed` over bn254, which are
+/// - `keccak_challenges.txt`: Challenges squeezed by native [`EvmTranscript`]
+///   after absorbing each scalar and point (with `loader_evm`).
+/// - `keccak_transcript.yul`: Yul generated by [`EvmTranscript`] on
+///   [`EvmLoader`] reading the same inputs from calldata (with `loader_evm`).
+/// - `poseidon_squeezes.txt`: Outputs of [`Poseidon`] with `T = 5`,
+///   `RATE = 4`, `R_F = 8` and `R_P = 60` squeezed after absorbing each
+///   scalar.
+/// - `accumulator_limbs.txt`: Limbs of [`KzgAccumulator`] of the first two
+///   points with `LIMBS = 3` and `BITS = 88`.
+///
+/// [`EvmTranscript`]: crate::system::halo2::transcript::evm::EvmTranscript
+/// [`EvmLoader`]: crate::loader::evm::EvmLoader
+pub fn test_vectors(seed: u64) -> Vec<(&'static str, String)> {
+    let inputs = seeded_inputs(seed);
+    let mut vectors = Vec::new();
+
+    #[cfg(feature = "loader_evm")]
+    {
+        use crate::{
+            loader::evm::EvmLoader,
+            system::halo2::transcript::evm::EvmTranscript,
+            util::transcript::{Transcript, TranscriptRead},
+        };
+        use std::rc::Rc;
+
+        let mut transcript = EvmTranscript::<G1Affine, NativeLoader, _, _>::new(Vec::<u8>::new());
+        let challenges = inputs
+            .iter()
+            .map(|(scalar, ec_point)| {
+                transcript.common_scalar(scalar).unwrap();
+                transcript.common_ec_point(ec_point).unwrap();
+                transcript.squeeze_challenge()
+            })
+            .collect::<Vec<_>>();
+        vectors.push(("keccak_challenges.txt", canonical_scalars(&challenges)));
+
+        let loader = EvmLoader::new::<crate::halo2_curves::bn256::Fq, Fr>();
+        let mut transcript = EvmTranscript::<G1Affine, Rc<EvmLoader>, _, _>::new(&loader);
+        for _ in inputs.iter() {
+            transcript.read_scalar().unwrap();
+            transcript.read_ec_point().unwrap();
+            transcript.squeeze_challenge();
+        }
+        vectors.push(("keccak_transcript.yul", canonical_yul(&loader.solidity_code())));
+    }
+
+    let mut poseidon = Poseidon::<Fr, Fr, 5, 4>::new(&NativeLoader, 8, 60);
+    let squeezes = inputs
+        .iter()
+        .map(|(scalar, _)| {
+            poseidon.update(&[*scalar]);
+            poseidon.squeeze()
+        })
+        .collect::<Vec<_>>();
+    vectors.push(("poseidon_squeezes.txt", canonical_scalars(&squeezes)));
+
+    let accumulator = KzgAccumulator::<G1Affine, NativeLoader>::new(inputs[0].1, inputs[1].1);
+    vectors.push(("accumulator_limbs.txt", canonical_accumulator_limbs::<_, 3, 88>(&accumulator)));
+
+    vectors
+}
+
+/// Compare [`test_vectors`] of `seed` against golden files in `dir`, see
+/// [`assert_golden`].
+pub fn assert_test_vectors(dir: impl AsRef<Path>, seed: u64) {
+    for (name, actual) in test_vectors(seed) {
+        assert_golden(dir.as_ref().join(name), &actual);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::util::golden::{
+        assert_golden, assert_test_vectors, canonical_calldata, canonical_yul, test_vectors,
+    };
+    use std::{fs, path::PathBuf, process};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("snark-verifier-golden-{name}-{}", process::id()))
+    }
+
+    #[test]
+    fn test_test_vectors() {
+        assert_eq!(test_vectors(0), test_vectors(0));
+        assert_ne!(test_vectors(0), test_vectors(1));
+
+        // The first run writes golden files, and the second one compares.
+        let dir = temp_dir("vectors");
+        assert_test_vectors(&dir, 0);
+        for (name, actual) in test_vectors(0) {
+            assert_eq!(fs::read_to_string(dir.join(name)).unwrap(), actual);
+        }
+        assert_test_vectors(&dir, 0);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_canonical() {
+        assert_eq!(canonical_yul("  a\n\n\tb  \n"), "a\nb\n");
+        let calldata = [[0; 0x20], [0xff; 0x20]].concat();
+        assert_eq!(
+            canonical_calldata(&calldata),
+            format!("{}\n{}\n", "00".repeat(32), "ff".repeat(32))
+        );
+    }
+
+    #[test]
+    fn test_assert_golden_mismatch() {
+        let dir = temp_dir("mismatch");
+        let path = dir.join("golden.txt");
+        assert_golden(&path, "a\nb\n");
+        assert_golden(&path, "a\nb\n");
+        let result = std::panic::catch_unwind(|| assert_golden(&path, "a\nc\n"));
+        fs::remove_dir_all(dir).unwrap();
+        let err = result.unwrap_err();
+        assert!(err.downcast_ref::<String>().unwrap().contains("mismatch at line 2"));
+    }
+}
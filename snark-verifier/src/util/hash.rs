@@ -1,8 +1,10 @@
 //! Hash algorithms.
 
+mod keccak;
 mod poseidon;
 
-pub use crate::util::hash::poseidon::Poseidon;
+pub use crate::util::hash::keccak::{fe_to_bits, keccak256, reverse_bytes, KeccakBit};
+pub use crate::util::hash::poseidon::{Poseidon, PoseidonPermutation, SqueezeDomain};
 
-#[cfg(feature = "loader_evm")]
+#[cfg(any(feature = "loader_evm", feature = "cosmwasm", feature = "loader_solana"))]
 pub use sha3::{Digest, Keccak256};
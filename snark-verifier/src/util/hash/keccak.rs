@@ -0,0 +1,279 @@
+//! Keccak-256 over bits of [`LoadedScalar`], which is used by in-circuit
+//! transcript to derive challenges bit-for-bit same as verifier on EVM.
+//!
+//! Each bit costs a few constraints per round, so hashing a block of 136 bytes
+//! is expensive and should be used only when matching EVM is required.
+use crate::{
+    loader::{LoadedScalar, ScalarLoader},
+    util::arithmetic::PrimeField,
+};
+
+/// Rate of Keccak-256 in bits.
+const RATE: usize = 1088;
+
+const NUM_ROUNDS: usize = 24;
+
+const ROTATION_OFFSETS: [[usize; 5]; 5] = [
+    [0, 36, 3, 41, 18],
+    [1, 44, 10, 45, 2],
+    [62, 6, 43, 15, 61],
+    [28, 55, 25, 21, 56],
+    [27, 20, 39, 8, 14],
+];
+
+const ROUND_CONSTANTS: [u64; NUM_ROUNDS] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808A,
+    0x8000000080008000,
+    0x000000000000808B,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008A,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000A,
+    0x000000008000808B,
+    0x800000000000008B,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800A,
+    0x800000008000000A,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+/// Bit of Keccak input or output, which is either known constant or loaded
+/// boolean field element.
+#[derive(Clone, Debug)]
+pub enum KeccakBit<L> {
+    Constant(bool),
+    Loaded(L),
+}
+
+impl<L> KeccakBit<L> {
+    /// Returns bits of `bytes`, least significant bit first in each byte.
+    pub fn from_bytes(bytes: &[u8]) -> Vec<Self> {
+        bytes
+            .iter()
+            .flat_map(|byte| (0..8).map(move |idx| KeccakBit::Constant(byte >> idx & 1 == 1)))
+            .collect()
+    }
+
+    /// Load the bit as field element.
+    pub fn load<F: PrimeField>(&self, loader: &<L as LoadedScalar<F>>::Loader) -> L
+    where
+        L: LoadedScalar<F>,
+    {
+        match self {
+            KeccakBit::Constant(bit) => loader.load_const(&F::from(*bit as u64)),
+            KeccakBit::Loaded(bit) => bit.clone(),
+        }
+    }
+}
+
+fn not<F: PrimeField, L: LoadedScalar<F>>(a: &KeccakBit<L>) -> KeccakBit<L> {
+    match a {
+        KeccakBit::Constant(a) => KeccakBit::Constant(!a),
+        KeccakBit::Loaded(a) => {
+            KeccakBit::Loaded(a.loader().sum_with_coeff_and_const(&[(-F::one(), a)], F::one()))
+        }
+    }
+}
+
+fn xor<F: PrimeField, L: LoadedScalar<F>>(a: &KeccakBit<L>, b: &KeccakBit<L>) -> KeccakBit<L> {
+    match (a, b) {
+        (KeccakBit::Constant(a), KeccakBit::Constant(b)) => KeccakBit::Constant(a ^ b),
+        (KeccakBit::Constant(false), bit) | (bit, KeccakBit::Constant(false)) => bit.clone(),
+        (KeccakBit::Constant(true), bit) | (bit, KeccakBit::Constant(true)) => not::<F, L>(bit),
+        (KeccakBit::Loaded(a), KeccakBit::Loaded(b)) => {
+            let loader = a.loader();
+            let ab = loader.product(&[a, b]);
+            KeccakBit::Loaded(loader.sum_with_coeff(&[
+                (F::one(), a),
+                (F::one(), b),
+                (-F::from(2), &ab),
+            ]))
+        }
+    }
+}
+
+/// Returns `!a & b`.
+fn and_not<F: PrimeField, L: LoadedScalar<F>>(a: &KeccakBit<L>, b: &KeccakBit<L>) -> KeccakBit<L> {
+    match (a, b) {
+        (KeccakBit::Constant(true), _) | (_, KeccakBit::Constant(false)) => {
+            KeccakBit::Constant(false)
+        }
+        (KeccakBit::Constant(false), bit) => bit.clone(),
+        (bit, KeccakBit::Constant(true)) => not::<F, L>(bit),
+        (KeccakBit::Loaded(a), KeccakBit::Loaded(b)) => {
+            let loader = a.loader();
+            let ab = loader.product(&[a, b]);
+            KeccakBit::Loaded(loader.sum_with_coeff(&[(F::one(), b), (-F::one(), &ab)]))
+        }
+    }
+}
+
+fn xor_all<F: PrimeField, L: LoadedScalar<F>>(
+    bits: impl IntoIterator<Item = KeccakBit<L>>,
+) -> KeccakBit<L> {
+    bits.into_iter().fold(KeccakBit::Constant(false), |acc, bit| xor::<F, L>(&acc, &bit))
+}
+
+/// Keccak-f[1600] permutation, where the state is indexed by
+/// `(x + 5 * y) * 64 + z`.
+fn permute<F: PrimeField, L: LoadedScalar<F>>(state: &mut [KeccakBit<L>]) {
+    for round_constant in ROUND_CONSTANTS {
+        // Theta
+        let c = (0..5)
+            .map(|x| {
+                (0..64)
+                    .map(|z| xor_all::<F, L>((0..5).map(|y| state[(x + 5 * y) * 64 + z].clone())))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        for x in 0..5 {
+            for z in 0..64 {
+                let d = xor::<F, L>(&c[(x + 4) % 5][z], &c[(x + 1) % 5][(z + 63) % 64]);
+                for y in 0..5 {
+                    state[(x + 5 * y) * 64 + z] = xor::<F, L>(&state[(x + 5 * y) * 64 + z], &d);
+                }
+            }
+        }
+
+        // Rho and pi
+        let mut b = vec![KeccakBit::Constant(false); 1600];
+        for x in 0..5 {
+            for y in 0..5 {
+                let offset = ROTATION_OFFSETS[x][y];
+                for z in 0..64 {
+                    b[(y + 5 * ((2 * x + 3 * y) % 5)) * 64 + z] =
+                        state[(x + 5 * y) * 64 + (z + 64 - offset) % 64].clone();
+                }
+            }
+        }
+
+        // Chi
+        for x in 0..5 {
+            for y in 0..5 {
+                for z in 0..64 {
+                    state[(x + 5 * y) * 64 + z] = xor::<F, L>(
+                        &b[(x + 5 * y) * 64 + z],
+                        &and_not::<F, L>(
+                            &b[((x + 1) % 5 + 5 * y) * 64 + z],
+                            &b[((x + 2) % 5 + 5 * y) * 64 + z],
+                        ),
+                    );
+                }
+            }
+        }
+
+        // Iota
+        for (z, bit) in state.iter_mut().take(64).enumerate() {
+            if round_constant >> z & 1 == 1 {
+                *bit = not::<F, L>(bit);
+            }
+        }
+    }
+}
+
+/// Returns Keccak-256 hash of `input` as 256 bits, where both `input` and
+/// output are bytes with least significant bit first in each byte. The loaded
+/// bits of `input` are assumed to be constrained to be boolean.
+///
+/// # Panics
+///
+/// Panics if length of `input` is not a multiple of 8.
+pub fn keccak256<F, L>(input: &[KeccakBit<L>]) -> Vec<KeccakBit<L>>
+where
+    F: PrimeField,
+    L: LoadedScalar<F>,
+{
+    assert_eq!(input.len() % 8, 0);
+
+    let num_padding = RATE - input.len() % RATE;
+    let input = input
+        .iter()
+        .cloned()
+        .chain((0..num_padding).map(|idx| KeccakBit::Constant(idx == 0 || idx == num_padding - 1)))
+        .collect::<Vec<_>>();
+
+    let mut state = vec![KeccakBit::Constant(false); 1600];
+    for block in input.chunks(RATE) {
+        for (lhs, rhs) in state.iter_mut().zip(block) {
+            *lhs = xor::<F, L>(lhs, rhs);
+        }
+        permute::<F, L>(&mut state);
+    }
+
+    state.truncate(256);
+    state
+}
+
+/// Returns bits of bytes of an `u256` in big-endian from its little-endian
+/// `bits`, which is also the inverse.
+pub fn reverse_bytes<T: Clone>(bits: &[T]) -> Vec<T> {
+    assert_eq!(bits.len(), 256);
+    bits.chunks(8).rev().flat_map(|byte| byte.iter().cloned()).collect()
+}
+
+/// Returns little-endian bits of `value` as 256 constant bits.
+pub fn fe_to_bits<F: PrimeField<Repr = [u8; 0x20]>, L>(value: &F) -> Vec<KeccakBit<L>> {
+    KeccakBit::from_bytes(value.to_repr().as_ref())
+}
+
+#[cfg(all(test, feature = "loader_evm"))]
+mod test {
+    use crate::{
+        halo2_curves::bn256::Fr,
+        util::{
+            arithmetic::Field,
+            hash::{keccak256, Digest, Keccak256, KeccakBit},
+        },
+    };
+
+    fn to_bytes(bits: &[KeccakBit<Fr>]) -> Vec<u8> {
+        bits.chunks(8)
+            .map(|byte| {
+                byte.iter().enumerate().fold(0, |acc, (idx, bit)| {
+                    let bit = match bit {
+                        KeccakBit::Constant(bit) => *bit,
+                        KeccakBit::Loaded(bit) => {
+                            assert!(*bit == Fr::zero() || *bit == Fr::one());
+                            *bit == Fr::one()
+                        }
+                    };
+                    acc | (bit as u8) << idx
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_keccak256() {
+        // Lengths around rate of 136 bytes
+        for len in [0, 1, 32, 64, 135, 136, 137, 272, 300] {
+            let message = (0..len).map(|idx| (idx * 7 + 3) as u8).collect::<Vec<_>>();
+            let expected = Keccak256::digest(&message).to_vec();
+
+            // Constant bits are folded without loading
+            let constant = keccak256::<Fr, Fr>(&KeccakBit::from_bytes(&message));
+            assert!(constant.iter().all(|bit| matches!(bit, KeccakBit::Constant(_))));
+            assert_eq!(to_bytes(&constant), expected, "length {len}");
+
+            let loaded = KeccakBit::<Fr>::from_bytes(&message)
+                .into_iter()
+                .map(|bit| match bit {
+                    KeccakBit::Constant(bit) => KeccakBit::Loaded(Fr::from(bit as u64)),
+                    bit => bit,
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(to_bytes(&keccak256::<Fr, Fr>(&loaded)), expected, "length {len}");
+        }
+    }
+}
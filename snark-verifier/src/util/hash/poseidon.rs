@@ -5,6 +5,70 @@ use crate::{
 };
 use std::{iter, marker::PhantomData, mem};
 
+/// Constants of Poseidon permutation, which is the only thing [`Poseidon`]
+/// needs from a parameter set, so the same permutation code runs with native
+/// field elements, in EVM and in circuit.
+///
+/// It's implemented for [`Spec`] of the `poseidon` crate, which is also the
+/// source of constants of the Poseidon chip of `halo2-base`, so hashes
+/// computed by [`Poseidon`] with [`Spec`] agree with the ones in circuit
+/// built by `halo2-base`.
+pub trait PoseidonPermutation<F: FieldExt, const T: usize, const RATE: usize> {
+    /// Returns number of full rounds.
+    fn r_f(&self) -> usize;
+
+    /// Returns round constants of the first half of full rounds.
+    fn start_constants(&self) -> &[[F; T]];
+
+    /// Returns round constants of partial rounds.
+    fn partial_constants(&self) -> &[F];
+
+    /// Returns round constants of the second half of full rounds.
+    fn end_constants(&self) -> &[[F; T]];
+
+    /// Returns MDS matrix.
+    fn mds(&self) -> [[F; T]; T];
+
+    /// Returns MDS matrix applied right before partial rounds, which is
+    /// factorized out of the following [`PoseidonPermutation::sparse_matrices`].
+    fn pre_sparse_mds(&self) -> [[F; T]; T];
+
+    /// Returns sparse MDS matrices of partial rounds.
+    fn sparse_matrices(&self) -> &[SparseMDSMatrix<F, T, RATE>];
+}
+
+impl<F: FieldExt, const T: usize, const RATE: usize> PoseidonPermutation<F, T, RATE>
+    for Spec<F, T, RATE>
+{
+    fn r_f(&self) -> usize {
+        Spec::r_f(self)
+    }
+
+    fn start_constants(&self) -> &[[F; T]] {
+        self.constants().start()
+    }
+
+    fn partial_constants(&self) -> &[F] {
+        self.constants().partial()
+    }
+
+    fn end_constants(&self) -> &[[F; T]] {
+        self.constants().end()
+    }
+
+    fn mds(&self) -> [[F; T]; T] {
+        self.mds_matrices().mds().rows()
+    }
+
+    fn pre_sparse_mds(&self) -> [[F; T]; T] {
+        self.mds_matrices().pre_sparse_mds().rows()
+    }
+
+    fn sparse_matrices(&self) -> &[SparseMDSMatrix<F, T, RATE>] {
+        self.mds_matrices().sparse_matrices()
+    }
+}
+
 #[derive(Clone, Debug)]
 struct State<F: FieldExt, L, const T: usize, const RATE: usize> {
     inner: [L; T],
@@ -95,40 +159,77 @@ impl<F: FieldExt, L: LoadedScalar<F>, const T: usize, const RATE: usize> State<F
     }
 }
 
-/// Poseidon hasher with configurable `RATE`.
-#[derive(Debug)]
-pub struct Poseidon<F: FieldExt, L, const T: usize, const RATE: usize> {
-    spec: Spec<F, T, RATE>,
+/// How [`Poseidon::squeeze`] separates squeezes of different phases.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SqueezeDomain {
+    /// Capacity element is only initialized to `2^64`, which reproduces
+    /// squeezing of already deployed circuits.
+    #[default]
+    Legacy,
+    /// Before each squeeze, `phase * 2^64 + len` is added to the capacity
+    /// element, where `phase` is the number of squeezes so far and `len` the
+    /// number of buffered elements, as the Poseidon sponge of newer
+    /// `halo2-base` does, so squeezes of different phases or lengths never
+    /// collide even if their inputs pad to the same chunks.
+    Tagged,
+}
+
+/// Poseidon hasher with configurable `RATE`, whose constants are given by
+/// [`PoseidonPermutation`] and default to [`Spec`].
+#[derive(Clone, Debug)]
+pub struct Poseidon<F: FieldExt, L, const T: usize, const RATE: usize, P = Spec<F, T, RATE>> {
+    spec: P,
     default_state: State<F, L, T, RATE>,
     state: State<F, L, T, RATE>,
     buf: Vec<L>,
+    squeeze_domain: SqueezeDomain,
+    num_squeezed: usize,
 }
 
 impl<F: FieldExt, L: LoadedScalar<F>, const T: usize, const RATE: usize> Poseidon<F, L, T, RATE> {
     /// Initialize a poseidon hasher.
     /// Generates a new spec with specific number of full and partial rounds. `SECURE_MDS` is usually 0, but may need to be specified because insecure matrices may sometimes be generated
     pub fn new(loader: &L::Loader, r_f: usize, r_p: usize) -> Self {
+        Self::from_spec(loader, Spec::new(r_f, r_p))
+    }
+}
+
+impl<F, L, const T: usize, const RATE: usize, P> Poseidon<F, L, T, RATE, P>
+where
+    F: FieldExt,
+    L: LoadedScalar<F>,
+    P: PoseidonPermutation<F, T, RATE>,
+{
+    /// Initialize a poseidon hasher from an existing spec.
+    pub fn from_spec(loader: &L::Loader, spec: P) -> Self {
         let default_state =
             State::new(poseidon::State::default().words().map(|state| loader.load_const(&state)));
         Self {
-            spec: Spec::new(r_f, r_p),
+            spec,
             state: default_state.clone(),
             default_state,
             buf: Vec::new(),
+            squeeze_domain: SqueezeDomain::default(),
+            num_squeezed: 0,
         }
     }
 
-    /// Initialize a poseidon hasher from an existing spec.
-    pub fn from_spec(loader: &L::Loader, spec: Spec<F, T, RATE>) -> Self {
-        let default_state =
-            State::new(poseidon::State::default().words().map(|state| loader.load_const(&state)));
-        Self { spec, state: default_state.clone(), default_state, buf: Vec::new() }
+    /// Set [`SqueezeDomain`], which defaults to [`SqueezeDomain::Legacy`].
+    pub fn with_squeeze_domain(mut self, squeeze_domain: SqueezeDomain) -> Self {
+        self.squeeze_domain = squeeze_domain;
+        self
+    }
+
+    /// Returns the spec providing constants of permutation.
+    pub fn spec(&self) -> &P {
+        &self.spec
     }
 
     /// Reset state to default and clear the buffer.
     pub fn clear(&mut self) {
         self.state = self.default_state.clone();
         self.buf.clear();
+        self.num_squeezed = 0;
     }
 
     /// Store given `elements` into buffer.
@@ -142,6 +243,12 @@ impl<F: FieldExt, L: LoadedScalar<F>, const T: usize, const RATE: usize> Poseido
         let buf = mem::take(&mut self.buf);
         let exact = buf.len() % RATE == 0;
 
+        if self.squeeze_domain == SqueezeDomain::Tagged {
+            let tag = F::from_u128(((self.num_squeezed as u128) << 64) + buf.len() as u128);
+            self.state.inner[0] = self.state.loader().sum_with_const(&[&self.state.inner[0]], tag);
+        }
+        self.num_squeezed += 1;
+
         for chunk in buf.chunks(RATE) {
             self.permutation(chunk);
         }
@@ -152,14 +259,19 @@ impl<F: FieldExt, L: LoadedScalar<F>, const T: usize, const RATE: usize> Poseido
         self.state.inner[1].clone()
     }
 
+    /// Returns number of permutations the next [`Poseidon::squeeze`] performs.
+    pub fn num_pending_permutations(&self) -> usize {
+        self.buf.len() / RATE + 1
+    }
+
     fn permutation(&mut self, inputs: &[L]) {
         let r_f = self.spec.r_f() / 2;
-        let mds = self.spec.mds_matrices().mds().rows();
-        let pre_sparse_mds = self.spec.mds_matrices().pre_sparse_mds().rows();
-        let sparse_matrices = self.spec.mds_matrices().sparse_matrices();
+        let mds = self.spec.mds();
+        let pre_sparse_mds = self.spec.pre_sparse_mds();
+        let sparse_matrices = self.spec.sparse_matrices();
 
         // First half of the full rounds
-        let constants = self.spec.constants().start();
+        let constants = self.spec.start_constants();
         self.state.absorb_with_pre_constants(inputs, &constants[0]);
         for constants in constants.iter().skip(1).take(r_f - 1) {
             self.state.sbox_full(constants);
@@ -169,14 +281,14 @@ impl<F: FieldExt, L: LoadedScalar<F>, const T: usize, const RATE: usize> Poseido
         self.state.apply_mds(&pre_sparse_mds);
 
         // Partial rounds
-        let constants = self.spec.constants().partial();
+        let constants = self.spec.partial_constants();
         for (constant, sparse_mds) in constants.iter().zip(sparse_matrices.iter()) {
             self.state.sbox_part(constant);
             self.state.apply_sparse_mds(sparse_mds);
         }
 
         // Second half of the full rounds
-        let constants = self.spec.constants().end();
+        let constants = self.spec.end_constants();
         for constants in constants.iter() {
             self.state.sbox_full(constants);
             self.state.apply_mds(&mds);
@@ -185,3 +297,122 @@ impl<F: FieldExt, L: LoadedScalar<F>, const T: usize, const RATE: usize> Poseido
         self.state.apply_mds(&mds);
     }
 }
+
+#[cfg(all(test, feature = "loader_halo2"))]
+mod test {
+    use crate::{
+        halo2_curves::bn256::{Fr, G1Affine},
+        loader::native::NativeLoader,
+        poseidon::{self, SparseMDSMatrix, Spec},
+        system::halo2::transcript::halo2::PoseidonTranscript,
+        util::{
+            arithmetic::Field,
+            hash::{Poseidon, PoseidonPermutation, SqueezeDomain},
+            transcript::Transcript,
+        },
+    };
+    use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+
+    #[test]
+    fn test_poseidon_cross_check() {
+        // Permutation delegating to `Spec`, to check constants are all taken from
+        // `PoseidonPermutation`.
+        struct Delegate(Spec<Fr, 5, 4>);
+
+        impl PoseidonPermutation<Fr, 5, 4> for Delegate {
+            fn r_f(&self) -> usize {
+                PoseidonPermutation::r_f(&self.0)
+            }
+
+            fn start_constants(&self) -> &[[Fr; 5]] {
+                self.0.start_constants()
+            }
+
+            fn partial_constants(&self) -> &[Fr] {
+                self.0.partial_constants()
+            }
+
+            fn end_constants(&self) -> &[[Fr; 5]] {
+                self.0.end_constants()
+            }
+
+            fn mds(&self) -> [[Fr; 5]; 5] {
+                PoseidonPermutation::mds(&self.0)
+            }
+
+            fn pre_sparse_mds(&self) -> [[Fr; 5]; 5] {
+                self.0.pre_sparse_mds()
+            }
+
+            fn sparse_matrices(&self) -> &[SparseMDSMatrix<Fr, 5, 4>] {
+                PoseidonPermutation::sparse_matrices(&self.0)
+            }
+        }
+
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        let mut reference = poseidon::Poseidon::<Fr, 5, 4>::new(8, 60);
+        let mut hasher = Poseidon::<Fr, Fr, 5, 4>::new(&NativeLoader, 8, 60);
+        let mut delegated =
+            Poseidon::<Fr, Fr, 5, 4, _>::from_spec(&NativeLoader, Delegate(Spec::new(8, 60)));
+        let mut transcript = PoseidonTranscript::<G1Affine, NativeLoader, _, 5, 4, 8, 60>::new(());
+        // Lengths around multiples of `RATE` cover both padding cases.
+        for num_input in 0..10 {
+            let inputs = (0..num_input).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+            reference.update(&inputs);
+            hasher.update(&inputs);
+            delegated.update(&inputs);
+            for input in inputs.iter() {
+                transcript.common_scalar(input).unwrap();
+            }
+
+            let expected = reference.squeeze();
+            assert_eq!(hasher.squeeze(), expected);
+            assert_eq!(delegated.squeeze(), expected);
+            assert_eq!(transcript.squeeze_challenge(), expected);
+        }
+    }
+
+    #[test]
+    fn test_poseidon_squeeze_domain() {
+        let hasher = |squeeze_domain| {
+            Poseidon::<Fr, Fr, 5, 4>::new(&NativeLoader, 8, 60).with_squeeze_domain(squeeze_domain)
+        };
+        let transcript = |squeeze_domain| {
+            PoseidonTranscript::<G1Affine, NativeLoader, _, 5, 4, 8, 60>::new(())
+                .with_squeeze_domain(squeeze_domain)
+        };
+        let mut legacy = hasher(SqueezeDomain::Legacy);
+        let mut default = Poseidon::<Fr, Fr, 5, 4>::new(&NativeLoader, 8, 60);
+        let mut tagged = hasher(SqueezeDomain::Tagged);
+        let mut tagged_transcript = transcript(SqueezeDomain::Tagged);
+        for num_input in 0..6 {
+            let inputs = (0..num_input).map(|idx| Fr::from(idx as u64 + 1)).collect::<Vec<_>>();
+            legacy.update(&inputs);
+            default.update(&inputs);
+            tagged.update(&inputs);
+            for input in inputs.iter() {
+                tagged_transcript.common_scalar(input).unwrap();
+            }
+
+            let expected = legacy.squeeze();
+            assert_eq!(default.squeeze(), expected);
+            let challenge = tagged.squeeze();
+            assert_ne!(challenge, expected);
+            assert_eq!(tagged_transcript.squeeze_challenge(), challenge);
+        }
+
+        // Same inputs absorbed in different phases are separated.
+        let mut lhs = hasher(SqueezeDomain::Tagged);
+        let mut rhs = hasher(SqueezeDomain::Tagged);
+        lhs.update(&[Fr::from(0)]);
+        lhs.squeeze();
+        rhs.squeeze();
+        rhs.update(&[Fr::from(0)]);
+        assert_ne!(lhs.squeeze(), rhs.squeeze());
+
+        // Clearing resets the phase.
+        lhs.clear();
+        let mut fresh = hasher(SqueezeDomain::Tagged);
+        assert_eq!(lhs.squeeze(), fresh.squeeze());
+    }
+}
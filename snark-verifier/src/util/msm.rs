@@ -1,7 +1,7 @@
 use crate::{
     loader::{LoadedEcPoint, Loader},
     util::{
-        arithmetic::{CurveAffine, Group, PrimeField},
+        arithmetic::{Curve, CurveAffine, Group, PrimeCurveAffine, PrimeField},
         Itertools,
     },
 };
@@ -304,3 +304,44 @@ pub fn multi_scalar_multiplication<C: CurveAffine>(scalars: &[C::Scalar], bases:
         result
     }
 }
+
+/// Multiples of a fixed base for every 4-bit window of scalar, so
+/// multiplication by the base takes one addition per window and no doubling.
+#[derive(Clone, Debug)]
+pub struct FixedBaseTable<C: CurveAffine> {
+    windows: Vec<Vec<C>>,
+}
+
+impl<C: CurveAffine> FixedBaseTable<C> {
+    /// Precompute [`FixedBaseTable`] of `base`.
+    pub fn new(base: C) -> Self {
+        let num_window = 2 * C::Scalar::Repr::default().as_ref().len();
+        let mut window_base = base.to_curve();
+        let windows = (0..num_window)
+            .map(|_| {
+                let multiples = iter::successors(Some(C::Curve::identity()), |multiple| {
+                    Some(*multiple + window_base)
+                })
+                .take(16)
+                .collect_vec();
+                window_base = multiples[15] + window_base;
+                let mut window = vec![C::identity(); 16];
+                C::Curve::batch_normalize(&multiples, &mut window);
+                window
+            })
+            .collect();
+        Self { windows }
+    }
+
+    /// Returns `scalar` times the base, where `scalar` is assumed to have
+    /// little-endian representation.
+    pub fn mul(&self, scalar: &C::Scalar) -> C::Curve {
+        scalar
+            .to_repr()
+            .as_ref()
+            .iter()
+            .flat_map(|byte| [byte & 0xf, byte >> 4])
+            .zip(self.windows.iter())
+            .fold(C::Curve::identity(), |acc, (digit, window)| acc + window[digit as usize])
+    }
+}
@@ -1,7 +1,10 @@
+#[cfg(feature = "protocol_spec")]
+pub mod spec;
+
 use crate::{
     loader::{LoadedScalar, Loader},
     util::{
-        arithmetic::{CurveAffine, Domain, Field, Fraction, Rotation},
+        arithmetic::{CurveAffine, Domain, Field, Fraction, GroupEncoding, PrimeField, Rotation},
         Itertools,
     },
     Protocol,
@@ -11,7 +14,7 @@ use num_traits::One;
 use serde::{Deserialize, Serialize};
 use std::{
     cmp::max,
-    collections::{BTreeMap, BTreeSet},
+    collections::{BTreeMap, BTreeSet, HashMap},
     fmt::Debug,
     iter::{self, Sum},
     ops::{Add, Mul, Neg, Sub},
@@ -44,11 +47,75 @@ where
             instance_committing_key: self.instance_committing_key.clone(),
             linearization: self.linearization,
             accumulator_indices: self.accumulator_indices.clone(),
+            rotation_sets: self.rotation_sets.clone(),
         }
     }
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+/// Cache of [`Protocol`]s loaded by a loader instance, so verifying many
+/// proofs of the same protocol with it (e.g. generating a verifier of several
+/// proofs on [`EvmLoader`], or verifying natively repeatedly) loads constants
+/// of each protocol only once.
+///
+/// Protocols are identified by preprocessed commitments and
+/// `transcript_initial_state`, which are the only loaded parts, and the latter
+/// binds the rest when compiled from a halo2 verifying key.
+///
+/// [`EvmLoader`]: crate::loader::evm::EvmLoader
+#[derive(Debug)]
+pub struct LoadedProtocolCache<C, L>
+where
+    C: CurveAffine,
+    L: Loader<C>,
+{
+    loader: L,
+    loaded: HashMap<Vec<u8>, Protocol<C, L>>,
+}
+
+impl<C, L> LoadedProtocolCache<C, L>
+where
+    C: CurveAffine,
+    L: Loader<C>,
+{
+    /// Initialize an empty [`LoadedProtocolCache`] of `loader`.
+    pub fn new(loader: &L) -> Self {
+        Self { loader: loader.clone(), loaded: HashMap::new() }
+    }
+
+    /// Returns the loader protocols are loaded by.
+    pub fn loader(&self) -> &L {
+        &self.loader
+    }
+
+    /// Returns `protocol` loaded by [`Protocol::loaded`], which is only called
+    /// the first time `protocol` is seen.
+    pub fn get_or_load(&mut self, protocol: &Protocol<C>) -> &Protocol<C, L> {
+        let key = protocol
+            .preprocessed
+            .iter()
+            .flat_map(|ec_point| ec_point.to_bytes().as_ref().to_vec())
+            .chain(
+                protocol
+                    .transcript_initial_state
+                    .iter()
+                    .flat_map(|scalar| scalar.to_repr().as_ref().to_vec()),
+            )
+            .collect();
+        self.loaded.entry(key).or_insert_with(|| protocol.loaded(&self.loader))
+    }
+
+    /// Returns number of loaded protocols.
+    pub fn len(&self) -> usize {
+        self.loaded.len()
+    }
+
+    /// Returns `true` if no protocol is loaded yet.
+    pub fn is_empty(&self) -> bool {
+        self.loaded.is_empty()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CommonPolynomial {
     Identity,
     Lagrange(i32),
@@ -76,11 +143,27 @@ where
         domain: &Domain<C::Scalar>,
         langranges: impl IntoIterator<Item = i32>,
         z: &L::LoadedScalar,
+    ) -> Self {
+        let omegas = langranges
+            .into_iter()
+            .sorted()
+            .dedup()
+            .map(|i| (i, domain.rotate_scalar(C::Scalar::one(), Rotation(i))))
+            .collect_vec();
+        Self::new_with_omegas(domain, omegas, z)
+    }
+
+    /// Same as [`CommonPolynomialEvaluation::new`] but with `omega^i` of each
+    /// lagrange `i` given, which only depend on `domain` so could be reused
+    /// across proofs.
+    pub fn new_with_omegas(
+        domain: &Domain<C::Scalar>,
+        omegas: impl IntoIterator<Item = (i32, C::Scalar)>,
+        z: &L::LoadedScalar,
     ) -> Self {
         let loader = z.loader();
 
         let zn = z.pow_const(domain.n as u64);
-        let langranges = langranges.into_iter().sorted().dedup().collect_vec();
 
         let one = loader.load_one();
         let zn_minus_one = zn.clone() - &one;
@@ -88,22 +171,15 @@ where
 
         let n_inv = loader.load_const(&domain.n_inv);
         let numer = zn_minus_one.clone() * &n_inv;
-        let omegas = langranges
-            .iter()
-            .map(|&i| loader.load_const(&domain.rotate_scalar(C::Scalar::one(), Rotation(i))))
-            .collect_vec();
-        let lagrange_evals = omegas
-            .iter()
-            .map(|omega| Fraction::new(numer.clone() * omega, z.clone() - omega))
-            .collect_vec();
+        let lagrange = omegas
+            .into_iter()
+            .map(|(i, omega)| {
+                let omega = loader.load_const(&omega);
+                (i, Fraction::new(numer.clone() * &omega, z.clone() - &omega))
+            })
+            .collect();
 
-        Self {
-            zn,
-            zn_minus_one,
-            zn_minus_one_inv,
-            identity: z.clone(),
-            lagrange: langranges.into_iter().zip(lagrange_evals).collect(),
-        }
+        Self { zn, zn_minus_one, zn_minus_one_inv, identity: z.clone(), lagrange }
     }
 
     pub fn zn(&self) -> &L::LoadedScalar {
@@ -166,7 +242,62 @@ impl Query {
     }
 }
 
+/// Queries sharing the identical set of rotations, which is precomputed from
+/// [`Protocol::queries`] so multi-open schemes don't need to rebuild them for
+/// every proof of the same circuit.
 #[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RotationSet {
+    pub rotations: Vec<Rotation>,
+    pub polys: Vec<usize>,
+    /// Index of query of each poly at each rotation.
+    pub query_indices: Vec<Vec<usize>>,
+}
+
+/// Group `queries` into [`RotationSet`]s, ordered by first appearance of
+/// polys, and keeping only the first query of duplicated ones.
+pub fn rotation_sets(queries: &[Query]) -> Vec<RotationSet> {
+    let poly_rotations = queries.iter().enumerate().fold(
+        Vec::<(usize, Vec<Rotation>, Vec<usize>)>::new(),
+        |mut poly_rotations, (idx, query)| {
+            if let Some(pos) = poly_rotations.iter().position(|(poly, _, _)| *poly == query.poly) {
+                let (_, rotations, indices) = &mut poly_rotations[pos];
+                if !rotations.contains(&query.rotation) {
+                    rotations.push(query.rotation);
+                    indices.push(idx);
+                }
+            } else {
+                poly_rotations.push((query.poly, vec![query.rotation], vec![idx]));
+            }
+            poly_rotations
+        },
+    );
+
+    let mut sets = Vec::<RotationSet>::new();
+    for (poly, rotations, indices) in poly_rotations {
+        if let Some(pos) = sets.iter().position(|set| {
+            BTreeSet::from_iter(set.rotations.iter()) == BTreeSet::from_iter(rotations.iter())
+        }) {
+            let set = &mut sets[pos];
+            if !set.polys.contains(&poly) {
+                set.polys.push(poly);
+                set.query_indices.push(
+                    set.rotations
+                        .iter()
+                        .map(|lhs| {
+                            let idx = rotations.iter().position(|rhs| lhs == rhs).unwrap();
+                            indices[idx]
+                        })
+                        .collect(),
+                );
+            }
+        } else {
+            sets.push(RotationSet { rotations, polys: vec![poly], query_indices: vec![indices] });
+        }
+    }
+    sets
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Expression<F> {
     Constant(F),
     CommonPolynomial(CommonPolynomial),
@@ -278,6 +409,229 @@ impl<F: Clone> Expression<F> {
     }
 }
 
+impl<F: Field> Expression<F> {
+    /// Returns a simplified expression with constants folded and trivially
+    /// zero terms pruned, e.g. constraints of selectors that are never enabled
+    /// (which are compressed into constant zero by `halo2_proofs`) or lookups
+    /// with such inputs.
+    ///
+    /// Leading zeros of [`Expression::DistributePowers`] are dropped since
+    /// they don't affect the result, while others are kept as constant zero to
+    /// preserve the powers distributed to later expressions.
+    ///
+    /// Sums are flattened into terms with coefficients, where constants are
+    /// folded into a single one and identical terms are merged, and products
+    /// with a constant are turned into [`Expression::Scaled`].
+    pub fn simplified(&self) -> Self {
+        let is_zero =
+            |expr: &Self| matches!(expr, Expression::Constant(scalar) if *scalar == F::zero());
+        let is_one =
+            |expr: &Self| matches!(expr, Expression::Constant(scalar) if *scalar == F::one());
+        match self {
+            Expression::Constant(_)
+            | Expression::CommonPolynomial(_)
+            | Expression::Polynomial(_)
+            | Expression::Challenge(_) => self.clone(),
+            Expression::Negated(a) => match a.simplified() {
+                Expression::Constant(scalar) => Expression::Constant(-scalar),
+                Expression::Negated(a) => *a,
+                a => -a,
+            },
+            Expression::Sum(a, b) => {
+                let mut constant = F::zero();
+                let mut terms = Vec::new();
+                for expr in [a.simplified(), b.simplified()] {
+                    expr.collect_terms(F::one(), &mut constant, &mut terms);
+                }
+                terms
+                    .into_iter()
+                    .filter(|(_, coeff)| *coeff != F::zero())
+                    .map(|(term, coeff)| term.with_coeff(coeff))
+                    .chain((constant != F::zero()).then_some(Expression::Constant(constant)))
+                    .reduce(|acc, term| acc + term)
+                    .unwrap_or(Expression::Constant(F::zero()))
+            }
+            Expression::Product(a, b) => match (a.simplified(), b.simplified()) {
+                (Expression::Constant(a), Expression::Constant(b)) => Expression::Constant(a * b),
+                (a, b) if is_zero(&a) || is_zero(&b) => Expression::Constant(F::zero()),
+                (a, b) if is_one(&a) => b,
+                (a, b) if is_one(&b) => a,
+                (Expression::Constant(scalar), expr) | (expr, Expression::Constant(scalar)) => {
+                    match expr {
+                        Expression::Scaled(a, inner) => *a * (inner * scalar),
+                        expr => expr * scalar,
+                    }
+                }
+                (a, b) => a * b,
+            },
+            Expression::Scaled(a, scalar) => match a.simplified() {
+                Expression::Constant(a) => Expression::Constant(a * scalar),
+                _ if *scalar == F::zero() => Expression::Constant(F::zero()),
+                a if *scalar == F::one() => a,
+                Expression::Scaled(a, inner) => *a * (inner * scalar),
+                a => a * scalar,
+            },
+            Expression::DistributePowers(exprs, scalar) => {
+                let exprs = exprs
+                    .iter()
+                    .map(Self::simplified)
+                    .skip_while(|expr| is_zero(expr))
+                    .collect::<Vec<_>>();
+                match exprs.len() {
+                    0 => Expression::Constant(F::zero()),
+                    1 => exprs.into_iter().next().unwrap(),
+                    _ => Expression::DistributePowers(exprs, scalar.simplified().into()),
+                }
+            }
+        }
+    }
+
+    /// Returns an equivalent expression with common factors of sums pulled out
+    /// in a multivariate Horner scheme, e.g. `a * b * c + a * b * d + a * e`
+    /// into `a * (b * (c + d) + e)`, which saves multiplications done by
+    /// [`Loader`](crate::loader::Loader) when evaluating it.
+    ///
+    /// Factors are chosen greedily by the number of terms sharing them, so
+    /// it's expected to be applied after [`Expression::simplified`] for
+    /// identical terms to be merged first.
+    pub fn factored(&self) -> Self {
+        match self {
+            Expression::Constant(_)
+            | Expression::CommonPolynomial(_)
+            | Expression::Polynomial(_)
+            | Expression::Challenge(_) => self.clone(),
+            Expression::Negated(a) => -a.factored(),
+            Expression::Sum(_, _) => {
+                let mut constant = F::zero();
+                let mut terms = Vec::new();
+                self.clone().collect_terms(F::one(), &mut constant, &mut terms);
+                let monomials = terms
+                    .into_iter()
+                    .filter(|(_, coeff)| *coeff != F::zero())
+                    .map(|(term, coeff)| (term.factors(), coeff))
+                    .collect();
+                let factored = Self::horner(monomials);
+                if constant == F::zero() {
+                    factored
+                } else {
+                    factored + Expression::Constant(constant)
+                }
+            }
+            Expression::Product(_, _) => {
+                self.clone().factors().into_iter().reduce(|acc, factor| acc * factor).unwrap()
+            }
+            Expression::Scaled(a, scalar) => a.factored() * *scalar,
+            Expression::DistributePowers(exprs, scalar) => Expression::DistributePowers(
+                exprs.iter().map(Self::factored).collect(),
+                scalar.factored().into(),
+            ),
+        }
+    }
+
+    /// Returns factors of product chain `self`, each of which is factored.
+    fn factors(self) -> Vec<Self> {
+        match self {
+            Expression::Product(a, b) => {
+                let mut factors = a.factors();
+                factors.extend(b.factors());
+                factors
+            }
+            factor => vec![factor.factored()],
+        }
+    }
+
+    /// Returns sum of `monomials` in a multivariate Horner scheme by pulling
+    /// out the factor shared by most monomials recursively.
+    fn horner(monomials: Vec<(Vec<Self>, F)>) -> Self {
+        let mut counts = Vec::<(&Self, usize)>::new();
+        for (factors, _) in monomials.iter() {
+            for (idx, factor) in factors.iter().enumerate() {
+                if factors[..idx].contains(factor) {
+                    continue;
+                }
+                match counts.iter_mut().find(|(lhs, _)| *lhs == factor) {
+                    Some((_, count)) => *count += 1,
+                    None => counts.push((factor, 1)),
+                }
+            }
+        }
+        let common = counts
+            .into_iter()
+            .rev()
+            .max_by_key(|(_, count)| *count)
+            .filter(|(_, count)| *count > 1)
+            .map(|(factor, _)| factor.clone());
+
+        match common {
+            Some(common) => {
+                let (mut with, without): (Vec<_>, Vec<_>) =
+                    monomials.into_iter().partition(|(factors, _)| factors.contains(&common));
+                for (factors, _) in with.iter_mut() {
+                    let idx = factors.iter().position(|factor| *factor == common).unwrap();
+                    factors.remove(idx);
+                }
+                let factored = match Self::horner(with) {
+                    Expression::Constant(coeff) => common.with_coeff(coeff),
+                    rest => common * rest,
+                };
+                if without.is_empty() {
+                    factored
+                } else {
+                    factored + Self::horner(without)
+                }
+            }
+            None => {
+                let mut constant = F::zero();
+                let terms = monomials
+                    .into_iter()
+                    .filter_map(|(factors, coeff)| {
+                        match factors.into_iter().reduce(|acc, factor| acc * factor) {
+                            Some(product) => Some(product.with_coeff(coeff)),
+                            None => {
+                                constant += coeff;
+                                None
+                            }
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                terms
+                    .into_iter()
+                    .chain((constant != F::zero()).then_some(Expression::Constant(constant)))
+                    .reduce(|acc, term| acc + term)
+                    .unwrap_or(Expression::Constant(F::zero()))
+            }
+        }
+    }
+
+    /// Returns `self` multiplied by `coeff`, with `1` and `-1` special-cased.
+    fn with_coeff(self, coeff: F) -> Self {
+        match coeff {
+            coeff if coeff == F::one() => self,
+            coeff if coeff == -F::one() => -self,
+            coeff => self * coeff,
+        }
+    }
+
+    /// Collect terms of simplified `self` multiplied by `coeff` into `terms`,
+    /// where constants are accumulated into `constant` and identical terms
+    /// share the same entry.
+    fn collect_terms(self, coeff: F, constant: &mut F, terms: &mut Vec<(Self, F)>) {
+        match self {
+            Expression::Constant(scalar) => *constant += coeff * scalar,
+            Expression::Negated(a) => a.collect_terms(-coeff, constant, terms),
+            Expression::Scaled(a, scalar) => a.collect_terms(coeff * scalar, constant, terms),
+            Expression::Sum(a, b) => {
+                a.collect_terms(coeff, constant, terms);
+                b.collect_terms(coeff, constant, terms);
+            }
+            term => match terms.iter_mut().find(|(lhs, _)| *lhs == term) {
+                Some((_, lhs_coeff)) => *lhs_coeff += coeff,
+                None => terms.push((term, coeff)),
+            },
+        }
+    }
+}
+
 impl<F: Clone> From<Query> for Expression<F> {
     fn from(query: Query) -> Self {
         Self::Polynomial(query)
@@ -361,7 +715,7 @@ fn merge_left_right<T: Ord>(a: Option<BTreeSet<T>>, b: Option<BTreeSet<T>>) -> O
     }
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LinearizationStrategy {
     /// Older linearization strategy of GWC19, which has linearization
     /// polynomial that doesn't evaluate to 0, and requires prover to send extra
@@ -378,3 +732,225 @@ pub struct InstanceCommittingKey<C> {
     pub bases: Vec<C>,
     pub constant: Option<C>,
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        halo2_curves::bn256::Fr,
+        util::protocol::{CommonPolynomial, Expression, Query},
+    };
+
+    #[test]
+    fn test_expression_simplified() {
+        let zero = Expression::Constant(Fr::zero());
+        let one = Expression::Constant(Fr::one());
+        let a = Expression::<Fr>::Polynomial(Query::new(0, 0));
+        let b = Expression::<Fr>::Polynomial(Query::new(1, 0));
+
+        // Lookup with inputs of a selector never enabled
+        let input = Expression::DistributePowers(
+            vec![&zero * &a, &zero * &b],
+            Expression::Challenge(0).into(),
+        );
+        assert!(matches!(input.simplified(), Expression::Constant(scalar) if scalar == Fr::zero()));
+
+        let expr = (&one * &a + &zero) * Fr::one() - -(&b * Fr::from(2));
+        assert_eq!(expr.simplified().used_query(), expr.used_query());
+        assert!(matches!(
+            expr.simplified(),
+            Expression::Sum(lhs, rhs) if matches!(*lhs, Expression::Polynomial(_))
+                && matches!(*rhs, Expression::Scaled(_, _))
+        ));
+
+        // Leading zeros are dropped, but others are kept to preserve powers
+        let distributed = Expression::DistributePowers(
+            vec![zero.clone(), a.clone(), zero.clone(), b.clone()],
+            Expression::Challenge(0).into(),
+        );
+        assert!(matches!(
+            distributed.simplified(),
+            Expression::DistributePowers(exprs, _) if exprs.len() == 3
+        ));
+    }
+
+    #[test]
+    fn test_expression_simplified_sum() {
+        let constant = |value: u64| Expression::Constant(Fr::from(value));
+        let a = Expression::<Fr>::Polynomial(Query::new(0, 0));
+        let b = Expression::<Fr>::Polynomial(Query::new(1, 0));
+        let l_0 = Expression::<Fr>::CommonPolynomial(CommonPolynomial::Lagrange(0));
+
+        let expr = &a + &b * &l_0 - &a + constant(2) + constant(3) * &a + &b * &l_0 - constant(1);
+        assert_eq!(expr.simplified(), &a * Fr::from(3) + (&b * &l_0) * Fr::from(2) + constant(1));
+
+        let expr = (&a - &b) * constant(2) + (&b - &a) * constant(2);
+        assert_eq!(expr.simplified(), constant(0));
+    }
+
+    #[test]
+    fn test_expression_factored() {
+        let [a, b, c, d, e] =
+            [0, 1, 2, 3, 4].map(|poly| Expression::<Fr>::Polynomial(Query::new(poly, 0)));
+        let num_product = |expr: &Expression<Fr>| {
+            expr.evaluate(
+                &|_| 0,
+                &|_| 0,
+                &|_| 0,
+                &|_| 0,
+                &|a| a,
+                &|a, b| a + b,
+                &|a, b| a + b + 1,
+                &|a, _| a + 1,
+            )
+        };
+
+        let expr = &a * &b * &c + &a * &b * &d + &a * &e - &a * &b * Fr::from(2);
+        let factored = expr.simplified().factored();
+        assert_eq!(factored, &a * (&b * (&c + &d + Expression::Constant(-Fr::from(2))) + &e));
+        assert_eq!(num_product(&expr), 7);
+        assert_eq!(num_product(&factored), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "loader_halo2")]
+    fn test_loaded_protocol_cache() {
+        use crate::{
+            halo2_curves::bn256::{Bn256, G1Affine},
+            halo2_proofs::{
+                poly::{
+                    commitment::ParamsProver,
+                    kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK},
+                },
+                transcript::{Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer},
+            },
+            loader::native::NativeLoader,
+            pcs::kzg::{Bdfg21, Kzg},
+            system::halo2::test::{
+                kzg::{halo2_kzg_config, halo2_kzg_create_snark, halo2_kzg_prepare},
+                StandardPlonk,
+            },
+            util::protocol::LoadedProtocolCache,
+            verifier::{Plonk, PlonkVerifier},
+        };
+        use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+
+        type Pcs = Kzg<Bn256, Bdfg21>;
+
+        let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+            9,
+            halo2_kzg_config!(true, 1),
+            StandardPlonk::rand(ChaCha20Rng::from_seed(Default::default()))
+        );
+        let snark = halo2_kzg_create_snark!(
+            ProverSHPLONK<_>,
+            VerifierSHPLONK<_>,
+            Blake2bWrite<_, _, _>,
+            Blake2bRead<_, _, _>,
+            Challenge255<_>,
+            &params,
+            &pk,
+            &protocol,
+            &circuits
+        );
+        let svk = params.get_g()[0].into();
+        let dk = (params.g2(), params.s_g2()).into();
+
+        let mut cache = LoadedProtocolCache::<G1Affine, NativeLoader>::new(&NativeLoader);
+        assert!(cache.is_empty());
+        let first = cache.get_or_load(&snark.protocol) as *const _;
+        for _ in 0..2 {
+            let protocol = cache.get_or_load(&snark.protocol);
+            assert!(std::ptr::eq(first, protocol));
+            let mut transcript =
+                Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&snark.proof[..]);
+            let proof = Plonk::<Pcs>::read_proof(&svk, protocol, &snark.instances, &mut transcript);
+            assert!(Plonk::<Pcs>::verify(&svk, &dk, protocol, &snark.instances, &proof));
+        }
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "loader_halo2")]
+    fn test_rotation_sets() {
+        use crate::{
+            halo2_curves::bn256::{Bn256, G1Affine},
+            halo2_proofs::{
+                poly::{
+                    commitment::ParamsProver,
+                    kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK},
+                },
+                transcript::{Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer},
+            },
+            loader::native::NativeLoader,
+            pcs::kzg::{Bdfg21, Kzg, KzgSuccinctVerifyingKey},
+            system::halo2::test::{
+                kzg::{halo2_kzg_config, halo2_kzg_create_snark, halo2_kzg_prepare},
+                RotatedInstance,
+            },
+            util::protocol::rotation_sets,
+            verifier::{Plonk, PlonkVerifier},
+        };
+        use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+
+        let queries = [(0, 0), (1, 0), (0, 1), (1, 1), (2, 0), (0, 0), (3, 1), (3, 0)]
+            .map(|(poly, rotation)| Query::new(poly, rotation));
+        let sets = rotation_sets(&queries);
+        let summary = sets
+            .iter()
+            .map(|set| {
+                (
+                    set.rotations.iter().map(|rotation| rotation.0).collect::<Vec<_>>(),
+                    set.polys.clone(),
+                    set.query_indices.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        // Duplicated query is skipped, and indices follow rotations of the set.
+        assert_eq!(
+            summary,
+            vec![
+                (vec![0, 1], vec![0, 1, 3], vec![vec![0, 2], vec![1, 3], vec![7, 6]]),
+                (vec![0], vec![2], vec![vec![4]]),
+            ]
+        );
+
+        let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+            9,
+            halo2_kzg_config!(true, 2),
+            RotatedInstance::rand(ChaCha20Rng::from_seed(Default::default()))
+        );
+        let svk = KzgSuccinctVerifyingKey::new(params.get_g()[0]);
+        let snark = halo2_kzg_create_snark!(
+            ProverSHPLONK<_>,
+            VerifierSHPLONK<_>,
+            Blake2bWrite<_, _, _>,
+            Blake2bRead<_, _, _>,
+            Challenge255<_>,
+            &params,
+            &pk,
+            &protocol,
+            &circuits
+        );
+        assert!(!snark.protocol.rotation_sets.is_empty());
+
+        // Precomputed rotation sets give the same accumulator as rebuilding them.
+        type Pcs = Kzg<Bn256, Bdfg21>;
+        let succinct_verify = |protocol: &crate::Protocol<G1Affine>| {
+            let mut transcript =
+                Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&snark.proof[..]);
+            let proof = <Plonk<Pcs> as PlonkVerifier<_, NativeLoader, _>>::read_proof(
+                &svk,
+                protocol,
+                &snark.instances,
+                &mut transcript,
+            );
+            Plonk::<Pcs>::succinct_verify(&svk, protocol, &snark.instances, &proof)
+                .into_iter()
+                .map(|accumulator| (accumulator.lhs, accumulator.rhs))
+                .collect::<Vec<_>>()
+        };
+        let mut rebuilt = snark.protocol.clone();
+        rebuilt.rotation_sets = Vec::new();
+        assert_eq!(succinct_verify(&snark.protocol), succinct_verify(&rebuilt));
+    }
+}
@@ -0,0 +1,236 @@
+//! Human-readable description of [`Protocol`] for external tooling (auditors,
+//! gas estimators or verifier generators of other targets), where field
+//! elements are big-endian hex strings and expressions are tagged trees.
+use crate::{
+    util::{
+        arithmetic::{Coordinates, CurveAffine, Field, PrimeField},
+        protocol::{CommonPolynomial, Expression, LinearizationStrategy, Query},
+    },
+    Protocol,
+};
+use serde::{Deserialize, Serialize};
+
+/// Field element as big-endian hex string with `0x` prefix.
+///
+/// It assumes the field has little-endian `Repr`, as all curves in
+/// `halo2curves` do.
+fn fe_to_hex<F: PrimeField>(fe: &F) -> String {
+    let mut bytes = fe.to_repr().as_ref().to_vec();
+    bytes.reverse();
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Affine point with coordinates in big-endian hex, where identity is encoded
+/// as `(0, 0)`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EcPointSpec {
+    pub x: String,
+    pub y: String,
+}
+
+impl EcPointSpec {
+    fn new<C: CurveAffine>(ec_point: &C) -> Self {
+        let coordinates: Option<Coordinates<C>> = ec_point.coordinates().into();
+        let [x, y] = coordinates
+            .map_or([C::Base::zero(); 2], |coordinates| [*coordinates.x(), *coordinates.y()]);
+        Self { x: fe_to_hex(&x), y: fe_to_hex(&y) }
+    }
+}
+
+/// Tree of [`Expression`], tagged by `type`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExpressionSpec {
+    Constant {
+        value: String,
+    },
+    Identity,
+    Lagrange {
+        rotation: i32,
+    },
+    Polynomial {
+        poly: usize,
+        rotation: i32,
+    },
+    Challenge {
+        index: usize,
+    },
+    Negated {
+        expr: Box<ExpressionSpec>,
+    },
+    Sum {
+        lhs: Box<ExpressionSpec>,
+        rhs: Box<ExpressionSpec>,
+    },
+    Product {
+        lhs: Box<ExpressionSpec>,
+        rhs: Box<ExpressionSpec>,
+    },
+    Scaled {
+        expr: Box<ExpressionSpec>,
+        scalar: String,
+    },
+    /// `exprs[0] * base^(n-1) + exprs[1] * base^(n-2) + ... + exprs[n-1]`.
+    DistributePowers {
+        exprs: Vec<ExpressionSpec>,
+        base: Box<ExpressionSpec>,
+    },
+}
+
+impl ExpressionSpec {
+    fn new<F: PrimeField>(expression: &Expression<F>) -> Self {
+        let boxed = |expression: &Expression<F>| Box::new(Self::new(expression));
+        match expression {
+            Expression::Constant(constant) => Self::Constant { value: fe_to_hex(constant) },
+            Expression::CommonPolynomial(CommonPolynomial::Identity) => Self::Identity,
+            Expression::CommonPolynomial(CommonPolynomial::Lagrange(rotation)) => {
+                Self::Lagrange { rotation: *rotation }
+            }
+            Expression::Polynomial(Query { poly, rotation }) => {
+                Self::Polynomial { poly: *poly, rotation: rotation.0 }
+            }
+            Expression::Challenge(index) => Self::Challenge { index: *index },
+            Expression::Negated(expr) => Self::Negated { expr: boxed(expr) },
+            Expression::Sum(lhs, rhs) => Self::Sum { lhs: boxed(lhs), rhs: boxed(rhs) },
+            Expression::Product(lhs, rhs) => Self::Product { lhs: boxed(lhs), rhs: boxed(rhs) },
+            Expression::Scaled(expr, scalar) => {
+                Self::Scaled { expr: boxed(expr), scalar: fe_to_hex(scalar) }
+            }
+            Expression::DistributePowers(exprs, base) => Self::DistributePowers {
+                exprs: exprs.iter().map(Self::new).collect(),
+                base: boxed(base),
+            },
+        }
+    }
+}
+
+/// Query of polynomial at rotation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuerySpec {
+    pub poly: usize,
+    pub rotation: i32,
+}
+
+impl From<&Query> for QuerySpec {
+    fn from(query: &Query) -> Self {
+        Self { poly: query.poly, rotation: query.rotation.0 }
+    }
+}
+
+/// Evaluation domain.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DomainSpec {
+    pub k: usize,
+    pub n: usize,
+    pub gen: String,
+}
+
+/// Numerator of quotient polynomial.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotientSpec {
+    pub chunk_degree: usize,
+    pub num_chunk: usize,
+    /// Gates, permutation and lookup constraints combined by powers of a
+    /// challenge, usually as [`ExpressionSpec::DistributePowers`].
+    pub numerator: ExpressionSpec,
+}
+
+/// Committing key of instances.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstanceCommittingKeySpec {
+    pub bases: Vec<EcPointSpec>,
+    pub constant: Option<EcPointSpec>,
+}
+
+/// Typed and human-readable export of [`Protocol`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtocolSpec {
+    pub domain: DomainSpec,
+    pub preprocessed: Vec<EcPointSpec>,
+    pub num_instance: Vec<usize>,
+    /// Number of witness polynomials of each phase.
+    pub num_witness: Vec<usize>,
+    /// Number of challenges squeezed after each phase.
+    pub num_challenge: Vec<usize>,
+    /// Queries evaluated by prover, in the order of proof.
+    pub evaluations: Vec<QuerySpec>,
+    /// Queries opened by the multi-open scheme.
+    pub queries: Vec<QuerySpec>,
+    pub quotient: QuotientSpec,
+    pub transcript_initial_state: Option<String>,
+    pub instance_committing_key: Option<InstanceCommittingKeySpec>,
+    pub linearization: Option<LinearizationStrategy>,
+    pub accumulator_indices: Vec<Vec<(usize, usize)>>,
+}
+
+impl ProtocolSpec {
+    /// Export `protocol`.
+    pub fn new<C: CurveAffine>(protocol: &Protocol<C>) -> Self {
+        Self {
+            domain: DomainSpec {
+                k: protocol.domain.k,
+                n: protocol.domain.n,
+                gen: fe_to_hex(&protocol.domain.gen),
+            },
+            preprocessed: protocol.preprocessed.iter().map(EcPointSpec::new).collect(),
+            num_instance: protocol.num_instance.clone(),
+            num_witness: protocol.num_witness.clone(),
+            num_challenge: protocol.num_challenge.clone(),
+            evaluations: protocol.evaluations.iter().map(QuerySpec::from).collect(),
+            queries: protocol.queries.iter().map(QuerySpec::from).collect(),
+            quotient: QuotientSpec {
+                chunk_degree: protocol.quotient.chunk_degree,
+                num_chunk: protocol.quotient.num_chunk(),
+                numerator: ExpressionSpec::new(&protocol.quotient.numerator),
+            },
+            transcript_initial_state: protocol.transcript_initial_state.as_ref().map(fe_to_hex),
+            instance_committing_key: protocol.instance_committing_key.as_ref().map(|key| {
+                InstanceCommittingKeySpec {
+                    bases: key.bases.iter().map(EcPointSpec::new).collect(),
+                    constant: key.constant.as_ref().map(EcPointSpec::new),
+                }
+            }),
+            linearization: protocol.linearization,
+            accumulator_indices: protocol.accumulator_indices.clone(),
+        }
+    }
+
+    /// Returns pretty JSON of the spec.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+#[cfg(all(test, feature = "loader_halo2"))]
+mod test {
+    use crate::{
+        halo2_curves::bn256::{Bn256, Fr},
+        halo2_proofs::plonk::keygen_vk,
+        system::halo2::{
+            compile,
+            test::{kzg::setup, StandardPlonk},
+            Config,
+        },
+        util::protocol::spec::{ExpressionSpec, ProtocolSpec},
+    };
+    use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+
+    #[test]
+    fn test_protocol_spec() {
+        let params = setup::<Bn256>(9);
+        let circuit = StandardPlonk::<Fr>::rand(ChaCha20Rng::from_seed(Default::default()));
+        let vk = keygen_vk(&params, &circuit).unwrap();
+        let protocol = compile(&params, &vk, Config::kzg().with_num_instance(vec![4]));
+
+        let spec = ProtocolSpec::new(&protocol);
+        assert_eq!(spec.domain.k, 9);
+        assert_eq!(spec.num_witness, protocol.num_witness);
+        assert_eq!(spec.preprocessed.len(), protocol.preprocessed.len());
+        assert_eq!(spec.evaluations.len(), protocol.evaluations.len());
+        assert!(matches!(spec.quotient.numerator, ExpressionSpec::DistributePowers { .. }));
+
+        let json = spec.to_json();
+        assert!(json.contains("\"type\": \"distribute_powers\""));
+        assert_eq!(serde_json::from_str::<ProtocolSpec>(&json).unwrap(), spec);
+    }
+}
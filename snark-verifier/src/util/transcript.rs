@@ -1,7 +1,13 @@
 use crate::{
-    loader::{native::NativeLoader, Loader},
-    {util::arithmetic::CurveAffine, Error},
+    loader::{native::NativeLoader, Loader, ScalarLoader},
+    util::{
+        arithmetic::{fe_from_big, CurveAffine, PrimeField},
+        Itertools,
+    },
+    Error,
 };
+use num_bigint::BigUint;
+use std::{io::ErrorKind, iter};
 
 pub trait Transcript<C, L>
 where
@@ -19,6 +25,32 @@ where
     fn common_ec_point(&mut self, ec_point: &L::LoadedEcPoint) -> Result<(), Error>;
 
     fn common_scalar(&mut self, scalar: &L::LoadedScalar) -> Result<(), Error>;
+
+    /// Absorb auxiliary data not carried by proof (e.g. a block hash or batch
+    /// index) to bind the proof to it, which prover and verifier must absorb
+    /// at the same point of transcript.
+    fn absorb_aux(&mut self, aux: &[L::LoadedScalar]) -> Result<(), Error> {
+        aux.iter().try_for_each(|scalar| self.common_scalar(scalar))
+    }
+
+    /// Absorb auxiliary `bytes` by [`Transcript::absorb_aux`], encoded by
+    /// [`bytes_to_aux`].
+    fn common_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        let aux = bytes_to_aux::<C::Scalar>(bytes)
+            .iter()
+            .map(|scalar| self.loader().load_const(scalar))
+            .collect_vec();
+        self.absorb_aux(&aux)
+    }
+}
+
+/// Encode `bytes` into scalars absorbed by [`Transcript::common_bytes`], which
+/// are the length of `bytes` followed by chunks of 31 bytes interpreted in
+/// big-endian, so each chunk fits in the scalar field.
+pub fn bytes_to_aux<F: PrimeField>(bytes: &[u8]) -> Vec<F> {
+    iter::once(F::from(bytes.len() as u64))
+        .chain(bytes.chunks(31).map(|chunk| fe_from_big(BigUint::from_bytes_be(chunk))))
+        .collect()
 }
 
 pub trait TranscriptRead<C, L>: Transcript<C, L>
@@ -37,10 +69,33 @@ where
     fn read_n_ec_points(&mut self, n: usize) -> Result<Vec<L::LoadedEcPoint>, Error> {
         (0..n).map(|_| self.read_ec_point()).collect()
     }
+
+    /// Read a proof-of-work nonce as a scalar, then squeeze a challenge and
+    /// check its lowest `bits` bits are all zero. The challenge is discarded,
+    /// so it only costs prover `2^bits` squeezes on average to pass but makes
+    /// every later challenge that much more expensive to grind.
+    fn read_proof_of_work(&mut self, bits: usize) -> Result<(), Error> {
+        let _ = bits;
+        Err(unsupported_proof_of_work())
+    }
 }
 
 pub trait TranscriptWrite<C: CurveAffine>: Transcript<C, NativeLoader> {
     fn write_scalar(&mut self, scalar: C::Scalar) -> Result<(), Error>;
 
     fn write_ec_point(&mut self, ec_point: C) -> Result<(), Error>;
+
+    /// Grind and write a proof-of-work nonce accepted by
+    /// [`TranscriptRead::read_proof_of_work`] with same `bits`.
+    fn write_proof_of_work(&mut self, bits: usize) -> Result<(), Error> {
+        let _ = bits;
+        Err(unsupported_proof_of_work())
+    }
+}
+
+fn unsupported_proof_of_work() -> Error {
+    Error::Transcript(
+        ErrorKind::Unsupported,
+        "Proof-of-work is not supported by this transcript".to_string(),
+    )
 }
@@ -6,9 +6,13 @@ use crate::{
 };
 use std::fmt::Debug;
 
+#[cfg(feature = "cosmwasm")]
+pub mod cosmwasm;
+pub mod disassembler;
+pub mod groth16;
 mod plonk;
 
-pub use plonk::{Plonk, PlonkProof};
+pub use plonk::{Plonk, PlonkChallenges, PlonkProof, PreparedProtocol};
 
 pub trait PlonkVerifier<C, L, MOS>
 where
@@ -27,6 +31,11 @@ where
     where
         T: TranscriptRead<C, L>;
 
+    /// Returns the accumulator of `proof` followed by old accumulators carried
+    /// in `instances` at `protocol.accumulator_indices`, if the proof is of an
+    /// aggregation circuit. So proofs which already carry accumulators are
+    /// re-accumulated by accumulating all returned accumulators, without
+    /// extracting the old ones separately.
     fn succinct_verify(
         svk: &MOS::SuccinctVerifyingKey,
         protocol: &Protocol<C, L>,
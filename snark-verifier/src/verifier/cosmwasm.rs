@@ -0,0 +1,102 @@
+//! Minimal verification entrypoint for CosmWasm contracts, which verifies
+//! proofs generated for the EVM verifier (e.g. by
+//! `snark_verifier_sdk::evm::gen_evm_proof_shplonk`) with bn254 arithmetic
+//! done by arkworks through [`ArkLoader`].
+//!
+//! A contract stores a [`CosmwasmVerifier`] generated off-chain from
+//! [`Protocol`] (it's `serde` serializable so could be instantiated by message
+//! or embedded), and calls [`CosmwasmVerifier::verify`] with the same calldata
+//! as the EVM verifier takes. Feature `cosmwasm` only pulls in arkworks and
+//! `sha3` for the keccak256 transcript, not the EVM loader. Nothing here
+//! touches filesystem or randomness, but the crate still depends on `std`.
+use crate::{
+    cost::CostEstimation,
+    halo2_curves::bn256::{Bn256, Fr, G1Affine, G2Affine},
+    loader::{
+        ark::{fr_to_ark, ArkLoader, ArkTranscript, Scalar, LOADER},
+        native::NativeLoader,
+    },
+    pcs::kzg::{Bdfg21, Kzg, KzgDecidingKey, KzgSuccinctVerifyingKey},
+    system::halo2::transcript::evm::EvmTranscript,
+    util::{arithmetic::PrimeField, Itertools},
+    verifier::{Plonk, PlonkVerifier},
+    Error, Protocol,
+};
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+
+type Pv = Plonk<Kzg<Bn256, Bdfg21>>;
+
+/// Everything needed to verify proofs of a circuit.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CosmwasmVerifier {
+    pub protocol: Protocol<G1Affine>,
+    /// Generator on G1 of the trusted setup.
+    pub g: G1Affine,
+    /// Generator on G2 of the trusted setup.
+    pub g2: G2Affine,
+    /// Generator to the trusted-setup secret on G2.
+    pub s_g2: G2Affine,
+}
+
+impl CosmwasmVerifier {
+    /// Initialize a [`CosmwasmVerifier`] with `protocol` compiled by
+    /// [`compile`](crate::system::halo2::compile) and generators of params
+    /// it's compiled with.
+    pub fn new(protocol: Protocol<G1Affine>, g: G1Affine, g2: G2Affine, s_g2: G2Affine) -> Self {
+        Self { protocol, g, g2, s_g2 }
+    }
+
+    /// Returns length in bytes of calldata accepted by
+    /// [`CosmwasmVerifier::verify`].
+    pub fn calldata_len(&self) -> usize {
+        let cost = Pv::estimate_cost(&self.protocol);
+        0x20 * cost.num_instance + 0x40 * cost.num_commitment + 0x20 * cost.num_evaluation
+    }
+
+    /// Verify `calldata` consisting of instances followed by proof, all in 32
+    /// bytes big-endian words, which must be generated with SHPLONK and
+    /// [`EvmTranscript`].
+    ///
+    /// Calldata of unexpected length, with non-canonical instance, or with
+    /// non-canonical scalar or invalid ec point in proof is rejected with
+    /// error.
+    pub fn verify(&self, calldata: &[u8]) -> Result<bool, Error> {
+        let calldata_len = self.calldata_len();
+        if calldata.len() != calldata_len {
+            return Err(Error::Transcript(
+                ErrorKind::InvalidData,
+                format!("Expected calldata of {calldata_len} bytes, got {}", calldata.len()),
+            ));
+        }
+
+        let num_instance = self.protocol.num_instance.iter().sum::<usize>();
+        let (instances, proof) = calldata.split_at(num_instance * 0x20);
+        let mut instances = instances
+            .chunks(0x20)
+            .map(|bytes| {
+                let mut repr = [0; 0x20];
+                repr.copy_from_slice(bytes);
+                repr.reverse();
+                Option::<Fr>::from(Fr::from_repr(repr))
+                    .map(|scalar| Scalar(fr_to_ark(&scalar)))
+                    .ok_or(Error::InvalidInstances)
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter();
+        let instances = self
+            .protocol
+            .num_instance
+            .iter()
+            .map(|num_instance| instances.by_ref().take(*num_instance).collect_vec())
+            .collect_vec();
+
+        let svk = KzgSuccinctVerifyingKey::new(self.g);
+        let dk = KzgDecidingKey::<Bn256>::from((self.g2, self.s_g2));
+        let protocol = self.protocol.loaded::<ArkLoader>(&LOADER);
+        let mut transcript =
+            ArkTranscript(EvmTranscript::<G1Affine, NativeLoader, _, _>::new(proof));
+        let proof = Pv::try_read_proof(&svk, &protocol, &instances, &mut transcript)?;
+        Ok(Pv::verify(&svk, &dk, &protocol, &instances, &proof))
+    }
+}
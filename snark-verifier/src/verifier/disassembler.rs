@@ -0,0 +1,323 @@
+//! Disassembler of [`PlonkProof`] for diagnosing mismatches between prover and
+//! verifier transcripts.
+//!
+//! It reads a proof natively through a [`RecordingTranscript`], then labels
+//! every recorded item by the [`Protocol`] layout, so the first diverging
+//! commitment, evaluation or challenge could be spotted directly.
+use crate::{
+    loader::native::NativeLoader,
+    pcs::{AccumulatorEncoding, MultiOpenScheme},
+    util::{
+        arithmetic::CurveAffine,
+        transcript::{Transcript, TranscriptRead},
+        Itertools,
+    },
+    verifier::{Plonk, PlonkChallenges},
+    Error, Protocol,
+};
+use std::{
+    fmt::{self, Display},
+    iter,
+};
+
+/// Item absorbed, read or squeezed by transcript.
+#[derive(Clone, Debug)]
+pub enum TranscriptItem<C: CurveAffine> {
+    CommonScalar(C::Scalar),
+    CommonEcPoint(C),
+    ReadScalar(C::Scalar),
+    ReadEcPoint(C),
+    SqueezeChallenge(C::Scalar),
+}
+
+impl<C: CurveAffine> Display for TranscriptItem<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranscriptItem::CommonScalar(scalar) => write!(f, "common scalar    {scalar:?}"),
+            TranscriptItem::CommonEcPoint(ec_point) => write!(f, "common ec point  {ec_point:?}"),
+            TranscriptItem::ReadScalar(scalar) => write!(f, "read scalar      {scalar:?}"),
+            TranscriptItem::ReadEcPoint(ec_point) => write!(f, "read ec point    {ec_point:?}"),
+            TranscriptItem::SqueezeChallenge(challenge) => {
+                write!(f, "squeeze          {challenge:?}")
+            }
+        }
+    }
+}
+
+/// Transcript wrapper recording every [`TranscriptItem`] going through it.
+#[derive(Debug)]
+pub struct RecordingTranscript<C: CurveAffine, T> {
+    inner: T,
+    items: Vec<TranscriptItem<C>>,
+}
+
+impl<C: CurveAffine, T> RecordingTranscript<C, T> {
+    /// Wrap `inner` transcript.
+    pub fn new(inner: T) -> Self {
+        Self { inner, items: Vec::new() }
+    }
+
+    /// Returns recorded items.
+    pub fn items(&self) -> &[TranscriptItem<C>] {
+        &self.items
+    }
+
+    /// Returns inner transcript and recorded items.
+    pub fn into_inner(self) -> (T, Vec<TranscriptItem<C>>) {
+        (self.inner, self.items)
+    }
+}
+
+impl<C, T> Transcript<C, NativeLoader> for RecordingTranscript<C, T>
+where
+    C: CurveAffine,
+    T: Transcript<C, NativeLoader>,
+{
+    fn loader(&self) -> &NativeLoader {
+        self.inner.loader()
+    }
+
+    fn squeeze_challenge(&mut self) -> C::Scalar {
+        let challenge = self.inner.squeeze_challenge();
+        self.items.push(TranscriptItem::SqueezeChallenge(challenge));
+        challenge
+    }
+
+    fn common_ec_point(&mut self, ec_point: &C) -> Result<(), Error> {
+        self.items.push(TranscriptItem::CommonEcPoint(*ec_point));
+        self.inner.common_ec_point(ec_point)
+    }
+
+    fn common_scalar(&mut self, scalar: &C::Scalar) -> Result<(), Error> {
+        self.items.push(TranscriptItem::CommonScalar(*scalar));
+        self.inner.common_scalar(scalar)
+    }
+}
+
+impl<C, T> TranscriptRead<C, NativeLoader> for RecordingTranscript<C, T>
+where
+    C: CurveAffine,
+    T: TranscriptRead<C, NativeLoader>,
+{
+    fn read_scalar(&mut self) -> Result<C::Scalar, Error> {
+        let scalar = self.inner.read_scalar()?;
+        self.items.push(TranscriptItem::ReadScalar(scalar));
+        Ok(scalar)
+    }
+
+    fn read_ec_point(&mut self) -> Result<C, Error> {
+        let ec_point = self.inner.read_ec_point()?;
+        self.items.push(TranscriptItem::ReadEcPoint(ec_point));
+        Ok(ec_point)
+    }
+}
+
+/// Parsed structure of a proof with each transcript item labelled.
+#[derive(Clone, Debug)]
+pub struct Disassembly<C: CurveAffine> {
+    /// Labelled transcript items in order.
+    pub items: Vec<(String, TranscriptItem<C>)>,
+    /// Challenges of successfully read proof, or the error returned by
+    /// reading (e.g. proof is truncated or has invalid encoding), in which
+    /// case `items` end right before the malformed one.
+    pub challenges: Result<PlonkChallenges<C, NativeLoader>, Error>,
+}
+
+impl<C: CurveAffine> Display for Disassembly<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.items.iter().map(|(label, _)| label.len()).max().unwrap_or_default();
+        for (idx, (label, item)) in self.items.iter().enumerate() {
+            writeln!(f, "#{idx:<4} {label:<width$}  {item}")?;
+        }
+        match &self.challenges {
+            Ok(challenges) => {
+                writeln!(f, "challenges:")?;
+                for (idx, challenge) in challenges.challenges.iter().enumerate() {
+                    writeln!(f, "  challenge[{idx}] = {challenge:?}")?;
+                }
+                writeln!(f, "  z = {:?}", challenges.z)?;
+                for (idx, challenge) in challenges.pcs.iter().enumerate() {
+                    writeln!(f, "  pcs[{idx}] = {challenge:?}")?;
+                }
+                Ok(())
+            }
+            Err(err) => {
+                writeln!(f, "failed to read proof after item #{}: {err:?}", self.items.len())
+            }
+        }
+    }
+}
+
+/// Read proof from `transcript` by [`Plonk`] and returns [`Disassembly`],
+/// which can be printed by [`Display`].
+pub fn disassemble<C, MOS, AE, T>(
+    svk: &MOS::SuccinctVerifyingKey,
+    protocol: &Protocol<C>,
+    instances: &[Vec<C::Scalar>],
+    transcript: T,
+) -> Disassembly<C>
+where
+    C: CurveAffine,
+    MOS: MultiOpenScheme<C, NativeLoader>,
+    AE: AccumulatorEncoding<C, NativeLoader, MOS>,
+    T: TranscriptRead<C, NativeLoader>,
+{
+    let mut transcript = RecordingTranscript::new(transcript);
+    let challenges = Plonk::<MOS, AE>::try_read_proof(svk, protocol, instances, &mut transcript)
+        .map(|proof| proof.challenges());
+
+    let items = labels(protocol, instances)
+        .into_iter()
+        .chain((0..).map(|idx| format!("pcs[{idx}]")))
+        .zip(transcript.into_inner().1)
+        .collect();
+
+    Disassembly { items, challenges }
+}
+
+/// Returns labels of transcript items in reading order of [`Plonk`].
+fn labels<C: CurveAffine>(protocol: &Protocol<C>, instances: &[Vec<C::Scalar>]) -> Vec<String> {
+    let transcript_initial_state =
+        protocol.transcript_initial_state.as_ref().map(|_| "transcript_initial_state".to_string());
+    let instances = if protocol.instance_committing_key.is_some() {
+        (0..instances.len()).map(|i| format!("committed_instance[{i}]")).collect_vec()
+    } else {
+        instances
+            .iter()
+            .enumerate()
+            .flat_map(|(i, instances)| {
+                (0..instances.len()).map(move |j| format!("instance[{i}][{j}]"))
+            })
+            .collect_vec()
+    };
+    let (mut num_witness, mut num_challenge) = (0, 0);
+    let phases = protocol
+        .num_witness
+        .iter()
+        .zip(protocol.num_challenge.iter())
+        .enumerate()
+        .flat_map(|(phase, (&n, &m))| {
+            let witnesses = (num_witness..num_witness + n)
+                .map(|idx| format!("witness[{idx}] (phase {phase})"))
+                .collect_vec();
+            let challenges = (num_challenge..num_challenge + m)
+                .map(|idx| format!("challenge[{idx}] (phase {phase})"))
+                .collect_vec();
+            num_witness += n;
+            num_challenge += m;
+            witnesses.into_iter().chain(challenges)
+        })
+        .collect_vec();
+    let quotients = (0..protocol.quotient.num_chunk()).map(|idx| format!("quotient[{idx}]"));
+    let evaluations = protocol.evaluations.iter().enumerate().map(|(idx, query)| {
+        format!("evaluation[{idx}] (poly {}, rotation {})", query.poly, query.rotation.0)
+    });
+
+    iter::empty()
+        .chain(transcript_initial_state)
+        .chain(instances)
+        .chain(phases)
+        .chain(quotients)
+        .chain(Some("z".to_string()))
+        .chain(evaluations)
+        .collect()
+}
+
+#[cfg(all(test, feature = "loader_halo2"))]
+mod test {
+    use crate::{
+        halo2_curves::bn256::{Bn256, G1Affine},
+        halo2_proofs::{
+            poly::{
+                commitment::ParamsProver,
+                kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK},
+            },
+            transcript::{Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer},
+        },
+        pcs::kzg::{Bdfg21, Kzg, KzgSuccinctVerifyingKey},
+        system::halo2::test::{
+            kzg::{halo2_kzg_config, halo2_kzg_create_snark, halo2_kzg_prepare},
+            RotatedInstance,
+        },
+        verifier::{
+            disassembler::{disassemble, TranscriptItem},
+            Plonk,
+        },
+        Error,
+    };
+    use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+    use std::io::ErrorKind;
+
+    #[test]
+    fn test_disassemble() {
+        type Pcs = Kzg<Bn256, Bdfg21>;
+
+        let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+            9,
+            halo2_kzg_config!(true, 2),
+            RotatedInstance::rand(ChaCha20Rng::from_seed(Default::default()))
+        );
+        let svk = KzgSuccinctVerifyingKey::new(params.get_g()[0]);
+        let snark = halo2_kzg_create_snark!(
+            ProverSHPLONK<_>,
+            VerifierSHPLONK<_>,
+            Blake2bWrite<_, _, _>,
+            Blake2bRead<_, _, _>,
+            Challenge255<_>,
+            &params,
+            &pk,
+            &protocol,
+            &circuits
+        );
+
+        let disassembly = disassemble::<_, Pcs, (), _>(
+            &svk,
+            &snark.protocol,
+            &snark.instances,
+            Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&snark.proof[..]),
+        );
+        let proof = Plonk::<Pcs>::try_read_proof(
+            &svk,
+            &snark.protocol,
+            &snark.instances,
+            &mut Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&snark.proof[..]),
+        )
+        .unwrap();
+        let challenges = disassembly.challenges.as_ref().unwrap();
+        assert_eq!(challenges.challenges, proof.challenges().challenges);
+        assert_eq!(challenges.z, proof.challenges().z);
+        assert_eq!(challenges.pcs, proof.challenges().pcs);
+
+        // Transcript initial state, instances, witnesses, challenges, quotients, z,
+        // evaluations and 5 items of Bdfg21 (mu, gamma, w, z' and w')
+        let num_item = 1
+            + snark.instances.iter().map(Vec::len).sum::<usize>()
+            + snark.protocol.num_witness.iter().sum::<usize>()
+            + snark.protocol.num_challenge.iter().sum::<usize>()
+            + snark.protocol.quotient.num_chunk()
+            + 1
+            + snark.protocol.evaluations.len()
+            + 5;
+        assert_eq!(disassembly.items.len(), num_item);
+        assert_eq!(disassembly.items[0].0, "transcript_initial_state");
+        assert_eq!(disassembly.items[1].0, "instance[0][0]");
+        assert!(matches!(disassembly.items[1].1, TranscriptItem::CommonScalar(_)));
+        assert_eq!(disassembly.items.last().unwrap().0, "pcs[4]");
+        assert!(matches!(disassembly.items.last().unwrap().1, TranscriptItem::ReadEcPoint(_)));
+
+        // Truncated proof is rejected with items read so far
+        let truncated = &snark.proof[..snark.proof.len() / 2];
+        let disassembly = disassemble::<_, Pcs, (), _>(
+            &svk,
+            &snark.protocol,
+            &snark.instances,
+            Blake2bRead::<_, G1Affine, Challenge255<_>>::init(truncated),
+        );
+        assert!(matches!(
+            disassembly.challenges,
+            Err(Error::Transcript(ErrorKind::UnexpectedEof, _))
+        ));
+        assert!(disassembly.items.len() < num_item);
+        assert!(disassembly.to_string().contains("failed to read proof"));
+    }
+}
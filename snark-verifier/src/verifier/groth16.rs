@@ -0,0 +1,325 @@
+//! [Groth16](<https://eprint.iacr.org/2016/260>) verifier generic over
+//! [`Loader`], so proofs from circom/gnark-style toolchains could be verified
+//! natively or by the same generated EVM contract as halo2-PLONK proofs.
+//!
+//! Calldata follows the convention of the PLONK verifier, which is instances
+//! as 32-byte big-endian words followed by the proof. A Groth16 proof is
+//! encoded as `A` in `(x, y)`, `B` in `(x.c1, x.c0, y.c1, y.c0)` and `C` in
+//! `(x, y)`, 256 bytes in total. To verify both kinds of proofs in a single
+//! contract, run [`PlonkVerifier::verify`] and [`Groth16::verify`] on the same
+//! [`EvmLoader`] with the Groth16 proof read from the offset after the PLONK
+//! proof.
+//!
+//! Without pairing in circuit, [`Groth16::prepare_instances`] could still be
+//! done by an aggregation circuit, which exposes the resulting `IC` and defers
+//! [`Groth16::verify_prepared`] to its own verifier.
+//!
+//! [`PlonkVerifier::verify`]: crate::verifier::PlonkVerifier::verify
+//! [`EvmLoader`]: crate::loader::evm::EvmLoader
+use crate::{
+    loader::{LoadedEcPoint, Loader},
+    util::{arithmetic::MultiMillerLoop, msm::Msm},
+    Error,
+};
+use std::{fmt::Debug, iter, marker::PhantomData};
+
+/// [`Loader`] able to perform the final pairing check of [`Groth16`].
+pub trait Groth16Loader<M: MultiMillerLoop>: Loader<M::G1Affine> {
+    /// Loaded G2 point, which is only needed for `B` of proof.
+    type LoadedG2Point: Clone + Debug;
+    /// Output of pairing check.
+    type Output: Clone + Debug;
+
+    /// Performs pairing check `e(a, b) * e(g1_0, g2_0) * ... * e(g1_n, g2_n)
+    /// == 1`, where `(a, b)` is `proof_term` and the rest are `const_terms`.
+    fn pairing_check(
+        &self,
+        proof_term: (&Self::LoadedEcPoint, &Self::LoadedG2Point),
+        const_terms: &[(&Self::LoadedEcPoint, M::G2Affine)],
+    ) -> Self::Output;
+}
+
+/// Groth16 verifying key.
+#[derive(Clone, Debug)]
+pub struct Groth16VerifyingKey<M: MultiMillerLoop> {
+    /// $[\alpha]_1$.
+    pub alpha: M::G1Affine,
+    /// $[\beta]_2$.
+    pub beta: M::G2Affine,
+    /// $[\gamma]_2$.
+    pub gamma: M::G2Affine,
+    /// $[\delta]_2$.
+    pub delta: M::G2Affine,
+    /// Bases of instances, where the first one is for the constant term.
+    pub ic: Vec<M::G1Affine>,
+}
+
+impl<M: MultiMillerLoop> Groth16VerifyingKey<M> {
+    /// Initialize a [`Groth16VerifyingKey`].
+    pub fn new(
+        alpha: M::G1Affine,
+        beta: M::G2Affine,
+        gamma: M::G2Affine,
+        delta: M::G2Affine,
+        ic: Vec<M::G1Affine>,
+    ) -> Self {
+        Self { alpha, beta, gamma, delta, ic }
+    }
+
+    /// Returns number of instances.
+    pub fn num_instance(&self) -> usize {
+        self.ic.len().saturating_sub(1)
+    }
+}
+
+/// Groth16 proof.
+#[derive(Clone, Debug)]
+pub struct Groth16Proof<M, L>
+where
+    M: MultiMillerLoop,
+    L: Groth16Loader<M>,
+{
+    /// $[A]_1$.
+    pub a: L::LoadedEcPoint,
+    /// $[B]_2$.
+    pub b: L::LoadedG2Point,
+    /// $[C]_1$.
+    pub c: L::LoadedEcPoint,
+}
+
+impl<M, L> Groth16Proof<M, L>
+where
+    M: MultiMillerLoop,
+    L: Groth16Loader<M>,
+{
+    /// Initialize a [`Groth16Proof`].
+    pub fn new(a: L::LoadedEcPoint, b: L::LoadedG2Point, c: L::LoadedEcPoint) -> Self {
+        Self { a, b, c }
+    }
+}
+
+/// Groth16 verifier.
+#[derive(Clone, Debug)]
+pub struct Groth16<M>(PhantomData<M>);
+
+impl<M: MultiMillerLoop> Groth16<M> {
+    /// Verify `proof` against `instances`, which checks
+    /// `e(A, B) = e(alpha, beta) * e(IC, gamma) * e(C, delta)` with `IC`
+    /// returned by [`Groth16::prepare_instances`].
+    pub fn verify<L>(
+        vk: &Groth16VerifyingKey<M>,
+        instances: &[L::LoadedScalar],
+        proof: &Groth16Proof<M, L>,
+    ) -> Result<L::Output, Error>
+    where
+        L: Groth16Loader<M>,
+    {
+        let ic = Self::prepare_instances(proof.a.loader(), vk, instances)?;
+        Ok(Self::verify_prepared(vk, &ic, proof))
+    }
+
+    /// Returns `IC`, the linear combination of [`Groth16VerifyingKey::ic`] by
+    /// `1` and `instances`, which is the only part of verification depending
+    /// on instances. It only requires [`Loader`], so an aggregation circuit
+    /// could compute it in circuit and defer [`Groth16::verify_prepared`] to
+    /// its own verifier.
+    pub fn prepare_instances<L>(
+        loader: &L,
+        vk: &Groth16VerifyingKey<M>,
+        instances: &[L::LoadedScalar],
+    ) -> Result<L::LoadedEcPoint, Error>
+    where
+        L: Loader<M::G1Affine>,
+    {
+        if instances.len() != vk.num_instance() || vk.ic.is_empty() {
+            return Err(Error::InvalidInstances);
+        }
+
+        let bases = vk.ic.iter().map(|ic| loader.ec_point_load_const(ic)).collect::<Vec<_>>();
+        Ok(iter::once(Msm::base(&bases[0]))
+            .chain(
+                bases[1..]
+                    .iter()
+                    .zip(instances.iter())
+                    .map(|(base, instance)| Msm::base(base) * instance),
+            )
+            .sum::<Msm<_, _>>()
+            .evaluate(None))
+    }
+
+    /// Verify `proof` with `ic` returned by [`Groth16::prepare_instances`].
+    pub fn verify_prepared<L>(
+        vk: &Groth16VerifyingKey<M>,
+        ic: &L::LoadedEcPoint,
+        proof: &Groth16Proof<M, L>,
+    ) -> L::Output
+    where
+        L: Groth16Loader<M>,
+    {
+        let loader = proof.a.loader();
+        let alpha = loader.ec_point_load_const(&vk.alpha);
+        loader.pairing_check(
+            (&proof.a, &proof.b),
+            &[(&alpha, -vk.beta), (ic, -vk.gamma), (&proof.c, -vk.delta)],
+        )
+    }
+}
+
+mod native {
+    use crate::{
+        loader::native::NativeLoader,
+        util::arithmetic::{Group, MillerLoopResult, MultiMillerLoop},
+        verifier::groth16::Groth16Loader,
+    };
+    use std::iter;
+
+    impl<M: MultiMillerLoop> Groth16Loader<M> for NativeLoader {
+        type LoadedG2Point = M::G2Affine;
+        type Output = bool;
+
+        fn pairing_check(
+            &self,
+            (a, b): (&M::G1Affine, &M::G2Affine),
+            const_terms: &[(&M::G1Affine, M::G2Affine)],
+        ) -> bool {
+            let terms = iter::once((*a, M::G2Prepared::from(*b)))
+                .chain(const_terms.iter().map(|(g1, g2)| (**g1, M::G2Prepared::from(*g2))))
+                .collect::<Vec<_>>();
+            let terms = terms.iter().map(|(g1, g2)| (g1, g2)).collect::<Vec<_>>();
+            M::multi_miller_loop(&terms).final_exponentiation().is_identity().into()
+        }
+    }
+}
+
+#[cfg(feature = "loader_evm")]
+mod evm {
+    use crate::{
+        loader::{
+            evm::{g2_to_u256s, EcPoint, EvmLoader, G2Operand, U256},
+            native::NativeLoader,
+        },
+        util::arithmetic::{CurveAffine, MultiMillerLoop, PrimeField},
+        verifier::groth16::{Groth16Loader, Groth16Proof},
+    };
+    use std::{iter, rc::Rc};
+
+    impl<M> Groth16Loader<M> for Rc<EvmLoader>
+    where
+        M: MultiMillerLoop,
+        M::Scalar: PrimeField<Repr = [u8; 0x20]>,
+    {
+        type LoadedG2Point = G2Operand;
+        type Output = ();
+
+        fn pairing_check(
+            &self,
+            (a, b): (&EcPoint, &G2Operand),
+            const_terms: &[(&EcPoint, M::G2Affine)],
+        ) {
+            let terms = iter::once((a, *b))
+                .chain(
+                    const_terms
+                        .iter()
+                        .map(|(g1, g2)| (*g1, G2Operand::Constant(g2_to_u256s::<M>(*g2)))),
+                )
+                .collect::<Vec<_>>();
+            self.multi_pairing(&terms);
+        }
+    }
+
+    impl<M> Groth16Proof<M, Rc<EvmLoader>>
+    where
+        M: MultiMillerLoop,
+        M::Scalar: PrimeField<Repr = [u8; 0x20]>,
+    {
+        /// Read a proof encoded by [`Groth16Proof::to_calldata`] from calldata
+        /// at `offset`.
+        pub fn read_calldata(loader: &Rc<EvmLoader>, offset: usize) -> Self {
+            let a = loader.calldataload_ec_point(offset);
+            let b = G2Operand::Calldata(offset + 0x40);
+            let c = loader.calldataload_ec_point(offset + 0xc0);
+            Self::new(a, b, c)
+        }
+    }
+
+    impl<M: MultiMillerLoop> Groth16Proof<M, NativeLoader> {
+        /// Encode proof into 256 bytes to be read by
+        /// [`Groth16Proof::read_calldata`].
+        pub fn to_calldata(&self) -> Vec<u8> {
+            let g1_to_u256s = |ec_point: &M::G1Affine| {
+                let coordinates = ec_point.coordinates().unwrap();
+                [coordinates.x(), coordinates.y()]
+                    .map(|coordinate| U256::from_little_endian(coordinate.to_repr().as_ref()))
+            };
+            let (x_1, x_0, y_1, y_0) = g2_to_u256s::<M>(self.b);
+            g1_to_u256s(&self.a)
+                .into_iter()
+                .chain([x_1, x_0, y_1, y_0])
+                .chain(g1_to_u256s(&self.c))
+                .flat_map(|value| {
+                    let mut bytes = [0; 32];
+                    value.to_big_endian(&mut bytes);
+                    bytes
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(all(test, feature = "loader_evm"))]
+mod test {
+    use crate::{
+        halo2_curves::bn256::{Bn256, Fq, Fr, G1, G2},
+        loader::{
+            evm::{compile_solidity, encode_calldata, execute, EvmLoader},
+            native::NativeLoader,
+        },
+        util::{
+            arithmetic::{Curve, Field, Group},
+            Itertools,
+        },
+        verifier::groth16::{Groth16, Groth16Proof, Groth16VerifyingKey},
+    };
+    use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+    use std::rc::Rc;
+
+    #[test]
+    fn test_evm_groth16() {
+        // Setup with known trapdoor, then solve `C` from random `A` and `B`.
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        let [alpha, beta, gamma, delta, a, b] = [(); 6].map(|_| Fr::random(&mut rng));
+        let ic = (0..3).map(|_| Fr::random(&mut rng)).collect_vec();
+        let instances = (0..2).map(|_| Fr::random(&mut rng)).collect_vec();
+        let ic_eval = ic[0] + ic[1] * instances[0] + ic[2] * instances[1];
+        let c = (a * b - alpha * beta - ic_eval * gamma) * delta.invert().unwrap();
+
+        let g1 = |scalar: Fr| (G1::generator() * scalar).to_affine();
+        let g2 = |scalar: Fr| (G2::generator() * scalar).to_affine();
+        let vk = Groth16VerifyingKey::<Bn256>::new(
+            g1(alpha),
+            g2(beta),
+            g2(gamma),
+            g2(delta),
+            ic.iter().copied().map(g1).collect(),
+        );
+        let proof = Groth16Proof::<Bn256, NativeLoader>::new(g1(a), g2(b), g1(c));
+        let wrong_proof = Groth16Proof::<Bn256, NativeLoader>::new(g1(a), g2(b), g1(c + Fr::one()));
+
+        assert!(Groth16::verify(&vk, &instances, &proof).unwrap());
+        assert!(!Groth16::verify(&vk, &instances, &wrong_proof).unwrap());
+        assert!(Groth16::verify(&vk, &instances[..1], &proof).is_err());
+
+        let deployment_code = {
+            let loader = EvmLoader::new::<Fq, Fr>();
+            let instances = (0..2).map(|idx| loader.calldataload_scalar(idx * 0x20)).collect_vec();
+            let proof = Groth16Proof::<Bn256, Rc<EvmLoader>>::read_calldata(&loader, 0x40);
+            Groth16::verify(&vk, &instances, &proof).unwrap();
+            compile_solidity(&loader.solidity_code())
+        };
+
+        let calldata = encode_calldata(&[instances.clone()], &proof.to_calldata());
+        let wrong_calldata = encode_calldata(&[instances], &wrong_proof.to_calldata());
+        assert!(execute(deployment_code.clone(), calldata).0);
+        assert!(!execute(deployment_code, wrong_calldata).0);
+    }
+}
@@ -1,10 +1,13 @@
 use crate::{
     cost::{Cost, CostEstimation},
-    loader::{native::NativeLoader, LoadedScalar, Loader},
-    pcs::{self, AccumulatorEncoding, MultiOpenScheme},
+    loader::{
+        native::{with_fixed_base_tables, FixedBaseTables, NativeLoader},
+        LoadedScalar, Loader,
+    },
+    pcs::{self, AccumulatorEncoding, Decider, MultiOpenScheme},
     util::{
-        arithmetic::{CurveAffine, Field, Rotation},
-        msm::Msm,
+        arithmetic::{CurveAffine, Field, GroupEncoding, Rotation},
+        msm::{FixedBaseTable, Msm},
         protocol::{
             CommonPolynomial::Lagrange, CommonPolynomialEvaluation, LinearizationStrategy, Query,
         },
@@ -15,7 +18,7 @@ use crate::{
     Error, Protocol,
 };
 use rustc_hash::FxHashMap;
-use std::{iter, marker::PhantomData};
+use std::{iter, marker::PhantomData, sync::Arc};
 
 pub struct Plonk<MOS, AE = ()>(PhantomData<(MOS, AE)>);
 
@@ -46,24 +49,115 @@ where
         instances: &[Vec<L::LoadedScalar>],
         proof: &Self::Proof,
     ) -> Vec<MOS::Accumulator> {
-        let common_poly_eval = {
-            let mut common_poly_eval = CommonPolynomialEvaluation::new(
-                &protocol.domain,
-                lagranges(protocol, instances),
+        Self::succinct_verify_with(protocol, instances, proof, |commitments, queries| {
+            MOS::succinct_verify_with_rotation_sets(
+                svk,
+                commitments,
                 &proof.z,
-            );
+                queries,
+                &protocol.rotation_sets,
+                &proof.pcs,
+            )
+        })
+    }
+}
 
-            L::batch_invert(common_poly_eval.denoms());
-            common_poly_eval.evaluate();
+impl<MOS, AE> Plonk<MOS, AE> {
+    /// Precompute state of multi-open scheme from queries of `protocol`, which
+    /// can be reused across proofs of the same protocol by
+    /// [`Plonk::read_proof_prepared`] and [`Plonk::succinct_verify_prepared`].
+    pub fn prepare<C, L>(protocol: &Protocol<C, L>) -> MOS::Prepared
+    where
+        C: CurveAffine,
+        L: Loader<C>,
+        MOS: MultiOpenScheme<C, L>,
+    {
+        MOS::prepare(&PlonkProof::<C, L, MOS>::empty_queries(protocol))
+    }
 
-            common_poly_eval
-        };
+    /// Same as [`PlonkVerifier::read_proof`] but with `prepared` from
+    /// [`Plonk::prepare`].
+    pub fn read_proof_prepared<C, L, T>(
+        svk: &MOS::SuccinctVerifyingKey,
+        prepared: &MOS::Prepared,
+        protocol: &Protocol<C, L>,
+        instances: &[Vec<L::LoadedScalar>],
+        transcript: &mut T,
+    ) -> PlonkProof<C, L, MOS>
+    where
+        C: CurveAffine,
+        L: Loader<C>,
+        MOS: MultiOpenScheme<C, L>,
+        AE: AccumulatorEncoding<C, L, MOS>,
+        T: TranscriptRead<C, L>,
+    {
+        PlonkProof::read_prepared::<T, AE>(svk, prepared, protocol, instances, transcript)
+    }
+
+    /// Same as [`PlonkVerifier::read_proof`] but returns an error instead of
+    /// panicking on malformed proof or instances, for verifying untrusted
+    /// input natively.
+    pub fn try_read_proof<C, L, T>(
+        svk: &MOS::SuccinctVerifyingKey,
+        protocol: &Protocol<C, L>,
+        instances: &[Vec<L::LoadedScalar>],
+        transcript: &mut T,
+    ) -> Result<PlonkProof<C, L, MOS>, Error>
+    where
+        C: CurveAffine,
+        L: Loader<C>,
+        MOS: MultiOpenScheme<C, L>,
+        AE: AccumulatorEncoding<C, L, MOS>,
+        T: TranscriptRead<C, L>,
+    {
+        PlonkProof::try_read::<T, AE>(svk, protocol, instances, transcript)
+    }
+
+    /// Same as [`PlonkVerifier::succinct_verify`] but with `prepared` from
+    /// [`Plonk::prepare`].
+    pub fn succinct_verify_prepared<C, L>(
+        svk: &MOS::SuccinctVerifyingKey,
+        prepared: &MOS::Prepared,
+        protocol: &Protocol<C, L>,
+        instances: &[Vec<L::LoadedScalar>],
+        proof: &PlonkProof<C, L, MOS>,
+    ) -> Vec<MOS::Accumulator>
+    where
+        C: CurveAffine,
+        L: Loader<C>,
+        MOS: MultiOpenScheme<C, L>,
+    {
+        Self::succinct_verify_with(protocol, instances, proof, |commitments, queries| {
+            MOS::succinct_verify_prepared(svk, prepared, commitments, &proof.z, queries, &proof.pcs)
+        })
+    }
+
+    fn succinct_verify_with<'a, C, L>(
+        protocol: &'a Protocol<C, L>,
+        instances: &[Vec<L::LoadedScalar>],
+        proof: &'a PlonkProof<C, L, MOS>,
+        pcs_succinct_verify: impl FnOnce(
+            &[Msm<'a, C, L>],
+            &[pcs::Query<C::Scalar, L::LoadedScalar>],
+        ) -> MOS::Accumulator,
+    ) -> Vec<MOS::Accumulator>
+    where
+        C: CurveAffine,
+        L: Loader<C>,
+        MOS: MultiOpenScheme<C, L>,
+    {
+        if proof.committed_instances.is_none() {
+            assert_num_instance(&protocol.num_instance, instances);
+        }
+
+        let common_poly_eval =
+            L::common_poly_eval(&protocol.domain, lagranges(protocol, instances), &proof.z);
 
         let mut evaluations = proof.evaluations(protocol, instances, &common_poly_eval);
         let commitments = proof.commitments(protocol, &common_poly_eval, &mut evaluations);
         let queries = proof.queries(protocol, evaluations);
 
-        let accumulator = MOS::succinct_verify(svk, &commitments, &proof.z, &queries, &proof.pcs);
+        let accumulator = pcs_succinct_verify(&commitments, &queries);
 
         let accumulators = iter::empty()
             .chain(Some(accumulator))
@@ -72,6 +166,195 @@ where
 
         accumulators
     }
+
+    /// Same as [`PlonkVerifier::read_proof`] but in committed-instance mode,
+    /// see [`PlonkProof::read_with_committed_instances`].
+    pub fn read_proof_with_committed_instances<C, L, T>(
+        svk: &MOS::SuccinctVerifyingKey,
+        protocol: &Protocol<C, L>,
+        committed_instances: &[L::LoadedEcPoint],
+        transcript: &mut T,
+    ) -> PlonkProof<C, L, MOS>
+    where
+        C: CurveAffine,
+        L: Loader<C>,
+        MOS: MultiOpenScheme<C, L>,
+        T: TranscriptRead<C, L>,
+    {
+        PlonkProof::read_with_committed_instances(svk, protocol, committed_instances, transcript)
+    }
+
+    /// Same as [`PlonkVerifier::succinct_verify`] but for proof read by
+    /// [`Plonk::read_proof_with_committed_instances`], for aggregating such
+    /// proofs in circuit.
+    pub fn succinct_verify_with_committed_instances<C, L>(
+        svk: &MOS::SuccinctVerifyingKey,
+        protocol: &Protocol<C, L>,
+        proof: &PlonkProof<C, L, MOS>,
+    ) -> Vec<MOS::Accumulator>
+    where
+        C: CurveAffine,
+        L: Loader<C>,
+        MOS: MultiOpenScheme<C, L>,
+        AE: AccumulatorEncoding<C, L, MOS>,
+    {
+        debug_assert!(proof.committed_instances.is_some());
+        <Self as PlonkVerifier<C, L, MOS>>::succinct_verify(svk, protocol, &[], proof)
+    }
+
+    /// Same as [`PlonkVerifier::verify`] but for proof read by
+    /// [`Plonk::read_proof_with_committed_instances`], whose instance
+    /// evaluations are in proof so instances are not needed.
+    pub fn verify_with_committed_instances<C, L>(
+        svk: &MOS::SuccinctVerifyingKey,
+        dk: &MOS::DecidingKey,
+        protocol: &Protocol<C, L>,
+        proof: &PlonkProof<C, L, MOS>,
+    ) -> MOS::Output
+    where
+        C: CurveAffine,
+        L: Loader<C>,
+        MOS: MultiOpenScheme<C, L> + Decider<C, L>,
+        AE: AccumulatorEncoding<C, L, MOS>,
+    {
+        debug_assert!(proof.committed_instances.is_some());
+        <Self as PlonkVerifier<C, L, MOS>>::verify(svk, dk, protocol, &[], proof)
+    }
+
+    /// Same as [`PlonkVerifier::succinct_verify`] but also returns all
+    /// challenges derived from transcript, for diagnostics or for checking
+    /// challenge derivation against another verifier (e.g. on EVM).
+    pub fn succinct_verify_with_challenges<C, L>(
+        svk: &MOS::SuccinctVerifyingKey,
+        protocol: &Protocol<C, L>,
+        instances: &[Vec<L::LoadedScalar>],
+        proof: &PlonkProof<C, L, MOS>,
+    ) -> (Vec<MOS::Accumulator>, PlonkChallenges<C, L>)
+    where
+        C: CurveAffine,
+        L: Loader<C>,
+        MOS: MultiOpenScheme<C, L>,
+        AE: AccumulatorEncoding<C, L, MOS>,
+    {
+        let accumulators =
+            <Self as PlonkVerifier<C, L, MOS>>::succinct_verify(svk, protocol, instances, proof);
+        (accumulators, proof.challenges())
+    }
+}
+
+/// [`Protocol`] prepared once for native verification of many proofs, e.g.
+/// by a service verifying proofs of a handful of verifying keys.
+///
+/// Besides [`MultiOpenScheme::Prepared`], it precomputes [`FixedBaseTable`]s
+/// of preprocessed commitments, which are used by [`NativeLoader`] instead of
+/// variable-base multiplication while reading and verifying proofs through
+/// it. Evaluations of common polynomials are already memoized per domain by
+/// [`NativeLoader`].
+#[derive(Clone, Debug)]
+pub struct PreparedProtocol<C, MOS>
+where
+    C: CurveAffine,
+    MOS: MultiOpenScheme<C, NativeLoader>,
+{
+    protocol: Protocol<C>,
+    prepared: MOS::Prepared,
+    tables: Arc<FixedBaseTables<C>>,
+}
+
+impl<C, MOS> PreparedProtocol<C, MOS>
+where
+    C: CurveAffine,
+    MOS: MultiOpenScheme<C, NativeLoader>,
+{
+    /// Prepare `protocol`.
+    pub fn new(protocol: Protocol<C>) -> Self {
+        let prepared = Plonk::<MOS>::prepare(&protocol);
+        let tables = protocol
+            .preprocessed
+            .iter()
+            .map(|ec_point| (ec_point.to_bytes().as_ref().to_vec(), FixedBaseTable::new(*ec_point)))
+            .collect();
+        Self { protocol, prepared, tables: Arc::new(tables) }
+    }
+
+    /// Returns the prepared [`Protocol`].
+    pub fn protocol(&self) -> &Protocol<C> {
+        &self.protocol
+    }
+
+    /// Same as [`Plonk::read_proof_prepared`] with tables of preprocessed
+    /// commitments.
+    pub fn read_proof<AE, T>(
+        &self,
+        svk: &MOS::SuccinctVerifyingKey,
+        instances: &[Vec<C::Scalar>],
+        transcript: &mut T,
+    ) -> PlonkProof<C, NativeLoader, MOS>
+    where
+        AE: AccumulatorEncoding<C, NativeLoader, MOS>,
+        T: TranscriptRead<C, NativeLoader>,
+    {
+        with_fixed_base_tables(&self.tables, || {
+            Plonk::<MOS, AE>::read_proof_prepared(
+                svk,
+                &self.prepared,
+                &self.protocol,
+                instances,
+                transcript,
+            )
+        })
+    }
+
+    /// Same as [`Plonk::succinct_verify_prepared`] with tables of
+    /// preprocessed commitments.
+    pub fn succinct_verify(
+        &self,
+        svk: &MOS::SuccinctVerifyingKey,
+        instances: &[Vec<C::Scalar>],
+        proof: &PlonkProof<C, NativeLoader, MOS>,
+    ) -> Vec<MOS::Accumulator> {
+        with_fixed_base_tables(&self.tables, || {
+            Plonk::<MOS>::succinct_verify_prepared(
+                svk,
+                &self.prepared,
+                &self.protocol,
+                instances,
+                proof,
+            )
+        })
+    }
+
+    /// Same as [`PlonkVerifier::verify`] with tables of preprocessed
+    /// commitments.
+    pub fn verify(
+        &self,
+        svk: &MOS::SuccinctVerifyingKey,
+        dk: &MOS::DecidingKey,
+        instances: &[Vec<C::Scalar>],
+        proof: &PlonkProof<C, NativeLoader, MOS>,
+    ) -> MOS::Output
+    where
+        MOS: Decider<C, NativeLoader>,
+    {
+        MOS::decide_all(dk, self.succinct_verify(svk, instances, proof))
+    }
+}
+
+/// Challenges derived from transcript while reading a [`PlonkProof`].
+#[derive(Clone, Debug)]
+pub struct PlonkChallenges<C, L>
+where
+    C: CurveAffine,
+    L: Loader<C>,
+{
+    /// Challenges of all phases in squeezing order, which are `theta`,
+    /// `beta`, `gamma` and `alpha` with user challenges in between for halo2
+    /// circuits.
+    pub challenges: Vec<L::LoadedScalar>,
+    /// Evaluation point `x`.
+    pub z: L::LoadedScalar,
+    /// Challenges of the multi-open scheme in squeezing order.
+    pub pcs: Vec<L::LoadedScalar>,
 }
 
 #[derive(Clone, Debug)]
@@ -88,6 +371,9 @@ where
     pub z: L::LoadedScalar,
     pub evaluations: Vec<L::LoadedScalar>,
     pub pcs: MOS::Proof,
+    /// Accumulators decoded from instances at `protocol.accumulator_indices`
+    /// by [`AccumulatorEncoding::from_repr`], which are returned along with
+    /// the new one by [`PlonkVerifier::succinct_verify`].
     pub old_accumulators: Vec<MOS::Accumulator>,
 }
 
@@ -107,15 +393,66 @@ where
         T: TranscriptRead<C, L>,
         AE: AccumulatorEncoding<C, L, MOS>,
     {
-        if let Some(transcript_initial_state) = &protocol.transcript_initial_state {
-            transcript.common_scalar(transcript_initial_state).unwrap();
+        let prepared = MOS::prepare(&Self::empty_queries(protocol));
+        Self::read_prepared::<T, AE>(svk, &prepared, protocol, instances, transcript)
+    }
+
+    /// Same as [`PlonkProof::read`] but with state of multi-open scheme
+    /// precomputed by [`MultiOpenScheme::prepare`].
+    pub fn read_prepared<T, AE>(
+        svk: &MOS::SuccinctVerifyingKey,
+        prepared: &MOS::Prepared,
+        protocol: &Protocol<C, L>,
+        instances: &[Vec<L::LoadedScalar>],
+        transcript: &mut T,
+    ) -> Self
+    where
+        T: TranscriptRead<C, L>,
+        AE: AccumulatorEncoding<C, L, MOS>,
+    {
+        assert_num_instance(&protocol.num_instance, instances);
+
+        Self::try_read_prepared::<T, AE>(svk, prepared, protocol, instances, transcript)
+            .unwrap_or_else(|err| panic!("Failed to read proof: {err:?}"))
+    }
+
+    /// Same as [`PlonkProof::read`] but returns an error instead of panicking
+    /// when `instances` don't match `protocol`, carry invalid old
+    /// accumulators, or `transcript` holds a malformed proof.
+    pub fn try_read<T, AE>(
+        svk: &MOS::SuccinctVerifyingKey,
+        protocol: &Protocol<C, L>,
+        instances: &[Vec<L::LoadedScalar>],
+        transcript: &mut T,
+    ) -> Result<Self, Error>
+    where
+        T: TranscriptRead<C, L>,
+        AE: AccumulatorEncoding<C, L, MOS>,
+    {
+        let prepared = MOS::prepare(&Self::empty_queries(protocol));
+        Self::try_read_prepared::<T, AE>(svk, &prepared, protocol, instances, transcript)
+    }
+
+    /// Same as [`PlonkProof::try_read`] but with state of multi-open scheme
+    /// precomputed by [`MultiOpenScheme::prepare`].
+    pub fn try_read_prepared<T, AE>(
+        svk: &MOS::SuccinctVerifyingKey,
+        prepared: &MOS::Prepared,
+        protocol: &Protocol<C, L>,
+        instances: &[Vec<L::LoadedScalar>],
+        transcript: &mut T,
+    ) -> Result<Self, Error>
+    where
+        T: TranscriptRead<C, L>,
+        AE: AccumulatorEncoding<C, L, MOS>,
+    {
+        if !is_num_instance_matched(&protocol.num_instance, instances) {
+            return Err(Error::InvalidInstances);
         }
 
-        debug_assert_eq!(
-            protocol.num_instance,
-            instances.iter().map(|instances| instances.len()).collect_vec(),
-            "Invalid Instances"
-        );
+        if let Some(transcript_initial_state) = &protocol.transcript_initial_state {
+            transcript.common_scalar(transcript_initial_state)?;
+        }
 
         let committed_instances = if let Some(ick) = &protocol.instance_committing_key {
             let loader = transcript.loader();
@@ -136,28 +473,115 @@ where
                 })
                 .collect_vec();
             for committed_instance in committed_instances.iter() {
-                transcript.common_ec_point(committed_instance).unwrap();
+                transcript.common_ec_point(committed_instance)?;
             }
 
             Some(committed_instances)
         } else {
             for instances in instances.iter() {
                 for instance in instances.iter() {
-                    transcript.common_scalar(instance).unwrap();
+                    transcript.common_scalar(instance)?;
                 }
             }
 
             None
         };
 
+        let old_accumulators = protocol
+            .accumulator_indices
+            .iter()
+            .enumerate()
+            .map(|(idx, accumulator_indices)| {
+                AE::from_repr(
+                    &accumulator_indices.iter().map(|&(i, j)| &instances[i][j]).collect_vec(),
+                )
+                .map_err(|err| {
+                    Error::AssertionFailure(format!(
+                        "Invalid old accumulator {idx} in instances: {err:?}"
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Self::read_remaining(
+            svk,
+            prepared,
+            protocol,
+            committed_instances,
+            old_accumulators,
+            transcript,
+        )
+    }
+
+    /// Read proof in committed-instance mode, where `committed_instances`
+    /// (one commitment per instance column) are given instead of instances,
+    /// which keeps input size constant no matter how large instances are.
+    /// Commitments are absorbed into transcript the same as in
+    /// [`PlonkProof::read`], so the proof is the same.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `protocol` is not compiled with
+    /// [`Config::set_query_instance`](crate::system::halo2::Config::set_query_instance),
+    /// or has accumulators in instances, which can't be read from commitments.
+    pub fn read_with_committed_instances<T>(
+        svk: &MOS::SuccinctVerifyingKey,
+        protocol: &Protocol<C, L>,
+        committed_instances: &[L::LoadedEcPoint],
+        transcript: &mut T,
+    ) -> Self
+    where
+        T: TranscriptRead<C, L>,
+    {
+        assert!(
+            protocol.instance_committing_key.is_some(),
+            "Committed-instance mode requires protocol compiled with query_instance"
+        );
+        assert!(
+            protocol.accumulator_indices.is_empty(),
+            "Committed-instance mode doesn't support accumulators in instances"
+        );
+        assert_eq!(committed_instances.len(), protocol.num_instance.len());
+
+        if let Some(transcript_initial_state) = &protocol.transcript_initial_state {
+            transcript.common_scalar(transcript_initial_state).unwrap();
+        }
+        for committed_instance in committed_instances.iter() {
+            transcript.common_ec_point(committed_instance).unwrap();
+        }
+
+        Self::read_remaining(
+            svk,
+            &MOS::prepare(&Self::empty_queries(protocol)),
+            protocol,
+            Some(committed_instances.to_vec()),
+            Vec::new(),
+            transcript,
+        )
+        .unwrap_or_else(|err| panic!("Failed to read proof: {err:?}"))
+    }
+
+    fn read_remaining<T>(
+        svk: &MOS::SuccinctVerifyingKey,
+        prepared: &MOS::Prepared,
+        protocol: &Protocol<C, L>,
+        committed_instances: Option<Vec<L::LoadedEcPoint>>,
+        old_accumulators: Vec<MOS::Accumulator>,
+        transcript: &mut T,
+    ) -> Result<Self, Error>
+    where
+        T: TranscriptRead<C, L>,
+    {
         let (witnesses, challenges) = {
             let (witnesses, challenges): (Vec<_>, Vec<_>) = protocol
                 .num_witness
                 .iter()
                 .zip(protocol.num_challenge.iter())
                 .map(|(&n, &m)| {
-                    (transcript.read_n_ec_points(n).unwrap(), transcript.squeeze_n_challenges(m))
+                    Ok((transcript.read_n_ec_points(n)?, transcript.squeeze_n_challenges(m)))
                 })
+                .collect::<Result<Vec<_>, Error>>()?
+                .into_iter()
                 .unzip();
 
             (
@@ -166,25 +590,14 @@ where
             )
         };
 
-        let quotients = transcript.read_n_ec_points(protocol.quotient.num_chunk()).unwrap();
+        let quotients = transcript.read_n_ec_points(protocol.quotient.num_chunk())?;
 
         let z = transcript.squeeze_challenge();
-        let evaluations = transcript.read_n_scalars(protocol.evaluations.len()).unwrap();
+        let evaluations = transcript.read_n_scalars(protocol.evaluations.len())?;
 
-        let pcs = MOS::read_proof(svk, &Self::empty_queries(protocol), transcript);
+        let pcs = MOS::read_proof_prepared(svk, prepared, transcript)?;
 
-        let old_accumulators = protocol
-            .accumulator_indices
-            .iter()
-            .map(|accumulator_indices| {
-                AE::from_repr(
-                    &accumulator_indices.iter().map(|&(i, j)| &instances[i][j]).collect_vec(),
-                )
-                .unwrap()
-            })
-            .collect_vec();
-
-        Self {
+        Ok(Self {
             committed_instances,
             witnesses,
             challenges,
@@ -193,6 +606,15 @@ where
             evaluations,
             pcs,
             old_accumulators,
+        })
+    }
+
+    /// Returns challenges derived from transcript while reading the proof.
+    pub fn challenges(&self) -> PlonkChallenges<C, L> {
+        PlonkChallenges {
+            challenges: self.challenges.clone(),
+            z: self.z.clone(),
+            pcs: MOS::challenges(&self.pcs),
         }
     }
 
@@ -331,16 +753,8 @@ where
         let loader = common_poly_eval.zn().loader();
         let instance_evals = protocol.instance_committing_key.is_none().then(|| {
             let offset = protocol.preprocessed.len();
-            let queries = {
-                let range = offset..offset + protocol.num_instance.len();
-                protocol
-                    .quotient
-                    .numerator
-                    .used_query()
-                    .into_iter()
-                    .filter(move |query| range.contains(&query.poly))
-            };
-            queries
+            instance_queries(protocol)
+                .into_iter()
                 .map(move |query| {
                     let instances = instances[query.poly - offset].iter();
                     let l_i_minus_r = (-query.rotation.0..)
@@ -383,6 +797,59 @@ where
     }
 }
 
+fn is_num_instance_matched<T>(num_instance: &[usize], instances: &[Vec<T>]) -> bool {
+    num_instance.len() == instances.len()
+        && num_instance
+            .iter()
+            .zip(instances)
+            .all(|(expected, instances)| *expected == instances.len())
+}
+
+/// Assert `instances` have the same shape as `num_instance`, which is the most
+/// common integration mistake and otherwise misbehaves deep in evaluation.
+/// Shape of instances is static in every loader (e.g. number of assigned cells
+/// in circuit), so it's checked natively instead of constrained.
+///
+/// # Panics
+///
+/// Panics with the first mismatch.
+fn assert_num_instance<T>(num_instance: &[usize], instances: &[Vec<T>]) {
+    assert!(
+        num_instance.len() == instances.len(),
+        "Invalid instances: expected {} instance columns, but got {}",
+        num_instance.len(),
+        instances.len()
+    );
+    for (column, (expected, instances)) in num_instance.iter().zip(instances).enumerate() {
+        assert!(
+            *expected == instances.len(),
+            "Invalid instances: expected {expected} instances in column {column}, but got {}",
+            instances.len()
+        );
+    }
+}
+
+/// Returns queries of instance polynomials used in quotient, which could be at
+/// any rotation. When instances are not committed, evaluation at rotation `r`
+/// is computed by verifier as `sum_i instance_i * L_{i - r}(z)`, which needs
+/// lagrange evaluations from `L_{-max_rotation}` to
+/// `L_{max_instance_len - 1 - min_rotation}`.
+fn instance_queries<C, L>(protocol: &Protocol<C, L>) -> Vec<Query>
+where
+    C: CurveAffine,
+    L: Loader<C>,
+{
+    let offset = protocol.preprocessed.len();
+    let range = offset..offset + protocol.num_instance.len();
+    protocol
+        .quotient
+        .numerator
+        .used_query()
+        .into_iter()
+        .filter(|query| range.contains(&query.poly))
+        .collect()
+}
+
 fn lagranges<C, L>(
     protocol: &Protocol<C, L>,
     instances: &[Vec<L::LoadedScalar>],
@@ -392,25 +859,10 @@ where
     L: Loader<C>,
 {
     let instance_eval_lagrange = protocol.instance_committing_key.is_none().then(|| {
-        let queries = {
-            let offset = protocol.preprocessed.len();
-            let range = offset..offset + protocol.num_instance.len();
-            protocol
-                .quotient
-                .numerator
-                .used_query()
-                .into_iter()
-                .filter(move |query| range.contains(&query.poly))
-        };
-        let (min_rotation, max_rotation) = queries.fold((0, 0), |(min, max), query| {
-            if query.rotation.0 < min {
-                (query.rotation.0, max)
-            } else if query.rotation.0 > max {
-                (min, query.rotation.0)
-            } else {
-                (min, max)
-            }
-        });
+        let (min_rotation, max_rotation) =
+            instance_queries(protocol).into_iter().fold((0, 0), |(min, max), query| {
+                (min.min(query.rotation.0), max.max(query.rotation.0))
+            });
         let max_instance_len =
             Iterator::max(instances.iter().map(|instance| instance.len())).unwrap_or_default();
         -max_rotation..max_instance_len as i32 + min_rotation.abs()
@@ -422,3 +874,259 @@ where
         .into_iter()
         .chain(instance_eval_lagrange.into_iter().flatten())
 }
+
+#[cfg(all(test, feature = "loader_halo2"))]
+mod test {
+    use crate::{
+        halo2_curves::bn256::{Bn256, Fr, G1Affine, G1},
+        halo2_proofs::{
+            plonk::keygen_vk,
+            poly::{
+                commitment::ParamsProver,
+                kzg::multiopen::{ProverGWC, ProverSHPLONK, VerifierGWC, VerifierSHPLONK},
+            },
+            transcript::{Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer},
+        },
+        loader::native::NativeLoader,
+        pcs::kzg::{Bdfg21, Gwc19, Kzg, KzgDecidingKey, KzgSuccinctVerifyingKey},
+        system::halo2::{
+            compile,
+            test::{
+                kzg::{halo2_kzg_config, halo2_kzg_create_snark, halo2_kzg_prepare, setup},
+                RotatedInstance, StandardPlonk,
+            },
+            Config,
+        },
+        util::{
+            arithmetic::{Curve, Field, Group},
+            msm::FixedBaseTable,
+            transcript::{Transcript, TranscriptRead},
+        },
+        verifier::{Plonk, PlonkVerifier, PreparedProtocol},
+    };
+    use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+
+    #[test]
+    #[should_panic(
+        expected = "Committed-instance mode requires protocol compiled with query_instance"
+    )]
+    fn test_committed_instances_reject_protocol_without_query_instance() {
+        let params = setup::<Bn256>(9);
+        let circuit = StandardPlonk::<Fr>::rand(ChaCha20Rng::from_seed(Default::default()));
+        let vk = keygen_vk(&params, &circuit).unwrap();
+        let protocol = compile(&params, &vk, Config::kzg().with_num_instance(vec![4]));
+
+        let svk = KzgSuccinctVerifyingKey::new(params.get_g()[0]);
+        Plonk::<Kzg<Bn256, Bdfg21>>::read_proof_with_committed_instances::<_, NativeLoader, _>(
+            &svk,
+            &protocol,
+            &[G1Affine::default()],
+            &mut Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&[][..]),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid instances: expected 4 instances in column 0, but got 3")]
+    fn test_read_proof_reject_invalid_num_instance() {
+        let params = setup::<Bn256>(9);
+        let circuit = StandardPlonk::<Fr>::rand(ChaCha20Rng::from_seed(Default::default()));
+        let vk = keygen_vk(&params, &circuit).unwrap();
+        let protocol = compile(&params, &vk, Config::kzg().with_num_instance(vec![4]));
+
+        // Fails before reading anything from the empty proof.
+        let svk = KzgSuccinctVerifyingKey::new(params.get_g()[0]);
+        <Plonk<Kzg<Bn256, Bdfg21>> as PlonkVerifier<_, NativeLoader, _>>::read_proof(
+            &svk,
+            &protocol,
+            &[vec![Fr::zero(); 3]],
+            &mut Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&[][..]),
+        );
+    }
+
+    #[test]
+    fn test_plonk_prepared() {
+        let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+            9,
+            halo2_kzg_config!(true, 2),
+            RotatedInstance::rand(ChaCha20Rng::from_seed(Default::default()))
+        );
+        let svk = KzgSuccinctVerifyingKey::new(params.get_g()[0]);
+
+        macro_rules! test_prepared {
+            ($prover:ty, $verifier:ty, $pcs:ty) => {{
+                let snark = halo2_kzg_create_snark!(
+                    $prover,
+                    $verifier,
+                    Blake2bWrite<_, _, _>,
+                    Blake2bRead<_, _, _>,
+                    Challenge255<_>,
+                    &params,
+                    &pk,
+                    &protocol,
+                    &circuits
+                );
+                let transcript = || Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&snark.proof[..]);
+
+                let expected = {
+                    let proof = <Plonk<$pcs> as PlonkVerifier<_, NativeLoader, _>>::read_proof(
+                        &svk,
+                        &snark.protocol,
+                        &snark.instances,
+                        &mut transcript(),
+                    );
+                    Plonk::<$pcs>::succinct_verify(&svk, &snark.protocol, &snark.instances, &proof)
+                };
+
+                // Prepared once and reused for every proof of the same protocol.
+                let prepared = Plonk::<$pcs>::prepare::<_, NativeLoader>(&snark.protocol);
+                for _ in 0..2 {
+                    let proof = Plonk::<$pcs>::read_proof_prepared(
+                        &svk,
+                        &prepared,
+                        &snark.protocol,
+                        &snark.instances,
+                        &mut transcript(),
+                    );
+                    let accumulators = Plonk::<$pcs>::succinct_verify_prepared(
+                        &svk,
+                        &prepared,
+                        &snark.protocol,
+                        &snark.instances,
+                        &proof,
+                    );
+                    assert_eq!(accumulators.len(), expected.len());
+                    for (lhs, rhs) in accumulators.iter().zip(expected.iter()) {
+                        assert_eq!((lhs.lhs, lhs.rhs), (rhs.lhs, rhs.rhs));
+                    }
+                }
+            }};
+        }
+
+        test_prepared!(ProverSHPLONK<_>, VerifierSHPLONK<_>, Kzg<Bn256, Bdfg21>);
+        test_prepared!(ProverGWC<_>, VerifierGWC<_>, Kzg<Bn256, Gwc19>);
+    }
+
+    #[test]
+    fn test_plonk_challenges() {
+        type Pcs = Kzg<Bn256, Bdfg21>;
+
+        let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+            9,
+            halo2_kzg_config!(true, 2),
+            RotatedInstance::rand(ChaCha20Rng::from_seed(Default::default()))
+        );
+        let svk = KzgSuccinctVerifyingKey::new(params.get_g()[0]);
+        let snark = halo2_kzg_create_snark!(
+            ProverSHPLONK<_>,
+            VerifierSHPLONK<_>,
+            Blake2bWrite<_, _, _>,
+            Blake2bRead<_, _, _>,
+            Challenge255<_>,
+            &params,
+            &pk,
+            &protocol,
+            &circuits
+        );
+        let transcript = || Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&snark.proof[..]);
+
+        let proof = <Plonk<Pcs> as PlonkVerifier<_, NativeLoader, _>>::read_proof(
+            &svk,
+            &snark.protocol,
+            &snark.instances,
+            &mut transcript(),
+        );
+        let (accumulators, challenges) = Plonk::<Pcs>::succinct_verify_with_challenges(
+            &svk,
+            &snark.protocol,
+            &snark.instances,
+            &proof,
+        );
+        let expected =
+            Plonk::<Pcs>::succinct_verify(&svk, &snark.protocol, &snark.instances, &proof);
+        assert_eq!(accumulators.len(), expected.len());
+        for (lhs, rhs) in accumulators.iter().zip(expected.iter()) {
+            assert_eq!((lhs.lhs, lhs.rhs), (rhs.lhs, rhs.rhs));
+        }
+
+        // Replay the native transcript in the same order as the proof is read
+        let mut transcript = transcript();
+        if let Some(transcript_initial_state) = &snark.protocol.transcript_initial_state {
+            transcript.common_scalar(transcript_initial_state).unwrap();
+        }
+        for instance in snark.instances.iter().flatten() {
+            transcript.common_scalar(instance).unwrap();
+        }
+        let mut phase_challenges = Vec::new();
+        for (&num_witness, &num_challenge) in
+            snark.protocol.num_witness.iter().zip(snark.protocol.num_challenge.iter())
+        {
+            transcript.read_n_ec_points(num_witness).unwrap();
+            phase_challenges.extend(transcript.squeeze_n_challenges(num_challenge));
+        }
+        transcript.read_n_ec_points(snark.protocol.quotient.num_chunk()).unwrap();
+        let z = transcript.squeeze_challenge();
+        transcript.read_n_scalars(snark.protocol.evaluations.len()).unwrap();
+        let mu = transcript.squeeze_challenge();
+        let gamma = transcript.squeeze_challenge();
+        transcript.read_ec_point().unwrap();
+        let z_prime = transcript.squeeze_challenge();
+        transcript.read_ec_point().unwrap();
+
+        assert!(!phase_challenges.is_empty());
+        assert_eq!(challenges.challenges, phase_challenges);
+        assert_eq!(challenges.z, z);
+        assert_eq!(challenges.pcs, vec![mu, gamma, z_prime]);
+    }
+
+    #[test]
+    fn test_prepared_protocol() {
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        let base = G1::random(&mut rng).to_affine();
+        let table = FixedBaseTable::new(base);
+        for scalar in [Fr::zero(), Fr::one(), -Fr::one(), Fr::random(&mut rng)] {
+            assert_eq!(table.mul(&scalar).to_affine(), (base * scalar).to_affine());
+        }
+
+        let (params, pk, protocol, circuits) = halo2_kzg_prepare!(
+            9,
+            halo2_kzg_config!(true, 2),
+            RotatedInstance::rand(ChaCha20Rng::from_seed(Default::default()))
+        );
+        let svk = KzgSuccinctVerifyingKey::new(params.get_g()[0]);
+        let dk = KzgDecidingKey::<Bn256>::new(params.g2(), params.s_g2());
+        let snark = halo2_kzg_create_snark!(
+            ProverSHPLONK<_>,
+            VerifierSHPLONK<_>,
+            Blake2bWrite<_, _, _>,
+            Blake2bRead<_, _, _>,
+            Challenge255<_>,
+            &params,
+            &pk,
+            &protocol,
+            &circuits
+        );
+        let transcript = || Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&snark.proof[..]);
+
+        type Pcs = Kzg<Bn256, Bdfg21>;
+        let expected = {
+            let proof = <Plonk<Pcs> as PlonkVerifier<_, NativeLoader, _>>::read_proof(
+                &svk,
+                &snark.protocol,
+                &snark.instances,
+                &mut transcript(),
+            );
+            Plonk::<Pcs>::succinct_verify(&svk, &snark.protocol, &snark.instances, &proof)
+        };
+
+        let prepared = PreparedProtocol::<_, Pcs>::new(snark.protocol.clone());
+        for _ in 0..2 {
+            let proof = prepared.read_proof::<(), _>(&svk, &snark.instances, &mut transcript());
+            let accumulators = prepared.succinct_verify(&svk, &snark.instances, &proof);
+            assert_eq!(accumulators.len(), expected.len());
+            for (lhs, rhs) in accumulators.iter().zip(expected.iter()) {
+                assert_eq!((lhs.lhs, lhs.rhs), (rhs.lhs, rhs.rhs));
+            }
+            assert!(prepared.verify(&svk, &dk, &snark.instances, &proof));
+        }
+    }
+}